@@ -53,7 +53,7 @@ impl<T, P: Push<T>> Push<T> for Pusher<T, P> {
     }
 }
 
-use std::sync::mpsc::Sender;
+use crate::sync::mpsc::Sender;
 
 /// The push half of an intra-thread channel.
 pub struct ArcPusher<T, P: Push<T>> {
@@ -0,0 +1,377 @@
+//! An allocator injecting communication faults, for testing.
+//!
+//! A `Faulty` allocator wraps another allocator, and subjects the messages received on selected
+//! channels to faults chosen by a seeded `FaultPolicy`: messages may be dropped, duplicated,
+//! delivered out of order with respect to other messages of the channel, or delayed. This allows
+//! operators and drivers to be tested against communication anomalies without a flaky network.
+//! Faults are applied as messages are received, and each worker draws its faults from a generator
+//! seeded by the policy's seed and its index, so that a run can be repeated.
+//!
+//! Timely dataflow relies on its channels delivering each message exactly once, and the progress
+//! of dataflows is not guaranteed once messages are dropped or duplicated. Pipeline channels,
+//! which connect a worker to itself, are not subject to faults.
+//!
+//! # Examples
+//! ```
+//! use timely_communication::{Allocate, Message};
+//! use timely_communication::allocator::Process;
+//! use timely_communication::allocator::faulty::{FaultPolicy, FaultyBuilder};
+//!
+//! let policy = FaultPolicy::new(42).duplicates(1.0);
+//! let builders = FaultyBuilder::wrap(Process::new_vector(2), &policy);
+//!
+//! let guards = timely_communication::initialize_from(builders, Box::new(()), |mut allocator| {
+//!     let (mut senders, mut receiver) = allocator.allocate::<u64>(0);
+//!     for sender in senders.iter_mut() {
+//!         sender.send(Message::from_typed(allocator.index() as u64));
+//!         sender.done();
+//!     }
+//!     // Each of the two messages is received twice.
+//!     let mut received = 0;
+//!     while received < 4 {
+//!         allocator.receive();
+//!         if receiver.recv().is_some() {
+//!             received += 1;
+//!         }
+//!         allocator.release();
+//!     }
+//! }).unwrap();
+//! guards.join();
+//! ```
+
+use std::rc::Rc;
+use std::cell::RefCell;
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashSet, VecDeque};
+use std::time::{Duration, Instant};
+
+use crate::{Data, Push, Pull, Message};
+use crate::allocator::{Allocate, AllocateBuilder, Event, PeerFailure};
+use crate::allocator::thread::{ThreadPusher, ThreadPuller};
+
+/// The faults to inject, and the channels to inject them into.
+///
+/// Probabilities are of each received message, and are drawn independently.
+#[derive(Clone, Debug)]
+pub struct FaultPolicy {
+    seed: u64,
+    channels: Option<HashSet<usize>>,
+    drops: f64,
+    duplicates: f64,
+    reorders: f64,
+    delays: f64,
+    max_delay: Duration,
+}
+
+impl FaultPolicy {
+    /// A policy injecting no faults, whose faults are drawn from a generator seeded with `seed`.
+    pub fn new(seed: u64) -> Self {
+        FaultPolicy {
+            seed,
+            channels: None,
+            drops: 0.0,
+            duplicates: 0.0,
+            reorders: 0.0,
+            delays: 0.0,
+            max_delay: Duration::from_millis(0),
+        }
+    }
+
+    /// Restricts faults to the channels with the given identifiers, rather than all channels.
+    pub fn channels<I: IntoIterator<Item=usize>>(mut self, identifiers: I) -> Self {
+        self.channels = Some(identifiers.into_iter().collect());
+        self
+    }
+
+    /// Drops each message with probability `probability`.
+    pub fn drops(mut self, probability: f64) -> Self {
+        self.drops = probability;
+        self
+    }
+
+    /// Delivers each message twice with probability `probability`.
+    pub fn duplicates(mut self, probability: f64) -> Self {
+        self.duplicates = probability;
+        self
+    }
+
+    /// Delivers each message ahead of a random number of the messages of its channel awaiting
+    /// delivery, with probability `probability`.
+    pub fn reorders(mut self, probability: f64) -> Self {
+        self.reorders = probability;
+        self
+    }
+
+    /// Delays each message with probability `probability`, by a random duration of at most `max_delay`.
+    pub fn delays(mut self, probability: f64, max_delay: Duration) -> Self {
+        self.delays = probability;
+        self.max_delay = max_delay;
+        self
+    }
+
+    /// Indicates whether faults are injected into the channel with identifier `identifier`.
+    pub fn applies_to(&self, identifier: usize) -> bool {
+        self.channels.as_ref().map(|channels| channels.contains(&identifier)).unwrap_or(true)
+    }
+}
+
+/// A builder of `Faulty` allocators.
+pub struct FaultyBuilder<B> {
+    builder: B,
+    policy: FaultPolicy,
+}
+
+impl<B: AllocateBuilder> FaultyBuilder<B> {
+    /// Wraps `builder`, whose allocators will inject faults according to `policy`.
+    pub fn new(builder: B, policy: FaultPolicy) -> Self {
+        FaultyBuilder { builder, policy }
+    }
+
+    /// Wraps each of `builders`, with the same policy.
+    pub fn wrap(builders: Vec<B>, policy: &FaultPolicy) -> Vec<Self> {
+        builders.into_iter().map(|builder| FaultyBuilder::new(builder, policy.clone())).collect()
+    }
+}
+
+impl<B: AllocateBuilder> AllocateBuilder for FaultyBuilder<B> {
+    type Allocator = Faulty<B::Allocator>;
+    fn build(self) -> Self::Allocator {
+        let inner = self.builder.build();
+        let mut random = Random { state: self.policy.seed };
+        // Each worker draws its own faults, from a generator distinguished by its index.
+        for _ in 0 ..= inner.index() { random.next_u64(); }
+        Faulty {
+            inner,
+            policy: Rc::new(self.policy),
+            state: Rc::new(RefCell::new(FaultState { random, deadlines: BinaryHeap::new() })),
+        }
+    }
+}
+
+/// An allocator injecting faults into the messages received on the channels of another allocator.
+pub struct Faulty<A: Allocate> {
+    inner: A,
+    policy: Rc<FaultPolicy>,
+    state: Rc<RefCell<FaultState>>,
+}
+
+impl<A: Allocate> Allocate for Faulty<A> {
+    fn index(&self) -> usize { self.inner.index() }
+    fn peers(&self) -> usize { self.inner.peers() }
+    fn worker_offsets(&self) -> Vec<usize> { self.inner.worker_offsets() }
+    fn allocate<T: Data>(&mut self, identifier: usize) -> (Vec<Box<dyn Push<Message<T>>>>, Box<dyn Pull<Message<T>>>) {
+        let (pushers, puller) = self.inner.allocate(identifier);
+        if !self.policy.applies_to(identifier) {
+            return (pushers, puller);
+        }
+        let puller = FaultyPuller {
+            identifier,
+            puller,
+            policy: self.policy.clone(),
+            state: self.state.clone(),
+            delayed: Vec::new(),
+            ready: VecDeque::new(),
+            current: None,
+        };
+        (pushers, Box::new(puller))
+    }
+    fn peer_failures(&self) -> Vec<PeerFailure> { self.inner.peer_failures() }
+    fn discard_undeliverable(&mut self) { self.inner.discard_undeliverable(); }
+    fn events(&self) -> &Rc<RefCell<VecDeque<(usize, Event)>>> { self.inner.events() }
+    fn await_events(&self, duration: Option<Duration>) {
+        // Wake no later than the next delayed message is due.
+        let due = self.state.borrow().deadlines.peek().map(|Reverse((deadline, _))| deadline.saturating_duration_since(Instant::now()));
+        let duration = match (duration, due) {
+            (Some(duration), Some(due)) => Some(::std::cmp::min(duration, due)),
+            (duration, due) => duration.or(due),
+        };
+        self.inner.await_events(duration);
+    }
+    fn receive(&mut self) {
+        self.inner.receive();
+        // Report delayed messages that have come due, so that their channels are read.
+        let now = Instant::now();
+        let mut state = self.state.borrow_mut();
+        let mut events = self.inner.events().borrow_mut();
+        while state.deadlines.peek().map(|Reverse((deadline, _))| *deadline <= now).unwrap_or(false) {
+            let Reverse((_, identifier)) = state.deadlines.pop().unwrap();
+            events.push_back((identifier, Event::Pushed(1)));
+        }
+    }
+    fn release(&mut self) { self.inner.release(); }
+    fn pipeline<T: 'static>(&mut self, identifier: usize) -> (ThreadPusher<Message<T>>, ThreadPuller<Message<T>>) {
+        self.inner.pipeline(identifier)
+    }
+}
+
+// State shared by the channels of a worker.
+struct FaultState {
+    random: Random,
+    // The times at which delayed messages come due, and their channels.
+    deadlines: BinaryHeap<Reverse<(Instant, usize)>>,
+}
+
+// Receives messages from a channel, and subjects them to faults.
+struct FaultyPuller<T> {
+    identifier: usize,
+    puller: Box<dyn Pull<Message<T>>>,
+    policy: Rc<FaultPolicy>,
+    state: Rc<RefCell<FaultState>>,
+    // Delayed messages, and the times at which they come due.
+    delayed: Vec<(Instant, Message<T>)>,
+    // Messages awaiting delivery.
+    ready: VecDeque<Message<T>>,
+    current: Option<Message<T>>,
+}
+
+impl<T: Data> FaultyPuller<T> {
+    // Subjects a received message to faults, and queues what remains of it.
+    fn admit(&mut self, message: Message<T>) {
+        let mut state = self.state.borrow_mut();
+        if state.random.chance(self.policy.drops) {
+            return;
+        }
+        let copies = if state.random.chance(self.policy.duplicates) {
+            let mut bytes = Vec::with_capacity(message.length_in_bytes());
+            message.into_bytes(&mut bytes);
+            vec![message, copy(bytes)]
+        }
+        else {
+            vec![message]
+        };
+        for message in copies {
+            if state.random.chance(self.policy.delays) {
+                let deadline = Instant::now() + self.policy.max_delay.mul_f64(state.random.next_f64());
+                state.deadlines.push(Reverse((deadline, self.identifier)));
+                self.delayed.push((deadline, message));
+            }
+            else if state.random.chance(self.policy.reorders) {
+                let index = state.random.below(self.ready.len() + 1);
+                self.ready.insert(index, message);
+            }
+            else {
+                self.ready.push_back(message);
+            }
+        }
+    }
+}
+
+impl<T: Data> Pull<Message<T>> for FaultyPuller<T> {
+    fn pull(&mut self) -> &mut Option<Message<T>> {
+        while let Some(message) = self.puller.pull().take() {
+            self.admit(message);
+        }
+        if !self.delayed.is_empty() {
+            let now = Instant::now();
+            let mut index = 0;
+            while index < self.delayed.len() {
+                if self.delayed[index].0 <= now {
+                    let (_, message) = self.delayed.remove(index);
+                    self.ready.push_back(message);
+                }
+                else {
+                    index += 1;
+                }
+            }
+        }
+        self.current = self.ready.pop_front();
+        &mut self.current
+    }
+}
+
+// A message decoded from the binary representation of another.
+#[cfg(not(feature = "bincode"))]
+fn copy<T: Data>(bytes: Vec<u8>) -> Message<T> {
+    // Safe, as the bytes are those of a valid message of the same type.
+    unsafe { Message::from_bytes(crate::bytes::arc::Bytes::from(bytes)) }
+}
+
+#[cfg(feature = "bincode")]
+fn copy<T: Data>(bytes: Vec<u8>) -> Message<T> {
+    Message::from_bytes(crate::bytes::arc::Bytes::from(bytes))
+}
+
+// A seeded generator of pseudo-random numbers (splitmix64).
+struct Random {
+    state: u64,
+}
+
+impl Random {
+    fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+    // A number drawn uniformly from `[0, 1)`.
+    fn next_f64(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+    // A number drawn uniformly from `[0, bound)`.
+    fn below(&mut self, bound: usize) -> usize {
+        (self.next_u64() % bound as u64) as usize
+    }
+    // Indicates an event of probability `probability`, drawing a number only for uncertain events.
+    fn chance(&mut self, probability: f64) -> bool {
+        probability >= 1.0 || (probability > 0.0 && self.next_f64() < probability)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use std::time::Duration;
+
+    use crate::{Allocate, Message};
+    use crate::allocator::{AllocateBuilder, Event};
+    use crate::allocator::thread::ThreadBuilder;
+    use super::{FaultPolicy, FaultyBuilder};
+
+    // Sends `0 .. count` on a channel of a single worker, returning what it then receives.
+    fn exchange(policy: FaultPolicy, count: u64) -> Vec<u64> {
+        let mut allocator = FaultyBuilder::new(ThreadBuilder, policy).build();
+        let (mut senders, mut receiver) = allocator.allocate::<u64>(1);
+        for index in 0 .. count {
+            senders[0].send(Message::from_typed(index));
+        }
+        let mut received = Vec::new();
+        while let Some(message) = receiver.recv() {
+            received.push(*message);
+        }
+        received
+    }
+
+    #[test]
+    fn applies_faults_to_selected_channels() {
+        assert_eq!(exchange(FaultPolicy::new(0).drops(1.0), 3), Vec::<u64>::new());
+        assert_eq!(exchange(FaultPolicy::new(0).drops(1.0).channels(vec![2]), 3), vec![0, 1, 2]);
+        assert_eq!(exchange(FaultPolicy::new(0).duplicates(1.0), 2), vec![0, 0, 1, 1]);
+    }
+
+    #[test]
+    fn reorders_repeatably() {
+        let reordered = exchange(FaultPolicy::new(7).reorders(0.5), 100);
+        assert_ne!(reordered, (0 .. 100).collect::<Vec<_>>());
+        assert_eq!(reordered, exchange(FaultPolicy::new(7).reorders(0.5), 100));
+        let mut sorted = reordered;
+        sorted.sort();
+        assert_eq!(sorted, (0 .. 100).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn reports_delayed_messages_when_due() {
+        let policy = FaultPolicy::new(0).delays(1.0, Duration::from_millis(20));
+        let mut allocator = FaultyBuilder::new(ThreadBuilder, policy).build();
+        let (mut senders, mut receiver) = allocator.allocate::<u64>(1);
+        senders[0].send(Message::from_typed(5));
+        assert!(receiver.recv().is_none());
+        allocator.events().borrow_mut().clear();
+
+        allocator.await_events(Some(Duration::from_millis(20)));
+        ::std::thread::sleep(Duration::from_millis(20));
+        allocator.receive();
+        assert!(allocator.events().borrow().iter().any(|(channel, event)| *channel == 1 && matches!(event, Event::Pushed(1))));
+        assert_eq!(receiver.recv().map(|message| *message), Some(5));
+    }
+}
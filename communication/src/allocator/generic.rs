@@ -9,7 +9,7 @@ use std::collections::VecDeque;
 
 use crate::allocator::thread::ThreadBuilder;
 use crate::allocator::process::ProcessBuilder as TypedProcessBuilder;
-use crate::allocator::{Allocate, AllocateBuilder, Event, Thread, Process};
+use crate::allocator::{Allocate, AllocateBuilder, Event, PeerFailure, Thread, Process};
 use crate::allocator::zero_copy::allocator_process::{ProcessBuilder, ProcessAllocator};
 use crate::allocator::zero_copy::allocator::{TcpBuilder, TcpAllocator};
 
@@ -47,6 +47,22 @@ impl Generic {
             &Generic::ZeroCopy(ref z) => z.peers(),
         }
     }
+    /// The index of the first worker of each process, and then the number of workers.
+    pub fn worker_offsets(&self) -> Vec<usize> {
+        match self {
+            Generic::Thread(t) => t.worker_offsets(),
+            Generic::Process(p) => p.worker_offsets(),
+            Generic::ProcessBinary(pb) => pb.worker_offsets(),
+            Generic::ZeroCopy(z) => z.worker_offsets(),
+        }
+    }
+    /// The connections to other processes that have failed.
+    pub fn peer_failures(&self) -> Vec<PeerFailure> {
+        match self {
+            Generic::ZeroCopy(z) => z.peer_failures(),
+            _ => Vec::new(),
+        }
+    }
     /// Constructs several send endpoints and one receive endpoint.
     fn allocate<T: Data>(&mut self, identifier: usize) -> (Vec<Box<dyn Push<Message<T>>>>, Box<dyn Pull<Message<T>>>) {
         match self {
@@ -87,6 +103,15 @@ impl Generic {
 impl Allocate for Generic {
     fn index(&self) -> usize { self.index() }
     fn peers(&self) -> usize { self.peers() }
+    fn worker_offsets(&self) -> Vec<usize> { self.worker_offsets() }
+    fn peer_failures(&self) -> Vec<PeerFailure> { self.peer_failures() }
+    fn discard_undeliverable(&mut self) {
+        match self {
+            Generic::Process(p) => p.discard_undeliverable(),
+            Generic::ZeroCopy(z) => z.discard_undeliverable(),
+            _ => { },
+        }
+    }
     fn allocate<T: Data>(&mut self, identifier: usize) -> (Vec<Box<dyn Push<Message<T>>>>, Box<dyn Pull<Message<T>>>) {
         self.allocate(identifier)
     }
@@ -15,6 +15,8 @@ pub mod generic;
 
 pub mod canary;
 pub mod counters;
+pub mod faulty;
+pub mod simulated;
 
 pub mod zero_copy;
 
@@ -31,6 +33,50 @@ pub trait AllocateBuilder : Send {
     type Allocator: Allocate;
     /// Builds allocator, consumes self.
     fn build(self) -> Self::Allocator;
+    /// Boxes the builder, which may then be chosen at runtime among builders of the same allocator.
+    fn boxed(self) -> BoxedBuilder<Self::Allocator> where Self: Sized+'static {
+        Box::new(self)
+    }
+}
+
+/// A boxed builder of allocators of type `A`, whose own type is not known.
+///
+/// Boxed builders are themselves builders, and so may be supplied wherever builders are, for
+/// example to start workers with allocators of a transport selected as a program runs.
+///
+/// # Examples
+/// ```
+/// use timely_communication::Allocate;
+/// use timely_communication::allocator::{AllocateBuilder, BoxedBuilder, Process};
+/// use timely_communication::allocator::process::ProcessBuilder;
+///
+/// let builders: Vec<BoxedBuilder<Process>> = Process::new_vector(2).into_iter().map(ProcessBuilder::boxed).collect();
+/// let guards = timely_communication::initialize_from(builders, Box::new(()), |allocator| allocator.index()).unwrap();
+/// let indices = guards.join().into_iter().map(|result| result.unwrap()).collect::<Vec<_>>();
+/// assert_eq!(indices, vec![0, 1]);
+/// ```
+pub type BoxedBuilder<A> = Box<dyn BuildBoxed<Allocator=A>>;
+
+/// A builder of allocators that may be built once boxed, as `AllocateBuilder` is for every builder.
+pub trait BuildBoxed : Send {
+    /// The type of allocator to be built.
+    type Allocator: Allocate;
+    /// Builds allocator, consumes self.
+    fn build_boxed(self: Box<Self>) -> Self::Allocator;
+}
+
+impl<B: AllocateBuilder> BuildBoxed for B {
+    type Allocator = B::Allocator;
+    fn build_boxed(self: Box<Self>) -> Self::Allocator {
+        (*self).build()
+    }
+}
+
+impl<A: Allocate> AllocateBuilder for BoxedBuilder<A> {
+    type Allocator = A;
+    fn build(self) -> A {
+        self.build_boxed()
+    }
 }
 
 /// A type capable of allocating channels.
@@ -42,8 +88,24 @@ pub trait Allocate {
     fn index(&self) -> usize;
     /// The number of workers in the communication group.
     fn peers(&self) -> usize;
+    /// The index of the first worker of each process, in order of the processes, and then the number of workers.
+    ///
+    /// Processes may host different numbers of workers, whose indices are assigned in order of
+    /// the processes, and so the workers of process `p` are those from `offsets[p]` up to
+    /// `offsets[p+1]`. By default, all workers are hosted by one process.
+    fn worker_offsets(&self) -> Vec<usize> { vec![0, self.peers()] }
     /// Constructs several send endpoints and one receive endpoint.
     fn allocate<T: Data>(&mut self, identifier: usize) -> (Vec<Box<dyn Push<Message<T>>>>, Box<dyn Pull<Message<T>>>);
+    /// The connections to other processes that have failed, in the order in which they failed.
+    ///
+    /// Once the connection to a process fails, nothing more is received from it, and what is
+    /// sent to it is discarded. By default, no connections fail.
+    fn peer_failures(&self) -> Vec<PeerFailure> { Vec::new() }
+    /// Discards, rather than panicking on, messages sent to workers that have dropped their end of a channel.
+    ///
+    /// Workers that continue after others fail may drop channels that their peers still send on.
+    /// By default, sending on such a channel panics, as it indicates a worker has failed.
+    fn discard_undeliverable(&mut self) { }
     /// A shared queue of communication events with channel identifier.
     ///
     /// It is expected that users of the channel allocator will regularly
@@ -93,6 +155,23 @@ pub trait Allocate {
     }
 }
 
+/// The failure of the connection to another process, as reported by `Allocate::peer_failures`.
+#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+pub struct PeerFailure {
+    /// The identity of the process.
+    pub process: usize,
+    /// A description of the failure.
+    pub message: String,
+}
+
+impl std::fmt::Display for PeerFailure {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "connection to process {} failed: {}", self.process, self.message)
+    }
+}
+
+impl std::error::Error for PeerFailure {}
+
 /// A communication channel event.
 pub enum Event {
     /// A number of messages pushed into the channel.
@@ -3,8 +3,9 @@
 use std::rc::Rc;
 use std::cell::RefCell;
 use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::any::Any;
-use std::sync::mpsc::{Sender, Receiver, channel};
+use crate::sync::mpsc::{Sender, Receiver, channel};
 use std::time::Duration;
 use std::collections::{HashMap, VecDeque};
 
@@ -27,6 +28,9 @@ pub struct ProcessBuilder {
 
     counters_send: Vec<Sender<(usize, Event)>>,
     counters_recv: Receiver<(usize, Event)>,
+
+    // Set once messages to dropped channels should be discarded, shared by all workers.
+    discard: Arc<AtomicBool>,
 }
 
 impl AllocateBuilder for ProcessBuilder {
@@ -51,6 +55,7 @@ impl AllocateBuilder for ProcessBuilder {
             buzzers,
             counters_send: self.counters_send,
             counters_recv: self.counters_recv,
+            discard: self.discard,
         }
     }
 }
@@ -65,6 +70,7 @@ pub struct Process {
     buzzers: Vec<Buzzer>,
     counters_send: Vec<Sender<(usize, Event)>>,
     counters_recv: Receiver<(usize, Event)>,
+    discard: Arc<AtomicBool>,
 }
 
 impl Process {
@@ -82,6 +88,7 @@ impl Process {
         }
 
         let channels = Arc::new(Mutex::new(HashMap::new()));
+        let discard = Arc::new(AtomicBool::new(false));
 
         // Allocate matrix of buzzer send and recv endpoints.
         let (buzzers_send, buzzers_recv) = crate::promise_futures(peers, peers);
@@ -101,6 +108,7 @@ impl Process {
                     channels: channels.clone(),
                     counters_send: counters_send.clone(),
                     counters_recv: recv,
+                    discard: discard.clone(),
                 }
             })
             .collect()
@@ -110,6 +118,7 @@ impl Process {
 impl Allocate for Process {
     fn index(&self) -> usize { self.index }
     fn peers(&self) -> usize { self.peers }
+    fn discard_undeliverable(&mut self) { self.discard.store(true, Ordering::Relaxed); }
     fn allocate<T: Any+Send+Sync+'static>(&mut self, identifier: usize) -> (Vec<Box<dyn Push<Message<T>>>>, Box<dyn Pull<Message<T>>>) {
 
         // this is race-y global initialisation of all channels for all workers, performed by the
@@ -128,7 +137,7 @@ impl Allocate for Process {
                 for index in 0 .. self.peers {
                     let (s, r): (Sender<Message<T>>, Receiver<Message<T>>) = channel();
                     // TODO: the buzzer in the pusher may be redundant, because we need to buzz post-counter.
-                    pushers.push((Pusher { target: s }, self.buzzers[index].clone()));
+                    pushers.push((Pusher { target: s, discard: self.discard.clone() }, self.buzzers[index].clone()));
                     pullers.push(Puller { source: r, current: None });
                 }
 
@@ -193,12 +202,14 @@ impl Allocate for Process {
 /// The push half of an intra-process channel.
 struct Pusher<T> {
     target: Sender<T>,
+    discard: Arc<AtomicBool>,
 }
 
 impl<T> Clone for Pusher<T> {
     fn clone(&self) -> Self {
         Self {
             target: self.target.clone(),
+            discard: self.discard.clone(),
         }
     }
 }
@@ -206,7 +217,9 @@ impl<T> Clone for Pusher<T> {
 impl<T> Push<T> for Pusher<T> {
     #[inline] fn push(&mut self, element: &mut Option<T>) {
         if let Some(element) = element.take() {
-            self.target.send(element).unwrap();
+            if self.target.send(element).is_err() && !self.discard.load(Ordering::Relaxed) {
+                panic!("Failed to send to a dropped channel");
+            }
         }
     }
 }
@@ -0,0 +1,297 @@
+//! An allocator simulating the latency and bandwidth of network links.
+//!
+//! A `Simulated` allocator wraps another allocator, for example the process allocator, and holds
+//! back each message it sends to another worker for as long as a `NetworkProfile` says its link
+//! would take to carry it: the link's latency, plus the time to transmit the message at the link's
+//! bandwidth, which it spends only once messages sent before it have been transmitted. This
+//! allows dataflows to be evaluated under the conditions of wide-area networks before deployment.
+//!
+//! Messages are released by the sending worker, as it calls `receive`, and so delays are at least
+//! those between the worker's steps; parked workers wake when their next message is due. Links
+//! are those between pairs of workers, and links of workers to themselves are not delayed unless
+//! configured otherwise. Message sizes are those of their binary representation.
+//!
+//! # Examples
+//! ```
+//! use std::time::{Duration, Instant};
+//! use timely_communication::{Allocate, Message};
+//! use timely_communication::allocator::Process;
+//! use timely_communication::allocator::simulated::{Link, NetworkProfile, SimulatedBuilder};
+//!
+//! // Workers zero and one are separated by a slow link.
+//! let profile = NetworkProfile::new(Link::default())
+//!     .link(0, 1, Link::new(Duration::from_millis(50)))
+//!     .link(1, 0, Link::new(Duration::from_millis(50)).bandwidth(1 << 20));
+//! let builders = SimulatedBuilder::wrap(Process::new_vector(2), &profile);
+//!
+//! let guards = timely_communication::initialize_from(builders, Box::new(()), |mut allocator| {
+//!     let start = Instant::now();
+//!     let (mut senders, mut receiver) = allocator.allocate::<u64>(0);
+//!     let other = 1 - allocator.index();
+//!     senders[other].send(Message::from_typed(allocator.index() as u64));
+//!     senders[other].done();
+//!     let mut received = None;
+//!     while received.is_none() {
+//!         allocator.receive();
+//!         received = receiver.recv().map(|message| *message);
+//!         allocator.release();
+//!         allocator.await_events(Some(Duration::from_millis(10)));
+//!     }
+//!     assert_eq!(received, Some(other as u64));
+//!     assert!(start.elapsed() >= Duration::from_millis(50));
+//! }).unwrap();
+//! guards.join();
+//! ```
+
+use std::rc::{Rc, Weak};
+use std::cell::{Cell, RefCell};
+use std::collections::{HashMap, VecDeque};
+use std::time::{Duration, Instant};
+
+use crate::{Data, Push, Pull, Message};
+use crate::allocator::{Allocate, AllocateBuilder, Event, PeerFailure};
+use crate::allocator::thread::{ThreadPusher, ThreadPuller};
+
+/// The latency and bandwidth of a link between two workers.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct Link {
+    /// The time each message spends in flight.
+    pub latency: Duration,
+    /// The number of bytes per second the link transmits, or `None` for unlimited bandwidth.
+    pub bandwidth: Option<u64>,
+}
+
+impl Link {
+    /// A link of latency `latency`, and unlimited bandwidth.
+    pub fn new(latency: Duration) -> Self {
+        Link { latency, bandwidth: None }
+    }
+
+    /// Limits the link to `bytes_per_second`.
+    pub fn bandwidth(mut self, bytes_per_second: u64) -> Self {
+        self.bandwidth = Some(bytes_per_second);
+        self
+    }
+
+    // The time to transmit `bytes` over the link.
+    fn transmission(&self, bytes: usize) -> Duration {
+        match self.bandwidth {
+            Some(bandwidth) if bandwidth > 0 => Duration::from_secs_f64(bytes as f64 / bandwidth as f64),
+            _ => Duration::from_secs(0),
+        }
+    }
+}
+
+/// The links between workers.
+#[derive(Clone, Debug)]
+pub struct NetworkProfile {
+    default: Link,
+    links: HashMap<(usize, usize), Link>,
+}
+
+impl NetworkProfile {
+    /// A profile in which workers are connected to each other by links like `default`.
+    pub fn new(default: Link) -> Self {
+        NetworkProfile { default, links: HashMap::new() }
+    }
+
+    /// Sets the link from worker `source` to worker `target`.
+    pub fn link(mut self, source: usize, target: usize, link: Link) -> Self {
+        self.links.insert((source, target), link);
+        self
+    }
+
+    /// The link from worker `source` to worker `target`.
+    pub fn link_between(&self, source: usize, target: usize) -> Link {
+        match self.links.get(&(source, target)) {
+            Some(link) => *link,
+            None if source == target => Link::default(),
+            None => self.default,
+        }
+    }
+}
+
+/// A builder of `Simulated` allocators.
+pub struct SimulatedBuilder<B> {
+    builder: B,
+    profile: NetworkProfile,
+}
+
+impl<B: AllocateBuilder> SimulatedBuilder<B> {
+    /// Wraps `builder`, whose allocators will delay messages according to `profile`.
+    pub fn new(builder: B, profile: NetworkProfile) -> Self {
+        SimulatedBuilder { builder, profile }
+    }
+
+    /// Wraps each of `builders`, with the same profile.
+    pub fn wrap(builders: Vec<B>, profile: &NetworkProfile) -> Vec<Self> {
+        builders.into_iter().map(|builder| SimulatedBuilder::new(builder, profile.clone())).collect()
+    }
+}
+
+impl<B: AllocateBuilder> AllocateBuilder for SimulatedBuilder<B> {
+    type Allocator = Simulated<B::Allocator>;
+    fn build(self) -> Self::Allocator {
+        Simulated {
+            inner: self.builder.build(),
+            profile: self.profile,
+            queues: Vec::new(),
+            next: Rc::new(Cell::new(None)),
+        }
+    }
+}
+
+/// An allocator delaying the messages sent on the channels of another allocator.
+pub struct Simulated<A: Allocate> {
+    inner: A,
+    profile: NetworkProfile,
+    // The queues of messages in flight, one for each delayed link of each channel.
+    queues: Vec<Weak<RefCell<dyn Release>>>,
+    // The earliest time at which a message in flight is due, if any.
+    next: Rc<Cell<Option<Instant>>>,
+}
+
+impl<A: Allocate> Allocate for Simulated<A> {
+    fn index(&self) -> usize { self.inner.index() }
+    fn peers(&self) -> usize { self.inner.peers() }
+    fn worker_offsets(&self) -> Vec<usize> { self.inner.worker_offsets() }
+    fn allocate<T: Data>(&mut self, identifier: usize) -> (Vec<Box<dyn Push<Message<T>>>>, Box<dyn Pull<Message<T>>>) {
+        let (pushers, puller) = self.inner.allocate(identifier);
+        let index = self.inner.index();
+        let pushers = pushers.into_iter().enumerate().map(|(target, pusher)| {
+            let link = self.profile.link_between(index, target);
+            if link == Link::default() {
+                return pusher;
+            }
+            let queue = Rc::new(RefCell::new(Queue { pusher, messages: VecDeque::new(), idle: Instant::now() }));
+            let release: Rc<RefCell<dyn Release>> = queue.clone();
+            self.queues.push(Rc::downgrade(&release));
+            Box::new(SimulatedPusher { link, queue, next: self.next.clone() }) as Box<dyn Push<Message<T>>>
+        }).collect();
+        (pushers, puller)
+    }
+    fn peer_failures(&self) -> Vec<PeerFailure> { self.inner.peer_failures() }
+    fn discard_undeliverable(&mut self) { self.inner.discard_undeliverable(); }
+    fn events(&self) -> &Rc<RefCell<VecDeque<(usize, Event)>>> { self.inner.events() }
+    fn await_events(&self, duration: Option<Duration>) {
+        // Wake no later than the next message in flight is due.
+        let due = self.next.get().map(|next| next.saturating_duration_since(Instant::now()));
+        let duration = match (duration, due) {
+            (Some(duration), Some(due)) => Some(::std::cmp::min(duration, due)),
+            (duration, due) => duration.or(due),
+        };
+        self.inner.await_events(duration);
+    }
+    fn receive(&mut self) {
+        let now = Instant::now();
+        if self.next.get().map(|next| next <= now).unwrap_or(false) {
+            let mut next = None;
+            self.queues.retain(|queue| {
+                if let Some(queue) = queue.upgrade() {
+                    if let Some(due) = queue.borrow_mut().release(now) {
+                        next = Some(next.map_or(due, |next: Instant| next.min(due)));
+                    }
+                    true
+                }
+                else { false }
+            });
+            self.next.set(next);
+        }
+        self.inner.receive();
+    }
+    fn release(&mut self) { self.inner.release(); }
+    fn pipeline<T: 'static>(&mut self, identifier: usize) -> (ThreadPusher<Message<T>>, ThreadPuller<Message<T>>) {
+        self.inner.pipeline(identifier)
+    }
+}
+
+impl<A: Allocate> Drop for Simulated<A> {
+    fn drop(&mut self) {
+        // Deliver the messages still in flight, as the worker will no longer release them.
+        for queue in self.queues.iter().filter_map(|queue| queue.upgrade()) {
+            queue.borrow_mut().flush();
+        }
+    }
+}
+
+// A queue of messages in flight, which releases them to its pusher as they come due.
+trait Release {
+    // Releases the messages due at `now`, returning the time the next message is due, if any.
+    fn release(&mut self, now: Instant) -> Option<Instant>;
+    // Releases all messages.
+    fn flush(&mut self);
+}
+
+struct Queue<T> {
+    pusher: Box<dyn Push<Message<T>>>,
+    // Messages and flushes, with the times at which they are due.
+    messages: VecDeque<(Instant, Option<Message<T>>)>,
+    // The time at which the link finishes transmitting the messages sent so far.
+    idle: Instant,
+}
+
+impl<T> Release for Queue<T> {
+    fn release(&mut self, now: Instant) -> Option<Instant> {
+        while self.messages.front().map(|(due, _)| *due <= now).unwrap_or(false) {
+            let (_, mut message) = self.messages.pop_front().unwrap();
+            self.pusher.push(&mut message);
+        }
+        self.messages.front().map(|(due, _)| *due)
+    }
+    fn flush(&mut self) {
+        for (_, mut message) in self.messages.drain(..) {
+            self.pusher.push(&mut message);
+        }
+    }
+}
+
+// Sends messages over a simulated link, by way of a queue of messages in flight.
+struct SimulatedPusher<T> {
+    link: Link,
+    queue: Rc<RefCell<Queue<T>>>,
+    next: Rc<Cell<Option<Instant>>>,
+}
+
+impl<T: Data> Push<Message<T>> for SimulatedPusher<T> {
+    fn push(&mut self, element: &mut Option<Message<T>>) {
+        let now = Instant::now();
+        let mut queue = self.queue.borrow_mut();
+        let due = match element.as_ref() {
+            Some(message) => {
+                // The message is transmitted once the link is idle, and arrives a latency later.
+                queue.idle = ::std::cmp::max(queue.idle, now) + self.link.transmission(message.length_in_bytes());
+                queue.idle + self.link.latency
+            },
+            // Flushes follow the messages before them.
+            None => queue.messages.back().map(|(due, _)| *due).unwrap_or(now),
+        };
+        queue.messages.push_back((due, element.take()));
+        if self.next.get().map(|next| due < next).unwrap_or(true) {
+            self.next.set(Some(due));
+        }
+    }
+}
+
+impl<T> Drop for SimulatedPusher<T> {
+    fn drop(&mut self) {
+        self.queue.borrow_mut().flush();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use std::time::Duration;
+
+    use super::{Link, NetworkProfile};
+
+    #[test]
+    fn links_default_to_the_profile() {
+        let slow = Link::new(Duration::from_millis(10)).bandwidth(1000);
+        let profile = NetworkProfile::new(Link::new(Duration::from_millis(1))).link(0, 1, slow);
+        assert_eq!(profile.link_between(0, 1), slow);
+        assert_eq!(profile.link_between(1, 0), Link::new(Duration::from_millis(1)));
+        assert_eq!(profile.link_between(1, 1), Link::default());
+        assert_eq!(slow.transmission(500), Duration::from_millis(500));
+    }
+}
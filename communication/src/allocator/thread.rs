@@ -90,7 +90,17 @@ impl<T> Push<T> for Pusher<T> {
     }
 }
 
+/// The number of pulled elements retained for return to the push half of a channel.
+///
+/// Producers re-use at most one element for each element they push, and so need few in reserve,
+/// while the bound limits the memory held by channels whose producers do not re-use elements.
+const RECYCLED: usize = 16;
+
 /// The pull half of an intra-thread channel.
+///
+/// Each pulled element left in place by the consumer is returned to the push half when the
+/// next element is pulled, where it is handed back to the pusher in exchange for a pushed
+/// element. This allows producers to re-use the allocations of containers they have sent.
 pub struct Puller<T> {
     current: Option<T>,
     source: Rc<RefCell<(VecDeque<T>, VecDeque<T>)>>,
@@ -100,13 +110,38 @@ impl<T> Pull<T> for Puller<T> {
     #[inline]
     fn pull(&mut self) -> &mut Option<T> {
         let mut borrow = self.source.borrow_mut();
-        // if let Some(element) = self.current.take() {
-        //     // TODO : Arbitrary constant.
-        //     if borrow.1.len() < 16 {
-        //         borrow.1.push_back(element);
-        //     }
-        // }
+        if let Some(element) = self.current.take() {
+            if borrow.1.len() < RECYCLED {
+                borrow.1.push_back(element);
+            }
+        }
         self.current = borrow.0.pop_front();
         &mut self.current
     }
 }
+
+#[cfg(test)]
+mod tests {
+
+    use crate::{Push, Pull};
+    use super::{Pusher, Puller};
+
+    #[test]
+    fn recycles_pulled_elements() {
+        let shared = ::std::rc::Rc::new(::std::cell::RefCell::new((Default::default(), Default::default())));
+        let mut pusher = Pusher { target: shared.clone() };
+        let mut puller = Puller { source: shared, current: None };
+
+        let mut element = Some(vec![1, 2, 3]);
+        pusher.push(&mut element);
+        assert_eq!(element, None);
+
+        // Pull the element and leave it in place, for the next pull to recycle.
+        assert_eq!(puller.pull().as_ref(), Some(&vec![1, 2, 3]));
+        assert_eq!(puller.pull().as_ref(), None);
+
+        let mut element = Some(vec![4]);
+        pusher.push(&mut element);
+        assert_eq!(element, Some(vec![1, 2, 3]));
+    }
+}
@@ -1,8 +1,9 @@
 //! Zero-copy allocator based on TCP.
 use std::rc::Rc;
 use std::cell::RefCell;
-use std::collections::{VecDeque, HashMap};
-use std::sync::mpsc::{Sender, Receiver};
+use std::collections::{VecDeque, HashMap, HashSet};
+use std::sync::Arc;
+use crate::sync::mpsc::{Sender, Receiver};
 
 use bytes::arc::Bytes;
 
@@ -10,11 +11,12 @@ use crate::networking::MessageHeader;
 
 use crate::{Allocate, Message, Data, Push, Pull};
 use crate::allocator::AllocateBuilder;
-use crate::allocator::Event;
+use crate::allocator::{Event, PeerFailure};
 use crate::allocator::canary::Canary;
 
 use super::bytes_exchange::{BytesPull, SendEndpoint, MergeQueue};
 use super::push_pull::{Pusher, PullerInner};
+use super::tcp::PeerFailures;
 
 /// Builds an instance of a TcpAllocator.
 ///
@@ -26,14 +28,35 @@ pub struct TcpBuilder<A: AllocateBuilder> {
     inner:  A,
     index:  usize,                      // number out of peers
     peers:  usize,                      // number of peer allocators.
+    process: usize,                     // index of this process.
+    offsets: Vec<usize>,                // index of the first worker of each process, and then peers.
+    failures: Arc<PeerFailures>,        // failures of connections, recorded by network threads.
     futures:   Vec<Receiver<MergeQueue>>,  // to receive queues to each network thread.
     promises:   Vec<Sender<MergeQueue>>,    // to send queues from each network thread.
 }
 
+/// Builders for local threads, and the queues of network threads to and from them.
+type Builders<A> = (Vec<TcpBuilder<A>>, Vec<Vec<Sender<MergeQueue>>>, Vec<Vec<Receiver<MergeQueue>>>);
+
 /// Creates a vector of builders, sharing appropriate state.
 ///
 /// `threads` is the number of workers in a single process, `processes` is the
-/// total number of processes.
+/// total number of processes, each of which has the same number of workers.
+/// The returned tuple is that of `new_vector_with_workers`.
+pub fn new_vector<A: AllocateBuilder>(
+    allocators: Vec<A>,
+    my_process: usize,
+    processes: usize)
+-> Builders<A>
+{
+    let workers = vec![allocators.len(); processes];
+    new_vector_with_workers(allocators, my_process, &workers)
+}
+
+/// Creates a vector of builders, for processes with the numbers of workers in `workers`.
+///
+/// Worker indices are assigned in order of the processes, and the number of allocators should
+/// be the number of workers of `my_process`.
 /// The returned tuple contains
 /// ```ignore
 /// (
@@ -42,15 +65,34 @@ pub struct TcpBuilder<A: AllocateBuilder> {
 ///   info to spawn ingress comm thresds,
 /// )
 /// ```
-pub fn new_vector<A: AllocateBuilder>(
+pub fn new_vector_with_workers<A: AllocateBuilder>(
     allocators: Vec<A>,
     my_process: usize,
-    processes: usize)
--> (Vec<TcpBuilder<A>>,
-    Vec<Vec<Sender<MergeQueue>>>,
-    Vec<Vec<Receiver<MergeQueue>>>)
+    workers: &[usize])
+-> Builders<A>
+{
+    new_vector_with_failures(allocators, my_process, workers, Default::default())
+}
+
+/// Creates a vector of builders as `new_vector_with_workers` does, whose allocators report the connection failures in `failures`.
+///
+/// The failures should be those recorded by the network threads the returned queues connect to.
+pub fn new_vector_with_failures<A: AllocateBuilder>(
+    allocators: Vec<A>,
+    my_process: usize,
+    workers: &[usize],
+    failures: Arc<PeerFailures>)
+-> Builders<A>
 {
     let threads = allocators.len();
+    let processes = workers.len();
+    assert_eq!(threads, workers[my_process]);
+
+    let mut offsets = Vec::with_capacity(processes + 1);
+    offsets.push(0);
+    for count in workers.iter() {
+        offsets.push(offsets[offsets.len() - 1] + count);
+    }
 
     // For queues from worker threads to network threads, and vice versa.
     let (network_promises, worker_futures) = crate::promise_futures(processes-1, threads);
@@ -65,8 +107,11 @@ pub fn new_vector<A: AllocateBuilder>(
         .map(|(index, ((inner, promises), futures))| {
             TcpBuilder {
                 inner,
-                index: my_process * threads + index,
-                peers: threads * processes,
+                index: offsets[my_process] + index,
+                peers: offsets[processes],
+                process: my_process,
+                offsets: offsets.clone(),
+                failures: failures.clone(),
                 promises,
                 futures,
             }})
@@ -104,11 +149,15 @@ impl<A: AllocateBuilder> TcpBuilder<A> {
             inner: self.inner.build(),
             index: self.index,
             peers: self.peers,
+            process: self.process,
+            offsets: self.offsets,
+            failures: self.failures,
             canaries: Rc::new(RefCell::new(Vec::new())),
             staged: Vec::new(),
             sends,
             recvs,
             to_local: HashMap::new(),
+            dropped: HashSet::new(),
         }
     }
 }
@@ -120,6 +169,9 @@ pub struct TcpAllocator<A: Allocate> {
 
     index:      usize,                              // number out of peers
     peers:      usize,                              // number of peer allocators (for typed channel allocation).
+    process:    usize,                              // index of this process.
+    offsets:    Vec<usize>,                         // index of the first worker of each process, and then peers.
+    failures:   Arc<PeerFailures>,                  // failures of connections, recorded by network threads.
 
     staged:     Vec<Bytes>,                         // staging area for incoming Bytes
     canaries:   Rc<RefCell<Vec<usize>>>,
@@ -128,27 +180,32 @@ pub struct TcpAllocator<A: Allocate> {
     sends:      Vec<Rc<RefCell<SendEndpoint<MergeQueue>>>>,     // sends[x] -> goes to process x.
     recvs:      Vec<MergeQueue>,                                // recvs[x] <- from process x.
     to_local:   HashMap<usize, Rc<RefCell<VecDeque<Bytes>>>>,   // to worker-local typed pullers.
+    // Channels whose pullers have been dropped, whose messages are discarded as they arrive.
+    dropped:    HashSet<usize>,
 }
 
 impl<A: Allocate> Allocate for TcpAllocator<A> {
     fn index(&self) -> usize { self.index }
     fn peers(&self) -> usize { self.peers }
+    fn worker_offsets(&self) -> Vec<usize> { self.offsets.clone() }
+    fn peer_failures(&self) -> Vec<PeerFailure> { self.failures.list() }
+    fn discard_undeliverable(&mut self) { self.inner.discard_undeliverable(); }
     fn allocate<T: Data>(&mut self, identifier: usize) -> (Vec<Box<dyn Push<Message<T>>>>, Box<dyn Pull<Message<T>>>) {
 
         // Result list of boxed pushers.
         let mut pushes = Vec::<Box<dyn Push<Message<T>>>>::new();
 
         // Inner exchange allocations.
-        let inner_peers = self.inner.peers();
-        let (mut inner_sends, inner_recv) = self.inner.allocate(identifier);
+        let (inner_sends, inner_recv) = self.inner.allocate(identifier);
+        let mut inner_sends = inner_sends.into_iter();
 
         for target_index in 0 .. self.peers() {
 
-            // TODO: crappy place to hardcode this rule.
-            let mut process_id = target_index / inner_peers;
+            // The process hosting the target, whose workers' indices start at its offset.
+            let mut process_id = self.offsets.iter().rposition(|offset| *offset <= target_index).expect("offsets start at zero");
 
-            if process_id == self.index / inner_peers {
-                pushes.push(inner_sends.remove(0));
+            if process_id == self.process {
+                pushes.push(inner_sends.next().expect("too few process-local pushers"));
             }
             else {
                 // message header template.
@@ -161,7 +218,7 @@ impl<A: Allocate> Allocate for TcpAllocator<A> {
                 };
 
                 // create, box, and stash new process_binary pusher.
-                if process_id > self.index / inner_peers { process_id -= 1; }
+                if process_id > self.process { process_id -= 1; }
                 pushes.push(Box::new(Pusher::new(header, self.sends[process_id].clone())));
             }
         }
@@ -185,12 +242,13 @@ impl<A: Allocate> Allocate for TcpAllocator<A> {
 
         // Check for channels whose `Puller` has been dropped.
         let mut canaries = self.canaries.borrow_mut();
+        // Messages may remain, or yet arrive, if the channel was dropped while other workers sent
+        // on it, as when a dataflow is dropped, and are discarded rather than retained.
         for dropped_channel in canaries.drain(..) {
-            let dropped =
             self.to_local
                 .remove(&dropped_channel)
                 .expect("non-existent channel dropped");
-            assert!(dropped.borrow().is_empty());
+            self.dropped.insert(dropped_channel);
         }
         ::std::mem::drop(canaries);
 
@@ -214,6 +272,10 @@ impl<A: Allocate> Allocate for TcpAllocator<A> {
                     let mut peel = bytes.extract_to(header.required_bytes());
                     let _ = peel.extract_to(40);
 
+                    if self.dropped.contains(&header.channel) {
+                        continue;
+                    }
+
                     // Increment message count for channel.
                     events.push_back((header.channel, Event::Pushed(1)));
 
@@ -2,8 +2,8 @@
 
 use std::rc::Rc;
 use std::cell::RefCell;
-use std::collections::{VecDeque, HashMap};
-use std::sync::mpsc::{Sender, Receiver};
+use std::collections::{VecDeque, HashMap, HashSet};
+use crate::sync::mpsc::{Sender, Receiver};
 
 use bytes::arc::Bytes;
 
@@ -84,6 +84,7 @@ impl ProcessBuilder {
             sends,
             recvs,
             to_local: HashMap::new(),
+            dropped: HashSet::new(),
             // _signal: self.signal,
         }
     }
@@ -114,6 +115,8 @@ pub struct ProcessAllocator {
     sends:      Vec<Rc<RefCell<SendEndpoint<MergeQueue>>>>, // sends[x] -> goes to thread x.
     recvs:      Vec<MergeQueue>,                            // recvs[x] <- from thread x.
     to_local:   HashMap<usize, Rc<RefCell<VecDeque<Bytes>>>>,          // to worker-local typed pullers.
+    // Channels whose pullers have been dropped, whose messages are discarded as they arrive.
+    dropped:    HashSet<usize>,
 }
 
 impl Allocate for ProcessAllocator {
@@ -157,12 +160,13 @@ impl Allocate for ProcessAllocator {
 
         // Check for channels whose `Puller` has been dropped.
         let mut canaries = self.canaries.borrow_mut();
+        // Messages may remain, or yet arrive, if the channel was dropped while other workers sent
+        // on it, as when a dataflow is dropped, and are discarded rather than retained.
         for dropped_channel in canaries.drain(..) {
-            let dropped =
             self.to_local
                 .remove(&dropped_channel)
                 .expect("non-existent channel dropped");
-            assert!(dropped.borrow().is_empty());
+            self.dropped.insert(dropped_channel);
         }
         std::mem::drop(canaries);
 
@@ -184,6 +188,10 @@ impl Allocate for ProcessAllocator {
                     let mut peel = bytes.extract_to(header.required_bytes());
                     let _ = peel.extract_to(40);
 
+                    if self.dropped.contains(&header.channel) {
+                        continue;
+                    }
+
                     // Increment message count for channel.
                     events.push_back((header.channel, Event::Pushed(1)));
 
@@ -232,4 +240,34 @@ impl Allocate for ProcessAllocator {
             }
         }
     }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+
+    use crate::{Allocate, Message, Push};
+    use super::ProcessBuilder;
+
+    #[test]
+    fn discards_messages_to_dropped_channels() {
+        let mut allocator = ProcessBuilder::new_vector(1).pop().expect("one builder").build();
+        let (mut senders, receiver) = allocator.allocate::<u64>(5);
+
+        // A message received but not pulled before the channel is dropped.
+        senders[0].send(Message::from_typed(0));
+        senders[0].done();
+        allocator.release();
+        allocator.receive();
+        assert_eq!(allocator.to_local[&5].borrow().len(), 1);
+        drop(receiver);
+
+        // A message sent after the channel is dropped, as by a worker yet to drop it.
+        senders[0].send(Message::from_typed(1));
+        senders[0].done();
+        allocator.release();
+        allocator.events().borrow_mut().clear();
+        allocator.receive();
+
+        assert!(!allocator.to_local.contains_key(&5));
+        assert!(allocator.events().borrow().is_empty());
+    }
+}
@@ -1,6 +1,6 @@
 //! Types and traits for sharing `Bytes`.
 
-use std::sync::{Arc, Mutex};
+use crate::sync::{Arc, Mutex};
 use std::collections::VecDeque;
 
 use bytes::arc::Bytes;
@@ -21,7 +21,7 @@ pub trait BytesPull {
     fn drain_into(&mut self, vec: &mut Vec<Bytes>);
 }
 
-use std::sync::atomic::{AtomicBool, Ordering};
+use crate::sync::{AtomicBool, Ordering};
 /// An unbounded queue of bytes intended for point-to-point communication
 /// between threads. Cloning returns another handle to the same queue.
 ///
@@ -47,6 +47,10 @@ impl MergeQueue {
         if self.panic.load(Ordering::SeqCst) { panic!("MergeQueue poisoned."); }
         Arc::strong_count(&self.queue) == 1 && self.queue.lock().expect("Failed to acquire lock").is_empty()
     }
+    /// The number of bytes in the queue.
+    pub fn queued_bytes(&self) -> usize {
+        self.queue.lock().expect("Failed to acquire lock").iter().map(|bytes| bytes.len()).sum()
+    }
 }
 
 impl BytesPush for MergeQueue {
@@ -57,6 +61,7 @@ impl BytesPush for MergeQueue {
         // try to acquire lock without going to sleep (Rust's lock() might yield)
         let mut lock_ok = self.queue.try_lock();
         while let Result::Err(::std::sync::TryLockError::WouldBlock) = lock_ok {
+            crate::sync::hint::spin_loop();
             lock_ok = self.queue.try_lock();
         }
         let mut queue = lock_ok.expect("MergeQueue mutex poisoned.");
@@ -98,6 +103,7 @@ impl BytesPull for MergeQueue {
         // try to acquire lock without going to sleep (Rust's lock() might yield)
         let mut lock_ok = self.queue.try_lock();
         while let Result::Err(::std::sync::TryLockError::WouldBlock) = lock_ok {
+            crate::sync::hint::spin_loop();
             lock_ok = self.queue.try_lock();
         }
         let mut queue = lock_ok.expect("MergeQueue mutex poisoned.");
@@ -3,9 +3,11 @@
 use std::sync::Arc;
 // use crate::allocator::Process;
 use crate::allocator::process::ProcessBuilder;
-use crate::networking::create_sockets;
+use crate::networking::{create_sockets, try_create_sockets, exchange_handshake};
 use super::tcp::{send_loop, recv_loop};
-use super::allocator::{TcpBuilder, new_vector};
+use super::tcp::PeerFailures;
+use super::allocator::{TcpBuilder, new_vector_with_failures};
+use crate::initialize::{InitializeError, ThreadConfig, ThreadKind};
 
 /// Join handles for send and receive threads.
 ///
@@ -41,8 +43,47 @@ pub fn initialize_networking(
     log_sender: Box<dyn Fn(CommunicationSetup)->Option<Logger<CommunicationEvent, CommunicationSetup>>+Send+Sync>)
 -> ::std::io::Result<(Vec<TcpBuilder<ProcessBuilder>>, CommsGuard)>
 {
-    let sockets = create_sockets(addresses, my_index, noisy)?;
-    initialize_networking_from_sockets(sockets, my_index, threads, log_sender)
+    initialize_networking_with(addresses, my_index, threads, noisy, log_sender, &ThreadConfig::default())
+}
+
+/// Initializes network connections, spawning network threads as `threads_config` describes.
+///
+/// Processes may have different numbers of `threads`, which they exchange once connected, in the
+/// same messages with which they verify that they serialize messages with the same backend.
+pub fn initialize_networking_with(
+    addresses: Vec<String>,
+    my_index: usize,
+    threads: usize,
+    noisy: bool,
+    log_sender: Box<dyn Fn(CommunicationSetup)->Option<Logger<CommunicationEvent, CommunicationSetup>>+Send+Sync>,
+    threads_config: &ThreadConfig)
+-> ::std::io::Result<(Vec<TcpBuilder<ProcessBuilder>>, CommsGuard)>
+{
+    let mut sockets = create_sockets(addresses, my_index, noisy)?;
+    let workers = exchange_handshake(&mut sockets, my_index, threads)?;
+    initialize_networking_from_sockets_with_workers(sockets, my_index, workers, log_sender, threads_config)
+}
+
+/// Initializes network connections as `initialize_networking_with` does, reporting why they could not be.
+///
+/// Processes that cannot be reached, for reasons other than that they do not yet listen, are
+/// reported rather than retried indefinitely, as are addresses that cannot be bound and
+/// connections that do not present the expected handshake.
+pub fn try_initialize_networking_with(
+    addresses: Vec<String>,
+    my_index: usize,
+    threads: usize,
+    noisy: bool,
+    log_sender: Box<dyn Fn(CommunicationSetup)->Option<Logger<CommunicationEvent, CommunicationSetup>>+Send+Sync>,
+    threads_config: &ThreadConfig)
+-> Result<(Vec<TcpBuilder<ProcessBuilder>>, CommsGuard), InitializeError>
+{
+    let mut sockets = try_create_sockets(addresses, my_index, noisy)?;
+    let workers = exchange_handshake(&mut sockets, my_index, threads)?;
+    if let Some(process) = workers.iter().position(|workers| *workers == 0) {
+        return Err(InitializeError::handshake(Some(process), "process reported no workers"));
+    }
+    Ok(initialize_networking_from_sockets_with_workers(sockets, my_index, workers, log_sender, threads_config)?)
 }
 
 /// Initialize send and recv threads from sockets.
@@ -53,24 +94,53 @@ pub fn initialize_networking(
 /// It is important that the `sockets` argument contain sockets for each remote process, in order, and
 /// with position `my_index` set to `None`.
 pub fn initialize_networking_from_sockets(
-    mut sockets: Vec<Option<std::net::TcpStream>>,
+    sockets: Vec<Option<std::net::TcpStream>>,
     my_index: usize,
     threads: usize,
     log_sender: Box<dyn Fn(CommunicationSetup)->Option<Logger<CommunicationEvent, CommunicationSetup>>+Send+Sync>)
 -> ::std::io::Result<(Vec<TcpBuilder<ProcessBuilder>>, CommsGuard)>
+{
+    initialize_networking_from_sockets_with(sockets, my_index, threads, log_sender, &ThreadConfig::default())
+}
+
+/// Initialize send and recv threads from sockets, spawning them as `threads_config` describes.
+pub fn initialize_networking_from_sockets_with(
+    sockets: Vec<Option<std::net::TcpStream>>,
+    my_index: usize,
+    threads: usize,
+    log_sender: Box<dyn Fn(CommunicationSetup)->Option<Logger<CommunicationEvent, CommunicationSetup>>+Send+Sync>,
+    threads_config: &ThreadConfig)
+-> ::std::io::Result<(Vec<TcpBuilder<ProcessBuilder>>, CommsGuard)>
+{
+    let workers = vec![threads; sockets.len()];
+    initialize_networking_from_sockets_with_workers(sockets, my_index, workers, log_sender, threads_config)
+}
+
+/// Initialize send and recv threads from sockets, for processes with the numbers of workers in `workers`.
+///
+/// The numbers may be learned from connected sockets with `networking::exchange_handshake`.
+pub fn initialize_networking_from_sockets_with_workers(
+    mut sockets: Vec<Option<std::net::TcpStream>>,
+    my_index: usize,
+    workers: Vec<usize>,
+    log_sender: Box<dyn Fn(CommunicationSetup)->Option<Logger<CommunicationEvent, CommunicationSetup>>+Send+Sync>,
+    threads_config: &ThreadConfig)
+-> ::std::io::Result<(Vec<TcpBuilder<ProcessBuilder>>, CommsGuard)>
 {
     // Sockets are expected to be blocking,
     for socket in sockets.iter_mut() {
         if let Some(socket) = socket {
-            socket.set_nonblocking(false).expect("failed to set socket to blocking");
+            socket.set_nonblocking(false)?;
         }
     }
 
     let log_sender = Arc::new(log_sender);
-    let processes = sockets.len();
+    let threads = workers[my_index];
+    let worker_offset = workers[.. my_index].iter().sum::<usize>();
 
     let process_allocators = crate::allocator::process::Process::new_vector(threads);
-    let (builders, promises, futures) = new_vector(process_allocators, my_index, processes);
+    let failures = Arc::new(PeerFailures::default());
+    let (builders, promises, futures) = new_vector_with_failures(process_allocators, my_index, &workers, failures.clone());
 
     let mut promises_iter = promises.into_iter();
     let mut futures_iter = futures.into_iter();
@@ -88,20 +158,19 @@ pub fn initialize_networking_from_sockets(
 
             {
                 let log_sender = log_sender.clone();
+                let failures = failures.clone();
                 let stream = stream.try_clone()?;
                 let join_guard =
-                ::std::thread::Builder::new()
-                    .name(format!("send thread {}", index))
-                    .spawn(move || {
+                threads_config.spawn(ThreadKind::Sender, index, move || {
 
-                        let logger = log_sender(CommunicationSetup {
-                            process: my_index,
-                            sender: true,
-                            remote: Some(index),
-                        });
+                    let logger = log_sender(CommunicationSetup {
+                        process: my_index,
+                        sender: true,
+                        remote: Some(index),
+                    });
 
-                        send_loop(stream, remote_recv, my_index, index, logger);
-                    })?;
+                    send_loop(stream, remote_recv, my_index, index, &failures, logger);
+                })?;
 
                 send_guards.push(join_guard);
             }
@@ -111,18 +180,17 @@ pub fn initialize_networking_from_sockets(
             {
                 // let remote_sends = remote_sends.clone();
                 let log_sender = log_sender.clone();
+                let failures = failures.clone();
                 let stream = stream.try_clone()?;
                 let join_guard =
-                ::std::thread::Builder::new()
-                    .name(format!("recv thread {}", index))
-                    .spawn(move || {
-                        let logger = log_sender(CommunicationSetup {
-                            process: my_index,
-                            sender: false,
-                            remote: Some(index),
-                        });
-                        recv_loop(stream, remote_send, threads * my_index, my_index, index, logger);
-                    })?;
+                threads_config.spawn(ThreadKind::Receiver, index, move || {
+                    let logger = log_sender(CommunicationSetup {
+                        process: my_index,
+                        sender: false,
+                        remote: Some(index),
+                    });
+                    recv_loop(stream, remote_send, worker_offset, my_index, index, &failures, logger);
+                })?;
 
                 recv_guards.push(join_guard);
             }
@@ -2,7 +2,11 @@
 
 use std::io::{Read, Write};
 use std::net::TcpStream;
-use std::sync::mpsc::{Sender, Receiver};
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use crate::sync::mpsc::{Sender, Receiver};
+
+use crate::allocator::PeerFailure;
 
 use crate::networking::MessageHeader;
 
@@ -11,20 +15,53 @@ use super::bytes_exchange::MergeQueue;
 
 use logging_core::Logger;
 
-use crate::logging::{CommunicationEvent, CommunicationSetup, MessageEvent, StateEvent};
+use crate::logging::{CommunicationEvent, CommunicationSetup, MessageEvent, StateEvent, QueueEvent, SATURATION_BYTES};
+
+/// The failures of connections to other processes, shared by the network threads and workers of a process.
+#[derive(Default)]
+pub struct PeerFailures {
+    count: AtomicUsize,
+    failures: Mutex<Vec<PeerFailure>>,
+}
+
+impl PeerFailures {
+    /// Records the failure of the connection to `process`, unless one is already recorded.
+    pub fn record(&self, process: usize, message: String) {
+        let mut failures = self.failures.lock().expect("peer failures poisoned");
+        if !failures.iter().any(|failure| failure.process == process) {
+            failures.push(PeerFailure { process, message });
+            self.count.store(failures.len(), Ordering::SeqCst);
+        }
+    }
+    /// The number of recorded failures, read without locking.
+    pub fn len(&self) -> usize {
+        self.count.load(Ordering::SeqCst)
+    }
+    /// Returns `true` if no failures are recorded.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+    /// The recorded failures, in the order in which they were recorded.
+    pub fn list(&self) -> Vec<PeerFailure> {
+        if self.is_empty() { Vec::new() }
+        else { self.failures.lock().expect("peer failures poisoned").clone() }
+    }
+}
 
 /// Repeatedly reads from a TcpStream and carves out messages.
 ///
 /// The intended communication pattern is a sequence of (header, message)^* for valid
 /// messages, followed by a header for a zero length message indicating the end of stream.
-/// If the stream ends without being shut down, the receive thread panics in an attempt to
-/// take down the computation and cause the failures to cascade.
+/// If the stream fails or ends without being shut down, the receive thread records the failure
+/// in `failures` and completes, after passing along the messages it received, and workers
+/// receive nothing further from the process.
 pub fn recv_loop(
     mut reader: TcpStream,
     targets: Vec<Receiver<MergeQueue>>,
     worker_offset: usize,
     process: usize,
     remote: usize,
+    failures: &PeerFailures,
     mut logger: Option<Logger<CommunicationEvent, CommunicationSetup>>)
 {
     // Log the receive thread's start.
@@ -40,6 +77,9 @@ pub fn recv_loop(
         stageds.push(Vec::new());
     }
 
+    // Whether each target's queue was last observed to be saturated.
+    let mut saturated = vec![false; targets.len()];
+
     // Each loop iteration adds to `self.Bytes` and consumes all complete messages.
     // At the start of each iteration, `self.buffer[..self.length]` represents valid
     // data, and the remaining capacity is available for reading from the reader.
@@ -56,15 +96,17 @@ pub fn recv_loop(
 
         // Attempt to read some more bytes into self.buffer.
         let read = match reader.read(&mut buffer.empty()) {
+            Ok(0) => {
+                failures.record(remote, "connection closed without shutting down".to_string());
+                break;
+            },
             Ok(n) => n,
-            Err(x) => {
-                // We don't expect this, as socket closure results in Ok(0) reads.
-                println!("Error: {:?}", x);
-                0
+            Err(error) => {
+                failures.record(remote, format!("read failed: {}", error));
+                break;
             },
         };
 
-        assert!(read > 0);
         buffer.make_valid(read);
 
         // Consume complete messages from the front of self.buffer.
@@ -85,13 +127,14 @@ pub fn recv_loop(
             else {
                 // Shutting down; confirm absence of subsequent data.
                 active = false;
-                if !buffer.valid().is_empty() {
-                    panic!("Clean shutdown followed by data.");
-                }
-                buffer.ensure_capacity(1);
-                if reader.read(&mut buffer.empty()).expect("read failure") > 0 {
-                    panic!("Clean shutdown followed by data.");
+                let subsequent = !buffer.valid().is_empty() || {
+                    buffer.ensure_capacity(1);
+                    reader.read(&mut buffer.empty()).map(|read| read > 0).unwrap_or(false)
+                };
+                if subsequent {
+                    failures.record(remote, "clean shutdown followed by data".to_string());
                 }
+                break;
             }
         }
 
@@ -99,7 +142,17 @@ pub fn recv_loop(
         for (index, staged) in stageds.iter_mut().enumerate() {
             // FIXME: try to merge `staged` before handing it to BytesPush::extend
             use crate::allocator::zero_copy::bytes_exchange::BytesPush;
+            let extended = !staged.is_empty();
             targets[index].extend(staged.drain(..));
+
+            // Report targets whose queues have become saturated.
+            if let (true, Some(logger)) = (extended, logger.as_mut()) {
+                let bytes = targets[index].queued_bytes();
+                if bytes > SATURATION_BYTES && !saturated[index] {
+                    logger.log(QueueEvent { send: false, process, remote, worker: Some(worker_offset + index), bytes });
+                }
+                saturated[index] = bytes > SATURATION_BYTES;
+            }
         }
     }
 
@@ -111,12 +164,15 @@ pub fn recv_loop(
 ///
 /// The intended communication pattern is a sequence of (header, message)^* for valid
 /// messages, followed by a header for a zero length message indicating the end of stream.
+/// If the stream fails, the send thread records the failure in `failures`, and discards the
+/// messages of workers until they complete.
 pub fn send_loop(
     // TODO: Maybe we don't need BufWriter with consolidation in writes.
     writer: TcpStream,
     sources: Vec<Sender<MergeQueue>>,
     process: usize,
     remote: usize,
+    failures: &PeerFailures,
     mut logger: Option<Logger<CommunicationEvent, CommunicationSetup>>)
 {
 
@@ -133,6 +189,12 @@ pub fn send_loop(
     let mut writer = ::std::io::BufWriter::with_capacity(1 << 16, writer);
     let mut stash = Vec::new();
 
+    // Whether the sources were last observed to be saturated.
+    let mut saturated = false;
+
+    // Whether the stream has failed, after which messages are discarded.
+    let mut failed = false;
+
     while !sources.is_empty() {
 
         // TODO: Round-robin better, to release resources fairly when overloaded.
@@ -148,13 +210,30 @@ pub fn send_loop(
             // still be a signal incoming.
             //
             // We could get awoken by more data, a channel closing, or spuriously perhaps.
-            writer.flush().expect("Failed to flush writer.");
+            if !failed {
+                if let Err(error) = writer.flush() {
+                    failures.record(remote, format!("write failed: {}", error));
+                    failed = true;
+                }
+            }
             sources.retain(|source| !source.is_complete());
             if !sources.is_empty() {
                 std::thread::park();
             }
         }
+        else if failed {
+            stash.clear();
+        }
         else {
+            // Report sources whose queued bytes have become saturated.
+            if let Some(logger) = logger.as_mut() {
+                let bytes = stash.iter().map(|bytes| bytes.len()).sum::<usize>();
+                if bytes > SATURATION_BYTES && !saturated {
+                    logger.log(QueueEvent { send: true, process, remote, worker: None, bytes });
+                }
+                saturated = bytes > SATURATION_BYTES;
+            }
+
             // TODO: Could do scatter/gather write here.
             for mut bytes in stash.drain(..) {
 
@@ -167,25 +246,35 @@ pub fn send_loop(
                     }
                 });
 
-                writer.write_all(&bytes[..]).expect("Write failure in send_loop.");
+                if let Err(error) = writer.write_all(&bytes[..]) {
+                    failures.record(remote, format!("write failed: {}", error));
+                    failed = true;
+                    break;
+                }
             }
+            stash.clear();
         }
     }
 
-    // Write final zero-length header.
+    // Write final zero-length header, unless the stream has failed.
     // Would be better with meaningful metadata, but as this stream merges many
     // workers it isn't clear that there is anything specific to write here.
-    let header = MessageHeader {
-        channel:    0,
-        source:     0,
-        target:     0,
-        length:     0,
-        seqno:      0,
-    };
-    header.write_to(&mut writer).expect("Failed to write header!");
-    writer.flush().expect("Failed to flush writer.");
-    writer.get_mut().shutdown(::std::net::Shutdown::Write).expect("Write shutdown failed");
-    logger.as_mut().map(|logger| logger.log(MessageEvent { is_send: true, header }));
+    if !failed {
+        let header = MessageHeader {
+            channel:    0,
+            source:     0,
+            target:     0,
+            length:     0,
+            seqno:      0,
+        };
+        let shutdown = header.write_to(&mut writer)
+            .and_then(|_| writer.flush())
+            .and_then(|_| writer.get_mut().shutdown(::std::net::Shutdown::Write));
+        if let Err(error) = shutdown {
+            failures.record(remote, format!("shutdown failed: {}", error));
+        }
+        logger.as_mut().map(|logger| logger.log(MessageEvent { is_send: true, header }));
+    }
 
     // Log the receive thread's start.
     logger.as_mut().map(|l| l.log(StateEvent { send: true, process, remote, start: false, }));
@@ -0,0 +1,175 @@
+//! A rendezvous for processes of a cluster, assigning their identities and ports.
+//!
+//! Rather than assign each process an identity and an address by hand, the processes may each
+//! connect to one rendezvous address, served by `serve`. Each process binds a port chosen by the
+//! operating system, and reports it with `join`. Once the expected number of processes have
+//! joined, each receives its identity, in the order in which they joined, and the addresses of
+//! all processes, with which it proceeds to connect to the others as usual.
+//!
+//! A process is reached at the address from which it connected to the rendezvous, with the port
+//! it reported, unless it names a host at which it is reached. The port remains bound from when
+//! it is reported, and the networking layer accepts connections from the other processes on the
+//! listener bound by `join`, rather than binding the port again, so that no other program can
+//! take the port in the meantime.
+//!
+//! # Examples
+//! ```
+//! use timely_communication::bootstrap;
+//!
+//! let (rendezvous, server) = bootstrap::serve("localhost:0", 2).unwrap();
+//!
+//! let joins = (0 .. 2).map(|_| std::thread::spawn(move || bootstrap::join(rendezvous, None).unwrap())).collect::<Vec<_>>();
+//! let mut joined = joins.into_iter().map(|join| join.join().unwrap()).collect::<Vec<_>>();
+//! server.join().unwrap().unwrap();
+//!
+//! joined.sort_by_key(|joined| joined.process);
+//! assert_eq!(joined[0].process, 0);
+//! assert_eq!(joined[1].process, 1);
+//! assert_eq!(joined[0].addresses, joined[1].addresses);
+//! assert_eq!(joined[0].addresses.len(), 2);
+//! ```
+
+use std::io::{self, Read, Write, Result};
+use std::net::{SocketAddr, TcpListener, TcpStream, ToSocketAddrs};
+use std::sync::Mutex;
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+// Sent first by each joining process, to distinguish the rendezvous from other traffic.
+const BOOTSTRAP_MAGIC: u64 = 0x7a3cb1d24e09f615;
+
+// Listeners bound by `join`, by their bind addresses, until the networking layer takes them.
+static LISTENERS: Mutex<Vec<(String, TcpListener)>> = Mutex::new(Vec::new());
+
+/// Takes the listener `join` bound for the bind address `address`, if any.
+pub(crate) fn take_listener(address: &str) -> Option<TcpListener> {
+    let mut listeners = LISTENERS.lock().expect("bootstrap listeners poisoned");
+    let position = listeners.iter().position(|(bind, _)| bind == address)?;
+    Some(listeners.swap_remove(position).1)
+}
+
+/// The outcome of joining a rendezvous.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Joined {
+    /// The identity of the process.
+    pub process: usize,
+    /// The addresses at which the processes are reached, in order of their identities.
+    pub addresses: Vec<String>,
+    /// The address at which the process should listen for connections.
+    pub bind: String,
+}
+
+impl Joined {
+    /// The addresses with which to connect to the processes, in which this process has its bind address.
+    pub fn connect_addresses(&self) -> Vec<String> {
+        let mut addresses = self.addresses.clone();
+        addresses[self.process] = self.bind.clone();
+        addresses
+    }
+}
+
+/// Serves a rendezvous for `processes` processes at `addr`, from a background thread.
+///
+/// The thread completes once the processes have joined and been told their identities. The
+/// method returns the address at which the rendezvous is served, which reveals the port chosen
+/// for addresses with port zero, and a handle to the thread.
+pub fn serve<A: ToSocketAddrs>(addr: A, processes: usize) -> Result<(SocketAddr, JoinHandle<Result<()>>)> {
+    let listener = TcpListener::bind(addr)?;
+    let local = listener.local_addr()?;
+    let handle = thread::Builder::new()
+        .name("bootstrap server".to_string())
+        .spawn(move || {
+            let mut joined = Vec::with_capacity(processes);
+            while joined.len() < processes {
+                let (mut stream, peer) = listener.accept()?;
+                // Connections from other programs, and those that fail or stall, are ignored.
+                stream.set_read_timeout(Some(Duration::from_secs(10)))?;
+                if let Ok((port, host)) = read_join(&mut stream) {
+                    let address = if host.is_empty() { SocketAddr::new(peer.ip(), port).to_string() } else { format!("{}:{}", host, port) };
+                    joined.push((stream, address));
+                }
+            }
+            let addresses = joined.iter().map(|(_, address)| address.clone()).collect::<Vec<_>>();
+            for (process, (mut stream, _)) in joined.into_iter().enumerate() {
+                write_u64(&mut stream, process as u64)?;
+                write_u64(&mut stream, addresses.len() as u64)?;
+                for address in addresses.iter() {
+                    write_string(&mut stream, address)?;
+                }
+                stream.flush()?;
+            }
+            Ok(())
+        })?;
+    Ok((local, handle))
+}
+
+/// Joins the rendezvous at `rendezvous`, awaiting the other processes.
+///
+/// The process binds a port chosen by the operating system, on all interfaces, and is reached
+/// at `host` if it is supplied, or else at the address from which it connects. Connections to
+/// the rendezvous are retried while they are refused, as it may not yet be served. The port
+/// remains bound, and initializing networking with the addresses of `connect_addresses` accepts
+/// connections on it.
+pub fn join<A: ToSocketAddrs>(rendezvous: A, host: Option<&str>) -> Result<Joined> {
+    let listener = TcpListener::bind("0.0.0.0:0")?;
+    let port = listener.local_addr()?.port();
+
+    let mut stream = loop {
+        match TcpStream::connect(&rendezvous) {
+            Ok(stream) => break stream,
+            Err(error) if error.kind() == io::ErrorKind::ConnectionRefused => thread::sleep(Duration::from_secs(1)),
+            Err(error) => return Err(error),
+        }
+    };
+    write_u64(&mut stream, BOOTSTRAP_MAGIC)?;
+    write_u64(&mut stream, port as u64)?;
+    write_string(&mut stream, host.unwrap_or(""))?;
+    stream.flush()?;
+
+    let process = read_u64(&mut stream)? as usize;
+    let processes = read_u64(&mut stream)? as usize;
+    let addresses = (0 .. processes).map(|_| read_string(&mut stream)).collect::<Result<Vec<_>>>()?;
+    if process >= addresses.len() {
+        return Err(io::Error::new(io::ErrorKind::InvalidData, "rendezvous assigned an identity beyond its processes"));
+    }
+
+    let bind = format!("0.0.0.0:{}", port);
+    LISTENERS.lock().expect("bootstrap listeners poisoned").push((bind.clone(), listener));
+    Ok(Joined { process, addresses, bind })
+}
+
+/// Reads the port and host reported by a joining process.
+fn read_join(stream: &mut TcpStream) -> Result<(u16, String)> {
+    if read_u64(stream)? != BOOTSTRAP_MAGIC {
+        return Err(io::Error::new(io::ErrorKind::InvalidData, "received incorrect bootstrap magic"));
+    }
+    let port = read_u64(stream)? as u16;
+    let host = read_string(stream)?;
+    Ok((port, host))
+}
+
+fn write_u64<W: Write>(writer: &mut W, value: u64) -> Result<()> {
+    writer.write_all(&value.to_le_bytes())
+}
+
+fn read_u64<R: Read>(reader: &mut R) -> Result<u64> {
+    let mut buffer = [0u8; 8];
+    reader.read_exact(&mut buffer)?;
+    Ok(u64::from_le_bytes(buffer))
+}
+
+fn write_string<W: Write>(writer: &mut W, value: &str) -> Result<()> {
+    write_u64(writer, value.len() as u64)?;
+    writer.write_all(value.as_bytes())
+}
+
+fn read_string<R: Read>(reader: &mut R) -> Result<String> {
+    let length = read_u64(reader)? as usize;
+    // Addresses are short; longer strings are not from a joining process.
+    if length > 1 << 16 {
+        return Err(io::Error::new(io::ErrorKind::InvalidData, "bootstrap string too long"));
+    }
+    let mut buffer = vec![0u8; length];
+    reader.read_exact(&mut buffer)?;
+    String::from_utf8(buffer).map_err(|_| io::Error::new(io::ErrorKind::InvalidData, "bootstrap string not UTF-8"))
+}
@@ -1,22 +1,63 @@
 //! A type that can unpark specific threads.
+//!
+//! A thread driven by an asynchronous executor, rather than by parking, may instead register
+//! a `Waker` with `register_waker`, which the buzzers of the thread then also wake.
 
-use std::thread::Thread;
+use std::task::Waker;
+
+use crate::sync::{Arc, AtomicBool, Mutex, Ordering};
+use crate::sync::thread::{self, Thread};
+
+crate::sync::thread_local! {
+    // The waker registered for the current thread, shared by buzzers of the thread.
+    static WAKER: Arc<WakerSlot> = Arc::new(WakerSlot { registered: AtomicBool::new(false), waker: Mutex::new(None) });
+}
+
+/// The waker registered for a thread, if any.
+struct WakerSlot {
+    /// Set once a waker is registered, so that buzzes need not lock `waker` otherwise.
+    registered: AtomicBool,
+    waker: Mutex<Option<Waker>>,
+}
 
 /// Can unpark a specific thread.
 #[derive(Clone)]
 pub struct Buzzer {
     thread: Thread,
+    waker: Arc<WakerSlot>,
 }
 
 impl Buzzer {
     /// Creates a new buzzer for the current thread.
     pub fn new() -> Self {
         Self {
-            thread: std::thread::current()
+            thread: thread::current(),
+            waker: WAKER.with(|waker| waker.clone()),
         }
     }
-    /// Unparks the target thread.
+    /// Unparks the target thread, and wakes any waker registered for it.
     pub fn buzz(&self) {
-        self.thread.unpark()
+        self.thread.unpark();
+        // Either this swap observes a registration, or the registering thread's swap observes
+        // this one, and with it whatever was sent before the buzz.
+        if self.waker.registered.swap(false, Ordering::AcqRel) {
+            let waker = self.waker.waker.lock().expect("buzzer waker poisoned").take();
+            if let Some(waker) = waker {
+                waker.wake();
+            }
+        }
     }
-}
\ No newline at end of file
+}
+
+/// Registers `waker` to be woken by the next buzz of any buzzer for the current thread.
+///
+/// The waker is woken at most once, and should be registered again before each wait.
+pub fn register_waker(waker: &Waker) {
+    WAKER.with(|slot| {
+        let mut registered = slot.waker.lock().expect("buzzer waker poisoned");
+        if !registered.as_ref().map(|registered| registered.will_wake(waker)).unwrap_or(false) {
+            *registered = Some(waker.clone());
+        }
+        slot.registered.swap(true, Ordering::AcqRel);
+    });
+}
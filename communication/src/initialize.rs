@@ -2,7 +2,7 @@
 
 use std::thread;
 #[cfg(feature = "getopts")]
-use std::io::BufRead;
+use std::io::Read;
 #[cfg(feature = "getopts")]
 use getopts;
 use std::sync::Arc;
@@ -11,9 +11,10 @@ use std::any::Any;
 
 use crate::allocator::thread::ThreadBuilder;
 use crate::allocator::{AllocateBuilder, Process, Generic, GenericBuilder};
-use crate::allocator::zero_copy::initialize::initialize_networking;
+use crate::allocator::zero_copy::initialize::{initialize_networking_with, try_initialize_networking_with};
 
 use crate::logging::{CommunicationSetup, CommunicationEvent};
+use crate::message::Serialization;
 use logging_core::Logger;
 
 
@@ -38,6 +39,266 @@ pub enum Configuration {
     }
 }
 
+/// A failure to initialize communication, reported by the fallible variants of initialization.
+///
+/// Embedding services may distinguish configurations to correct from failures to retry, such
+/// as ports in use or processes not yet reachable.
+///
+/// # Examples
+/// ```
+/// use timely_communication::{Configuration, InitializeError};
+///
+/// let args = vec!["program", "-n", "2", "-p", "2"].into_iter().map(|arg| arg.to_string());
+/// match Configuration::try_from_args(args) {
+///     Err(InitializeError::Config(message)) => println!("invalid configuration: {}", message),
+///     _ => panic!("process 2 of 2 accepted"),
+/// }
+/// ```
+#[derive(Debug)]
+pub enum InitializeError {
+    /// The configuration or arguments do not describe a computation, as described.
+    Config(String),
+    /// The process could not listen for connections at its address.
+    Bind {
+        /// The address at which the process would listen.
+        address: String,
+        /// The error binding the address.
+        error: ::std::io::Error,
+    },
+    /// Another process could not be reached, for reasons other than that it does not yet listen.
+    Unreachable {
+        /// The identity of the process.
+        process: usize,
+        /// The address at which the process is reached.
+        address: String,
+        /// The error connecting to the process.
+        error: ::std::io::Error,
+    },
+    /// A connection did not present the handshake of a process of the computation.
+    Handshake {
+        /// The identity the connection presented, if it presented one.
+        process: Option<usize>,
+        /// A description of the mismatch.
+        message: String,
+    },
+    /// Another process serializes messages with a different backend than this process.
+    Serialization {
+        /// The identity of the process.
+        process: usize,
+        /// The backend of the process.
+        theirs: Serialization,
+        /// The backend of this process.
+        ours: Serialization,
+    },
+    /// Another input or output error, as when connections fail or threads cannot be spawned.
+    Io(::std::io::Error),
+}
+
+impl InitializeError {
+    pub(crate) fn handshake(process: Option<usize>, message: &str) -> Self {
+        InitializeError::Handshake { process, message: message.to_string() }
+    }
+}
+
+impl ::std::fmt::Display for InitializeError {
+    fn fmt(&self, f: &mut ::std::fmt::Formatter) -> ::std::fmt::Result {
+        match self {
+            InitializeError::Config(message) => write!(f, "invalid configuration: {}", message),
+            InitializeError::Bind { address, error } => write!(f, "failed to listen at {}: {}", address, error),
+            InitializeError::Unreachable { process, address, error } => write!(f, "failed to reach process {} at {}: {}", process, address, error),
+            InitializeError::Handshake { process: Some(process), message } => write!(f, "handshake from process {} failed: {}", process, message),
+            InitializeError::Handshake { process: None, message } => write!(f, "handshake failed: {}", message),
+            InitializeError::Serialization { process, theirs, ours } => write!(f, "process {} serializes with {}, but this process with {}", process, theirs, ours),
+            InitializeError::Io(error) => write!(f, "failed to initialize communication: {}", error),
+        }
+    }
+}
+
+impl ::std::error::Error for InitializeError {
+    fn source(&self) -> Option<&(dyn ::std::error::Error + 'static)> {
+        match self {
+            InitializeError::Bind { error, .. } | InitializeError::Unreachable { error, .. } | InitializeError::Io(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+impl From<::std::io::Error> for InitializeError {
+    fn from(error: ::std::io::Error) -> Self {
+        InitializeError::Io(error)
+    }
+}
+
+impl From<InitializeError> for ::std::io::Error {
+    fn from(error: InitializeError) -> Self {
+        match error {
+            InitializeError::Io(error) => error,
+            InitializeError::Config(_) => ::std::io::Error::new(::std::io::ErrorKind::InvalidInput, error.to_string()),
+            InitializeError::Handshake { .. } | InitializeError::Serialization { .. } => ::std::io::Error::new(::std::io::ErrorKind::InvalidData, error.to_string()),
+            InitializeError::Bind { error: ref inner, .. } | InitializeError::Unreachable { error: ref inner, .. } => ::std::io::Error::new(inner.kind(), error.to_string()),
+        }
+    }
+}
+
+/// The kinds of threads spawned by `initialize`.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub enum ThreadKind {
+    /// A worker thread, with the index of its worker.
+    Worker,
+    /// A network thread sending to another process, with the index of that process.
+    Sender,
+    /// A network thread receiving from another process, with the index of that process.
+    Receiver,
+}
+
+/// A function invoked in each spawned thread, with its kind and index, before the thread starts its work.
+pub type SpawnHook = Arc<dyn Fn(ThreadKind, usize)+Send+Sync>;
+
+/// The names of spawned threads, and a hook to run in each before it starts its work.
+///
+/// The names are templates in which `{}` is replaced by the index of the worker, or of the
+/// remote process for network threads. The hook may set thread priorities or affinities, or
+/// install thread-local state such as tracing subscribers, before the worker runs its closure
+/// or the network thread starts its loop.
+///
+/// # Examples
+/// ```
+/// use std::sync::Arc;
+/// use timely_communication::{Configuration, ThreadConfig, ThreadKind};
+///
+/// let threads = ThreadConfig {
+///     worker_name: "compute-{}".to_string(),
+///     on_start: Some(Arc::new(|kind, index| {
+///         assert_eq!(kind, ThreadKind::Worker);
+///         assert_eq!(std::thread::current().name(), Some(&format!("compute-{}", index)[..]));
+///     })),
+///     ..Default::default()
+/// };
+///
+/// let (builders, others) = Configuration::Process(2).try_build_with(&threads).unwrap();
+/// let guards = timely_communication::initialize_from_with(builders, others, threads, |_allocator| {
+///     std::thread::current().name().map(|name| name.to_string())
+/// }).unwrap();
+///
+/// let names = guards.join().into_iter().map(|name| name.unwrap().unwrap()).collect::<Vec<_>>();
+/// assert_eq!(names, vec!["compute-0", "compute-1"]);
+/// ```
+#[derive(Clone)]
+pub struct ThreadConfig {
+    /// The name of each worker thread.
+    pub worker_name: String,
+    /// The name of each thread sending to another process.
+    pub sender_name: String,
+    /// The name of each thread receiving from another process.
+    pub receiver_name: String,
+    /// A function to invoke in each thread before it starts its work.
+    pub on_start: Option<SpawnHook>,
+}
+
+impl ThreadConfig {
+    /// The name of a thread of `kind` for `index`.
+    pub fn name(&self, kind: ThreadKind, index: usize) -> String {
+        let template = match kind {
+            ThreadKind::Worker => &self.worker_name,
+            ThreadKind::Sender => &self.sender_name,
+            ThreadKind::Receiver => &self.receiver_name,
+        };
+        template.replace("{}", &index.to_string())
+    }
+
+    /// Spawns a thread of `kind` for `index`, which invokes the hook before running `body`.
+    pub fn spawn<T, F>(&self, kind: ThreadKind, index: usize, body: F) -> ::std::io::Result<thread::JoinHandle<T>>
+    where
+        T: Send+'static,
+        F: FnOnce()->T+Send+'static,
+    {
+        let hook = self.on_start.clone();
+        thread::Builder::new()
+            .name(self.name(kind, index))
+            .spawn(move || {
+                if let Some(hook) = hook {
+                    hook(kind, index);
+                }
+                body()
+            })
+    }
+}
+
+impl Default for ThreadConfig {
+    fn default() -> Self {
+        ThreadConfig {
+            worker_name: "worker thread {}".to_string(),
+            sender_name: "send thread {}".to_string(),
+            receiver_name: "recv thread {}".to_string(),
+            on_start: None,
+        }
+    }
+}
+
+/// A process of a cluster, as described by a line of a hostfile.
+#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+pub struct Host {
+    /// The address at which other processes connect to the process.
+    pub advertise: String,
+    /// The address at which the process listens for connections, if not its advertised address.
+    pub bind: Option<String>,
+    /// The number of workers of the process, if not the number given to each process.
+    pub workers: Option<usize>,
+}
+
+impl Host {
+    /// The address at which the process listens for connections.
+    pub fn bind(&self) -> &str {
+        self.bind.as_ref().unwrap_or(&self.advertise)
+    }
+}
+
+/// Parses the processes of a cluster from the text of a hostfile.
+///
+/// Each line describes a process, in order of their identities, with the address at which the
+/// other processes connect to it, and then optionally its number of workers, as `workers=N`,
+/// and the address at which it listens for connections, as `bind=ADDR`, for processes behind
+/// a translation of addresses. Text following a `#` is a comment, and blank lines are ignored.
+/// Processes may have different numbers of workers, whose indices are assigned in order of the
+/// processes.
+///
+/// # Examples
+/// ```
+/// use timely_communication::initialize::{Host, parse_hostfile};
+///
+/// let text = "
+///     big.example.com:2101    workers=16                      # a large machine
+///     small.example.com:2101  workers=2 bind=0.0.0.0:2101     # a small one, behind a NAT
+/// ";
+///
+/// let hosts = parse_hostfile(text).unwrap();
+/// assert_eq!(hosts[0], Host { advertise: "big.example.com:2101".to_string(), bind: None, workers: Some(16) });
+/// assert_eq!(hosts[1].bind(), "0.0.0.0:2101");
+/// assert!(parse_hostfile("host:2101 workers=many").is_err());
+/// ```
+pub fn parse_hostfile(text: &str) -> Result<Vec<Host>, String> {
+    let mut hosts = Vec::new();
+    for (number, line) in text.lines().enumerate() {
+        let line = line.split('#').next().unwrap_or("");
+        let mut fields = line.split_whitespace();
+        if let Some(advertise) = fields.next() {
+            let mut host = Host { advertise: advertise.to_string(), bind: None, workers: None };
+            for field in fields {
+                match field.split_once('=') {
+                    Some(("workers", workers)) => {
+                        let workers = workers.parse().map_err(|_| format!("line {}: invalid number of workers: {}", number + 1, workers))?;
+                        host.workers = Some(workers);
+                    },
+                    Some(("bind", bind)) => { host.bind = Some(bind.to_string()); },
+                    _ => return Err(format!("line {}: unrecognized field: {}", number + 1, field)),
+                }
+            }
+            hosts.push(host);
+        }
+    }
+    Ok(hosts)
+}
+
 #[cfg(feature = "getopts")]
 impl Configuration {
 
@@ -48,7 +309,7 @@ impl Configuration {
         opts.optopt("w", "threads", "number of per-process worker threads", "NUM");
         opts.optopt("p", "process", "identity of this process", "IDX");
         opts.optopt("n", "processes", "number of processes", "NUM");
-        opts.optopt("h", "hostfile", "text file whose lines are process addresses", "FILE");
+        opts.optopt("h", "hostfile", "text file whose lines describe processes, as for `parse_hostfile`", "FILE");
         opts.optflag("r", "report", "reports connection progress");
 
         opts
@@ -58,53 +319,124 @@ impl Configuration {
     ///
     /// Most commonly, this uses `std::env::Args()` as the supplied iterator.
     pub fn from_args<I: Iterator<Item=String>>(args: I) -> Result<Configuration,String> {
+        Configuration::try_from_args(args).map_err(|error| error.to_string())
+    }
+
+    /// Constructs a new configuration by parsing supplied text arguments, reporting why they do not describe one.
+    ///
+    /// Options whose values are not numbers, processes beyond the number of processes, and
+    /// hostfiles that cannot be read or describe too few processes are reported as
+    /// `InitializeError::Config`.
+    pub fn try_from_args<I: Iterator<Item=String>>(args: I) -> Result<Configuration,InitializeError> {
         let opts = Configuration::options();
+        let matches = opts.parse(args).map_err(|error| InitializeError::Config(error.to_string()))?;
+        let number = |option: &str, default: usize| -> Result<usize, InitializeError> {
+            match matches.opt_str(option) {
+                Some(value) => value.parse().map_err(|_| InitializeError::Config(format!("invalid value for -{}: {}", option, value))),
+                None => Ok(default),
+            }
+        };
+
+        let mut threads = number("w", 1)?;
+        let process = number("p", 0)?;
+        let processes = number("n", 1)?;
+        let report = matches.opt_present("report");
 
-        opts.parse(args)
-            .map_err(|e| format!("{:?}", e))
-            .map(|matches| {
-
-            // let mut config = Configuration::new(1, 0, Vec::new());
-            let threads = matches.opt_str("w").map(|x| x.parse().unwrap_or(1)).unwrap_or(1);
-            let process = matches.opt_str("p").map(|x| x.parse().unwrap_or(0)).unwrap_or(0);
-            let processes = matches.opt_str("n").map(|x| x.parse().unwrap_or(1)).unwrap_or(1);
-            let report = matches.opt_present("report");
-
-            assert!(process < processes);
-
-            if processes > 1 {
-                let mut addresses = Vec::new();
-                if let Some(hosts) = matches.opt_str("h") {
-                    let reader = ::std::io::BufReader::new(::std::fs::File::open(hosts.clone()).unwrap());
-                    for x in reader.lines().take(processes) {
-                        addresses.push(x.unwrap());
-                    }
-                    if addresses.len() < processes {
-                        panic!("could only read {} addresses from {}, but -n: {}", addresses.len(), hosts, processes);
-                    }
+        if process >= processes {
+            return Err(InitializeError::Config(format!("process {} is not among the {} processes", process, processes)));
+        }
+
+        let configuration =
+        if processes > 1 {
+            let mut addresses = Vec::new();
+            if let Some(hostfile) = matches.opt_str("h") {
+                let mut text = String::new();
+                ::std::fs::File::open(&hostfile)
+                    .and_then(|mut file| file.read_to_string(&mut text))
+                    .map_err(|error| InitializeError::Config(format!("failed to read {}: {}", hostfile, error)))?;
+                let hosts = parse_hostfile(&text).map_err(|error| InitializeError::Config(format!("failed to parse {}: {}", hostfile, error)))?;
+                if hosts.len() < processes {
+                    return Err(InitializeError::Config(format!("could only read {} addresses from {}, but -n: {}", hosts.len(), hostfile, processes)));
                 }
-                else {
-                    for index in 0..processes {
-                        addresses.push(format!("localhost:{}", 2101 + index));
-                    }
+                // Other processes are reached at their advertised addresses, and this process listens at its bind address.
+                for (index, host) in hosts.iter().take(processes).enumerate() {
+                    addresses.push(if index == process { host.bind().to_string() } else { host.advertise.clone() });
                 }
-
-                assert!(processes == addresses.len());
-                Configuration::Cluster {
-                    threads,
-                    process,
-                    addresses,
-                    report,
-                    log_fn: Box::new( | _ | None),
+                threads = hosts[process].workers.unwrap_or(threads);
+            }
+            else {
+                for index in 0..processes {
+                    addresses.push(format!("localhost:{}", 2101 + index));
                 }
             }
-            else if threads > 1 { Configuration::Process(threads) }
-            else { Configuration::Thread }
-        })
+
+            Configuration::Cluster {
+                threads,
+                process,
+                addresses,
+                report,
+                log_fn: Box::new( | _ | None),
+            }
+        }
+        else if threads > 1 { Configuration::Process(threads) }
+        else { Configuration::Thread };
+
+        configuration.validate()?;
+        Ok(configuration)
     }
 
     /// Attempts to assemble the described communication infrastructure.
     pub fn try_build(self) -> Result<(Vec<GenericBuilder>, Box<dyn Any>), String> {
+        self.try_build_with(&ThreadConfig::default())
+    }
+
+    /// Attempts to assemble the described communication infrastructure, spawning network threads as `threads_config` describes.
+    ///
+    /// Connections to processes that cannot be reached are retried until they succeed.
+    pub fn try_build_with(self, threads_config: &ThreadConfig) -> Result<(Vec<GenericBuilder>, Box<dyn Any>), String> {
+        self.build_retrying_with(threads_config).map_err(|error| error.to_string())
+    }
+}
+
+impl Configuration {
+
+    /// Checks that the configuration describes a computation, without connecting to other processes.
+    pub fn validate(&self) -> Result<(), InitializeError> {
+        match self {
+            Configuration::Thread => Ok(()),
+            Configuration::Process(0) | Configuration::Cluster { threads: 0, .. } => {
+                Err(InitializeError::Config("each process must have at least one worker".to_string()))
+            },
+            Configuration::Process(_) => Ok(()),
+            Configuration::Cluster { process, addresses, .. } if *process >= addresses.len() => {
+                Err(InitializeError::Config(format!("process {} is not among the {} addresses", process, addresses.len())))
+            },
+            Configuration::Cluster { .. } => Ok(()),
+        }
+    }
+
+    /// Assembles the described communication infrastructure, reporting why it could not be.
+    pub fn build(self) -> Result<(Vec<GenericBuilder>, Box<dyn Any>), InitializeError> {
+        self.build_with(&ThreadConfig::default())
+    }
+
+    /// Assembles the described communication infrastructure, spawning network threads as `threads_config` describes.
+    ///
+    /// Unlike `try_build_with`, failures are reported as an `InitializeError`, and processes
+    /// that cannot be reached are reported rather than retried indefinitely.
+    pub fn build_with(self, threads_config: &ThreadConfig) -> Result<(Vec<GenericBuilder>, Box<dyn Any>), InitializeError> {
+        self.assemble(threads_config, false)
+    }
+
+    /// Assembles the described communication infrastructure as `build_with` does, retrying connections
+    /// to processes that cannot be reached until they succeed, as `try_build_with` does.
+    pub fn build_retrying_with(self, threads_config: &ThreadConfig) -> Result<(Vec<GenericBuilder>, Box<dyn Any>), InitializeError> {
+        self.assemble(threads_config, true)
+    }
+
+    // Assembles the communication infrastructure, retrying unreachable processes indefinitely if `retry` is set.
+    fn assemble(self, threads_config: &ThreadConfig, retry: bool) -> Result<(Vec<GenericBuilder>, Box<dyn Any>), InitializeError> {
+        self.validate()?;
         match self {
             Configuration::Thread => {
                 Ok((vec![GenericBuilder::Thread(ThreadBuilder)], Box::new(())))
@@ -113,12 +445,13 @@ impl Configuration {
                 Ok((Process::new_vector(threads).into_iter().map(|x| GenericBuilder::Process(x)).collect(), Box::new(())))
             },
             Configuration::Cluster { threads, process, addresses, report, log_fn } => {
-                match initialize_networking(addresses, process, threads, report, log_fn) {
-                    Ok((stuff, guard)) => {
-                        Ok((stuff.into_iter().map(|x| GenericBuilder::ZeroCopy(x)).collect(), Box::new(guard)))
-                    },
-                    Err(err) => Err(format!("failed to initialize networking: {}", err))
+                let (stuff, guard) = if retry {
+                    initialize_networking_with(addresses, process, threads, report, log_fn, threads_config)?
                 }
+                else {
+                    try_initialize_networking_with(addresses, process, threads, report, log_fn, threads_config)?
+                };
+                Ok((stuff.into_iter().map(|x| GenericBuilder::ZeroCopy(x)).collect(), Box::new(guard)))
             },
         }
     }
@@ -249,10 +582,42 @@ pub fn initialize<T:Send+'static, F: Fn(Generic)->T+Send+Sync+'static>(
 /// else { println!("error in computation"); }
 /// ```
 pub fn initialize_from<A, T, F>(
+    builders: Vec<A>,
+    others: Box<dyn Any>,
+    func: F,
+) -> Result<WorkerGuards<T>,String>
+where
+    A: AllocateBuilder+'static,
+    T: Send+'static,
+    F: Fn(<A as AllocateBuilder>::Allocator)->T+Send+Sync+'static
+{
+    initialize_from_with(builders, others, ThreadConfig::default(), func)
+}
+
+/// Initializes computation and runs a distributed computation, spawning worker threads as `threads` describes.
+///
+/// See `ThreadConfig` for an example.
+pub fn initialize_from_with<A, T, F>(
     builders: Vec<A>,
     _others: Box<dyn Any>,
+    threads: ThreadConfig,
     func: F,
 ) -> Result<WorkerGuards<T>,String>
+where
+    A: AllocateBuilder+'static,
+    T: Send+'static,
+    F: Fn(<A as AllocateBuilder>::Allocator)->T+Send+Sync+'static
+{
+    try_initialize_from_with(builders, _others, threads, func).map_err(|error| error.to_string())
+}
+
+/// Initializes computation as `initialize_from_with` does, reporting why worker threads could not be spawned.
+pub fn try_initialize_from_with<A, T, F>(
+    builders: Vec<A>,
+    _others: Box<dyn Any>,
+    threads: ThreadConfig,
+    func: F,
+) -> Result<WorkerGuards<T>,InitializeError>
 where
     A: AllocateBuilder+'static,
     T: Send+'static,
@@ -262,13 +627,10 @@ where
     let mut guards = Vec::new();
     for (index, builder) in builders.into_iter().enumerate() {
         let clone = logic.clone();
-        guards.push(thread::Builder::new()
-                            .name(format!("worker thread {}", index))
-                            .spawn(move || {
-                                let communicator = builder.build();
-                                (*clone)(communicator)
-                            })
-                            .map_err(|e| format!("{:?}", e))?);
+        guards.push(threads.spawn(ThreadKind::Worker, index, move || {
+                               let communicator = builder.build();
+                               (*clone)(communicator)
+                           })?);
     }
 
     Ok(WorkerGuards { guards, _others })
@@ -294,6 +656,16 @@ impl<T:Send+'static> WorkerGuards<T> {
             .map(|guard| guard.join().map_err(|e| format!("{:?}", e)))
             .collect()
     }
+
+    /// Waits on the worker threads and returns the results they produce, or their panic payloads.
+    ///
+    /// Unlike `join`, the payloads of panics are presented intact, so that they may be downcast.
+    pub fn join_payloads(mut self) -> Vec<::std::thread::Result<T>> {
+        self.guards
+            .drain(..)
+            .map(|guard| guard.join())
+            .collect()
+    }
 }
 
 impl<T:Send+'static> Drop for WorkerGuards<T> {
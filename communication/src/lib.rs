@@ -94,6 +94,8 @@ pub mod initialize;
 pub mod logging;
 pub mod message;
 pub mod buzzer;
+pub mod bootstrap;
+mod sync;
 
 use std::any::Any;
 
@@ -104,8 +106,8 @@ use abomonation::Abomonation;
 
 pub use allocator::Generic as Allocator;
 pub use allocator::Allocate;
-pub use initialize::{initialize, initialize_from, Configuration, WorkerGuards};
-pub use message::Message;
+pub use initialize::{initialize, initialize_from, initialize_from_with, try_initialize_from_with, Configuration, ThreadConfig, ThreadKind, WorkerGuards, Host, parse_hostfile, InitializeError};
+pub use message::{Message, Serialization};
 
 /// A composite trait for types that may be used with channels.
 #[cfg(not(feature = "bincode"))]
@@ -167,7 +169,7 @@ impl<T, P: ?Sized + Pull<T>> Pull<T> for Box<P> {
 }
 
 
-use std::sync::mpsc::{Sender, Receiver, channel};
+use crate::sync::mpsc::{Sender, Receiver, channel};
 
 /// Allocate a matrix of send and receive changes to exchange items.
 ///
@@ -1,7 +1,11 @@
 //! Configuration and events for communication logging.
+//!
+//! The events serialize under the versioned schema described by `timely::logging::schema`.
+
+use serde_derive::{Serialize, Deserialize};
 
 /// Configuration information about a communication thread.
-#[derive(Abomonation, Debug, PartialEq, Eq, Hash, Clone, Copy)]
+#[derive(Abomonation, Serialize, Deserialize, Debug, PartialEq, Eq, Hash, Clone, Copy)]
 pub struct CommunicationSetup {
     /// True when this is a send thread (or the receive thread).
     pub sender: bool,
@@ -12,16 +16,18 @@ pub struct CommunicationSetup {
 }
 
 /// Various communication events.
-#[derive(Abomonation, Debug, PartialEq, Eq, Hash, Clone, Copy)]
+#[derive(Abomonation, Serialize, Deserialize, Debug, PartialEq, Eq, Hash, Clone, Copy)]
 pub enum CommunicationEvent {
     /// An observed message.
     Message(MessageEvent),
     /// A state transition.
     State(StateEvent),
+    /// A queue of a network thread exceeding its threshold.
+    Queue(QueueEvent),
 }
 
 /// An observed message.
-#[derive(Abomonation, Debug, PartialEq, Eq, Hash, Clone, Copy)]
+#[derive(Abomonation, Serialize, Deserialize, Debug, PartialEq, Eq, Hash, Clone, Copy)]
 pub struct MessageEvent {
     /// true for send event, false for receive event
     pub is_send: bool,
@@ -30,7 +36,7 @@ pub struct MessageEvent {
 }
 
 /// Starting or stopping communication threads.
-#[derive(Abomonation, Debug, PartialEq, Eq, Hash, Clone, Copy)]
+#[derive(Abomonation, Serialize, Deserialize, Debug, PartialEq, Eq, Hash, Clone, Copy)]
 pub struct StateEvent {
     /// Is the thread a send (vs a recv) thread.
     pub send: bool,
@@ -42,9 +48,33 @@ pub struct StateEvent {
     pub start: bool,
 }
 
+/// The bytes awaiting a network thread, or awaiting workers from it, exceeding `SATURATION_BYTES`.
+///
+/// The event is logged when the queue first exceeds the threshold, and again only once it has
+/// fallen back below it.
+#[derive(Abomonation, Serialize, Deserialize, Debug, PartialEq, Eq, Hash, Clone, Copy)]
+pub struct QueueEvent {
+    /// Is the queue that of a send thread, awaiting the network, or of a receive thread, awaiting workers.
+    pub send: bool,
+    /// The host process id.
+    pub process: usize,
+    /// The remote process id.
+    pub remote: usize,
+    /// For receive threads, the index of the worker whose queue is saturated.
+    pub worker: Option<usize>,
+    /// The number of bytes queued.
+    pub bytes: usize,
+}
+
 impl From<MessageEvent> for CommunicationEvent {
     fn from(v: MessageEvent) -> CommunicationEvent { CommunicationEvent::Message(v) }
 }
 impl From<StateEvent> for CommunicationEvent {
     fn from(v: StateEvent) -> CommunicationEvent { CommunicationEvent::State(v) }
 }
+impl From<QueueEvent> for CommunicationEvent {
+    fn from(v: QueueEvent) -> CommunicationEvent { CommunicationEvent::Queue(v) }
+}
+
+/// The number of queued bytes beyond which the queues of network threads are reported as saturated.
+pub const SATURATION_BYTES: usize = 1 << 24;
@@ -5,6 +5,73 @@ use bytes::arc::Bytes;
 use abomonation;
 use crate::Data;
 
+/// A backend serializing the messages sent between processes, chosen as the crate is built.
+///
+/// The backend is `Abomonation` unless the crate is built with the `bincode` feature, and the
+/// processes of a computation must be built alike, which they verify as they connect.
+///
+/// # Examples
+/// ```
+/// use timely_communication::Serialization;
+///
+/// let backend = Serialization::current();
+/// assert_eq!(backend.name().parse(), Ok(backend));
+/// assert!("columnar".parse::<Serialization>().is_err());
+/// ```
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+pub enum Serialization {
+    /// Serialization with `abomonation`, which requires types to implement `Abomonation`.
+    Abomonation,
+    /// Serialization with `bincode`, which requires types to implement `Serialize` and `Deserialize`.
+    Bincode,
+}
+
+impl Serialization {
+    /// The backend of this build.
+    pub fn current() -> Self {
+        if cfg!(feature = "bincode") { Serialization::Bincode } else { Serialization::Abomonation }
+    }
+    /// The name of the backend, as it appears in configurations.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Serialization::Abomonation => "abomonation",
+            Serialization::Bincode => "bincode",
+        }
+    }
+    /// The code by which processes identify the backend as they connect.
+    pub(crate) fn code(&self) -> u64 {
+        match self {
+            Serialization::Abomonation => 0,
+            Serialization::Bincode => 1,
+        }
+    }
+    /// The backend identified by `code`, if any.
+    pub(crate) fn from_code(code: u64) -> Option<Self> {
+        match code {
+            0 => Some(Serialization::Abomonation),
+            1 => Some(Serialization::Bincode),
+            _ => None,
+        }
+    }
+}
+
+impl ::std::fmt::Display for Serialization {
+    fn fmt(&self, f: &mut ::std::fmt::Formatter) -> ::std::fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl ::std::str::FromStr for Serialization {
+    type Err = String;
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        match name {
+            "abomonation" => Ok(Serialization::Abomonation),
+            "bincode" => Ok(Serialization::Bincode),
+            _ => Err(format!("unknown serialization backend: {}", name)),
+        }
+    }
+}
+
 /// Either an immutable or mutable reference.
 pub enum RefOrMut<'a, T> where T: 'a {
     /// An immutable reference.
@@ -86,12 +153,16 @@ impl<T> Message<T> {
     ///
     /// This method returns a mutable reference if the underlying data are typed Rust
     /// instances, which admit mutation, and it returns an immutable reference if the
-    /// data are serialized binary data.
+    /// data are serialized binary data. Shared data are returned mutably only once no
+    /// other references to them remain.
     pub fn as_ref_or_mut(&mut self) -> RefOrMut<T> {
         match &mut self.payload {
             MessageContents::Binary(bytes) => { RefOrMut::Ref(bytes) },
             MessageContents::Owned(typed) => { RefOrMut::Mut(typed) },
-            MessageContents::Arc(typed) => { RefOrMut::Ref(typed) },
+            MessageContents::Arc(typed) => {
+                if Arc::get_mut(typed).is_some() { RefOrMut::Mut(Arc::get_mut(typed).unwrap()) }
+                else { RefOrMut::Ref(typed) }
+            },
         }
     }
 }
@@ -191,8 +262,7 @@ impl<T: Clone> Message<T> {
         match self.payload {
             MessageContents::Binary(bytes) => bytes.clone(),
             MessageContents::Owned(instance) => instance,
-            // TODO: Could attempt `Arc::try_unwrap()` here.
-            MessageContents::Arc(instance) => (*instance).clone(),
+            MessageContents::Arc(instance) => Arc::try_unwrap(instance).unwrap_or_else(|instance| (*instance).clone()),
         }
     }
     /// Ensures the message is typed data and returns a mutable reference to it.
@@ -1,7 +1,7 @@
 //! Networking code for sending and receiving fixed size `Vec<u8>` between machines.
 
 use std::io;
-use std::io::{Read, Result};
+use std::io::{Read, Write, Result};
 use std::net::{TcpListener, TcpStream};
 use std::sync::Arc;
 use std::thread;
@@ -9,6 +9,10 @@ use std::thread::sleep;
 use std::time::Duration;
 
 use abomonation::{encode, decode};
+use serde_derive::{Serialize, Deserialize};
+
+use crate::initialize::InitializeError;
+use crate::message::Serialization;
 
 // This constant is sent along immediately after establishing a TCP stream, so
 // that it is easy to sniff out Timely traffic when it is multiplexed with
@@ -17,7 +21,7 @@ const HANDSHAKE_MAGIC: u64 = 0xc2f1fb770118add9;
 
 /// Framing data for each `Vec<u8>` transmission, indicating a typed channel, the source and
 /// destination workers, and the length in bytes.
-#[derive(Abomonation, Debug, PartialEq, Eq, Hash, Clone, Copy)]
+#[derive(Abomonation, Serialize, Deserialize, Debug, PartialEq, Eq, Hash, Clone, Copy)]
 pub struct MessageHeader {
     /// index of channel.
     pub channel:    usize,
@@ -64,66 +68,151 @@ impl MessageHeader {
 /// The item at index i in the resulting vec, is a Some(TcpSocket) to process i, except
 /// for item `my_index` which is None (no socket to self).
 pub fn create_sockets(addresses: Vec<String>, my_index: usize, noisy: bool) -> Result<Vec<Option<TcpStream>>> {
+    connect_sockets(addresses, my_index, noisy, true).map_err(io::Error::from)
+}
+
+/// Creates socket connections from a list of host addresses, reporting why they could not be.
+///
+/// Connections refused or timed out are retried, as other processes may not yet listen, and
+/// other failures to connect, such as addresses that do not resolve, report the process as
+/// unreachable rather than retry indefinitely.
+pub fn try_create_sockets(addresses: Vec<String>, my_index: usize, noisy: bool) -> ::std::result::Result<Vec<Option<TcpStream>>, InitializeError> {
+    connect_sockets(addresses, my_index, noisy, false)
+}
+
+fn connect_sockets(addresses: Vec<String>, my_index: usize, noisy: bool, persistent: bool) -> ::std::result::Result<Vec<Option<TcpStream>>, InitializeError> {
+
+    if my_index >= addresses.len() {
+        return Err(InitializeError::Config(format!("process {} is not among the {} addresses", my_index, addresses.len())));
+    }
 
     let hosts1 = Arc::new(addresses);
     let hosts2 = hosts1.clone();
 
-    let start_task = thread::spawn(move || start_connections(hosts1, my_index, noisy));
-    let await_task = thread::spawn(move || await_connections(hosts2, my_index, noisy));
+    // The first failure is reported without awaiting the other task, which may not complete.
+    let (send1, recv) = ::std::sync::mpsc::channel();
+    let send2 = send1.clone();
+    thread::spawn(move || send1.send((true, connect_all(hosts1, my_index, noisy, persistent))));
+    thread::spawn(move || send2.send((false, accept_all(hosts2, my_index, noisy))));
+
+    let mut started = None;
+    let mut awaited = None;
+    for _ in 0 .. 2 {
+        match recv.recv() {
+            Ok((true, result)) => started = Some(result?),
+            Ok((false, result)) => awaited = Some(result?),
+            Err(_) => return Err(InitializeError::Io(io::Error::other("connection thread panicked"))),
+        }
+    }
 
-    let mut results = start_task.join().unwrap()?;
+    let mut results = started.unwrap_or_default();
     results.push(None);
-    let to_extend = await_task.join().unwrap()?;
-    results.extend(to_extend.into_iter());
+    results.extend(awaited.unwrap_or_default());
 
     if noisy { println!("worker {}:\tinitialization complete", my_index) }
 
     Ok(results)
 }
 
-
 /// Result contains connections [0, my_index - 1].
 pub fn start_connections(addresses: Arc<Vec<String>>, my_index: usize, noisy: bool) -> Result<Vec<Option<TcpStream>>> {
-    let results = addresses.iter().take(my_index).enumerate().map(|(index, address)| {
+    connect_all(addresses, my_index, noisy, true).map_err(io::Error::from)
+}
+
+fn connect_all(addresses: Arc<Vec<String>>, my_index: usize, noisy: bool, persistent: bool) -> ::std::result::Result<Vec<Option<TcpStream>>, InitializeError> {
+    addresses.iter().take(my_index).enumerate().map(|(index, address)| {
         loop {
             match TcpStream::connect(address) {
                 Ok(mut stream) => {
-                    stream.set_nodelay(true).expect("set_nodelay call failed");
-                    unsafe { encode(&HANDSHAKE_MAGIC, &mut stream) }.expect("failed to encode/send handshake magic");
-                    unsafe { encode(&(my_index as u64), &mut stream) }.expect("failed to encode/send worker index");
+                    stream.set_nodelay(true)?;
+                    unsafe { encode(&HANDSHAKE_MAGIC, &mut stream) }?;
+                    unsafe { encode(&(my_index as u64), &mut stream) }?;
                     if noisy { println!("worker {}:\tconnection to worker {}", my_index, index); }
-                    break Some(stream);
+                    break Ok(Some(stream));
                 },
-                Err(error) => {
+                Err(error) if persistent || matches!(error.kind(), io::ErrorKind::ConnectionRefused | io::ErrorKind::TimedOut) => {
                     println!("worker {}:\terror connecting to worker {}: {}; retrying", my_index, index, error);
                     sleep(Duration::from_secs(1));
                 },
+                Err(error) => break Err(InitializeError::Unreachable { process: index, address: address.clone(), error }),
             }
         }
-    }).collect();
-
-    Ok(results)
+    }).collect()
 }
 
 /// Result contains connections [my_index + 1, addresses.len() - 1].
 pub fn await_connections(addresses: Arc<Vec<String>>, my_index: usize, noisy: bool) -> Result<Vec<Option<TcpStream>>> {
+    accept_all(addresses, my_index, noisy).map_err(io::Error::from)
+}
+
+fn accept_all(addresses: Arc<Vec<String>>, my_index: usize, noisy: bool) -> ::std::result::Result<Vec<Option<TcpStream>>, InitializeError> {
     let mut results: Vec<_> = (0..(addresses.len() - my_index - 1)).map(|_| None).collect();
-    let listener = TcpListener::bind(&addresses[my_index][..])?;
+    let address = &addresses[my_index];
+    // A rendezvous may have bound the address already, and kept it from other programs.
+    let listener = match crate::bootstrap::take_listener(address) {
+        Some(listener) => listener,
+        None => TcpListener::bind(&address[..]).map_err(|error| InitializeError::Bind { address: address.clone(), error })?,
+    };
 
     for _ in (my_index + 1) .. addresses.len() {
         let mut stream = listener.accept()?.0;
-        stream.set_nodelay(true).expect("set_nodelay call failed");
+        stream.set_nodelay(true)?;
         let mut buffer = [0u8;16];
         stream.read_exact(&mut buffer)?;
-        let (magic, mut buffer) = unsafe { decode::<u64>(&mut buffer) }.expect("failed to decode magic");
+        let (magic, mut buffer) = unsafe { decode::<u64>(&mut buffer) }.ok_or_else(|| InitializeError::handshake(None, "failed to decode magic"))?;
         if magic != &HANDSHAKE_MAGIC {
-            return Err(io::Error::new(io::ErrorKind::InvalidData,
-                "received incorrect timely handshake"));
+            return Err(InitializeError::handshake(None, "received incorrect timely handshake"));
+        }
+        let identifier = unsafe { decode::<u64>(&mut buffer) }.ok_or_else(|| InitializeError::handshake(None, "failed to decode worker index"))?.0.clone() as usize;
+        if identifier <= my_index || identifier >= addresses.len() {
+            return Err(InitializeError::handshake(Some(identifier), "connecting process is not among those expected to connect"));
+        }
+        if results[identifier - my_index - 1].is_some() {
+            return Err(InitializeError::handshake(Some(identifier), "connecting process has already connected"));
         }
-        let identifier = unsafe { decode::<u64>(&mut buffer) }.expect("failed to decode worker index").0.clone() as usize;
         results[identifier - my_index - 1] = Some(stream);
         if noisy { println!("worker {}:\tconnection from worker {}", my_index, identifier); }
     }
 
     Ok(results)
 }
+
+/// Verifies over connected `sockets` that each process serializes messages with the backend of
+/// this process, and exchanges the number of workers of each process.
+///
+/// Each process sends the code of its backend and its number of workers to each other process,
+/// as one message, and so each of two mismatched processes reports the backend of the other as an
+/// `InitializeError::Serialization`. The result contains the number of workers of each process,
+/// including `workers` at position `my_index`. Processes may have different numbers of workers,
+/// whose indices are then assigned in order of the processes.
+pub fn exchange_handshake(sockets: &mut [Option<TcpStream>], my_index: usize, workers: usize) -> ::std::result::Result<Vec<usize>, InitializeError> {
+    let ours = Serialization::current();
+    let mut message = [0u8; 16];
+    message[.. 8].copy_from_slice(&ours.code().to_le_bytes());
+    message[8 ..].copy_from_slice(&(workers as u64).to_le_bytes());
+    // Each message is written before any is read; they are small enough not to block.
+    for socket in sockets.iter_mut().flatten() {
+        socket.write_all(&message)?;
+    }
+    let mut result = Vec::with_capacity(sockets.len());
+    for (process, socket) in sockets.iter_mut().enumerate() {
+        match socket {
+            Some(socket) => {
+                let mut buffer = [0u8; 8];
+                socket.read_exact(&mut buffer)?;
+                match Serialization::from_code(u64::from_le_bytes(buffer)) {
+                    Some(theirs) if theirs == ours => { },
+                    Some(theirs) => return Err(InitializeError::Serialization { process, theirs, ours }),
+                    None => return Err(InitializeError::handshake(Some(process), "process reported an unknown serialization backend")),
+                }
+                socket.read_exact(&mut buffer)?;
+                result.push(u64::from_le_bytes(buffer) as usize);
+            },
+            None => {
+                assert_eq!(process, my_index);
+                result.push(workers);
+            },
+        }
+    }
+    Ok(result)
+}
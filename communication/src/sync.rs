@@ -0,0 +1,144 @@
+//! Synchronization primitives for handing data and wakeups between threads.
+//!
+//! The buzzers, merge queues, and channel counters of the crate take their primitives from here
+//! rather than from `std`, so that with the `loom` feature they are built against the types of
+//! [loom](https://docs.rs/loom), whose models explore the interleavings of threads exhaustively.
+//! The feature exists only to run the loom tests below,
+//!
+//! ```text
+//! cargo test -p timely_communication --release --features loom --lib sync::tests
+//! ```
+//!
+//! and the crate's allocators cannot be used outside of a loom model when it is enabled.
+
+#[cfg(feature = "loom")]
+pub(crate) use loom::{hint, thread, thread_local};
+#[cfg(feature = "loom")]
+pub(crate) use loom::sync::{Arc, Mutex, mpsc};
+#[cfg(feature = "loom")]
+pub(crate) use loom::sync::atomic::{AtomicBool, Ordering};
+
+#[cfg(not(feature = "loom"))]
+pub(crate) use std::{hint, thread, thread_local};
+#[cfg(not(feature = "loom"))]
+pub(crate) use std::sync::{Arc, Mutex, mpsc};
+#[cfg(not(feature = "loom"))]
+pub(crate) use std::sync::atomic::{AtomicBool, Ordering};
+
+#[cfg(all(test, feature = "loom"))]
+mod tests {
+
+    use std::collections::VecDeque;
+
+    use bytes::arc::Bytes;
+
+    use crate::Push;
+    use crate::allocator::Event;
+    use crate::allocator::counters::ArcPusher;
+    use crate::allocator::zero_copy::bytes_exchange::{BytesPull, BytesPush, MergeQueue};
+    use crate::buzzer::{Buzzer, register_waker};
+    use super::{Arc, AtomicBool, Mutex, Ordering, mpsc, thread};
+
+    // A buzz sent before the target parks, or while it is parked, must not be lost.
+    #[test]
+    fn buzz_wakes_parked_thread() {
+        loom::model(|| {
+            let buzzer = Buzzer::new();
+            let ready = Arc::new(AtomicBool::new(false));
+            let sender = {
+                let ready = ready.clone();
+                thread::spawn(move || {
+                    ready.store(true, Ordering::SeqCst);
+                    buzzer.buzz();
+                })
+            };
+            while !ready.load(Ordering::SeqCst) {
+                thread::park();
+            }
+            sender.join().unwrap();
+        });
+    }
+
+    // A buzz must wake a waker registered before the registering thread looks for work.
+    #[test]
+    fn buzz_wakes_registered_waker() {
+        struct Woken(AtomicBool);
+        impl std::task::Wake for Woken {
+            fn wake(self: std::sync::Arc<Self>) { self.0.store(true, Ordering::SeqCst); }
+        }
+        loom::model(|| {
+            let ready = Arc::new(AtomicBool::new(false));
+            let woken = std::sync::Arc::new(Woken(AtomicBool::new(false)));
+            let (send, recv) = mpsc::channel();
+            let receiver = {
+                let ready = ready.clone();
+                let woken = woken.clone();
+                thread::spawn(move || {
+                    send.send(Buzzer::new()).unwrap();
+                    register_waker(&std::task::Waker::from(woken));
+                    ready.load(Ordering::SeqCst)
+                })
+            };
+            let buzzer = recv.recv().unwrap();
+            ready.store(true, Ordering::SeqCst);
+            buzzer.buzz();
+            let seen = receiver.join().unwrap();
+            assert!(seen || woken.0.load(Ordering::SeqCst));
+        });
+    }
+
+    // Bytes extended into a queue are drained by its receiver, which is woken by the extension
+    // or by the drop of the sending half, whichever it awaits.
+    #[test]
+    fn merge_queue_hands_off_bytes() {
+        loom::model(|| {
+            let mut receiver = MergeQueue::new(Buzzer::new());
+            let mut sender = receiver.clone();
+            let thread = thread::spawn(move || {
+                sender.extend(Some(Bytes::from(vec![0u8; 8])));
+                sender.extend(Some(Bytes::from(vec![1u8; 8])));
+            });
+
+            let mut received = Vec::new();
+            loop {
+                receiver.drain_into(&mut received);
+                if receiver.is_complete() { break; }
+                thread::park();
+            }
+            receiver.drain_into(&mut received);
+            thread.join().unwrap();
+
+            assert_eq!(received.iter().map(|bytes| bytes.len()).sum::<usize>(), 16);
+        });
+    }
+
+    // A shared queue of records, standing in for the channel an `ArcPusher` wraps.
+    struct Shared(Arc<Mutex<VecDeque<u64>>>);
+
+    impl Push<u64> for Shared {
+        fn push(&mut self, element: &mut Option<u64>) {
+            if let Some(element) = element.take() {
+                self.0.lock().unwrap().push_back(element);
+            }
+        }
+    }
+
+    // A counting pusher announces a record only once it is visible to the receiver.
+    #[test]
+    fn counters_announce_pushed_records() {
+        loom::model(|| {
+            let records = Arc::new(Mutex::new(VecDeque::new()));
+            let (events_send, events_recv) = mpsc::channel();
+            let mut pusher = ArcPusher::new(Shared(records.clone()), 0, events_send, Buzzer::new());
+            let thread = thread::spawn(move || {
+                pusher.push(&mut Some(7));
+            });
+
+            let (channel, event) = events_recv.recv().unwrap();
+            assert_eq!(channel, 0);
+            assert!(matches!(event, Event::Pushed(1)));
+            assert_eq!(records.lock().unwrap().pop_front(), Some(7));
+            thread.join().unwrap();
+        });
+    }
+}
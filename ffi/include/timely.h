@@ -0,0 +1,52 @@
+/*
+ * C interface for embedding timely dataflow pipelines.
+ *
+ * Pipelines are written in Rust, and exported as pointers from `Pipeline::into_raw`. Functions
+ * returning `int` return `0` on success and `-1` on failure.
+ */
+
+#ifndef TIMELY_H
+#define TIMELY_H
+
+#include <stddef.h>
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+/* Logic building a dataflow from named inputs and outputs of byte-serialized records. */
+typedef struct timely_pipeline timely_pipeline;
+
+/* Workers running a pipeline. */
+typedef struct timely_cluster timely_cluster;
+
+/*
+ * Receives a record of an output, at epoch `time`. The bytes are valid only for the duration of
+ * the call. Callbacks are invoked from worker threads, though never concurrently.
+ */
+typedef void (*timely_output_callback)(void *context, uint64_t time, const uint8_t *data, size_t len);
+
+/* Starts `workers` worker threads running `pipeline`, returning NULL on failure. */
+timely_cluster *timely_cluster_new(const timely_pipeline *pipeline, size_t workers);
+
+/* Feeds the `len` bytes at `data` into the input named `input` as one record. */
+int timely_cluster_send(timely_cluster *cluster, const char *input, const uint8_t *data, size_t len);
+
+/* Advances all inputs to `epoch`, which must not precede the current epoch. */
+int timely_cluster_advance_to(timely_cluster *cluster, uint64_t epoch);
+
+/* Registers `callback` to receive the records of the output named `output`, with `context`. */
+int timely_cluster_on_output(timely_cluster *cluster, const char *output, timely_output_callback callback, void *context);
+
+/* Closes the inputs, awaits the completion of the workers, and frees the cluster. */
+int timely_cluster_close(timely_cluster *cluster);
+
+/* Frees a pipeline. Clusters started from the pipeline are unaffected. */
+void timely_pipeline_free(timely_pipeline *pipeline);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* TIMELY_H */
@@ -0,0 +1,366 @@
+//! A C interface for embedding timely dataflow pipelines.
+//!
+//! A pipeline is written in Rust as a `Pipeline`, which builds a dataflow from named inputs
+//! and outputs of byte-serialized records. A library exports the pipeline to C as a pointer
+//! from `Pipeline::into_raw`, with which a C program starts a `Cluster` of workers through
+//! `timely_cluster_new`. The program then feeds records into the named inputs, advances their
+//! epoch, and receives the records of the named outputs through callbacks, before closing the
+//! cluster. The declarations of the C interface are in `include/timely.h`.
+//!
+//! # Examples
+//! ```
+//! use std::os::raw::c_void;
+//! use timely::dataflow::operators::Map;
+//! use timely_ffi::*;
+//!
+//! // A pipeline that reverses the bytes of each record.
+//! let pipeline = Pipeline::new(|scope, ports| {
+//!     let reversed = ports.input(scope, "in").map(|mut record| { record.reverse(); record });
+//!     ports.output("out", &reversed);
+//! }).into_raw();
+//!
+//! extern "C" fn collect(context: *mut c_void, _time: u64, data: *const u8, len: usize) {
+//!     let records = unsafe { &*(context as *const std::sync::Mutex<Vec<Vec<u8>>>) };
+//!     records.lock().unwrap().push(unsafe { std::slice::from_raw_parts(data, len) }.to_vec());
+//! }
+//!
+//! let records = std::sync::Mutex::new(Vec::<Vec<u8>>::new());
+//! unsafe {
+//!     let cluster = timely_cluster_new(pipeline, 2);
+//!     let context = &records as *const _ as *mut c_void;
+//!     assert_eq!(timely_cluster_on_output(cluster, "out\0".as_ptr() as _, collect, context), 0);
+//!     assert_eq!(timely_cluster_send(cluster, "in\0".as_ptr() as _, b"abc".as_ptr(), 3), 0);
+//!     assert_eq!(timely_cluster_advance_to(cluster, 1), 0);
+//!     assert_eq!(timely_cluster_close(cluster), 0);
+//!     timely_pipeline_free(pipeline);
+//! }
+//! assert_eq!(records.into_inner().unwrap(), vec![b"cba".to_vec()]);
+//! ```
+
+#![forbid(missing_docs)]
+
+use std::collections::HashMap;
+use std::ffi::CStr;
+use std::os::raw::{c_char, c_int, c_void};
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::sync::{Arc, Mutex, mpsc};
+use std::thread::Thread;
+
+use timely::Configuration;
+use timely::communication::{Allocator, WorkerGuards};
+use timely::dataflow::{InputHandle, Stream};
+use timely::dataflow::operators::{Input, Inspect};
+use timely::dataflow::scopes::Child;
+use timely::worker::Worker;
+
+/// The scope in which pipelines build their dataflows, with epochs as timestamps.
+pub type Scope<'a> = Child<'a, Worker<Allocator>, u64>;
+
+/// A function receiving the records of an output: the context it was registered with, and the
+/// epoch and bytes of a record, which are valid only for the duration of the call.
+pub type OutputCallback = extern "C" fn(context: *mut c_void, time: u64, data: *const u8, len: usize);
+
+// A registered callback, and the context to present to it.
+#[derive(Clone, Copy)]
+struct Callback {
+    function: OutputCallback,
+    context: *mut c_void,
+}
+
+// The context is only presented back to the callback, whose registrant vouches for its use across threads.
+unsafe impl Send for Callback { }
+
+/// The named inputs and outputs of a pipeline, as it builds its dataflow at a worker.
+pub struct Ports {
+    inputs: Vec<(String, InputHandle<u64, Vec<u8>>)>,
+    callbacks: Arc<Mutex<HashMap<String, Callback>>>,
+}
+
+impl Ports {
+    /// Introduces an input named `name`, into which the embedding program feeds records.
+    ///
+    /// Each record is introduced at one worker. Pipelines must declare the same inputs, in the
+    /// same order, at each worker.
+    pub fn input<'a>(&mut self, scope: &mut Scope<'a>, name: &str) -> Stream<Scope<'a>, Vec<u8>> {
+        assert!(self.inputs.iter().all(|(input, _)| input != name), "input {} declared twice", name);
+        let (handle, stream) = scope.new_input();
+        self.inputs.push((name.to_owned(), handle));
+        stream
+    }
+
+    /// Presents the records of `stream` to the callback registered for the output named `name`.
+    ///
+    /// Records are presented as they are produced at each worker, and are discarded while no
+    /// callback is registered.
+    pub fn output(&mut self, name: &str, stream: &Stream<Scope<'_>, Vec<u8>>) {
+        let callbacks = self.callbacks.clone();
+        let name = name.to_owned();
+        stream.inspect_batch(move |time, records| {
+            // The lock is held through the calls, so that callbacks are never invoked concurrently.
+            let callbacks = callbacks.lock().expect("callbacks poisoned");
+            if let Some(callback) = callbacks.get(&name) {
+                for record in records.iter() {
+                    (callback.function)(callback.context, *time, record.as_ptr(), record.len());
+                }
+            }
+        });
+    }
+}
+
+// Logic building a dataflow at a worker.
+type Build = dyn Fn(&mut Scope<'_>, &mut Ports)+Send+Sync;
+
+/// Logic building a dataflow from named inputs and outputs of byte-serialized records.
+pub struct Pipeline {
+    build: Arc<Build>,
+}
+
+impl Pipeline {
+    /// A pipeline building its dataflow at each worker with `build`.
+    pub fn new<F: Fn(&mut Scope<'_>, &mut Ports)+Send+Sync+'static>(build: F) -> Self {
+        Pipeline { build: Arc::new(build) }
+    }
+
+    /// Transfers the pipeline to C, which releases it with `timely_pipeline_free`.
+    pub fn into_raw(self) -> *mut Pipeline {
+        Box::into_raw(Box::new(self))
+    }
+}
+
+// An instruction from the embedding program to a worker.
+enum Command {
+    // A record for the input at a position.
+    Send(usize, Vec<u8>),
+    // The epoch to which to advance all inputs.
+    Advance(u64),
+}
+
+/// Workers running a pipeline, fed by the embedding program.
+pub struct Cluster {
+    // Command queues of the workers, and their threads, to unpark once commands are queued.
+    workers: Vec<(mpsc::Sender<Command>, Thread)>,
+    inputs: Vec<String>,
+    callbacks: Arc<Mutex<HashMap<String, Callback>>>,
+    // The worker to receive the next record.
+    next: usize,
+    epoch: u64,
+    guards: Option<WorkerGuards<()>>,
+}
+
+impl Cluster {
+    /// Starts `workers` worker threads running `pipeline`.
+    pub fn start(pipeline: &Pipeline, workers: usize) -> Result<Self, String> {
+
+        let build = pipeline.build.clone();
+        let callbacks: Arc<Mutex<HashMap<String, Callback>>> = Default::default();
+        let (senders, receivers): (Vec<_>, Vec<_>) = (0 .. workers).map(|_| mpsc::channel()).unzip();
+        let receivers = Mutex::new(receivers.into_iter().map(Some).collect::<Vec<_>>());
+        let (ready_send, ready_recv) = mpsc::channel();
+        let ready_send = Mutex::new(ready_send);
+
+        let shared = callbacks.clone();
+        let guards = timely::execute(Configuration::Process(workers), move |worker| {
+
+            let index = worker.index();
+            let receiver: mpsc::Receiver<Command> = receivers.lock().expect("receivers poisoned")[index].take().expect("worker started twice");
+            let mut ports = Ports { inputs: Vec::new(), callbacks: shared.clone() };
+            // Failures to build are reported, so that the cluster does not await this worker.
+            let built = catch_unwind(AssertUnwindSafe(|| worker.dataflow(|scope| (build)(scope, &mut ports))));
+
+            let names = built.as_ref().ok().map(|_| ports.inputs.iter().map(|(name, _)| name.clone()).collect::<Vec<_>>());
+            let _ = ready_send.lock().expect("ready poisoned").send((index, std::thread::current(), names));
+            if let Err(payload) = built {
+                std::panic::resume_unwind(payload);
+            }
+
+            loop {
+                match receiver.try_recv() {
+                    Ok(Command::Send(input, record)) => ports.inputs[input].1.send(record),
+                    Ok(Command::Advance(epoch)) => {
+                        for (_, handle) in ports.inputs.iter_mut() {
+                            handle.advance_to(epoch);
+                        }
+                    },
+                    Err(mpsc::TryRecvError::Empty) => {
+                        if !worker.step_or_park(None) {
+                            std::thread::park();
+                        }
+                    },
+                    Err(mpsc::TryRecvError::Disconnected) => break,
+                }
+            }
+        })?;
+
+        let mut started = ready_recv.iter().take(workers).collect::<Vec<_>>();
+        started.sort_by_key(|(index, _, _)| *index);
+        let declared = started.iter().map(|(_, _, names)| names.clone()).collect::<Vec<_>>();
+
+        // Once constructed, the cluster shuts down the workers if it is dropped.
+        let mut cluster = Cluster {
+            workers: senders.into_iter().zip(started.into_iter().map(|(_, thread, _)| thread)).collect(),
+            inputs: Vec::new(),
+            callbacks,
+            next: 0,
+            epoch: 0,
+            guards: Some(guards),
+        };
+
+        if declared.iter().any(|names| names.is_none()) {
+            return Err("pipeline failed to build".to_owned());
+        }
+        if declared.iter().any(|names| names != &declared[0]) {
+            return Err("workers declared different inputs".to_owned());
+        }
+        cluster.inputs = declared[0].clone().unwrap_or_default();
+        Ok(cluster)
+    }
+
+    /// Feeds `record` into the input named `input`, at the current epoch.
+    ///
+    /// Records are distributed among the workers in turn.
+    pub fn send(&mut self, input: &str, record: Vec<u8>) -> Result<(), String> {
+        let position = self.inputs.iter().position(|name| name == input).ok_or_else(|| format!("no input named {}", input))?;
+        let (sender, thread) = &self.workers[self.next];
+        sender.send(Command::Send(position, record)).map_err(|_| "worker has stopped".to_owned())?;
+        thread.unpark();
+        self.next = (self.next + 1) % self.workers.len();
+        Ok(())
+    }
+
+    /// Advances all inputs to `epoch`, after which records are introduced at `epoch`.
+    pub fn advance_to(&mut self, epoch: u64) -> Result<(), String> {
+        if epoch < self.epoch {
+            return Err(format!("cannot advance from epoch {} to {}", self.epoch, epoch));
+        }
+        self.epoch = epoch;
+        for (sender, thread) in self.workers.iter() {
+            sender.send(Command::Advance(epoch)).map_err(|_| "worker has stopped".to_owned())?;
+            thread.unpark();
+        }
+        Ok(())
+    }
+
+    /// Registers `function` to receive the records of the output named `output`, with `context`.
+    ///
+    /// The callback replaces any previously registered for the output, and is invoked from the
+    /// threads of the workers, though never concurrently with other callbacks.
+    pub fn on_output(&self, output: &str, function: OutputCallback, context: *mut c_void) {
+        self.callbacks.lock().expect("callbacks poisoned").insert(output.to_owned(), Callback { function, context });
+    }
+
+    /// Closes the inputs, and awaits the completion of the workers.
+    pub fn close(mut self) -> Result<(), String> {
+        self.shut_down()
+    }
+
+    fn shut_down(&mut self) -> Result<(), String> {
+        for (sender, thread) in self.workers.drain(..) {
+            drop(sender);
+            thread.unpark();
+        }
+        match self.guards.take() {
+            Some(guards) => guards.join().into_iter().collect(),
+            None => Ok(()),
+        }
+    }
+}
+
+impl Drop for Cluster {
+    fn drop(&mut self) {
+        let _ = self.shut_down();
+    }
+}
+
+// Runs `logic`, reporting failures, including panics, as `-1`.
+fn status<F: FnOnce()->Result<(), String>>(logic: F) -> c_int {
+    match catch_unwind(AssertUnwindSafe(logic)) {
+        Ok(Ok(())) => 0,
+        _ => -1,
+    }
+}
+
+// Reads a name passed from C.
+unsafe fn name<'a>(name: *const c_char) -> Result<&'a str, String> {
+    if name.is_null() {
+        return Err("null name".to_owned());
+    }
+    CStr::from_ptr(name).to_str().map_err(|error| error.to_string())
+}
+
+/// Starts `workers` worker threads running `pipeline`, returning null on failure.
+///
+/// # Safety
+/// `pipeline` must be null or a pointer from `Pipeline::into_raw` that has not been freed.
+#[no_mangle]
+pub unsafe extern "C" fn timely_cluster_new(pipeline: *const Pipeline, workers: usize) -> *mut Cluster {
+    if pipeline.is_null() || workers == 0 {
+        return std::ptr::null_mut();
+    }
+    match catch_unwind(AssertUnwindSafe(|| Cluster::start(&*pipeline, workers))) {
+        Ok(Ok(cluster)) => Box::into_raw(Box::new(cluster)),
+        _ => std::ptr::null_mut(),
+    }
+}
+
+/// Feeds the `len` bytes at `data` into the input named `input` as one record, returning `0` on success.
+///
+/// # Safety
+/// `cluster` must be a live pointer from `timely_cluster_new`, `input` a NUL-terminated string,
+/// and `data` must point to `len` readable bytes.
+#[no_mangle]
+pub unsafe extern "C" fn timely_cluster_send(cluster: *mut Cluster, input: *const c_char, data: *const u8, len: usize) -> c_int {
+    status(|| {
+        let cluster = cluster.as_mut().ok_or_else(|| "null cluster".to_owned())?;
+        let record = if len == 0 { Vec::new() } else { std::slice::from_raw_parts(data, len).to_vec() };
+        cluster.send(name(input)?, record)
+    })
+}
+
+/// Advances all inputs to `epoch`, returning `0` on success and `-1` if `epoch` is in the past.
+///
+/// # Safety
+/// `cluster` must be a live pointer from `timely_cluster_new`.
+#[no_mangle]
+pub unsafe extern "C" fn timely_cluster_advance_to(cluster: *mut Cluster, epoch: u64) -> c_int {
+    status(|| cluster.as_mut().ok_or_else(|| "null cluster".to_owned())?.advance_to(epoch))
+}
+
+/// Registers `callback` to receive the records of the output named `output`, returning `0` on success.
+///
+/// # Safety
+/// `cluster` must be a live pointer from `timely_cluster_new`, and `output` a NUL-terminated
+/// string. `context` must remain valid for use by `callback`, from any thread, until the cluster
+/// is closed or another callback is registered for the output.
+#[no_mangle]
+pub unsafe extern "C" fn timely_cluster_on_output(cluster: *mut Cluster, output: *const c_char, callback: OutputCallback, context: *mut c_void) -> c_int {
+    status(|| {
+        let cluster = cluster.as_ref().ok_or_else(|| "null cluster".to_owned())?;
+        cluster.on_output(name(output)?, callback, context);
+        Ok(())
+    })
+}
+
+/// Closes the inputs, awaits the completion of the workers, and frees the cluster.
+///
+/// Returns `0` if all workers completed, and `-1` if any failed.
+///
+/// # Safety
+/// `cluster` must be a live pointer from `timely_cluster_new`, which is not used afterwards.
+#[no_mangle]
+pub unsafe extern "C" fn timely_cluster_close(cluster: *mut Cluster) -> c_int {
+    if cluster.is_null() {
+        return -1;
+    }
+    status(|| Box::from_raw(cluster).close())
+}
+
+/// Frees a pipeline. Clusters started from the pipeline are unaffected.
+///
+/// # Safety
+/// `pipeline` must be null or a pointer from `Pipeline::into_raw`, which is not used afterwards.
+#[no_mangle]
+pub unsafe extern "C" fn timely_pipeline_free(pipeline: *mut Pipeline) {
+    if !pipeline.is_null() {
+        drop(Box::from_raw(pipeline));
+    }
+}
@@ -2,8 +2,15 @@
 use std::rc::Rc;
 use std::cell::RefCell;
 use std::any::Any;
-use std::collections::HashMap;
-use std::time::{Instant, Duration};
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Condvar, Mutex};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread::JoinHandle;
+use std::time::Duration;
+#[cfg(all(target_arch = "wasm32", target_os = "unknown"))]
+use web_time::Instant;
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+use std::time::Instant;
 
 pub struct Registry<Id> {
     /// A worker-specific identifier.
@@ -35,6 +42,100 @@ impl<Id: Clone+'static> Registry<Id> {
         self.insert_logger(name, logger)
     }
 
+    /// Binds a log name to an action on log event batches, presenting only events accepted by `filter`.
+    ///
+    /// The filter observes each event as it is logged, and may change its behavior over time,
+    /// for example by consulting state shared with the application, or amend the events it
+    /// accepts, for example to record a sampling rate. See `insert` for the action.
+    pub fn insert_filtered<T: 'static, P: FnMut(&mut T)->bool+'static, F: FnMut(&Duration, &mut Vec<(Duration, Id, T)>)+'static>(
+        &mut self,
+        name: &str,
+        filter: P,
+        action: F) -> Option<Box<dyn Any>>
+    {
+        let logger = Logger::<T, Id>::new(self.time, self.id.clone(), action).with_filter(filter);
+        self.insert_logger(name, logger)
+    }
+
+    /// Binds a log name to an action on log event batches, declaring the events of interest to it.
+    ///
+    /// The logger presents only events accepted by `filter`, as `insert_filtered`, and makes
+    /// `interest` available through `Logger::interest` to those logging, who may consult it to
+    /// skip the construction of events the filter would not accept. See `insert` for the action.
+    pub fn insert_interested<T: 'static, I: Any, P: FnMut(&mut T)->bool+'static, F: FnMut(&Duration, &mut Vec<(Duration, Id, T)>)+'static>(
+        &mut self,
+        name: &str,
+        interest: I,
+        filter: P,
+        action: F) -> Option<Box<dyn Any>>
+    {
+        let logger = Logger::<T, Id>::new(self.time, self.id.clone(), action).with_filter(filter).with_interest(interest);
+        self.insert_logger(name, logger)
+    }
+
+    /// Binds a log name to an action on log event batches, performed by a thread of its own.
+    ///
+    /// Events are queued for the action, and once `capacity` events are queued, because the
+    /// action falls behind, the logger responds according to `backpressure`. When events are
+    /// discarded, their number is presented to the action as a `Dropped` event, with its next
+    /// batch. The thread is awaited, and the events queued for it delivered, when the action is
+    /// dropped. See `insert` for the action.
+    ///
+    /// # Examples
+    /// ```
+    /// use std::sync::{Arc, Mutex};
+    /// use std::time::Instant;
+    /// use timely_logging::{Registry, Backpressure, Dropped};
+    ///
+    /// #[derive(Debug, PartialEq)]
+    /// enum Event { Work(u64), Dropped(usize) }
+    ///
+    /// impl From<Dropped> for Event {
+    ///     fn from(dropped: Dropped) -> Self { Event::Dropped(dropped.count) }
+    /// }
+    ///
+    /// let counts = Arc::new(Mutex::new((0, 0)));
+    /// let shared = counts.clone();
+    ///
+    /// let mut registry = Registry::new(Instant::now(), 0);
+    /// registry.insert_bounded::<Event,_>("work", 4, Backpressure::DropOldest, move |_time, data| {
+    ///     let mut counts = shared.lock().unwrap();
+    ///     for (_elapsed, _id, event) in data.drain(..) {
+    ///         match event {
+    ///             Event::Work(_) => counts.0 += 1,
+    ///             Event::Dropped(count) => counts.1 += count,
+    ///         }
+    ///     }
+    /// });
+    ///
+    /// let logger = registry.get::<Event>("work").unwrap();
+    /// for round in 0 .. 10_000 {
+    ///     logger.log(Event::Work(round));
+    /// }
+    ///
+    /// // Dropping the logger and its binding delivers the queued events.
+    /// drop(logger);
+    /// registry.remove("work");
+    ///
+    /// // Each event was either delivered or counted as dropped.
+    /// let (delivered, dropped) = *counts.lock().unwrap();
+    /// assert_eq!(delivered + dropped, 10_000);
+    /// ```
+    pub fn insert_bounded<T, F>(
+        &mut self,
+        name: &str,
+        capacity: usize,
+        backpressure: Backpressure,
+        action: F) -> Option<Box<dyn Any>>
+    where
+        Id: Send,
+        T: From<Dropped>+Send+'static,
+        F: FnMut(&Duration, &mut Vec<(Duration, Id, T)>)+Send+'static,
+    {
+        let logger = Logger::<T, Id>::bounded(self.time, self.id.clone(), capacity, backpressure, action);
+        self.insert_logger(name, logger)
+    }
+
     /// Binds a log name to a logger.
     pub fn insert_logger<T: 'static>(
         &mut self,
@@ -85,12 +186,18 @@ impl<Id> Flush for Registry<Id> {
     }
 }
 
+/// A predicate on logged events, retaining those for which it returns `true`.
+type Filter<T> = Rc<RefCell<dyn FnMut(&mut T)->bool>>;
+
 /// A buffering logger.
 pub struct Logger<T, E> {
     id:     E,
     time:   Instant,                                                    // common instant used for all loggers.
     action: Rc<RefCell<dyn FnMut(&Duration, &mut Vec<(Duration, E, T)>)>>,  // action to take on full log buffers.
     buffer: Rc<RefCell<Vec<(Duration, E, T)>>>,                         // shared buffer; not obviously best design.
+    filter: Option<Filter<T>>,                                          // events to retain, if not all.
+    interest: Option<Rc<dyn Any>>,                                      // declared interest of the action, if any.
+    disconnected: Option<Arc<AtomicBool>>,                              // set once the thread of a bounded action stops.
 }
 
 impl<T, E: Clone> Clone for Logger<T, E> {
@@ -100,6 +207,9 @@ impl<T, E: Clone> Clone for Logger<T, E> {
             time: self.time,
             action: self.action.clone(),
             buffer: self.buffer.clone(),
+            filter: self.filter.clone(),
+            interest: self.interest.clone(),
+            disconnected: self.disconnected.clone(),
         }
     }
 }
@@ -115,9 +225,84 @@ impl<T, E: Clone> Logger<T, E> {
             time,
             action: Rc::new(RefCell::new(action)),
             buffer: Rc::new(RefCell::new(Vec::with_capacity(1024))),
+            filter: None,
+            interest: None,
+            disconnected: None,
+        }
+    }
+
+    /// Retains only the logged events for which `filter` returns `true`.
+    ///
+    /// Discarded events are neither buffered nor presented to the action. The filter may amend
+    /// the events it retains.
+    pub fn with_filter<P: FnMut(&mut T)->bool+'static>(mut self, filter: P) -> Self {
+        self.filter = Some(Rc::new(RefCell::new(filter)));
+        self
+    }
+
+    /// Allocates a logger whose action is performed by a thread of its own, as `Registry::insert_bounded`.
+    ///
+    /// # Panics
+    ///
+    /// If `capacity` is zero.
+    pub fn bounded<F>(time: Instant, id: E, capacity: usize, backpressure: Backpressure, action: F) -> Self
+    where
+        E: Send+'static,
+        T: From<Dropped>+Send+'static,
+        F: FnMut(&Duration, &mut Vec<(Duration, E, T)>)+Send+'static,
+    {
+        let mut producer = Producer::spawn(id.clone(), capacity, backpressure, action);
+        let disconnected = producer.disconnected.clone();
+        // Events are discarded once the action has stopped, as reported by `check`.
+        let mut logger = Logger::new(time, id, move |time, data| { let _ = producer.push(time, data); });
+        logger.disconnected = Some(disconnected);
+        logger
+    }
+
+    /// Reports whether the action of a bounded logger has stopped, because it panicked.
+    ///
+    /// Once the action has stopped, events are discarded rather than queued, and a logging
+    /// thread blocked by `Backpressure::Block` is released. Other loggers always report `Ok`.
+    ///
+    /// # Examples
+    /// ```
+    /// use std::time::Instant;
+    /// use timely_logging::{Logger, Backpressure, Dropped};
+    ///
+    /// struct Event;
+    /// impl From<Dropped> for Event { fn from(_: Dropped) -> Self { Event } }
+    ///
+    /// let logger = Logger::<Event, usize>::bounded(Instant::now(), 0, 1, Backpressure::Block, |_time, data| {
+    ///     if !data.is_empty() { panic!("failed to write events"); }
+    /// });
+    ///
+    /// // The logging thread is released once the action panics, rather than blocking forever.
+    /// for _ in 0 .. 10_000 {
+    ///     logger.log(Event);
+    /// }
+    /// assert!(logger.check().is_err());
+    /// ```
+    pub fn check(&self) -> Result<(), Disconnected> {
+        match self.disconnected.as_ref() {
+            Some(disconnected) if disconnected.load(Ordering::SeqCst) => Err(Disconnected),
+            _ => Ok(()),
         }
     }
 
+    /// Declares `interest` as the events of interest to the action, as `Registry::insert_interested`.
+    pub fn with_interest<I: Any>(mut self, interest: I) -> Self {
+        self.interest = Some(Rc::new(interest));
+        self
+    }
+
+    /// The declared interest of the action, if it is of type `I`.
+    ///
+    /// Those logging may consult the interest before constructing an event, to skip events the
+    /// action would not accept. Loggers without a declared interest accept all events.
+    pub fn interest<I: Any>(&self) -> Option<&I> {
+        self.interest.as_ref().and_then(|interest| interest.downcast_ref())
+    }
+
     /// Logs an event.
     ///
     /// The event has its timestamp recorded at the moment of logging, but it may be delayed
@@ -148,9 +333,14 @@ impl<T, E: Clone> Logger<T, E> {
     where I: IntoIterator, I::Item: Into<T>
     {
         let mut buffer = self.buffer.borrow_mut();
+        let mut filter = self.filter.as_ref().map(|filter| filter.borrow_mut());
         let elapsed = self.time.elapsed();
         for event in events {
-            buffer.push((elapsed.clone(), self.id.clone(), event.into()));
+            let mut event = event.into();
+            if let Some(filter) = filter.as_mut() {
+                if !(**filter)(&mut event) { continue; }
+            }
+            buffer.push((elapsed.clone(), self.id.clone(), event));
             if buffer.len() == buffer.capacity() {
                 // Would call `self.flush()`, but for `RefCell` panic.
                 let mut action = self.action.borrow_mut();
@@ -191,3 +381,169 @@ impl<T, E> Flush for Logger<T, E> {
         buffer.reserve(1024);
     }
 }
+
+/// How a bounded logger responds once its queue is full, because its action falls behind.
+#[derive(Debug, Clone, Copy, Hash, Eq, PartialEq)]
+pub enum Backpressure {
+    /// Waits for the action to take queued events, blocking the logging thread.
+    Block,
+    /// Discards the oldest queued events, to make room for new events.
+    DropOldest,
+    /// Discards new events, retaining those already queued.
+    DropNewest,
+}
+
+/// The number of events a bounded logger discarded, presented to its action as an event.
+#[derive(Debug, Clone, Copy, Hash, Eq, PartialEq)]
+pub struct Dropped {
+    /// The number of events discarded since the last `Dropped` event.
+    pub count: usize,
+}
+
+/// The failure of a bounded logger, whose action has stopped because it panicked.
+#[derive(Debug, Clone, Copy, Hash, Eq, PartialEq)]
+pub struct Disconnected;
+
+impl std::fmt::Display for Disconnected {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "the action of the bounded logger has stopped")
+    }
+}
+
+impl std::error::Error for Disconnected { }
+
+/// Events of a bounded logger not yet taken by its action.
+struct Queue<T, E> {
+    events: VecDeque<(Duration, E, T)>,
+    // The time through which the logging thread has reported its events.
+    time: Duration,
+    // The number of events discarded and not yet reported.
+    dropped: usize,
+    // Set once the logging thread will log no further events.
+    closed: bool,
+    // Set once the thread performing the action will take no further events.
+    disconnected: bool,
+}
+
+/// A queue shared by a logging thread and the thread performing its action.
+type Shared<T, E> = Arc<(Mutex<Queue<T, E>>, Condvar)>;
+
+/// The logging thread's end of a bounded queue, which closes the queue and awaits its action when dropped.
+struct Producer<T, E> {
+    shared: Shared<T, E>,
+    capacity: usize,
+    backpressure: Backpressure,
+    thread: Option<JoinHandle<()>>,
+    // Set with `Queue::disconnected`, for those without the lock.
+    disconnected: Arc<AtomicBool>,
+}
+
+/// Disconnects a queue when dropped, by the thread performing its action as it exits or unwinds.
+struct Disconnect<T, E> {
+    shared: Shared<T, E>,
+    disconnected: Arc<AtomicBool>,
+}
+
+impl<T, E> Drop for Disconnect<T, E> {
+    fn drop(&mut self) {
+        let (lock, condvar) = &*self.shared;
+        let mut queue = lock.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        queue.disconnected = true;
+        self.disconnected.store(true, Ordering::SeqCst);
+        drop(queue);
+        // Release a logging thread blocked on a full queue.
+        condvar.notify_all();
+    }
+}
+
+impl<T: From<Dropped>+Send+'static, E: Clone+Send+'static> Producer<T, E> {
+    /// Spawns a thread performing `action` on the events queued by the returned producer.
+    fn spawn<F>(id: E, capacity: usize, backpressure: Backpressure, mut action: F) -> Self
+    where
+        F: FnMut(&Duration, &mut Vec<(Duration, E, T)>)+Send+'static,
+    {
+        assert!(capacity > 0, "bounded loggers require a positive capacity");
+        let queue = Queue { events: VecDeque::new(), time: Duration::default(), dropped: 0, closed: false, disconnected: false };
+        let shared: Shared<T, E> = Arc::new((Mutex::new(queue), Condvar::new()));
+        let disconnected = Arc::new(AtomicBool::new(false));
+        let consumer = Disconnect { shared: shared.clone(), disconnected: disconnected.clone() };
+        let thread = ::std::thread::Builder::new()
+            .name("bounded logger".to_string())
+            .spawn(move || {
+                let (lock, condvar) = &*consumer.shared;
+                let mut reported = Duration::default();
+                loop {
+                    let (mut batch, time, dropped, closed) = {
+                        let mut queue = lock.lock().expect("logger queue poisoned");
+                        while queue.events.is_empty() && queue.dropped == 0 && queue.time == reported && !queue.closed {
+                            queue = condvar.wait(queue).expect("logger queue poisoned");
+                        }
+                        let dropped = ::std::mem::take(&mut queue.dropped);
+                        (queue.events.drain(..).collect::<Vec<_>>(), queue.time, dropped, queue.closed)
+                    };
+                    // Make room for a blocked logging thread.
+                    condvar.notify_all();
+                    if dropped > 0 {
+                        let elapsed = batch.last().map(|event| event.0).unwrap_or(reported);
+                        batch.push((elapsed, id.clone(), Dropped { count: dropped }.into()));
+                    }
+                    action(&time, &mut batch);
+                    reported = time;
+                    if closed { break; }
+                }
+            })
+            .expect("failed to spawn bounded logger");
+        Producer { shared, capacity, backpressure, thread: Some(thread), disconnected }
+    }
+
+    /// Queues the events of `data`, and reports the logging thread's progress through `time`.
+    ///
+    /// Fails, discarding the events, once the thread performing the action has stopped.
+    fn push(&mut self, time: &Duration, data: &mut Vec<(Duration, E, T)>) -> Result<(), Disconnected> {
+        let (lock, condvar) = &*self.shared;
+        let mut queue = lock.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        for event in data.drain(..) {
+            if queue.events.len() >= self.capacity {
+                match self.backpressure {
+                    Backpressure::Block => {
+                        condvar.notify_all();
+                        while queue.events.len() >= self.capacity && !queue.disconnected {
+                            queue = condvar.wait(queue).unwrap_or_else(|poisoned| poisoned.into_inner());
+                        }
+                    },
+                    Backpressure::DropOldest => {
+                        queue.events.pop_front();
+                        queue.dropped += 1;
+                    },
+                    Backpressure::DropNewest => {
+                        queue.dropped += 1;
+                        continue;
+                    },
+                }
+            }
+            if queue.disconnected { break; }
+            queue.events.push_back(event);
+        }
+        if queue.disconnected {
+            data.clear();
+            return Err(Disconnected);
+        }
+        queue.time = *time;
+        drop(queue);
+        condvar.notify_all();
+        Ok(())
+    }
+}
+
+impl<T, E> Drop for Producer<T, E> {
+    fn drop(&mut self) {
+        let (lock, condvar) = &*self.shared;
+        if let Ok(mut queue) = lock.lock() {
+            queue.closed = true;
+        }
+        condvar.notify_all();
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
@@ -0,0 +1,42 @@
+extern crate timely;
+
+use timely::bench::{Bench, Transport, Workload};
+
+// Runs a standard workload for each combination of worker counts and batch sizes, e.g.
+//
+//     cargo run --release --example bench -- exchange process 1,2,4 1000,10000 1000
+fn main() {
+
+    let mut args = std::env::args().skip(1);
+    let workload = args.next().expect("Must specify workload: exchange, barrier, or iteration");
+    let transport = match args.next().expect("Must specify transport: thread, process, or binary").as_str() {
+        "thread" => Transport::Thread,
+        "process" => Transport::Process,
+        "binary" => Transport::ProcessBinary,
+        other => panic!("Unknown transport: {}", other),
+    };
+    let workers = list(&args.next().unwrap_or_else(|| "1".to_owned()));
+    let batches = list(&args.next().unwrap_or_else(|| "1000".to_owned()));
+    let rounds = args.next().map(|x| x.parse::<usize>().expect("Rounds must be an usize")).unwrap_or(1000);
+    let iterations = args.next().map(|x| x.parse::<u64>().expect("Iterations must be an u64")).unwrap_or(10);
+
+    for &peers in workers.iter() {
+        for &batch in batches.iter() {
+            let workload = match workload.as_str() {
+                "exchange" => Workload::Exchange { batch },
+                "barrier" => Workload::Barrier,
+                "iteration" => Workload::Iteration { batch, iterations },
+                other => panic!("Unknown workload: {}", other),
+            };
+            match Bench::new(workload).transport(transport).workers(peers).rounds(rounds).run() {
+                Ok(measurement) => println!("{}", measurement),
+                Err(error) => println!("{}\t{}\tworkers={}\tfailed: {}", workload, transport, peers, error),
+            }
+        }
+    }
+}
+
+// Parses a comma-separated list of numbers.
+fn list(text: &str) -> Vec<usize> {
+    text.split(',').map(|x| x.parse().expect("Lists must be comma-separated usizes")).collect()
+}
@@ -22,9 +22,9 @@ fn main() {
         let index = worker.index();
         let peers = worker.peers();
 
-        let mut input1 = InputHandle::new();
-        let mut input2 = InputHandle::new();
-        let mut probe = ProbeHandle::new();
+        let mut input1 = InputHandle::<u64, _>::new();
+        let mut input2 = InputHandle::<u64, _>::new();
+        let mut probe = ProbeHandle::<u64>::new();
 
         worker.dataflow(|scope| {
 
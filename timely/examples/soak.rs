@@ -0,0 +1,42 @@
+extern crate timely;
+
+use std::time::Duration;
+
+use timely::bench::{Bench, Transport, Workload};
+
+// Runs a standard workload for a number of seconds, and reports how the durations of rounds
+// change over the run, e.g.
+//
+//     cargo run --release --example soak -- exchange 4 1000 60
+fn main() {
+
+    let mut args = std::env::args().skip(1);
+    let workload = args.next().expect("Must specify workload: exchange, barrier, or iteration");
+    let workers: usize = args.next().expect("Must specify workers").parse().expect("Workers must be an usize");
+    let batch: usize = args.next().expect("Must specify batch").parse().expect("Batch must be an usize");
+    let seconds: u64 = args.next().expect("Must specify seconds").parse().expect("Seconds must be an u64");
+
+    let workload = match workload.as_str() {
+        "exchange" => Workload::Exchange { batch },
+        "barrier" => Workload::Barrier,
+        "iteration" => Workload::Iteration { batch, iterations: 10 },
+        other => panic!("Unknown workload: {}", other),
+    };
+
+    let measurement = Bench::new(workload)
+        .transport(Transport::Process)
+        .workers(workers)
+        .soak(Duration::from_secs(seconds))
+        .run()
+        .unwrap();
+
+    println!("{}", measurement);
+
+    // Rounds slowing as the run proceeds indicate growing state or leaked resources.
+    let window = std::cmp::max(1, measurement.round_times.len() / 10);
+    for (index, times) in measurement.round_times.chunks(window).enumerate() {
+        let mut times = times.to_vec();
+        times.sort();
+        println!("rounds {}..{}\tp50={:?}\tmax={:?}", index * window, index * window + times.len(), times[times.len() / 2], times[times.len() - 1]);
+    }
+}
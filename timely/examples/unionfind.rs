@@ -22,8 +22,8 @@ fn main() {
         let index = worker.index();
         let peers = worker.peers();
 
-        let mut input = InputHandle::new();
-        let mut probe = ProbeHandle::new();
+        let mut input = InputHandle::<u64, _>::new();
+        let mut probe = ProbeHandle::<u64>::new();
 
         worker.dataflow(|scope| {
             scope.input_from(&mut input)
@@ -0,0 +1,49 @@
+extern crate timely;
+extern crate timely_communication;
+
+use timely::dataflow::operators::*;
+use timely::dataflow::operators::unordered_input::ActivateCapabilitySet;
+use timely_communication::Configuration;
+
+/// Events from an external system, each with the time it occurred, interleaved with
+/// watermarks indicating that all events before a time have been delivered.
+enum External {
+    Event(usize, &'static str),
+    Watermark(usize),
+}
+
+fn main() {
+    timely::execute(Configuration::Thread, |worker| {
+        let (mut input, cap) = worker.dataflow::<usize,_,_>(|scope| {
+            let (input, stream) = scope.new_unordered_input();
+            stream.inspect_batch(|t, x| println!("{:?} -> {:?}", t, x));
+            input
+        });
+
+        let mut capabilities = ActivateCapabilitySet::from_elem(cap);
+
+        let external = vec![
+            External::Event(2, "b"),
+            External::Event(0, "a"),
+            External::Event(5, "e"),
+            External::Watermark(3),
+            External::Event(4, "d"),
+            External::Event(3, "c"),
+            External::Watermark(6),
+        ];
+
+        for item in external {
+            match item {
+                External::Event(time, data) => {
+                    input.session(capabilities.retain(&time)).give(data);
+                },
+                External::Watermark(time) => {
+                    capabilities.downgrade_all_before(&time);
+                },
+            }
+            worker.step();
+        }
+
+        // Dropping the capabilities completes the input.
+    }).unwrap();
+}
@@ -0,0 +1,344 @@
+//! Standard workloads for measuring the performance of timely dataflow.
+//!
+//! A `Bench` runs one of a few standard workloads, each a sequence of rounds in which every worker
+//! introduces some records at an epoch and waits for the epoch to complete:
+//!
+//! * `Workload::Exchange` exchanges a batch of records from each worker among all workers, and
+//!   measures the throughput of the communication layer;
+//! * `Workload::Barrier` introduces no records, and measures the latency of progress tracking,
+//!   as each round completes only once all workers have advanced their inputs;
+//! * `Workload::Iteration` circulates a batch of records from each worker around a loop,
+//!   exchanging them on each iteration, and measures the iterations each second.
+//!
+//! Benchmarks are parameterized by the `Transport` the workers communicate through, the number of
+//! workers, and the batch sizes of the workloads, and run either for a number of rounds or, as soak
+//! tests, for a duration. The resulting `Measurement` reports the rounds and records processed
+//! each second, and quantiles of the durations of rounds, so that changes to the communication and
+//! scheduling layers can be compared by running the same benchmarks before and after. The `bench`
+//! and `soak` examples run benchmarks from the command line.
+//!
+//! # Examples
+//! ```
+//! use timely::bench::{Bench, Transport, Workload};
+//!
+//! let measurement = Bench::new(Workload::Exchange { batch: 1000 })
+//!     .transport(Transport::Process)
+//!     .workers(2)
+//!     .rounds(10)
+//!     .run()
+//!     .unwrap();
+//!
+//! assert_eq!(measurement.rounds, 10);
+//! assert_eq!(measurement.records, 2 * 10 * 1000);
+//! println!("{}", measurement);
+//! ```
+
+use std::fmt::{self, Display};
+
+use crate::time::{Duration, Instant};
+
+use crate::communication::{Allocate, ThreadConfig};
+use crate::communication::allocator::{GenericBuilder, Process};
+use crate::communication::allocator::thread::ThreadBuilder;
+use crate::communication::allocator::zero_copy::allocator_process::ProcessBuilder;
+use crate::order::Product;
+use crate::dataflow::{InputHandle, ProbeHandle, Scope};
+use crate::dataflow::operators::{Input, Exchange, Probe, Feedback, ConnectLoop, Concat, Enter, Leave, Map, Filter};
+use crate::worker::Worker;
+
+/// A standard workload.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum Workload {
+    /// Each worker exchanges `batch` records among all workers each round.
+    Exchange {
+        /// The number of records each worker introduces each round.
+        batch: usize,
+    },
+    /// Each worker advances its input each round, introducing no records.
+    Barrier,
+    /// Each worker circulates `batch` records around a loop for `iterations` iterations each round.
+    Iteration {
+        /// The number of records each worker introduces each round.
+        batch: usize,
+        /// The number of iterations of the loop each round.
+        iterations: u64,
+    },
+}
+
+impl Display for Workload {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Workload::Exchange { batch } => write!(f, "exchange(batch={})", batch),
+            Workload::Barrier => write!(f, "barrier"),
+            Workload::Iteration { batch, iterations } => write!(f, "iteration(batch={}, iterations={})", batch, iterations),
+        }
+    }
+}
+
+/// The allocator through which the workers of a benchmark communicate.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum Transport {
+    /// A single worker, communicating with itself.
+    Thread,
+    /// Workers in one process, exchanging typed records.
+    Process,
+    /// Workers in one process, exchanging serialized records.
+    ProcessBinary,
+}
+
+impl Transport {
+    // Builders of allocators for `workers` workers.
+    fn builders(self, workers: usize) -> Result<Vec<GenericBuilder>, String> {
+        match self {
+            Transport::Thread if workers == 1 => Ok(vec![GenericBuilder::Thread(ThreadBuilder)]),
+            Transport::Thread => Err(format!("the thread transport supports one worker, not {}", workers)),
+            Transport::Process => Ok(Process::new_vector(workers).into_iter().map(GenericBuilder::Process).collect()),
+            Transport::ProcessBinary => Ok(ProcessBuilder::new_vector(workers).into_iter().map(GenericBuilder::ProcessBinary).collect()),
+        }
+    }
+}
+
+impl Display for Transport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Transport::Thread => write!(f, "thread"),
+            Transport::Process => write!(f, "process"),
+            Transport::ProcessBinary => write!(f, "process-binary"),
+        }
+    }
+}
+
+/// How long a benchmark runs.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum Length {
+    /// A number of rounds.
+    Rounds(usize),
+    /// As many rounds as each worker completes within a duration.
+    Duration(Duration),
+}
+
+/// A benchmark of a workload, with its transport, number of workers, and length.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub struct Bench {
+    workload: Workload,
+    transport: Transport,
+    workers: usize,
+    length: Length,
+}
+
+impl Bench {
+    /// A benchmark of `workload`, on one worker of the process transport, for 1000 rounds.
+    pub fn new(workload: Workload) -> Self {
+        Bench { workload, transport: Transport::Process, workers: 1, length: Length::Rounds(1000) }
+    }
+
+    /// Sets the transport through which workers communicate.
+    pub fn transport(mut self, transport: Transport) -> Self {
+        self.transport = transport;
+        self
+    }
+
+    /// Sets the number of workers.
+    pub fn workers(mut self, workers: usize) -> Self {
+        self.workers = workers;
+        self
+    }
+
+    /// Runs the benchmark for `rounds` rounds.
+    pub fn rounds(mut self, rounds: usize) -> Self {
+        self.length = Length::Rounds(rounds);
+        self
+    }
+
+    /// Runs the benchmark as a soak test, for as many rounds as complete within `duration`.
+    ///
+    /// Workers stop independently, once their own round completes after the duration, and so
+    /// may complete different numbers of rounds.
+    pub fn soak(mut self, duration: Duration) -> Self {
+        self.length = Length::Duration(duration);
+        self
+    }
+
+    /// Runs the benchmark, and reports its measurement.
+    pub fn run(&self) -> Result<Measurement, String> {
+        if self.workers == 0 {
+            return Err("benchmarks require at least one worker".to_owned());
+        }
+        let builders = self.transport.builders(self.workers)?;
+        let (workload, length) = (self.workload, self.length);
+        let guards = crate::execute::execute_with_allocators(builders, ThreadConfig::default(), move |worker| {
+            run_worker(worker, workload, length)
+        }).map_err(|error| error.to_string())?;
+
+        let mut workers = Vec::new();
+        for result in guards.join() {
+            workers.push(result?);
+        }
+        let first = workers.iter().find(|worker| worker.index == 0).expect("no measurement from worker zero");
+        Ok(Measurement {
+            workload: self.workload,
+            transport: self.transport,
+            workers: self.workers,
+            rounds: first.round_times.len(),
+            records: workers.iter().map(|worker| worker.records).sum(),
+            elapsed: workers.iter().map(|worker| worker.elapsed).max().unwrap_or_default(),
+            round_times: first.round_times.clone(),
+        })
+    }
+}
+
+/// The measurement of a benchmark.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Measurement {
+    /// The workload measured.
+    pub workload: Workload,
+    /// The transport through which workers communicated.
+    pub transport: Transport,
+    /// The number of workers.
+    pub workers: usize,
+    /// The number of rounds completed by worker zero.
+    pub rounds: usize,
+    /// The number of records introduced, across all workers and rounds.
+    ///
+    /// Each iteration of a record around a loop counts as a record.
+    pub records: usize,
+    /// The time from the start of the first round until the last worker completed its rounds.
+    pub elapsed: Duration,
+    /// The duration of each round, as observed by worker zero.
+    pub round_times: Vec<Duration>,
+}
+
+impl Measurement {
+    /// The rounds completed each second.
+    pub fn rounds_per_sec(&self) -> f64 {
+        self.rounds as f64 / self.elapsed.as_secs_f64()
+    }
+
+    /// The records processed each second.
+    pub fn records_per_sec(&self) -> f64 {
+        self.records as f64 / self.elapsed.as_secs_f64()
+    }
+
+    /// The duration of rounds at `quantile`, between zero and one, or `None` if no rounds completed.
+    pub fn latency(&self, quantile: f64) -> Option<Duration> {
+        let mut times = self.round_times.clone();
+        times.sort();
+        let index = ((times.len() as f64 - 1.0) * quantile.clamp(0.0, 1.0)).round() as usize;
+        times.get(index).copied()
+    }
+}
+
+impl Display for Measurement {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}\t{}\tworkers={}\trounds={}\telapsed={:?}\trounds/s={:.1}\trecords/s={:.1}",
+            self.workload, self.transport, self.workers, self.rounds, self.elapsed, self.rounds_per_sec(), self.records_per_sec())?;
+        if let (Some(median), Some(tail), Some(max)) = (self.latency(0.5), self.latency(0.99), self.latency(1.0)) {
+            write!(f, "\tp50={:?}\tp99={:?}\tmax={:?}", median, tail, max)?;
+        }
+        Ok(())
+    }
+}
+
+// The measurement of one worker.
+struct WorkerMeasurement {
+    index: usize,
+    records: usize,
+    elapsed: Duration,
+    round_times: Vec<Duration>,
+}
+
+// Builds the dataflow of `workload` and runs its rounds on `worker`.
+fn run_worker<A: Allocate>(worker: &mut Worker<A>, workload: Workload, length: Length) -> WorkerMeasurement {
+
+    let mut input = InputHandle::<u64, u64>::new();
+    let mut probe = ProbeHandle::new();
+
+    let (batch, multiplicity) = match workload {
+        Workload::Exchange { batch } => {
+            worker.dataflow(|scope| {
+                scope.input_from(&mut input)
+                     .exchange(|x| *x)
+                     .probe_with(&mut probe);
+            });
+            (batch, 1)
+        },
+        Workload::Barrier => {
+            worker.dataflow(|scope| {
+                scope.input_from(&mut input)
+                     .probe_with(&mut probe);
+            });
+            (0, 1)
+        },
+        Workload::Iteration { batch, iterations } => {
+            let iterations = iterations.max(1);
+            worker.dataflow(|scope| {
+                let stream = scope.input_from(&mut input);
+                scope.iterative::<u64,_,_>(|inner| {
+                    // Records carry the number of iterations they have yet to complete.
+                    let (handle, cycle) = inner.feedback(Product::new(0, 1));
+                    let step = stream.enter(inner)
+                                     .map(move |x| (x, iterations))
+                                     .concat(&cycle)
+                                     .exchange(|(x, _)| *x)
+                                     .map(|(x, remaining)| (x + 1, remaining - 1));
+                    step.filter(|(_, remaining)| *remaining > 0)
+                        .connect_loop(handle);
+                    step.filter(|(_, remaining)| *remaining == 0)
+                        .leave()
+                        .probe_with(&mut probe);
+                });
+            });
+            (batch, iterations as usize)
+        },
+    };
+
+    let index = worker.index();
+    let offset = (index * batch) as u64;
+    let mut round_times = Vec::new();
+    let start = Instant::now();
+    loop {
+        let done = match length {
+            Length::Rounds(rounds) => round_times.len() >= rounds,
+            Length::Duration(duration) => start.elapsed() >= duration,
+        };
+        if done { break; }
+
+        let round = Instant::now();
+        for record in 0 .. batch as u64 {
+            input.send(offset + record);
+        }
+        input.advance_to(input.time() + 1);
+        while probe.less_than(input.time()) {
+            worker.step();
+        }
+        round_times.push(round.elapsed());
+    }
+    let elapsed = start.elapsed();
+
+    WorkerMeasurement { index, records: round_times.len() * batch * multiplicity, elapsed, round_times }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::{Bench, Transport, Workload};
+
+    #[test]
+    fn workloads_complete_on_each_transport() {
+        let workloads = vec![Workload::Exchange { batch: 100 }, Workload::Barrier, Workload::Iteration { batch: 10, iterations: 5 }];
+        for workload in workloads {
+            for &(transport, workers) in &[(Transport::Thread, 1), (Transport::Process, 3)] {
+                let measurement = Bench::new(workload).transport(transport).workers(workers).rounds(5).run().unwrap();
+                assert_eq!(measurement.rounds, 5);
+                assert_eq!(measurement.round_times.len(), 5);
+                let expected = match workload {
+                    Workload::Exchange { batch } => workers * 5 * batch,
+                    Workload::Barrier => 0,
+                    Workload::Iteration { batch, iterations } => workers * 5 * batch * iterations as usize,
+                };
+                assert_eq!(measurement.records, expected);
+            }
+        }
+        assert!(Bench::new(Workload::Barrier).transport(Transport::Thread).workers(2).run().is_err());
+    }
+}
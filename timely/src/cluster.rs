@@ -0,0 +1,281 @@
+//! Launching the processes of a cluster from a program, for tests and small deployments.
+//!
+//! A `Launcher` starts a number of processes of a program, each told its identity, the number
+//! of processes and of workers, and the addresses of all processes. The configuration is passed
+//! both as arguments, `-w`, `-n`, `-p`, and `-h` with a hostfile, which `execute_from_args`
+//! understands, and as the environment variables `Config::from_env` reads. By default the
+//! processes run on this machine, at ports chosen by the operating system, and a spawner may
+//! instead start each process elsewhere, for example over `ssh`, at addresses supplied for them.
+//!
+//! The output of the processes may be inherited, forwarded with each line prefixed by the
+//! identity of its process, or captured and returned as the processes are joined. Processes
+//! not joined are killed as their `Cluster` is dropped.
+//!
+//! # Examples
+//! ```
+//! use timely::cluster::{Launcher, Output};
+//!
+//! // The test relaunches itself, with its processes told their configuration in the environment.
+//! if std::env::var("TIMELY_PROCESS").is_ok() {
+//!     let guards = timely::Config::from_env().unwrap().execute(|worker| {
+//!         println!("worker {} of {}", worker.index(), worker.peers());
+//!     }).unwrap();
+//!     drop(guards);
+//!     std::process::exit(0);
+//! }
+//!
+//! let cluster = Launcher::new(std::env::current_exe().unwrap())
+//!     .processes(2)
+//!     .workers(2)
+//!     .output(Output::Capture)
+//!     .launch()
+//!     .unwrap();
+//!
+//! let exits = cluster.join().unwrap();
+//! assert!(exits.iter().all(|exit| exit.status.success()));
+//! assert!(exits[1].stdout.contains("worker 3 of 4"));
+//! ```
+
+use std::ffi::{OsStr, OsString};
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::net::TcpListener;
+use std::path::PathBuf;
+use std::process::{Child, Command, ExitStatus, Stdio};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread::JoinHandle;
+
+/// A function adapting the command that starts a process, given the identity of the process.
+type Spawner = Box<dyn FnMut(usize, Command)->Command>;
+
+/// Distinguishes the hostfiles of launchers in the same process.
+static LAUNCHES: AtomicUsize = AtomicUsize::new(0);
+
+/// What becomes of the output of launched processes.
+#[derive(Debug, Clone, Copy, Hash, Eq, PartialEq)]
+pub enum Output {
+    /// The processes write to the standard output and error of this process.
+    Inherit,
+    /// Each line is written to the standard output or error of this process, prefixed by `[process]`.
+    Prefix,
+    /// The output is collected, and returned as the processes are joined.
+    Capture,
+}
+
+/// Starts the processes of a cluster with a consistent configuration.
+pub struct Launcher {
+    program: OsString,
+    args: Vec<OsString>,
+    processes: usize,
+    workers: usize,
+    addresses: Option<Vec<String>>,
+    output: Output,
+    spawner: Option<Spawner>,
+}
+
+impl Launcher {
+    /// A launcher of one process of `program`, with one worker.
+    pub fn new<P: AsRef<OsStr>>(program: P) -> Self {
+        Launcher {
+            program: program.as_ref().to_os_string(),
+            args: Vec::new(),
+            processes: 1,
+            workers: 1,
+            addresses: None,
+            output: Output::Inherit,
+            spawner: None,
+        }
+    }
+
+    /// Adds an argument to those passed to each process, before those describing its configuration.
+    pub fn arg<S: AsRef<OsStr>>(mut self, arg: S) -> Self {
+        self.args.push(arg.as_ref().to_os_string());
+        self
+    }
+
+    /// Sets the number of processes.
+    pub fn processes(mut self, processes: usize) -> Self {
+        self.processes = processes;
+        self
+    }
+
+    /// Sets the number of workers of each process.
+    pub fn workers(mut self, workers: usize) -> Self {
+        self.workers = workers;
+        self
+    }
+
+    /// Sets the addresses of the processes, rather than ports of this machine chosen as they launch.
+    pub fn addresses<I: IntoIterator<Item=S>, S: Into<String>>(mut self, addresses: I) -> Self {
+        self.addresses = Some(addresses.into_iter().map(|address| address.into()).collect());
+        self
+    }
+
+    /// Sets what becomes of the output of the processes.
+    pub fn output(mut self, output: Output) -> Self {
+        self.output = output;
+        self
+    }
+
+    /// Sets a function adapting the command that starts each process, for example to start it on another host.
+    ///
+    /// The function receives the identity of the process and a command starting it on this
+    /// machine, with its arguments and environment, and returns the command to run instead. The
+    /// output of the returned command is treated as that of the process.
+    pub fn spawner<F: FnMut(usize, Command)->Command+'static>(mut self, spawner: F) -> Self {
+        self.spawner = Some(Box::new(spawner));
+        self
+    }
+
+    /// Starts the processes.
+    ///
+    /// Should a process fail to start, those already started are killed.
+    pub fn launch(mut self) -> io::Result<Cluster> {
+        if self.processes == 0 || self.workers == 0 {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, "a cluster needs at least one process and one worker"));
+        }
+        let addresses = match self.addresses.take() {
+            Some(addresses) if addresses.len() == self.processes => addresses,
+            Some(addresses) => {
+                let message = format!("{} addresses given for {} processes", addresses.len(), self.processes);
+                return Err(io::Error::new(io::ErrorKind::InvalidInput, message));
+            },
+            None => free_ports(self.processes)?.into_iter().map(|port| format!("127.0.0.1:{}", port)).collect(),
+        };
+
+        let hostfile = ::std::env::temp_dir().join(format!("timely-launch-{}-{}.txt", ::std::process::id(), LAUNCHES.fetch_add(1, Ordering::SeqCst)));
+        let mut file = ::std::fs::File::create(&hostfile)?;
+        for address in addresses.iter() {
+            writeln!(file, "{}", address)?;
+        }
+        drop(file);
+
+        let mut cluster = Cluster { processes: Vec::new(), hostfile };
+        for process in 0 .. self.processes {
+            let mut command = Command::new(&self.program);
+            command
+                .args(&self.args)
+                .arg("-w").arg(self.workers.to_string())
+                .arg("-n").arg(self.processes.to_string())
+                .arg("-p").arg(process.to_string())
+                .arg("-h").arg(&cluster.hostfile)
+                .env("TIMELY_WORKERS", self.workers.to_string())
+                .env("TIMELY_PROCESSES", self.processes.to_string())
+                .env("TIMELY_PROCESS", process.to_string())
+                .env("TIMELY_ADDRESSES", addresses.join(","));
+            if let Some(spawner) = self.spawner.as_mut() {
+                command = spawner(process, command);
+            }
+            let piped = self.output != Output::Inherit;
+            if piped {
+                command.stdout(Stdio::piped()).stderr(Stdio::piped());
+            }
+
+            let mut child = command.spawn()?;
+            let stdout = child.stdout.take().map(|stdout| forward(stdout, process, self.output, false));
+            let stderr = child.stderr.take().map(|stderr| forward(stderr, process, self.output, true));
+            cluster.processes.push(Launched { process, child, stdout, stderr });
+        }
+        Ok(cluster)
+    }
+}
+
+/// Launches `processes` processes of `program` on this machine, each with `workers` workers.
+///
+/// The output of the processes is inherited. Refer to `Launcher` for other options.
+pub fn launch<P: AsRef<OsStr>>(program: P, processes: usize, workers: usize) -> io::Result<Cluster> {
+    Launcher::new(program).processes(processes).workers(workers).launch()
+}
+
+/// The exit of a launched process.
+#[derive(Debug, Clone)]
+pub struct Exit {
+    /// The identity of the process.
+    pub process: usize,
+    /// The status with which the process exited.
+    pub status: ExitStatus,
+    /// The standard output of the process, if it was captured.
+    pub stdout: String,
+    /// The standard error of the process, if it was captured.
+    pub stderr: String,
+}
+
+/// The processes of a launched cluster.
+pub struct Cluster {
+    processes: Vec<Launched>,
+    hostfile: PathBuf,
+}
+
+/// A launched process, and the threads forwarding or capturing its output.
+struct Launched {
+    process: usize,
+    child: Child,
+    stdout: Option<JoinHandle<String>>,
+    stderr: Option<JoinHandle<String>>,
+}
+
+impl Cluster {
+    /// The operating system identifiers of the processes, in order of their identities.
+    pub fn ids(&self) -> Vec<u32> {
+        self.processes.iter().map(|launched| launched.child.id()).collect()
+    }
+
+    /// Awaits the exit of each process, in order of their identities.
+    pub fn join(mut self) -> io::Result<Vec<Exit>> {
+        let mut exits = Vec::with_capacity(self.processes.len());
+        for mut launched in ::std::mem::take(&mut self.processes) {
+            let status = launched.child.wait()?;
+            let output = |handle: Option<JoinHandle<String>>| handle.map(|handle| handle.join().unwrap_or_default()).unwrap_or_default();
+            exits.push(Exit {
+                process: launched.process,
+                status,
+                stdout: output(launched.stdout),
+                stderr: output(launched.stderr),
+            });
+        }
+        Ok(exits)
+    }
+
+    /// Kills the processes that have not exited.
+    pub fn kill(&mut self) -> io::Result<()> {
+        for launched in self.processes.iter_mut() {
+            if launched.child.try_wait()?.is_none() {
+                launched.child.kill()?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Drop for Cluster {
+    fn drop(&mut self) {
+        let _ = self.kill();
+        for launched in self.processes.iter_mut() {
+            let _ = launched.child.wait();
+        }
+        let _ = ::std::fs::remove_file(&self.hostfile);
+    }
+}
+
+/// Forwards or captures `reader` from a thread, which returns the captured output.
+fn forward<R: Read+Send+'static>(reader: R, process: usize, output: Output, error: bool) -> JoinHandle<String> {
+    ::std::thread::spawn(move || {
+        let mut captured = String::new();
+        for line in BufReader::new(reader).lines() {
+            match (line, output) {
+                (Ok(line), Output::Capture) => { captured.push_str(&line); captured.push('\n'); },
+                (Ok(line), _) if error => eprintln!("[{}] {}", process, line),
+                (Ok(line), _) => println!("[{}] {}", process, line),
+                (Err(_), _) => break,
+            }
+        }
+        captured
+    })
+}
+
+/// Ports of this machine not in use, chosen by the operating system.
+///
+/// The ports are released before they are returned, for the launched processes to bind.
+fn free_ports(count: usize) -> io::Result<Vec<u16>> {
+    let listeners = (0 .. count).map(|_| TcpListener::bind("127.0.0.1:0")).collect::<io::Result<Vec<_>>>()?;
+    listeners.iter().map(|listener| listener.local_addr().map(|addr| addr.port())).collect()
+}
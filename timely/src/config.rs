@@ -0,0 +1,940 @@
+//! A builder for the configuration of a timely dataflow execution.
+//!
+//! `Config` describes an execution in code, as `execute_from_args` does from command line
+//! arguments: the number of workers in each process, the processes and their addresses, the
+//! names and start hooks of spawned threads, and functions installing loggers in communication
+//! threads and in workers. The description is validated as the execution starts, and problems
+//! are reported as a `ConfigError` rather than by a panic.
+//!
+//! With the `toml` feature, a description may also be read from a file with `Config::from_file`,
+//! so that deployments may keep the configuration of a cluster under version control.
+//!
+//! A description may also be read from environment variables, with `Config::from_env`, and from
+//! command line arguments. The sources compose, with those applied later taking precedence: a
+//! description read from a file may be amended by `with_env`, and then by `with_args`, so that the
+//! arguments of a process override its environment, which overrides the file.
+//!
+//! # Examples
+//! ```
+//! use timely::Config;
+//! use timely::dataflow::operators::{ToStream, Inspect};
+//!
+//! let guards = Config::new()
+//!     .workers(2)
+//!     .worker_logging(|worker| {
+//!         worker.log_register().insert::<timely::logging::TimelyEvent,_>("timely", |_time, data| {
+//!             data.clear();
+//!         });
+//!     })
+//!     .execute(|worker| {
+//!         worker.dataflow::<u64,_,_>(|scope| {
+//!             (0..10).to_stream(scope)
+//!                    .inspect(|x| println!("seen: {:?}", x));
+//!         });
+//!         worker.index()
+//!     })
+//!     .unwrap();
+//!
+//! let indices = guards.join().into_iter().map(|index| index.unwrap()).collect::<Vec<_>>();
+//! assert_eq!(indices, vec![0, 1]);
+//!
+//! // A process identity must name one of the processes.
+//! let error = Config::new().processes(2).process(2).configuration().err();
+//! assert_eq!(error, Some(timely::ConfigError::ProcessOutOfRange { process: 2, processes: 2 }));
+//! ```
+
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use crate::communication::{Allocator, Configuration, Host, Serialization, ThreadConfig, WorkerGuards, parse_hostfile};
+use crate::communication::logging::{CommunicationEvent, CommunicationSetup};
+use crate::execute::{Lifecycle, WorkerHook};
+use crate::logging_core::Logger;
+use crate::worker::{PeerFailurePolicy, Worker};
+
+/// A function constructing loggers for communication threads.
+type CommunicationLogFn = Box<dyn Fn(CommunicationSetup)->Option<Logger<CommunicationEvent, CommunicationSetup>>+Send+Sync>;
+/// A function installing loggers in each worker.
+type WorkerLogFn = Arc<dyn Fn(&mut Worker<Allocator>)+Send+Sync>;
+
+/// A problem with a `Config`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum ConfigError {
+    /// There are no workers in each process.
+    NoWorkers,
+    /// There are no processes.
+    NoProcesses,
+    /// The identity of the process does not name one of the processes.
+    ProcessOutOfRange {
+        /// The identity of the process.
+        process: usize,
+        /// The number of processes.
+        processes: usize,
+    },
+    /// The number of addresses differs from the number of processes.
+    AddressCount {
+        /// The number of addresses.
+        addresses: usize,
+        /// The number of processes.
+        processes: usize,
+    },
+    /// The number of per-process worker counts differs from the number of processes.
+    WorkerCounts {
+        /// The number of worker counts.
+        counts: usize,
+        /// The number of processes.
+        processes: usize,
+    },
+    /// A capacity of buffers or threads, named by the variant, is zero.
+    ZeroCapacity(String),
+    /// An address is empty.
+    EmptyAddress(usize),
+    /// An address is given to more than one process.
+    DuplicateAddress(String),
+    /// The file of addresses could not be read.
+    Hostfile {
+        /// The path of the file.
+        path: PathBuf,
+        /// A description of the failure.
+        message: String,
+    },
+    /// The configuration file could not be read or parsed.
+    File {
+        /// The path of the file.
+        path: PathBuf,
+        /// A description of the failure.
+        message: String,
+    },
+    /// The serialization backend is not the one this build of timely uses.
+    Serialization(String),
+    /// An environment variable has a value that could not be parsed.
+    Env {
+        /// The name of the variable.
+        variable: String,
+        /// Its value.
+        value: String,
+    },
+    /// The command line arguments could not be parsed.
+    Args(String),
+    /// The rendezvous of the processes failed.
+    Bootstrap {
+        /// The address of the rendezvous.
+        rendezvous: String,
+        /// A description of the failure.
+        message: String,
+    },
+}
+
+impl std::fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            ConfigError::NoWorkers => write!(f, "each process must have at least one worker"),
+            ConfigError::NoProcesses => write!(f, "there must be at least one process"),
+            ConfigError::ProcessOutOfRange { process, processes } => write!(f, "process {} is not among the {} processes", process, processes),
+            ConfigError::AddressCount { addresses, processes } => write!(f, "{} addresses given for {} processes", addresses, processes),
+            ConfigError::WorkerCounts { counts, processes } => write!(f, "{} worker counts given for {} processes", counts, processes),
+            ConfigError::ZeroCapacity(kind) => write!(f, "the {} capacity must be positive", kind),
+            ConfigError::EmptyAddress(index) => write!(f, "the address of process {} is empty", index),
+            ConfigError::DuplicateAddress(address) => write!(f, "address {} is given to more than one process", address),
+            ConfigError::Hostfile { path, message } => write!(f, "failed to read addresses from {}: {}", path.display(), message),
+            ConfigError::File { path, message } => write!(f, "failed to read configuration from {}: {}", path.display(), message),
+            ConfigError::Env { variable, value } => write!(f, "failed to parse {}={}", variable, value),
+            ConfigError::Args(message) => write!(f, "failed to parse arguments: {}", message),
+            ConfigError::Bootstrap { rendezvous, message } => write!(f, "failed to join rendezvous at {}: {}", rendezvous, message),
+            ConfigError::Serialization(backend) => write!(f, "serialization backend {} is not that of this build, which is {}", backend, Serialization::current()),
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
+/// A description of a timely dataflow execution, built in code.
+///
+/// By default an execution has one worker in one process. Setting more than one process, or
+/// their addresses, describes a cluster, whose processes each start with the same description
+/// other than their identity. Without addresses, the processes of a cluster are found at
+/// `localhost`, with ports increasing from 2101, as for `execute_from_args`.
+pub struct Config {
+    workers: usize,
+    process_workers: Option<Vec<usize>>,
+    processes: Option<usize>,
+    process: usize,
+    addresses: Option<Vec<String>>,
+    hostfile: Option<PathBuf>,
+    bootstrap: Option<String>,
+    report: bool,
+    peer_failure: PeerFailurePolicy,
+    serialization: Option<Serialization>,
+    output_capacity: Option<usize>,
+    exchange_capacity: Option<usize>,
+    #[cfg(feature = "rayon")]
+    pool_threads: Option<usize>,
+    threads: ThreadConfig,
+    communication_log_fn: Option<CommunicationLogFn>,
+    worker_log_fn: Option<WorkerLogFn>,
+    lifecycle: Lifecycle,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            workers: 1,
+            process_workers: None,
+            processes: None,
+            process: 0,
+            addresses: None,
+            hostfile: None,
+            bootstrap: None,
+            report: false,
+            peer_failure: PeerFailurePolicy::default(),
+            serialization: None,
+            output_capacity: None,
+            exchange_capacity: None,
+            #[cfg(feature = "rayon")]
+            pool_threads: None,
+            threads: ThreadConfig::default(),
+            communication_log_fn: None,
+            worker_log_fn: None,
+            lifecycle: Lifecycle::default(),
+        }
+    }
+}
+
+impl Config {
+    /// A description of one worker in one process.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the number of worker threads in each process.
+    ///
+    /// Processes of a hostfile may have numbers of workers of their own.
+    pub fn workers(mut self, workers: usize) -> Self {
+        self.workers = workers;
+        self
+    }
+
+    /// Sets the number of worker threads of each process, in order of their identities, for processes of different sizes.
+    ///
+    /// Without addresses or a number of processes, the number of processes is the number of
+    /// counts. The counts take precedence over `workers`, and those of a hostfile over them. Each
+    /// process starts only its own workers, and learns the numbers of workers of the others as
+    /// they connect, so that processes need not agree on their counts beyond their own.
+    ///
+    /// # Examples
+    /// ```
+    /// use timely::Config;
+    ///
+    /// let config = Config::new().addresses(vec!["big:2101", "small:2101"]).process_workers(vec![16, 2]);
+    /// assert!(config.validate().is_ok());
+    /// assert!(Config::new().processes(3).process_workers(vec![16, 2]).validate().is_err());
+    /// ```
+    pub fn process_workers(mut self, counts: Vec<usize>) -> Self {
+        self.process_workers = Some(counts);
+        self
+    }
+
+    /// Sets the number of processes.
+    ///
+    /// If addresses are given, the number of processes defaults to the number of addresses.
+    pub fn processes(mut self, processes: usize) -> Self {
+        self.processes = Some(processes);
+        self
+    }
+
+    /// Sets the identity of this process, from zero up to the number of processes.
+    pub fn process(mut self, process: usize) -> Self {
+        self.process = process;
+        self
+    }
+
+    /// Sets the addresses of the processes, in order of their identities.
+    pub fn addresses<I: IntoIterator<Item=S>, S: Into<String>>(mut self, addresses: I) -> Self {
+        self.addresses = Some(addresses.into_iter().map(|address| address.into()).collect());
+        self.hostfile = None;
+        self.bootstrap = None;
+        self
+    }
+
+    /// Reads the processes from the lines of the file at `path`, as the execution starts.
+    ///
+    /// The lines are as for `communication::parse_hostfile`, and may give a process a number of
+    /// workers of its own, which takes precedence over `workers`, and an address at which it
+    /// listens other than that at which it is reached. If the number of processes is set, only
+    /// that many processes are read.
+    pub fn hostfile<P: Into<PathBuf>>(mut self, path: P) -> Self {
+        self.hostfile = Some(path.into());
+        self.addresses = None;
+        self.bootstrap = None;
+        self
+    }
+
+    /// Learns the identity of this process, and the addresses of all processes, from the rendezvous at `rendezvous`.
+    ///
+    /// As the execution starts, the process joins the rendezvous, which is served by
+    /// `communication::bootstrap::serve` and assigns identities and ports chosen by the
+    /// operating system to the processes as they join. The identity learned replaces that set
+    /// by `process`, and the number of processes, if set, must be that the rendezvous serves.
+    ///
+    /// # Examples
+    /// ```
+    /// use timely::Config;
+    /// use timely::communication::bootstrap;
+    ///
+    /// let (rendezvous, _server) = bootstrap::serve("localhost:0", 2).unwrap();
+    ///
+    /// let processes = (0 .. 2).map(|_| std::thread::spawn(move || {
+    ///     let guards = Config::new().bootstrap(rendezvous.to_string()).execute(|worker| worker.peers()).unwrap();
+    ///     guards.join().into_iter().map(|peers| peers.unwrap()).collect::<Vec<_>>()
+    /// })).collect::<Vec<_>>();
+    ///
+    /// for process in processes {
+    ///     assert_eq!(process.join().unwrap(), vec![2]);
+    /// }
+    ///
+    /// // The identity set by `process` is replaced, and the configuration is learned only as the execution starts.
+    /// let config = Config::new().process(1).bootstrap(rendezvous.to_string());
+    /// assert!(config.validate().is_ok());
+    /// assert!(matches!(config.configuration(), Err(timely::ConfigError::Bootstrap { .. })));
+    /// ```
+    pub fn bootstrap<S: Into<String>>(mut self, rendezvous: S) -> Self {
+        self.bootstrap = Some(rendezvous.into());
+        self.addresses = None;
+        self.hostfile = None;
+        self
+    }
+
+    /// Sets whether to report the progress of connecting to other processes.
+    pub fn report(mut self, report: bool) -> Self {
+        self.report = report;
+        self
+    }
+
+    /// Sets what workers do once the connection to another process fails, as for `Worker::set_peer_failure_policy`.
+    pub fn peer_failure(mut self, policy: PeerFailurePolicy) -> Self {
+        self.peer_failure = policy;
+        self
+    }
+
+    /// Sets the serialization backend of the cluster, which must be that of this build.
+    ///
+    /// The backend is chosen as timely is built, by its `bincode` feature, and a description
+    /// naming another backend fails to validate rather than start processes that could not
+    /// communicate. Processes also verify as they connect that they serialize alike, and those
+    /// built differently fail with `InitializeError::Serialization`.
+    ///
+    /// # Examples
+    /// ```
+    /// use timely::Config;
+    /// use timely::communication::Serialization;
+    ///
+    /// assert!(Config::new().serialization(Serialization::current()).validate().is_ok());
+    ///
+    /// let other = if Serialization::current() == Serialization::Bincode { Serialization::Abomonation } else { Serialization::Bincode };
+    /// assert!(Config::new().serialization(other).validate().is_err());
+    /// ```
+    pub fn serialization(mut self, backend: Serialization) -> Self {
+        self.serialization = Some(backend);
+        self
+    }
+
+    /// Sets the number of records at which operator outputs flush, as for `Worker::set_output_capacity`.
+    ///
+    /// Workers start with the capacity, which they may change for the dataflows they construct.
+    pub fn output_capacity(mut self, capacity: usize) -> Self {
+        self.output_capacity = Some(capacity);
+        self
+    }
+
+    /// Sets the number of records at which exchange channels send, as for `Worker::set_exchange_capacity`.
+    ///
+    /// Workers start with the capacity, which they may change for the dataflows they construct.
+    ///
+    /// # Examples
+    /// ```
+    /// use timely::{Config, ConfigError};
+    ///
+    /// let config = Config::new().workers(2).output_capacity(64).exchange_capacity(256);
+    /// assert!(config.validate().is_ok());
+    ///
+    /// let error = Config::new().exchange_capacity(0).validate().err();
+    /// assert_eq!(error, Some(ConfigError::ZeroCapacity("exchange".to_string())));
+    /// ```
+    pub fn exchange_capacity(mut self, capacity: usize) -> Self {
+        self.exchange_capacity = Some(capacity);
+        self
+    }
+
+    /// Sets the number of threads of a pool shared by the workers of each process, as for `Worker::set_pool`.
+    ///
+    /// Operators such as `ParallelMap::map_parallel` fan out the work of their batches across
+    /// the pool, whose threads are named `pool thread {}` with their index.
+    ///
+    /// # Examples
+    /// ```
+    /// use timely::Config;
+    /// use timely::dataflow::operators::{ToStream, ParallelMap, Inspect};
+    ///
+    /// Config::new()
+    ///     .workers(2)
+    ///     .pool_threads(4)
+    ///     .execute(|worker| {
+    ///         worker.dataflow::<u64,_,_>(|scope| {
+    ///             (0 .. 1000u64).to_stream(scope)
+    ///                           .map_parallel(|x| x * x)
+    ///                           .inspect(|x| println!("seen: {:?}", x));
+    ///         });
+    ///     })
+    ///     .unwrap();
+    /// ```
+    #[cfg(feature = "rayon")]
+    pub fn pool_threads(mut self, threads: usize) -> Self {
+        self.pool_threads = Some(threads);
+        self
+    }
+
+    /// Sets the names of spawned threads, and the hook each runs before it starts its work.
+    pub fn threads(mut self, threads: ThreadConfig) -> Self {
+        self.threads = threads;
+        self
+    }
+
+    /// Sets a function constructing loggers for the communication threads of a cluster.
+    ///
+    /// The function is the `log_fn` of `Configuration::Cluster`, and is unused by executions in
+    /// a single process without addresses, which have no communication threads.
+    pub fn communication_logging<F>(mut self, log_fn: F) -> Self
+    where
+        F: Fn(CommunicationSetup)->Option<Logger<CommunicationEvent, CommunicationSetup>>+Send+Sync+'static,
+    {
+        self.communication_log_fn = Some(Box::new(log_fn));
+        self
+    }
+
+    /// Sets a function invoked with each worker as it is constructed, to install loggers.
+    ///
+    /// The function is invoked before the worker runs the closure of the execution, and after
+    /// any loggers installed in response to environment variables, which it may replace.
+    pub fn worker_logging<F: Fn(&mut Worker<Allocator>)+Send+Sync+'static>(mut self, log_fn: F) -> Self {
+        self.worker_log_fn = Some(Arc::new(log_fn));
+        self
+    }
+
+    /// Adds a function invoked with each worker as it starts, before the closure of the execution.
+    ///
+    /// Functions are invoked in the order in which they were added, on the thread of the worker,
+    /// before it constructs any dataflow and after the loggers of `worker_logging` are installed.
+    /// The worker presents its `index`, its `peers`, and its place among the processes, and the
+    /// functions may initialize resources for the worker, such as connections to databases,
+    /// caches, or registries of metrics.
+    ///
+    /// # Examples
+    /// ```
+    /// use std::sync::Arc;
+    /// use std::sync::atomic::{AtomicUsize, Ordering};
+    /// use timely::Config;
+    /// use timely::dataflow::operators::{ToStream, Inspect};
+    ///
+    /// let started = Arc::new(AtomicUsize::new(0));
+    /// let stopped = Arc::new(AtomicUsize::new(0));
+    /// let (start, stop) = (started.clone(), stopped.clone());
+    ///
+    /// let guards = Config::new()
+    ///     .workers(2)
+    ///     .on_worker_start(move |worker| {
+    ///         println!("starting worker {} of {}", worker.index(), worker.peers());
+    ///         start.fetch_add(1, Ordering::SeqCst);
+    ///     })
+    ///     .on_worker_stop(move |worker| {
+    ///         assert!(worker.installed_dataflows().is_empty());
+    ///         stop.fetch_add(1, Ordering::SeqCst);
+    ///     })
+    ///     .execute(|worker| {
+    ///         worker.dataflow::<u64,_,_>(|scope| {
+    ///             (0..10).to_stream(scope).inspect(|x| println!("seen: {:?}", x));
+    ///         });
+    ///     })
+    ///     .unwrap();
+    ///
+    /// guards.join();
+    /// assert_eq!(started.load(Ordering::SeqCst), 2);
+    /// assert_eq!(stopped.load(Ordering::SeqCst), 2);
+    /// ```
+    pub fn on_worker_start<F: Fn(&mut Worker<Allocator>)+Send+Sync+'static>(mut self, hook: F) -> Self {
+        self.lifecycle.start.push(Arc::new(hook));
+        self
+    }
+
+    /// Adds a function invoked with each worker as it stops, once its dataflows have completed.
+    ///
+    /// Functions are invoked in the order in which they were added, on the thread of the worker,
+    /// after the closure of the execution has returned and the worker has stepped its dataflows
+    /// to completion, and may release the resources of the worker. They are not invoked if the
+    /// worker panics, though they are for workers with failed dataflows, as caught by
+    /// `Worker::set_catch_panics`.
+    pub fn on_worker_stop<F: Fn(&mut Worker<Allocator>)+Send+Sync+'static>(mut self, hook: F) -> Self {
+        self.lifecycle.stop.push(Arc::new(hook));
+        self
+    }
+
+    /// Reads a description from the TOML document at `path`.
+    ///
+    /// Each key of the document is optional, and those absent leave the defaults of `Config::new`
+    /// in place. The document is the same for all processes, whose identities are usually set
+    /// afterwards with `process`, and it may set the following keys.
+    ///
+    /// ```toml
+    /// workers = 4                              # worker threads in each process
+    /// process_workers = [4, 8]                 # or else: worker threads of each process in turn
+    /// processes = 2                            # the number of processes
+    /// process = 0                              # the identity of this process
+    /// addresses = ["host0:2101", "host1:2101"] # or: hostfile = "hosts.txt", or: bootstrap = "host0:2100"
+    /// serialization = "abomonation"            # or "bincode", which must match the build
+    ///
+    /// [network]
+    /// report = true                            # report the progress of connecting to processes
+    /// peer_failure = "abort"                   # or "continue" or "degrade", once a connection to a process fails
+    ///
+    /// [channels]
+    /// output_capacity = 1024                   # records at which operator outputs flush
+    /// exchange_capacity = 1024                 # records at which exchange channels send
+    ///
+    /// [threads]
+    /// worker_name = "worker-{}"                # names of spawned threads, as for `ThreadConfig`
+    /// sender_name = "send-{}"
+    /// receiver_name = "recv-{}"
+    ///
+    /// [logging]
+    /// worker_addr = "localhost:8000"           # or else: worker_path = "logs/run"
+    /// communication_path = "logs/run"          # or else: communication_addr = "localhost:8001"
+    /// ```
+    ///
+    /// Logging destinations are as for the `TIMELY_WORKER_LOG_ADDR` family of environment
+    /// variables, and are installed as by `communication_logging` and `worker_logging`. Unknown
+    /// keys are reported as errors, rather than ignored.
+    ///
+    /// # Examples
+    /// ```
+    /// use timely::Config;
+    ///
+    /// let path = std::env::temp_dir().join(format!("timely-config-{}.toml", std::process::id()));
+    /// std::fs::write(&path, "workers = 2\naddresses = [\"localhost:2101\", \"localhost:2102\"]\n").unwrap();
+    /// let config = Config::from_file(&path).unwrap().process(1);
+    /// assert!(config.validate().is_ok());
+    ///
+    /// std::fs::write(&path, "wrokers = 2\n").unwrap();
+    /// assert!(Config::from_file(&path).is_err());
+    /// std::fs::remove_file(&path).unwrap();
+    /// ```
+    #[cfg(feature = "toml")]
+    pub fn from_file<P: AsRef<::std::path::Path>>(path: P) -> Result<Self, ConfigError> {
+        let path = path.as_ref();
+        let error = |message: String| ConfigError::File { path: path.to_path_buf(), message };
+        let text = ::std::fs::read_to_string(path).map_err(|err| error(err.to_string()))?;
+        let document: document::Document = ::toml::from_str(&text).map_err(|err| error(err.to_string()))?;
+        document.into_config(path)
+    }
+
+    /// Reads a description from environment variables.
+    ///
+    /// The variables are those `with_env` reads, and those not set leave the defaults of
+    /// `Config::new` in place.
+    pub fn from_env() -> Result<Self, ConfigError> {
+        Self::new().with_env()
+    }
+
+    /// Amends the description with the environment variables that are set.
+    ///
+    /// The variables are
+    ///
+    /// * `TIMELY_WORKERS`, the number of worker threads in each process,
+    /// * `TIMELY_PROCESSES`, the number of processes,
+    /// * `TIMELY_PROCESS`, the identity of this process,
+    /// * `TIMELY_ADDRESSES`, the addresses of the processes, separated by commas,
+    /// * `TIMELY_HOSTFILE`, a file whose lines are the addresses of the processes,
+    /// * `TIMELY_BOOTSTRAP`, the address of a rendezvous of the processes,
+    /// * `TIMELY_REPORT`, which is `true` or `1` to report the progress of connecting,
+    /// * `TIMELY_PEER_FAILURE`, which is `abort`, `continue`, or `degrade`, as for `peer_failure`,
+    /// * `TIMELY_SERIALIZATION`, which is `abomonation` or `bincode`, as for `serialization`, and
+    /// * `TIMELY_OUTPUT_CAPACITY` and `TIMELY_EXCHANGE_CAPACITY`, numbers of records, as for
+    ///   `output_capacity` and `exchange_capacity`.
+    ///
+    /// Variables that are set but cannot be parsed are reported as errors. The destinations of
+    /// logged events are read from their own variables, such as `TIMELY_WORKER_LOG_ADDR`, as the
+    /// execution starts, whatever its configuration.
+    ///
+    /// # Examples
+    /// ```
+    /// use timely::Config;
+    ///
+    /// std::env::set_var("TIMELY_WORKERS", "3");
+    /// std::env::set_var("TIMELY_ADDRESSES", "host0:2101,host1:2101");
+    /// std::env::set_var("TIMELY_PROCESS", "1");
+    ///
+    /// // Arguments take precedence over the environment.
+    /// let args = vec!["program", "-w", "2"].into_iter().map(String::from);
+    /// let config = Config::from_env().unwrap().with_args(args).unwrap();
+    /// match config.configuration().unwrap() {
+    ///     timely::Configuration::Cluster { threads, process, addresses, .. } => {
+    ///         assert_eq!(threads, 2);
+    ///         assert_eq!(process, 1);
+    ///         assert_eq!(addresses, vec!["host0:2101", "host1:2101"]);
+    ///     },
+    ///     _ => panic!("expected a cluster"),
+    /// }
+    ///
+    /// std::env::set_var("TIMELY_WORKERS", "three");
+    /// assert!(Config::from_env().is_err());
+    /// ```
+    pub fn with_env(mut self) -> Result<Self, ConfigError> {
+        if let Some(workers) = parse_env("TIMELY_WORKERS")? { self = self.workers(workers); }
+        if let Some(processes) = parse_env("TIMELY_PROCESSES")? { self = self.processes(processes); }
+        if let Some(process) = parse_env("TIMELY_PROCESS")? { self = self.process(process); }
+        if let Ok(addresses) = ::std::env::var("TIMELY_ADDRESSES") {
+            self = self.addresses(addresses.split(',').map(|address| address.trim()));
+        }
+        if let Ok(hostfile) = ::std::env::var("TIMELY_HOSTFILE") {
+            self = self.hostfile(hostfile);
+        }
+        if let Ok(rendezvous) = ::std::env::var("TIMELY_BOOTSTRAP") {
+            self = self.bootstrap(rendezvous);
+        }
+        if let Ok(report) = ::std::env::var("TIMELY_REPORT") {
+            match &report[..] {
+                "true" | "1" => { self = self.report(true); },
+                "false" | "0" => { self = self.report(false); },
+                _ => return Err(ConfigError::Env { variable: "TIMELY_REPORT".to_string(), value: report }),
+            }
+        }
+        if let Some(policy) = parse_env("TIMELY_PEER_FAILURE")? { self = self.peer_failure(policy); }
+        if let Ok(backend) = ::std::env::var("TIMELY_SERIALIZATION") {
+            let serialization = backend.parse().map_err(|_| ConfigError::Env { variable: "TIMELY_SERIALIZATION".to_string(), value: backend })?;
+            self = self.serialization(serialization);
+        }
+        if let Some(capacity) = parse_env("TIMELY_OUTPUT_CAPACITY")? { self = self.output_capacity(capacity); }
+        if let Some(capacity) = parse_env("TIMELY_EXCHANGE_CAPACITY")? { self = self.exchange_capacity(capacity); }
+        Ok(self)
+    }
+
+    /// Amends the description with the options present in command line arguments.
+    ///
+    /// The options are those `execute_from_args` understands, `-w`, `-n`, `-p`, `-h`, and `-r`,
+    /// and those absent leave the description in place. Options whose values cannot be parsed
+    /// are reported as errors.
+    pub fn with_args<I: Iterator<Item=String>>(mut self, args: I) -> Result<Self, ConfigError> {
+        let matches = Configuration::options().parse(args).map_err(|err| ConfigError::Args(err.to_string()))?;
+        let parse = |option: &str| -> Result<Option<usize>, ConfigError> {
+            matches.opt_str(option)
+                .map(|value| value.parse().map_err(|_| ConfigError::Args(format!("invalid value for -{}: {}", option, value))))
+                .transpose()
+        };
+        if let Some(workers) = parse("w")? { self = self.workers(workers); }
+        if let Some(processes) = parse("n")? { self = self.processes(processes); }
+        if let Some(process) = parse("p")? { self = self.process(process); }
+        if let Some(hostfile) = matches.opt_str("h") { self = self.hostfile(hostfile); }
+        if matches.opt_present("r") { self = self.report(true); }
+        Ok(self)
+    }
+
+    /// The number of processes, their addresses, and the number of workers of this process, after validation.
+    ///
+    /// This process is given the address at which it listens, and others those at which they are reached.
+    fn cluster(&self) -> Result<(usize, Option<Vec<String>>, usize), ConfigError> {
+        if let Some(backend) = self.serialization.filter(|backend| *backend != Serialization::current()) {
+            return Err(ConfigError::Serialization(backend.name().to_string()));
+        }
+        if self.output_capacity == Some(0) {
+            return Err(ConfigError::ZeroCapacity("output".to_string()));
+        }
+        if self.exchange_capacity == Some(0) {
+            return Err(ConfigError::ZeroCapacity("exchange".to_string()));
+        }
+        #[cfg(feature = "rayon")]
+        if self.pool_threads == Some(0) {
+            return Err(ConfigError::ZeroCapacity("pool".to_string()));
+        }
+
+        let hosts = match &self.hostfile {
+            Some(path) => {
+                let error = |message: String| ConfigError::Hostfile { path: path.clone(), message };
+                let text = ::std::fs::read_to_string(path).map_err(|err| error(err.to_string()))?;
+                let mut hosts = parse_hostfile(&text).map_err(error)?;
+                hosts.truncate(self.processes.unwrap_or(usize::MAX));
+                Some(hosts)
+            },
+            None => self.addresses.as_ref().map(|addresses| {
+                addresses.iter().map(|address| Host { advertise: address.clone(), bind: None, workers: None }).collect()
+            }),
+        };
+
+        let processes = self.processes
+            .or(hosts.as_ref().map(|hosts| hosts.len()))
+            .or(self.process_workers.as_ref().map(|counts| counts.len()))
+            .unwrap_or(1);
+        if processes == 0 {
+            return Err(ConfigError::NoProcesses);
+        }
+        // The identity of a process joining a rendezvous is not known until it joins, and replaces that set.
+        let process = if self.bootstrap.is_some() { 0 } else { self.process };
+        if process >= processes {
+            return Err(ConfigError::ProcessOutOfRange { process, processes });
+        }
+
+        let mut workers = self.workers;
+        if let Some(counts) = self.process_workers.as_ref() {
+            if counts.len() != processes {
+                return Err(ConfigError::WorkerCounts { counts: counts.len(), processes });
+            }
+            if counts.contains(&0) {
+                return Err(ConfigError::NoWorkers);
+            }
+            workers = counts[process];
+        }
+        if let Some(hosts) = hosts.as_ref() {
+            if hosts.len() != processes {
+                return Err(ConfigError::AddressCount { addresses: hosts.len(), processes });
+            }
+            for (index, host) in hosts.iter().enumerate() {
+                if host.advertise.trim().is_empty() {
+                    return Err(ConfigError::EmptyAddress(index));
+                }
+                if hosts[.. index].iter().any(|other| other.advertise == host.advertise) {
+                    return Err(ConfigError::DuplicateAddress(host.advertise.clone()));
+                }
+            }
+            workers = hosts[process].workers.unwrap_or(workers);
+        }
+        if workers == 0 {
+            return Err(ConfigError::NoWorkers);
+        }
+
+        let addresses = hosts.map(|hosts| {
+            hosts.iter().enumerate().map(|(index, host)| {
+                if index == process { host.bind().to_string() } else { host.advertise.clone() }
+            }).collect()
+        });
+
+        Ok((processes, addresses, workers))
+    }
+
+    /// Checks the description, without starting an execution.
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        self.cluster().map(|_| ())
+    }
+
+    /// The configuration of the communication infrastructure.
+    ///
+    /// The result describes the workers and processes, and the communication loggers, but not
+    /// the names of threads, the loggers of workers, nor their policy on failures, which are
+    /// applied by `execute`. A description joining a rendezvous learns its configuration only
+    /// as its execution starts, and is reported as a `ConfigError::Bootstrap`.
+    pub fn configuration(self) -> Result<Configuration, ConfigError> {
+        if let Some(rendezvous) = self.bootstrap.as_ref() {
+            self.validate()?;
+            let message = "the configuration is learned from the rendezvous as the execution starts".to_string();
+            return Err(ConfigError::Bootstrap { rendezvous: rendezvous.clone(), message });
+        }
+        self.split().map(|(configuration, _, _)| configuration)
+    }
+
+    /// Splits the description into the configuration of communication, of threads, and of worker logging.
+    fn split(mut self) -> Result<(Configuration, ThreadConfig, Option<WorkerLogFn>), ConfigError> {
+        self.validate()?;
+        if let Some(rendezvous) = self.bootstrap.take() {
+            let error = |message: String| ConfigError::Bootstrap { rendezvous: rendezvous.clone(), message };
+            let joined = crate::communication::bootstrap::join(&rendezvous[..], None).map_err(|err| error(err.to_string()))?;
+            if self.processes.map(|processes| processes != joined.addresses.len()) == Some(true) {
+                return Err(error(format!("rendezvous serves {} processes", joined.addresses.len())));
+            }
+            self.process = joined.process;
+            self.addresses = Some(joined.connect_addresses());
+        }
+        let (processes, addresses, workers) = self.cluster()?;
+        let configuration = match addresses {
+            None if processes == 1 && workers == 1 => Configuration::Thread,
+            None if processes == 1 => Configuration::Process(workers),
+            addresses => Configuration::Cluster {
+                threads: workers,
+                process: self.process,
+                addresses: addresses.unwrap_or_else(|| (0 .. processes).map(|index| format!("localhost:{}", 2101 + index)).collect()),
+                report: self.report,
+                log_fn: self.communication_log_fn.unwrap_or_else(|| Box::new(|_| None)),
+            },
+        };
+        Ok((configuration, self.threads, self.worker_log_fn))
+    }
+
+    /// Executes a timely dataflow as described, with per-worker logic `func`.
+    ///
+    /// Refer to [`execute`](../execute/fn.execute.html) for more details.
+    pub fn execute<T, F>(mut self, func: F) -> Result<WorkerGuards<T>, String>
+    where
+        T: Send+'static,
+        F: Fn(&mut Worker<Allocator>)->T+Send+Sync+'static,
+    {
+        let (peer_failure, output_capacity, exchange_capacity) = (self.peer_failure, self.output_capacity, self.exchange_capacity);
+        let mut lifecycle = ::std::mem::take(&mut self.lifecycle);
+        #[cfg(feature = "rayon")]
+        let pool_threads = self.pool_threads;
+        let (configuration, threads, worker_log_fn) = self.split().map_err(|err| err.to_string())?;
+        #[cfg(feature = "rayon")]
+        let pool = match pool_threads {
+            Some(threads) => Some(Arc::new(rayon::ThreadPoolBuilder::new()
+                .num_threads(threads)
+                .thread_name(|index| format!("pool thread {}", index))
+                .build()
+                .map_err(|err| err.to_string())?)),
+            None => None,
+        };
+        let setup: WorkerHook = Arc::new(move |worker| {
+            worker.set_peer_failure_policy(peer_failure);
+            worker.set_output_capacity(output_capacity);
+            worker.set_exchange_capacity(exchange_capacity);
+            #[cfg(feature = "rayon")]
+            worker.set_pool(pool.clone());
+            if let Some(log_fn) = worker_log_fn.as_ref() {
+                log_fn(worker);
+            }
+        });
+        lifecycle.start.insert(0, setup);
+        crate::execute::try_execute_lifecycle(configuration, threads, lifecycle, true, func).map_err(|err| err.to_string())
+    }
+}
+
+/// The value of the environment variable `variable`, if it is set.
+fn parse_env<T: ::std::str::FromStr>(variable: &str) -> Result<Option<T>, ConfigError> {
+    match ::std::env::var(variable) {
+        Ok(value) => value.parse().map(Some).map_err(|_| ConfigError::Env { variable: variable.to_string(), value }),
+        Err(_) => Ok(None),
+    }
+}
+
+/// The layout of a configuration file.
+#[cfg(feature = "toml")]
+mod document {
+
+    use std::path::{Path, PathBuf};
+
+    use crate::communication::ThreadConfig;
+    use crate::execute::LogDestination;
+    use super::{Config, ConfigError};
+
+    /// The keys at the top of the document.
+    #[derive(Deserialize, Default)]
+    #[serde(deny_unknown_fields)]
+    pub(super) struct Document {
+        workers: Option<usize>,
+        process_workers: Option<Vec<usize>>,
+        processes: Option<usize>,
+        process: Option<usize>,
+        addresses: Option<Vec<String>>,
+        hostfile: Option<PathBuf>,
+        bootstrap: Option<String>,
+        serialization: Option<String>,
+        #[serde(default)]
+        network: Network,
+        #[serde(default)]
+        channels: Channels,
+        #[serde(default)]
+        threads: Threads,
+        #[serde(default)]
+        logging: Logging,
+    }
+
+    /// The `[network]` table.
+    #[derive(Deserialize, Default)]
+    #[serde(deny_unknown_fields)]
+    struct Network {
+        report: Option<bool>,
+        peer_failure: Option<String>,
+    }
+
+    /// The `[channels]` table.
+    #[derive(Deserialize, Default)]
+    #[serde(deny_unknown_fields)]
+    struct Channels {
+        output_capacity: Option<usize>,
+        exchange_capacity: Option<usize>,
+    }
+
+    /// The `[threads]` table.
+    #[derive(Deserialize, Default)]
+    #[serde(deny_unknown_fields)]
+    struct Threads {
+        worker_name: Option<String>,
+        sender_name: Option<String>,
+        receiver_name: Option<String>,
+    }
+
+    /// The `[logging]` table.
+    #[derive(Deserialize, Default)]
+    #[serde(deny_unknown_fields)]
+    struct Logging {
+        worker_addr: Option<String>,
+        worker_path: Option<String>,
+        communication_addr: Option<String>,
+        communication_path: Option<String>,
+    }
+
+    /// The destination of an address or a path, of which at most one may be set.
+    fn destination(file: &Path, kind: &str, addr: Option<String>, path: Option<String>) -> Result<Option<LogDestination>, ConfigError> {
+        match (addr, path) {
+            (Some(_), Some(_)) => Err(ConfigError::File {
+                path: file.to_path_buf(),
+                message: format!("both {}_addr and {}_path are set", kind, kind),
+            }),
+            (Some(addr), None) => LogDestination::addr(addr).map(Some),
+            (None, Some(path)) => LogDestination::path(path).map(Some),
+            (None, None) => Ok(None),
+        }
+    }
+
+    impl Document {
+        /// A description of the document, read from `file`.
+        pub(super) fn into_config(self, file: &Path) -> Result<Config, ConfigError> {
+            let mut config = Config::new();
+            if let Some(backend) = self.serialization {
+                let serialization = backend.parse().map_err(|_| ConfigError::Serialization(backend))?;
+                config = config.serialization(serialization);
+            }
+            if let Some(workers) = self.workers { config = config.workers(workers); }
+            if let Some(counts) = self.process_workers { config = config.process_workers(counts); }
+            if let Some(processes) = self.processes { config = config.processes(processes); }
+            if let Some(process) = self.process { config = config.process(process); }
+            match (self.addresses, self.hostfile, self.bootstrap) {
+                (Some(addresses), None, None) => { config = config.addresses(addresses); },
+                (None, Some(hostfile), None) => { config = config.hostfile(hostfile); },
+                (None, None, Some(rendezvous)) => { config = config.bootstrap(rendezvous); },
+                (None, None, None) => { },
+                _ => return Err(ConfigError::File {
+                    path: file.to_path_buf(),
+                    message: "at most one of addresses, hostfile, and bootstrap may be set".to_string(),
+                }),
+            }
+            if let Some(report) = self.network.report { config = config.report(report); }
+            if let Some(policy) = self.network.peer_failure {
+                let policy = policy.parse().map_err(|message| ConfigError::File { path: file.to_path_buf(), message })?;
+                config = config.peer_failure(policy);
+            }
+            if let Some(capacity) = self.channels.output_capacity { config = config.output_capacity(capacity); }
+            if let Some(capacity) = self.channels.exchange_capacity { config = config.exchange_capacity(capacity); }
+
+            let defaults = ThreadConfig::default();
+            config = config.threads(ThreadConfig {
+                worker_name: self.threads.worker_name.unwrap_or(defaults.worker_name),
+                sender_name: self.threads.sender_name.unwrap_or(defaults.sender_name),
+                receiver_name: self.threads.receiver_name.unwrap_or(defaults.receiver_name),
+                on_start: None,
+            });
+
+            if let Some(destination) = destination(file, "worker", self.logging.worker_addr, self.logging.worker_path)? {
+                config = config.worker_logging(move |worker| destination.install(worker));
+            }
+            if let Some(destination) = destination(file, "communication", self.logging.communication_addr, self.logging.communication_path)? {
+                config = config.communication_logging(destination.communication_logger());
+            }
+
+            Ok(config)
+        }
+    }
+}
@@ -0,0 +1,177 @@
+//! Sorting and consolidation of batches of records with counts.
+//!
+//! Batches here are vectors of records paired with a count, or "diff", and consolidating a
+//! batch sorts it by record, sums the counts of equal records, and discards records whose counts
+//! sum to zero. Counts may be any type that can be summed and whose `Default` is zero, such as
+//! the signed integers. Consolidated batches can be merged, which consolidates across batches in
+//! linear time, and `consolidate_updates` does the same for records with timestamps.
+//!
+//! The crate does not have container traits; batches are the vectors that streams carry.
+//!
+//! # Examples
+//! ```
+//! use timely::consolidation::{consolidate, merge_consolidated};
+//!
+//! let mut counts = vec![("b", 1), ("a", 2), ("b", -1), ("c", 1), ("a", 1)];
+//! consolidate(&mut counts);
+//! assert_eq!(counts, vec![("a", 3), ("c", 1)]);
+//!
+//! let mut other = vec![("c", -1), ("d", 4)];
+//! consolidate(&mut other);
+//! assert_eq!(merge_consolidated(counts, other), vec![("a", 3), ("d", 4)]);
+//! ```
+
+use std::ops::AddAssign;
+
+/// Sorts `vec` by record and consolidates the counts of equal records, discarding zero counts.
+pub fn consolidate<D: Ord, R: AddAssign+Default+PartialEq>(vec: &mut Vec<(D, R)>) {
+    consolidate_from(vec, 0);
+}
+
+/// Sorts and consolidates `vec[offset..]`, leaving `vec[..offset]` unchanged.
+///
+/// This allows a batch to be consolidated as it is extended, without revisiting the prefix.
+pub fn consolidate_from<D: Ord, R: AddAssign+Default+PartialEq>(vec: &mut Vec<(D, R)>, offset: usize) {
+    let length = consolidate_slice(&mut vec[offset..]);
+    vec.truncate(offset + length);
+}
+
+/// Sorts and consolidates `slice`, returning the length of the consolidated prefix.
+///
+/// Elements beyond the returned length are left in an unspecified order.
+pub fn consolidate_slice<D: Ord, R: AddAssign+Default+PartialEq>(slice: &mut [(D, R)]) -> usize {
+    slice.sort_by(|x, y| x.0.cmp(&y.0));
+    // Accumulate each run of equal records at `offset`, the end of the consolidated prefix.
+    let mut offset = 0;
+    for index in 1 .. slice.len() {
+        if slice[offset].0 == slice[index].0 {
+            let count = std::mem::take(&mut slice[index].1);
+            slice[offset].1 += count;
+        }
+        else {
+            if slice[offset].1 != R::default() {
+                offset += 1;
+            }
+            slice.swap(offset, index);
+        }
+    }
+    if offset < slice.len() && slice[offset].1 != R::default() {
+        offset += 1;
+    }
+    offset
+}
+
+/// Sorts `vec` by record and time and consolidates the counts of equal pairs, discarding zero counts.
+pub fn consolidate_updates<D: Ord, T: Ord, R: AddAssign+Default+PartialEq>(vec: &mut Vec<(D, T, R)>) {
+    vec.sort_by(|x, y| (&x.0, &x.1).cmp(&(&y.0, &y.1)));
+    let mut offset = 0;
+    for index in 1 .. vec.len() {
+        if vec[offset].0 == vec[index].0 && vec[offset].1 == vec[index].1 {
+            let count = std::mem::take(&mut vec[index].2);
+            vec[offset].2 += count;
+        }
+        else {
+            if vec[offset].2 != R::default() {
+                offset += 1;
+            }
+            vec.swap(offset, index);
+        }
+    }
+    if offset < vec.len() && vec[offset].2 != R::default() {
+        offset += 1;
+    }
+    vec.truncate(offset);
+}
+
+/// Merges two consolidated batches into one consolidated batch.
+///
+/// Both batches must be consolidated, as by `consolidate`, for the result to be.
+pub fn merge_consolidated<D: Ord, R: AddAssign+Default+PartialEq>(batch1: Vec<(D, R)>, batch2: Vec<(D, R)>) -> Vec<(D, R)> {
+    let mut result = Vec::with_capacity(batch1.len() + batch2.len());
+    let mut batch1 = batch1.into_iter().peekable();
+    let mut batch2 = batch2.into_iter().peekable();
+    while let (Some(x), Some(y)) = (batch1.peek(), batch2.peek()) {
+        match x.0.cmp(&y.0) {
+            std::cmp::Ordering::Less => result.push(batch1.next().unwrap()),
+            std::cmp::Ordering::Greater => result.push(batch2.next().unwrap()),
+            std::cmp::Ordering::Equal => {
+                let (record, mut count) = batch1.next().unwrap();
+                count += batch2.next().unwrap().1;
+                if count != R::default() {
+                    result.push((record, count));
+                }
+            },
+        }
+    }
+    result.extend(batch1);
+    result.extend(batch2);
+    result
+}
+
+/// Consolidates the records of several batches into one consolidated batch.
+///
+/// # Examples
+/// ```
+/// use timely::consolidation::consolidate_batches;
+///
+/// let batches = vec![vec![(1, 1), (2, 1)], vec![(2, -1), (3, 2)], vec![(1, 1)]];
+/// assert_eq!(consolidate_batches(batches), vec![(1, 2), (3, 2)]);
+/// ```
+pub fn consolidate_batches<D, R, I>(batches: I) -> Vec<(D, R)>
+where
+    D: Ord,
+    R: AddAssign+Default+PartialEq,
+    I: IntoIterator<Item=Vec<(D, R)>>,
+{
+    let mut result = Vec::new();
+    for mut batch in batches {
+        // Batches are consolidated as they are appended, and all together once collected.
+        let offset = result.len();
+        result.append(&mut batch);
+        consolidate_from(&mut result, offset);
+    }
+    consolidate(&mut result);
+    result
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn consolidate_discards_zeros() {
+        let mut vec = vec![(3, 1), (1, 1), (3, -1), (2, 0), (1, 2)];
+        consolidate(&mut vec);
+        assert_eq!(vec, vec![(1, 3)]);
+
+        let mut vec: Vec<(u8, i64)> = vec![(0, 1), (0, -1)];
+        consolidate(&mut vec);
+        assert!(vec.is_empty());
+    }
+
+    #[test]
+    fn consolidate_from_keeps_prefix() {
+        let mut vec = vec![(5, 1), (4, 1), (2, 1), (1, 1), (2, 1)];
+        consolidate_from(&mut vec, 2);
+        assert_eq!(vec, vec![(5, 1), (4, 1), (1, 1), (2, 2)]);
+    }
+
+    #[test]
+    fn consolidate_updates_by_time() {
+        let mut vec = vec![("a", 1, 1), ("a", 0, 1), ("a", 1, 1), ("b", 0, 1), ("b", 0, -1)];
+        consolidate_updates(&mut vec);
+        assert_eq!(vec, vec![("a", 0, 1), ("a", 1, 2)]);
+    }
+
+    #[test]
+    fn merge_matches_consolidate() {
+        let mut batch1 = (0 .. 100).map(|x| (x % 7, if x % 2 == 0 { 1 } else { -1 })).collect::<Vec<_>>();
+        let mut batch2 = (0 .. 50).map(|x| (x % 11, 1)).collect::<Vec<_>>();
+        let mut all = batch1.iter().chain(batch2.iter()).cloned().collect::<Vec<_>>();
+        consolidate(&mut batch1);
+        consolidate(&mut batch2);
+        consolidate(&mut all);
+        assert_eq!(merge_consolidated(batch1, batch2), all);
+    }
+}
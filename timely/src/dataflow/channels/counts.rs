@@ -0,0 +1,109 @@
+//! Cumulative counts of the messages, records, and bytes moved by channels.
+//!
+//! Each channel connected by a parallelization contract counts, at each worker, the messages
+//! the worker sends along it and those it receives. The counts are available as snapshots from
+//! `Worker::channel_counts`, for applications to observe without consuming the logged events.
+//!
+//! The bytes of records are estimated as `size_of::<D>()` for each record of type `D`, as for
+//! the bytes buffered under a `Quota`, and so exclude memory the records own indirectly.
+//!
+//! # Examples
+//! ```
+//! use timely::dataflow::InputHandle;
+//! use timely::dataflow::operators::{Input, Exchange, Probe};
+//!
+//! timely::execute_from_args(::std::env::args(), |worker| {
+//!     let mut input = InputHandle::new();
+//!     let probe = worker.dataflow::<u64,_,_>(|scope| {
+//!         scope.input_from(&mut input)
+//!              .exchange(|x: &u64| *x)
+//!              .probe()
+//!     });
+//!
+//!     for round in 0 .. 10 {
+//!         input.send(round);
+//!     }
+//!     input.advance_to(1);
+//!     worker.step_while(|| probe.less_than(input.time()));
+//!
+//!     let counts = worker.channel_counts();
+//!     let exchanged = counts.iter().find(|counts| counts.sent.records > 0).unwrap();
+//!     assert_eq!(exchanged.sent.records, 10);
+//!     assert_eq!(exchanged.sent.bytes, 10 * ::std::mem::size_of::<u64>());
+//! }).unwrap();
+//! ```
+
+use std::rc::Rc;
+use std::cell::Cell;
+
+/// The messages, records, and estimated bytes moved in one direction along a channel.
+#[derive(Debug, Clone, Copy, Default, Hash, Eq, PartialEq)]
+pub struct Volume {
+    /// The number of messages.
+    pub messages: usize,
+    /// The number of records in the messages.
+    pub records: usize,
+    /// The estimated bytes of the records.
+    pub bytes: usize,
+}
+
+impl Volume {
+    /// Adds a message of `records` records, of `bytes` bytes in total.
+    fn add(self, records: usize, bytes: usize) -> Self {
+        Volume {
+            messages: self.messages + 1,
+            records: self.records + records,
+            bytes: self.bytes + bytes,
+        }
+    }
+}
+
+/// A snapshot of the counts of a channel at a worker.
+#[derive(Debug, Clone, Copy, Default, Hash, Eq, PartialEq)]
+pub struct ChannelCounts {
+    /// Worker-unique identifier of the channel, linkable to the identifiers of `Graph` and the logged events.
+    pub channel: usize,
+    /// The volume sent by the worker along the channel, counting broadcast messages once for each recipient.
+    pub sent: Volume,
+    /// The volume received by the worker from the channel.
+    pub received: Volume,
+}
+
+/// The counts of a channel, shared by its pushers and pullers at a worker.
+#[derive(Debug, Clone, Default)]
+pub struct ChannelCounter {
+    sent: Rc<Cell<Volume>>,
+    received: Rc<Cell<Volume>>,
+}
+
+impl ChannelCounter {
+    /// Allocates zeroed counts.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The volume sent by the worker.
+    pub fn sent(&self) -> Volume {
+        self.sent.get()
+    }
+
+    /// The volume received by the worker.
+    pub fn received(&self) -> Volume {
+        self.received.get()
+    }
+
+    /// A snapshot of the counts, for the channel identified by `channel`.
+    pub fn counts(&self, channel: usize) -> ChannelCounts {
+        ChannelCounts { channel, sent: self.sent(), received: self.received() }
+    }
+
+    /// Counts a message of `records` records of type `D` as sent.
+    pub(crate) fn send<D>(&self, records: usize) {
+        self.sent.set(self.sent.get().add(records, records * ::std::mem::size_of::<D>()));
+    }
+
+    /// Counts a message of `records` records of type `D` as received.
+    pub(crate) fn receive<D>(&self, records: usize) {
+        self.received.set(self.received.get().add(records, records * ::std::mem::size_of::<D>()));
+    }
+}
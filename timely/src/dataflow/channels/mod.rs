@@ -1,6 +1,8 @@
 //! Structured communication between timely dataflow operators.
 
 use crate::communication::Push;
+#[cfg(feature = "markers")]
+use crate::dataflow::markers::Marker;
 
 /// A collection of types that may be pushed at.
 pub mod pushers;
@@ -8,6 +10,8 @@ pub mod pushers;
 pub mod pullers;
 /// Parallelization contracts, describing how data must be exchanged between operators.
 pub mod pact;
+/// Cumulative counts of the volume moved by channels.
+pub mod counts;
 
 /// The input to and output from timely dataflow communication channels.
 pub type Bundle<T, D> = crate::communication::Message<Message<T, D>>;
@@ -23,6 +27,9 @@ pub struct Message<T, D> {
     pub from: usize,
     /// A sequence number for this worker-to-worker stream.
     pub seq: usize,
+    /// Latency markers travelling with the data.
+    #[cfg(feature = "markers")]
+    pub markers: Vec<Marker>,
 }
 
 impl<T, D> Message<T, D> {
@@ -33,15 +40,50 @@ impl<T, D> Message<T, D> {
 
     /// Creates a new message instance from arguments.
     pub fn new(time: T, data: Vec<D>, from: usize, seq: usize) -> Self {
-        Message { time, data, from, seq }
+        Message {
+            time,
+            data,
+            from,
+            seq,
+            #[cfg(feature = "markers")]
+            markers: Vec::new(),
+        }
     }
 
     /// Forms a message, and pushes contents at `pusher`.
     #[inline]
     pub fn push_at<P: Push<Bundle<T, D>>>(buffer: &mut Vec<D>, time: T, pusher: &mut P) {
+        Self::push_at_capacity(buffer, time, pusher, Self::default_length());
+    }
+
+    /// Forms a message, pushes contents at `pusher`, and leaves `buffer` with `capacity`.
+    #[inline]
+    pub fn push_at_capacity<P: Push<Bundle<T, D>>>(buffer: &mut Vec<D>, time: T, pusher: &mut P, capacity: usize) {
+        let data = ::std::mem::replace(buffer, Vec::new());
+        Self::push_message_at_capacity(buffer, Message::new(time, data, 0, 0), pusher, capacity);
+    }
+
+    /// Forms a message carrying `markers`, and pushes contents at `pusher`.
+    #[cfg(feature = "markers")]
+    #[inline]
+    pub fn push_marked_at<P: Push<Bundle<T, D>>>(buffer: &mut Vec<D>, markers: Vec<Marker>, time: T, pusher: &mut P) {
+        Self::push_marked_at_capacity(buffer, markers, time, pusher, Self::default_length());
+    }
 
+    /// Forms a message carrying `markers`, pushes contents at `pusher`, and leaves `buffer` with `capacity`.
+    #[cfg(feature = "markers")]
+    #[inline]
+    pub fn push_marked_at_capacity<P: Push<Bundle<T, D>>>(buffer: &mut Vec<D>, markers: Vec<Marker>, time: T, pusher: &mut P, capacity: usize) {
         let data = ::std::mem::replace(buffer, Vec::new());
-        let message = Message::new(time, data, 0, 0);
+        let mut message = Message::new(time, data, 0, 0);
+        message.markers = markers;
+        Self::push_message_at_capacity(buffer, message, pusher, capacity);
+    }
+
+    // Pushes `message`, whose contents were taken from `buffer`, and leaves `buffer` with `capacity`.
+    #[inline]
+    fn push_message_at_capacity<P: Push<Bundle<T, D>>>(buffer: &mut Vec<D>, message: Message<T, D>, pusher: &mut P, capacity: usize) {
+
         let mut bundle = Some(Bundle::from_typed(message));
 
         pusher.push(&mut bundle);
@@ -54,7 +96,7 @@ impl<T, D> Message<T, D> {
         }
 
         // TODO: Unclear we always want this here.
-        if buffer.capacity() != Self::default_length() {
-            *buffer = Vec::with_capacity(Self::default_length());
+        if buffer.capacity() != capacity {
+            *buffer = Vec::with_capacity(capacity);
         }
     }}
@@ -8,12 +8,18 @@
 //! The progress tracking logic assumes that this number is independent of the pact used.
 
 use std::marker::PhantomData;
+use std::rc::Rc;
+use std::cell::Cell;
 
 use crate::communication::{Push, Pull, Data};
 use crate::communication::allocator::thread::{ThreadPusher, ThreadPuller};
 
 use crate::worker::AsWorker;
+use crate::logging::{EventKind, LogInterest};
+use crate::dataflow::memory::MemoryAccount;
+use crate::dataflow::channels::counts::ChannelCounter;
 use crate::dataflow::channels::pushers::Exchange as ExchangePusher;
+use crate::dataflow::channels::pushers::Broadcast as BroadcastPusher;
 use super::{Bundle, Message};
 
 use crate::logging::TimelyLogger as Logger;
@@ -37,21 +43,54 @@ impl<T: 'static, D: 'static> ParallelizationContract<T, D> for Pipeline {
         let (pusher, puller) = allocator.pipeline::<Message<T, D>>(identifier, address);
         // // ignore `&mut A` and use thread allocator
         // let (pusher, puller) = Thread::new::<Bundle<T, D>>();
-        (LogPusher::new(pusher, allocator.index(), allocator.index(), identifier, logging.clone()),
-         LogPuller::new(puller, allocator.index(), identifier, logging.clone()))
+        let buffered = buffered(allocator, address);
+        let memory = allocator.memory_account(address);
+        let counts = allocator.channel_counter(identifier);
+        (LogPusher::new(pusher, allocator.index(), allocator.index(), identifier, logging.clone()).with_buffered(buffered.clone()).with_memory(memory.clone()).with_counts(counts.clone()),
+         LogPuller::new(puller, allocator.index(), identifier, logging.clone()).with_buffered(buffered).with_memory(memory).with_counts(counts).with_undrained(undrained(allocator, address)))
     }
 }
 
 /// An exchange between multiple observers by data
-pub struct Exchange<D, F: FnMut(&D)->u64+'static> { hash_func: F, phantom: PhantomData<D>, }
+pub struct Exchange<D, F: FnMut(&D)->u64+'static> { hash_func: F, capacity: Option<usize>, phantom: PhantomData<D>, }
 impl<D, F: FnMut(&D)->u64> Exchange<D, F> {
     /// Allocates a new `Exchange` pact from a distribution function.
     pub fn new(func: F) -> Exchange<D, F> {
         Exchange {
             hash_func:  func,
+            capacity:   None,
             phantom:    PhantomData,
         }
     }
+    /// Sets the number of records buffered for each worker before they are sent.
+    ///
+    /// The capacity defaults to that of the worker, set by `Worker::set_exchange_capacity`.
+    ///
+    /// # Examples
+    /// ```
+    /// use timely::dataflow::operators::{ToStream, Inspect};
+    /// use timely::dataflow::operators::generic::operator::Operator;
+    /// use timely::dataflow::channels::pact::Exchange;
+    ///
+    /// timely::execute(timely::Configuration::Process(2), |worker| {
+    ///     worker.dataflow::<usize,_,_>(|scope| {
+    ///         (0..100u64)
+    ///             .to_stream(scope)
+    ///             .unary(Exchange::new(|x: &u64| *x).with_capacity(8), "Small", |_,_| move |input, output| {
+    ///                 input.for_each(|time, data| {
+    ///                     assert!(data.len() <= 8);
+    ///                     output.session(&time).give_vec(&mut data.replace(Vec::new()));
+    ///                 });
+    ///             })
+    ///             .inspect(|x| println!("seen: {:?}", x));
+    ///     });
+    /// }).unwrap();
+    /// ```
+    pub fn with_capacity(mut self, capacity: usize) -> Self {
+        assert!(capacity > 0, "Exchange capacity must be positive");
+        self.capacity = Some(capacity);
+        self
+    }
 }
 
 // Exchange uses a `Box<Pushable>` because it cannot know what type of pushable will return from the allocator.
@@ -62,8 +101,184 @@ impl<T: Eq+Data+Clone, D: Data+Clone, F: FnMut(&D)->u64+'static> Parallelization
     type Puller = Box<dyn Pull<Bundle<T, D>>>;
     fn connect<A: AsWorker>(mut self, allocator: &mut A, identifier: usize, address: &[usize], logging: Option<Logger>) -> (Self::Pusher, Self::Puller) {
         let (senders, receiver) = allocator.allocate::<Message<T, D>>(identifier, address);
-        let senders = senders.into_iter().enumerate().map(|(i,x)| LogPusher::new(x, allocator.index(), i, identifier, logging.clone())).collect::<Vec<_>>();
-        (Box::new(ExchangePusher::new(senders, move |_, d| (self.hash_func)(d))), Box::new(LogPuller::new(receiver, allocator.index(), identifier, logging.clone())))
+        let buffered = buffered(allocator, address);
+        let memory = allocator.memory_account(address);
+        let counts = allocator.channel_counter(identifier);
+        let senders = senders.into_iter().enumerate().map(|(i,x)| LogPusher::new(x, allocator.index(), i, identifier, logging.clone()).with_buffered(buffered.clone()).with_memory(memory.clone()).with_counts(counts.clone())).collect::<Vec<_>>();
+        let capacity = exchange_capacity::<T, D, A>(self.capacity, allocator);
+        (Box::new(ExchangePusher::with_capacity(senders, capacity, move |_, d| (self.hash_func)(d))), Box::new(LogPuller::new(receiver, allocator.index(), identifier, logging.clone()).with_buffered(buffered).with_memory(memory).with_counts(counts).with_undrained(undrained(allocator, address))))
+    }
+}
+
+/// An exchange between multiple observers by data, using consistent hashing.
+///
+/// Each worker owns the arcs of a ring of hash values preceding its virtual nodes. When the
+/// number of workers changes, only the keys on arcs whose owner changes move, in expectation
+/// a fraction of keys proportional to the change in the number of workers. With the `Exchange`
+/// pact, which partitions hashes by remainder, most keys would move.
+///
+/// # Examples
+/// ```
+/// use timely::dataflow::operators::{ToStream, Inspect};
+/// use timely::dataflow::operators::generic::operator::Operator;
+/// use timely::dataflow::channels::pact::ConsistentExchange;
+///
+/// timely::execute_from_args(::std::env::args(), |worker| {
+///     let index = worker.index();
+///     let peers = worker.peers();
+///     worker.dataflow::<usize,_,_>(|scope| {
+///         let pact = ConsistentExchange::new(|x: &u64| *x);
+///         // The ring with which the pact routes records, to compute ownership of keys.
+///         let ring = pact.ring(peers);
+///         (0..10u64)
+///             .to_stream(scope)
+///             .unary(pact, "Owned", |_,_| move |input, output| {
+///                 input.for_each(|time, data| {
+///                     output.session(&time).give_iterator(data.iter().cloned());
+///                 });
+///             })
+///             .inspect(move |x| assert_eq!(ring.owner(*x), index));
+///     });
+/// }).unwrap();
+/// ```
+pub struct ConsistentExchange<D, F: FnMut(&D)->u64+'static> { hash_func: F, virtual_nodes: usize, capacity: Option<usize>, phantom: PhantomData<D>, }
+impl<D, F: FnMut(&D)->u64> ConsistentExchange<D, F> {
+    /// Allocates a new `ConsistentExchange` pact from a distribution function.
+    pub fn new(func: F) -> ConsistentExchange<D, F> {
+        Self::with_virtual_nodes(Ring::DEFAULT_VIRTUAL_NODES, func)
+    }
+    /// Allocates a new `ConsistentExchange` pact with `virtual_nodes` ring positions for each worker.
+    ///
+    /// More virtual nodes balance keys among workers more evenly, at the expense of memory.
+    pub fn with_virtual_nodes(virtual_nodes: usize, func: F) -> ConsistentExchange<D, F> {
+        ConsistentExchange {
+            hash_func:  func,
+            virtual_nodes,
+            capacity:   None,
+            phantom:    PhantomData,
+        }
+    }
+    /// Sets the number of records buffered for each worker before they are sent, as for `Exchange::with_capacity`.
+    pub fn with_capacity(mut self, capacity: usize) -> Self {
+        assert!(capacity > 0, "Exchange capacity must be positive");
+        self.capacity = Some(capacity);
+        self
+    }
+    /// The ring by which the pact distributes records among `workers` workers.
+    pub fn ring(&self, workers: usize) -> Ring {
+        Ring::new(workers, self.virtual_nodes)
+    }
+}
+
+impl<T: Eq+Data+Clone, D: Data+Clone, F: FnMut(&D)->u64+'static> ParallelizationContract<T, D> for ConsistentExchange<D, F> {
+    type Pusher = Box<dyn Push<Bundle<T, D>>>;
+    type Puller = Box<dyn Pull<Bundle<T, D>>>;
+    fn connect<A: AsWorker>(mut self, allocator: &mut A, identifier: usize, address: &[usize], logging: Option<Logger>) -> (Self::Pusher, Self::Puller) {
+        let ring = self.ring(allocator.peers());
+        let (senders, receiver) = allocator.allocate::<Message<T, D>>(identifier, address);
+        let buffered = buffered(allocator, address);
+        let memory = allocator.memory_account(address);
+        let counts = allocator.channel_counter(identifier);
+        let senders = senders.into_iter().enumerate().map(|(i,x)| LogPusher::new(x, allocator.index(), i, identifier, logging.clone()).with_buffered(buffered.clone()).with_memory(memory.clone()).with_counts(counts.clone())).collect::<Vec<_>>();
+        // The owner is less than the number of workers, and so is unchanged by the pusher's partitioning.
+        let capacity = exchange_capacity::<T, D, A>(self.capacity, allocator);
+        (Box::new(ExchangePusher::with_capacity(senders, capacity, move |_, d| ring.owner((self.hash_func)(d)) as u64)), Box::new(LogPuller::new(receiver, allocator.index(), identifier, logging.clone()).with_buffered(buffered).with_memory(memory).with_counts(counts).with_undrained(undrained(allocator, address))))
+    }
+}
+
+/// The capacity of the buffers of an exchange, as set for the channel, or else for the worker, or else by default.
+fn exchange_capacity<T, D, A: AsWorker>(capacity: Option<usize>, allocator: &A) -> usize {
+    capacity.or_else(|| allocator.exchange_capacity()).unwrap_or_else(Message::<T, D>::default_length)
+}
+
+/// A consistent hashing ring, assigning hash values to workers.
+///
+/// Each worker has a number of virtual nodes at pseudo-random positions on the ring, determined
+/// only by the worker index and the number of virtual nodes, and so the same in all processes.
+/// A hash value belongs to the worker with the first virtual node at or after it, wrapping around.
+///
+/// # Examples
+/// ```
+/// use timely::dataflow::channels::pact::Ring;
+///
+/// let before = Ring::new(4, 128);
+/// let after = Ring::new(5, 128);
+///
+/// // Adding a fifth worker moves only the keys it comes to own.
+/// let moved = (0..10_000u64).filter(|key| before.owner(*key) != after.owner(*key)).count();
+/// assert!((0..10_000u64).all(|key| before.owner(key) == after.owner(key) || after.owner(key) == 4));
+/// assert!(moved < 3_500);
+/// ```
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Ring {
+    /// Positions of virtual nodes, sorted, and the workers that own them.
+    nodes: Vec<(u64, usize)>,
+    workers: usize,
+}
+
+impl Ring {
+    /// The number of virtual nodes for each worker used by `ConsistentExchange::new`.
+    pub const DEFAULT_VIRTUAL_NODES: usize = 128;
+
+    /// Constructs a ring for `workers` workers, each with `virtual_nodes` virtual nodes.
+    ///
+    /// # Panics
+    /// Panics if either `workers` or `virtual_nodes` is zero.
+    pub fn new(workers: usize, virtual_nodes: usize) -> Ring {
+        assert!(workers > 0, "Ring::new(): no workers");
+        assert!(virtual_nodes > 0, "Ring::new(): no virtual nodes");
+        let mut nodes = Vec::with_capacity(workers * virtual_nodes);
+        for worker in 0 .. workers {
+            for node in 0 .. virtual_nodes {
+                nodes.push((mix(((worker as u64) << 32) | node as u64), worker));
+            }
+        }
+        nodes.sort();
+        Ring { nodes, workers }
+    }
+    /// The number of workers among which the ring distributes hash values.
+    pub fn workers(&self) -> usize {
+        self.workers
+    }
+    /// The worker owning `hash`.
+    ///
+    /// Hash values are mixed before they are placed on the ring, so that nearby values, for
+    /// example those of small integer keys, are distributed among workers.
+    pub fn owner(&self, hash: u64) -> usize {
+        let position = mix(hash);
+        let index = match self.nodes.binary_search_by(|&(node, _)| node.cmp(&position)) {
+            Ok(index) => index,
+            Err(index) => index,
+        };
+        self.nodes[index % self.nodes.len()].1
+    }
+}
+
+/// The finalizer of SplitMix64, spreading similar values across the full range of `u64`.
+fn mix(mut x: u64) -> u64 {
+    x = x.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    x = (x ^ (x >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    x = (x ^ (x >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    x ^ (x >> 31)
+}
+
+/// A broadcast to all observers, sharing each batch of data among them
+///
+/// Each batch is shared among workers of the same process without cloning, and is serialized
+/// from the shared batch for workers of other processes. Workers observe shared batches by
+/// reference, and clone them only if they require ownership.
+pub struct Broadcast;
+
+impl<T: Eq+Data+Clone, D: Data+Clone> ParallelizationContract<T, D> for Broadcast {
+    type Pusher = BroadcastPusher<T, D, Box<dyn Push<Bundle<T, D>>>>;
+    type Puller = Box<dyn Pull<Bundle<T, D>>>;
+    fn connect<A: AsWorker>(self, allocator: &mut A, identifier: usize, address: &[usize], logging: Option<Logger>) -> (Self::Pusher, Self::Puller) {
+        let (senders, receiver) = allocator.allocate::<Message<T, D>>(identifier, address);
+        let buffered = buffered(allocator, address);
+        let memory = allocator.memory_account(address);
+        let counts = allocator.channel_counter(identifier);
+        let senders = senders.into_iter().enumerate().map(|(i,x)| Box::new(LogPusher::new(x, allocator.index(), i, identifier, logging.clone()).with_buffered(buffered.clone()).with_memory(memory.clone()).with_counts(counts.clone())) as Box<dyn Push<Bundle<T, D>>>).collect::<Vec<_>>();
+        (BroadcastPusher::new(senders, allocator.index()), Box::new(LogPuller::new(receiver, allocator.index(), identifier, logging.clone()).with_buffered(buffered).with_memory(memory).with_counts(counts).with_undrained(undrained(allocator, address))))
     }
 }
 
@@ -90,6 +305,16 @@ impl<T: Eq+Data+Clone, D: Data+Clone, F: FnMut(&D)->u64+'static> Parallelization
 // }
 
 
+/// The count of bytes buffered in the channels of the dataflow containing `address`, if kept.
+fn buffered<A: AsWorker>(allocator: &A, address: &[usize]) -> Option<Rc<Cell<i64>>> {
+    address.first().and_then(|dataflow| allocator.buffered_counter(*dataflow))
+}
+
+/// The count of inputs left with messages remaining, shared among the inputs of the operator at `address`.
+fn undrained<A: AsWorker>(allocator: &A, address: &[usize]) -> Rc<Cell<usize>> {
+    allocator.activations().borrow().saturation().undrained_inputs(address)
+}
+
 /// Wraps a `Message<T,D>` pusher to provide a `Push<(T, Content<D>)>`.
 pub struct LogPusher<T, D, P: Push<Bundle<T, D>>> {
     pusher: P,
@@ -99,6 +324,9 @@ pub struct LogPusher<T, D, P: Push<Bundle<T, D>>> {
     target: usize,
     phantom: ::std::marker::PhantomData<(T, D)>,
     logging: Option<Logger>,
+    buffered: Option<Rc<Cell<i64>>>,
+    memory: Option<MemoryAccount>,
+    counts: Option<ChannelCounter>,
 }
 impl<T, D, P: Push<Bundle<T, D>>> LogPusher<T, D, P> {
     /// Allocates a new pusher.
@@ -111,7 +339,33 @@ impl<T, D, P: Push<Bundle<T, D>>> LogPusher<T, D, P> {
             target,
             phantom: ::std::marker::PhantomData,
             logging,
+            buffered: None,
+            memory: None,
+            counts: None,
+        }
+    }
+    /// Adds the bytes of each record sent to this worker to `buffered`.
+    ///
+    /// Records sent to other workers are discharged by their recipients, and so not charged.
+    pub(crate) fn with_buffered(mut self, buffered: Option<Rc<Cell<i64>>>) -> Self {
+        if self.source == self.target {
+            self.buffered = buffered;
         }
+        self
+    }
+    /// Counts the bytes of each record sent to this worker as queued in `memory`.
+    ///
+    /// Records sent to other workers are accounted by their recipients, if at all.
+    pub(crate) fn with_memory(mut self, memory: Option<MemoryAccount>) -> Self {
+        if self.source == self.target {
+            self.memory = memory;
+        }
+        self
+    }
+    /// Counts each sent message in `counts`.
+    pub(crate) fn with_counts(mut self, counts: Option<ChannelCounter>) -> Self {
+        self.counts = counts;
+        self
     }
 }
 
@@ -127,14 +381,24 @@ impl<T, D, P: Push<Bundle<T, D>>> Push<Bundle<T, D>> for LogPusher<T, D, P> {
                 message.from = self.source;
             }
 
-            self.logging.as_ref().map(|l| l.log(crate::logging::MessagesEvent {
+            self.logging.as_ref().filter(|l| l.interested_in_channel(EventKind::Messages, self.channel)).map(|l| l.log(crate::logging::MessagesEvent {
                 is_send: true,
                 channel: self.channel,
                 source: self.source,
                 target: self.target,
                 seq_no: self.counter-1,
                 length: bundle.data.len(),
+                sample_rate: 1,
             }));
+            if let Some(buffered) = self.buffered.as_ref() {
+                buffered.set(buffered.get() + (bundle.data.len() * ::std::mem::size_of::<D>()) as i64);
+            }
+            if let Some(memory) = self.memory.as_ref() {
+                memory.enqueue(bundle.data.len() * ::std::mem::size_of::<D>());
+            }
+            if let Some(counts) = self.counts.as_ref() {
+                counts.send::<D>(bundle.data.len());
+            }
         }
         self.pusher.push(pair);
     }
@@ -147,6 +411,11 @@ pub struct LogPuller<T, D, P: Pull<Bundle<T, D>>> {
     index: usize,
     phantom: ::std::marker::PhantomData<(T, D)>,
     logging: Option<Logger>,
+    buffered: Option<Rc<Cell<i64>>>,
+    memory: Option<MemoryAccount>,
+    counts: Option<ChannelCounter>,
+    undrained: Option<Rc<Cell<usize>>>,
+    remaining: bool,
 }
 impl<T, D, P: Pull<Bundle<T, D>>> LogPuller<T, D, P> {
     /// Allocates a new `Puller`.
@@ -157,8 +426,33 @@ impl<T, D, P: Pull<Bundle<T, D>>> LogPuller<T, D, P> {
             index,
             phantom: ::std::marker::PhantomData,
             logging,
+            buffered: None,
+            memory: None,
+            counts: None,
+            undrained: None,
+            remaining: false,
         }
     }
+    /// Subtracts the bytes of each record received from this worker from `buffered`.
+    pub(crate) fn with_buffered(mut self, buffered: Option<Rc<Cell<i64>>>) -> Self {
+        self.buffered = buffered;
+        self
+    }
+    /// Counts the bytes of each record received from this worker as no longer queued in `memory`.
+    pub(crate) fn with_memory(mut self, memory: Option<MemoryAccount>) -> Self {
+        self.memory = memory;
+        self
+    }
+    /// Counts each received message in `counts`.
+    pub(crate) fn with_counts(mut self, counts: Option<ChannelCounter>) -> Self {
+        self.counts = counts;
+        self
+    }
+    /// Counts the puller in `undrained` while it was last read without being found empty.
+    pub(crate) fn with_undrained(mut self, undrained: Rc<Cell<usize>>) -> Self {
+        self.undrained = Some(undrained);
+        self
+    }
 }
 
 impl<T, D, P: Pull<Bundle<T, D>>> Pull<Bundle<T, D>> for LogPuller<T, D, P> {
@@ -168,15 +462,40 @@ impl<T, D, P: Pull<Bundle<T, D>>> Pull<Bundle<T, D>> for LogPuller<T, D, P> {
         if let Some(bundle) = result {
             let channel = self.channel;
             let target = self.index;
-            self.logging.as_ref().map(|l| l.log(crate::logging::MessagesEvent {
+            self.logging.as_ref().filter(|l| l.interested_in_channel(EventKind::Messages, channel)).map(|l| l.log(crate::logging::MessagesEvent {
                 is_send: false,
                 channel,
                 source: bundle.from,
                 target,
                 seq_no: bundle.seq,
                 length: bundle.data.len(),
+                sample_rate: 1,
             }));
+            if let (Some(buffered), true) = (self.buffered.as_ref(), bundle.from == target) {
+                buffered.set(buffered.get() - (bundle.data.len() * ::std::mem::size_of::<D>()) as i64);
+            }
+            if let (Some(memory), true) = (self.memory.as_ref(), bundle.from == target) {
+                memory.dequeue(bundle.data.len() * ::std::mem::size_of::<D>());
+            }
+            if let Some(counts) = self.counts.as_ref() {
+                counts.receive::<D>(bundle.data.len());
+            }
+        }
+        if let Some(undrained) = self.undrained.as_ref() {
+            let remaining = result.is_some();
+            if remaining != self.remaining {
+                self.remaining = remaining;
+                undrained.set(if remaining { undrained.get() + 1 } else { undrained.get() - 1 });
+            }
         }
         result
     }
 }
+
+impl<T, D, P: Pull<Bundle<T, D>>> Drop for LogPuller<T, D, P> {
+    fn drop(&mut self) {
+        if let (true, Some(undrained)) = (self.remaining, self.undrained.as_ref()) {
+            undrained.set(undrained.get() - 1);
+        }
+    }
+}
@@ -4,6 +4,8 @@ use std::rc::Rc;
 use std::cell::RefCell;
 
 use crate::dataflow::channels::Bundle;
+#[cfg(feature = "markers")]
+use crate::dataflow::markers::MarkerCarrier;
 use crate::progress::ChangeBatch;
 use crate::communication::Pull;
 
@@ -11,11 +13,18 @@ use crate::communication::Pull;
 pub struct Counter<T: Ord+Clone+'static, D, P: Pull<Bundle<T, D>>> {
     pullable: P,
     consumed: Rc<RefCell<ChangeBatch<T>>>,
+    // Receives the markers of pulled messages.
+    #[cfg(feature = "markers")]
+    markers: Option<MarkerCarrier<T>>,
+    // The message last returned, taken from `pullable` to pass over messages without records.
+    #[cfg(feature = "markers")]
+    current: Option<Bundle<T, D>>,
     phantom: ::std::marker::PhantomData<D>,
 }
 
 impl<T:Ord+Clone+'static, D, P: Pull<Bundle<T, D>>> Counter<T, D, P> {
     /// Retrieves the next timestamp and batch of data.
+    #[cfg(not(feature = "markers"))]
     #[inline]
     pub fn next(&mut self) -> Option<&mut Bundle<T, D>> {
         if let Some(message) = self.pullable.pull() {
@@ -27,6 +36,26 @@ impl<T:Ord+Clone+'static, D, P: Pull<Bundle<T, D>>> Counter<T, D, P> {
         }
         else { None }
     }
+
+    /// Retrieves the next timestamp and batch of data.
+    ///
+    /// Messages without records, which carry only markers, are passed over once their markers
+    /// are received.
+    #[cfg(feature = "markers")]
+    #[inline]
+    pub fn next(&mut self) -> Option<&mut Bundle<T, D>> {
+        loop {
+            let message = self.pullable.pull().take()?;
+            if let (Some(carrier), false) = (self.markers.as_ref(), message.markers.is_empty()) {
+                carrier.receive(&message.time, &message.markers[..]);
+            }
+            if message.data.len() > 0 {
+                self.consumed.borrow_mut().update(message.time.clone(), message.data.len() as i64);
+                self.current = Some(message);
+                return self.current.as_mut();
+            }
+        }
+    }
 }
 
 impl<T:Ord+Clone+'static, D, P: Pull<Bundle<T, D>>> Counter<T, D, P> {
@@ -36,8 +65,18 @@ impl<T:Ord+Clone+'static, D, P: Pull<Bundle<T, D>>> Counter<T, D, P> {
             phantom: ::std::marker::PhantomData,
             pullable,
             consumed: Rc::new(RefCell::new(ChangeBatch::new())),
+            #[cfg(feature = "markers")]
+            markers: None,
+            #[cfg(feature = "markers")]
+            current: None,
         }
     }
+    /// Passes the markers of pulled messages to `carrier`.
+    #[cfg(feature = "markers")]
+    pub(crate) fn with_markers(mut self, carrier: MarkerCarrier<T>) -> Self {
+        self.markers = Some(carrier);
+        self
+    }
     /// A references to shared changes in counts, for cloning or draining.
     pub fn consumed(&self) -> &Rc<RefCell<ChangeBatch<T>>> {
         &self.consumed
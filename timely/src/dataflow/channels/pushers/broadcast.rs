@@ -0,0 +1,59 @@
+//! The broadcast pattern shares each pushed bundle among all target pushees.
+
+use std::sync::Arc;
+use std::marker::PhantomData;
+
+use crate::communication::Push;
+use crate::dataflow::channels::Bundle;
+
+/// Shares each pushed bundle among all target pushees, rather than cloning it for each.
+///
+/// Each bundle is wrapped once in an `Arc`, and a reference to it is pushed to each target.
+/// Targets within the process observe the shared bundle by reference, and targets in other
+/// processes serialize it directly from the shared bundle.
+pub struct Broadcast<T, D, P: Push<Bundle<T, D>>> {
+    pushers: Vec<P>,
+    source: usize,
+    counter: usize,
+    phantom: PhantomData<(T, D)>,
+}
+
+impl<T, D, P: Push<Bundle<T, D>>> Broadcast<T, D, P> {
+    /// Allocates a new `Broadcast` from a supplied set of pushers, on behalf of worker `source`.
+    pub fn new(pushers: Vec<P>, source: usize) -> Broadcast<T, D, P> {
+        Broadcast {
+            pushers,
+            source,
+            counter: 0,
+            phantom: PhantomData,
+        }
+    }
+}
+
+impl<T: Clone, D: Clone, P: Push<Bundle<T, D>>> Push<Bundle<T, D>> for Broadcast<T, D, P> {
+    #[inline]
+    fn push(&mut self, message: &mut Option<Bundle<T, D>>) {
+        // if only one pusher, nothing to share
+        if self.pushers.len() == 1 {
+            self.pushers[0].push(message);
+        }
+        else if let Some(mut bundle) = message.take() {
+            // Stamp the sequence number and source, which targets cannot do once shared.
+            if let Some(message) = bundle.if_mut() {
+                message.seq = self.counter;
+                message.from = self.source;
+            }
+            self.counter += 1;
+
+            let shared = Arc::new(bundle.into_typed());
+            for pusher in self.pushers.iter_mut() {
+                pusher.push(&mut Some(Bundle::from_arc(shared.clone())));
+            }
+        }
+        else {
+            for pusher in self.pushers.iter_mut() {
+                pusher.push(&mut None);
+            }
+        }
+    }
+}
@@ -5,6 +5,9 @@ use crate::dataflow::channels::{Bundle, Message};
 use crate::progress::Timestamp;
 use crate::dataflow::operators::Capability;
 use crate::communication::Push;
+use crate::dataflow::memory::MemoryAccount;
+#[cfg(feature = "markers")]
+use crate::dataflow::markers::{Marker, Pending};
 
 /// Buffers data sent at the same time, for efficient communication.
 ///
@@ -13,20 +16,53 @@ use crate::communication::Push;
 pub struct Buffer<T, D, P: Push<Bundle<T, D>>> {
     time: Option<T>,  // the currently open time, if it is open
     buffer: Vec<D>,   // a buffer for records, to send at self.time
+    capacity: usize,  // the number of records at which the buffer is flushed
     pusher: P,
+    memory: Option<MemoryAccount>,  // the account charged with the bytes of `buffer`
+    accounted: usize, // the bytes of `buffer` charged to `memory`
+    #[cfg(feature = "markers")]
+    markers: Option<Pending>,  // markers to send with the next message
 }
 
 impl<T, D, P: Push<Bundle<T, D>>> Buffer<T, D, P> where T: Eq+Clone {
 
     /// Creates a new `Buffer`.
     pub fn new(pusher: P) -> Buffer<T, D, P> {
+        Self::with_capacity(pusher, Message::<T, D>::default_length())
+    }
+
+    /// Creates a new `Buffer` which flushes once it holds `capacity` records.
+    pub fn with_capacity(pusher: P, capacity: usize) -> Buffer<T, D, P> {
+        assert!(capacity > 0, "Buffer capacity must be positive");
         Buffer {
             time: None,
-            buffer: Vec::with_capacity(Message::<T, D>::default_length()),
+            buffer: Vec::with_capacity(capacity),
+            capacity,
             pusher,
+            memory: None,
+            accounted: 0,
+            #[cfg(feature = "markers")]
+            markers: None,
         }
     }
 
+    /// Charges the bytes allocated by the buffer to `memory`, as the producing operator's.
+    pub(crate) fn with_memory(mut self, memory: Option<MemoryAccount>) -> Buffer<T, D, P> {
+        self.memory = memory;
+        self.account();
+        self
+    }
+
+    /// Sends the markers pending in `markers` with the next message.
+    #[cfg(feature = "markers")]
+    pub(crate) fn with_markers(mut self, markers: Pending) -> Buffer<T, D, P> {
+        self.markers = Some(markers);
+        self
+    }
+
+    /// The number of records at which the buffer is flushed.
+    pub fn capacity(&self) -> usize { self.capacity }
+
     /// Returns a `Session`, which accepts data to send at the associated time
     pub fn session(&mut self, time: &T) -> Session<T, D, P> {
         if let Some(true) = self.time.as_ref().map(|x| x != time) { self.flush(); }
@@ -58,7 +94,31 @@ impl<T, D, P: Push<Bundle<T, D>>> Buffer<T, D, P> where T: Eq+Clone {
     fn flush(&mut self) {
         if !self.buffer.is_empty() {
             let time = self.time.as_ref().unwrap().clone();
-            Message::push_at(&mut self.buffer, time, &mut self.pusher);
+            #[cfg(feature = "markers")]
+            {
+                let markers = self.take_markers();
+                Message::push_marked_at_capacity(&mut self.buffer, markers, time, &mut self.pusher, self.capacity);
+            }
+            #[cfg(not(feature = "markers"))]
+            Message::push_at_capacity(&mut self.buffer, time, &mut self.pusher, self.capacity);
+            self.account();
+        }
+    }
+
+    // The markers to send with the next message.
+    #[cfg(feature = "markers")]
+    fn take_markers(&mut self) -> Vec<Marker> {
+        self.markers.as_ref().map(|markers| ::std::mem::take(&mut *markers.borrow_mut())).unwrap_or_default()
+    }
+
+    // Brings the charge to `self.memory` up to date with the allocation of `self.buffer`,
+    // which changes as flushing replaces it with recycled or newly allocated containers.
+    fn account(&mut self) {
+        if let Some(memory) = self.memory.as_ref() {
+            let bytes = self.buffer.capacity() * ::std::mem::size_of::<D>();
+            memory.allocate(bytes);
+            memory.release(self.accounted);
+            self.accounted = bytes;
         }
     }
 
@@ -66,11 +126,24 @@ impl<T, D, P: Push<Bundle<T, D>>> Buffer<T, D, P> where T: Eq+Clone {
     fn give(&mut self, data: D) {
         self.buffer.push(data);
         // assert!(self.buffer.capacity() == Message::<O::Data>::default_length());
-        if self.buffer.len() == self.buffer.capacity() {
+        if self.buffer.len() >= self.capacity {
             self.flush();
         }
     }
 
+    // internal method for use by the `Write` implementations of sessions.
+    fn give_slice(&mut self, mut data: &[D]) where D: Clone {
+        while !data.is_empty() {
+            // Fill the buffer to capacity, so that it is flushed as it would be record-at-a-time.
+            let (head, tail) = data.split_at(::std::cmp::min(self.capacity - self.buffer.len(), data.len()));
+            self.buffer.extend_from_slice(head);
+            if self.buffer.len() >= self.capacity {
+                self.flush();
+            }
+            data = tail;
+        }
+    }
+
     // Gives an entire message at a specific time.
     fn give_vec(&mut self, vector: &mut Vec<D>) {
         // flush to ensure fifo-ness
@@ -79,11 +152,25 @@ impl<T, D, P: Push<Bundle<T, D>>> Buffer<T, D, P> where T: Eq+Clone {
         }
 
         let time = self.time.as_ref().expect("Buffer::give_vec(): time is None.").clone();
+        #[cfg(feature = "markers")]
+        {
+            let markers = self.take_markers();
+            Message::push_marked_at(vector, markers, time, &mut self.pusher);
+        }
+        #[cfg(not(feature = "markers"))]
         Message::push_at(vector, time, &mut self.pusher);
     }
 }
 
 
+impl<T, D, P: Push<Bundle<T, D>>> Drop for Buffer<T, D, P> {
+    fn drop(&mut self) {
+        if let Some(memory) = self.memory.as_ref() {
+            memory.release(self.accounted);
+        }
+    }
+}
+
 /// An output session for sending records at a specified time.
 ///
 /// The `Session` struct provides the user-facing interface to an operator output, namely
@@ -111,6 +198,10 @@ impl<'a, T, D, P: Push<Bundle<T, D>>+'a> Session<'a, T, D, P>  where T: Eq+Clone
     /// The `Content` type is the backing memory for communication in timely, and it can
     /// often be more efficient to re-use this memory rather than have timely allocate
     /// new backing memory.
+    ///
+    /// On return, `message` is empty. When the consumer has returned a container from an
+    /// earlier message, `message` is that recycled container, and the operator can fill it
+    /// without allocating.
     #[inline]
     pub fn give_vec(&mut self, message: &mut Vec<D>) {
         if message.len() > 0 {
@@ -119,6 +210,49 @@ impl<'a, T, D, P: Push<Bundle<T, D>>+'a> Session<'a, T, D, P>  where T: Eq+Clone
     }
 }
 
+/// Writes bytes as records of the session, so that encoders can write directly to an output.
+///
+/// Records are sent as the buffer fills, and `flush` sends the records buffered so far.
+///
+/// # Examples
+/// ```
+/// use std::io::Write;
+/// use timely::dataflow::operators::{ToStream, Capture};
+/// use timely::dataflow::operators::generic::operator::Operator;
+/// use timely::dataflow::channels::pact::Pipeline;
+/// use timely::dataflow::operators::capture::Event;
+///
+/// let captured = timely::example(|scope| {
+///     (0..3u64).to_stream(scope)
+///              .unary(Pipeline, "Format", |_,_| |input, output| {
+///                  input.for_each(|time, data| {
+///                      let mut session = output.session(&time);
+///                      for datum in data.iter() {
+///                          writeln!(session, "record {}", datum).unwrap();
+///                      }
+///                  });
+///              })
+///              .capture()
+/// });
+///
+/// // Bytes arrive in the order they were written.
+/// let bytes = captured.try_iter().flat_map(|event| match event {
+///     Event::Messages(_, data) => data,
+///     Event::Progress(_) => Vec::new(),
+/// }).collect::<Vec<u8>>();
+/// assert_eq!(String::from_utf8(bytes).unwrap(), "record 0\nrecord 1\nrecord 2\n");
+/// ```
+impl<'a, T, P: Push<Bundle<T, u8>>+'a> ::std::io::Write for Session<'a, T, u8, P> where T: Eq+Clone+'a {
+    fn write(&mut self, buf: &[u8]) -> ::std::io::Result<usize> {
+        self.buffer.give_slice(buf);
+        Ok(buf.len())
+    }
+    fn flush(&mut self) -> ::std::io::Result<()> {
+        self.buffer.flush();
+        Ok(())
+    }
+}
+
 /// A session which will flush itself when dropped.
 pub struct AutoflushSession<'a, T: Timestamp, D, P: Push<Bundle<T, D>>+'a> where
     T: Eq+Clone+'a, D: 'a {
@@ -150,6 +284,19 @@ impl<'a, T: Timestamp, D, P: Push<Bundle<T, D>>+'a> AutoflushSession<'a, T, D, P
     }
 }
 
+/// Writes bytes as records of the session, which are flushed at the latest when the session,
+/// and with it the capability, is dropped.
+impl<'a, T: Timestamp, P: Push<Bundle<T, u8>>+'a> ::std::io::Write for AutoflushSession<'a, T, u8, P> where T: Eq+Clone+'a {
+    fn write(&mut self, buf: &[u8]) -> ::std::io::Result<usize> {
+        self.buffer.give_slice(buf);
+        Ok(buf.len())
+    }
+    fn flush(&mut self) -> ::std::io::Result<()> {
+        self.buffer.flush();
+        Ok(())
+    }
+}
+
 impl<'a, T: Timestamp, D, P: Push<Bundle<T, D>>+'a> Drop for AutoflushSession<'a, T, D, P> where T: Eq+Clone+'a, D: 'a {
     fn drop(&mut self) {
         self.buffer.cease();
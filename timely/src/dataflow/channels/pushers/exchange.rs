@@ -3,38 +3,83 @@
 use crate::Data;
 use crate::communication::Push;
 use crate::dataflow::channels::{Bundle, Message};
+#[cfg(feature = "markers")]
+use crate::dataflow::markers::{Marker, push_markers};
 
 // TODO : Software write combining
 /// Distributes records among target pushees according to a distribution function.
 pub struct Exchange<T, D, P: Push<Bundle<T, D>>, H: FnMut(&T, &D) -> u64> {
     pushers: Vec<P>,
     buffers: Vec<Vec<D>>,
+    #[cfg(feature = "markers")]
+    markers: Vec<Vec<Marker>>,  // markers to send with the next message to each pushee
+    #[cfg(feature = "markers")]
+    incoming: Vec<Marker>,      // markers of the message being routed
+    #[cfg(feature = "markers")]
+    marked: Vec<bool>,          // pushees given the incoming markers
     current: Option<T>,
     hash_func: H,
+    capacity: usize,            // the number of records at which a buffer is sent.
 }
 
 impl<T: Clone, D, P: Push<Bundle<T, D>>, H: FnMut(&T, &D)->u64>  Exchange<T, D, P, H> {
     /// Allocates a new `Exchange` from a supplied set of pushers and a distribution function.
     pub fn new(pushers: Vec<P>, key: H) -> Exchange<T, D, P, H> {
+        Self::with_capacity(pushers, Message::<T, D>::default_length(), key)
+    }
+    /// Allocates a new `Exchange` whose buffers for each pushee send once they hold `capacity` records.
+    pub fn with_capacity(pushers: Vec<P>, capacity: usize, key: H) -> Exchange<T, D, P, H> {
+        assert!(capacity > 0, "Exchange capacity must be positive");
         let mut buffers = vec![];
         for _ in 0..pushers.len() {
-            buffers.push(Vec::with_capacity(Message::<T, D>::default_length()));
+            buffers.push(Vec::with_capacity(capacity));
         }
         Exchange {
+            #[cfg(feature = "markers")]
+            markers: vec![Vec::new(); pushers.len()],
+            #[cfg(feature = "markers")]
+            incoming: Vec::new(),
+            #[cfg(feature = "markers")]
+            marked: vec![false; pushers.len()],
             pushers,
             hash_func: key,
             buffers,
             current: None,
+            capacity,
         }
     }
     #[inline]
     fn flush(&mut self, index: usize) {
-        if !self.buffers[index].is_empty() {
-            if let Some(ref time) = self.current {
-                Message::push_at(&mut self.buffers[index], time.clone(), &mut self.pushers[index]);
+        if let Some(ref time) = self.current {
+            #[cfg(feature = "markers")]
+            {
+                let markers = ::std::mem::take(&mut self.markers[index]);
+                if !self.buffers[index].is_empty() {
+                    Message::push_marked_at_capacity(&mut self.buffers[index], markers, time.clone(), &mut self.pushers[index], self.capacity);
+                }
+                else if !markers.is_empty() {
+                    push_markers(time.clone(), markers, &mut self.pushers[index]);
+                }
+            }
+            #[cfg(not(feature = "markers"))]
+            if !self.buffers[index].is_empty() {
+                Message::push_at_capacity(&mut self.buffers[index], time.clone(), &mut self.pushers[index], self.capacity);
             }
         }
     }
+    /// Buffers `datum` for the pushee at `index`, with the incoming markers if the pushee has not yet received them.
+    #[inline]
+    fn route(&mut self, index: usize, datum: D) {
+        self.buffers[index].push(datum);
+        #[cfg(feature = "markers")]
+        if !self.incoming.is_empty() && !self.marked[index] {
+            self.marked[index] = true;
+            self.markers[index].extend_from_slice(&self.incoming[..]);
+        }
+        if self.buffers[index].len() >= self.capacity {
+            self.flush(index);
+        }
+    }
 }
 
 impl<T: Eq+Data, D: Data, P: Push<Bundle<T, D>>, H: FnMut(&T, &D)->u64> Push<Bundle<T, D>> for Exchange<T, D, P, H> {
@@ -58,16 +103,24 @@ impl<T: Eq+Data, D: Data, P: Push<Bundle<T, D>>, H: FnMut(&T, &D)->u64> Push<Bun
             }
             self.current = Some(time.clone());
 
+            // Markers continue to each pushee receiving records of the message, and to every
+            // pushee from a message without records.
+            #[cfg(feature = "markers")]
+            {
+                self.incoming = ::std::mem::take(&mut message.markers);
+                if data.is_empty() {
+                    for markers in self.markers.iter_mut() {
+                        markers.extend_from_slice(&self.incoming[..]);
+                    }
+                }
+            }
+
             // if the number of pushers is a power of two, use a mask
             if (self.pushers.len() & (self.pushers.len() - 1)) == 0 {
                 let mask = (self.pushers.len() - 1) as u64;
                 for datum in data.drain(..) {
                     let index = (((self.hash_func)(time, &datum)) & mask) as usize;
-
-                    self.buffers[index].push(datum);
-                    if self.buffers[index].len() == self.buffers[index].capacity() {
-                        self.flush(index);
-                    }
+                    self.route(index, datum);
 
                     // unsafe {
                     //     self.buffers.get_unchecked_mut(index).push(datum);
@@ -82,13 +135,15 @@ impl<T: Eq+Data, D: Data, P: Push<Bundle<T, D>>, H: FnMut(&T, &D)->u64> Push<Bun
             else {
                 for datum in data.drain(..) {
                     let index = (((self.hash_func)(time, &datum)) % self.pushers.len() as u64) as usize;
-                    self.buffers[index].push(datum);
-                    if self.buffers[index].len() == self.buffers[index].capacity() {
-                        self.flush(index);
-                    }
+                    self.route(index, datum);
                 }
             }
 
+            #[cfg(feature = "markers")]
+            {
+                self.incoming.clear();
+                self.marked.iter_mut().for_each(|marked| *marked = false);
+            }
         }
         else {
             // flush
@@ -1,8 +1,10 @@
 pub use self::tee::{Tee, TeeHelper};
 pub use self::exchange::Exchange;
+pub use self::broadcast::Broadcast;
 pub use self::counter::Counter;
 
 pub mod tee;
 pub mod exchange;
+pub mod broadcast;
 pub mod counter;
 pub mod buffer;
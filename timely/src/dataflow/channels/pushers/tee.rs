@@ -9,6 +9,10 @@ use crate::dataflow::channels::{Bundle, Message};
 use crate::communication::Push;
 
 /// Wraps a shared list of `Box<Push>` to forward pushes to. Owned by `Stream`.
+///
+/// Each message is cloned for all but the last consumer, which receives the message by move.
+/// The buffer for clones is not allocated in advance, and so a stream with a single consumer,
+/// which never clones messages, never allocates it.
 pub struct Tee<T: 'static, D: 'static> {
     buffer: Vec<D>,
     shared: Rc<RefCell<Vec<Box<dyn Push<Bundle<T, D>>>>>>,
@@ -21,6 +25,9 @@ impl<T: Data, D: Data> Push<Bundle<T, D>> for Tee<T, D> {
         if let Some(message) = message {
             for index in 1..pushers.len() {
                 self.buffer.extend_from_slice(&message.data);
+                #[cfg(feature = "markers")]
+                Message::push_marked_at(&mut self.buffer, message.markers.clone(), message.time.clone(), &mut pushers[index-1]);
+                #[cfg(not(feature = "markers"))]
                 Message::push_at(&mut self.buffer, message.time.clone(), &mut pushers[index-1]);
             }
         }
@@ -41,7 +48,7 @@ impl<T, D> Tee<T, D> {
     pub fn new() -> (Tee<T, D>, TeeHelper<T, D>) {
         let shared = Rc::new(RefCell::new(Vec::new()));
         let port = Tee {
-            buffer: Vec::with_capacity(Message::<T, D>::default_length()),
+            buffer: Vec::new(),
             shared: shared.clone(),
         };
 
@@ -52,7 +59,7 @@ impl<T, D> Tee<T, D> {
 impl<T, D> Clone for Tee<T, D> {
     fn clone(&self) -> Tee<T, D> {
         Tee {
-            buffer: Vec::with_capacity(self.buffer.capacity()),
+            buffer: Vec::new(),
             shared: self.shared.clone(),
         }
     }
@@ -77,3 +84,43 @@ impl<T, D> Clone for TeeHelper<T, D> {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+
+    use std::rc::Rc;
+    use std::cell::RefCell;
+
+    use crate::communication::Push;
+    use crate::dataflow::channels::{Bundle, Message};
+    use super::Tee;
+
+    /// Data that must not be cloned.
+    struct Moved(usize);
+    impl Clone for Moved {
+        fn clone(&self) -> Self { panic!("single consumer cloned data") }
+    }
+
+    /// Records the data pushed at it.
+    struct Record(Rc<RefCell<Vec<usize>>>);
+    impl Push<Bundle<usize, Moved>> for Record {
+        fn push(&mut self, message: &mut Option<Bundle<usize, Moved>>) {
+            if let Some(message) = message.take() {
+                let message = message.if_typed().expect("single consumer received shared data");
+                self.0.borrow_mut().extend(message.data.iter().map(|x| x.0));
+            }
+        }
+    }
+
+    #[test]
+    fn single_consumer_moves() {
+        let (mut tee, helper) = Tee::<usize, Moved>::new();
+        let seen = Rc::new(RefCell::new(Vec::new()));
+        helper.add_pusher(Record(seen.clone()));
+
+        let mut buffer = vec![Moved(0), Moved(1), Moved(2)];
+        Message::push_at(&mut buffer, 0, &mut tee);
+        assert_eq!(*seen.borrow(), vec![0, 1, 2]);
+        assert_eq!(tee.buffer.capacity(), 0);
+    }
+}
@@ -0,0 +1,256 @@
+//! Descriptions of the structure of constructed dataflows.
+//!
+//! A worker records the operators and channels of the dataflows it constructs, and presents
+//! them as a `Graph` through `Worker::graph`. A graph can be rendered for Graphviz with
+//! `to_dot`, or serialized with serde, for example as JSON, for consumption by other tools.
+//!
+//! The current state of each operator, including its input frontiers, is available through
+//! `Worker::introspect`, for applications that report the status of their dataflows, and the
+//! progress traffic of each scope through `Worker::progress_stats`, to tell whether exchanging
+//! progress, rather than data, limits a computation.
+//!
+//! Operators are identified by their addresses, sequences of indices from the dataflow down.
+//! Each scope is itself an operator in its parent, and within the scope it occupies index zero,
+//! so that channels entering or leaving the scope connect to its address.
+
+use std::fmt::Write;
+use std::time::Duration;
+
+/// An operator in a dataflow, including scopes.
+#[derive(Serialize, Deserialize, Debug, Clone, Hash, Eq, PartialEq, Ord, PartialOrd)]
+pub struct Operator {
+    /// Worker-unique identifier for the operator, linkable to `OperatesEvent`.
+    pub id: usize,
+    /// Sequence of nested scope identifiers indicating the path from the root to this instance.
+    pub addr: Vec<usize>,
+    /// Names of the scopes enclosing this instance, from the dataflow down, ending with its own.
+    pub names: Vec<String>,
+}
+
+impl Operator {
+    /// The name of the operator.
+    pub fn name(&self) -> &str {
+        self.names.last().map(|x| &x[..]).unwrap_or("")
+    }
+}
+
+/// A channel between two operators in the same scope.
+#[derive(Serialize, Deserialize, Debug, Clone, Hash, Eq, PartialEq, Ord, PartialOrd)]
+pub struct Channel {
+    /// Worker-unique identifier for the channel, linkable to `ChannelsEvent`.
+    pub id: usize,
+    /// Sequence of nested scope identifiers indicating the path from the root to the scope.
+    pub scope_addr: Vec<usize>,
+    /// Source descriptor, indicating operator index and output port.
+    pub source: (usize, usize),
+    /// Target descriptor, indicating operator index and input port.
+    pub target: (usize, usize),
+    /// The parallelization contract of the channel, for example `Pipeline` or `Exchange`.
+    ///
+    /// Channels that enter or leave a scope are described as `Enter` and `Leave`.
+    pub pact: String,
+}
+
+/// The current state of an operator, as reported by `Worker::introspect`.
+#[derive(Serialize, Deserialize, Debug, Clone, Hash, Eq, PartialEq, Ord, PartialOrd)]
+pub struct OperatorStatus {
+    /// Worker-unique identifier for the operator, linkable to `OperatesEvent`.
+    pub id: usize,
+    /// Sequence of nested scope identifiers indicating the path from the root to this instance.
+    pub addr: Vec<usize>,
+    /// A helpful name.
+    pub name: String,
+    /// The frontier of each input, with timestamps formatted as strings.
+    pub frontiers: Vec<Vec<String>>,
+    /// The identifiers of the channels connected to each input.
+    ///
+    /// Channels leaving a scope connect to one of its outputs rather than to an input, and are
+    /// not listed; the channels from that output are listed at the inputs they reach.
+    pub channels: Vec<Vec<usize>>,
+    /// The records sent to each input and not yet received, by timestamp formatted as a string.
+    ///
+    /// Counts are of records across all workers, as known to this worker.
+    pub pending: Vec<Vec<(String, i64)>>,
+    /// The capabilities held for each output, by timestamp formatted as a string.
+    ///
+    /// Counts are of capabilities across all workers, as known to this worker. They may be
+    /// transiently negative, when the worker has yet to learn of capabilities another worker holds.
+    pub capabilities: Vec<Vec<(String, i64)>>,
+    /// The number of times the operator has been scheduled.
+    ///
+    /// Recent activity can be determined by comparing the counts of successive reports.
+    pub activations: usize,
+    /// The total time spent in activations of the operator.
+    ///
+    /// The time of a scope includes the time of the operators it contains.
+    pub elapsed: Duration,
+    /// The estimated bytes queued for the operator in its input channels, if memory is accounted.
+    ///
+    /// Memory accounting is enabled by `Worker::set_memory_accounting`.
+    pub queued_bytes: Option<usize>,
+    /// The estimated bytes held by the operator, if memory is accounted.
+    pub held_bytes: Option<usize>,
+}
+
+/// Counters of the progress traffic of a scope at a worker, as reported by `Worker::progress_stats`.
+///
+/// Each message is counted once for each worker it is sent to or received from, including the
+/// worker itself.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, Hash, Eq, PartialEq)]
+pub struct ProgressStats {
+    /// Sequence of nested scope identifiers indicating the path from the root to the scope.
+    pub addr: Vec<usize>,
+    /// Worker-unique identifier for the channel carrying the progress updates of the scope.
+    pub channel: usize,
+    /// The number of messages sent.
+    pub sent_messages: usize,
+    /// The number of updates in the messages sent.
+    pub sent_updates: usize,
+    /// The size of the encoded updates in the messages sent, in bytes.
+    pub sent_bytes: usize,
+    /// The number of messages received.
+    pub received_messages: usize,
+    /// The number of updates in the messages received.
+    pub received_updates: usize,
+    /// The size of the encoded updates in the messages received, in bytes.
+    pub received_bytes: usize,
+    /// The largest number of updates sent in one message.
+    pub largest_batch: usize,
+    /// The number of messages received from other workers, stamped with the time they were sent.
+    ///
+    /// Messages are stamped only by workers whose `ProgressPolicy` sets `latency`.
+    pub remote_messages: usize,
+    /// The total estimated latency of messages received from other workers.
+    ///
+    /// Latencies are estimated by comparing the system clocks of the sending and receiving
+    /// workers, and so include any difference between the clocks of their machines.
+    pub remote_latency: Duration,
+    /// The largest estimated latency of a message received from another worker.
+    pub max_latency: Duration,
+}
+
+impl ProgressStats {
+    /// The mean number of updates in each message sent.
+    pub fn mean_batch(&self) -> f64 {
+        if self.sent_messages == 0 { 0.0 } else { self.sent_updates as f64 / self.sent_messages as f64 }
+    }
+
+    /// The mean estimated latency of messages received from other workers, if any were received.
+    pub fn mean_latency(&self) -> Option<Duration> {
+        if self.remote_messages == 0 { None } else { Some(self.remote_latency / self.remote_messages as u32) }
+    }
+}
+
+/// The operators and channels of the dataflows installed on a worker.
+///
+/// # Examples
+/// ```
+/// use timely::dataflow::Scope;
+/// use timely::dataflow::operators::{ToStream, Exchange, Enter, Leave, Inspect};
+///
+/// timely::execute_from_args(::std::env::args(), |worker| {
+///
+///     worker.dataflow_named::<usize,_,_>("Example", |scope| {
+///         let stream = (0..10).to_stream(scope);
+///         scope.region_named("Shuffle", |inner| {
+///             stream.enter(inner).exchange(|x| *x as u64).leave()
+///         })
+///         .inspect(|x| println!("seen: {:?}", x));
+///     });
+///
+///     let graph = worker.graph();
+///     assert!(graph.operators.iter().any(|op| op.names == vec!["Example", "Shuffle", "Exchange"]));
+///     assert!(graph.channels.iter().any(|channel| channel.pact == "Exchange"));
+///     assert!(graph.to_dot().starts_with("digraph"));
+///     assert!(serde_json::to_string(&graph).unwrap().contains("\"pact\":\"Exchange\""));
+/// });
+/// ```
+#[derive(Serialize, Deserialize, Debug, Clone, Default, Hash, Eq, PartialEq)]
+pub struct Graph {
+    /// The operators, ordered by address.
+    pub operators: Vec<Operator>,
+    /// The channels, ordered by identifier.
+    pub channels: Vec<Channel>,
+}
+
+impl Graph {
+
+    /// Renders the graph in the Graphviz DOT language.
+    ///
+    /// Each scope is drawn as a cluster, containing a node for the scope itself, at which
+    /// channels entering and leaving the scope start and end. Channels are labeled with
+    /// their parallelization contracts.
+    pub fn to_dot(&self) -> String {
+        let mut dot = String::new();
+        writeln!(dot, "digraph {{").unwrap();
+        self.write_dot_scope(&mut dot, &[], 1);
+        for channel in self.channels.iter() {
+            writeln!(
+                dot,
+                "  {} -> {} [label=\"{}\"];",
+                node_name(&channel.scope_addr, channel.source.0),
+                node_name(&channel.scope_addr, channel.target.0),
+                escape(&channel.pact),
+            ).unwrap();
+        }
+        writeln!(dot, "}}").unwrap();
+        dot
+    }
+
+    // Writes the operators immediately within the scope at `addr`, and recursively their scopes.
+    fn write_dot_scope(&self, dot: &mut String, addr: &[usize], depth: usize) {
+        let indent = "  ".repeat(depth);
+        let children = self.operators.iter().filter(|op| op.addr.len() == addr.len() + 1 && op.addr.starts_with(addr));
+        for child in children {
+            let is_scope = self.operators.iter().any(|op| op.addr.len() > child.addr.len() && op.addr.starts_with(&child.addr[..]));
+            let name = node_name(&child.addr[..child.addr.len()-1], child.addr[child.addr.len()-1]);
+            let label = format!("{} (id {})", escape(child.name()), child.id);
+            if is_scope {
+                writeln!(dot, "{}subgraph cluster_{} {{", indent, name).unwrap();
+                writeln!(dot, "{}  label=\"{}\";", indent, label).unwrap();
+                writeln!(dot, "{}  {} [label=\"{}\", shape=box];", indent, name, label).unwrap();
+                self.write_dot_scope(dot, &child.addr[..], depth + 1);
+                writeln!(dot, "{}}}", indent).unwrap();
+            }
+            else {
+                writeln!(dot, "{}{} [label=\"{}\"];", indent, name, label).unwrap();
+            }
+        }
+    }
+}
+
+/// The DOT node name for the operator at `index` within the scope at `scope_addr`.
+///
+/// Index zero refers to the scope itself, except at the root where it is the first dataflow.
+fn node_name(scope_addr: &[usize], index: usize) -> String {
+    let mut name = "op".to_string();
+    for x in scope_addr.iter() {
+        write!(name, "_{}", x).unwrap();
+    }
+    if index != 0 || scope_addr.is_empty() {
+        write!(name, "_{}", index).unwrap();
+    }
+    name
+}
+
+/// Escapes quotes and backslashes, for use within DOT and JSON strings.
+pub(crate) fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            c if (c as u32) < 0x20 => { write!(escaped, "\\u{:04x}", c as u32).unwrap(); },
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// The name of a parallelization contract type, without its module path or type parameters.
+pub(crate) fn pact_name<P>() -> String {
+    let name = ::std::any::type_name::<P>();
+    let name = name.split('<').next().unwrap_or(name);
+    name.rsplit("::").next().unwrap_or(name).to_string()
+}
@@ -0,0 +1,289 @@
+//! Histograms of the latency with which epochs complete.
+//!
+//! An `EpochLatency` measures, for each epoch closed at an input, the wall-clock time from when
+//! the epoch is closed to when a probe reports it complete, which is when the probe's frontier
+//! first passes it. Epochs are closed with `EpochLatency::advance_input`, which advances an input
+//! and closes the epoch it leaves, or with `EpochLatency::close` for inputs advanced otherwise.
+//! The probe's frontier reflects the inputs of all workers, and so an epoch closed at one worker
+//! completes once every worker has closed it and the dataflow has finished its work.
+//!
+//! Latencies are recorded in a `LatencyHistogram`, whose buckets grow in proportion to the
+//! values they hold, in the manner of an HDR histogram, so that quantiles are reported to within
+//! a few percent over the full range of durations. The histogram may be queried at any time.
+//!
+//! # Examples
+//! ```
+//! use timely::dataflow::InputHandle;
+//! use timely::dataflow::operators::{Input, Exchange, Probe};
+//! use timely::dataflow::latency::EpochLatency;
+//!
+//! timely::execute_from_args(::std::env::args(), |worker| {
+//!     let mut input = InputHandle::new();
+//!     let probe = worker.dataflow::<u64,_,_>(|scope| {
+//!         scope.input_from(&mut input)
+//!              .exchange(|x: &u64| *x)
+//!              .probe()
+//!     });
+//!
+//!     let latency = EpochLatency::new(&probe);
+//!     for round in 0 .. 100 {
+//!         input.send(round);
+//!         latency.advance_input(&mut input, round + 1);
+//!         worker.step_while(|| probe.less_than(input.time()));
+//!     }
+//!
+//!     let histogram = latency.histogram();
+//!     assert_eq!(histogram.count(), 100);
+//!     assert_eq!(latency.pending(), 0);
+//!     println!("median: {:?}, p99: {:?}, max: {:?}", histogram.quantile(0.5), histogram.quantile(0.99), histogram.max());
+//! }).unwrap();
+//! ```
+
+use std::rc::Rc;
+use std::cell::RefCell;
+use crate::time::{Duration, Instant};
+
+use crate::Data;
+use crate::progress::Timestamp;
+use crate::dataflow::{InputHandle, ProbeHandle};
+
+/// The number of bits of each value a `LatencyHistogram` retains, beyond its leading bit.
+const PRECISION: u32 = 5;
+/// The number of buckets for each power of two.
+const SUB_BUCKETS: usize = 1 << PRECISION;
+
+/// A histogram of durations, with buckets of exponentially increasing width.
+///
+/// Durations are counted in nanoseconds. Values less than 32 nanoseconds have buckets of their
+/// own, and larger values share buckets with values within 1/32 of them, so that reported values
+/// are within about three percent of the recorded values they stand for.
+///
+/// # Examples
+/// ```
+/// use std::time::Duration;
+/// use timely::dataflow::latency::LatencyHistogram;
+///
+/// let mut histogram = LatencyHistogram::new();
+/// for millis in 1 ..= 100 {
+///     histogram.record(Duration::from_millis(millis));
+/// }
+/// assert_eq!(histogram.count(), 100);
+/// assert_eq!(histogram.min(), Some(Duration::from_millis(1)));
+/// assert_eq!(histogram.max(), Some(Duration::from_millis(100)));
+///
+/// let median = histogram.quantile(0.5).unwrap();
+/// assert!(median >= Duration::from_millis(50) && median <= Duration::from_millis(52));
+/// ```
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+pub struct LatencyHistogram {
+    // Counts of values in each bucket, allocated as larger values are recorded.
+    counts: Vec<u64>,
+    count: u64,
+    min: u64,
+    max: u64,
+    sum: u128,
+}
+
+impl LatencyHistogram {
+    /// Allocates an empty histogram.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Records one occurrence of `latency`.
+    pub fn record(&mut self, latency: Duration) {
+        let nanos = ::std::cmp::min(latency.as_nanos(), u64::MAX as u128) as u64;
+        let index = bucket(nanos);
+        if self.counts.len() <= index {
+            self.counts.resize(index + 1, 0);
+        }
+        self.counts[index] += 1;
+        if self.count == 0 || nanos < self.min { self.min = nanos; }
+        if nanos > self.max { self.max = nanos; }
+        self.count += 1;
+        self.sum += nanos as u128;
+    }
+
+    /// Adds the recorded values of `other` to the histogram.
+    pub fn merge(&mut self, other: &LatencyHistogram) {
+        if other.count == 0 { return; }
+        if self.counts.len() < other.counts.len() {
+            self.counts.resize(other.counts.len(), 0);
+        }
+        for (count, other) in self.counts.iter_mut().zip(other.counts.iter()) {
+            *count += other;
+        }
+        if self.count == 0 || other.min < self.min { self.min = other.min; }
+        if other.max > self.max { self.max = other.max; }
+        self.count += other.count;
+        self.sum += other.sum;
+    }
+
+    /// Discards all recorded values.
+    pub fn clear(&mut self) {
+        *self = Self::default();
+    }
+
+    /// The number of recorded values.
+    pub fn count(&self) -> u64 { self.count }
+    /// Returns `true` if no values have been recorded.
+    pub fn is_empty(&self) -> bool { self.count == 0 }
+
+    /// The least recorded value, exactly.
+    pub fn min(&self) -> Option<Duration> {
+        if self.count > 0 { Some(Duration::from_nanos(self.min)) } else { None }
+    }
+
+    /// The greatest recorded value, exactly.
+    pub fn max(&self) -> Option<Duration> {
+        if self.count > 0 { Some(Duration::from_nanos(self.max)) } else { None }
+    }
+
+    /// The mean of the recorded values, exactly.
+    pub fn mean(&self) -> Option<Duration> {
+        if self.count > 0 { Some(Duration::from_nanos((self.sum / self.count as u128) as u64)) } else { None }
+    }
+
+    /// The least value at least a fraction `quantile` of the recorded values do not exceed.
+    ///
+    /// The result is the greatest value of the bucket in which the quantile falls, and so is
+    /// within the histogram's precision of it, and is no greater than the greatest recorded value.
+    /// Quantiles are clamped to between zero and one.
+    pub fn quantile(&self, quantile: f64) -> Option<Duration> {
+        if self.count == 0 { return None; }
+        let quantile = if quantile.is_nan() { 0.0 } else { quantile.clamp(0.0, 1.0) };
+        let rank = ::std::cmp::max((quantile * self.count as f64).ceil() as u64, 1);
+        let mut seen = 0;
+        for (index, count) in self.counts.iter().enumerate() {
+            seen += count;
+            if seen >= rank {
+                let value = ::std::cmp::max(::std::cmp::min(upper(index), self.max), self.min);
+                return Some(Duration::from_nanos(value));
+            }
+        }
+        Some(Duration::from_nanos(self.max))
+    }
+
+    /// The non-empty buckets, as the range of values each holds and the number of values recorded in it.
+    pub fn buckets(&self) -> impl Iterator<Item=(Duration, Duration, u64)>+'_ {
+        self.counts
+            .iter()
+            .enumerate()
+            .filter(|(_, count)| **count > 0)
+            .map(|(index, count)| (Duration::from_nanos(lower(index)), Duration::from_nanos(upper(index)), *count))
+    }
+}
+
+/// The index of the bucket holding `value`.
+fn bucket(value: u64) -> usize {
+    if value < SUB_BUCKETS as u64 {
+        value as usize
+    }
+    else {
+        let exponent = 63 - value.leading_zeros();
+        let block = (exponent - PRECISION + 1) as usize;
+        let offset = ((value >> (exponent - PRECISION)) as usize) & (SUB_BUCKETS - 1);
+        block * SUB_BUCKETS + offset
+    }
+}
+
+/// The least value held by the bucket at `index`.
+fn lower(index: usize) -> u64 {
+    if index < SUB_BUCKETS {
+        index as u64
+    }
+    else {
+        let block = index / SUB_BUCKETS;
+        let offset = index % SUB_BUCKETS;
+        ((SUB_BUCKETS + offset) as u64) << (block - 1)
+    }
+}
+
+/// The greatest value held by the bucket at `index`.
+fn upper(index: usize) -> u64 {
+    if index < SUB_BUCKETS {
+        index as u64
+    }
+    else {
+        let block = index / SUB_BUCKETS;
+        lower(index).saturating_add((1u64 << (block - 1)) - 1)
+    }
+}
+
+/// Measures the latency with which a probe reports epochs complete after they close at an input.
+///
+/// The measurements are taken by the probe operator, as it is scheduled, and are shared among
+/// clones of the `EpochLatency`.
+pub struct EpochLatency<T: Timestamp> {
+    probe: ProbeHandle<T>,
+    shared: Rc<RefCell<Epochs<T>>>,
+}
+
+/// Epochs closed and not yet complete, and the latencies of those completed.
+struct Epochs<T> {
+    closed: Vec<(T, Instant)>,
+    histogram: LatencyHistogram,
+}
+
+impl<T: Timestamp> EpochLatency<T> {
+    /// Measures the latency with which `probe` reports epochs complete.
+    pub fn new(probe: &ProbeHandle<T>) -> Self {
+        let shared = Rc::new(RefCell::new(Epochs { closed: Vec::new(), histogram: LatencyHistogram::new() }));
+        let epochs = Rc::downgrade(&shared);
+        probe.observe(move |frontier| {
+            if let Some(epochs) = epochs.upgrade() {
+                let now = Instant::now();
+                let mut epochs = epochs.borrow_mut();
+                let Epochs { closed, histogram } = &mut *epochs;
+                closed.retain(|(time, start)| {
+                    let complete = !frontier.less_equal(time);
+                    if complete {
+                        histogram.record(now.duration_since(*start));
+                    }
+                    !complete
+                });
+            }
+        });
+        EpochLatency { probe: probe.clone(), shared }
+    }
+
+    /// Records that the epoch `time` has closed now.
+    ///
+    /// The epoch should be closed as the input advances beyond it, before the worker next steps.
+    /// An epoch the probe has already reported complete is not recorded.
+    pub fn close(&self, time: T) {
+        if self.probe.less_equal(&time) {
+            self.shared.borrow_mut().closed.push((time, Instant::now()));
+        }
+    }
+
+    /// Advances `input` to `next`, and records that the epoch it leaves has closed.
+    pub fn advance_input<D: Data>(&self, input: &mut InputHandle<T, D>, next: T) {
+        let time = input.time().clone();
+        input.advance_to(next);
+        if !time.eq(input.time()) {
+            self.close(time);
+        }
+    }
+
+    /// The number of epochs closed and not yet reported complete.
+    pub fn pending(&self) -> usize {
+        self.shared.borrow().closed.len()
+    }
+
+    /// A copy of the histogram of the latencies of completed epochs.
+    pub fn histogram(&self) -> LatencyHistogram {
+        self.shared.borrow().histogram.clone()
+    }
+
+    /// Takes the histogram of the latencies of completed epochs, leaving it empty.
+    pub fn take_histogram(&self) -> LatencyHistogram {
+        ::std::mem::take(&mut self.shared.borrow_mut().histogram)
+    }
+}
+
+impl<T: Timestamp> Clone for EpochLatency<T> {
+    fn clone(&self) -> Self {
+        EpochLatency { probe: self.probe.clone(), shared: self.shared.clone() }
+    }
+}
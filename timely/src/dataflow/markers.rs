@@ -0,0 +1,229 @@
+//! Latency markers, which travel through a dataflow along with its records.
+//!
+//! A `Marker` is injected at an input with `InputHandle::mark`, and carries an identifier and the
+//! wall-clock time at which it was injected. It travels with the next message the input sends,
+//! through the channels of the dataflow, and is observed by the probes it reaches, which report
+//! its end-to-end latency from `ProbeHandle::take_markers`.
+//!
+//! Markers are available with the `markers` feature, without which messages do not carry them.
+//!
+//! Channels route markers with the records that follow them. An exchange forwards a marker to
+//! each worker receiving one of the records of its message, and a stream with several consumers
+//! forwards it to each of them. An operator built with the generic operator builders forwards
+//! the markers it receives at its inputs with the next message of each of its outputs in the
+//! same activation, and otherwise, once the activation ends, in a message without records, which
+//! receivers pass over once they have taken its markers. A marker therefore continues even past
+//! operators that hold or discard the records it arrived with, and an input sends a marker not
+//! followed by records as it advances its epoch. Operators without outputs, and those built with
+//! `builder_raw` other than probes, discard markers.
+//!
+//! Origins are taken from the system clock, so that latencies may be measured across processes
+//! on the same machine, or on machines whose clocks are synchronized.
+//!
+//! # Examples
+//! ```
+//! use timely::dataflow::InputHandle;
+//! use timely::dataflow::operators::{Input, Exchange, Map, Probe};
+//!
+//! timely::execute_from_args(::std::env::args(), |worker| {
+//!     let mut input = InputHandle::new();
+//!     let probe = worker.dataflow::<u64,_,_>(|scope| {
+//!         scope.input_from(&mut input)
+//!              .exchange(|x: &u64| *x)
+//!              .map(|x| x + 1)
+//!              .probe()
+//!     });
+//!
+//!     for round in 0 .. 10 {
+//!         // Measure the latency of the first record of each round.
+//!         input.mark(round);
+//!         input.send(round);
+//!         input.advance_to(round + 1);
+//!         worker.step_while(|| probe.less_than(input.time()));
+//!     }
+//!
+//!     let observed = probe.take_markers();
+//!     for observation in observed.iter() {
+//!         println!("marker {} observed after {:?}", observation.marker.id, observation.latency);
+//!     }
+//!     assert!(observed.iter().all(|observation| observation.worker == worker.index()));
+//!     if worker.peers() == 1 {
+//!         assert_eq!(observed.len(), 10);
+//!     }
+//! }).unwrap();
+//! ```
+
+use std::rc::Rc;
+use std::cell::RefCell;
+use crate::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::communication::Push;
+use crate::dataflow::channels::{Bundle, Message};
+
+/// An identified point in a stream of records, and the time at which it was injected.
+#[derive(Abomonation, Serialize, Deserialize, Debug, Clone, Copy, Hash, Eq, PartialEq, Ord, PartialOrd)]
+pub struct Marker {
+    /// An identifier chosen by the application.
+    pub id: u64,
+    /// The system time at which the marker was injected, since the Unix epoch.
+    pub origin: Duration,
+}
+
+impl Marker {
+    /// Creates a marker identified by `id`, originating now.
+    pub fn new(id: u64) -> Self {
+        Marker { id, origin: since_epoch() }
+    }
+
+    /// The time elapsed since the marker was injected, or zero if the system clock is behind its origin.
+    pub fn elapsed(&self) -> Duration {
+        since_epoch().saturating_sub(self.origin)
+    }
+}
+
+/// The system time since the Unix epoch, or zero if the system clock precedes it.
+fn since_epoch() -> Duration {
+    SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default()
+}
+
+/// The arrival of a marker at a probe.
+#[derive(Debug, Clone, Copy, Hash, Eq, PartialEq)]
+pub struct MarkerObservation {
+    /// The observed marker.
+    pub marker: Marker,
+    /// The index of the worker whose probe observed the marker.
+    pub worker: usize,
+    /// The time from the injection of the marker to its observation.
+    pub latency: Duration,
+}
+
+/// Markers received at the inputs of an operator, awaiting a message of each of its outputs.
+#[derive(Clone)]
+pub(crate) struct MarkerCarrier<T> {
+    shared: Rc<RefCell<Carried<T>>>,
+}
+
+/// Markers awaiting the next message of an output.
+pub(crate) type Pending = Rc<RefCell<Vec<Marker>>>;
+
+/// Sends markers at a time, in a message without records.
+type Sender<T> = Box<dyn FnMut(T, Vec<Marker>)>;
+
+struct Carried<T> {
+    // The time of the last message to bring markers, at which unsent markers are sent.
+    time: Option<T>,
+    outputs: Vec<(Pending, Sender<T>)>,
+    observer: Option<Box<dyn FnMut(&Marker)>>,
+}
+
+impl<T: Clone> MarkerCarrier<T> {
+    /// Allocates a carrier for an operator without outputs.
+    pub(crate) fn new() -> Self {
+        MarkerCarrier { shared: Rc::new(RefCell::new(Carried { time: None, outputs: Vec::new(), observer: None })) }
+    }
+
+    /// Calls `observer` with each marker received.
+    pub(crate) fn observe<F: FnMut(&Marker)+'static>(&self, observer: F) {
+        self.shared.borrow_mut().observer = Some(Box::new(observer));
+    }
+
+    /// Adds an output, which sends markers without records to `pusher`, returning the markers pending for it.
+    pub(crate) fn add_output<P: Push<Bundle<T, D>>+'static, D: 'static>(&self, mut pusher: P) -> Pending where T: 'static {
+        let pending = Rc::new(RefCell::new(Vec::new()));
+        let send = Box::new(move |time, markers| {
+            push_markers(time, markers, &mut pusher);
+            pusher.push(&mut None);
+        });
+        self.shared.borrow_mut().outputs.push((pending.clone(), send));
+        pending
+    }
+
+    /// Holds `markers`, received at `time`, for the next message of each output.
+    pub(crate) fn receive(&self, time: &T, markers: &[Marker]) {
+        let mut carried = self.shared.borrow_mut();
+        carried.time = Some(time.clone());
+        if let Some(observer) = carried.observer.as_mut() {
+            markers.iter().for_each(observer);
+        }
+        for (pending, _) in carried.outputs.iter() {
+            pending.borrow_mut().extend_from_slice(markers);
+        }
+    }
+
+    /// Sends the markers no output message has carried, in messages without records.
+    pub(crate) fn flush(&self) {
+        let mut carried = self.shared.borrow_mut();
+        if let Some(time) = carried.time.take() {
+            for (pending, send) in carried.outputs.iter_mut() {
+                let markers = ::std::mem::take(&mut *pending.borrow_mut());
+                if !markers.is_empty() {
+                    send(time.clone(), markers);
+                }
+            }
+        }
+    }
+}
+
+/// Pushes `markers` at `pusher` in a message at `time` without records.
+///
+/// As the message has no records it has no effect on progress tracking, and receivers pass over
+/// it once they have taken its markers, rather than presenting its time to operators.
+pub(crate) fn push_markers<T, D, P: Push<Bundle<T, D>>>(time: T, markers: Vec<Marker>, pusher: &mut P) {
+    let mut message = Message::new(time, Vec::new(), 0, 0);
+    message.markers = markers;
+    pusher.push(&mut Some(Bundle::from_typed(message)));
+}
+
+#[cfg(test)]
+mod tests {
+
+    use crate::dataflow::InputHandle;
+    use crate::dataflow::operators::{Input, Exchange, Filter, Probe};
+
+    #[test]
+    fn markers_pass_discarded_records() {
+        let guards = crate::execute(crate::Configuration::Process(2), |worker| {
+            let mut input = InputHandle::new();
+            let probe = worker.dataflow::<u64,_,_>(|scope| {
+                scope.input_from(&mut input)
+                     .filter(|_| false)
+                     .exchange(|x: &u64| *x)
+                     .probe()
+            });
+
+            input.mark(worker.index() as u64);
+            input.send(0);
+            input.advance_to(1);
+            worker.step_while(|| probe.less_than(input.time()));
+            let mut observed = probe.take_markers().iter().map(|observation| observation.marker.id).collect::<Vec<_>>();
+            observed.sort();
+            observed
+        }).unwrap();
+
+        // Each marker reaches each worker, as the filter sent no records with which to route it.
+        for observed in guards.join() {
+            assert_eq!(observed.unwrap(), vec![0, 1]);
+        }
+    }
+
+    #[test]
+    fn markers_without_records() {
+        let guards = crate::execute(crate::Configuration::Thread, |worker| {
+            let mut input = InputHandle::new();
+            let probe = worker.dataflow::<u64,_,_>(|scope| {
+                scope.input_from(&mut input)
+                     .filter(|_: &u64| true)
+                     .probe()
+            });
+
+            input.mark(7);
+            input.advance_to(1);
+            worker.step_while(|| probe.less_than(input.time()));
+            probe.take_markers().iter().map(|observation| observation.marker.id).collect::<Vec<_>>()
+        }).unwrap();
+
+        for observed in guards.join() {
+            assert_eq!(observed.unwrap(), vec![7]);
+        }
+    }
+}
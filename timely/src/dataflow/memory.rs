@@ -0,0 +1,153 @@
+//! Attribution of memory to the operators that hold it.
+//!
+//! With `Worker::set_memory_accounting`, each operator of subsequently constructed dataflows has
+//! a `MemoryAccount` counting the bytes attributed to it at the worker. The account counts the
+//! bytes *queued* in the channels delivering to the operator's inputs, of records sent by the
+//! same worker and not yet received, and the bytes *held* by the operator, which are those of
+//! its output buffers and any the operator reports itself, for example for the state it keeps.
+//!
+//! The bytes of records are estimated as `size_of::<D>()` for each record of type `D`, and so
+//! exclude memory the records own indirectly, such as the contents of a `String`. Operators
+//! whose records own substantial memory may report it themselves, with `allocate` and `release`.
+//!
+//! The accounts are available through `Worker::introspect`, and changes to them are logged as
+//! `MemoryEvent`s once the operator has been scheduled.
+//!
+//! # Examples
+//! ```
+//! use timely::dataflow::InputHandle;
+//! use timely::dataflow::operators::{Input, Probe};
+//! use timely::dataflow::operators::generic::operator::Operator;
+//! use timely::dataflow::channels::pact::Pipeline;
+//!
+//! timely::execute_from_args(::std::env::args(), |worker| {
+//!     worker.set_memory_accounting(true);
+//!     let mut input = InputHandle::new();
+//!     let probe = worker.dataflow::<usize,_,_>(|scope| {
+//!         scope.input_from(&mut input)
+//!              .unary(Pipeline, "Stash", |_capability, info| {
+//!                  let account = info.memory.clone();
+//!                  let mut stash = Vec::new();
+//!                  let mut vector = Vec::new();
+//!                  move |input, output| {
+//!                      input.for_each(|time, data| {
+//!                          data.swap(&mut vector);
+//!                          // Account for the records the operator retains.
+//!                          if let Some(account) = account.as_ref() {
+//!                              account.allocate(vector.len() * ::std::mem::size_of::<usize>());
+//!                          }
+//!                          stash.extend(vector.drain(..));
+//!                          output.session(&time).give(stash.len());
+//!                      });
+//!                  }
+//!              })
+//!              .probe()
+//!     });
+//!
+//!     for round in 0 .. 10 {
+//!         input.send(round);
+//!     }
+//!     input.advance_to(1);
+//!     worker.step_while(|| probe.less_than(input.time()));
+//!
+//!     let statuses = worker.introspect();
+//!     let stash = statuses.iter().find(|status| status.name == "Stash").unwrap();
+//!     assert!(stash.held_bytes.unwrap() >= 10 * ::std::mem::size_of::<usize>());
+//!     assert_eq!(stash.queued_bytes, Some(0));
+//! }).unwrap();
+//! ```
+
+use std::rc::Rc;
+use std::cell::Cell;
+use std::cell::RefCell;
+use std::collections::HashMap;
+
+/// The bytes attributed to an operator at a worker, shared by the operator and its channels.
+#[derive(Clone, Default, Debug)]
+pub struct MemoryAccount {
+    // Bytes sent towards the operator's inputs and not yet received. May be transiently negative.
+    queued: Rc<Cell<i64>>,
+    // Bytes held by the operator.
+    held: Rc<Cell<i64>>,
+}
+
+impl MemoryAccount {
+    /// Allocates an empty account.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Attributes `bytes` held by the operator to the account.
+    pub fn allocate(&self, bytes: usize) {
+        self.held.set(self.held.get() + bytes as i64);
+    }
+
+    /// Withdraws `bytes` the operator no longer holds from the account.
+    pub fn release(&self, bytes: usize) {
+        self.held.set(self.held.get() - bytes as i64);
+    }
+
+    /// The bytes held by the operator.
+    pub fn held(&self) -> usize {
+        ::std::cmp::max(self.held.get(), 0) as usize
+    }
+
+    /// The bytes queued for the operator in its input channels.
+    pub fn queued(&self) -> usize {
+        ::std::cmp::max(self.queued.get(), 0) as usize
+    }
+
+    /// The bytes held by and queued for the operator.
+    pub fn total(&self) -> usize {
+        self.held() + self.queued()
+    }
+
+    /// Counts `bytes` as sent towards the operator.
+    pub(crate) fn enqueue(&self, bytes: usize) {
+        self.queued.set(self.queued.get() + bytes as i64);
+    }
+
+    /// Counts `bytes` as received by the operator.
+    pub(crate) fn dequeue(&self, bytes: usize) {
+        self.queued.set(self.queued.get() - bytes as i64);
+    }
+}
+
+/// The accounts of the operators of a worker, by address, shared among the worker's scopes.
+#[derive(Clone, Default)]
+pub struct MemoryAccounts {
+    enabled: Rc<Cell<bool>>,
+    accounts: Rc<RefCell<HashMap<Vec<usize>, MemoryAccount>>>,
+}
+
+impl MemoryAccounts {
+    /// Allocates disabled accounting.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Enables or disables accounting for operators constructed from now on.
+    pub fn set_enabled(&self, enabled: bool) {
+        self.enabled.set(enabled);
+    }
+
+    /// Indicates whether operators constructed from now on are accounted.
+    pub fn enabled(&self) -> bool {
+        self.enabled.get()
+    }
+
+    /// The account of the operator at `address`, if accounting is enabled.
+    pub fn account(&self, address: &[usize]) -> Option<MemoryAccount> {
+        if self.enabled.get() {
+            Some(self.accounts.borrow_mut().entry(address.to_vec()).or_default().clone())
+        }
+        else {
+            None
+        }
+    }
+
+    /// Discards the accounts of the operators of the dataflow at `dataflow`.
+    pub fn forget_dataflow(&self, dataflow: usize) {
+        self.accounts.borrow_mut().retain(|address, _| address[0] != dataflow);
+    }
+}
@@ -23,3 +23,10 @@ pub mod operators;
 pub mod channels;
 pub mod scopes;
 pub mod stream;
+pub mod graph;
+pub mod memory;
+#[cfg(feature = "markers")]
+pub mod markers;
+pub mod latency;
+pub mod snapshot;
+pub mod watchdog;
@@ -5,7 +5,7 @@ use std::collections::HashMap;
 use crate::{Data, ExchangeData};
 use crate::dataflow::{Stream, Scope};
 use crate::dataflow::operators::generic::operator::Operator;
-use crate::dataflow::channels::pact::Exchange;
+use crate::dataflow::channels::pact::{Exchange, ParallelizationContract};
 
 /// Generic intra-timestamp aggregation
 ///
@@ -75,31 +75,45 @@ impl<S: Scope, K: ExchangeData+Hash+Eq, V: ExchangeData> Aggregate<S, K, V> for
         emit: E,
         hash: H) -> Stream<S, R> where S::Timestamp: Eq {
 
-        let mut aggregates = HashMap::new();
-        let mut vector = Vec::new();
-        self.unary_notify(Exchange::new(move |&(ref k, _)| hash(k)), "Aggregate", vec![], move |input, output, notificator| {
+        aggregate_with(self, Exchange::new(move |&(ref k, _)| hash(k)), fold, emit)
+    }
+}
 
-            // read each input, fold into aggregates
-            input.for_each(|time, data| {
-                data.swap(&mut vector);
-                let agg_time = aggregates.entry(time.time().clone()).or_insert_with(HashMap::new);
-                for (key, val) in vector.drain(..) {
-                    let agg = agg_time.entry(key.clone()).or_insert_with(Default::default);
-                    fold(&key, val, agg);
-                }
-                notificator.notify_at(time.retain());
-            });
+/// Aggregates `(key, val)` data within each time, after distributing it according to `pact`.
+pub(crate) fn aggregate_with<S, K, V, R, D, F, E, P>(stream: &Stream<S, (K, V)>, pact: P, fold: F, emit: E) -> Stream<S, R>
+where
+    S: Scope,
+    K: Data+Hash+Eq,
+    V: Data,
+    R: Data,
+    D: Default+'static,
+    F: Fn(&K, V, &mut D)+'static,
+    E: Fn(K, D)->R+'static,
+    P: ParallelizationContract<S::Timestamp, (K, V)>,
+{
+    let mut aggregates = HashMap::new();
+    let mut vector = Vec::new();
+    stream.unary_notify(pact, "Aggregate", vec![], move |input, output, notificator| {
 
-            // pop completed aggregates, send along whatever
-            notificator.for_each(|time,_,_| {
-                if let Some(aggs) = aggregates.remove(time.time()) {
-                    let mut session = output.session(&time);
-                    for (key, agg) in aggs {
-                        session.give(emit(key, agg));
-                    }
-                }
-            });
-        })
+        // read each input, fold into aggregates
+        input.for_each(|time, data| {
+            data.swap(&mut vector);
+            let agg_time = aggregates.entry(time.time().clone()).or_insert_with(HashMap::new);
+            for (key, val) in vector.drain(..) {
+                let agg = agg_time.entry(key.clone()).or_insert_with(Default::default);
+                fold(&key, val, agg);
+            }
+            notificator.notify_at(time.retain());
+        });
 
-    }
+        // pop completed aggregates, send along whatever
+        notificator.for_each(|time,_,_| {
+            if let Some(aggs) = aggregates.remove(time.time()) {
+                let mut session = output.session(&time);
+                for (key, agg) in aggs {
+                    session.give(emit(key, agg));
+                }
+            }
+        });
+    })
 }
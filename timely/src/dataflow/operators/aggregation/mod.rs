@@ -1,9 +1,16 @@
 //! Aggregation operators of various flavors
 //!
-//! Two traits, `Aggregate` and `StateMachine`, which support the accumulation of streamed information.
+//! Four traits, `Aggregate`, `SessionWindow`, `TopK`, and `StateMachine`, which support the accumulation of
+//! streamed information.
 //!
 //! `Aggregate` accumulates records within times, and releases the accumulations once the time is complete.
 //!
+//! `SessionWindow` accumulates keyed records into sessions of activity separated by gaps in time, and
+//! releases each accumulation once no further record can extend its session.
+//!
+//! `TopK` selects the greatest records within times, optionally for each key, and releases them once the
+//! time is complete.
+//!
 //! `StateMachine` responds to a sequence of keyed events, maintaining and updating a state for each key.
 //! The user logic may produce output records for each transition, and optionally de-register the state to
 //! clean up when appropriate.
@@ -12,7 +19,11 @@
 //! `StateMachine` to track an accumulation across timestamps.
 
 pub use self::aggregate::Aggregate;
+pub use self::session::SessionWindow;
 pub use self::state_machine::StateMachine;
+pub use self::topk::TopK;
 
 pub mod state_machine;
 pub mod aggregate;
+pub mod session;
+pub mod topk;
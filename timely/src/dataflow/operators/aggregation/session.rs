@@ -0,0 +1,142 @@
+//! Aggregation into sessions of activity, separated by gaps.
+use std::hash::Hash;
+use std::collections::{HashMap, BTreeMap};
+
+use crate::{Data, ExchangeData};
+use crate::order::TotalOrder;
+use crate::progress::{Timestamp, PathSummary};
+use crate::dataflow::{Stream, Scope};
+use crate::dataflow::operators::Capability;
+use crate::dataflow::operators::generic::operator::Operator;
+use crate::dataflow::channels::pact::Exchange;
+
+/// Aggregation into per-key sessions.
+///
+/// Extension method grouping keyed data into sessions, in which each record's timestamp is
+/// within a gap of the previous record's. For aggregation within a timestamp, consider
+/// `Aggregate`.
+pub trait SessionWindow<S: Scope, K: ExchangeData+Hash, V: ExchangeData> {
+    /// Aggregates data of the form `(key, val)` into sessions, using user-supplied logic.
+    ///
+    /// The `session_window` method takes a `gap` and functions `fold`, `emit`, and `hash`;
+    /// used to combine new `V` data with the existing `D` state of a session, to produce `R`
+    /// output from a key, the first and last timestamps of its session, and its `D` state,
+    /// and to route `K` keys, respectively.
+    ///
+    /// A record extends the session of its key if its timestamp is at most the session's
+    /// last timestamp advanced by `gap`, and otherwise starts a new session. Records are
+    /// applied in timestamp order, once their timestamps are complete. A session is closed,
+    /// and its output produced at its last timestamp, once the input frontier has passed its
+    /// last timestamp advanced by `gap`.
+    ///
+    /// # Examples
+    /// ```
+    /// use timely::dataflow::operators::{ToStream, Delay, Map, Capture};
+    /// use timely::dataflow::operators::aggregation::SessionWindow;
+    /// use timely::dataflow::operators::capture::Extract;
+    ///
+    /// let captured = timely::example(|scope| {
+    ///     vec![(0, 'a'), (1, 'a'), (2, 'b'), (5, 'a')]
+    ///         .to_stream(scope)
+    ///         .delay(|(time, _), _| *time)
+    ///         .map(|(_time, key)| (key, 1))
+    ///         .session_window(
+    ///             2,
+    ///             |_key, val, agg| { *agg += val; },
+    ///             |key, first, last, agg: usize| (key, first, last, agg),
+    ///             |key| *key as u64
+    ///         )
+    ///         .capture()
+    /// });
+    ///
+    /// // 'a' is active at times 0 and 1, then again at time 5.
+    /// assert_eq!(captured.extract(), vec![
+    ///     (1, vec![('a', 0, 1, 2)]),
+    ///     (2, vec![('b', 2, 2, 1)]),
+    ///     (5, vec![('a', 5, 5, 1)]),
+    /// ]);
+    /// ```
+    fn session_window<R: Data, D: Default+'static, F: Fn(&K, V, &mut D)+'static, E: Fn(K, S::Timestamp, S::Timestamp, D)->R+'static, H: Fn(&K)->u64+'static>(
+        &self,
+        gap: <S::Timestamp as Timestamp>::Summary,
+        fold: F,
+        emit: E,
+        hash: H) -> Stream<S, R> where S::Timestamp: TotalOrder;
+}
+
+/// The state of an open session.
+struct Session<T: Timestamp, D> {
+    first: T,
+    /// A capability for the timestamp of the session's last record.
+    last: Capability<T>,
+    state: D,
+}
+
+impl<S: Scope, K: ExchangeData+Hash+Eq, V: ExchangeData> SessionWindow<S, K, V> for Stream<S, (K, V)> {
+
+    fn session_window<R: Data, D: Default+'static, F: Fn(&K, V, &mut D)+'static, E: Fn(K, S::Timestamp, S::Timestamp, D)->R+'static, H: Fn(&K)->u64+'static>(
+        &self,
+        gap: <S::Timestamp as Timestamp>::Summary,
+        fold: F,
+        emit: E,
+        hash: H) -> Stream<S, R> where S::Timestamp: TotalOrder {
+
+        // Records not yet applied, by timestamp, with capabilities for their timestamps.
+        let mut pending = BTreeMap::<S::Timestamp, (Capability<S::Timestamp>, Vec<(K, V)>)>::new();
+        let mut sessions = HashMap::<K, Session<S::Timestamp, D>>::new();
+        let mut vector = Vec::new();
+
+        self.unary_frontier(Exchange::new(move |(k, _)| hash(k)), "SessionWindow", move |_capability, _info| move |input, output| {
+
+            input.for_each(|time, data| {
+                data.swap(&mut vector);
+                pending
+                    .entry(time.time().clone())
+                    .or_insert_with(|| (time.retain(), Vec::new()))
+                    .1
+                    .append(&mut vector);
+            });
+
+            let frontier = input.frontier();
+
+            // Apply records at complete timestamps, in timestamp order.
+            while pending.keys().next().map(|time| !frontier.less_equal(time)).unwrap_or(false) {
+                let time = pending.keys().next().unwrap().clone();
+                let (capability, records) = pending.remove(&time).unwrap();
+                for (key, val) in records {
+                    let extends = sessions.get(&key).map(|session| {
+                        gap.results_in(session.last.time()).map(|end| time <= end).unwrap_or(true)
+                    });
+                    if extends == Some(false) {
+                        let session = sessions.remove(&key).unwrap();
+                        let last = session.last.time().clone();
+                        output.session(&session.last).give(emit(key.clone(), session.first, last, session.state));
+                    }
+                    let session = sessions.entry(key.clone()).or_insert_with(|| Session {
+                        first: time.clone(),
+                        last: capability.clone(),
+                        state: Default::default(),
+                    });
+                    session.last = capability.clone();
+                    fold(&key, val, &mut session.state);
+                }
+            }
+
+            // Close sessions that no future record can extend.
+            let closed = sessions
+                .iter()
+                .filter(|(_key, session)| {
+                    gap.results_in(session.last.time())
+                       .map(|end| !frontier.less_equal(&end))
+                       .unwrap_or_else(|| frontier.is_empty())
+                })
+                .map(|(key, _session)| key.clone())
+                .collect::<Vec<_>>();
+            for key in closed {
+                let session = sessions.remove(&key).unwrap();
+                let last = session.last.time().clone();
+                output.session(&session.last).give(emit(key, session.first, last, session.state));
+            }
+        })
+    }
+}
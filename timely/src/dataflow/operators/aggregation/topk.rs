@@ -0,0 +1,154 @@
+//! Selection of the greatest records within each timestamp.
+use std::rc::Rc;
+use std::hash::Hash;
+use std::cmp::{Ordering, Reverse};
+use std::collections::{HashMap, BinaryHeap};
+
+use crate::{Data, ExchangeData};
+use crate::dataflow::{Stream, Scope};
+use crate::dataflow::operators::generic::operator::Operator;
+use crate::dataflow::operators::keyed::hash_key;
+use crate::dataflow::channels::pact::{Pipeline, Exchange, ParallelizationContract};
+
+/// Selection of the greatest records within each timestamp.
+///
+/// Each worker first selects its own greatest records, and only these are exchanged to
+/// select the greatest records overall, so that at most `k` records per timestamp (and
+/// per key) leave each worker.
+pub trait TopK<S: Scope, D: ExchangeData> {
+    /// Produces the `k` records with the greatest values of `by` at each timestamp.
+    ///
+    /// Results are produced at a single worker once their timestamp is complete, in
+    /// decreasing order of `by`. Records with equal values of `by` are selected arbitrarily.
+    ///
+    /// # Examples
+    /// ```
+    /// use timely::dataflow::operators::{ToStream, Capture};
+    /// use timely::dataflow::operators::aggregation::TopK;
+    /// use timely::dataflow::operators::capture::Extract;
+    ///
+    /// let captured = timely::example(|scope| {
+    ///     vec![3, 1, 4, 1, 5, 9, 2, 6]
+    ///         .to_stream(scope)
+    ///         .topk(3, |x| *x)
+    ///         .capture()
+    /// });
+    ///
+    /// assert_eq!(captured.extract(), vec![(0, vec![5, 6, 9])]);
+    /// ```
+    fn topk<O: Ord+'static, B: Fn(&D)->O+'static>(&self, k: usize, by: B) -> Stream<S, D>;
+
+    /// Produces the `k` records with the greatest values of `by` for each key at each timestamp.
+    ///
+    /// Results for a key are produced at the worker its key hashes to once their timestamp
+    /// is complete, in decreasing order of `by`.
+    ///
+    /// # Examples
+    /// ```
+    /// use timely::dataflow::operators::{ToStream, Capture};
+    /// use timely::dataflow::operators::aggregation::TopK;
+    /// use timely::dataflow::operators::capture::Extract;
+    ///
+    /// let captured = timely::example(|scope| {
+    ///     vec![('a', 3), ('b', 1), ('a', 4), ('b', 5), ('a', 9), ('b', 2)]
+    ///         .to_stream(scope)
+    ///         .topk_by_key(2, |x| x.0, |x| x.1)
+    ///         .capture()
+    /// });
+    ///
+    /// assert_eq!(captured.extract(), vec![(0, vec![('a', 4), ('a', 9), ('b', 2), ('b', 5)])]);
+    /// ```
+    fn topk_by_key<K: Hash+Eq+'static, O: Ord+'static, KF: Fn(&D)->K+'static, B: Fn(&D)->O+'static>(
+        &self,
+        k: usize,
+        key: KF,
+        by: B) -> Stream<S, D>;
+}
+
+impl<S: Scope, D: ExchangeData> TopK<S, D> for Stream<S, D> {
+
+    fn topk<O: Ord+'static, B: Fn(&D)->O+'static>(&self, k: usize, by: B) -> Stream<S, D> {
+        let by = Rc::new(by);
+        let local = select_with(self, Pipeline, k, Rc::new(|_: &D| ()), by.clone());
+        select_with(&local, Exchange::new(|_| 0), k, Rc::new(|_: &D| ()), by)
+    }
+
+    fn topk_by_key<K: Hash+Eq+'static, O: Ord+'static, KF: Fn(&D)->K+'static, B: Fn(&D)->O+'static>(
+        &self,
+        k: usize,
+        key: KF,
+        by: B) -> Stream<S, D> {
+
+        let key = Rc::new(key);
+        let by = Rc::new(by);
+        let local = select_with(self, Pipeline, k, key.clone(), by.clone());
+        let route = key.clone();
+        select_with(&local, Exchange::new(move |x| hash_key(&route(x))), k, key, by)
+    }
+}
+
+/// A record ordered by its rank alone.
+struct Ranked<O, D> {
+    rank: O,
+    datum: D,
+}
+
+impl<O: Ord, D> PartialEq for Ranked<O, D> {
+    fn eq(&self, other: &Self) -> bool { self.rank == other.rank }
+}
+impl<O: Ord, D> Eq for Ranked<O, D> { }
+impl<O: Ord, D> PartialOrd for Ranked<O, D> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> { Some(self.cmp(other)) }
+}
+impl<O: Ord, D> Ord for Ranked<O, D> {
+    fn cmp(&self, other: &Self) -> Ordering { self.rank.cmp(&other.rank) }
+}
+
+/// Selects the `k` greatest records by `by` for each key at each time, after distributing
+/// records according to `pact`.
+fn select_with<S, D, K, O, KF, B, P>(stream: &Stream<S, D>, pact: P, k: usize, key: Rc<KF>, by: Rc<B>) -> Stream<S, D>
+where
+    S: Scope,
+    D: Data,
+    K: Hash+Eq+'static,
+    O: Ord+'static,
+    KF: Fn(&D)->K+'static,
+    B: Fn(&D)->O+'static,
+    P: ParallelizationContract<S::Timestamp, D>,
+{
+    // Each heap holds the least of its selected records at its top, to be evicted first.
+    let mut heaps = HashMap::<S::Timestamp, HashMap<K, BinaryHeap<Reverse<Ranked<O, D>>>>>::new();
+    let mut vector = Vec::new();
+    stream.unary_notify(pact, "TopK", vec![], move |input, output, notificator| {
+
+        input.for_each(|time, data| {
+            data.swap(&mut vector);
+            let heaps_time = heaps.entry(time.time().clone()).or_insert_with(HashMap::new);
+            for datum in vector.drain(..) {
+                let heap = heaps_time.entry(key(&datum)).or_insert_with(BinaryHeap::new);
+                let ranked = Ranked { rank: by(&datum), datum };
+                if heap.len() < k {
+                    heap.push(Reverse(ranked));
+                }
+                else if heap.peek().map(|least| least.0 < ranked).unwrap_or(false) {
+                    heap.pop();
+                    heap.push(Reverse(ranked));
+                }
+            }
+            notificator.notify_at(time.retain());
+        });
+
+        notificator.for_each(|time,_,_| {
+            if let Some(heaps_time) = heaps.remove(time.time()) {
+                let mut session = output.session(&time);
+                for (_key, heap) in heaps_time {
+                    let mut selected = heap.into_vec();
+                    selected.sort();
+                    for Reverse(ranked) in selected {
+                        session.give(ranked.datum);
+                    }
+                }
+            }
+        });
+    })
+}
@@ -10,6 +10,8 @@ use crate::ExchangeData;
 // use progress::{Timestamp, Operate, operate::{Schedule, SharedProgress}, Antichain};
 use crate::dataflow::{Stream, Scope};
 use crate::dataflow::operators::{Map, Exchange};
+use crate::dataflow::channels::pact::Broadcast as BroadcastPact;
+use crate::dataflow::operators::generic::operator::Operator;
 
 // use dataflow::channels::{Message, Bundle};
 // use dataflow::channels::pushers::Counter as PushCounter;
@@ -33,6 +35,29 @@ pub trait Broadcast<D: ExchangeData> {
     /// });
     /// ```
     fn broadcast(&self) -> Self;
+
+    /// Broadcast records to all workers, sharing rather than cloning each batch of records.
+    ///
+    /// Each batch is shared among the workers of a process, and serialized once for each worker
+    /// of other processes. Receiving workers clone a shared batch only while other workers still
+    /// hold it, and the last to receive it takes ownership. This is preferable to `broadcast`
+    /// for records that are expensive to clone.
+    ///
+    /// # Examples
+    /// ```
+    /// use timely::dataflow::operators::{ToStream, Broadcast, Capture};
+    /// use timely::dataflow::operators::capture::Extract;
+    ///
+    /// let captured = timely::example(|scope| {
+    ///     (0..10).map(|x| vec![x; 100])
+    ///            .to_stream(scope)
+    ///            .broadcast_shared()
+    ///            .capture()
+    /// });
+    ///
+    /// assert_eq!(captured.extract()[0].1.len(), 10);
+    /// ```
+    fn broadcast_shared(&self) -> Self;
 }
 
 impl<G: Scope, D: ExchangeData> Broadcast<D> for Stream<G, D> {
@@ -79,6 +104,16 @@ impl<G: Scope, D: ExchangeData> Broadcast<D> for Stream<G, D> {
 
         // Stream::new(Source { index: operator_index, port: 0 }, registrar, scope)
     }
+
+    fn broadcast_shared(&self) -> Stream<G, D> {
+        let mut vector = Vec::new();
+        self.unary(BroadcastPact, "BroadcastShared", move |_,_| move |input, output| {
+            input.for_each(|time, data| {
+                data.swap(&mut vector);
+                output.session(&time).give_vec(&mut vector);
+            });
+        })
+    }
 }
 
 // struct BroadcastOperator<T: Timestamp, D: ExchangeData> {
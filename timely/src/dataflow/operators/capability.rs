@@ -35,6 +35,8 @@ pub trait CapabilityTrait<T: Timestamp> {
     /// The timestamp associated with the capability.
     fn time(&self) -> &T;
     fn valid_for_output(&self, query_buffer: &Rc<RefCell<ChangeBatch<T>>>) -> bool;
+    /// Describes the operator and output of the capability, for panic messages, if known.
+    fn context(&self) -> String { String::new() }
 }
 
 impl<'a, T: Timestamp, C: CapabilityTrait<T>> CapabilityTrait<T> for &'a C {
@@ -42,12 +44,32 @@ impl<'a, T: Timestamp, C: CapabilityTrait<T>> CapabilityTrait<T> for &'a C {
     fn valid_for_output(&self, query_buffer: &Rc<RefCell<ChangeBatch<T>>>) -> bool {
         (**self).valid_for_output(query_buffer)
     }
+    fn context(&self) -> String { (**self).context() }
 }
 impl<'a, T: Timestamp, C: CapabilityTrait<T>> CapabilityTrait<T> for &'a mut C {
     fn time(&self) -> &T { (**self).time() }
     fn valid_for_output(&self, query_buffer: &Rc<RefCell<ChangeBatch<T>>>) -> bool {
         (**self).valid_for_output(query_buffer)
     }
+    fn context(&self) -> String { (**self).context() }
+}
+
+/// The operator whose capabilities these are, as named by the `capability-checks` feature.
+#[cfg(feature = "capability-checks")]
+#[derive(Debug)]
+pub(crate) struct Origin {
+    /// The name of the operator.
+    pub name: String,
+    /// The address of the operator.
+    pub address: Vec<usize>,
+}
+
+#[cfg(feature = "capability-checks")]
+impl Origin {
+    // Describes `output` of the operator, for panic messages.
+    pub(crate) fn describe(&self, output: usize) -> String {
+        format!(" (operator {} at address {:?}, output {})", self.name, self.address, output)
+    }
 }
 
 /// The capability to send data with a certain timestamp on a dataflow edge.
@@ -59,6 +81,8 @@ impl<'a, T: Timestamp, C: CapabilityTrait<T>> CapabilityTrait<T> for &'a mut C {
 pub struct Capability<T: Timestamp> {
     time: T,
     internal: Rc<RefCell<ChangeBatch<T>>>,
+    #[cfg(feature = "capability-checks")]
+    origin: Option<(Rc<Origin>, usize)>,
 }
 
 impl<T: Timestamp> CapabilityTrait<T> for Capability<T> {
@@ -66,6 +90,13 @@ impl<T: Timestamp> CapabilityTrait<T> for Capability<T> {
     fn valid_for_output(&self, query_buffer: &Rc<RefCell<ChangeBatch<T>>>) -> bool {
         Rc::ptr_eq(&self.internal, query_buffer)
     }
+    fn context(&self) -> String {
+        #[cfg(feature = "capability-checks")]
+        if let Some((origin, output)) = self.origin.as_ref() {
+            return origin.describe(*output);
+        }
+        String::new()
+    }
 }
 
 impl<T: Timestamp> Capability<T> {
@@ -82,9 +113,9 @@ impl<T: Timestamp> Capability<T> {
     #[inline]
     pub fn delayed(&self, new_time: &T) -> Capability<T> {
         if !self.time.less_equal(new_time) {
-            panic!("Attempted to delay {:?} to {:?}, which is not `less_equal` the capability's time.", self, new_time);
+            panic!("Attempted to delay {:?} to {:?}, which is not `less_equal` the capability's time{}.", self, new_time, self.context());
         }
-        mint(new_time.clone(), self.internal.clone())
+        self.derive(new_time.clone())
     }
 
     /// Downgrades the capability to one corresponding to `new_time`.
@@ -95,6 +126,22 @@ impl<T: Timestamp> Capability<T> {
         let new_cap = self.delayed(new_time);
         *self = new_cap;
     }
+
+    // Mints a capability for `time` on the same output as `self`.
+    fn derive(&self, time: T) -> Capability<T> {
+        #[cfg_attr(not(feature = "capability-checks"), allow(unused_mut))]
+        let mut capability = mint(time, self.internal.clone());
+        #[cfg(feature = "capability-checks")]
+        { capability.origin = self.origin.clone(); }
+        capability
+    }
+
+    /// Records the operator and output the capability is for, to be named by misuse assertions.
+    #[cfg(feature = "capability-checks")]
+    pub(crate) fn with_origin(mut self, origin: Rc<Origin>, output: usize) -> Capability<T> {
+        self.origin = Some((origin, output));
+        self
+    }
 }
 
 /// Creates a new capability at `t` while incrementing (and keeping a reference to) the provided
@@ -106,6 +153,8 @@ pub fn mint<T: Timestamp>(time: T, internal: Rc<RefCell<ChangeBatch<T>>>) -> Cap
     Capability {
         time,
         internal,
+        #[cfg(feature = "capability-checks")]
+        origin: None,
     }
 }
 
@@ -122,7 +171,7 @@ impl<T: Timestamp> Drop for Capability<T> {
 impl<T: Timestamp> Clone for Capability<T> {
     #[inline]
     fn clone(&self) -> Capability<T> {
-        mint(self.time.clone(), self.internal.clone())
+        self.derive(self.time.clone())
     }
 }
 
@@ -169,6 +218,8 @@ impl<T: Timestamp> ::std::hash::Hash for Capability<T> {
 pub struct CapabilityRef<'cap, T: Timestamp+'cap> {
     time: &'cap T,
     internal: Rc<RefCell<Vec<Rc<RefCell<ChangeBatch<T>>>>>>,
+    #[cfg(feature = "capability-checks")]
+    origin: Option<Rc<Origin>>,
 }
 
 impl<'cap, T: Timestamp+'cap> CapabilityTrait<T> for CapabilityRef<'cap, T> {
@@ -177,6 +228,13 @@ impl<'cap, T: Timestamp+'cap> CapabilityTrait<T> for CapabilityRef<'cap, T> {
         // let borrow = ;
         self.internal.borrow().iter().any(|rc| Rc::ptr_eq(rc, query_buffer))
     }
+    fn context(&self) -> String {
+        #[cfg(feature = "capability-checks")]
+        if let Some(origin) = self.origin.as_ref() {
+            return format!(" (operator {} at address {:?})", origin.name, origin.address);
+        }
+        String::new()
+    }
 }
 
 impl<'cap, T: Timestamp+'cap> CapabilityRef<'cap, T> {
@@ -199,14 +257,9 @@ impl<'cap, T: Timestamp+'cap> CapabilityRef<'cap, T> {
     pub fn delayed_for_output(&self, new_time: &T, output_port: usize) -> Capability<T> {
         // TODO : Test operator summary?
         if !self.time.less_equal(new_time) {
-            panic!("Attempted to delay {:?} to {:?}, which is not `less_equal` the capability's time.", self, new_time);
-        }
-        if output_port < self.internal.borrow().len() {
-            mint(new_time.clone(), self.internal.borrow()[output_port].clone())
-        }
-        else {
-            panic!("Attempted to acquire a capability for a non-existent output port.");
+            panic!("Attempted to delay {:?} to {:?}, which is not `less_equal` the capability's time{}.", self, new_time, self.context());
         }
+        self.mint_for_output(new_time.clone(), output_port)
     }
 
     /// Transform to an owned capability.
@@ -222,12 +275,29 @@ impl<'cap, T: Timestamp+'cap> CapabilityRef<'cap, T> {
 
     /// Transforms to an owned capability for a specific output port.
     pub fn retain_for_output(self, output_port: usize) -> Capability<T> {
-        if output_port < self.internal.borrow().len() {
-            mint(self.time.clone(), self.internal.borrow()[output_port].clone())
-        }
-        else {
-            panic!("Attempted to acquire a capability for a non-existent output port.");
+        self.mint_for_output(self.time.clone(), output_port)
+    }
+
+    // Mints a capability for `time` on `output_port`.
+    fn mint_for_output(&self, time: T, output_port: usize) -> Capability<T> {
+        let outputs = self.internal.borrow().len();
+        if output_port >= outputs {
+            panic!("Attempted to acquire a capability for non-existent output port {} of {}{}.", output_port, outputs, self.context());
         }
+        let capability = mint(time, self.internal.borrow()[output_port].clone());
+        #[cfg(feature = "capability-checks")]
+        let capability = match self.origin.as_ref() {
+            Some(origin) => capability.with_origin(origin.clone(), output_port),
+            None => capability,
+        };
+        capability
+    }
+
+    /// Records the operator the capability is for, to be named by misuse assertions.
+    #[cfg(feature = "capability-checks")]
+    pub(crate) fn with_origin(mut self, origin: Option<Rc<Origin>>) -> Self {
+        self.origin = origin;
+        self
     }
 }
 
@@ -254,6 +324,8 @@ pub fn mint_ref<'cap, T: Timestamp>(time: &'cap T, internal: Rc<RefCell<Vec<Rc<R
     CapabilityRef {
         time,
         internal,
+        #[cfg(feature = "capability-checks")]
+        origin: None,
     }
 }
 
@@ -281,7 +353,14 @@ impl<T: Timestamp> CapabilitySet<T> {
     ///
     /// This method panics if there does not exist a capability in `self.elements` less or equal to `time`.
     pub fn delayed(&self, time: &T) -> Capability<T> {
-        self.elements.iter().find(|c| c.time().less_equal(time)).unwrap().delayed(time)
+        match self.elements.iter().find(|c| c.time().less_equal(time)) {
+            Some(capability) => capability.delayed(time),
+            None => {
+                let times = self.elements.iter().map(|c| c.time()).collect::<Vec<_>>();
+                let context = self.elements.first().map(|c| c.context()).unwrap_or_default();
+                panic!("Attempted to delay capabilities for {:?} to {:?}, which is not greater or equal to any of them{}.", times, time, context);
+            },
+        }
     }
 
     /// Downgrades the set of capabilities to correspond with the times in `frontier`.
@@ -111,6 +111,55 @@ pub trait Capture<T: Timestamp, D: Data> {
         self.capture_into(send);
         recv
     }
+
+    /// Captures a stream into a blocking iterator over its timestamped batches.
+    ///
+    /// The returned `CaptureIter` yields `(time, data)` pairs in the order they were
+    /// produced, and ends once the capturing operator has been shut down. At most
+    /// `capacity` events are buffered; once the buffer is full the worker blocks until
+    /// the iterator catches up, so the iterator must be drained on another thread.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use timely::dataflow::operators::{Capture, ToStream};
+    ///
+    /// let results = timely::execute(timely::Configuration::Thread, |worker| {
+    ///     let iter = worker.dataflow::<u64,_,_>(|scope|
+    ///         (0..1000u64)
+    ///             .to_stream(scope)
+    ///             .capture_into_iter(4)
+    ///     );
+    ///     std::thread::spawn(move || iter.flat_map(|(_time, data)| data).sum::<u64>())
+    /// }).unwrap().join();
+    ///
+    /// for result in results {
+    ///     assert_eq!(result.unwrap().join().unwrap(), 499500);
+    /// }
+    /// ```
+    fn capture_into_iter(&self, capacity: usize) -> CaptureIter<T, D> {
+        let (send, recv) = ::std::sync::mpsc::sync_channel(capacity);
+        self.capture_into(send);
+        CaptureIter { recv }
+    }
+}
+
+/// A blocking iterator over the timestamped batches of a captured stream.
+///
+/// Produced by `Capture::capture_into_iter`, and sendable to other threads.
+pub struct CaptureIter<T, D> {
+    recv: ::std::sync::mpsc::Receiver<Event<T, D>>,
+}
+
+impl<T, D> Iterator for CaptureIter<T, D> {
+    type Item = (T, Vec<D>);
+    fn next(&mut self) -> Option<Self::Item> {
+        // progress updates are skipped; the channel disconnects when the operator is dropped.
+        self.recv.iter().find_map(|event| match event {
+            Event::Messages(time, data) => Some((time, data)),
+            Event::Progress(_) => None,
+        })
+    }
 }
 
 impl<S: Scope, D: Data> Capture<S::Timestamp, D> for Stream<S, D> {
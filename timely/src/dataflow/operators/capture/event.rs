@@ -41,6 +41,14 @@ impl<T, D> EventPusher<T, D> for ::std::sync::mpsc::Sender<Event<T, D>> {
     }
 }
 
+// implementation for bounded MPSC channels, which block while the buffer is full.
+impl<T, D> EventPusher<T, D> for ::std::sync::mpsc::SyncSender<Event<T, D>> {
+    fn push(&mut self, event: Event<T, D>) {
+        // NOTE: As above, an Err(x) result just means the receiver is gone.
+        let _ = self.send(event);
+    }
+}
+
 /// A linked-list event pusher and iterator.
 pub mod link {
 
@@ -111,16 +119,124 @@ pub mod link {
     }
 }
 
+#[cfg(feature = "arrow")]
+pub mod arrow;
+#[cfg(feature = "avro")]
+pub mod avro;
+
 /// A binary event pusher and iterator.
+///
+/// Streams written by `EventWriter` begin with a `Header`, which records the version of the
+/// format and the names of the timestamp and data types, and continue with the events each
+/// encoded by `abomonation`. `EventReader` reads streams of any supported version, including
+/// those written before headers were introduced, which it reports as version zero.
 pub mod binary {
 
     use std::io::Write;
     use abomonation::Abomonation;
     use super::{Event, EventPusher, EventIterator};
 
+    /// Bytes that begin each stream written with a header.
+    pub const MAGIC: [u8; 8] = *b"TIMELYEV";
+
+    /// The version of the format written by `EventWriter`.
+    pub const VERSION: u32 = 1;
+
+    /// Headers are padded to a multiple of this many bytes, to align the events that follow.
+    const ALIGN: usize = 16;
+
+    /// A description of a stream of binary events.
+    #[derive(Debug, Clone, Hash, Eq, PartialEq)]
+    pub struct Header {
+        /// The version of the format, where zero indicates a stream without a header.
+        pub version: u32,
+        /// The name of the timestamp type, as reported by the writer.
+        pub time: String,
+        /// The name of the data type, as reported by the writer.
+        pub data: String,
+    }
+
+    impl Header {
+        /// Describes the current format, for timestamps `T` and data `D`.
+        pub fn new<T, D>() -> Self {
+            Header {
+                version: VERSION,
+                time: ::std::any::type_name::<T>().to_owned(),
+                data: ::std::any::type_name::<D>().to_owned(),
+            }
+        }
+
+        /// Writes the header, as it should appear at the start of a stream.
+        pub fn write_to<W: Write>(&self, writer: &mut W) -> ::std::io::Result<()> {
+            writer.write_all(&MAGIC)?;
+            writer.write_all(&self.version.to_le_bytes())?;
+            let mut length = MAGIC.len() + 4;
+            for name in [&self.time, &self.data].iter() {
+                writer.write_all(&(name.len() as u32).to_le_bytes())?;
+                writer.write_all(name.as_bytes())?;
+                length += 4 + name.len();
+            }
+            writer.write_all(&[0u8; ALIGN][.. padding(length)])?;
+            Ok(())
+        }
+
+        /// Reads a header from the start of `bytes`, returning it and the number of bytes read.
+        ///
+        /// The result is `Ok(None)` if `bytes` holds only a prefix of a header, and an error
+        /// if `bytes` does not begin with `MAGIC`. Headers of unsupported versions are errors.
+        pub fn read_from(bytes: &[u8]) -> Result<Option<(Self, usize)>, String> {
+            if bytes.len() < MAGIC.len() {
+                return if MAGIC.starts_with(bytes) { Ok(None) } else { Err("Missing header".to_owned()) };
+            }
+            if bytes[..MAGIC.len()] != MAGIC {
+                return Err("Missing header".to_owned());
+            }
+            let mut offset = MAGIC.len();
+            let version = match read_u32(bytes, &mut offset) { Some(x) => x, None => return Ok(None) };
+            if version == 0 || version > VERSION {
+                return Err(format!("Unsupported event stream version: {}", version));
+            }
+            let mut names = Vec::with_capacity(2);
+            for _ in 0 .. 2 {
+                let len = match read_u32(bytes, &mut offset) { Some(x) => x as usize, None => return Ok(None) };
+                if bytes.len() < offset + len {
+                    return Ok(None);
+                }
+                names.push(String::from_utf8_lossy(&bytes[offset .. offset + len]).into_owned());
+                offset += len;
+            }
+            offset += padding(offset);
+            if bytes.len() < offset {
+                return Ok(None);
+            }
+            let data = names.pop().unwrap();
+            let time = names.pop().unwrap();
+            Ok(Some((Header { version, time, data }, offset)))
+        }
+    }
+
+    // The number of bytes needed to round `length` up to a multiple of `ALIGN`.
+    fn padding(length: usize) -> usize {
+        (ALIGN - length % ALIGN) % ALIGN
+    }
+
+    // Reads a little-endian `u32` at `offset`, advancing it, if enough bytes are present.
+    fn read_u32(bytes: &[u8], offset: &mut usize) -> Option<u32> {
+        if bytes.len() < *offset + 4 {
+            return None;
+        }
+        let mut word = [0u8; 4];
+        word.copy_from_slice(&bytes[*offset .. *offset + 4]);
+        *offset += 4;
+        Some(u32::from_le_bytes(word))
+    }
+
     /// A wrapper for `W: Write` implementing `EventPusher<T, D>`.
+    ///
+    /// The header is written before the first event.
     pub struct EventWriter<T, D, W: ::std::io::Write> {
         stream: W,
+        header: bool,
         phant: ::std::marker::PhantomData<(T,D)>,
     }
 
@@ -129,6 +245,7 @@ pub mod binary {
         pub fn new(w: W) -> EventWriter<T, D, W> {
             EventWriter {
                 stream: w,
+                header: false,
                 phant: ::std::marker::PhantomData,
             }
         }
@@ -137,13 +254,34 @@ pub mod binary {
     impl<T: Abomonation, D: Abomonation, W: ::std::io::Write> EventPusher<T, D> for EventWriter<T, D, W> {
         fn push(&mut self, event: Event<T, D>) {
             // TODO: `push` has no mechanism to report errors, so we `unwrap`.
+            if !self.header {
+                Header::new::<T, D>().write_to(&mut self.stream).expect("Event header write failed");
+                self.header = true;
+            }
             unsafe { ::abomonation::encode(&event, &mut self.stream).expect("Event abomonation/write failed"); }
         }
     }
 
     /// A Wrapper for `R: Read` implementing `EventIterator<T, D>`.
+    ///
+    /// # Examples
+    /// ```
+    /// use timely::dataflow::operators::capture::{Event, EventPusher, EventReader, EventWriter};
+    /// use timely::dataflow::operators::capture::event::EventIterator;
+    ///
+    /// let mut bytes = Vec::new();
+    /// EventWriter::<u64, String, _>::new(&mut bytes).push(Event::Messages(0, vec!["hello".to_string()]));
+    ///
+    /// let mut reader = EventReader::<u64, String, _>::new(&bytes[..]);
+    /// let mut event = reader.next();
+    /// while event.is_none() { event = reader.next(); }
+    /// assert_eq!(event, Some(&Event::Messages(0, vec!["hello".to_string()])));
+    /// assert_eq!(reader.header().unwrap().version, 1);
+    /// assert_eq!(reader.header().unwrap().data, "alloc::string::String");
+    /// ```
     pub struct EventReader<T, D, R: ::std::io::Read> {
         reader: R,
+        header: Option<Header>,
         bytes: Vec<u8>,
         buff1: Vec<u8>,
         buff2: Vec<u8>,
@@ -157,6 +295,7 @@ pub mod binary {
         pub fn new(r: R) -> EventReader<T, D, R> {
             EventReader {
                 reader: r,
+                header: None,
                 bytes: vec![0u8; 1 << 20],
                 buff1: vec![],
                 buff2: vec![],
@@ -165,16 +304,47 @@ pub mod binary {
                 phant: ::std::marker::PhantomData,
             }
         }
+
+        /// The header of the stream, once enough of the stream has been read to determine it.
+        ///
+        /// Streams written without a header are reported with version zero and empty type names.
+        pub fn header(&self) -> Option<&Header> {
+            self.header.as_ref()
+        }
+
+        // Determines the header from the buffered bytes, if possible, and consumes it.
+        fn read_header(&mut self) {
+            match Header::read_from(&self.buff1[self.consumed..]) {
+                Ok(Some((header, length))) => {
+                    self.consumed += length;
+                    self.header = Some(header);
+                },
+                Ok(None) => { },
+                Err(error) => {
+                    if self.buff1[self.consumed..].starts_with(&MAGIC) {
+                        panic!("{}", error);
+                    }
+                    self.header = Some(Header { version: 0, time: String::new(), data: String::new() });
+                },
+            }
+        }
     }
 
     impl<T: Abomonation, D: Abomonation, R: ::std::io::Read> EventIterator<T, D> for EventReader<T, D, R> {
         fn next(&mut self) -> Option<&Event<T, D>> {
 
-            // if we can decode something, we should just return it! :D
-            if unsafe { ::abomonation::decode::<Event<T,D>>(&mut self.buff1[self.consumed..]) }.is_some() {
-                let (item, rest) = unsafe { ::abomonation::decode::<Event<T,D>>(&mut self.buff1[self.consumed..]) }.unwrap();
-                self.consumed = self.valid - rest.len();
-                return Some(item);
+            if self.header.is_none() {
+                self.read_header();
+            }
+
+            // Versions zero and one share the encoding of events.
+            if self.header.is_some() {
+                // if we can decode something, we should just return it! :D
+                if unsafe { ::abomonation::decode::<Event<T,D>>(&mut self.buff1[self.consumed..]) }.is_some() {
+                    let (item, rest) = unsafe { ::abomonation::decode::<Event<T,D>>(&mut self.buff1[self.consumed..]) }.unwrap();
+                    self.consumed = self.valid - rest.len();
+                    return Some(item);
+                }
             }
             // if we exhaust data we should shift back (if any shifting to do)
             if self.consumed > 0 {
@@ -193,4 +363,57 @@ pub mod binary {
             None
         }
     }
+
+    #[cfg(test)]
+    mod tests {
+
+        use super::{EventReader, EventWriter};
+        use super::super::{Event, EventPusher, EventIterator};
+
+        // Reads all events from a reader, which must eventually produce them.
+        fn read_all(reader: &mut EventReader<u64, u64, &[u8]>, count: usize) -> Vec<Event<u64, u64>> {
+            let mut events = Vec::new();
+            while events.len() < count {
+                if let Some(event) = reader.next() {
+                    events.push(event.clone());
+                }
+            }
+            events
+        }
+
+        #[test]
+        fn reads_streams_without_headers() {
+            let events = vec![Event::Progress(vec![(0, 1)]), Event::Messages(0, vec![1, 2, 3])];
+            let mut bytes = Vec::new();
+            for event in events.iter() {
+                unsafe { ::abomonation::encode(event, &mut bytes).unwrap(); }
+            }
+            let mut reader = EventReader::<u64, u64, _>::new(&bytes[..]);
+            assert_eq!(read_all(&mut reader, 2), events);
+            assert_eq!(reader.header().map(|h| h.version), Some(0));
+        }
+
+        #[test]
+        fn reads_streams_with_headers() {
+            let events = vec![Event::Progress(vec![(0, 1)]), Event::Messages(0, vec![1, 2, 3])];
+            let mut bytes = Vec::new();
+            let mut writer = EventWriter::new(&mut bytes);
+            for event in events.iter() {
+                writer.push(event.clone());
+            }
+            let mut reader = EventReader::<u64, u64, _>::new(&bytes[..]);
+            assert_eq!(read_all(&mut reader, 2), events);
+            assert_eq!(reader.header().map(|h| h.version), Some(super::VERSION));
+            assert_eq!(reader.header().map(|h| &h.time[..]), Some("u64"));
+        }
+
+        #[test]
+        #[should_panic(expected = "Unsupported event stream version")]
+        fn rejects_unknown_versions() {
+            let mut bytes = super::MAGIC.to_vec();
+            bytes.extend_from_slice(&(super::VERSION + 1).to_le_bytes());
+            let mut reader = EventReader::<u64, u64, _>::new(&bytes[..]);
+            for _ in 0 .. 3 { reader.next(); }
+        }
+    }
 }
@@ -0,0 +1,144 @@
+//! An event pusher writing Arrow IPC streams.
+//!
+//! `ArrowEventWriter` writes the data of captured streams of `RecordBatch`es as one Arrow IPC
+//! stream, and their progress as another, so that captures can be read by any Arrow library.
+//! Each data batch is written with an additional `timely_time` column, holding the time of the
+//! batch. Each progress event is written as a batch of the `progress_schema`, whose rows are the
+//! changes to the counts of times, and whose `batches` column records the number of data batches
+//! written before the event, which orders the two streams with respect to each other.
+
+use std::io::Write;
+use std::sync::Arc;
+
+use arrow_array::{ArrayRef, Int64Array, RecordBatch, UInt64Array};
+use arrow_ipc::writer::StreamWriter;
+use arrow_schema::{DataType, Field, Schema, SchemaRef};
+
+use super::{Event, EventPusher};
+
+/// The name of the column holding the times of data batches.
+pub const TIME_COLUMN: &str = "timely_time";
+
+/// The schema of the progress stream: the time, the change to its count, and the number of
+/// data batches written before the event.
+pub fn progress_schema() -> SchemaRef {
+    Arc::new(Schema::new(vec![
+        Field::new("time", DataType::UInt64, false),
+        Field::new("diff", DataType::Int64, false),
+        Field::new("batches", DataType::UInt64, false),
+    ]))
+}
+
+/// Writes captured events as Arrow IPC streams of data and of progress.
+///
+/// Times are written as `u64`. The streams are finished when the writer is dropped, or by
+/// `finish`. As with `EventWriter`, failures to write panic.
+///
+/// # Examples
+/// ```
+/// use std::sync::Arc;
+/// use arrow_array::{Array, Int64Array, RecordBatch, UInt64Array};
+/// use arrow_ipc::reader::StreamReader;
+/// use arrow_schema::{DataType, Field, Schema};
+/// use timely::dataflow::operators::capture::{Event, EventPusher};
+/// use timely::dataflow::operators::capture::event::arrow::ArrowEventWriter;
+///
+/// let schema = Arc::new(Schema::new(vec![Field::new("value", DataType::Int64, false)]));
+/// let batch = RecordBatch::try_new(schema.clone(), vec![Arc::new(Int64Array::from(vec![1, 2, 3]))]).unwrap();
+///
+/// let (mut data, mut progress) = (Vec::new(), Vec::new());
+/// let mut writer = ArrowEventWriter::<u64, _>::new(schema, &mut data, &mut progress);
+/// writer.push(Event::Messages(5, vec![batch]));
+/// writer.push(Event::Progress(vec![(5, -1), (6, 1)]));
+/// writer.finish();
+/// drop(writer);
+///
+/// let batches = StreamReader::try_new(&data[..], None).unwrap().map(|b| b.unwrap()).collect::<Vec<_>>();
+/// let times = batches[0].column_by_name("timely_time").unwrap().as_any().downcast_ref::<UInt64Array>().unwrap();
+/// assert_eq!(times.values(), &[5, 5, 5]);
+///
+/// let updates = StreamReader::try_new(&progress[..], None).unwrap().map(|b| b.unwrap()).collect::<Vec<_>>();
+/// assert_eq!(updates[0].num_rows(), 2);
+/// ```
+pub struct ArrowEventWriter<T, W: Write> {
+    // The schema of data batches, including the time column.
+    schema: SchemaRef,
+    data: Option<StreamWriter<W>>,
+    progress: Option<StreamWriter<W>>,
+    // The number of data batches written.
+    batches: u64,
+    phant: ::std::marker::PhantomData<T>,
+}
+
+impl<T, W: Write> ArrowEventWriter<T, W> {
+    /// Writes data batches of schema `schema` to `data`, and progress to `progress`.
+    ///
+    /// The schema of the data stream extends `schema` with the time column, and records the
+    /// name of the timestamp type as the `timely.time` metadata.
+    pub fn new(schema: SchemaRef, data: W, progress: W) -> Self {
+        let mut fields = schema.fields().iter().cloned().collect::<Vec<_>>();
+        fields.push(Arc::new(Field::new(TIME_COLUMN, DataType::UInt64, false)));
+        let mut metadata = schema.metadata().clone();
+        metadata.insert("timely.time".to_owned(), ::std::any::type_name::<T>().to_owned());
+        let schema = Arc::new(Schema::new_with_metadata(fields, metadata));
+
+        ArrowEventWriter {
+            data: Some(StreamWriter::try_new(data, &schema).expect("Arrow data stream write failed")),
+            progress: Some(StreamWriter::try_new(progress, &progress_schema()).expect("Arrow progress stream write failed")),
+            schema,
+            batches: 0,
+            phant: ::std::marker::PhantomData,
+        }
+    }
+
+    /// Finishes both streams, after which further events are discarded.
+    pub fn finish(&mut self) {
+        if let Some(mut data) = self.data.take() {
+            data.finish().expect("Arrow data stream write failed");
+        }
+        if let Some(mut progress) = self.progress.take() {
+            progress.finish().expect("Arrow progress stream write failed");
+        }
+    }
+}
+
+impl<T: Clone+Into<u64>, W: Write> EventPusher<T, RecordBatch> for ArrowEventWriter<T, W> {
+    fn push(&mut self, event: Event<T, RecordBatch>) {
+        match event {
+            Event::Messages(time, batches) => {
+                if let Some(data) = self.data.as_mut() {
+                    let time: u64 = time.into();
+                    for batch in batches {
+                        let mut columns = batch.columns().to_vec();
+                        columns.push(Arc::new(UInt64Array::from(vec![time; batch.num_rows()])) as ArrayRef);
+                        let batch = RecordBatch::try_new(self.schema.clone(), columns).expect("Arrow batch does not match schema");
+                        data.write(&batch).expect("Arrow data stream write failed");
+                        self.batches += 1;
+                    }
+                }
+            },
+            Event::Progress(updates) => {
+                if let Some(progress) = self.progress.as_mut() {
+                    if !updates.is_empty() {
+                        let batches = UInt64Array::from(vec![self.batches; updates.len()]);
+                        let (times, diffs): (Vec<u64>, Vec<i64>) = updates.into_iter().map(|(time, diff)| (time.into(), diff)).unzip();
+                        let columns: Vec<ArrayRef> = vec![Arc::new(UInt64Array::from(times)), Arc::new(Int64Array::from(diffs)), Arc::new(batches)];
+                        let batch = RecordBatch::try_new(progress_schema(), columns).expect("Arrow progress batch invalid");
+                        progress.write(&batch).expect("Arrow progress stream write failed");
+                    }
+                }
+            },
+        }
+    }
+}
+
+impl<T, W: Write> Drop for ArrowEventWriter<T, W> {
+    fn drop(&mut self) {
+        if let Some(mut data) = self.data.take() {
+            let _ = data.finish();
+        }
+        if let Some(mut progress) = self.progress.take() {
+            let _ = progress.finish();
+        }
+    }
+}
@@ -0,0 +1,210 @@
+//! An event pusher and iterator using Avro object container files.
+//!
+//! `AvroEventWriter` writes the events of a captured stream as records of an Avro container file,
+//! whose schema `event_schema` derives from Avro schemas of the timestamp and data types, so that
+//! captures can be read by the Avro libraries of other ecosystems. Each record has the fields
+//!
+//! * `times`, an array of times: the time of a message, or the times of a progress update,
+//! * `diffs`, an array of the changes to the counts of `times` for progress updates, and empty
+//!   for messages, and
+//! * `data`, the records of a message, or null for progress updates.
+//!
+//! Timestamps and data are encoded through their `serde` implementations, which must agree with
+//! the supplied schemas. The data schema may be registered with a schema registry, in which case
+//! `AvroEventWriter::with_registry` records its subject and identifier in the metadata of the file,
+//! as the `schema.registry.subject` and `schema.registry.id` entries, and `AvroEventReader::registry`
+//! reports them. The writer and reader do not contact the registry themselves.
+//!
+//! # Examples
+//! ```
+//! use apache_avro::Schema;
+//! use timely::dataflow::operators::capture::{Event, EventPusher};
+//! use timely::dataflow::operators::capture::event::EventIterator;
+//! use timely::dataflow::operators::capture::event::avro::{AvroEventReader, AvroEventWriter, SchemaReference};
+//!
+//! let mut bytes = Vec::new();
+//! let mut writer = AvroEventWriter::<u64, String, _>::new(&Schema::Long, &Schema::String, &mut bytes)
+//!     .with_registry("events-value", 7);
+//! writer.push(Event::Progress(vec![(0, 1)]));
+//! writer.push(Event::Messages(0, vec!["hello".to_string()]));
+//! writer.push(Event::Progress(vec![(0, -1)]));
+//! drop(writer);
+//!
+//! let mut reader = AvroEventReader::<u64, String, _>::new(&bytes[..]).unwrap();
+//! assert_eq!(reader.registry(), Some(SchemaReference { subject: "events-value".to_string(), id: 7 }));
+//! assert_eq!(reader.next(), Some(&Event::Progress(vec![(0, 1)])));
+//! assert_eq!(reader.next(), Some(&Event::Messages(0, vec!["hello".to_string()])));
+//! assert_eq!(reader.next(), Some(&Event::Progress(vec![(0, -1)])));
+//! assert_eq!(reader.next(), None);
+//! ```
+
+use std::collections::HashMap;
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+use std::io::{Read, Write};
+
+use apache_avro::{from_value, Reader, Schema, Writer};
+use apache_avro::types::Value;
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+
+use super::{Event, EventPusher, EventIterator};
+
+/// The metadata entry naming the registry subject of the data schema.
+pub const REGISTRY_SUBJECT: &str = "schema.registry.subject";
+/// The metadata entry holding the registry identifier of the data schema, in decimal.
+pub const REGISTRY_ID: &str = "schema.registry.id";
+
+/// The schema of the records of a capture, for timestamps and data of schemas `time` and `data`.
+pub fn event_schema(time: &Schema, data: &Schema) -> Schema {
+    let time = serde_json::to_string(time).expect("Avro time schema invalid");
+    let data = serde_json::to_string(data).expect("Avro data schema invalid");
+    let json = format!(r#"{{
+        "type": "record", "name": "Event", "namespace": "timely",
+        "fields": [
+            {{ "name": "times", "type": {{ "type": "array", "items": {} }} }},
+            {{ "name": "diffs", "type": {{ "type": "array", "items": "long" }} }},
+            {{ "name": "data", "type": ["null", {{ "type": "array", "items": {} }}] }}
+        ]
+    }}"#, time, data);
+    Schema::parse_str(&json).expect("Avro event schema invalid")
+}
+
+/// A data schema registered with a schema registry.
+#[derive(Debug, Clone, Hash, Eq, PartialEq)]
+pub struct SchemaReference {
+    /// The subject under which the schema is registered.
+    pub subject: String,
+    /// The identifier the registry assigned to the schema.
+    pub id: u32,
+}
+
+// The fields of a record of a capture.
+#[derive(Serialize, Deserialize)]
+struct Record<T, D> {
+    times: Vec<T>,
+    diffs: Vec<i64>,
+    data: Option<Vec<D>>,
+}
+
+/// Writes captured events as records of an Avro container file.
+///
+/// Each event is written as a block of the file, so that events are visible to readers as
+/// they are pushed. As with `EventWriter`, failures to write panic.
+pub struct AvroEventWriter<T, D, W: Write> {
+    schema: Schema,
+    stream: W,
+    // The synchronization marker of the file, which ends each block.
+    marker: [u8; 16],
+    // Metadata to write with the header, until it has been written.
+    metadata: Option<HashMap<String, Value>>,
+    phant: ::std::marker::PhantomData<(T, D)>,
+}
+
+impl<T, D, W: Write> AvroEventWriter<T, D, W> {
+    /// Writes events with timestamps and data of schemas `time` and `data` to `w`.
+    pub fn new(time: &Schema, data: &Schema, w: W) -> Self {
+        AvroEventWriter {
+            schema: event_schema(time, data),
+            stream: w,
+            marker: sync_marker(),
+            metadata: Some(HashMap::new()),
+            phant: ::std::marker::PhantomData,
+        }
+    }
+
+    /// Records that the data schema is registered under `subject` with identifier `id`.
+    ///
+    /// Must be called before the first event is pushed.
+    pub fn with_registry(mut self, subject: &str, id: u32) -> Self {
+        let metadata = self.metadata.as_mut().expect("Avro header already written");
+        metadata.insert(REGISTRY_SUBJECT.to_owned(), Value::Bytes(subject.as_bytes().to_vec()));
+        metadata.insert(REGISTRY_ID.to_owned(), Value::Bytes(id.to_string().into_bytes()));
+        self
+    }
+}
+
+impl<T: Serialize, D: Serialize, W: Write> EventPusher<T, D> for AvroEventWriter<T, D, W> {
+    fn push(&mut self, event: Event<T, D>) {
+        let record = match event {
+            Event::Messages(time, data) => Record { times: vec![time], diffs: Vec::new(), data: Some(data) },
+            Event::Progress(updates) => {
+                let (times, diffs) = updates.into_iter().unzip();
+                Record { times, diffs, data: None }
+            },
+        };
+        // Writers borrow their schema, and so are constructed for each event, with the header
+        // written by the first of them and the marker shared by all of them.
+        let mut writer = match self.metadata.take() {
+            Some(metadata) => {
+                Writer::builder()
+                    .schema(&self.schema)
+                    .writer(&mut self.stream)
+                    .marker(self.marker)
+                    .user_metadata(metadata)
+                    .build()
+            },
+            None => Writer::append_to(&self.schema, &mut self.stream, self.marker),
+        };
+        writer.append_ser(record).expect("Avro event write failed");
+        writer.into_inner().expect("Avro event write failed");
+    }
+}
+
+/// Reads captured events from an Avro container file, as written by `AvroEventWriter`.
+///
+/// The reader blocks on the underlying reader, and reports no further events once the file
+/// is exhausted. Records that do not match the types of timestamps and data panic.
+pub struct AvroEventReader<T, D, R: Read> {
+    reader: Reader<'static, R>,
+    registry: Option<SchemaReference>,
+    event: Option<Event<T, D>>,
+}
+
+impl<T, D, R: Read> AvroEventReader<T, D, R> {
+    /// Reads events from `r`, after reading the header of the file.
+    pub fn new(r: R) -> Result<Self, apache_avro::Error> {
+        let reader = Reader::new(r)?;
+        let metadata = reader.user_metadata();
+        let subject = metadata.get(REGISTRY_SUBJECT).map(|bytes| String::from_utf8_lossy(bytes).into_owned());
+        let id = metadata.get(REGISTRY_ID).and_then(|bytes| String::from_utf8_lossy(bytes).parse().ok());
+        let registry = match (subject, id) {
+            (Some(subject), Some(id)) => Some(SchemaReference { subject, id }),
+            _ => None,
+        };
+        Ok(AvroEventReader { reader, registry, event: None })
+    }
+
+    /// The registered data schema the file references, if any.
+    pub fn registry(&self) -> Option<SchemaReference> {
+        self.registry.clone()
+    }
+
+    /// The schema with which the file was written.
+    pub fn schema(&self) -> &Schema {
+        self.reader.writer_schema()
+    }
+}
+
+impl<T: DeserializeOwned, D: DeserializeOwned, R: Read> EventIterator<T, D> for AvroEventReader<T, D, R> {
+    fn next(&mut self) -> Option<&Event<T, D>> {
+        let value = self.reader.next()?.expect("Avro event read failed");
+        let record: Record<T, D> = from_value(&value).expect("Avro event does not match types");
+        self.event = Some(match record.data {
+            Some(data) => Event::Messages(record.times.into_iter().next().expect("Avro message without time"), data),
+            None => Event::Progress(record.times.into_iter().zip(record.diffs).collect()),
+        });
+        self.event.as_ref()
+    }
+}
+
+// A synchronization marker, which should be unlikely to occur within the blocks of the file.
+fn sync_marker() -> [u8; 16] {
+    let mut marker = [0u8; 16];
+    for half in marker.chunks_mut(8) {
+        let mut hasher = RandomState::new().build_hasher();
+        hasher.write_u64(crate::time::SystemTime::now().duration_since(crate::time::UNIX_EPOCH).map(|d| d.as_nanos() as u64).unwrap_or(0));
+        half.copy_from_slice(&hasher.finish().to_le_bytes());
+    }
+    marker
+}
@@ -73,7 +73,7 @@
 //! }).unwrap();
 //! ```
 
-pub use self::capture::Capture;
+pub use self::capture::{Capture, CaptureIter};
 pub use self::replay::Replay;
 pub use self::extract::Extract;
 pub use self::event::{Event, EventPusher};
@@ -1,7 +1,9 @@
 //! Merges the contents of multiple streams.
 
+use std::collections::BTreeMap;
 
 use crate::Data;
+use crate::order::TotalOrder;
 use crate::dataflow::channels::pact::Pipeline;
 use crate::dataflow::{Stream, Scope};
 
@@ -96,3 +98,90 @@ impl<G: Scope, D: Data> Concatenate<G, D> for G {
         result
     }
 }
+
+/// Merge the contents of multiple streams, in timestamp order.
+pub trait MergeSorted<G: Scope, D: Data> {
+    /// Merge the contents of multiple streams, producing records in timestamp order.
+    ///
+    /// Records are buffered until all inputs have completed their timestamps, and are then
+    /// produced one timestamp at a time, in order. Records at the same timestamp are produced
+    /// in no particular order. Unlike `concatenate`, downstream operators observe batches in
+    /// timestamp order, at the cost of buffering records until their timestamps complete.
+    ///
+    /// # Examples
+    /// ```
+    /// use timely::dataflow::operators::{ToStream, MergeSorted, Inspect};
+    /// use timely::dataflow::operators::delay::Delay;
+    ///
+    /// timely::example(|scope| {
+    ///
+    ///     let streams = vec![(0..5).to_stream(scope).delay(|x, _| 4 - *x),
+    ///                        (0..5).to_stream(scope).delay(|x, _| *x)];
+    ///
+    ///     let mut latest = 0;
+    ///     scope.merge_sorted(streams)
+    ///          .inspect_batch(move |time, _data| {
+    ///              assert!(latest <= *time);
+    ///              latest = *time;
+    ///          });
+    /// });
+    /// ```
+    fn merge_sorted<I>(&self, sources: I) -> Stream<G, D>
+    where
+        G::Timestamp: TotalOrder,
+        I: IntoIterator<Item=Stream<G, D>>;
+}
+
+impl<G: Scope, D: Data> MergeSorted<G, D> for Stream<G, D> {
+    fn merge_sorted<I>(&self, sources: I) -> Stream<G, D>
+    where
+        G::Timestamp: TotalOrder,
+        I: IntoIterator<Item=Stream<G, D>>
+    {
+        let clone = self.clone();
+        self.scope().merge_sorted(Some(clone).into_iter().chain(sources))
+    }
+}
+
+impl<G: Scope, D: Data> MergeSorted<G, D> for G {
+    fn merge_sorted<I>(&self, sources: I) -> Stream<G, D>
+    where
+        G::Timestamp: TotalOrder,
+        I: IntoIterator<Item=Stream<G, D>>
+    {
+
+        // create an operator builder.
+        use crate::dataflow::operators::generic::builder_rc::OperatorBuilder;
+        let mut builder = OperatorBuilder::new("MergeSorted".to_string(), self.clone());
+
+        // create new input handles for each input stream.
+        let mut handles = sources.into_iter().map(|s| builder.new_input(&s, Pipeline)).collect::<Vec<_>>();
+
+        // create one output handle for the merged results.
+        let (mut output, result) = builder.new_output();
+
+        // build an operator that plays out input data once all inputs have completed its time.
+        builder.build(move |_capability| {
+
+            let mut buffered = BTreeMap::new();
+            move |frontiers| {
+                for handle in handles.iter_mut() {
+                    handle.for_each(|time, data| {
+                        buffered.entry(time.time().clone())
+                                .or_insert_with(|| (time.retain(), Vec::new()))
+                                .1.extend(data.replace(Vec::new()));
+                    })
+                }
+
+                let mut output = output.activate();
+                while let Some(time) = buffered.keys().next().cloned() {
+                    if frontiers.iter().any(|frontier| frontier.less_equal(&time)) { break; }
+                    let (capability, mut data) = buffered.remove(&time).unwrap();
+                    output.session(&capability).give_vec(&mut data);
+                }
+            }
+        });
+
+        result
+    }
+}
@@ -1,5 +1,7 @@
 //! Counts the number of records at each time.
 use std::collections::HashMap;
+use std::time::Duration;
+use crate::time::Instant;
 
 use crate::communication::message::RefOrMut;
 
@@ -7,6 +9,7 @@ use crate::Data;
 use crate::dataflow::channels::pact::Pipeline;
 use crate::dataflow::{Stream, Scope};
 use crate::dataflow::operators::generic::operator::Operator;
+use crate::scheduling::DelayedActivation;
 
 /// Accumulates records within a timestamp.
 pub trait Accumulate<G: Scope, D: Data> {
@@ -48,6 +51,63 @@ pub trait Accumulate<G: Scope, D: Data> {
     fn count(&self) -> Stream<G, usize> {
         self.accumulate(0, |sum, data| *sum += data.len())
     }
+    /// Accumulates records within a timestamp, also reporting partial accumulations periodically.
+    ///
+    /// In addition to the accumulation produced once each time is complete, the accumulations of
+    /// incomplete times are produced every `interval` of wall-clock time, so that consumers see
+    /// fresh results even when times complete slowly.
+    ///
+    /// Each partial accumulation reflects all records received at its time so far, including
+    /// those of earlier partial accumulations, and so replaces rather than adds to them. The final
+    /// accumulation of a time is produced exactly once, when the input frontier passes the time,
+    /// and follows all of its partial accumulations; a time that completes as an interval elapses
+    /// receives only its final accumulation. Partial accumulations are produced when the operator
+    /// is next scheduled after an interval elapses, and so may be delayed by other operators.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `interval` is zero.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::time::Duration;
+    /// use timely::dataflow::operators::{ToStream, Accumulate, Capture};
+    /// use timely::dataflow::operators::capture::Extract;
+    ///
+    /// let captured = timely::example(|scope| {
+    ///     (0..10).to_stream(scope)
+    ///            .accumulate_every(0, Duration::from_secs(60), |sum, data| { for &x in data.iter() { *sum += x; } })
+    ///            .capture()
+    /// });
+    ///
+    /// let extracted = captured.extract();
+    /// assert_eq!(extracted, vec![(0, vec![45])]);
+    /// ```
+    fn accumulate_every<A: Data>(&self, default: A, interval: Duration, logic: impl Fn(&mut A, RefOrMut<Vec<D>>)+'static) -> Stream<G, A>;
+    /// Counts the number of records observed at each time, also reporting partial counts periodically.
+    ///
+    /// Partial counts of incomplete times are produced every `interval`, as for `accumulate_every`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::time::Duration;
+    /// use timely::dataflow::operators::{ToStream, Accumulate, Capture};
+    /// use timely::dataflow::operators::capture::Extract;
+    ///
+    /// let captured = timely::example(|scope| {
+    ///     (0..10).to_stream(scope)
+    ///            .count_every(Duration::from_secs(60))
+    ///            .capture()
+    /// });
+    ///
+    /// let extracted = captured.extract();
+    /// assert_eq!(extracted, vec![(0, vec![10])]);
+    /// ```
+    fn count_every(&self, interval: Duration) -> Stream<G, usize> {
+        self.accumulate_every(0, interval, |sum, data| *sum += data.len())
+    }
 }
 
 impl<G: Scope, D: Data> Accumulate<G, D> for Stream<G, D> {
@@ -67,4 +127,62 @@ impl<G: Scope, D: Data> Accumulate<G, D> for Stream<G, D> {
             });
         })
     }
+
+    fn accumulate_every<A: Data>(&self, default: A, interval: Duration, logic: impl Fn(&mut A, RefOrMut<Vec<D>>)+'static) -> Stream<G, A> {
+
+        assert!(interval > Duration::from_secs(0), "Accumulation interval must be positive");
+        let mut accums = HashMap::new();
+        let scope = self.scope();
+        let clock = scope.clock();
+        let mut flushed = clock.now();
+        // The activation at the end of the interval, and the start of that interval.
+        let mut wakeup: Option<(Instant, DelayedActivation)> = None;
+        self.unary_frontier(Pipeline, "AccumulateEvery", move |_, info| {
+
+            let activator = scope.activator_for(&info.address[..]);
+
+            move |input, output| {
+
+                // The interval starts once there are accumulations to report.
+                if accums.is_empty() {
+                    flushed = clock.now();
+                }
+
+                input.for_each(|time, data| {
+                    let (_, accum) = accums.entry(time.time().clone()).or_insert_with(|| (time.retain(), default.clone()));
+                    logic(accum, data);
+                });
+
+                let frontier = input.frontier();
+                let complete = accums.keys().filter(|time| !frontier.less_equal(time)).cloned().collect::<Vec<_>>();
+
+                // Report partial accumulations of incomplete times if the interval has elapsed.
+                let elapsed = clock.elapsed(flushed);
+                if elapsed >= interval {
+                    for (cap, accum) in accums.values().filter(|(cap, _)| frontier.less_equal(cap.time())) {
+                        output.session(cap).give(accum.clone());
+                    }
+                    flushed = clock.now();
+                }
+
+                // Report final accumulations for completed times.
+                for time in complete {
+                    if let Some((cap, accum)) = accums.remove(&time) {
+                        output.session(&cap).give(accum);
+                    }
+                }
+
+                // Arm one activation for the end of each interval, and none without accumulations.
+                let armed = wakeup.as_ref().is_some_and(|(start, _)| *start == flushed);
+                if accums.is_empty() || !armed {
+                    if let Some((_, activation)) = wakeup.take() {
+                        activator.cancel(activation);
+                    }
+                    if !accums.is_empty() {
+                        wakeup = Some((flushed, activator.activate_after(interval - clock.elapsed(flushed).min(interval))));
+                    }
+                }
+            }
+        })
+    }
 }
@@ -31,6 +31,7 @@ use crate::Data;
 use crate::communication::Push;
 use crate::dataflow::channels::pushers::{Counter, Tee};
 use crate::dataflow::channels::{Bundle, Message};
+use crate::dataflow::graph::Channel;
 
 use crate::worker::AsWorker;
 use crate::dataflow::{Stream, Scope};
@@ -94,6 +95,13 @@ impl<G: Scope, T: Timestamp+Refines<G::Timestamp>, D: Data> Enter<G, T, D> for S
         let input = scope.subgraph.borrow_mut().new_input(produced);
 
         let channel_id = scope.clone().new_identifier();
+        scope.register_channel(Channel {
+            id: channel_id,
+            scope_addr: self.scope().addr(),
+            source: (self.name().node, self.name().port),
+            target: (input.node, input.port),
+            pact: "Enter".to_string(),
+        });
         self.connect_to(input, ingress, channel_id);
         Stream::new(Source::new(0, input.port), registrar, scope.clone())
     }
@@ -126,6 +134,13 @@ impl<'a, G: Scope, D: Data, T: Timestamp+Refines<G::Timestamp>> Leave<G, D> for
         let output = scope.subgraph.borrow_mut().new_output();
         let (targets, registrar) = Tee::<G::Timestamp, D>::new();
         let channel_id = scope.clone().new_identifier();
+        scope.register_channel(Channel {
+            id: channel_id,
+            scope_addr: scope.addr(),
+            source: (self.name().node, self.name().port),
+            target: (0, output.port),
+            pact: "Leave".to_string(),
+        });
         self.connect_to(Target::new(0, output.port), EgressNub { targets, phantom: PhantomData }, channel_id);
 
         Stream::new(
@@ -147,7 +162,10 @@ impl<TOuter: Timestamp, TInner: Timestamp+Refines<TOuter>, TData: Data> Push<Bun
         if let Some(message) = message {
             let outer_message = message.as_mut();
             let data = ::std::mem::replace(&mut outer_message.data, Vec::new());
-            let mut inner_message = Some(Bundle::from_typed(Message::new(TInner::to_inner(outer_message.time.clone()), data, 0, 0)));
+            let inner_message = Message::new(TInner::to_inner(outer_message.time.clone()), data, 0, 0);
+            #[cfg(feature = "markers")]
+            let inner_message = Message { markers: ::std::mem::take(&mut outer_message.markers), ..inner_message };
+            let mut inner_message = Some(Bundle::from_typed(inner_message));
             self.targets.push(&mut inner_message);
             if let Some(inner_message) = inner_message {
                 if let Some(inner_message) = inner_message.if_typed() {
@@ -171,7 +189,10 @@ where TOuter: Timestamp, TInner: Timestamp+Refines<TOuter>, TData: Data {
         if let Some(message) = message {
             let inner_message = message.as_mut();
             let data = ::std::mem::replace(&mut inner_message.data, Vec::new());
-            let mut outer_message = Some(Bundle::from_typed(Message::new(inner_message.time.clone().to_outer(), data, 0, 0)));
+            let outer_message = Message::new(inner_message.time.clone().to_outer(), data, 0, 0);
+            #[cfg(feature = "markers")]
+            let outer_message = Message { markers: ::std::mem::take(&mut inner_message.markers), ..outer_message };
+            let mut outer_message = Some(Bundle::from_typed(outer_message));
             self.targets.push(&mut outer_message);
             if let Some(outer_message) = outer_message {
                 if let Some(outer_message) = outer_message.if_typed() {
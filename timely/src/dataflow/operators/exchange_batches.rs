@@ -0,0 +1,119 @@
+//! Exchange the rows of Arrow record batches between workers, keeping them columnar.
+//!
+//! A pact moves whole records, and so exchanging a stream of `RecordBatch`es would move whole
+//! batches. `ExchangeBatches` instead splits each batch by the worker each row is routed to,
+//! selecting the rows for each worker with Arrow's `take` kernel, and exchanges the resulting
+//! batches on their precomputed worker. Batches are serialized as Arrow IPC streams as they
+//! are exchanged, and so are never materialized as rows.
+//!
+//! This is an operator rather than a `ParallelizationContract`, because a pact must not change
+//! the number of records at each time: progress tracking counts records where an operator
+//! produces them and again where the next consumes them, and a batch split among workers is
+//! consumed as several records. Splitting the batches in an operator of their own, ahead of
+//! an `Exchange` of whole parts, keeps the two counts in agreement.
+
+use arrow_array::{RecordBatch, UInt32Array};
+use arrow_ipc::reader::StreamReader;
+use arrow_ipc::writer::StreamWriter;
+use arrow_select::take::take_record_batch;
+
+use crate::dataflow::{Scope, Stream};
+use crate::dataflow::channels::pact::{Exchange, Pipeline};
+use crate::dataflow::operators::generic::operator::Operator;
+
+/// Extension trait for `Stream`.
+pub trait ExchangeBatches<G: Scope> {
+    /// Exchanges the rows of each batch among workers, by the hashes of their keys.
+    ///
+    /// For each batch, `route` returns the hash of the key of each of its rows, typically
+    /// computed from a key column. Each row is sent to the worker its hash selects, as `Exchange`
+    /// would select for a record of that hash, among the batches of rows sent to that worker.
+    /// Batches are split with Arrow's `take` kernel, and a batch without rows for a worker sends
+    /// it nothing. The operator panics if `route` returns other than one hash for each row.
+    ///
+    /// # Examples
+    /// ```
+    /// use std::sync::Arc;
+    /// use arrow_array::{Array, Int64Array, RecordBatch};
+    /// use arrow_schema::{DataType, Field, Schema};
+    /// use timely::dataflow::operators::{ToStream, ExchangeBatches, Inspect};
+    ///
+    /// timely::execute(timely::Configuration::Process(2), |worker| {
+    ///     let index = worker.index();
+    ///     worker.dataflow::<u64,_,_>(|scope| {
+    ///         let schema = Arc::new(Schema::new(vec![Field::new("key", DataType::Int64, false)]));
+    ///         let keys = Int64Array::from((0 .. 10).collect::<Vec<i64>>());
+    ///         let batch = RecordBatch::try_new(schema, vec![Arc::new(keys)]).unwrap();
+    ///
+    ///         Some(batch).to_stream(scope)
+    ///             .exchange_batches(|batch| {
+    ///                 let keys = batch.column(0).as_any().downcast_ref::<Int64Array>().unwrap();
+    ///                 keys.values().iter().map(|key| *key as u64).collect()
+    ///             })
+    ///             .inspect(move |batch| {
+    ///                 let keys = batch.column(0).as_any().downcast_ref::<Int64Array>().unwrap();
+    ///                 assert!(keys.values().iter().all(|key| *key as usize % 2 == index));
+    ///             });
+    ///     });
+    /// }).unwrap();
+    /// ```
+    fn exchange_batches<F: Fn(&RecordBatch)->Vec<u64>+'static>(&self, route: F) -> Stream<G, RecordBatch>;
+}
+
+impl<G: Scope> ExchangeBatches<G> for Stream<G, RecordBatch> {
+    fn exchange_batches<F: Fn(&RecordBatch)->Vec<u64>+'static>(&self, route: F) -> Stream<G, RecordBatch> {
+
+        let peers = self.scope().peers() as u64;
+        let mut vector = Vec::new();
+        self.unary(Pipeline, "PartitionBatches", move |_, _| move |input, output| {
+            input.for_each(|time, data| {
+                data.swap(&mut vector);
+                let mut session = output.session(&time);
+                for batch in vector.drain(..) {
+                    let hashes = route(&batch);
+                    assert_eq!(hashes.len(), batch.num_rows(), "ExchangeBatches: one hash required for each row");
+                    let mut rows = vec![Vec::new(); peers as usize];
+                    for (row, hash) in hashes.into_iter().enumerate() {
+                        rows[worker(hash, peers) as usize].push(row as u32);
+                    }
+                    for (target, rows) in rows.into_iter().enumerate() {
+                        if !rows.is_empty() {
+                            let part = take_record_batch(&batch, &UInt32Array::from(rows)).expect("ExchangeBatches: failed to take rows");
+                            session.give((target as u64, encode(&part)));
+                        }
+                    }
+                }
+            });
+        })
+        .unary(Exchange::new(|(target, _): &(u64, Vec<u8>)| *target), "DecodeBatches", |_, _| {
+            let mut vector = Vec::new();
+            move |input, output| {
+                input.for_each(|time, data| {
+                    data.swap(&mut vector);
+                    output.session(&time).give_iterator(vector.drain(..).map(|(_, bytes)| decode(&bytes)));
+                });
+            }
+        })
+    }
+}
+
+// The worker `Exchange` sends a record of hash `hash` to, among `peers` workers.
+fn worker(hash: u64, peers: u64) -> u64 {
+    if peers.is_power_of_two() { hash & (peers - 1) } else { hash % peers }
+}
+
+// Serializes a batch as an Arrow IPC stream.
+fn encode(batch: &RecordBatch) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    let mut writer = StreamWriter::try_new(&mut bytes, &batch.schema()).expect("ExchangeBatches: failed to encode batch");
+    writer.write(batch).expect("ExchangeBatches: failed to encode batch");
+    writer.finish().expect("ExchangeBatches: failed to encode batch");
+    drop(writer);
+    bytes
+}
+
+// Deserializes a batch written by `encode`.
+fn decode(bytes: &[u8]) -> RecordBatch {
+    let mut reader = StreamReader::try_new(bytes, None).expect("ExchangeBatches: failed to decode batch");
+    reader.next().expect("ExchangeBatches: batch missing").expect("ExchangeBatches: failed to decode batch")
+}
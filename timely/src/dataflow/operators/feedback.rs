@@ -1,11 +1,15 @@
 //! Create cycles in a timely dataflow graph.
 
+use std::collections::HashMap;
+
 use crate::Data;
 
 use crate::progress::{Timestamp, PathSummary};
 use crate::progress::frontier::Antichain;
-use crate::order::Product;
+use crate::order::{Product, PartialOrder};
 
+use crate::logging::{TimelyLogger, IterationEvent};
+use crate::worker::AsWorker;
 use crate::dataflow::channels::pushers::Tee;
 use crate::dataflow::channels::pact::Pipeline;
 use crate::dataflow::{Stream, Scope};
@@ -19,7 +23,7 @@ pub trait Feedback<G: Scope> {
     ///
     /// The resulting `Stream` will have its data defined by a future call to `connect_loop` with
     /// its `Handle` passed as an argument. Data passed through the stream will have their
-    /// timestamps advanced by `summary`, and will be dropped if the result exceeds `limit`.
+    /// timestamps advanced by `summary`.
     ///
     /// # Examples
     /// ```
@@ -37,6 +41,25 @@ pub trait Feedback<G: Scope> {
     /// });
     /// ```
     fn feedback<D: Data>(&mut self, summary: <G::Timestamp as Timestamp>::Summary) -> (Handle<G, D>, Stream<G, D>);
+
+    /// Creates a `Stream` and a `Handle` to later bind the source of that `Stream`, dropping
+    /// data whose advanced timestamps would not be less or equal to `limit`.
+    ///
+    /// # Examples
+    /// ```
+    /// use timely::dataflow::Scope;
+    /// use timely::dataflow::operators::{Feedback, ConnectLoop, ToStream, Concat, Inspect};
+    ///
+    /// timely::example(|scope| {
+    ///     // circulate 0..10 until time 100, without a condition to leave the loop.
+    ///     let (handle, cycle) = scope.feedback_with_limit(1, 100);
+    ///     (0..10).to_stream(scope)
+    ///            .concat(&cycle)
+    ///            .inspect(|x| println!("seen: {:?}", x))
+    ///            .connect_loop(handle);
+    /// });
+    /// ```
+    fn feedback_with_limit<D: Data>(&mut self, summary: <G::Timestamp as Timestamp>::Summary, limit: G::Timestamp) -> (Handle<G, D>, Stream<G, D>);
 }
 
 /// Creates a `Stream` and a `Handle` to later bind the source of that `Stream`.
@@ -45,7 +68,7 @@ pub trait LoopVariable<'a, G: Scope, T: Timestamp> {
     ///
     /// The resulting `Stream` will have its data defined by a future call to `connect_loop` with
     /// its `Handle` passed as an argument. Data passed through the stream will have their
-    /// timestamps advanced by `summary`, and will be dropped if the result exceeds `limit`.
+    /// timestamps advanced by `summary`.
     ///
     /// # Examples
     /// ```
@@ -64,7 +87,32 @@ pub trait LoopVariable<'a, G: Scope, T: Timestamp> {
     ///     });
     /// });
     /// ```
-    fn loop_variable<D: Data>(&mut self, summary: T::Summary) -> (Handle<Iterative<'a, G, T>, D>, Stream<Iterative<'a, G, T>, D>);
+    fn loop_variable<D: Data>(&mut self, summary: T::Summary) -> LoopPair<'a, G, T, D>;
+
+    /// Creates a `Stream` and a `Handle` to later bind the source of that `Stream`, dropping
+    /// data whose advanced iteration counts would not be less or equal to `max_iterations`.
+    ///
+    /// This bounds loops that may fail to converge. When timely logging is enabled, the loop
+    /// also reports the rounds it completes for each epoch as `IterationEvent`s.
+    ///
+    /// # Examples
+    /// ```
+    /// use timely::dataflow::Scope;
+    /// use timely::dataflow::operators::{LoopVariable, ConnectLoop, ToStream, Concat, Map, Inspect};
+    ///
+    /// timely::example(|scope| {
+    ///     // halve records until they reach zero, but for at most 5 iterations.
+    ///     scope.iterative::<usize,_,_>(|inner| {
+    ///         let (handle, cycle) = inner.loop_variable_with_limit(1, 5);
+    ///         (0..1000).to_stream(inner)
+    ///                  .concat(&cycle)
+    ///                  .inspect_time(|t, x| assert!(t.inner <= 5, "record {:?} at {:?}", x, t))
+    ///                  .map(|x| x / 2)
+    ///                  .connect_loop(handle);
+    ///     });
+    /// });
+    /// ```
+    fn loop_variable_with_limit<D: Data>(&mut self, summary: T::Summary, max_iterations: T) -> LoopPair<'a, G, T, D>;
 }
 
 impl<G: Scope> Feedback<G> for G {
@@ -73,16 +121,55 @@ impl<G: Scope> Feedback<G> for G {
         let mut builder = OperatorBuilder::new("Feedback".to_owned(), self.clone());
         let (output, stream) = builder.new_output();
 
-        (Handle { builder, summary, output }, stream)
+        (Handle { builder, summary, output, limit: None, observer: None }, stream)
+    }
+
+    fn feedback_with_limit<D: Data>(&mut self, summary: <G::Timestamp as Timestamp>::Summary, limit: G::Timestamp) -> (Handle<G, D>, Stream<G, D>) {
+        let (mut handle, stream) = self.feedback(summary);
+        handle.limit = Some(Box::new(move |time| time.less_equal(&limit)));
+        (handle, stream)
     }
 }
 
 impl<'a, G: Scope, T: Timestamp> LoopVariable<'a, G, T> for Iterative<'a, G, T> {
-    fn loop_variable<D: Data>(&mut self, summary: T::Summary) -> (Handle<Iterative<'a, G, T>, D>, Stream<Iterative<'a, G, T>, D>) {
-        self.feedback(Product::new(Default::default(), summary))
+    fn loop_variable<D: Data>(&mut self, summary: T::Summary) -> LoopPair<'a, G, T, D> {
+        let (mut handle, stream) = self.feedback(Product::new(Default::default(), summary));
+        handle.observer = report_rounds(self.logging(), handle.builder.global());
+        (handle, stream)
+    }
+
+    fn loop_variable_with_limit<D: Data>(&mut self, summary: T::Summary, max_iterations: T) -> LoopPair<'a, G, T, D> {
+        let (mut handle, stream) = self.loop_variable(summary);
+        handle.limit = Some(Box::new(move |time: &Product<G::Timestamp, T>| time.inner.less_equal(&max_iterations)));
+        (handle, stream)
     }
 }
 
+/// Logs an `IterationEvent` whenever the loop's input frontier advances to a new round of an epoch.
+///
+/// Returns `None` if timely logging is not enabled.
+fn report_rounds<TOuter: Timestamp, T: Timestamp>(logging: Option<TimelyLogger>, id: usize) -> Option<Observer<Product<TOuter, T>>> {
+    let logger = logging?;
+    // The most recent round observed for each epoch, and the number of rounds completed.
+    let mut rounds: HashMap<TOuter, (T, u64)> = HashMap::new();
+    Some(Box::new(move |frontier: &[Product<TOuter, T>]| {
+        for time in frontier.iter() {
+            let entry = rounds.entry(time.outer.clone()).or_insert_with(|| (time.inner.clone(), 0));
+            if entry.0 != time.inner {
+                entry.0 = time.inner.clone();
+                entry.1 += 1;
+                logger.log(IterationEvent {
+                    id,
+                    epoch: format!("{:?}", time.outer),
+                    rounds: entry.1,
+                });
+            }
+        }
+        // Epochs absent from the frontier have completed.
+        rounds.retain(|epoch, _| frontier.iter().any(|time| &time.outer == epoch));
+    }))
+}
+
 /// Connect a `Stream` to the input of a loop variable.
 pub trait ConnectLoop<G: Scope, D: Data> {
     /// Connect a `Stream` to be the input of a loop variable.
@@ -111,15 +198,21 @@ impl<G: Scope, D: Data> ConnectLoop<G, D> for Stream<G, D> {
         let mut builder = helper.builder;
         let summary = helper.summary;
         let mut output = helper.output;
+        let limit = helper.limit;
+        let mut observer = helper.observer;
 
         let mut input = builder.new_input_connection(self, Pipeline, vec![Antichain::from_elem(summary.clone())]);
 
         let mut vector = Vec::new();
-        builder.build(move |_capability| move |_frontier| {
+        builder.build(move |_capability| move |frontier| {
+            if let Some(observer) = observer.as_mut() {
+                observer(&frontier[0].frontier());
+            }
             let mut output = output.activate();
             input.for_each(|cap, data| {
                 data.swap(&mut vector);
-                if let Some(new_time) = summary.results_in(cap.time()) {
+                let new_time = summary.results_in(cap.time()).filter(|time| limit.as_ref().map(|within| within(time)).unwrap_or(true));
+                if let Some(new_time) = new_time {
                     let new_cap = cap.delayed(&new_time);
                     output
                         .session(&new_cap)
@@ -130,9 +223,20 @@ impl<G: Scope, D: Data> ConnectLoop<G, D> for Stream<G, D> {
     }
 }
 
+/// The handle binding the source of a loop variable, and the stream it produces.
+type LoopPair<'a, G, T, D> = (Handle<Iterative<'a, G, T>, D>, Stream<Iterative<'a, G, T>, D>);
+
+/// Indicates whether an advanced timestamp is within a loop's limit.
+type Limit<T> = Box<dyn Fn(&T)->bool>;
+
+/// Observes the frontier of timestamps that may yet be fed back.
+type Observer<T> = Box<dyn FnMut(&[T])>;
+
 /// A handle used to bind the source of a loop variable.
 pub struct Handle<G: Scope, D: Data> {
     builder: OperatorBuilder<G>,
     summary: <G::Timestamp as Timestamp>::Summary,
     output: OutputWrapper<G::Timestamp, D, Tee<G::Timestamp, D>>,
+    limit: Option<Limit<G::Timestamp>>,
+    observer: Option<Observer<G::Timestamp>>,
 }
@@ -0,0 +1,143 @@
+//! Read records from a directory of CSV or JSON-lines files, sharded across workers.
+//!
+//! The files of the directory are listed in order of their names, and the file at position
+//! `k` in that order is read by worker `k % peers`, which introduces its records at time `k`.
+//! Each worker holds a capability only for the file it is reading, so the frontier of the
+//! resulting stream passes `k` once all files up to and including the `k`-th have been read.
+//! Files whose names start with `.` are ignored, so that files still being written under a
+//! temporary name are not read.
+
+use std::fs::{File, read_dir};
+use std::io::{BufRead, BufReader};
+use std::path::{Path, PathBuf};
+
+use serde::de::DeserializeOwned;
+
+use crate::Data;
+use crate::dataflow::channels::Message;
+use crate::dataflow::operators::generic::operator::source;
+use crate::dataflow::{Scope, Stream};
+
+/// The format of the files read by `ReadFiles`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FileFormat {
+    /// Comma-separated values, with a header row naming the fields of each record.
+    Csv,
+    /// One JSON value per line; blank lines are skipped.
+    JsonLines,
+}
+
+/// Reads records from files into a timely `Stream`.
+pub trait ReadFiles<G: Scope> {
+    /// Reads the files of `directory` as records of type `D`, sharding files across workers.
+    ///
+    /// Records of the `k`-th file, in order of file names, are introduced at time `k`. Records
+    /// are read in chunks, so that reading a large file does not prevent the worker from doing
+    /// other work. The operator panics if the directory cannot be listed, or if a file cannot
+    /// be read or contains a record that does not parse as a `D`, naming the file.
+    ///
+    /// # Examples
+    /// ```
+    /// use timely::dataflow::operators::{ReadFiles, Capture};
+    /// use timely::dataflow::operators::capture::Extract;
+    /// use timely::dataflow::operators::file_source::FileFormat;
+    ///
+    /// let directory = std::env::temp_dir().join(format!("timely-files-{}", std::process::id()));
+    /// std::fs::create_dir_all(&directory).unwrap();
+    /// std::fs::write(directory.join("a.jsonl"), "[\"a\", 1]\n[\"b\", 2]\n").unwrap();
+    /// std::fs::write(directory.join("b.jsonl"), "[\"c\", 3]\n").unwrap();
+    ///
+    /// let path = directory.clone();
+    /// let captured = timely::example(move |scope| {
+    ///     scope.read_files::<(String, u64), _>(path, FileFormat::JsonLines).capture()
+    /// });
+    ///
+    /// assert_eq!(captured.extract(), vec![
+    ///     (0, vec![("a".to_string(), 1), ("b".to_string(), 2)]),
+    ///     (1, vec![("c".to_string(), 3)]),
+    /// ]);
+    /// std::fs::remove_dir_all(&directory).unwrap();
+    /// ```
+    fn read_files<D: Data+DeserializeOwned, P: Into<PathBuf>>(&self, directory: P, format: FileFormat) -> Stream<G, D>;
+}
+
+impl<G: Scope<Timestamp=u64>> ReadFiles<G> for G {
+    fn read_files<D: Data+DeserializeOwned, P: Into<PathBuf>>(&self, directory: P, format: FileFormat) -> Stream<G, D> {
+
+        let directory = directory.into();
+        let mut names = read_dir(&directory)
+            .expect("failed to list file source directory")
+            .map(|entry| entry.expect("failed to list file source directory"))
+            .filter(|entry| entry.file_type().map(|kind| kind.is_file()).unwrap_or(false))
+            .map(|entry| entry.file_name())
+            .filter(|name| !name.to_string_lossy().starts_with('.'))
+            .collect::<Vec<_>>();
+        names.sort();
+
+        let (index, peers) = (self.index(), self.peers());
+        let mut files = names
+            .into_iter()
+            .enumerate()
+            .filter(|(position, _)| position % peers == index)
+            .map(|(position, name)| (position as u64, directory.join(name)))
+            .collect::<Vec<_>>()
+            .into_iter();
+
+        source(self, "ReadFiles", |capability, info| {
+
+            let activator = self.activator_for(&info.address[..]);
+
+            let mut capability = Some(capability);
+            let mut current: Option<Box<dyn Iterator<Item=D>>> = None;
+
+            move |output| {
+
+                if current.is_none() {
+                    if let Some((time, path)) = files.next() {
+                        capability.as_mut().unwrap().downgrade(&time);
+                        current = Some(records(path, format));
+                    }
+                    else {
+                        capability = None;
+                        return;
+                    }
+                }
+
+                let mut session = output.session(capability.as_ref().unwrap());
+                let mut given = 0;
+                for record in current.as_mut().unwrap().take(256 * Message::<u64, D>::default_length()) {
+                    session.give(record);
+                    given += 1;
+                }
+                // An exhausted file releases its time at the next activation.
+                if given == 0 {
+                    current = None;
+                }
+                activator.activate();
+            }
+        })
+    }
+}
+
+/// Opens the file at `path` as an iterator over its records, which panics on malformed records.
+fn records<D: DeserializeOwned+'static>(path: PathBuf, format: FileFormat) -> Box<dyn Iterator<Item=D>> {
+    let file = File::open(&path).unwrap_or_else(|error| failed(&path, error));
+    match format {
+        FileFormat::Csv => {
+            Box::new(csv::Reader::from_reader(file).into_deserialize().map(move |record| {
+                record.unwrap_or_else(|error| failed(&path, error))
+            }))
+        },
+        FileFormat::JsonLines => {
+            Box::new(BufReader::new(file).lines().filter_map(move |line| {
+                let line = line.unwrap_or_else(|error| failed(&path, error));
+                if line.trim().is_empty() { None }
+                else { Some(serde_json::from_str(&line).unwrap_or_else(|error| failed(&path, error))) }
+            }))
+        },
+    }
+}
+
+fn failed<E: std::fmt::Display>(path: &Path, error: E) -> ! {
+    panic!("failed to read {}: {}", path.display(), error)
+}
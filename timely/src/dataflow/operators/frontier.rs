@@ -0,0 +1,91 @@
+//! Frontier changes presented as data.
+//!
+//! Dataflows that react to progress elsewhere, for example to trigger compaction or to emit
+//! watermark records to a sink, can consume the frontier of a stream as a stream of its own.
+//! Each time the frontier advances, each newly reached timestamp is produced as a record at
+//! that timestamp, and the stream completes once the frontier is empty.
+//!
+//! The frontier of a probe may also be presented in a different dataflow, using
+//! `probe::Handle::frontier_stream`.
+
+use crate::progress::Timestamp;
+use crate::progress::frontier::{Antichain, AntichainRef};
+use crate::dataflow::{Stream, Scope};
+use crate::dataflow::channels::pact::Pipeline;
+use crate::dataflow::channels::pushers::Tee;
+use crate::dataflow::operators::CapabilitySet;
+use crate::dataflow::operators::generic::{Operator, OutputHandle};
+use crate::Data;
+
+/// Presents the frontier of a `Stream` as data.
+pub trait FrontierStream<G: Scope> {
+    /// Produces each timestamp the frontier of the stream reaches, at that timestamp.
+    ///
+    /// The records of the stream are discarded. The initial frontier is not reported.
+    ///
+    /// # Examples
+    /// ```
+    /// use std::rc::Rc;
+    /// use std::cell::RefCell;
+    /// use timely::dataflow::InputHandle;
+    /// use timely::dataflow::operators::{Input, FrontierStream, Inspect, Probe};
+    ///
+    /// timely::execute_from_args(::std::env::args(), |worker| {
+    ///
+    ///     let mut input = InputHandle::new();
+    ///     let reached = Rc::new(RefCell::new(Vec::new()));
+    ///     let shared = reached.clone();
+    ///     let probe = worker.dataflow::<usize,_,_>(|scope| {
+    ///         scope.input_from(&mut input)
+    ///              .inspect(|x: &usize| println!("record: {}", x))
+    ///              .frontier_stream()
+    ///              .inspect(move |time| shared.borrow_mut().push(*time))
+    ///              .probe()
+    ///     });
+    ///
+    ///     for round in 0..3 {
+    ///         input.send(round);
+    ///         input.advance_to(round + 1);
+    ///         worker.step_while(|| probe.less_than(input.time()));
+    ///     }
+    ///
+    ///     assert_eq!(*reached.borrow(), vec![1, 2, 3]);
+    /// }).unwrap();
+    /// ```
+    fn frontier_stream(&self) -> Stream<G, G::Timestamp>;
+}
+
+impl<G: Scope, D: Data> FrontierStream<G> for Stream<G, D> {
+    fn frontier_stream(&self) -> Stream<G, G::Timestamp> {
+        self.unary_frontier(Pipeline, "FrontierStream", |capability, _info| {
+            let mut capabilities = CapabilitySet::new();
+            capabilities.insert(capability);
+            let mut reported = Antichain::from_elem(Default::default());
+            let mut vector = Vec::new();
+            move |input, output| {
+                input.for_each(|_time, data| data.swap(&mut vector));
+                vector.clear();
+                report_changes(input.frontier().frontier(), &mut reported, &mut capabilities, output);
+            }
+        })
+    }
+}
+
+/// Produces each element of `current` absent from `reported`, and downgrades `capabilities`.
+pub(crate) fn report_changes<T: Timestamp>(
+    current: AntichainRef<T>,
+    reported: &mut Antichain<T>,
+    capabilities: &mut CapabilitySet<T>,
+    output: &mut OutputHandle<T, T, Tee<T, T>>,
+) {
+    if &current[..] != reported.elements() {
+        for time in current.iter() {
+            if !reported.elements().contains(time) {
+                output.session(&capabilities.delayed(time)).give(time.clone());
+            }
+        }
+        capabilities.downgrade(&current[..]);
+        reported.clear();
+        reported.extend(current.iter().cloned());
+    }
+}
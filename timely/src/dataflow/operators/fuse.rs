@@ -0,0 +1,120 @@
+//! Fusion of record-by-record transformations into a single operator.
+//!
+//! Each of `map`, `filter`, and `flat_map` on a `Stream` introduces an operator, which must be
+//! scheduled and which buffers its output before the next operator in the chain sees it. For
+//! long pipelines of such transformations, the `fuse` method instead accumulates the logic of
+//! each stage and installs a single operator once the chain is complete.
+//!
+//! Fusion is opted into for each chain, by calling `fuse`, rather than applied to existing
+//! chains of `map`, `filter`, and `flat_map` as a dataflow is constructed. Each of those methods
+//! returns a `Stream`, which names the output of an operator already built, and which further
+//! operators may consume alongside the next stage of the chain. Folding a later stage into that
+//! operator would require deferring the construction of every stream until its consumers are
+//! known, which the dataflow builders do not do.
+
+use crate::Data;
+use crate::dataflow::{Stream, Scope};
+use crate::dataflow::channels::pact::Pipeline;
+use crate::dataflow::operators::generic::operator::Operator;
+
+/// Extension trait for `Stream`.
+pub trait Fuse<S: Scope, D: Data> {
+    /// Begins a chain of transformations to be fused into a single operator.
+    ///
+    /// The chain is installed as an operator when `into_stream` is called.
+    ///
+    /// # Examples
+    /// ```
+    /// use timely::dataflow::operators::{ToStream, Fuse, Capture};
+    /// use timely::dataflow::operators::capture::Extract;
+    ///
+    /// let captured = timely::example(|scope| {
+    ///     (0..10).to_stream(scope)
+    ///            .fuse()
+    ///            .map(|x| x + 1)
+    ///            .filter(|x| x % 2 == 0)
+    ///            .flat_map(|x| vec![x; 2])
+    ///            .into_stream()
+    ///            .capture()
+    /// });
+    ///
+    /// assert_eq!(captured.extract(), vec![(0, vec![2, 2, 4, 4, 6, 6, 8, 8, 10, 10])]);
+    /// ```
+    fn fuse(&self) -> Fused<S, D, D>;
+}
+
+impl<S: Scope, D: Data> Fuse<S, D> for Stream<S, D> {
+    fn fuse(&self) -> Fused<S, D, D> {
+        Fused {
+            stream: self.clone(),
+            names: Vec::new(),
+            logic: Box::new(|datum, next| next(datum)),
+        }
+    }
+}
+
+/// Presents each record produced from an input record to a supplied closure.
+type Logic<D, D2> = Box<dyn FnMut(D, &mut dyn FnMut(D2))>;
+
+/// A chain of record-by-record transformations applied to a `Stream`.
+///
+/// The `logic` presents each transformed record of type `D2` from an input record of type `D`
+/// to a supplied closure, which allows stages to be composed without intermediate buffers.
+pub struct Fused<S: Scope, D: Data, D2> {
+    stream: Stream<S, D>,
+    names: Vec<&'static str>,
+    logic: Logic<D, D2>,
+}
+
+impl<S: Scope, D: Data, D2: 'static> Fused<S, D, D2> {
+
+    /// Consumes each element of the chain and yields a new element.
+    pub fn map<D3, F: FnMut(D2)->D3+'static>(self, mut func: F) -> Fused<S, D, D3> {
+        self.extend("Map", move |mut logic| Box::new(move |datum, next| {
+            logic(datum, &mut |x| next(func(x)))
+        }))
+    }
+
+    /// Retains only elements of the chain satisfying `predicate`.
+    pub fn filter<P: FnMut(&D2)->bool+'static>(self, mut predicate: P) -> Fused<S, D, D2> {
+        self.extend("Filter", move |mut logic| Box::new(move |datum, next| {
+            logic(datum, &mut |x| if predicate(&x) { next(x) })
+        }))
+    }
+
+    /// Consumes each element of the chain and yields some number of new elements.
+    pub fn flat_map<I: IntoIterator, F: FnMut(D2)->I+'static>(self, mut func: F) -> Fused<S, D, I::Item> {
+        self.extend("FlatMap", move |mut logic| Box::new(move |datum, next| {
+            logic(datum, &mut |x| for y in func(x) { next(y) })
+        }))
+    }
+
+    /// Installs the chain as a single operator, and returns its output stream.
+    ///
+    /// The operator is named after the fused stages, for example `Fused(Map, Filter)`.
+    pub fn into_stream(self) -> Stream<S, D2> where D2: Data {
+        let name = format!("Fused({})", self.names.join(", "));
+        let mut logic = self.logic;
+        let mut vector = Vec::new();
+        self.stream.unary(Pipeline, &name, move |_,_| move |input, output| {
+            input.for_each(|time, data| {
+                data.swap(&mut vector);
+                let mut session = output.session(&time);
+                for datum in vector.drain(..) {
+                    logic(datum, &mut |x| session.give(x));
+                }
+            });
+        })
+    }
+
+    // Wraps the chain's logic to form a new stage, recorded as `name`.
+    fn extend<D3>(self, name: &'static str, wrap: impl FnOnce(Logic<D, D2>)->Logic<D, D3>) -> Fused<S, D, D3> {
+        let mut names = self.names;
+        names.push(name);
+        Fused {
+            stream: self.stream,
+            names,
+            logic: wrap(self.logic),
+        }
+    }
+}
@@ -0,0 +1,138 @@
+//! Operators defined by callbacks for received data and for completed times.
+//!
+//! Operators that must act once a time is complete usually retain capabilities for the times
+//! at which they receive data, and consult their input frontiers or a notificator to learn when
+//! they may act. With more than one input it is easy to act on a time too early, once only some
+//! of the inputs have completed it, or to act on times out of order.
+//!
+//! A `CallbackBuilder` instead owns the capabilities and the frontier logic. Each input presents
+//! its data to a callback along with a state for the data's time, and once the time is complete
+//! on all inputs, a final callback consumes the state to produce output at the time.
+
+use std::collections::HashMap;
+
+use crate::Data;
+use crate::dataflow::{Stream, Scope};
+use crate::dataflow::channels::pushers::Tee;
+use crate::dataflow::channels::pact::ParallelizationContract;
+use crate::dataflow::operators::Capability;
+use crate::dataflow::operators::generic::OutputWrapper;
+use crate::dataflow::operators::generic::builder_rc::OperatorBuilder;
+
+/// The times with received data, with capabilities for the times and the states of the times.
+type Pending<T, S> = HashMap<T, (Capability<T>, S)>;
+
+/// Reads the available data of an input into the pending times.
+type Reader<T, S> = Box<dyn FnMut(&mut Pending<T, S>)>;
+
+/// Builds operators from callbacks for received data and for completed times.
+///
+/// Each time has a state of type `S`, created with `Default::default` when data first arrive at
+/// the time. Once no input may receive further data at the time, the operator presents the state
+/// to the callback supplied to `build`, whose output is produced at the time. Times are completed
+/// in timestamp order.
+///
+/// # Examples
+/// ```
+/// use timely::dataflow::operators::{ToStream, Capture};
+/// use timely::dataflow::operators::capture::Extract;
+/// use timely::dataflow::operators::generic::builder_callback::CallbackBuilder;
+/// use timely::dataflow::channels::pact::Pipeline;
+///
+/// let captured = timely::example(|scope| {
+///     let words = vec!["one", "two", "three"].into_iter().map(String::from).to_stream(scope);
+///     let numbers = (0..10u64).to_stream(scope);
+///
+///     // Reports the number of words and the sum of the numbers at each time.
+///     let mut builder = CallbackBuilder::new("Summary", scope.clone());
+///     builder.input(&words, Pipeline, |_time, batch: &mut Vec<String>, state: &mut (usize, u64)| {
+///         state.0 += batch.len();
+///     });
+///     builder.input(&numbers, Pipeline, |_time, batch: &mut Vec<u64>, state: &mut (usize, u64)| {
+///         state.1 += batch.iter().sum::<u64>();
+///     });
+///     builder.build(|_time, state, output| output.push(state))
+///            .capture()
+/// });
+///
+/// assert_eq!(captured.extract(), vec![(0, vec![(3, 45)])]);
+/// ```
+pub struct CallbackBuilder<G: Scope, S, D: Data> {
+    builder: OperatorBuilder<G>,
+    readers: Vec<Reader<G::Timestamp, S>>,
+    output: OutputWrapper<G::Timestamp, D, Tee<G::Timestamp, D>>,
+    stream: Stream<G, D>,
+}
+
+impl<G: Scope, S: Default+'static, D: Data> CallbackBuilder<G, S, D> {
+
+    /// Allocates a new callback operator builder from its containing scope.
+    pub fn new(name: &str, scope: G) -> Self {
+        let mut builder = OperatorBuilder::new(name.to_owned(), scope);
+        let (output, stream) = builder.new_output();
+        CallbackBuilder {
+            builder,
+            readers: Vec::new(),
+            output,
+            stream,
+        }
+    }
+
+    /// Adds an input, whose batches of data are presented to `on_data` with their time and the
+    /// state of their time.
+    ///
+    /// The batch may be drained or swapped by `on_data`, and is cleared afterwards.
+    pub fn input<D1, P, L>(&mut self, stream: &Stream<G, D1>, pact: P, mut on_data: L)
+    where
+        D1: Data,
+        P: ParallelizationContract<G::Timestamp, D1>,
+        L: FnMut(&G::Timestamp, &mut Vec<D1>, &mut S)+'static,
+    {
+        let mut input = self.builder.new_input(stream, pact);
+        let mut vector = Vec::new();
+        self.readers.push(Box::new(move |pending| {
+            input.for_each(|time, data| {
+                data.swap(&mut vector);
+                let (capability, state) = pending
+                    .entry(time.time().clone())
+                    .or_insert_with(|| (time.retain(), Default::default()));
+                on_data(capability.time(), &mut vector, state);
+                vector.clear();
+            });
+        }));
+    }
+
+    /// Creates the operator, and returns its output stream.
+    ///
+    /// Once a time is complete on all inputs, `on_time_complete` consumes its state, and the
+    /// records it pushes to the supplied vector are produced at the time.
+    pub fn build<L>(self, mut on_time_complete: L) -> Stream<G, D>
+    where
+        L: FnMut(&G::Timestamp, S, &mut Vec<D>)+'static,
+    {
+        let CallbackBuilder { builder, mut readers, mut output, stream } = self;
+        builder.build(move |_capabilities| {
+            let mut pending = Pending::new();
+            let mut complete = Vec::new();
+            let mut buffer = Vec::new();
+            move |frontiers| {
+                for reader in readers.iter_mut() {
+                    reader(&mut pending);
+                }
+
+                complete.extend(pending.keys().filter(|time| !frontiers.iter().any(|f| f.less_equal(time))).cloned());
+                // Earlier times in the partial order are also earlier in the total order.
+                complete.sort();
+
+                let mut output = output.activate();
+                for time in complete.drain(..) {
+                    let (capability, state) = pending.remove(&time).unwrap();
+                    on_time_complete(&time, state, &mut buffer);
+                    output.session(&capability).give_vec(&mut buffer);
+                    buffer.clear();
+                }
+            }
+        });
+        stream
+    }
+}
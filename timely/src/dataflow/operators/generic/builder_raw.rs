@@ -10,7 +10,7 @@ use std::cell::RefCell;
 
 use crate::Data;
 
-use crate::scheduling::{Schedule, Activations};
+use crate::scheduling::{Schedule, Activations, OperatorPriority};
 
 use crate::progress::{Source, Target};
 use crate::progress::{Timestamp, Operate, operate::SharedProgress, Antichain};
@@ -18,7 +18,10 @@ use crate::progress::{Timestamp, Operate, operate::SharedProgress, Antichain};
 use crate::dataflow::{Stream, Scope};
 use crate::dataflow::channels::pushers::Tee;
 use crate::dataflow::channels::pact::ParallelizationContract;
+use crate::dataflow::graph::{Channel, pact_name};
+use crate::dataflow::memory::MemoryAccount;
 use crate::dataflow::operators::generic::operator_info::OperatorInfo;
+use crate::dataflow::operators::generic::Timers;
 
 /// Contains type-free information about the operator properties.
 pub struct OperatorShape {
@@ -60,6 +63,7 @@ pub struct OperatorBuilder<G: Scope> {
     address: Vec<usize>,    // path to the operator (ending with index).
     shape: OperatorShape,
     summary: Vec<Vec<Antichain<<G::Timestamp as Timestamp>::Summary>>>,
+    priority: OperatorPriority,
 }
 
 impl<G: Scope> OperatorBuilder<G> {
@@ -80,6 +84,7 @@ impl<G: Scope> OperatorBuilder<G> {
             address,
             shape: OperatorShape::new(name, peers),
             summary: vec![],
+            priority: OperatorPriority::default(),
         }
     }
 
@@ -103,6 +108,16 @@ impl<G: Scope> OperatorBuilder<G> {
         self.shape.notify = notify;
     }
 
+    /// Sets the scheduling level of the operator among the active operators of its scope.
+    pub fn set_priority(&mut self, level: usize) {
+        self.priority.set_level(level);
+    }
+
+    /// A handle to the scheduling priority of the operator, with which to adjust it at runtime.
+    pub fn priority(&self) -> OperatorPriority {
+        self.priority.clone()
+    }
+
     /// Adds a new input to a generic operator builder, returning the `Pull` implementor to use.
     pub fn new_input<D: Data, P>(&mut self, stream: &Stream<G, D>, pact: P) -> P::Puller
         where
@@ -120,6 +135,13 @@ impl<G: Scope> OperatorBuilder<G> {
         let logging = self.scope.logging();
         let (sender, receiver) = pact.connect(&mut self.scope, channel_id, &self.address[..], logging);
         let target = Target::new(self.index, self.shape.inputs);
+        self.scope.register_channel(Channel {
+            id: channel_id,
+            scope_addr: self.scope.addr(),
+            source: (stream.name().node, stream.name().port),
+            target: (target.node, target.port),
+            pact: pact_name::<P>(),
+        });
         stream.connect_to(target, sender, channel_id);
 
         self.shape.inputs += 1;
@@ -167,14 +189,25 @@ impl<G: Scope> OperatorBuilder<G> {
             logic,
             shared_progress: Rc::new(RefCell::new(SharedProgress::new(inputs, outputs))),
             summary: self.summary,
+            priority: self.priority,
         };
 
         self.scope.add_operator_with_indices(Box::new(operator), self.index, self.global);
     }
 
+    /// Creates wall-clock timers that schedule the operator when they fire.
+    pub fn timers(&self) -> Timers {
+        Timers::new(self.scope.activator_for(&self.address[..]))
+    }
+
+    /// The memory account of the operator, if memory accounting is enabled.
+    pub fn memory(&self) -> Option<MemoryAccount> {
+        self.scope.memory_account(&self.address[..])
+    }
+
     /// Information describing the operator.
     pub fn operator_info(&self) -> OperatorInfo {
-        OperatorInfo::new(self.index, self.global, &self.address[..])
+        OperatorInfo::new(self.index, self.global, &self.address[..]).with_memory(self.memory())
     }
 }
 
@@ -189,6 +222,7 @@ where
     shared_progress: Rc<RefCell<SharedProgress<T>>>,
     activations: Rc<RefCell<Activations>>,
     summary: Vec<Vec<Antichain<T::Summary>>>,
+    priority: OperatorPriority,
 }
 
 impl<T, L> Schedule for OperatorCore<T, L>
@@ -202,6 +236,7 @@ where
         let shared_progress = &mut *self.shared_progress.borrow_mut();
         (self.logic)(shared_progress)
     }
+    fn priority(&self) -> Option<OperatorPriority> { Some(self.priority.clone()) }
 }
 
 impl<T, L> Operate<T> for OperatorCore<T, L>
@@ -18,8 +18,14 @@ use crate::dataflow::channels::pact::ParallelizationContract;
 use crate::dataflow::channels::pullers::Counter as PullCounter;
 use crate::dataflow::operators::capability::Capability;
 use crate::dataflow::operators::capability::mint as mint_capability;
+#[cfg(feature = "capability-checks")]
+use crate::dataflow::operators::capability::Origin;
 use crate::dataflow::operators::generic::handles::{InputHandle, new_input_handle, OutputWrapper};
 use crate::dataflow::operators::generic::operator_info::OperatorInfo;
+use crate::dataflow::operators::generic::Timers;
+#[cfg(feature = "markers")]
+use crate::dataflow::markers::MarkerCarrier;
+use crate::scheduling::OperatorPriority;
 
 use crate::logging::TimelyLogger as Logger;
 
@@ -33,6 +39,11 @@ pub struct OperatorBuilder<G: Scope> {
     internal: Rc<RefCell<Vec<Rc<RefCell<ChangeBatch<G::Timestamp>>>>>>,
     produced: Vec<Rc<RefCell<ChangeBatch<G::Timestamp>>>>,
     logging: Option<Logger>,
+    output_capacity: Option<usize>,
+    #[cfg(feature = "markers")]
+    markers: MarkerCarrier<G::Timestamp>,
+    #[cfg(feature = "capability-checks")]
+    origin: Rc<Origin>,
 }
 
 impl<G: Scope> OperatorBuilder<G> {
@@ -40,21 +51,48 @@ impl<G: Scope> OperatorBuilder<G> {
     /// Allocates a new generic operator builder from its containing scope.
     pub fn new(name: String, scope: G) -> Self {
         let logging = scope.logging();
+        let output_capacity = scope.output_capacity();
+        #[cfg(feature = "capability-checks")]
+        let origin_name = name.clone();
+        let builder = OperatorBuilderRaw::new(name, scope);
         OperatorBuilder {
-            builder: OperatorBuilderRaw::new(name, scope),
+            #[cfg(feature = "capability-checks")]
+            origin: Rc::new(Origin { name: origin_name, address: builder.operator_info().address }),
+            builder,
             frontier: Vec::new(),
             consumed: Vec::new(),
             internal: Rc::new(RefCell::new(Vec::new())),
             produced: Vec::new(),
             logging,
+            output_capacity,
+            #[cfg(feature = "markers")]
+            markers: MarkerCarrier::new(),
         }
     }
 
+    /// Sets the number of records at which subsequently added outputs flush.
+    ///
+    /// The capacity defaults to that of the scope, which is set by `Worker::set_output_capacity`.
+    pub fn set_output_capacity(&mut self, capacity: usize) {
+        assert!(capacity > 0, "Output capacity must be positive");
+        self.output_capacity = Some(capacity);
+    }
+
     /// Indicates whether the operator requires frontier information.
     pub fn set_notify(&mut self, notify: bool) {
         self.builder.set_notify(notify);
     }
 
+    /// Sets the scheduling level of the operator among the active operators of its scope.
+    pub fn set_priority(&mut self, level: usize) {
+        self.builder.set_priority(level);
+    }
+
+    /// A handle to the scheduling priority of the operator, with which to adjust it at runtime.
+    pub fn priority(&self) -> OperatorPriority {
+        self.builder.priority()
+    }
+
     /// Adds a new input to a generic operator builder, returning the `Pull` implementor to use.
     pub fn new_input<D: Data, P>(&mut self, stream: &Stream<G, D>, pact: P) -> InputHandle<G::Timestamp, D, P::Puller>
     where
@@ -72,10 +110,15 @@ impl<G: Scope> OperatorBuilder<G> {
         let puller = self.builder.new_input_connection(stream, pact, connection);
 
         let input = PullCounter::new(puller);
+        #[cfg(feature = "markers")]
+        let input = input.with_markers(self.markers.clone());
         self.frontier.push(MutableAntichain::new());
         self.consumed.push(input.consumed().clone());
 
-        new_input_handle(input, self.internal.clone(), self.logging.clone())
+        let handle = new_input_handle(input, self.internal.clone(), self.logging.clone());
+        #[cfg(feature = "capability-checks")]
+        let handle = handle.with_origin(self.origin.clone());
+        handle
     }
 
     /// Adds a new output to a generic operator builder, returning the `Pull` implementor to use.
@@ -92,10 +135,21 @@ impl<G: Scope> OperatorBuilder<G> {
         let internal = Rc::new(RefCell::new(ChangeBatch::new()));
         self.internal.borrow_mut().push(internal.clone());
 
-        let mut buffer = PushBuffer::new(PushCounter::new(tee));
-        self.produced.push(buffer.inner().produced().clone());
-
-        (OutputWrapper::new(buffer, internal), stream)
+        #[cfg(feature = "markers")]
+        let pending = self.markers.add_output(tee.clone());
+        let counter = PushCounter::new(tee);
+        self.produced.push(counter.produced().clone());
+        let buffer = match self.output_capacity {
+            Some(capacity) => PushBuffer::with_capacity(counter, capacity),
+            None => PushBuffer::new(counter),
+        }.with_memory(self.builder.memory());
+        #[cfg(feature = "markers")]
+        let buffer = buffer.with_markers(pending);
+
+        let wrapper = OutputWrapper::new(buffer, internal);
+        #[cfg(feature = "capability-checks")]
+        let wrapper = wrapper.with_origin(self.origin.clone(), self.internal.borrow().len() - 1);
+        (wrapper, stream)
     }
 
     /// Creates an operator implementation from supplied logic constructor.
@@ -108,7 +162,10 @@ impl<G: Scope> OperatorBuilder<G> {
         let mut capabilities = Vec::new();
         for output_index in 0  .. self.internal.borrow().len() {
             let borrow = &self.internal.borrow()[output_index];
-            capabilities.push(mint_capability(Default::default(), borrow.clone()));
+            let capability = mint_capability(Default::default(), borrow.clone());
+            #[cfg(feature = "capability-checks")]
+            let capability = capability.with_origin(self.origin.clone(), output_index);
+            capabilities.push(capability);
             // Discard evidence of creation, as we are assumed to start with one.
             borrow.borrow_mut().clear();
         }
@@ -119,6 +176,8 @@ impl<G: Scope> OperatorBuilder<G> {
         let self_consumed = self.consumed;
         let self_internal = self.internal;
         let self_produced = self.produced;
+        #[cfg(feature = "markers")]
+        let self_markers = self.markers;
 
         let raw_logic = 
         move |progress: &mut SharedProgress<G::Timestamp>| {
@@ -131,6 +190,10 @@ impl<G: Scope> OperatorBuilder<G> {
             // invoke supplied logic
             logic(&self_frontier[..]);
 
+            // send markers the logic did not send with its output.
+            #[cfg(feature = "markers")]
+            self_markers.flush();
+
             // move batches of consumed changes.
             for index in 0 .. progress.consumeds.len() {
                 self_consumed[index].borrow_mut().drain_into(&mut progress.consumeds[index]);
@@ -164,6 +227,11 @@ impl<G: Scope> OperatorBuilder<G> {
         self.builder.global()
     }
 
+    /// Creates wall-clock timers that schedule the operator when they fire.
+    pub fn timers(&self) -> Timers {
+        self.builder.timers()
+    }
+
     /// Creates operator info for the operator.
     pub fn operator_info(&self) -> OperatorInfo {
         self.builder.operator_info()
@@ -242,4 +310,50 @@ mod tests {
             "Hello".to_owned()
         });
     }
+
+    #[cfg(feature = "capability-checks")]
+    #[test]
+    #[should_panic(expected = "Attempted to open output session (operator Failure at address [0, 1], output 1) with a capability for 0 of another output (operator Failure at address [0, 1], output 0)")]
+    fn incorrect_capabilities_name_operator() {
+
+        use crate::dataflow::operators::generic::builder_rc::OperatorBuilder;
+
+        crate::example(|scope| {
+
+            let mut builder = OperatorBuilder::new("Failure".to_owned(), scope.clone());
+
+            let (_output1, _stream1) = builder.new_output::<()>();
+            let (mut output2, _stream2) = builder.new_output::<()>();
+
+            builder.build(move |capabilities| {
+                move |_frontiers| {
+                    output2.activate().session(&capabilities[0]);
+                }
+            });
+        })
+    }
+
+    #[cfg(feature = "capability-checks")]
+    #[test]
+    #[should_panic(expected = "which is not greater or equal to any of them (operator Downgrade at address [0, 1], output 0)")]
+    fn delays_past_downgrades_name_operator() {
+
+        use crate::dataflow::operators::CapabilitySet;
+        use crate::dataflow::operators::generic::builder_rc::OperatorBuilder;
+
+        crate::example(|scope| {
+
+            let mut builder = OperatorBuilder::<_>::new("Downgrade".to_owned(), scope.clone());
+            let (_output, _stream) = builder.new_output::<()>();
+
+            builder.build(move |mut capabilities| {
+                let mut set = CapabilitySet::new();
+                set.insert(capabilities.pop().unwrap());
+                move |_frontiers| {
+                    set.downgrade(&[5]);
+                    set.delayed(&3);
+                }
+            });
+        })
+    }
 }
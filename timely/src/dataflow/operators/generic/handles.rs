@@ -16,16 +16,22 @@ use crate::dataflow::channels::pushers::buffer::{Buffer, Session};
 use crate::dataflow::channels::Bundle;
 use crate::communication::{Push, Pull, message::RefOrMut};
 use crate::logging::TimelyLogger as Logger;
+use crate::logging::{EventKind, LogInterest};
 
 use crate::dataflow::operators::CapabilityRef;
 use crate::dataflow::operators::capability::mint_ref as mint_capability_ref;
 use crate::dataflow::operators::capability::CapabilityTrait;
+#[cfg(feature = "capability-checks")]
+use crate::dataflow::operators::capability::Origin;
 
 /// Handle to an operator's input stream.
 pub struct InputHandle<T: Timestamp, D, P: Pull<Bundle<T, D>>> {
     pull_counter: PullCounter<T, D, P>,
     internal: Rc<RefCell<Vec<Rc<RefCell<ChangeBatch<T>>>>>>,
     logging: Option<Logger>,
+    // The operator named by capability misuse assertions.
+    #[cfg(feature = "capability-checks")]
+    origin: Option<Rc<Origin>>,
 }
 
 /// Handle to an operator's input stream and frontier.
@@ -44,18 +50,27 @@ impl<'a, T: Timestamp, D: Data, P: Pull<Bundle<T, D>>> InputHandle<T, D, P> {
     #[inline]
     pub fn next(&mut self) -> Option<(CapabilityRef<T>, RefOrMut<Vec<D>>)> {
         let internal = &self.internal;
+        #[cfg(feature = "capability-checks")]
+        let origin = &self.origin;
         self.pull_counter.next().map(|bundle| {
-            match bundle.as_ref_or_mut() {
-                RefOrMut::Ref(bundle) => {
-                    (mint_capability_ref(&bundle.time, internal.clone()), RefOrMut::Ref(&bundle.data))
-                },
-                RefOrMut::Mut(bundle) => {
-                    (mint_capability_ref(&bundle.time, internal.clone()), RefOrMut::Mut(&mut bundle.data))
-                },
-            }
+            let (time, data) = match bundle.as_ref_or_mut() {
+                RefOrMut::Ref(bundle) => (&bundle.time, RefOrMut::Ref(&bundle.data)),
+                RefOrMut::Mut(bundle) => (&bundle.time, RefOrMut::Mut(&mut bundle.data)),
+            };
+            let capability = mint_capability_ref(time, internal.clone());
+            #[cfg(feature = "capability-checks")]
+            let capability = capability.with_origin(origin.clone());
+            (capability, data)
         })
     }
 
+    /// Names `origin` as the operator of the capabilities of received messages.
+    #[cfg(feature = "capability-checks")]
+    pub(crate) fn with_origin(mut self, origin: Rc<Origin>) -> Self {
+        self.origin = Some(origin);
+        self
+    }
+
     /// Repeatedly calls `logic` till exhaustion of the available input data.
     /// `logic` receives a capability and an input buffer.
     ///
@@ -76,7 +91,7 @@ impl<'a, T: Timestamp, D: Data, P: Pull<Bundle<T, D>>> InputHandle<T, D, P> {
     /// ```
     #[inline]
     pub fn for_each<F: FnMut(CapabilityRef<T>, RefOrMut<Vec<D>>)>(&mut self, mut logic: F) {
-        let mut logging = self.logging.clone();
+        let mut logging = self.logging.clone().filter(|l| l.interested(EventKind::GuardedMessage));
         while let Some((cap, data)) = self.next() {
             logging.as_mut().map(|l| l.log(crate::logging::GuardedMessageEvent { is_start: true }));
             logic(cap, data);
@@ -144,6 +159,8 @@ pub fn new_input_handle<T: Timestamp, D, P: Pull<Bundle<T, D>>>(pull_counter: Pu
         pull_counter,
         internal,
         logging,
+        #[cfg(feature = "capability-checks")]
+        origin: None,
     }
 }
 
@@ -155,6 +172,8 @@ pub fn new_input_handle<T: Timestamp, D, P: Pull<Bundle<T, D>>>(pull_counter: Pu
 pub struct OutputWrapper<T: Timestamp, D, P: Push<Bundle<T, D>>> {
     push_buffer: Buffer<T, D, PushCounter<T, D, P>>,
     internal_buffer: Rc<RefCell<ChangeBatch<T>>>,
+    #[cfg(feature = "capability-checks")]
+    origin: Option<(Rc<Origin>, usize)>,
 }
 
 impl<T: Timestamp, D, P: Push<Bundle<T, D>>> OutputWrapper<T, D, P> {
@@ -163,6 +182,8 @@ impl<T: Timestamp, D, P: Push<Bundle<T, D>>> OutputWrapper<T, D, P> {
         OutputWrapper {
             push_buffer,
             internal_buffer,
+            #[cfg(feature = "capability-checks")]
+            origin: None,
         }
     }
     /// Borrows the push buffer into a handle, which can be used to send records.
@@ -173,8 +194,17 @@ impl<T: Timestamp, D, P: Push<Bundle<T, D>>> OutputWrapper<T, D, P> {
         OutputHandle {
             push_buffer: &mut self.push_buffer,
             internal_buffer: &self.internal_buffer,
+            #[cfg(feature = "capability-checks")]
+            origin: &self.origin,
         }
     }
+
+    /// Records that the output is `output` of operator `origin`, to be named by misuse assertions.
+    #[cfg(feature = "capability-checks")]
+    pub(crate) fn with_origin(mut self, origin: Rc<Origin>, output: usize) -> Self {
+        self.origin = Some((origin, output));
+        self
+    }
 }
 
 
@@ -182,6 +212,8 @@ impl<T: Timestamp, D, P: Push<Bundle<T, D>>> OutputWrapper<T, D, P> {
 pub struct OutputHandle<'a, T: Timestamp, D: 'a, P: Push<Bundle<T, D>>+'a> {
     push_buffer: &'a mut Buffer<T, D, PushCounter<T, D, P>>,
     internal_buffer: &'a Rc<RefCell<ChangeBatch<T>>>,
+    #[cfg(feature = "capability-checks")]
+    origin: &'a Option<(Rc<Origin>, usize)>,
 }
 
 impl<'a, T: Timestamp, D, P: Push<Bundle<T, D>>> OutputHandle<'a, T, D, P> {
@@ -208,6 +240,11 @@ impl<'a, T: Timestamp, D, P: Push<Bundle<T, D>>> OutputHandle<'a, T, D, P> {
     /// });
     /// ```
     pub fn session<'b, C: CapabilityTrait<T>>(&'b mut self, cap: &'b C) -> Session<'b, T, D, PushCounter<T, D, P>> where 'a: 'b {
+        #[cfg(feature = "capability-checks")]
+        if !cap.valid_for_output(self.internal_buffer) {
+            let output = self.origin.as_ref().map(|(origin, output)| origin.describe(*output)).unwrap_or_default();
+            panic!("Attempted to open output session{} with a capability for {:?} of another output{}", output, cap.time(), cap.context());
+        }
         assert!(cap.valid_for_output(&self.internal_buffer), "Attempted to open output session with invalid capability");
         self.push_buffer.session(cap.time())
     }
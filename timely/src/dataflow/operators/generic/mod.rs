@@ -5,15 +5,18 @@
 pub mod operator;
 pub mod builder_rc;
 pub mod builder_raw;
+pub mod builder_callback;
 // pub mod builder_ref;
 mod handles;
 mod notificator;
 mod operator_info;
+mod timers;
 
 pub use self::handles::{InputHandle, FrontieredInputHandle, OutputHandle, OutputWrapper};
-pub use self::notificator::{Notificator, FrontierNotificator};
+pub use self::notificator::{Notificator, FrontierNotificator, SubsetNotificator};
 
 // pub use self::unary::Unary;
 // pub use self::binary::Binary;
 pub use self::operator::{Operator, source};
 pub use self::operator_info::OperatorInfo;
+pub use self::timers::Timers;
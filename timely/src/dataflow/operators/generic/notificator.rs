@@ -2,6 +2,7 @@ use crate::progress::frontier::{AntichainRef, MutableAntichain};
 use crate::progress::Timestamp;
 use crate::dataflow::operators::Capability;
 use crate::logging::TimelyLogger as Logger;
+use crate::logging::{EventKind, LogInterest};
 
 /// Tracks requests for notification and delivers available notifications.
 ///
@@ -79,10 +80,11 @@ impl<'a, T: Timestamp> Notificator<'a, T> {
     /// representing how many capabilities were requested for that specific timestamp.
     #[inline]
     pub fn for_each<F: FnMut(Capability<T>, u64, &mut Notificator<T>)>(&mut self, mut logic: F) {
+        let logging = self.logging.clone().filter(|l| l.interested(EventKind::GuardedProgress));
         while let Some((cap, count)) = self.next() {
-            self.logging.as_ref().map(|l| l.log(crate::logging::GuardedProgressEvent { is_start: true }));
+            logging.as_ref().map(|l| l.log(crate::logging::GuardedProgressEvent { is_start: true }));
             logic(cap, count, self);
-            self.logging.as_ref().map(|l| l.log(crate::logging::GuardedProgressEvent { is_start: false }));
+            logging.as_ref().map(|l| l.log(crate::logging::GuardedProgressEvent { is_start: false }));
         }
     }
 }
@@ -403,6 +405,133 @@ impl<T: Timestamp> FrontierNotificator<T> {
     }
 }
 
+/// Tracks requests for notification once times are complete on subsets of inputs.
+///
+/// Each request names the inputs whose frontiers must have passed its time, and is delivered
+/// once they have, regardless of the frontiers of other inputs. This supports operators whose
+/// inputs advance independently, for example an operator that can act on the data of its first
+/// input as soon as that input is complete, but must wait on all inputs for other actions.
+///
+/// Requests for the same time and the same inputs are delivered once. Available notifications
+/// are delivered in timestamp order, and those for the same inputs are non-decreasing as long as
+/// no requests are made at times prior to those already delivered.
+///
+/// # Examples
+/// ```
+/// use std::rc::Rc;
+/// use std::cell::RefCell;
+/// use timely::dataflow::operators::{Input, Inspect, SubsetNotificator};
+/// use timely::dataflow::operators::generic::operator::Operator;
+/// use timely::dataflow::channels::pact::Pipeline;
+///
+/// timely::execute(timely::Configuration::Thread, |worker| {
+///     let reported = Rc::new(RefCell::new(Vec::new()));
+///     let shared = reported.clone();
+///     let (mut in1, mut in2) = worker.dataflow::<usize,_,_>(|scope| {
+///         let (in1_handle, in1) = scope.new_input::<usize>();
+///         let (in2_handle, in2) = scope.new_input::<usize>();
+///         in1.binary_frontier(&in2, Pipeline, Pipeline, "example", |_cap, _info| {
+///             let mut notificator = SubsetNotificator::new();
+///             let mut vector = Vec::new();
+///             move |input1, input2, output| {
+///                 // Times of the first input are reported once the first input completes them.
+///                 input1.for_each(|time, data| {
+///                     data.swap(&mut vector);
+///                     notificator.notify_at(time.retain(), &[0]);
+///                 });
+///                 // Times of the second input are reported once both inputs complete them.
+///                 input2.for_each(|time, data| {
+///                     data.swap(&mut vector);
+///                     notificator.notify_at(time.retain(), &[0, 1]);
+///                 });
+///                 notificator.for_each(&[input1.frontier(), input2.frontier()], |time, inputs, _| {
+///                     output.session(&time).give((*time.time(), inputs.len()));
+///                 });
+///             }
+///         })
+///         .inspect(move |x| shared.borrow_mut().push(*x));
+///
+///         (in1_handle, in2_handle)
+///     });
+///
+///     in1.send(0);
+///     in2.send(0);
+///     in1.advance_to(1);
+///     worker.step_while(|| reported.borrow().is_empty());
+///     assert_eq!(*reported.borrow(), vec![(0, 1)]);
+///
+///     in2.advance_to(1);
+///     worker.step_while(|| reported.borrow().len() < 2);
+///     assert_eq!(*reported.borrow(), vec![(0, 1), (0, 2)]);
+/// }).unwrap();
+/// ```
+pub struct SubsetNotificator<T: Timestamp> {
+    /// Requested capabilities, with the sorted and deduplicated indices of their inputs.
+    pending: Vec<(Capability<T>, Vec<usize>)>,
+}
+
+impl<T: Timestamp> SubsetNotificator<T> {
+    /// Allocates a new `SubsetNotificator`.
+    pub fn new() -> Self {
+        SubsetNotificator {
+            pending: Vec::new(),
+        }
+    }
+
+    /// Requests a notification at the time associated with capability `cap`, once the time is
+    /// complete on each of `inputs`. Takes ownership of the capability.
+    ///
+    /// The indices of `inputs` refer to positions in the frontiers supplied to `for_each`. A
+    /// request naming no inputs is available immediately.
+    #[inline]
+    pub fn notify_at(&mut self, cap: Capability<T>, inputs: &[usize]) {
+        let mut inputs = inputs.to_vec();
+        inputs.sort();
+        inputs.dedup();
+        self.pending.push((cap, inputs));
+    }
+
+    /// Repeatedly calls `logic` till exhaustion of the notifications made available by inspecting
+    /// the frontiers.
+    ///
+    /// `logic` receives a capability for `t`, the timestamp being notified, and the inputs on which
+    /// `t` is complete, as requested.
+    ///
+    /// # Panics
+    /// Panics if a request names an input for which there is no frontier.
+    pub fn for_each<'a, F: FnMut(Capability<T>, &[usize], &mut SubsetNotificator<T>)>(&mut self, frontiers: &'a [&'a MutableAntichain<T>], mut logic: F) {
+        let mut available = self.take_available(frontiers);
+        while !available.is_empty() {
+            for (cap, inputs) in available.drain(..) {
+                logic(cap, &inputs[..], self);
+            }
+            available = self.take_available(frontiers);
+        }
+    }
+
+    /// Iterates over pending capabilities and the inputs on which their times must be complete.
+    pub fn pending<'a>(&'a self) -> ::std::slice::Iter<'a, (Capability<T>, Vec<usize>)> {
+        self.pending.iter()
+    }
+
+    /// Removes and returns pending requests whose times are complete on their inputs, in timestamp order.
+    fn take_available(&mut self, frontiers: &[&MutableAntichain<T>]) -> Vec<(Capability<T>, Vec<usize>)> {
+        self.pending.sort_by(|x, y| (x.0.time(), &x.1).cmp(&(y.0.time(), &y.1)));
+        self.pending.dedup_by(|x, y| x.0.time() == y.0.time() && x.1 == y.1);
+        let (available, pending) = self.pending.drain(..).partition(|(cap, inputs)| {
+            inputs.iter().all(|&input| !frontiers[input].less_equal(cap.time()))
+        });
+        self.pending = pending;
+        available
+    }
+}
+
+impl<T: Timestamp> Default for SubsetNotificator<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 #[derive(PartialEq, Eq)]
 struct OrderReversed<T: Timestamp> {
     element: Capability<T>,
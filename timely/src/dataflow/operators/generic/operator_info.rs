@@ -1,3 +1,4 @@
+use crate::dataflow::memory::MemoryAccount;
 
 /// Information about the operator being constructed
 #[derive(Clone)]
@@ -8,6 +9,8 @@ pub struct OperatorInfo {
     pub global_id: usize,
     /// Operator address.
     pub address: Vec<usize>,
+    /// The memory account of the operator, if memory accounting is enabled.
+    pub memory: Option<MemoryAccount>,
 }
 
 impl OperatorInfo {
@@ -17,6 +20,13 @@ impl OperatorInfo {
             local_id,
             global_id,
             address: address.to_vec(),
+            memory: None,
         }
     }
+
+    /// Attaches the memory account of the operator.
+    pub(crate) fn with_memory(mut self, memory: Option<MemoryAccount>) -> OperatorInfo {
+        self.memory = memory;
+        self
+    }
 }
@@ -0,0 +1,122 @@
+//! Processing-time timers for operators.
+
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+use crate::time::{Duration, Instant};
+
+use crate::scheduling::{Activator, Clock};
+
+/// Wall-clock timers for an operator, which schedule the operator when they fire.
+///
+/// Timers are measured by the clock of the worker, which for tests may be a manual clock.
+/// Each registered timer activates the operator once its delay has elapsed, at which point the
+/// operator can retrieve the identifiers of fired timers with `fired`. This supports operators
+/// that must act on the passage of time, for example to time out or evict state, or to
+/// periodically flush output, without the help of external threads.
+///
+/// # Examples
+/// ```
+/// use std::time::Duration;
+/// use timely::dataflow::operators::{Input, Inspect, Probe};
+/// use timely::dataflow::operators::generic::builder_rc::OperatorBuilder;
+/// use timely::dataflow::channels::pact::Pipeline;
+///
+/// timely::execute_from_args(std::env::args(), |worker| {
+///     let (mut input, probe) = worker.dataflow::<usize,_,_>(|scope| {
+///         let (input, stream) = scope.new_input::<String>();
+///
+///         // Each record is reported once it is ten milliseconds old.
+///         let mut builder = OperatorBuilder::new("Delay".to_owned(), scope.clone());
+///         let mut records = builder.new_input(&stream, Pipeline);
+///         let (mut output, delayed) = builder.new_output();
+///         let mut timers = builder.timers();
+///
+///         builder.build(move |_capabilities| {
+///             let mut pending = std::collections::HashMap::new();
+///             let mut vector = Vec::new();
+///             move |_frontiers| {
+///                 let mut output = output.activate();
+///                 records.for_each(|time, data| {
+///                     let time = time.retain();
+///                     data.swap(&mut vector);
+///                     for datum in vector.drain(..) {
+///                         let timer = timers.register_timer(Duration::from_millis(10));
+///                         pending.insert(timer, (time.clone(), datum));
+///                     }
+///                 });
+///                 while let Some(timer) = timers.fired() {
+///                     let (time, datum) = pending.remove(&timer).unwrap();
+///                     output.session(&time).give(datum);
+///                 }
+///             }
+///         });
+///
+///         let probe = delayed.inspect(|x| println!("delayed: {}", x)).probe();
+///         (input, probe)
+///     });
+///
+///     input.send("hello".to_owned());
+///     input.advance_to(1);
+///     while probe.less_than(input.time()) {
+///         worker.step();
+///     }
+/// }).unwrap();
+/// ```
+pub struct Timers {
+    pending: BinaryHeap<Reverse<(Instant, usize)>>,
+    next_id: usize,
+    activator: Activator,
+    clock: Clock,
+}
+
+impl Timers {
+    /// Creates timers that schedule the operator through `activator`.
+    pub fn new(activator: Activator) -> Self {
+        Timers {
+            pending: BinaryHeap::new(),
+            next_id: 0,
+            clock: activator.clock(),
+            activator,
+        }
+    }
+
+    /// Registers a timer that fires after `delay`, and returns its identifier.
+    ///
+    /// The operator is scheduled once the timer fires, and the identifier is then returned
+    /// by `fired`. Identifiers are distinct for each timer registered with `self`.
+    pub fn register_timer(&mut self, delay: Duration) -> usize {
+        let id = self.next_id;
+        self.next_id += 1;
+        self.pending.push(Reverse((self.clock.now() + delay, id)));
+        self.activator.activate_after(delay);
+        id
+    }
+
+    /// Returns the identifier of a fired timer, if any, removing it from the pending timers.
+    ///
+    /// Timers are returned in the order of their deadlines.
+    pub fn fired(&mut self) -> Option<usize> {
+        let now = self.clock.now();
+        if self.pending.peek().map(|Reverse((deadline, _))| deadline <= &now) == Some(true) {
+            self.pending.pop().map(|Reverse((_, id))| id)
+        }
+        else {
+            None
+        }
+    }
+
+    /// The deadline of the next timer to fire, if any are pending.
+    pub fn next_deadline(&self) -> Option<Instant> {
+        self.pending.peek().map(|Reverse((deadline, _))| *deadline)
+    }
+
+    /// The number of timers that have not yet been returned by `fired`.
+    pub fn len(&self) -> usize {
+        self.pending.len()
+    }
+
+    /// Returns `true` if no timers are pending.
+    pub fn is_empty(&self) -> bool {
+        self.pending.is_empty()
+    }
+}
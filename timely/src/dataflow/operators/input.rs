@@ -1,8 +1,9 @@
 //! Create new `Streams` connected to external inputs.
 
 use std::rc::Rc;
-use std::cell::RefCell;
+use std::cell::{Cell, RefCell};
 use std::default::Default;
+use crate::time::{Duration, Instant};
 
 use crate::scheduling::{Schedule, Activator};
 
@@ -14,6 +15,8 @@ use crate::Data;
 use crate::communication::Push;
 use crate::dataflow::{Stream, ScopeParent, Scope};
 use crate::dataflow::channels::{Message, pushers::{Tee, Counter}};
+#[cfg(feature = "markers")]
+use crate::dataflow::markers::{Marker, push_markers};
 
 // TODO : This is an exogenous input, but it would be nice to wrap a Subgraph in something
 // TODO : more like a harness, with direct access to its inputs.
@@ -115,6 +118,7 @@ impl<G: Scope> Input for G where <G as ScopeParent>::Timestamp: TotalOrder {
         let progress = Rc::new(RefCell::new(ChangeBatch::new()));
 
         handle.register(counter, progress.clone());
+        handle.shutdown = self.shutdown_signal();
 
         let copies = self.peers();
 
@@ -177,6 +181,23 @@ pub struct Handle<T: Timestamp, D: Data> {
     buffer1: Vec<D>,
     buffer2: Vec<D>,
     now_at: T,
+    // Markers to send with the next records.
+    #[cfg(feature = "markers")]
+    markers: Vec<Marker>,
+    auto: Option<AutoAdvance<T>>,
+    // Set once the computation is shutting down, after which the handle is closed.
+    shutdown: Option<Rc<Cell<bool>>>,
+}
+
+/// Determines the next epoch of an automatically advancing input from its current epoch.
+type NextEpoch<T> = Box<dyn Fn(&T)->T>;
+
+/// How an input handle advances its epoch without calls to `advance_to`.
+struct AutoAdvance<T> {
+    /// The minimum time between advances, or `None` to advance after each flush.
+    interval: Option<Duration>,
+    last: Instant,
+    next: NextEpoch<T>,
 }
 
 impl<T:Timestamp, D: Data> Handle<T, D> {
@@ -215,6 +236,10 @@ impl<T:Timestamp, D: Data> Handle<T, D> {
             buffer1: Vec::with_capacity(Message::<T, D>::default_length()),
             buffer2: Vec::with_capacity(Message::<T, D>::default_length()),
             now_at: Default::default(),
+            #[cfg(feature = "markers")]
+            markers: Vec::new(),
+            auto: None,
+            shutdown: None,
         }
     }
 
@@ -258,7 +283,7 @@ impl<T:Timestamp, D: Data> Handle<T, D> {
         progress: Rc<RefCell<ChangeBatch<T>>>
     ) {
         // flush current contents, so new registrant does not see existing data.
-        if !self.buffer1.is_empty() { self.flush(); }
+        if !self.buffer1.is_empty() { self.flush_buffer(); }
 
         // we need to produce an appropriate update to the capabilities for `progress`, in case a
         // user has decided to drive the handle around a bit before registering it.
@@ -271,14 +296,20 @@ impl<T:Timestamp, D: Data> Handle<T, D> {
 
     // flushes our buffer at each of the destinations. there can be more than one; clone if needed.
     #[inline(never)]
-    fn flush(&mut self) {
+    fn flush_buffer(&mut self) {
         for index in 0 .. self.pushers.len() {
             if index < self.pushers.len() - 1 {
                 self.buffer2.extend_from_slice(&self.buffer1[..]);
+                #[cfg(feature = "markers")]
+                Message::push_marked_at(&mut self.buffer2, self.markers.clone(), self.now_at.clone(), &mut self.pushers[index]);
+                #[cfg(not(feature = "markers"))]
                 Message::push_at(&mut self.buffer2, self.now_at.clone(), &mut self.pushers[index]);
                 debug_assert!(self.buffer2.is_empty());
             }
             else {
+                #[cfg(feature = "markers")]
+                Message::push_marked_at(&mut self.buffer1, ::std::mem::take(&mut self.markers), self.now_at.clone(), &mut self.pushers[index]);
+                #[cfg(not(feature = "markers"))]
                 Message::push_at(&mut self.buffer1, self.now_at.clone(), &mut self.pushers[index]);
                 debug_assert!(self.buffer1.is_empty());
             }
@@ -288,7 +319,15 @@ impl<T:Timestamp, D: Data> Handle<T, D> {
 
     // closes the current epoch, flushing if needed, shutting if needed, and updating the frontier.
     fn close_epoch(&mut self) {
-        if !self.buffer1.is_empty() { self.flush(); }
+        if !self.buffer1.is_empty() { self.flush_buffer(); }
+        // send markers no records followed, in messages without records.
+        #[cfg(feature = "markers")]
+        if !self.markers.is_empty() {
+            for pusher in self.pushers.iter_mut() {
+                push_markers(self.now_at.clone(), self.markers.clone(), pusher);
+            }
+            self.markers.clear();
+        }
         for pusher in self.pushers.iter_mut() {
             pusher.done();
         }
@@ -301,13 +340,34 @@ impl<T:Timestamp, D: Data> Handle<T, D> {
         }
     }
 
+    // Closes the handle if the computation is shutting down, and indicates whether it has.
+    fn shutting_down(&mut self) -> bool {
+        if self.shutdown.as_ref().map(|flag| flag.get()) == Some(true) {
+            if !self.progress.is_empty() {
+                self.close_epoch();
+                self.activate.clear();
+                self.progress.clear();
+                self.pushers.clear();
+            }
+            true
+        }
+        else {
+            false
+        }
+    }
+
     #[inline]
     /// Sends one record into the corresponding timely dataflow `Stream`, at the current epoch.
+    ///
+    /// Once the computation is shutting down, as requested by `Worker::request_shutdown`, the
+    /// handle is closed and records are discarded.
     pub fn send(&mut self, data: D) {
+        if self.shutting_down() { return; }
         // assert!(self.buffer1.capacity() == Message::<T, D>::default_length());
         self.buffer1.push(data);
         if self.buffer1.len() == self.buffer1.capacity() {
-            self.flush();
+            self.flush_buffer();
+            self.advance_if_due(false);
         }
     }
 
@@ -316,23 +376,175 @@ impl<T:Timestamp, D: Data> Handle<T, D> {
     /// This method flushes single elements previously sent with `send`, to keep the insertion order.
     pub fn send_batch(&mut self, buffer: &mut Vec<D>) {
 
-        if !buffer.is_empty() {
+        if self.shutting_down() {
+            buffer.clear();
+        }
+        else if !buffer.is_empty() {
             // flush buffered elements to ensure local fifo.
-            if !self.buffer1.is_empty() { self.flush(); }
+            if !self.buffer1.is_empty() { self.flush_buffer(); }
 
             // push buffer (or clone of buffer) at each destination.
             for index in 0 .. self.pushers.len() {
                 if index < self.pushers.len() - 1 {
                     self.buffer2.extend_from_slice(&buffer[..]);
+                    #[cfg(feature = "markers")]
+                    Message::push_marked_at(&mut self.buffer2, self.markers.clone(), self.now_at.clone(), &mut self.pushers[index]);
+                    #[cfg(not(feature = "markers"))]
                     Message::push_at(&mut self.buffer2, self.now_at.clone(), &mut self.pushers[index]);
                     assert!(self.buffer2.is_empty());
                 }
                 else {
+                    #[cfg(feature = "markers")]
+                    Message::push_marked_at(buffer, ::std::mem::take(&mut self.markers), self.now_at.clone(), &mut self.pushers[index]);
+                    #[cfg(not(feature = "markers"))]
                     Message::push_at(buffer, self.now_at.clone(), &mut self.pushers[index]);
                     assert!(buffer.is_empty());
                 }
             }
             buffer.clear();
+            self.advance_if_due(false);
+        }
+    }
+
+    /// Sends a container of records into the corresponding timely dataflow `Stream`, at the current epoch.
+    ///
+    /// The container is moved into the channel as a single batch, rather than copied record by record.
+    /// Streams carry their records in vectors, into which the container is converted; for vectors, boxed
+    /// slices, `VecDeque`s, and `BinaryHeap`s the conversion re-uses the container's memory. As with
+    /// `send_batch`, records previously sent with `send` are flushed first.
+    ///
+    /// # Examples
+    /// ```
+    /// use std::collections::VecDeque;
+    /// use timely::dataflow::InputHandle;
+    /// use timely::dataflow::operators::{Input, Inspect, Probe};
+    ///
+    /// timely::execute_from_args(::std::env::args(), |worker| {
+    ///
+    ///     let mut input = InputHandle::new();
+    ///     let probe = worker.dataflow(|scope| {
+    ///         scope.input_from(&mut input)
+    ///              .inspect(|x: &u64| println!("seen: {:?}", x))
+    ///              .probe()
+    ///     });
+    ///
+    ///     input.send_container(vec![0, 1, 2].into_boxed_slice());
+    ///     input.send_container((3 .. 6).collect::<VecDeque<_>>());
+    ///     input.advance_to(1);
+    ///     worker.step_while(|| probe.less_than(input.time()));
+    /// }).unwrap();
+    /// ```
+    pub fn send_container<C: Into<Vec<D>>>(&mut self, container: C) {
+        let mut buffer = container.into();
+        self.send_batch(&mut buffer);
+    }
+
+    /// Injects a latency marker identified by `id`, to travel with the next records sent from the handle.
+    ///
+    /// Records previously sent with `send` are flushed first, so that the marker follows them. If
+    /// no records are sent before the epoch advances, the marker is sent on its own as it does.
+    /// The marker is reported by the probes it reaches; see the `markers` module for an example.
+    #[cfg(feature = "markers")]
+    pub fn mark(&mut self, id: u64) {
+        if self.shutting_down() { return; }
+        if !self.buffer1.is_empty() { self.flush_buffer(); }
+        self.markers.push(Marker::new(id));
+    }
+
+    /// Sends records previously sent with `send` into the dataflow, without waiting for a full batch.
+    ///
+    /// If the handle advances its epoch automatically, this method advances the epoch if it is due,
+    /// as set by `advance_on_flush` or `advance_every`.
+    pub fn flush(&mut self) {
+        if self.shutting_down() { return; }
+        if !self.buffer1.is_empty() { self.flush_buffer(); }
+        self.advance_if_due(true);
+    }
+
+    /// Advances the epoch to `next(epoch)` after each call to `flush`.
+    ///
+    /// # Examples
+    /// ```
+    /// use timely::dataflow::InputHandle;
+    /// use timely::dataflow::operators::{Input, Inspect, Probe};
+    ///
+    /// timely::execute_from_args(::std::env::args(), |worker| {
+    ///
+    ///     let mut input = InputHandle::new();
+    ///     let probe = worker.dataflow(|scope| {
+    ///         scope.input_from(&mut input)
+    ///              .inspect(|x: &u64| println!("seen: {:?}", x))
+    ///              .probe()
+    ///     });
+    ///
+    ///     input.advance_on_flush(|epoch: &usize| epoch + 1);
+    ///     for round in 0..10 {
+    ///         input.send(round);
+    ///         input.flush();
+    ///         worker.step_while(|| probe.less_than(input.time()));
+    ///     }
+    ///     assert_eq!(input.time(), &10);
+    /// }).unwrap();
+    /// ```
+    pub fn advance_on_flush<F: Fn(&T)->T+'static>(&mut self, next: F) {
+        self.auto = Some(AutoAdvance { interval: None, last: Instant::now(), next: Box::new(next) });
+    }
+
+    /// Advances the epoch to `next(epoch)` once at least `interval` has elapsed since the last
+    /// automatic advance.
+    ///
+    /// The handle has no thread of its own, and checks whether an advance is due only when it
+    /// sends a batch of records, or when `flush` is called. Ingest loops that may go without
+    /// records for a while should call `flush` regularly.
+    ///
+    /// # Examples
+    /// ```
+    /// use std::time::Duration;
+    /// use timely::dataflow::InputHandle;
+    /// use timely::dataflow::operators::{Input, Inspect, Probe};
+    ///
+    /// timely::execute_from_args(::std::env::args(), |worker| {
+    ///
+    ///     let mut input = InputHandle::new();
+    ///     let probe = worker.dataflow(|scope| {
+    ///         scope.input_from(&mut input)
+    ///              .inspect(|x: &u64| println!("seen: {:?}", x))
+    ///              .probe()
+    ///     });
+    ///
+    ///     input.advance_every(Duration::from_millis(10), |epoch: &usize| epoch + 1);
+    ///     input.send(0);
+    ///     std::thread::sleep(Duration::from_millis(10));
+    ///     input.flush();
+    ///     assert_eq!(input.time(), &1);
+    ///     worker.step_while(|| probe.less_than(input.time()));
+    /// }).unwrap();
+    /// ```
+    pub fn advance_every<F: Fn(&T)->T+'static>(&mut self, interval: Duration, next: F) {
+        self.auto = Some(AutoAdvance { interval: Some(interval), last: Instant::now(), next: Box::new(next) });
+    }
+
+    /// Stops advancing the epoch automatically.
+    pub fn stop_auto_advance(&mut self) {
+        self.auto = None;
+    }
+
+    // advances the epoch if the handle advances automatically and an advance is due, where
+    // `flushing` indicates a call to `flush`.
+    fn advance_if_due(&mut self, flushing: bool) {
+        let now_at = &self.now_at;
+        let next = self.auto.as_mut().and_then(|auto| {
+            let due = auto.interval.map(|interval| auto.last.elapsed() >= interval).unwrap_or(flushing);
+            if due {
+                auto.last = Instant::now();
+                Some((auto.next)(now_at))
+            }
+            else {
+                None
+            }
+        });
+        if let Some(next) = next {
+            self.advance_to(next);
         }
     }
 
@@ -343,6 +555,7 @@ impl<T:Timestamp, D: Data> Handle<T, D> {
     pub fn advance_to(&mut self, next: T) {
         // Assert that we do not rewind time.
         assert!(self.now_at.less_equal(&next));
+        self.shutting_down();
         // Flush buffers if time has actually changed.
         if !self.now_at.eq(&next) {
             self.close_epoch();
@@ -0,0 +1,189 @@
+//! Streams of keyed records, partitioned among workers by key.
+//!
+//! Many operators on `(key, val)` records first exchange the records so that all records with
+//! the same key are at the same worker. A `KeyedStream` records in its type that this exchange
+//! has already happened, so that its operators can proceed without exchanging records again.
+
+use std::hash::{Hash, Hasher};
+use std::collections::HashMap;
+use std::collections::hash_map::DefaultHasher;
+
+use crate::{Data, ExchangeData};
+use crate::dataflow::{Stream, Scope};
+use crate::dataflow::channels::pact::Pipeline;
+use crate::dataflow::operators::{Map, Filter, Exchange};
+use crate::dataflow::operators::generic::operator::Operator;
+use crate::dataflow::operators::aggregation::aggregate::aggregate_with;
+
+/// Extension trait for `Stream`.
+pub trait KeyBy<G: Scope, D: Data> {
+    /// Extracts a key and value from each record, and partitions the records among workers by key.
+    ///
+    /// # Examples
+    /// ```
+    /// use timely::dataflow::operators::{ToStream, KeyBy, Capture};
+    /// use timely::dataflow::operators::capture::Extract;
+    ///
+    /// let captured = timely::example(|scope| {
+    ///     (0..10).to_stream(scope)
+    ///            .key_by(|x| (x % 2, x))
+    ///            .aggregate(|_key, val, agg| *agg += val, |_key, agg: u64| agg)
+    ///            .into_stream()
+    ///            .capture()
+    /// });
+    ///
+    /// let mut extracted = captured.extract();
+    /// extracted[0].1.sort();
+    /// assert_eq!(extracted, vec![(0, vec![(0, 20), (1, 25)])]);
+    /// ```
+    fn key_by<K: ExchangeData+Hash+Eq, V: ExchangeData, L: Fn(D)->(K, V)+'static>(&self, logic: L) -> KeyedStream<G, K, V>;
+}
+
+impl<G: Scope, D: Data> KeyBy<G, D> for Stream<G, D> {
+    fn key_by<K: ExchangeData+Hash+Eq, V: ExchangeData, L: Fn(D)->(K, V)+'static>(&self, logic: L) -> KeyedStream<G, K, V> {
+        let stream = self.map(logic).exchange(|(key, _)| hash_key(key));
+        KeyedStream { stream }
+    }
+}
+
+/// The worker-independent hash by which keyed streams are partitioned.
+pub(crate) fn hash_key<K: Hash>(key: &K) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    key.hash(&mut hasher);
+    hasher.finish()
+}
+
+/// A stream of `(key, val)` records, partitioned among workers by key.
+///
+/// All records with the same key are at the same worker, and operators on keyed streams
+/// preserve this partitioning. Two keyed streams with the same key type are partitioned
+/// identically, and can be joined without exchanging records.
+#[derive(Clone)]
+pub struct KeyedStream<G: Scope, K, V> {
+    stream: Stream<G, (K, V)>,
+}
+
+impl<G: Scope, K: Data+Hash+Eq, V: Data> KeyedStream<G, K, V> {
+
+    /// The underlying stream of `(key, val)` records.
+    pub fn stream(&self) -> &Stream<G, (K, V)> {
+        &self.stream
+    }
+
+    /// Returns the underlying stream of `(key, val)` records.
+    pub fn into_stream(self) -> Stream<G, (K, V)> {
+        self.stream
+    }
+
+    /// Transforms the value of each record, retaining its key.
+    ///
+    /// # Examples
+    /// ```
+    /// use timely::dataflow::operators::{ToStream, KeyBy, Capture};
+    /// use timely::dataflow::operators::capture::Extract;
+    ///
+    /// let captured = timely::example(|scope| {
+    ///     (0..4).to_stream(scope)
+    ///            .key_by(|x| (x, x))
+    ///            .map_values(|x| x * 10)
+    ///            .into_stream()
+    ///            .capture()
+    /// });
+    ///
+    /// assert_eq!(captured.extract(), vec![(0, vec![(0, 0), (1, 10), (2, 20), (3, 30)])]);
+    /// ```
+    pub fn map_values<V2: Data, L: Fn(V)->V2+'static>(&self, logic: L) -> KeyedStream<G, K, V2> {
+        KeyedStream { stream: self.stream.map(move |(key, val)| (key, logic(val))) }
+    }
+
+    /// Retains only records satisfying `predicate`.
+    pub fn filter<P: Fn(&K, &V)->bool+'static>(&self, predicate: P) -> KeyedStream<G, K, V> {
+        KeyedStream { stream: self.stream.filter(move |(key, val)| predicate(key, val)) }
+    }
+
+    /// Aggregates the values of each key within each time.
+    ///
+    /// This behaves as `Aggregate::aggregate`, except that records are not exchanged and the
+    /// result for each key is produced with that key, as a keyed stream.
+    pub fn aggregate<R: Data, A: Default+'static, F: Fn(&K, V, &mut A)+'static, E: Fn(&K, A)->R+'static>(&self, fold: F, emit: E) -> KeyedStream<G, K, R> {
+        let stream = aggregate_with(&self.stream, Pipeline, fold, move |key, agg| {
+            let result = emit(&key, agg);
+            (key, result)
+        });
+        KeyedStream { stream }
+    }
+
+    /// Joins the records of two keyed streams with equal keys and times.
+    ///
+    /// Each pair of records with the same key and time produces a record with that key and the
+    /// pair of their values. Pairs are produced as soon as both records are received, and the
+    /// records are retained only until their time is complete.
+    ///
+    /// # Examples
+    /// ```
+    /// use timely::dataflow::operators::{ToStream, KeyBy, Capture};
+    /// use timely::dataflow::operators::capture::Extract;
+    ///
+    /// let captured = timely::example(|scope| {
+    ///     let names = vec![(1, 'a'), (2, 'b')].to_stream(scope).key_by(|x| x);
+    ///     let values = vec![(1, 10), (2, 20), (3, 30)].to_stream(scope).key_by(|x| x);
+    ///     names.join(&values)
+    ///          .into_stream()
+    ///          .capture()
+    /// });
+    ///
+    /// let mut extracted = captured.extract();
+    /// extracted[0].1.sort();
+    /// assert_eq!(extracted, vec![(0, vec![(1, ('a', 10)), (2, ('b', 20))])]);
+    /// ```
+    pub fn join<V2: Data>(&self, other: &KeyedStream<G, K, V2>) -> KeyedStream<G, K, (V, V2)> {
+
+        let mut state1 = HashMap::<G::Timestamp, HashMap<K, Vec<V>>>::new();
+        let mut state2 = HashMap::<G::Timestamp, HashMap<K, Vec<V2>>>::new();
+        let mut vector1 = Vec::new();
+        let mut vector2 = Vec::new();
+
+        let stream = self.stream.binary_notify(&other.stream, Pipeline, Pipeline, "KeyedJoin", vec![], move |input1, input2, output, notificator| {
+
+            input1.for_each(|time, data| {
+                data.swap(&mut vector1);
+                let mut session = output.session(&time);
+                let others = state2.entry(time.time().clone()).or_insert_with(HashMap::new);
+                let mine = state1.entry(time.time().clone()).or_insert_with(HashMap::new);
+                for (key, val1) in vector1.drain(..) {
+                    if let Some(vals2) = others.get(&key) {
+                        for val2 in vals2.iter() {
+                            session.give((key.clone(), (val1.clone(), val2.clone())));
+                        }
+                    }
+                    mine.entry(key).or_insert_with(Vec::new).push(val1);
+                }
+                notificator.notify_at(time.retain());
+            });
+
+            input2.for_each(|time, data| {
+                data.swap(&mut vector2);
+                let mut session = output.session(&time);
+                let others = state1.entry(time.time().clone()).or_insert_with(HashMap::new);
+                let mine = state2.entry(time.time().clone()).or_insert_with(HashMap::new);
+                for (key, val2) in vector2.drain(..) {
+                    if let Some(vals1) = others.get(&key) {
+                        for val1 in vals1.iter() {
+                            session.give((key.clone(), (val1.clone(), val2.clone())));
+                        }
+                    }
+                    mine.entry(key).or_insert_with(Vec::new).push(val2);
+                }
+                notificator.notify_at(time.retain());
+            });
+
+            // discard the records of completed times.
+            notificator.for_each(|time,_,_| {
+                state1.remove(time.time());
+                state2.remove(time.time());
+            });
+        });
+
+        KeyedStream { stream }
+    }
+}
@@ -33,6 +33,20 @@ pub trait Map<S: Scope, D: Data> {
     /// });
     /// ```
     fn map_in_place<L: FnMut(&mut D)+'static>(&self, logic: L) -> Stream<S, D>;
+    /// Consumes each element of the stream and yields a new element of the same type, re-using
+    /// the memory of each batch of input for its output.
+    ///
+    /// # Examples
+    /// ```
+    /// use timely::dataflow::operators::{ToStream, Map, Inspect};
+    ///
+    /// timely::example(|scope| {
+    ///     (0..10).to_stream(scope)
+    ///            .map_owned_in_place(|x| x * 2)
+    ///            .inspect(|x| println!("seen: {:?}", x));
+    /// });
+    /// ```
+    fn map_owned_in_place<L: FnMut(D)->D+'static>(&self, logic: L) -> Stream<S, D>;
     /// Consumes each element of the stream and yields some number of new elements.
     ///
     /// # Examples
@@ -46,6 +60,20 @@ pub trait Map<S: Scope, D: Data> {
     /// });
     /// ```
     fn flat_map<I: IntoIterator, L: FnMut(D)->I+'static>(&self, logic: L) -> Stream<S, I::Item> where I::Item: Data;
+    /// Consumes each element of the stream and yields some number of new elements of the same
+    /// type, re-using the memory of each batch of input for a subsequent batch of output.
+    ///
+    /// # Examples
+    /// ```
+    /// use timely::dataflow::operators::{ToStream, Map, Inspect};
+    ///
+    /// timely::example(|scope| {
+    ///     (0..10).to_stream(scope)
+    ///            .flat_map_in_place(|x| (0..x))
+    ///            .inspect(|x| println!("seen: {:?}", x));
+    /// });
+    /// ```
+    fn flat_map_in_place<I: IntoIterator<Item=D>, L: FnMut(D)->I+'static>(&self, logic: L) -> Stream<S, D>;
 }
 
 impl<S: Scope, D: Data> Map<S, D> for Stream<S, D> {
@@ -68,6 +96,17 @@ impl<S: Scope, D: Data> Map<S, D> for Stream<S, D> {
             })
         })
     }
+    fn map_owned_in_place<L: FnMut(D)->D+'static>(&self, mut logic: L) -> Stream<S, D> {
+        let mut vector = Vec::new();
+        self.unary(Pipeline, "MapOwnedInPlace", move |_,_| move |input, output| {
+            input.for_each(|time, data| {
+                data.swap(&mut vector);
+                // Collecting from a vector's own iterator into elements of the same type re-uses its allocation.
+                vector = ::std::mem::take(&mut vector).into_iter().map(&mut logic).collect();
+                output.session(&time).give_vec(&mut vector);
+            })
+        })
+    }
     // TODO : This would be more robust if it captured an iterator and then pulled an appropriate
     // TODO : number of elements from the iterator. This would allow iterators that produce many
     // TODO : records without taking arbitrarily long and arbitrarily much memory.
@@ -80,4 +119,17 @@ impl<S: Scope, D: Data> Map<S, D> for Stream<S, D> {
             });
         })
     }
+    fn flat_map_in_place<I: IntoIterator<Item=D>, L: FnMut(D)->I+'static>(&self, mut logic: L) -> Stream<S, D> {
+        let mut vector = Vec::new();
+        let mut buffer = Vec::new();
+        self.unary(Pipeline, "FlatMapInPlace", move |_,_| move |input, output| {
+            input.for_each(|time, data| {
+                data.swap(&mut vector);
+                // Output goes to the allocation of an earlier input, and this input's allocation is kept.
+                buffer.extend(vector.drain(..).flat_map(|x| logic(x).into_iter()));
+                ::std::mem::swap(&mut vector, &mut buffer);
+                output.session(&time).give_vec(&mut vector);
+            });
+        })
+    }
 }
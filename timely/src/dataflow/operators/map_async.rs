@@ -0,0 +1,112 @@
+//! Asynchronous transformation of records, with a bounded number of requests in flight.
+//!
+//! Records are often enriched by requests to external services, for example key-value stores,
+//! whose responses arrive asynchronously. The `map_async` operator issues a request for each
+//! record as a `Future`, and polls the requests from within the operator, holding capabilities
+//! for their timestamps until their responses arrive. Futures are woken through thread-safe
+//! activations, and so may be completed by other threads, for example those of a client library.
+
+use std::collections::VecDeque;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll, Wake, Waker};
+
+use crate::Data;
+use crate::dataflow::{Stream, Scope};
+use crate::dataflow::channels::pact::Pipeline;
+use crate::dataflow::operators::Capability;
+use crate::dataflow::operators::generic::operator::Operator;
+use crate::scheduling::SyncActivator;
+
+/// Extension trait for `Stream`.
+pub trait MapAsync<G: Scope, D: Data> {
+    /// Transforms each record into a future, and produces its output once complete.
+    ///
+    /// At most `limit` futures are in flight at any moment, and further records wait until
+    /// earlier futures complete. Each output has the timestamp of the record whose future
+    /// produced it, and outputs are produced in the order their futures complete.
+    ///
+    /// # Examples
+    /// ```
+    /// use timely::dataflow::operators::{ToStream, MapAsync, Capture};
+    /// use timely::dataflow::operators::capture::Extract;
+    ///
+    /// let captured = timely::example(|scope| {
+    ///     (0..10).to_stream(scope)
+    ///            .map_async(4, |x| async move { x + 1 })
+    ///            .capture()
+    /// });
+    ///
+    /// let extracted = captured.extract();
+    /// assert_eq!(extracted, vec![(0, (1..11).collect::<Vec<_>>())]);
+    /// ```
+    fn map_async<D2: Data, F: Future<Output=D2>+'static, L: FnMut(D)->F+'static>(&self, limit: usize, logic: L) -> Stream<G, D2>;
+}
+
+/// Wakes a future by activating the operator polling it.
+pub(crate) struct ActivateWaker {
+    pub(crate) activator: SyncActivator,
+}
+
+impl Wake for ActivateWaker {
+    fn wake(self: Arc<Self>) {
+        self.wake_by_ref();
+    }
+    fn wake_by_ref(self: &Arc<Self>) {
+        // The worker may have shut down, in which case there is nothing to activate.
+        let _ = self.activator.activate();
+    }
+}
+
+impl<G: Scope, D: Data> MapAsync<G, D> for Stream<G, D> {
+    fn map_async<D2: Data, F: Future<Output=D2>+'static, L: FnMut(D)->F+'static>(&self, limit: usize, mut logic: L) -> Stream<G, D2> {
+
+        assert!(limit > 0, "map_async requires a positive limit");
+
+        let scope = self.scope();
+        self.unary(Pipeline, "MapAsync", move |_, info| {
+
+            let waker = Waker::from(Arc::new(ActivateWaker { activator: scope.sync_activator_for(&info.address[..]) }));
+            let mut waiting = VecDeque::<(Capability<G::Timestamp>, D)>::new();
+            let mut in_flight = Vec::<(Capability<G::Timestamp>, Pin<Box<F>>)>::new();
+            let mut vector = Vec::new();
+
+            move |input, output| {
+
+                input.for_each(|time, data| {
+                    let time = time.retain();
+                    data.swap(&mut vector);
+                    waiting.extend(vector.drain(..).map(|datum| (time.clone(), datum)));
+                });
+
+                let mut context = Context::from_waker(&waker);
+                loop {
+                    // Issue requests for waiting records, up to the limit.
+                    while in_flight.len() < limit {
+                        if let Some((time, datum)) = waiting.pop_front() {
+                            in_flight.push((time, Box::pin(logic(datum))));
+                        }
+                        else { break; }
+                    }
+
+                    // Poll each request, producing the outputs of those that are complete.
+                    let count = in_flight.len();
+                    let mut index = 0;
+                    while index < in_flight.len() {
+                        if let Poll::Ready(result) = in_flight[index].1.as_mut().poll(&mut context) {
+                            let (time, _) = in_flight.swap_remove(index);
+                            output.session(&time).give(result);
+                        }
+                        else {
+                            index += 1;
+                        }
+                    }
+
+                    // Continue only if completed requests made room for waiting records.
+                    if in_flight.len() == count || waiting.is_empty() { break; }
+                }
+            }
+        })
+    }
+}
@@ -13,21 +13,37 @@ pub use self::enterleave::{Enter, EnterAt, Leave};
 pub use self::input::Input;
 pub use self::unordered_input::UnorderedInput;
 pub use self::feedback::{Feedback, LoopVariable, ConnectLoop};
-pub use self::concat::{Concat, Concatenate};
+pub use self::concat::{Concat, Concatenate, MergeSorted};
 pub use self::partition::Partition;
 pub use self::map::Map;
+pub use self::map_async::MapAsync;
+#[cfg(feature = "rayon")]
+pub use self::parallel::ParallelMap;
+pub use self::fuse::Fuse;
+pub use self::keyed::{KeyBy, KeyedStream};
 pub use self::inspect::Inspect;
 pub use self::filter::Filter;
 pub use self::delay::Delay;
+pub use self::reorder::Reorder;
 pub use self::exchange::Exchange;
+#[cfg(feature = "arrow")]
+pub use self::exchange_batches::ExchangeBatches;
 pub use self::broadcast::Broadcast;
+pub use self::side_input::SideInput;
 pub use self::probe::Probe;
+pub use self::frontier::FrontierStream;
 pub use self::to_stream::ToStream;
 pub use self::capture::Capture;
+#[cfg(feature = "file-source")]
+pub use self::file_source::ReadFiles;
+#[cfg(feature = "parquet")]
+pub use self::parquet::ParquetSink;
+#[cfg(feature = "futures")]
+pub use self::stream_async::{ToStreamAsync, ToStreamTimed, CaptureAsync};
 pub use self::branch::{Branch, BranchWhen};
 
 pub use self::generic::Operator;
-pub use self::generic::{Notificator, FrontierNotificator};
+pub use self::generic::{Notificator, FrontierNotificator, SubsetNotificator};
 
 pub use self::reclock::Reclock;
 pub use self::count::Accumulate;
@@ -40,14 +56,30 @@ pub mod feedback;
 pub mod concat;
 pub mod partition;
 pub mod map;
+pub mod map_async;
+#[cfg(feature = "rayon")]
+pub mod parallel;
+pub mod fuse;
+pub mod keyed;
 pub mod inspect;
 pub mod filter;
 pub mod delay;
+pub mod reorder;
 pub mod exchange;
+#[cfg(feature = "arrow")]
+pub mod exchange_batches;
 pub mod broadcast;
+pub mod side_input;
 pub mod probe;
+pub mod frontier;
 pub mod to_stream;
 pub mod capture;
+#[cfg(feature = "file-source")]
+pub mod file_source;
+#[cfg(feature = "futures")]
+pub mod stream_async;
+#[cfg(feature = "parquet")]
+pub mod parquet;
 pub mod branch;
 
 pub mod aggregation;
@@ -0,0 +1,59 @@
+//! Extension methods for `Stream` fanning the work of each batch across a thread pool.
+//!
+//! With the `rayon` feature, operators may spread CPU-heavy work on a batch of records across
+//! the threads of a pool, and rejoin the results before returning, so that the capability for
+//! the batch is held throughout and the results are sent at its time. Workers share the pool
+//! set by `Worker::set_pool`, or configured by `Config::pool_threads`, which custom operators
+//! obtain from their scope's `AsWorker::pool`, and otherwise use the global pool of `rayon`.
+//!
+//! The worker thread waits while the pool works, so fanning out only helps for batches whose
+//! work outweighs the cost of distributing it.
+
+use rayon::prelude::*;
+
+use crate::Data;
+use crate::dataflow::{Stream, Scope};
+use crate::dataflow::channels::pact::Pipeline;
+use crate::dataflow::operators::generic::operator::Operator;
+
+/// Extension trait for `Stream`.
+pub trait ParallelMap<S: Scope, D: Data+Send> {
+    /// Consumes each element of the stream and yields a new element, applying `logic` to the
+    /// elements of each batch in parallel on the worker's pool.
+    ///
+    /// The order of elements within each batch is preserved.
+    ///
+    /// # Examples
+    /// ```
+    /// use timely::dataflow::operators::{ToStream, ParallelMap, Capture};
+    /// use timely::dataflow::operators::capture::Extract;
+    ///
+    /// let captured = timely::example(|scope| {
+    ///     (0..10u64).to_stream(scope)
+    ///               .map_parallel(|x| x * x)
+    ///               .capture()
+    /// });
+    ///
+    /// let squares = captured.extract().into_iter().flat_map(|(_, data)| data).collect::<Vec<_>>();
+    /// assert_eq!(squares, (0..10u64).map(|x| x * x).collect::<Vec<_>>());
+    /// ```
+    fn map_parallel<D2: Data+Send, L: Fn(D)->D2+Send+Sync+'static>(&self, logic: L) -> Stream<S, D2>;
+}
+
+impl<S: Scope, D: Data+Send> ParallelMap<S, D> for Stream<S, D> {
+    fn map_parallel<D2: Data+Send, L: Fn(D)->D2+Send+Sync+'static>(&self, logic: L) -> Stream<S, D2> {
+        let pool = self.scope().pool();
+        let mut vector = Vec::new();
+        self.unary(Pipeline, "MapParallel", move |_,_| move |input, output| {
+            input.for_each(|time, data| {
+                data.swap(&mut vector);
+                let mut map = || vector.par_drain(..).map(&logic).collect::<Vec<_>>();
+                let mut results = match pool.as_ref() {
+                    Some(pool) => pool.install(map),
+                    None => map(),
+                };
+                output.session(&time).give_vec(&mut results);
+            });
+        })
+    }
+}
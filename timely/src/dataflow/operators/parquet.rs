@@ -0,0 +1,129 @@
+//! Write streams of Arrow record batches to Parquet files aligned with epochs.
+//!
+//! Each worker writes the batches it receives to its own files in a directory. A file is
+//! written each time the input frontier advances past the times of buffered batches, and
+//! holds exactly the batches whose times the frontier has passed since the previous file.
+//! The file's metadata records the range of times it covers, as the frontiers before and
+//! after, under the keys `timely.lower` and `timely.upper`. A time `t` is covered when it is
+//! in advance of `timely.lower` and not of `timely.upper`.
+//!
+//! The ranges of the files of a worker are contiguous, and the last file has an empty upper
+//! frontier, so a consumer can tell which times are complete from the files present. Files
+//! are written under a temporary name and renamed once complete, so partial files are never
+//! visible under their final names.
+
+use std::fs::{File, create_dir_all, rename};
+use std::path::PathBuf;
+
+use ::parquet::arrow::ArrowWriter;
+use ::parquet::file::properties::WriterProperties;
+use ::parquet::format::KeyValue;
+use arrow_array::RecordBatch;
+use arrow_schema::SchemaRef;
+
+use crate::dataflow::{Scope, Stream};
+use crate::dataflow::channels::pact::Pipeline;
+use crate::dataflow::operators::generic::operator::Operator;
+
+/// Extension trait for `Stream`.
+pub trait ParquetSink {
+    /// Writes the batches of the stream to Parquet files in `directory`, rolling files at frontier boundaries.
+    ///
+    /// Every batch must have the schema `schema`. Worker `index` writes its files as
+    /// `part-{index}-{sequence}.parquet`, numbering them from zero in the order written
+    /// and padding the sequence numbers to eight digits so that names sort in that order.
+    /// The sink panics if it fails to write a file.
+    ///
+    /// # Examples
+    /// ```
+    /// use std::sync::Arc;
+    /// use arrow_array::{Int64Array, RecordBatch};
+    /// use arrow_schema::{DataType, Field, Schema};
+    /// use parquet::file::reader::{FileReader, SerializedFileReader};
+    /// use timely::dataflow::InputHandle;
+    /// use timely::dataflow::operators::{Input, ParquetSink};
+    ///
+    /// let directory = std::env::temp_dir().join(format!("timely-parquet-{}", std::process::id()));
+    /// let schema = Arc::new(Schema::new(vec![Field::new("value", DataType::Int64, false)]));
+    ///
+    /// let (path, batch_schema) = (directory.clone(), schema.clone());
+    /// timely::execute(timely::Configuration::Thread, move |worker| {
+    ///     let mut input = InputHandle::new();
+    ///     worker.dataflow::<u64,_,_>(|scope| {
+    ///         scope.input_from(&mut input).sink_parquet(path.clone(), batch_schema.clone());
+    ///     });
+    ///     for round in 0..3 {
+    ///         let values = Int64Array::from(vec![round as i64; 10]);
+    ///         input.send(RecordBatch::try_new(batch_schema.clone(), vec![Arc::new(values)]).unwrap());
+    ///         input.advance_to(round + 1);
+    ///         worker.step();
+    ///     }
+    /// }).unwrap();
+    ///
+    /// // The last file covers all remaining times, and has an empty upper frontier.
+    /// let mut files = std::fs::read_dir(&directory).unwrap().map(|entry| entry.unwrap().path()).collect::<Vec<_>>();
+    /// files.sort();
+    /// let reader = SerializedFileReader::new(std::fs::File::open(files.last().unwrap()).unwrap()).unwrap();
+    /// let metadata = reader.metadata().file_metadata().key_value_metadata().unwrap();
+    /// assert!(metadata.iter().any(|kv| kv.key == "timely.upper" && kv.value.as_deref() == Some("[]")));
+    /// let rows = files.iter().map(|file| {
+    ///     SerializedFileReader::new(std::fs::File::open(file).unwrap()).unwrap().metadata().file_metadata().num_rows()
+    /// }).sum::<i64>();
+    /// assert_eq!(rows, 30);
+    /// std::fs::remove_dir_all(&directory).unwrap();
+    /// ```
+    fn sink_parquet<P: Into<PathBuf>>(&self, directory: P, schema: SchemaRef) {
+        self.sink_parquet_with_properties(directory, schema, WriterProperties::default());
+    }
+    /// Writes the batches of the stream to Parquet files as `sink_parquet`, with writer properties `properties`.
+    ///
+    /// The properties set the compression, encodings, and row group sizes of the files. The epoch
+    /// range of each file is added to any key-value metadata of the properties.
+    fn sink_parquet_with_properties<P: Into<PathBuf>>(&self, directory: P, schema: SchemaRef, properties: WriterProperties);
+}
+
+impl<G: Scope> ParquetSink for Stream<G, RecordBatch> {
+    fn sink_parquet_with_properties<P: Into<PathBuf>>(&self, directory: P, schema: SchemaRef, properties: WriterProperties) {
+
+        let directory = directory.into();
+        create_dir_all(&directory).expect("failed to create Parquet directory");
+        let index = self.scope().index();
+
+        let mut sequence = 0;
+        let mut pending = Vec::new();
+        // The frontier at the end of the last file written, or initially the minimal frontier.
+        let mut lower = vec![G::Timestamp::default()];
+
+        self.sink(Pipeline, "ParquetSink", move |input| {
+
+            input.for_each(|time, data| {
+                pending.push((time.time().clone(), data.replace(Vec::new())));
+            });
+
+            let upper = input.frontier().frontier().to_vec();
+            if upper != lower {
+                let frontier = input.frontier().frontier();
+                let (ready, later): (Vec<_>, Vec<_>) = pending.drain(..).partition(|(time, _)| !frontier.less_equal(time));
+                pending = later;
+                // Intervals without batches are covered by the next file, and the last file is always written.
+                if !ready.is_empty() || upper.is_empty() {
+                    let name = format!("part-{}-{:08}.parquet", index, sequence);
+                    let staged = directory.join(format!(".{}.tmp", name));
+                    let file = File::create(&staged).expect("failed to create Parquet file");
+                    let mut writer = ArrowWriter::try_new(file, schema.clone(), Some(properties.clone())).expect("failed to start Parquet file");
+                    for (_time, batches) in ready {
+                        for batch in batches {
+                            writer.write(&batch).expect("failed to write Parquet batch");
+                        }
+                    }
+                    writer.append_key_value_metadata(KeyValue::new("timely.lower".to_owned(), format!("{:?}", lower)));
+                    writer.append_key_value_metadata(KeyValue::new("timely.upper".to_owned(), format!("{:?}", upper)));
+                    writer.close().expect("failed to finish Parquet file");
+                    rename(&staged, directory.join(name)).expect("failed to rename Parquet file");
+                    sequence += 1;
+                    lower = upper;
+                }
+            }
+        });
+    }
+}
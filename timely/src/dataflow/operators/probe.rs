@@ -1,15 +1,22 @@
 //! Monitor progress at a `Stream`.
 
-use std::rc::Rc;
-use std::cell::RefCell;
+use std::rc::{Rc, Weak};
+use std::cell::{Cell, RefCell};
 
 use crate::progress::Timestamp;
-use crate::progress::frontier::{AntichainRef, MutableAntichain};
+use crate::progress::timestamp::Refines;
+use crate::progress::frontier::{Antichain, AntichainRef, MutableAntichain};
+use crate::scheduling::Activator;
 use crate::dataflow::channels::pushers::Counter as PushCounter;
 use crate::dataflow::channels::pushers::buffer::Buffer as PushBuffer;
 use crate::dataflow::channels::pact::Pipeline;
 use crate::dataflow::channels::pullers::Counter as PullCounter;
 use crate::dataflow::operators::generic::builder_raw::OperatorBuilder;
+use crate::dataflow::operators::generic::operator::source;
+use crate::dataflow::operators::CapabilitySet;
+use crate::dataflow::operators::frontier::report_changes;
+#[cfg(feature = "markers")]
+use crate::dataflow::markers::{MarkerCarrier, MarkerObservation};
 
 
 use crate::Data;
@@ -77,6 +84,31 @@ pub trait Probe<G: Scope, D: Data> {
     /// }).unwrap();
     /// ```
     fn probe_with(&self, handle: &mut Handle<G::Timestamp>) -> Stream<G, D>;
+
+    /// Inserts a progress probe in a stream, and attaches it to a probe of several streams.
+    ///
+    /// The timestamps of the stream must refine those of the `MultiProbe`, for example when the
+    /// stream is within a nested scope; see `MultiProbe::attach` for other timestamps.
+    ///
+    /// # Examples
+    /// ```
+    /// use timely::dataflow::operators::{ToStream, Probe, Inspect};
+    /// use timely::dataflow::operators::probe::MultiProbe;
+    ///
+    /// timely::execute_from_args(::std::env::args(), |worker| {
+    ///
+    ///     let mut probes = MultiProbe::<usize>::new();
+    ///     worker.dataflow::<usize,_,_>(|scope| {
+    ///         let stream = (0 .. 10).to_stream(scope);
+    ///         stream.inspect(|x| println!("first: {:?}", x)).probe_with_all(&mut probes);
+    ///         stream.inspect(|x| println!("second: {:?}", x)).probe_with_all(&mut probes);
+    ///     });
+    ///
+    ///     worker.step_while(|| !probes.done());
+    /// }).unwrap();
+    /// ```
+    fn probe_with_all<T: Timestamp>(&self, probes: &mut MultiProbe<T>) -> Stream<G, D>
+    where G::Timestamp: Refines<T>;
 }
 
 impl<G: Scope, D: Data> Probe<G, D> for Stream<G, D> {
@@ -92,9 +124,27 @@ impl<G: Scope, D: Data> Probe<G, D> for Stream<G, D> {
         let mut builder = OperatorBuilder::new("Probe".to_owned(), self.scope());
         let mut input = PullCounter::new(builder.new_input(self, Pipeline));
         let (tee, stream) = builder.new_output();
+        // Markers continue downstream of the probe, as well as being observed.
+        #[cfg(feature = "markers")]
+        let markers = MarkerCarrier::new();
+        #[cfg(feature = "markers")]
+        let pending = markers.add_output(tee.clone());
         let mut output = PushBuffer::new(PushCounter::new(tee));
+        #[cfg(feature = "markers")]
+        {
+            let worker = self.scope().index();
+            let observed = handle.markers.clone();
+            markers.observe(move |marker| {
+                observed.borrow_mut().push(MarkerObservation { marker: *marker, worker, latency: marker.elapsed() });
+            });
+            input = input.with_markers(markers.clone());
+            output = output.with_markers(pending);
+        }
 
         let shared_frontier = handle.frontier.clone();
+        let shared_started = handle.started.clone();
+        let activators = handle.activators.clone();
+        let observers = handle.observers.clone();
         let mut started = false;
 
         let mut vector = Vec::new();
@@ -104,7 +154,17 @@ impl<G: Scope, D: Data> Probe<G, D> for Stream<G, D> {
 
                 // surface all frontier changes to the shared frontier.
                 let mut borrow = shared_frontier.borrow_mut();
-                borrow.update_iter(progress.frontiers[0].drain());
+                let changed = borrow.update_iter(progress.frontiers[0].drain()).next().is_some();
+                if changed || !shared_started.get() {
+                    shared_started.set(true);
+                    // activate the operators, forgetting those that have been dropped.
+                    activators.borrow_mut().retain(|activator| {
+                        activator.upgrade().map(|activator| activator.activate()).is_some()
+                    });
+                    for observer in observers.borrow_mut().iter_mut() {
+                        observer(borrow.frontier());
+                    }
+                }
 
                 if !started {
                     // discard initial capability.
@@ -123,6 +183,8 @@ impl<G: Scope, D: Data> Probe<G, D> for Stream<G, D> {
                     output.session(time).give_vec(&mut vector);
                 }
                 output.cease();
+                #[cfg(feature = "markers")]
+                markers.flush();
 
                 // extract what we know about progress from the input and output adapters.
                 input.consumed().borrow_mut().drain_into(&mut progress.consumeds[0]);
@@ -134,13 +196,33 @@ impl<G: Scope, D: Data> Probe<G, D> for Stream<G, D> {
 
         stream
     }
+    fn probe_with_all<T: Timestamp>(&self, probes: &mut MultiProbe<T>) -> Stream<G, D>
+    where G::Timestamp: Refines<T>
+    {
+        let mut handle = Handle::new();
+        let stream = self.probe_with(&mut handle);
+        probes.attach(&handle, |time| time.clone().to_outer());
+        stream
+    }
 }
 
 /// Reports information about progress at the probe.
 pub struct Handle<T:Timestamp> {
-    frontier: Rc<RefCell<MutableAntichain<T>>>
+    frontier: Rc<RefCell<MutableAntichain<T>>>,
+    // set once a probe has first reported its frontier.
+    started: Rc<Cell<bool>>,
+    // operators to activate when the frontier changes, for as long as they exist.
+    activators: Rc<RefCell<Vec<Weak<Activator>>>>,
+    // markers observed by the probe and not yet taken.
+    #[cfg(feature = "markers")]
+    markers: Rc<RefCell<Vec<MarkerObservation>>>,
+    // functions called with the frontier when it changes.
+    observers: Rc<RefCell<Vec<Observer<T>>>>,
 }
 
+/// A function called with the frontier of a probe when it changes.
+type Observer<T> = Box<dyn FnMut(AntichainRef<T>)>;
+
 impl<T: Timestamp> Handle<T> {
     /// returns true iff the frontier is strictly less than `time`.
     #[inline] pub fn less_than(&self, time: &T) -> bool { self.frontier.borrow().less_than(time) }
@@ -149,7 +231,24 @@ impl<T: Timestamp> Handle<T> {
     /// returns true iff the frontier is empty.
     #[inline] pub fn done(&self) -> bool { self.frontier.borrow().is_empty() }
     /// Allocates a new handle.
-    #[inline] pub fn new() -> Self { Handle { frontier: Rc::new(RefCell::new(MutableAntichain::new())) } }
+    #[inline] pub fn new() -> Self {
+        Handle {
+            frontier: Rc::new(RefCell::new(MutableAntichain::new())),
+            started: Rc::new(Cell::new(false)),
+            activators: Rc::new(RefCell::new(Vec::new())),
+            #[cfg(feature = "markers")]
+            markers: Rc::new(RefCell::new(Vec::new())),
+            observers: Rc::new(RefCell::new(Vec::new())),
+        }
+    }
+
+    /// Takes the observations of the latency markers that have reached the probe since last taken.
+    ///
+    /// Markers are injected with `InputHandle::mark`; see the `markers` module for an example.
+    #[cfg(feature = "markers")]
+    pub fn take_markers(&self) -> Vec<MarkerObservation> {
+        ::std::mem::take(&mut *self.markers.borrow_mut())
+    }
 
     /// Invokes a method on the frontier, returning its result.
     ///
@@ -168,16 +267,192 @@ impl<T: Timestamp> Handle<T> {
     pub fn with_frontier<R, F: FnMut(AntichainRef<T>)->R>(&self, mut function: F) -> R {
         function(self.frontier.borrow().frontier())
     }
+
+    /// Calls `observer` with the frontier of the probe each time the probe reports a change to it.
+    ///
+    /// The observer is called by the probe operator as it is scheduled, and must not inspect the
+    /// probe through its handle, whose frontier is borrowed for the call.
+    pub(crate) fn observe<F: FnMut(AntichainRef<T>)+'static>(&self, observer: F) {
+        self.observers.borrow_mut().push(Box::new(observer));
+    }
+
+    /// Produces each timestamp the frontier of the probe reaches, at that timestamp, in `scope`.
+    ///
+    /// The scope may belong to a different dataflow than the probe, on the same worker, which
+    /// allows one dataflow to react to the progress of another. The stream completes once the
+    /// frontier of the probe is empty; the initial frontier is not reported.
+    ///
+    /// # Examples
+    /// ```
+    /// use std::rc::Rc;
+    /// use std::cell::RefCell;
+    /// use timely::dataflow::InputHandle;
+    /// use timely::dataflow::operators::{Input, Probe, Inspect};
+    ///
+    /// timely::execute_from_args(::std::env::args(), |worker| {
+    ///
+    ///     let mut input = InputHandle::new();
+    ///     let reached = Rc::new(RefCell::new(Vec::new()));
+    ///     let shared = reached.clone();
+    ///     let probe = worker.dataflow::<usize,_,_>(|scope| {
+    ///         scope.input_from(&mut input)
+    ///              .inspect(|x: &String| println!("record: {}", x))
+    ///              .probe()
+    ///     });
+    ///
+    ///     // A second dataflow reports the progress of the first.
+    ///     worker.dataflow::<usize,_,_>(|scope| {
+    ///         probe.frontier_stream(scope)
+    ///              .inspect(move |time| shared.borrow_mut().push(*time));
+    ///     });
+    ///
+    ///     for round in 0..3 {
+    ///         input.send(format!("round {}", round));
+    ///         input.advance_to(round + 1);
+    ///         worker.step_while(|| probe.less_than(input.time()));
+    ///     }
+    ///     input.close();
+    ///     while worker.step() { }
+    ///
+    ///     assert_eq!(*reached.borrow(), vec![1, 2, 3]);
+    /// }).unwrap();
+    /// ```
+    pub fn frontier_stream<G: Scope<Timestamp=T>>(&self, scope: &G) -> Stream<G, T> {
+        let handle = self.clone();
+        source(scope, "ProbeFrontier", move |capability, info| {
+            let activator = Rc::new(scope.activator_for(&info.address[..]));
+            handle.activators.borrow_mut().push(Rc::downgrade(&activator));
+            let mut capabilities = CapabilitySet::new();
+            capabilities.insert(capability);
+            let mut reported = Antichain::from_elem(Default::default());
+            move |output| {
+                // the activator is held by the operator, and forgotten by the probe once dropped.
+                let _ = &activator;
+                if handle.started.get() {
+                    let frontier = handle.frontier.borrow();
+                    report_changes(frontier.frontier(), &mut reported, &mut capabilities, output);
+                }
+            }
+        })
+    }
 }
 
 impl<T: Timestamp> Clone for Handle<T> {
     fn clone(&self) -> Self {
         Handle {
-            frontier: self.frontier.clone()
+            frontier: self.frontier.clone(),
+            started: self.started.clone(),
+            activators: self.activators.clone(),
+            #[cfg(feature = "markers")]
+            markers: self.markers.clone(),
+            observers: self.observers.clone(),
         }
     }
 }
 
+/// Reports information about progress at several probes, possibly of different timestamp types.
+///
+/// Each probe is attached with a projection of its timestamps onto the timestamps `T` of the
+/// `MultiProbe`, by which its frontier is compared with times `T`. The probes have passed a time
+/// once each of their frontiers has, which simplifies driving dataflows with several outputs.
+///
+/// # Examples
+/// ```
+/// use timely::dataflow::InputHandle;
+/// use timely::dataflow::operators::{Input, Probe, Map};
+/// use timely::dataflow::operators::probe::MultiProbe;
+///
+/// timely::execute_from_args(::std::env::args(), |worker| {
+///
+///     let mut input1 = InputHandle::new();
+///     let mut input2 = InputHandle::new();
+///     let mut probes = MultiProbe::<u64>::new();
+///
+///     let probe1 = worker.dataflow::<u64,_,_>(|scope| {
+///         scope.input_from(&mut input1).map(|x: u64| x + 1).probe()
+///     });
+///     // A second dataflow measures time in a different type.
+///     let probe2 = worker.dataflow::<u32,_,_>(|scope| {
+///         scope.input_from(&mut input2).map(|x: u32| x + 1).probe()
+///     });
+///     probes.attach(&probe1, |time| *time);
+///     probes.attach(&probe2, |time| *time as u64);
+///
+///     for round in 0 .. 10 {
+///         input1.send(round);
+///         input2.send(round as u32);
+///         input1.advance_to(round + 1);
+///         input2.advance_to(round as u32 + 1);
+///         worker.step_while(|| !probes.passed(&round));
+///     }
+///     assert!(!probes.less_equal(&9));
+/// }).unwrap();
+/// ```
+pub struct MultiProbe<T: Timestamp> {
+    probes: Rc<RefCell<Vec<Box<dyn ErasedProbe<T>>>>>,
+}
+
+/// A probe handle, with its timestamps projected onto `T`.
+trait ErasedProbe<T> {
+    fn less_than(&self, time: &T) -> bool;
+    fn less_equal(&self, time: &T) -> bool;
+    fn done(&self) -> bool;
+}
+
+struct Projected<S: Timestamp, F> {
+    handle: Handle<S>,
+    project: F,
+}
+
+impl<T: Timestamp, S: Timestamp, F: Fn(&S)->T> ErasedProbe<T> for Projected<S, F> {
+    fn less_than(&self, time: &T) -> bool {
+        self.handle.with_frontier(|frontier| frontier.iter().any(|x| (self.project)(x).less_than(time)))
+    }
+    fn less_equal(&self, time: &T) -> bool {
+        self.handle.with_frontier(|frontier| frontier.iter().any(|x| (self.project)(x).less_equal(time)))
+    }
+    fn done(&self) -> bool { self.handle.done() }
+}
+
+impl<T: Timestamp> MultiProbe<T> {
+    /// Allocates a new multi-probe, with no probes attached.
+    pub fn new() -> Self {
+        MultiProbe { probes: Rc::new(RefCell::new(Vec::new())) }
+    }
+
+    /// Attaches a probe, whose timestamps `project` maps onto `T`.
+    ///
+    /// The projection should preserve the order of timestamps, so that the probe is not
+    /// reported to have passed a time before its frontier has.
+    pub fn attach<S: Timestamp, F: Fn(&S)->T+'static>(&mut self, handle: &Handle<S>, project: F) {
+        self.probes.borrow_mut().push(Box::new(Projected { handle: handle.clone(), project }));
+    }
+
+    /// The number of probes attached.
+    pub fn len(&self) -> usize { self.probes.borrow().len() }
+    /// Returns `true` if no probes are attached.
+    pub fn is_empty(&self) -> bool { self.probes.borrow().is_empty() }
+
+    /// returns true iff the frontier of any probe is strictly less than `time`.
+    pub fn less_than(&self, time: &T) -> bool { self.probes.borrow().iter().any(|probe| probe.less_than(time)) }
+    /// returns true iff the frontier of any probe is less than or equal to `time`.
+    pub fn less_equal(&self, time: &T) -> bool { self.probes.borrow().iter().any(|probe| probe.less_equal(time)) }
+    /// returns true iff all probes have passed `time`, that is, none is less than or equal to it.
+    pub fn passed(&self, time: &T) -> bool { !self.less_equal(time) }
+    /// returns true iff the frontiers of all probes are empty.
+    pub fn done(&self) -> bool { self.probes.borrow().iter().all(|probe| probe.done()) }
+}
+
+impl<T: Timestamp> Default for MultiProbe<T> {
+    fn default() -> Self { Self::new() }
+}
+
+impl<T: Timestamp> Clone for MultiProbe<T> {
+    fn clone(&self) -> Self {
+        MultiProbe { probes: self.probes.clone() }
+    }
+}
+
 #[cfg(test)]
 mod tests {
 
@@ -0,0 +1,205 @@
+//! Restores an order among the records of each timestamp.
+//!
+//! Exchanging records between workers interleaves records from different sources, destroying
+//! any order the records had. Some consumers, for example sinks writing sorted files, require
+//! records in order. The operators in this module buffer the records of each timestamp, and
+//! produce them sorted by a key once the timestamp is complete.
+//!
+//! Timestamps with many records may not fit in memory. Records beyond a threshold are sorted
+//! and written to files in the temporary directory, and merged with records in memory once the
+//! timestamp is complete.
+
+use std::collections::HashMap;
+use std::fs::{File, remove_file};
+use std::io::{BufReader, BufWriter, Read, Write};
+use std::path::PathBuf;
+
+use crate::ExchangeData;
+use crate::bytes::arc::Bytes;
+use crate::communication::message::Message;
+use crate::dataflow::{Stream, Scope};
+use crate::dataflow::channels::pact::Pipeline;
+use crate::dataflow::operators::generic::operator::Operator;
+
+/// The number of records written to spill files as each serialized batch.
+const SPILL_BATCH: usize = 1024;
+
+/// Extension trait for `Stream`.
+pub trait Reorder<G: Scope, D: ExchangeData> {
+    /// Produces the records of each timestamp sorted by `key`, once the timestamp is complete.
+    ///
+    /// # Examples
+    /// ```
+    /// use timely::dataflow::operators::{ToStream, Reorder, Inspect};
+    ///
+    /// timely::example(|scope| {
+    ///     vec![3, 1, 4, 1, 5, 9, 2, 6].to_stream(scope)
+    ///         .reorder_by(|x| *x)
+    ///         .inspect_batch(|_time, data| {
+    ///             assert!(data.windows(2).all(|pair| pair[0] <= pair[1]));
+    ///         });
+    /// });
+    /// ```
+    fn reorder_by<K: Ord, F: Fn(&D)->K+'static>(&self, key: F) -> Stream<G, D> {
+        self.reorder_by_with_spill(key, usize::MAX)
+    }
+    /// Produces the records of each timestamp sorted by `key`, spilling records to disk.
+    ///
+    /// Once more than `threshold` records of a timestamp are held in memory, they are sorted
+    /// and written to a file, and merged with the other records of the timestamp once it is
+    /// complete.
+    ///
+    /// # Examples
+    /// ```
+    /// use timely::dataflow::operators::{ToStream, Reorder, Capture};
+    /// use timely::dataflow::operators::capture::Extract;
+    ///
+    /// let captured = timely::example(|scope| {
+    ///     (0..1000).map(|x| (x * 7919) % 1000).to_stream(scope)
+    ///         .reorder_by_with_spill(|x| *x, 100)
+    ///         .capture()
+    /// });
+    ///
+    /// let data = captured.extract().into_iter().flat_map(|(_, data)| data).collect::<Vec<_>>();
+    /// assert_eq!(data, (0..1000).collect::<Vec<_>>());
+    /// ```
+    fn reorder_by_with_spill<K: Ord, F: Fn(&D)->K+'static>(&self, key: F, threshold: usize) -> Stream<G, D>;
+}
+
+impl<G: Scope, D: ExchangeData> Reorder<G, D> for Stream<G, D> {
+    fn reorder_by_with_spill<K: Ord, F: Fn(&D)->K+'static>(&self, key: F, threshold: usize) -> Stream<G, D> {
+
+        let worker = self.scope().index();
+        self.unary_frontier(Pipeline, "Reorder", move |_, info| {
+
+            let mut buffers = HashMap::new();
+            let mut spills = 0;
+            let mut vector = Vec::new();
+
+            move |input, output| {
+
+                input.for_each(|time, data| {
+                    data.swap(&mut vector);
+                    let (_, records, runs) = buffers.entry(time.time().clone()).or_insert_with(|| (time.retain(), Vec::new(), Vec::new()));
+                    records.append(&mut vector);
+                    if records.len() > threshold {
+                        let path = ::std::env::temp_dir().join(format!("timely-reorder-{}-{}-{}-{}", ::std::process::id(), worker, info.global_id, spills));
+                        spills += 1;
+                        records.sort_by_key(|x| key(x));
+                        runs.push(spill(path, ::std::mem::take(records)));
+                    }
+                });
+
+                // Produce the records of completed timestamps, merging any spilled runs.
+                let frontier = input.frontier();
+                let complete = buffers.keys().filter(|time| !frontier.less_equal(time)).cloned().collect::<Vec<_>>();
+                for time in complete {
+                    let (capability, mut records, runs) = buffers.remove(&time).unwrap();
+                    records.sort_by_key(|x| key(x));
+                    let mut session = output.session(&capability);
+                    if runs.is_empty() {
+                        session.give_vec(&mut records);
+                    }
+                    else {
+                        let mut runs = runs.into_iter().map(Run::open).collect::<Vec<_>>();
+                        let mut records = records.into_iter().peekable();
+                        loop {
+                            // Find the run with the least next record, if it precedes the records in memory.
+                            let least = runs.iter_mut()
+                                            .enumerate()
+                                            .filter_map(|(index, run)| run.peek().map(|datum| (key(datum), index)))
+                                            .min();
+                            match least {
+                                Some((least, index)) if records.peek().map(|x| least < key(x)).unwrap_or(true) => {
+                                    session.give(runs[index].next().unwrap());
+                                },
+                                _ => match records.next() {
+                                    Some(datum) => session.give(datum),
+                                    None => break,
+                                },
+                            }
+                        }
+                    }
+                }
+            }
+        })
+    }
+}
+
+/// Writes sorted records to `path`, as length-prefixed serialized batches.
+fn spill<D: ExchangeData>(path: PathBuf, records: Vec<D>) -> Spill {
+    // Own the file before creating it, so that it is removed even if writing fails.
+    let spill = Spill { path };
+    let mut writer = BufWriter::new(File::create(&spill.path).expect("Reorder: failed to create spill file"));
+    let mut records = records.into_iter();
+    loop {
+        let batch = records.by_ref().take(SPILL_BATCH).collect::<Vec<_>>();
+        if batch.is_empty() { break; }
+        let message = Message::from_typed(batch);
+        writer.write_all(&(message.length_in_bytes() as u64).to_le_bytes()).expect("Reorder: failed to write spill file");
+        message.into_bytes(&mut writer);
+    }
+    writer.flush().expect("Reorder: failed to write spill file");
+    spill
+}
+
+/// A spill file, removed once dropped, whether read or abandoned with the operator.
+struct Spill {
+    path: PathBuf,
+}
+
+impl Drop for Spill {
+    fn drop(&mut self) {
+        let _ = remove_file(&self.path);
+    }
+}
+
+/// Reads records back from a spill file, one batch at a time, and removes the file once read.
+struct Run<D> {
+    reader: BufReader<File>,
+    batch: ::std::vec::IntoIter<D>,
+    next: Option<D>,
+    _spill: Spill,
+}
+
+impl<D: ExchangeData> Run<D> {
+    fn open(spill: Spill) -> Self {
+        let reader = BufReader::new(File::open(&spill.path).expect("Reorder: failed to open spill file"));
+        let mut run = Run { reader, batch: Vec::new().into_iter(), next: None, _spill: spill };
+        run.next = run.read();
+        run
+    }
+    fn peek(&mut self) -> Option<&D> {
+        self.next.as_ref()
+    }
+    fn next(&mut self) -> Option<D> {
+        let next = self.read();
+        ::std::mem::replace(&mut self.next, next)
+    }
+    // Reads the next record, loading the next batch from the file if needed.
+    fn read(&mut self) -> Option<D> {
+        if let Some(datum) = self.batch.next() {
+            return Some(datum);
+        }
+        let mut length = [0u8; 8];
+        if self.reader.read_exact(&mut length).is_err() {
+            return None;
+        }
+        let mut bytes = vec![0u8; u64::from_le_bytes(length) as usize];
+        self.reader.read_exact(&mut bytes[..]).expect("Reorder: failed to read spill file");
+        self.batch = decode::<D>(bytes).into_typed().into_iter();
+        self.batch.next()
+    }
+}
+
+// A batch of records decoded from the bytes `spill` wrote.
+#[cfg(not(feature = "bincode"))]
+fn decode<D: ExchangeData>(bytes: Vec<u8>) -> Message<Vec<D>> {
+    // Safe, as the bytes are those of a valid message of the same type.
+    unsafe { Message::from_bytes(Bytes::from(bytes)) }
+}
+
+#[cfg(feature = "bincode")]
+fn decode<D: ExchangeData>(bytes: Vec<u8>) -> Message<Vec<D>> {
+    Message::from_bytes(Bytes::from(bytes))
+}
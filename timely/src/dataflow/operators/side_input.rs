@@ -0,0 +1,147 @@
+//! Applies a low-rate control stream, broadcast to all workers, to a stream of records.
+//!
+//! Streams of records are often processed with the help of configuration that changes over
+//! time, for example a dynamic filter or a routing table. A side input broadcasts such changes
+//! to all workers, and applies the changes at each time to records at that and later times.
+//! Records wait for all changes at times up to and including their own, so that changes and
+//! records are never misordered.
+
+use std::collections::BTreeMap;
+
+use crate::{Data, ExchangeData};
+use crate::order::{PartialOrder, TotalOrder};
+use crate::dataflow::{Stream, Scope};
+use crate::dataflow::channels::pact::{Pipeline, Broadcast};
+use crate::dataflow::operators::generic::operator::Operator;
+
+/// Extension trait for `Stream`.
+pub trait SideInput<G: Scope, D: Data> {
+    /// Processes records using state maintained from a broadcast control stream.
+    ///
+    /// Each control record is broadcast to all workers, where `update` applies it to the state.
+    /// Each record of `self` is presented to `logic` along with the state once all control
+    /// records at times up to and including the record's time have been applied, and before
+    /// any control records at later times have been applied. The records produced by `logic`
+    /// have the time of the record presented to it.
+    ///
+    /// # Examples
+    /// ```
+    /// use timely::dataflow::operators::{ToStream, SideInput, Capture};
+    /// use timely::dataflow::operators::capture::Extract;
+    /// use timely::dataflow::operators::delay::Delay;
+    ///
+    /// let captured = timely::example(|scope| {
+    ///
+    ///     // Records at times 0 through 4.
+    ///     let data = (0..5u64).to_stream(scope).delay(|x, _| *x);
+    ///     // A threshold, set to 0 at time 0 and to 3 at time 2.
+    ///     let control = vec![(0, 0), (2, 3)].to_stream(scope).delay(|x, _| x.0);
+    ///
+    ///     data.with_side_input(&control, 0, |threshold, (_time, value)| *threshold = value, |threshold, x| {
+    ///             if x >= *threshold { Some(x) } else { None }
+    ///         })
+    ///         .capture()
+    /// });
+    ///
+    /// let extracted = captured.extract();
+    /// assert_eq!(extracted, vec![(0, vec![0]), (1, vec![1]), (3, vec![3]), (4, vec![4])]);
+    /// ```
+    fn with_side_input<C, S, D2, I, U, L>(&self, control: &Stream<G, C>, initial: S, update: U, logic: L) -> Stream<G, D2>
+    where
+        G::Timestamp: TotalOrder,
+        C: ExchangeData,
+        S: 'static,
+        D2: Data,
+        I: IntoIterator<Item=D2>,
+        U: FnMut(&mut S, C)+'static,
+        L: FnMut(&S, D)->I+'static;
+}
+
+impl<G: Scope, D: Data> SideInput<G, D> for Stream<G, D> {
+    fn with_side_input<C, S, D2, I, U, L>(&self, control: &Stream<G, C>, initial: S, mut update: U, mut logic: L) -> Stream<G, D2>
+    where
+        G::Timestamp: TotalOrder,
+        C: ExchangeData,
+        S: 'static,
+        D2: Data,
+        I: IntoIterator<Item=D2>,
+        U: FnMut(&mut S, C)+'static,
+        L: FnMut(&S, D)->I+'static,
+    {
+        let mut state = initial;
+        let mut controls = BTreeMap::new();
+        let mut records = BTreeMap::new();
+
+        self.binary_frontier(control, Pipeline, Broadcast, "SideInput", move |_, _| move |input, control, output| {
+
+            control.for_each(|time, data| {
+                controls.entry(time.time().clone()).or_insert_with(Vec::new).extend(data.replace(Vec::new()));
+            });
+            input.for_each(|time, data| {
+                records.entry(time.time().clone()).or_insert_with(|| (time.retain(), Vec::new())).1.extend(data.replace(Vec::new()));
+            });
+
+            // Process times in order, until reaching a time at which control records may still arrive,
+            // or a time whose control records would be applied before records that may still arrive.
+            let frontier = control.frontier();
+            let records_frontier = input.frontier();
+            loop {
+                let next_control = controls.keys().next().cloned();
+                let next_records = records.keys().next().cloned();
+                match (next_control, next_records) {
+                    // Control records apply to records at the same time, and so come first.
+                    (Some(time), next) if next.as_ref().map(|t| time.less_equal(t)).unwrap_or(true) => {
+                        if frontier.less_equal(&time) { break; }
+                        if records_frontier.frontier().iter().any(|t| t.less_than(&time)) { break; }
+                        for datum in controls.remove(&time).unwrap() {
+                            update(&mut state, datum);
+                        }
+                    },
+                    (_, Some(time)) => {
+                        if frontier.less_equal(&time) { break; }
+                        let (cap, data) = records.remove(&time).unwrap();
+                        let mut session = output.session(&cap);
+                        for datum in data {
+                            session.give_iterator(logic(&state, datum).into_iter());
+                        }
+                    },
+                    _ => break,
+                }
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use crate::dataflow::InputHandle;
+    use crate::dataflow::operators::{Input, Probe, Capture, SideInput};
+    use crate::dataflow::operators::capture::Extract;
+
+    #[test]
+    fn controls_wait_for_earlier_records() {
+        let captured = crate::execute_directly(|worker| {
+            let mut records = InputHandle::<u64, u64>::new();
+            let mut controls = InputHandle::<u64, u64>::new();
+            let (probe, captured) = worker.dataflow(|scope| {
+                let control = scope.input_from(&mut controls);
+                let output = scope.input_from(&mut records)
+                                  .with_side_input(&control, 0, |state, value| *state = value, |state, x| Some((*state, x)));
+                (output.probe(), output.capture())
+            });
+
+            // The control at time 1 completes before the record at time 0 arrives.
+            controls.advance_to(1);
+            controls.send(10);
+            controls.advance_to(2);
+            for _ in 0 .. 10 { worker.step(); }
+
+            records.send(5);
+            records.advance_to(2);
+            while probe.less_than(&2) { worker.step(); }
+            captured
+        });
+        assert_eq!(captured.extract(), vec![(0, vec![(0, 5)])]);
+    }
+}
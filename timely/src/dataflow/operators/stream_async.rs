@@ -0,0 +1,360 @@
+//! Conversions between timely streams and asynchronous `futures::Stream`s.
+//!
+//! Asynchronous applications produce and consume records as `futures::Stream`s. A stream of
+//! records becomes a timely stream with `ToStreamAsync`, which assigns epochs to records as an
+//! `EpochPolicy` says, and a stream of `Event`s, naming their own times, becomes one with
+//! `ToStreamTimed`. In the other direction, `CaptureAsync` presents the records of a timely
+//! stream, with their times, as a `futures::Stream` that ends once the timely stream completes.
+//!
+//! The sources poll their streams from within their operators, and are woken through
+//! thread-safe activations, and so streams may be driven by other threads, for example those
+//! of an asynchronous runtime. Captured records are delivered to whichever thread awaits them.
+//!
+//! # Examples
+//! ```
+//! use futures::executor::block_on;
+//! use futures::stream::{self, StreamExt};
+//! use timely::dataflow::operators::{Map, ToStreamAsync, CaptureAsync};
+//! use timely::dataflow::operators::stream_async::EpochPolicy;
+//!
+//! let captured = timely::example(|scope| {
+//!     stream::iter(0 .. 6)
+//!         .to_stream_async(scope, EpochPolicy::Count(2))
+//!         .map(|x| x * 10)
+//!         .capture_async()
+//! });
+//!
+//! let records = block_on(captured.collect::<Vec<_>>());
+//! assert_eq!(records, vec![(0, 0), (0, 10), (1, 20), (1, 30), (2, 40), (2, 50)]);
+//! ```
+
+use std::collections::VecDeque;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll, Waker};
+use crate::time::{Duration, Instant};
+
+use futures::Stream as AsyncStream;
+
+use crate::Data;
+use crate::dataflow::{Stream, Scope};
+use crate::dataflow::channels::pact::Pipeline;
+use crate::dataflow::operators::generic::operator::{source, Operator};
+use crate::dataflow::operators::map_async::ActivateWaker;
+use crate::progress::Timestamp;
+use crate::scheduling::ActivationToken;
+
+/// The number of items a source takes from its stream in one activation, before yielding to other operators.
+const ITEMS_PER_ACTIVATION: usize = 1024;
+
+/// How `ToStreamAsync` assigns epochs to the records of a stream.
+///
+/// Epochs are numbered from zero, and each completes once the source advances past it.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum EpochPolicy {
+    /// All records are in epoch zero, which completes once the stream ends.
+    Single,
+    /// The records ready together form an epoch, which completes once the stream awaits more.
+    Ready,
+    /// Each run of this many records forms an epoch.
+    Count(usize),
+    /// The records arriving in each period of this length form an epoch, counted from when the source starts.
+    ///
+    /// Epochs without records complete as their periods elapse, and so their numbers may skip.
+    Interval(Duration),
+}
+
+/// An item of a stream that names the times of its records, as consumed by `ToStreamTimed`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Event<T, D> {
+    /// A record, at a time not less than that of the most recent progress.
+    Message(T, D),
+    /// The stream presents no further records at times less than this one, or at any time if `None`.
+    Progress(Option<T>),
+}
+
+/// Converts an asynchronous stream of records into a timely stream, by epochs.
+pub trait ToStreamAsync<D: Data> {
+    /// Introduces the records of the stream into `scope`, with epochs assigned as `policy` says.
+    ///
+    /// The records are introduced at the worker that constructs the source, and each worker
+    /// constructing the dataflow should supply its own stream. The timely stream completes once
+    /// the asynchronous stream ends.
+    ///
+    /// # Examples
+    /// ```
+    /// use futures::stream;
+    /// use timely::dataflow::operators::{ToStreamAsync, Capture};
+    /// use timely::dataflow::operators::capture::Extract;
+    /// use timely::dataflow::operators::stream_async::EpochPolicy;
+    ///
+    /// let captured = timely::example(|scope| {
+    ///     stream::iter(0 .. 10)
+    ///         .to_stream_async(scope, EpochPolicy::Single)
+    ///         .capture()
+    /// });
+    ///
+    /// assert_eq!(captured.extract(), vec![(0, (0 .. 10).collect::<Vec<_>>())]);
+    /// ```
+    fn to_stream_async<G: Scope<Timestamp=u64>>(self, scope: &mut G, policy: EpochPolicy) -> Stream<G, D>;
+}
+
+impl<D: Data, S: AsyncStream<Item=D>+Unpin+'static> ToStreamAsync<D> for S {
+    fn to_stream_async<G: Scope<Timestamp=u64>>(self, scope: &mut G, policy: EpochPolicy) -> Stream<G, D> {
+
+        if let EpochPolicy::Count(count) = policy {
+            assert!(count > 0, "EpochPolicy::Count requires a positive count");
+        }
+        if let EpochPolicy::Interval(period) = policy {
+            assert!(period > Duration::from_secs(0), "EpochPolicy::Interval requires a positive period");
+        }
+
+        source(scope, "ToStreamAsync", |capability, info| {
+
+            let activator = scope.activator_for(&info.address[..]);
+            let waker = Waker::from(Arc::new(ActivateWaker { activator: scope.sync_activator_for(&info.address[..]) }));
+            let mut capability = Some(capability);
+            let mut stream = Some(self);
+            let started = Instant::now();
+            // The records given in the current epoch.
+            let mut count = 0;
+            // The activation at the end of an epoch, armed once for each epoch.
+            let mut wakeup: Option<(u64, ActivationToken)> = None;
+
+            move |output| {
+
+                if let (Some(capability), EpochPolicy::Interval(period)) = (capability.as_mut(), policy) {
+                    let epoch = (started.elapsed().as_nanos() / period.as_nanos()) as u64;
+                    if epoch > *capability.time() {
+                        capability.downgrade(&epoch);
+                    }
+                    // Awaken as the epoch ends, even without further records.
+                    if !wakeup.as_ref().is_some_and(|(armed, token)| *armed == epoch && token.is_pending()) {
+                        let ends = Duration::from_nanos(((epoch as u128 + 1) * period.as_nanos()) as u64);
+                        wakeup = Some((epoch, activator.schedule_after(ends.saturating_sub(started.elapsed()))));
+                    }
+                }
+
+                let mut context = Context::from_waker(&waker);
+                let mut taken = 0;
+                while let (Some(inner), Some(cap)) = (stream.as_mut(), capability.as_mut()) {
+                    if taken == ITEMS_PER_ACTIVATION {
+                        activator.activate();
+                        break;
+                    }
+                    match Pin::new(inner).poll_next(&mut context) {
+                        Poll::Ready(Some(datum)) => {
+                            output.session(cap).give(datum);
+                            taken += 1;
+                            count += 1;
+                            if policy == EpochPolicy::Count(count) {
+                                let next = *cap.time() + 1;
+                                cap.downgrade(&next);
+                                count = 0;
+                            }
+                        },
+                        Poll::Ready(None) => {
+                            stream = None;
+                            capability = None;
+                            wakeup = None;
+                        },
+                        Poll::Pending => {
+                            if policy == EpochPolicy::Ready && count > 0 {
+                                let next = *cap.time() + 1;
+                                cap.downgrade(&next);
+                                count = 0;
+                            }
+                            break;
+                        },
+                    }
+                }
+            }
+        })
+    }
+}
+
+/// Converts an asynchronous stream of timed records into a timely stream.
+pub trait ToStreamTimed<T: Timestamp, D: Data> {
+    /// Introduces the records of the stream into `scope`, at the times they name.
+    ///
+    /// The source holds a capability for the time of the most recent `Event::Progress`, which
+    /// is initially the minimal time, and the timely stream completes once the asynchronous
+    /// stream ends or presents the progress `None`. A record at a time less than that of the
+    /// most recent progress causes a panic.
+    ///
+    /// # Examples
+    /// ```
+    /// use futures::stream;
+    /// use timely::dataflow::operators::{ToStreamTimed, Capture};
+    /// use timely::dataflow::operators::capture::Extract;
+    /// use timely::dataflow::operators::stream_async::Event;
+    ///
+    /// let captured = timely::example(|scope| {
+    ///     let events = vec![
+    ///         Event::Message(3, 'a'),
+    ///         Event::Progress(Some(2)),
+    ///         Event::Message(2, 'b'),
+    ///         Event::Progress(None),
+    ///     ];
+    ///     stream::iter(events)
+    ///         .to_stream_timed(scope)
+    ///         .capture()
+    /// });
+    ///
+    /// assert_eq!(captured.extract(), vec![(2, vec!['b']), (3, vec!['a'])]);
+    /// ```
+    fn to_stream_timed<G: Scope<Timestamp=T>>(self, scope: &mut G) -> Stream<G, D>;
+}
+
+impl<T: Timestamp, D: Data, S: AsyncStream<Item=Event<T, D>>+Unpin+'static> ToStreamTimed<T, D> for S {
+    fn to_stream_timed<G: Scope<Timestamp=T>>(self, scope: &mut G) -> Stream<G, D> {
+
+        source(scope, "ToStreamTimed", |capability, info| {
+
+            let activator = scope.activator_for(&info.address[..]);
+            let waker = Waker::from(Arc::new(ActivateWaker { activator: scope.sync_activator_for(&info.address[..]) }));
+            let mut capability = Some(capability);
+            let mut stream = Some(self);
+
+            move |output| {
+                let mut context = Context::from_waker(&waker);
+                let mut taken = 0;
+                while let (Some(inner), Some(cap)) = (stream.as_mut(), capability.as_mut()) {
+                    if taken == ITEMS_PER_ACTIVATION {
+                        activator.activate();
+                        break;
+                    }
+                    match Pin::new(inner).poll_next(&mut context) {
+                        Poll::Ready(Some(Event::Message(time, datum))) => {
+                            output.session(&cap.delayed(&time)).give(datum);
+                            taken += 1;
+                        },
+                        Poll::Ready(Some(Event::Progress(Some(time)))) => {
+                            cap.downgrade(&time);
+                        },
+                        Poll::Ready(Some(Event::Progress(None))) | Poll::Ready(None) => {
+                            stream = None;
+                            capability = None;
+                        },
+                        Poll::Pending => break,
+                    }
+                }
+            }
+        })
+    }
+}
+
+/// Presents the records of a timely stream as an asynchronous stream.
+pub trait CaptureAsync<T: Timestamp, D: Data> {
+    /// Captures the records of the stream, with their times, as an asynchronous stream.
+    ///
+    /// The records are those of the worker that constructs the capture, in the order in which
+    /// they arrive, and the asynchronous stream ends once the timely stream completes, or its
+    /// dataflow is dropped. Records are buffered until they are taken, without limit.
+    ///
+    /// # Examples
+    /// ```
+    /// use futures::executor::block_on;
+    /// use futures::stream::StreamExt;
+    /// use timely::dataflow::operators::{ToStream, CaptureAsync};
+    ///
+    /// let captured = timely::example(|scope| {
+    ///     (0 .. 3).to_stream(scope).capture_async()
+    /// });
+    ///
+    /// assert_eq!(captured.frontier(), Vec::<u64>::new());
+    /// assert_eq!(block_on(captured.collect::<Vec<_>>()), vec![(0, 0), (0, 1), (0, 2)]);
+    /// ```
+    fn capture_async(&self) -> AsyncCapture<T, D>;
+}
+
+impl<G: Scope, D: Data> CaptureAsync<G::Timestamp, D> for Stream<G, D> where G::Timestamp: Send, D: Send {
+    fn capture_async(&self) -> AsyncCapture<G::Timestamp, D> {
+
+        let shared = Arc::new(Mutex::new(Captured {
+            records: VecDeque::new(),
+            frontier: vec![Default::default()],
+            complete: false,
+            waker: None,
+        }));
+
+        // Completes the capture as the operator is dropped, with its dataflow or once the frontier empties.
+        let closer = Closer { shared: shared.clone() };
+        let mut vector = Vec::new();
+        self.sink(Pipeline, "CaptureAsync", move |input| {
+            let mut captured = closer.shared.lock().expect("capture poisoned");
+            input.for_each(|time, data| {
+                data.swap(&mut vector);
+                captured.records.extend(vector.drain(..).map(|datum| (time.time().clone(), datum)));
+            });
+            let frontier = input.frontier().frontier();
+            if captured.frontier[..] != frontier[..] {
+                captured.frontier = frontier.to_vec();
+            }
+            if !captured.records.is_empty() || captured.frontier.is_empty() {
+                if let Some(waker) = captured.waker.take() {
+                    waker.wake();
+                }
+            }
+        });
+
+        AsyncCapture { shared }
+    }
+}
+
+/// The records captured from a timely stream, shared with the operator capturing them.
+struct Captured<T, D> {
+    records: VecDeque<(T, D)>,
+    frontier: Vec<T>,
+    complete: bool,
+    waker: Option<Waker>,
+}
+
+/// Marks a capture complete once dropped.
+struct Closer<T, D> {
+    shared: Arc<Mutex<Captured<T, D>>>,
+}
+
+impl<T, D> Drop for Closer<T, D> {
+    fn drop(&mut self) {
+        if let Ok(mut captured) = self.shared.lock() {
+            captured.complete = true;
+            if let Some(waker) = captured.waker.take() {
+                waker.wake();
+            }
+        }
+    }
+}
+
+/// The records of a timely stream, with their times, as an asynchronous stream.
+///
+/// The stream is constructed by `CaptureAsync::capture_async`, and may be moved to other threads.
+pub struct AsyncCapture<T, D> {
+    shared: Arc<Mutex<Captured<T, D>>>,
+}
+
+impl<T: Clone, D> AsyncCapture<T, D> {
+    /// The frontier of the captured stream, as last observed by the capturing operator.
+    ///
+    /// The records at times the frontier has passed have all been captured, though perhaps not yet taken.
+    pub fn frontier(&self) -> Vec<T> {
+        self.shared.lock().expect("capture poisoned").frontier.clone()
+    }
+}
+
+impl<T, D> AsyncStream for AsyncCapture<T, D> {
+    type Item = (T, D);
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let mut captured = self.shared.lock().expect("capture poisoned");
+        if let Some(record) = captured.records.pop_front() {
+            Poll::Ready(Some(record))
+        }
+        else if captured.complete || captured.frontier.is_empty() {
+            Poll::Ready(None)
+        }
+        else {
+            captured.waker = Some(cx.waker().clone());
+            Poll::Pending
+        }
+    }
+}
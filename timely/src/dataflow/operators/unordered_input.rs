@@ -3,6 +3,7 @@
 use std::rc::Rc;
 use std::cell::RefCell;
 use std::default::Default;
+use std::collections::BTreeMap;
 
 use crate::scheduling::{Schedule, Activations, ActivateOnDrop};
 
@@ -17,6 +18,8 @@ use crate::dataflow::channels::pushers::buffer::{Buffer as PushBuffer, Autoflush
 
 use crate::dataflow::operators::Capability;
 use crate::dataflow::operators::capability::mint as mint_capability;
+#[cfg(feature = "capability-checks")]
+use crate::dataflow::operators::capability::Origin;
 
 use crate::dataflow::{Stream, Scope};
 
@@ -83,7 +86,7 @@ impl<G: Scope> UnorderedInput<G> for G {
     fn new_unordered_input<D:Data>(&mut self) -> ((UnorderedHandle<G::Timestamp, D>, ActivateCapability<G::Timestamp>), Stream<G, D>) {
 
         let (output, registrar) = Tee::<G::Timestamp, D>::new();
-        let internal = Rc::new(RefCell::new(ChangeBatch::new()));
+        let internal = Rc::new(RefCell::new(ChangeBatch::<G::Timestamp>::new()));
         // let produced = Rc::new(RefCell::new(ChangeBatch::new()));
         let cap = mint_capability(Default::default(), internal.clone());
         let counter = PushCounter::new(output);
@@ -94,6 +97,8 @@ impl<G: Scope> UnorderedInput<G> for G {
         let mut address = self.addr();
         address.push(index);
 
+        #[cfg(feature = "capability-checks")]
+        let cap = cap.with_origin(Rc::new(Origin { name: "UnorderedInput".to_owned(), address: address.clone() }), 0);
         let cap = ActivateCapability::new(cap, &address[..], self.activations().clone());
 
         let helper = UnorderedHandle::new(counter);
@@ -205,3 +210,123 @@ impl<T: Timestamp> Drop for ActivateCapability<T> {
         self.activations.borrow_mut().activate(&self.address[..]);
     }
 }
+
+/// A set of capabilities for an unordered input, keyed by timestamp.
+///
+/// Inputs fed from external systems often receive records out of order, and must hold
+/// capabilities for each timestamp that may still receive records. This set mints
+/// capabilities for timestamps as they are needed, and releases them in bulk as the external
+/// system indicates that timestamps are complete. All capabilities are released when the set
+/// is dropped.
+///
+/// # Examples
+///
+/// ```
+/// use timely::dataflow::operators::{UnorderedInput, Capture};
+/// use timely::dataflow::operators::capture::Extract;
+/// use timely::dataflow::operators::unordered_input::ActivateCapabilitySet;
+///
+/// let (send, recv) = ::std::sync::mpsc::channel();
+/// let send = ::std::sync::Arc::new(::std::sync::Mutex::new(send));
+///
+/// timely::execute(timely::Configuration::Thread, move |worker| {
+///
+///     let send = send.lock().unwrap().clone();
+///     let (mut input, cap) = worker.dataflow::<usize,_,_>(|scope| {
+///         let (input, stream) = scope.new_unordered_input();
+///         stream.capture_into(send);
+///         input
+///     });
+///
+///     let mut capabilities = ActivateCapabilitySet::from_elem(cap);
+///
+///     // Records arrive out of order, each with the time at which it occurred.
+///     for (time, data) in vec![(3, 'c'), (1, 'a'), (4, 'd'), (2, 'b')] {
+///         input.session(capabilities.retain(&time)).give(data);
+///     }
+///
+///     // The external system indicates that times before 3 are complete.
+///     capabilities.downgrade_all_before(&3);
+///     assert_eq!(capabilities.times(), vec![3, 4]);
+///     worker.step();
+///
+/// }).unwrap();
+///
+/// assert_eq!(recv.extract(), vec![(1, vec!['a']), (2, vec!['b']), (3, vec!['c']), (4, vec!['d'])]);
+/// ```
+pub struct ActivateCapabilitySet<T: Timestamp> {
+    capabilities: BTreeMap<T, ActivateCapability<T>>,
+}
+
+impl<T: Timestamp> ActivateCapabilitySet<T> {
+    /// Creates a set holding only `capability`.
+    pub fn from_elem(capability: ActivateCapability<T>) -> Self {
+        let mut capabilities = BTreeMap::new();
+        capabilities.insert(capability.time().clone(), capability);
+        ActivateCapabilitySet { capabilities }
+    }
+
+    /// Returns a capability for `time`, minting one from a held capability if needed.
+    ///
+    /// The minted capability is retained by the set until it is released.
+    ///
+    /// # Panics
+    ///
+    /// Panics if no held capability is less or equal to `time`.
+    pub fn retain(&mut self, time: &T) -> ActivateCapability<T> {
+        if !self.capabilities.contains_key(time) {
+            let capability =
+            self.capabilities
+                .values()
+                .find(|capability| capability.time().less_equal(time))
+                .map(|capability| capability.delayed(time))
+                .expect("No capability less or equal to time");
+            self.capabilities.insert(time.clone(), capability);
+        }
+        self.capabilities[time].clone()
+    }
+
+    /// Releases capabilities for all times not strictly less than `time`.
+    ///
+    /// This indicates that no further records will be produced at or beyond `time`.
+    pub fn retain_until(&mut self, time: &T) {
+        self.capabilities.retain(|t, _| t.less_than(time));
+    }
+
+    /// Releases capabilities for times strictly less than `time`, retaining one for `time`.
+    ///
+    /// This indicates that no further records will be produced at times before `time`. The
+    /// set retains a capability for `time` if it released any capabilities and does not hold
+    /// one already, so that records may still be produced at `time`.
+    pub fn downgrade_all_before(&mut self, time: &T) {
+        let before =
+        self.capabilities
+            .keys()
+            .filter(|t| t.less_than(time))
+            .cloned()
+            .collect::<Vec<_>>();
+
+        if !before.is_empty() && !self.capabilities.contains_key(time) {
+            let capability = self.capabilities[&before[0]].delayed(time);
+            self.capabilities.insert(time.clone(), capability);
+        }
+        for t in before {
+            self.capabilities.remove(&t);
+        }
+    }
+
+    /// Releases the capability for `time`, if one is held.
+    pub fn release(&mut self, time: &T) {
+        self.capabilities.remove(time);
+    }
+
+    /// The times for which capabilities are held, in order.
+    pub fn times(&self) -> Vec<T> {
+        self.capabilities.keys().cloned().collect()
+    }
+
+    /// Returns `true` if the set holds no capabilities.
+    pub fn is_empty(&self) -> bool {
+        self.capabilities.is_empty()
+    }
+}
@@ -1,7 +1,7 @@
 //! A child dataflow scope, used to build nested dataflow scopes.
 
 use std::rc::Rc;
-use std::cell::RefCell;
+use std::cell::{Cell, RefCell};
 
 use crate::communication::{Data, Push, Pull};
 use crate::communication::allocator::thread::{ThreadPusher, ThreadPuller};
@@ -66,6 +66,40 @@ where
     fn log_register(&self) -> ::std::cell::RefMut<crate::logging_core::Registry<crate::logging::WorkerIdentifier>> {
         self.parent.log_register()
     }
+    fn output_capacity(&self) -> Option<usize> {
+        self.parent.output_capacity()
+    }
+    fn exchange_capacity(&self) -> Option<usize> {
+        self.parent.exchange_capacity()
+    }
+    #[cfg(feature = "rayon")]
+    fn pool(&self) -> Option<std::sync::Arc<rayon::ThreadPool>> {
+        self.parent.pool()
+    }
+    fn register_operator(&self, operator: crate::dataflow::graph::Operator) {
+        self.parent.register_operator(operator)
+    }
+    fn register_channel(&self, channel: crate::dataflow::graph::Channel) {
+        self.parent.register_channel(channel)
+    }
+    fn buffered_counter(&self, dataflow: usize) -> Option<Rc<Cell<i64>>> {
+        self.parent.buffered_counter(dataflow)
+    }
+    fn memory_account(&self, address: &[usize]) -> Option<crate::dataflow::memory::MemoryAccount> {
+        self.parent.memory_account(address)
+    }
+    fn channel_counter(&self, identifier: usize) -> Option<crate::dataflow::channels::counts::ChannelCounter> {
+        self.parent.channel_counter(identifier)
+    }
+    fn remote_activator_for(&self, worker: usize, path: &[usize]) -> Option<crate::scheduling::RemoteActivator> {
+        self.parent.remote_activator_for(worker, path)
+    }
+    fn shutdown_signal(&self) -> Option<Rc<Cell<bool>>> {
+        self.parent.shutdown_signal()
+    }
+    fn progress_policy(&self) -> crate::progress::broadcast::ProgressPolicy {
+        self.parent.progress_policy()
+    }
 }
 
 impl<'a, G, T> Scheduler for Child<'a, G, T>
@@ -98,6 +132,11 @@ where
     }
 
     fn add_operator_with_indices(&mut self, operator: Box<dyn Operate<Self::Timestamp>>, local: usize, global: usize) {
+        let mut addr = self.addr();
+        addr.push(local);
+        let mut names = self.subgraph.borrow().names.clone();
+        names.push(operator.name().to_owned());
+        self.parent.register_operator(crate::dataflow::graph::Operator { id: global, addr, names });
         self.subgraph.borrow_mut().add_child(operator, local, global);
     }
 
@@ -114,7 +153,9 @@ where
         let index = self.subgraph.borrow_mut().allocate_child_id();
         let path = self.subgraph.borrow().path.clone();
 
-        let subscope = RefCell::new(SubgraphBuilder::new_from(index, path, self.logging().clone(), name));
+        let mut subscope = SubgraphBuilder::new_from(index, path, self.logging().clone(), name);
+        subscope.names = self.subgraph.borrow().names.iter().cloned().chain(subscope.names).collect();
+        let subscope = RefCell::new(subscope);
         let result = {
             let mut builder = Child {
                 subgraph: &subscope,
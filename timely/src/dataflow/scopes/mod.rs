@@ -155,4 +155,33 @@ pub trait Scope: ScopeParent {
         self.scoped::<<Self as ScopeParent>::Timestamp,R,F>("Region", func)
     }
 
+    /// Creates a dataflow region with the same timestamp and a supplied name.
+    ///
+    /// This method is a specialization of `region` which names the region, so that its name
+    /// appears in the `names` of the `OperatesEvent` of each operator it contains, and in the
+    /// result of `Worker::operator_names`.
+    ///
+    /// # Examples
+    /// ```
+    /// use timely::dataflow::Scope;
+    /// use timely::dataflow::operators::{Input, Enter, Leave};
+    ///
+    /// timely::execute_from_args(std::env::args(), |worker| {
+    ///     // must specify types as nothing else drives inference.
+    ///     let input = worker.dataflow::<u64,_,_>(|child1| {
+    ///         let (input, stream) = child1.new_input::<String>();
+    ///         let output = child1.region_named("Parse", |child2| {
+    ///             stream.enter(child2).leave()
+    ///         });
+    ///         input
+    ///     });
+    /// });
+    /// ```
+    fn region_named<R, F>(&mut self, name: &str, func: F) -> R
+    where
+        F: FnOnce(&mut Child<Self, <Self as ScopeParent>::Timestamp>) -> R,
+    {
+        self.scoped::<<Self as ScopeParent>::Timestamp,R,F>(name, func)
+    }
+
 }
@@ -0,0 +1,141 @@
+//! Serializable snapshots of the frontiers of probes, for external observers.
+//!
+//! A `FrontierObserver` holds named probes, and its `snapshot` captures their current frontiers
+//! as a `FrontierSnapshot`, which may be serialized and published to a coordination system, for
+//! example to tell downstream consumers which epochs are complete. Probes observe the frontier
+//! of the stream they are attached to, so the frontier of any operator's output is observed by
+//! probing that output, with `Probe::probe_with`.
+//!
+//! The frontier of a probe reflects the progress of all workers, though workers may learn of it
+//! at different moments. Snapshots of several workers may be combined with `merge`, whose result
+//! is the lower envelope of their frontiers, and so is complete only where all of them are.
+//!
+//! # Examples
+//! ```
+//! use timely::dataflow::InputHandle;
+//! use timely::dataflow::operators::{Input, Exchange, Probe};
+//! use timely::dataflow::snapshot::{FrontierObserver, FrontierSnapshot};
+//!
+//! timely::execute_from_args(::std::env::args(), |worker| {
+//!     let mut input = InputHandle::new();
+//!     let probe = worker.dataflow::<u64,_,_>(|scope| {
+//!         scope.input_from(&mut input)
+//!              .exchange(|x: &u64| *x)
+//!              .probe()
+//!     });
+//!
+//!     let mut observer = FrontierObserver::new();
+//!     observer.add_probe("output", &probe);
+//!
+//!     input.send(0);
+//!     input.advance_to(3);
+//!     worker.step_while(|| probe.less_than(input.time()));
+//!
+//!     let snapshot = observer.snapshot(worker);
+//!     assert_eq!(snapshot.frontier("output"), Some(&[3][..]));
+//!     assert_eq!(snapshot.is_complete("output", &2), Some(true));
+//!     assert_eq!(snapshot.is_complete("output", &3), Some(false));
+//!
+//!     // Snapshots round-trip through serde.
+//!     let json = serde_json::to_string(&snapshot).unwrap();
+//!     let parsed: FrontierSnapshot<u64> = serde_json::from_str(&json).unwrap();
+//!     assert_eq!(parsed, snapshot);
+//! }).unwrap();
+//! ```
+
+use std::collections::BTreeMap;
+use crate::time::Duration;
+
+use crate::communication::Allocate;
+use crate::dataflow::ProbeHandle;
+use crate::progress::{Antichain, Timestamp};
+use crate::worker::Worker;
+
+/// The frontiers of named probes, as captured by `FrontierObserver::snapshot`.
+#[derive(Serialize, Deserialize, Debug, Clone, Eq, PartialEq)]
+pub struct FrontierSnapshot<T> {
+    /// The indices of the workers whose observations the snapshot reflects.
+    pub workers: Vec<usize>,
+    /// The time since the start of the worker at which the snapshot was captured, or the least of these for merged snapshots.
+    pub elapsed: Duration,
+    /// The frontier of each probe, by name.
+    pub frontiers: BTreeMap<String, Vec<T>>,
+}
+
+impl<T: Timestamp> FrontierSnapshot<T> {
+    /// The frontier of the probe named `name`, if the snapshot includes it.
+    pub fn frontier(&self, name: &str) -> Option<&[T]> {
+        self.frontiers.get(name).map(|frontier| &frontier[..])
+    }
+
+    /// Indicates whether `time` is complete at the probe named `name`, if the snapshot includes it.
+    ///
+    /// A time is complete once no element of the frontier is less or equal to it, after which
+    /// no more records at the time will be seen by the probe.
+    pub fn is_complete(&self, name: &str, time: &T) -> Option<bool> {
+        self.frontier(name).map(|frontier| !frontier.iter().any(|element| element.less_equal(time)))
+    }
+
+    /// Combines the snapshot with `other`, retaining the lower envelope of the frontiers of each probe.
+    ///
+    /// Probes in only one of the snapshots retain their frontiers.
+    pub fn merge(&mut self, other: &FrontierSnapshot<T>) {
+        for worker in other.workers.iter() {
+            if !self.workers.contains(worker) {
+                self.workers.push(*worker);
+            }
+        }
+        self.workers.sort();
+        self.elapsed = std::cmp::min(self.elapsed, other.elapsed);
+        for (name, frontier) in other.frontiers.iter() {
+            let merged = self.frontiers.entry(name.clone()).or_insert_with(|| frontier.clone());
+            let mut antichain = Antichain::new();
+            for element in merged.iter().chain(frontier.iter()) {
+                antichain.insert(element.clone());
+            }
+            *merged = antichain.elements().to_vec();
+        }
+    }
+}
+
+/// Captures the frontiers of named probes.
+pub struct FrontierObserver<T: Timestamp> {
+    probes: Vec<(String, ProbeHandle<T>)>,
+}
+
+impl<T: Timestamp> FrontierObserver<T> {
+    /// An observer of no probes.
+    pub fn new() -> Self {
+        FrontierObserver { probes: Vec::new() }
+    }
+
+    /// Adds `probe` to the observed probes, under `name`.
+    ///
+    /// A probe added under the name of an observed probe replaces it.
+    pub fn add_probe(&mut self, name: &str, probe: &ProbeHandle<T>) {
+        self.probes.retain(|(existing, _)| existing != name);
+        self.probes.push((name.to_owned(), probe.clone()));
+    }
+
+    /// Removes the probe named `name` from the observed probes.
+    pub fn remove_probe(&mut self, name: &str) {
+        self.probes.retain(|(existing, _)| existing != name);
+    }
+
+    /// Captures the current frontiers of the observed probes, as seen by `worker`.
+    pub fn snapshot<A: Allocate>(&self, worker: &Worker<A>) -> FrontierSnapshot<T> {
+        FrontierSnapshot {
+            workers: vec![worker.index()],
+            elapsed: worker.timer().elapsed(),
+            frontiers: self.probes.iter().map(|(name, probe)| {
+                (name.clone(), probe.with_frontier(|frontier| frontier.to_vec()))
+            }).collect(),
+        }
+    }
+}
+
+impl<T: Timestamp> Default for FrontierObserver<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,266 @@
+//! Detection of dataflows whose frontiers have stopped advancing.
+//!
+//! A dataflow hangs when some operator holds a capability it never releases, or when records
+//! sit undelivered in a channel, for example because the operator they await is never scheduled.
+//! A `Watchdog` observes the frontiers of the operators on a worker, and once they have not
+//! advanced for a configured period while capabilities remain outstanding, produces a
+//! `StallReport` listing each operator still holding capabilities, the timestamps it holds, and
+//! the channels to each of its inputs with undelivered records.
+//!
+//! A watchdog can be installed with `Worker::set_watchdog`, which logs reports as
+//! `TimelyEvent::Stall`, or checked explicitly with `Watchdog::check` by applications that step
+//! their workers themselves. Dataflows awaiting input hold capabilities in their inputs, and are
+//! reported as stalled if the input does not advance within the period.
+//!
+//! An installed watchdog observes frontiers as the worker steps, and a worker parked with nothing
+//! to do is not woken for it, unless the watchdog is built `with_wakeups`. Stalls of parked
+//! workers, for example those awaiting other processes, are otherwise reported once they next step.
+//!
+//! # Examples
+//! ```
+//! use std::time::Duration;
+//! use timely::dataflow::InputHandle;
+//! use timely::dataflow::operators::{Input, Probe};
+//! use timely::dataflow::watchdog::Watchdog;
+//!
+//! timely::execute_from_args(::std::env::args(), |worker| {
+//!     let mut input = InputHandle::<u64, u64>::new();
+//!     worker.dataflow(|scope| {
+//!         scope.input_from(&mut input).probe();
+//!     });
+//!
+//!     // The input holds its capability at time zero, and is never advanced.
+//!     let mut watchdog = Watchdog::new(Duration::from_millis(20));
+//!     let report = loop {
+//!         worker.step();
+//!         if let Some(report) = watchdog.check(worker) {
+//!             break report;
+//!         }
+//!     };
+//!
+//!     let held = report.operators.iter().find(|op| op.name == "Input").unwrap();
+//!     assert_eq!(held.capabilities[0].time, "0");
+//!     println!("{}", report);
+//! }).unwrap();
+//! ```
+
+use std::fmt::{self, Display};
+use crate::time::{Duration, Instant};
+
+use crate::communication::Allocate;
+use crate::dataflow::graph::OperatorStatus;
+use crate::worker::Worker;
+
+// The input frontiers of operators, by address.
+type Frontiers = Vec<(Vec<usize>, Vec<Vec<String>>)>;
+
+/// Observes the frontiers of a worker's operators, and reports when they stop advancing.
+#[derive(Debug, Clone)]
+pub struct Watchdog {
+    period: Duration,
+    // The frontiers of each operator last observed, and when they were first observed.
+    observed: Option<(Frontiers, Instant)>,
+    // The time before which frontiers are not observed again.
+    next_check: Option<Instant>,
+    // Whether the current stall has been reported.
+    reported: bool,
+    // Whether parked workers wake to observe frontiers.
+    wakeups: bool,
+}
+
+impl Watchdog {
+    /// A watchdog reporting frontiers that have not advanced for `period`.
+    pub fn new(period: Duration) -> Self {
+        Watchdog { period, observed: None, next_check: None, reported: false, wakeups: false }
+    }
+
+    /// Has workers that install the watchdog wake from parking to observe frontiers.
+    ///
+    /// Parked workers then wake a few times each period, so that stalls are reported promptly
+    /// even of workers with nothing to do, at the cost of those wake-ups.
+    pub fn with_wakeups(mut self) -> Self {
+        self.wakeups = true;
+        self
+    }
+
+    /// The period for which frontiers must not advance to be reported.
+    pub fn period(&self) -> Duration { self.period }
+
+    /// The time until the watchdog next observes the frontiers, if parked workers should wake for it.
+    ///
+    /// This is `None` unless the watchdog was built `with_wakeups`, and until it first observes
+    /// the frontiers.
+    pub fn next_check(&self) -> Option<Duration> {
+        self.next_check.filter(|_| self.wakeups).map(|next| next.saturating_duration_since(Instant::now()))
+    }
+
+    /// Observes the frontiers of the operators of `worker`, and reports a newly detected stall.
+    ///
+    /// Frontiers are observed at most four times each period, and so the method may be called
+    /// after every step. A stall is reported once, when the frontiers have not advanced for the
+    /// period while some operator holds capabilities or has records pending at its inputs, and
+    /// reported again only after the frontiers have advanced.
+    pub fn check<A: Allocate>(&mut self, worker: &Worker<A>) -> Option<StallReport> {
+        let now = Instant::now();
+        if self.next_check.map(|next| now < next).unwrap_or(false) {
+            return None;
+        }
+        self.next_check = Some(now + self.period / 4);
+
+        let statuses = worker.introspect();
+        let frontiers = statuses.iter().map(|status| (status.addr.clone(), status.frontiers.clone())).collect::<Vec<_>>();
+        let since = match self.observed.take() {
+            Some((observed, since)) if observed == frontiers => since,
+            _ => { self.reported = false; now },
+        };
+        self.observed = Some((frontiers, since));
+
+        let stalled = now.duration_since(since);
+        if self.reported || stalled < self.period {
+            return None;
+        }
+        let operators = statuses.iter().filter_map(StalledOperator::from_status).collect::<Vec<_>>();
+        if operators.is_empty() {
+            return None;
+        }
+        self.reported = true;
+        Some(StallReport { stalled, operators })
+    }
+}
+
+/// Operators holding capabilities, or awaiting records, while frontiers have not advanced.
+#[derive(Serialize, Deserialize, Abomonation, Debug, Clone, Hash, Eq, PartialEq, Ord, PartialOrd)]
+pub struct StallReport {
+    /// The time for which frontiers have not advanced.
+    pub stalled: Duration,
+    /// The operators holding capabilities or with records pending at their inputs, ordered by address.
+    pub operators: Vec<StalledOperator>,
+}
+
+impl Display for StallReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "Frontiers have not advanced for {:?}", self.stalled)?;
+        for operator in self.operators.iter() {
+            writeln!(f, "operator {} (id: {}, address: {:?}) with input frontiers {:?}", operator.name, operator.id, operator.addr, operator.frontiers)?;
+            for held in operator.capabilities.iter() {
+                writeln!(f, "  holds {} capabilities at {} on output {}", held.count, held.time, held.output)?;
+            }
+            for pending in operator.pending.iter() {
+                writeln!(f, "  awaits {} records at {} on input {} (channels {:?})", pending.count, pending.time, pending.input, pending.channels)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// An operator holding capabilities, or with records pending at its inputs.
+#[derive(Serialize, Deserialize, Abomonation, Debug, Clone, Hash, Eq, PartialEq, Ord, PartialOrd)]
+pub struct StalledOperator {
+    /// Worker-unique identifier for the operator, linkable to `OperatesEvent`.
+    pub id: usize,
+    /// Sequence of nested scope identifiers indicating the path from the root to this instance.
+    pub addr: Vec<usize>,
+    /// A helpful name.
+    pub name: String,
+    /// The frontier of each input, with timestamps formatted as strings.
+    pub frontiers: Vec<Vec<String>>,
+    /// The capabilities the operator holds.
+    pub capabilities: Vec<HeldCapability>,
+    /// The records sent to the operator and not yet received.
+    pub pending: Vec<PendingRecords>,
+}
+
+impl StalledOperator {
+    // The capabilities and pending records of `status`, if it has any.
+    fn from_status(status: &OperatorStatus) -> Option<Self> {
+        let capabilities = status.capabilities.iter().enumerate().flat_map(|(output, counts)| {
+            counts.iter().filter(|(_, count)| *count > 0).map(move |(time, count)| HeldCapability { output, time: time.clone(), count: *count })
+        }).collect::<Vec<_>>();
+        let pending = status.pending.iter().enumerate().flat_map(|(input, counts)| {
+            let channels = status.channels.get(input).cloned().unwrap_or_default();
+            counts.iter().filter(|(_, count)| *count > 0).map(move |(time, count)| PendingRecords { input, channels: channels.clone(), time: time.clone(), count: *count })
+        }).collect::<Vec<_>>();
+        if capabilities.is_empty() && pending.is_empty() {
+            None
+        }
+        else {
+            Some(StalledOperator {
+                id: status.id,
+                addr: status.addr.clone(),
+                name: status.name.clone(),
+                frontiers: status.frontiers.clone(),
+                capabilities,
+                pending,
+            })
+        }
+    }
+}
+
+/// Capabilities held by an operator at one timestamp.
+#[derive(Serialize, Deserialize, Abomonation, Debug, Clone, Hash, Eq, PartialEq, Ord, PartialOrd)]
+pub struct HeldCapability {
+    /// The output for which the capabilities are held.
+    pub output: usize,
+    /// The timestamp, formatted as a string.
+    pub time: String,
+    /// The number of capabilities held, across all workers.
+    pub count: i64,
+}
+
+/// Records at one timestamp sent to an input of an operator and not yet received.
+#[derive(Serialize, Deserialize, Abomonation, Debug, Clone, Hash, Eq, PartialEq, Ord, PartialOrd)]
+pub struct PendingRecords {
+    /// The input to which the records were sent.
+    pub input: usize,
+    /// The identifiers of the channels connected to the input.
+    pub channels: Vec<usize>,
+    /// The timestamp, formatted as a string.
+    pub time: String,
+    /// The number of records, across all workers.
+    pub count: i64,
+}
+
+#[cfg(test)]
+mod tests {
+
+    use std::time::Duration;
+
+    use crate::dataflow::channels::pact::Pipeline;
+    use crate::dataflow::operators::ToStream;
+    use crate::dataflow::operators::generic::operator::Operator;
+    use super::Watchdog;
+
+    #[test]
+    fn reports_undelivered_records() {
+        crate::execute_directly(|worker| {
+            worker.dataflow::<u64,_,_>(|scope| {
+                (0..3u64).to_stream(scope)
+                         .unary::<u64,_,_,_>(Pipeline, "Stuck", |_,_| |_input, _output| { });
+            });
+
+            let mut watchdog = Watchdog::new(Duration::from_millis(10));
+            let report = loop {
+                worker.step();
+                if let Some(report) = watchdog.check(worker) {
+                    break report;
+                }
+            };
+
+            let stuck = report.operators.iter().find(|op| op.name == "Stuck").unwrap();
+            assert!(stuck.capabilities.is_empty());
+            assert_eq!((stuck.pending[0].input, &stuck.pending[0].time[..], stuck.pending[0].count), (0, "0", 3));
+            assert_eq!(stuck.pending[0].channels.len(), 1);
+            assert!(report.to_string().contains("awaits 3 records at 0 on input 0"));
+
+            // The stall is reported only once.
+            worker.step();
+            ::std::thread::sleep(Duration::from_millis(20));
+            assert_eq!(watchdog.check(worker), None);
+
+            // The dataflow would otherwise never complete.
+            for dataflow in worker.installed_dataflows() {
+                worker.drop_dataflow(dataflow);
+            }
+        });
+    }
+}
@@ -1,8 +1,25 @@
 //! Starts a timely dataflow execution from configuration information and per-worker logic.
 
-use crate::communication::{initialize_from, Configuration, Allocator, allocator::AllocateBuilder, WorkerGuards};
+use std::sync::Arc;
+
+use crate::communication::{initialize_from, try_initialize_from_with, Configuration, InitializeError, ThreadConfig, Allocator, Allocate, allocator::AllocateBuilder, WorkerGuards};
+use crate::config::ConfigError;
 use crate::dataflow::scopes::Child;
 use crate::worker::Worker;
+use crate::communication::logging::{CommunicationEvent, CommunicationSetup};
+use crate::logging_core::Logger;
+
+/// A function invoked with a worker as it starts or stops.
+pub(crate) type WorkerHook = Arc<dyn Fn(&mut Worker<Allocator>)+Send+Sync>;
+
+/// Functions invoked with each worker as it starts, and as it stops.
+#[derive(Clone, Default)]
+pub(crate) struct Lifecycle {
+    /// Invoked in order before the closure of the execution, after loggers set by the environment.
+    pub(crate) start: Vec<WorkerHook>,
+    /// Invoked in order once the dataflows of the worker have completed.
+    pub(crate) stop: Vec<WorkerHook>,
+}
 
 /// Executes a single-threaded timely dataflow computation.
 ///
@@ -85,6 +102,7 @@ where
     let mut worker = crate::worker::Worker::new(alloc);
     let result = func(&mut worker);
     while worker.step_or_park(None) { }
+    resume_failure(worker);
     result
 }
 
@@ -141,69 +159,171 @@ where
 /// // the extracted data should have data (0..10) thrice at timestamp 0.
 /// assert_eq!(recv.extract()[0].1, (0..30).map(|x| x / 3).collect::<Vec<_>>());
 /// ```
-pub fn execute<T, F>(mut config: Configuration, func: F) -> Result<WorkerGuards<T>,String>
+pub fn execute<T, F>(config: Configuration, func: F) -> Result<WorkerGuards<T>,String>
 where
     T:Send+'static,
     F: Fn(&mut Worker<Allocator>)->T+Send+Sync+'static {
+    execute_with(config, ThreadConfig::default(), func)
+}
 
-    if let Configuration::Cluster { ref mut log_fn, .. } = config {
+/// Executes a timely dataflow from a configuration, spawning threads as `threads` describes.
+///
+/// The worker and network threads are named by the templates of `threads`, and each invokes its
+/// hook before it starts its work, as an opportunity to set thread priorities or affinities, or to
+/// install thread-local state. Refer to [`execute`](fn.execute.html) for more details.
+///
+/// # Examples
+/// ```rust
+/// use std::sync::Arc;
+/// use timely::communication::{ThreadConfig, ThreadKind};
+/// use timely::dataflow::operators::{ToStream, Inspect};
+///
+/// let threads = ThreadConfig {
+///     worker_name: "timely-worker-{}".to_string(),
+///     on_start: Some(Arc::new(|kind, index| {
+///         if kind == ThreadKind::Worker {
+///             println!("starting worker {}", index);
+///         }
+///     })),
+///     ..Default::default()
+/// };
+///
+/// timely::execute::execute_with(timely::Configuration::Process(2), threads, |worker| {
+///     let name = std::thread::current().name().unwrap().to_string();
+///     assert_eq!(name, format!("timely-worker-{}", worker.index()));
+///     worker.dataflow::<(),_,_>(|scope| {
+///         (0..10).to_stream(scope)
+///                .inspect(|x| println!("seen: {:?}", x));
+///     })
+/// }).unwrap();
+/// ```
+pub fn execute_with<T, F>(config: Configuration, threads: ThreadConfig, func: F) -> Result<WorkerGuards<T>,String>
+where
+    T:Send+'static,
+    F: Fn(&mut Worker<Allocator>)->T+Send+Sync+'static {
+    try_execute_lifecycle(config, threads, Lifecycle::default(), true, func).map_err(|error| error.to_string())
+}
 
-        *log_fn = Box::new(|events_setup| {
+/// Executes a timely dataflow from a configuration, reporting why it could not be started.
+///
+/// Unlike `execute`, failures to start the execution are reported as an `InitializeError`,
+/// which distinguishes invalid configurations from addresses that cannot be bound and
+/// processes that cannot be reached, rather than as a message. Processes that cannot be
+/// reached, for reasons other than that they do not yet listen, are reported rather than
+/// retried indefinitely.
+///
+/// # Examples
+/// ```rust
+/// use timely::InitializeError;
+/// use timely::dataflow::operators::{ToStream, Inspect};
+///
+/// match timely::try_execute(timely::Configuration::Process(0), |_worker| { }) {
+///     Err(InitializeError::Config(message)) => println!("invalid configuration: {}", message),
+///     _ => panic!("a process without workers started"),
+/// }
+///
+/// timely::try_execute(timely::Configuration::Process(2), |worker| {
+///     worker.dataflow::<(),_,_>(|scope| {
+///         (0..10).to_stream(scope)
+///                .inspect(|x| println!("seen: {:?}", x));
+///     })
+/// }).unwrap();
+/// ```
+pub fn try_execute<T, F>(config: Configuration, func: F) -> Result<WorkerGuards<T>,InitializeError>
+where
+    T:Send+'static,
+    F: Fn(&mut Worker<Allocator>)->T+Send+Sync+'static {
+    try_execute_with(config, ThreadConfig::default(), func)
+}
 
-            let mut result = None;
-            if let Ok(addr) = ::std::env::var("TIMELY_COMM_LOG_ADDR") {
+/// Executes a timely dataflow from a configuration, spawning threads as `threads` describes, and reporting why it could not be started.
+///
+/// Refer to [`try_execute`](fn.try_execute.html) and [`execute_with`](fn.execute_with.html) for more details.
+pub fn try_execute_with<T, F>(config: Configuration, threads: ThreadConfig, func: F) -> Result<WorkerGuards<T>,InitializeError>
+where
+    T:Send+'static,
+    F: Fn(&mut Worker<Allocator>)->T+Send+Sync+'static {
+    try_execute_lifecycle(config, threads, Lifecycle::default(), false, func)
+}
 
-                use ::std::net::TcpStream;
-                use crate::logging::BatchLogger;
-                use crate::dataflow::operators::capture::EventWriter;
+/// Executes a timely dataflow as `try_execute_with` does, invoking the hooks of `lifecycle` with each worker.
+///
+/// If `retry` is set, connections to processes that cannot be reached are retried until they succeed, as `execute` does.
+pub(crate) fn try_execute_lifecycle<T, F>(mut config: Configuration, threads: ThreadConfig, lifecycle: Lifecycle, retry: bool, func: F) -> Result<WorkerGuards<T>,InitializeError>
+where
+    T:Send+'static,
+    F: Fn(&mut Worker<Allocator>)->T+Send+Sync+'static {
 
-                eprintln!("enabled COMM logging to {}", addr);
+    // If an environment variable is set, log communication events to it, rather than as configured.
+    let comm_logging = LogDestination::from_env("COMM").map_err(|error| InitializeError::Config(error.to_string()))?;
+    if let (Configuration::Cluster { ref mut log_fn, .. }, Some(destination)) = (&mut config, comm_logging.clone()) {
+        *log_fn = Box::new(destination.communication_logger());
+    }
 
-                if let Ok(stream) = TcpStream::connect(&addr) {
-                    let writer = EventWriter::new(stream);
-                    let mut logger = BatchLogger::new(writer);
-                    result = Some(crate::logging_core::Logger::new(
-                        ::std::time::Instant::now(),
-                        events_setup,
-                        move |time, data| logger.publish_batch(time, data)
-                    ));
-                }
-                else {
-                    panic!("Could not connect to communication log address: {:?}", addr);
+    // If an environment variable is set, serve metrics for the process from its address.
+    #[cfg(feature = "metrics")]
+    let metrics = match ::std::env::var("TIMELY_METRICS_ADDR") {
+        Ok(addr) => {
+            let metrics = crate::metrics::Metrics::new();
+            let local = metrics.serve(&addr[..]).map_err(|error| InitializeError::Bind { address: addr.clone(), error })?;
+            crate::metrics::set_served_at(local);
+            if let Configuration::Cluster { ref mut log_fn, .. } = config {
+                if comm_logging.is_none() {
+                    *log_fn = Box::new(metrics.communication_logger());
                 }
             }
-            result
-        });
-    }
+            Some(metrics)
+        },
+        Err(_) => None,
+    };
 
-    let (allocators, other) = config.try_build()?;
+    // If an environment variable is set, serve a live feed of the process from its address.
+    #[cfg(feature = "viz")]
+    let feed = match ::std::env::var("TIMELY_VIZ_ADDR") {
+        Ok(addr) => {
+            let interval = ::std::env::var("TIMELY_VIZ_INTERVAL_MS")
+                .ok()
+                .and_then(|millis| millis.parse().ok())
+                .map(::std::time::Duration::from_millis)
+                .unwrap_or(::std::time::Duration::from_secs(1));
+            let feed = crate::viz::Feed::new(interval);
+            let local = feed.serve(&addr[..]).map_err(|error| InitializeError::Bind { address: addr.clone(), error })?;
+            crate::viz::set_served_at(local);
+            Some(feed)
+        },
+        Err(_) => None,
+    };
 
-    initialize_from(allocators, other, move |allocator| {
+    let worker_logging = LogDestination::from_env("WORKER").map_err(|error| InitializeError::Config(error.to_string()))?;
+    let (allocators, other) = if retry { config.build_retrying_with(&threads)? } else { config.build_with(&threads)? };
+
+    try_initialize_from_with(allocators, other, threads, move |allocator| {
 
         let mut worker = Worker::new(allocator);
 
+        // Metrics aggregate the worker's events, unless they are sent to a logging stream below.
+        #[cfg(feature = "metrics")]
+        let _tracker = metrics.as_ref().map(|metrics| metrics.install(&mut worker));
+
+        #[cfg(feature = "viz")]
+        if let Some(feed) = feed.as_ref() {
+            feed.install(&worker);
+        }
+
         // If an environment variable is set, use it as the default timely logging.
-        if let Ok(addr) = ::std::env::var("TIMELY_WORKER_LOG_ADDR") {
-
-            use ::std::net::TcpStream;
-            use crate::logging::{BatchLogger, TimelyEvent};
-            use crate::dataflow::operators::capture::EventWriter;
-
-            if let Ok(stream) = TcpStream::connect(&addr) {
-                let writer = EventWriter::new(stream);
-                let mut logger = BatchLogger::new(writer);
-                worker.log_register()
-                    .insert::<TimelyEvent,_>("timely", move |time, data|
-                        logger.publish_batch(time, data)
-                    );
-            }
-            else {
-                panic!("Could not connect logging stream to: {:?}", addr);
-            }
+        if let Some(destination) = worker_logging.as_ref() {
+            destination.install(&mut worker);
         }
 
+        for hook in lifecycle.start.iter() {
+            hook(&mut worker);
+        }
         let result = func(&mut worker);
         while worker.step_or_park(None) { }
+        for hook in lifecycle.stop.iter() {
+            hook(&mut worker);
+        }
+        resume_failure(worker);
         result
     })
 }
@@ -234,6 +354,8 @@ where
 /// If not specified, `localhost` will be used, with port numbers increasing from 2101 (chosen
 /// arbitrarily).
 ///
+/// To describe an execution in code rather than as arguments, use a [`Config`](../config/struct.Config.html).
+///
 /// # Examples
 ///
 /// ```rust
@@ -268,6 +390,26 @@ pub fn execute_from_args<I, T, F>(iter: I, func: F) -> Result<WorkerGuards<T>,St
     execute(configuration, func)
 }
 
+/// Executes a timely dataflow from supplied arguments, reporting why it could not be started.
+///
+/// Arguments that do not describe an execution are reported as `InitializeError::Config`,
+/// rather than cause a panic. Refer to [`execute_from_args`](fn.execute_from_args.html) for
+/// the arguments, and to [`try_execute`](fn.try_execute.html) for the errors.
+///
+/// # Examples
+///
+/// ```rust
+/// let args = vec!["-w", "two"].into_iter().map(|arg| arg.to_string());
+/// assert!(timely::try_execute_from_args(args, |_worker| { }).is_err());
+/// ```
+pub fn try_execute_from_args<I, T, F>(iter: I, func: F) -> Result<WorkerGuards<T>,InitializeError>
+    where I: Iterator<Item=String>,
+          T:Send+'static,
+          F: Fn(&mut Worker<Allocator>)->T+Send+Sync+'static, {
+    let configuration = Configuration::try_from_args(iter)?;
+    try_execute(configuration, func)
+}
+
 /// Executes a timely dataflow from supplied allocators and logging.
 ///
 /// Refer to [`execute`](fn.execute.html) for more details.
@@ -293,6 +435,164 @@ where
         let mut worker = Worker::new(allocator);
         let result = func(&mut worker);
         while worker.step_or_park(None) { }
+        resume_failure(worker);
+        result
+    })
+}
+
+/// Executes a timely dataflow with workers built from `builders`, one thread for each, spawned as `threads` describes.
+///
+/// The builders may construct allocators of any transport, for example one simulating a network
+/// or sharing memory among processes, with which the workers then exchange data and progress.
+/// Builders of different types are boxed, with `AllocateBuilder::boxed`, to choose among them as
+/// the program runs. The workers are of type `Worker<A>`, for the allocator `A` of the builders,
+/// and `func` may be generic in the allocator to run with each transport.
+///
+/// Workers log their events to the destinations of the `TIMELY_WORKER_LOG_ADDR` and
+/// `TIMELY_WORKER_LOG_PATH` environment variables, as with `execute`. The communication of the
+/// transport, and anything else the builders require, is up to the builders.
+///
+/// # Examples
+/// ```rust
+/// use timely::communication::{Allocate, ThreadConfig};
+/// use timely::communication::allocator::{AllocateBuilder, BoxedBuilder, Process, Thread};
+/// use timely::communication::allocator::thread::ThreadBuilder;
+/// use timely::dataflow::operators::{ToStream, Exchange, Inspect};
+/// use timely::worker::Worker;
+///
+/// // A dataflow for workers of any allocator.
+/// fn dataflow<A: Allocate>(worker: &mut Worker<A>) {
+///     worker.dataflow::<u64,_,_>(|scope| {
+///         (0 .. 10).to_stream(scope)
+///                  .exchange(|x| *x)
+///                  .inspect(|x| println!("seen: {:?}", x));
+///     });
+/// }
+///
+/// timely::execute::execute_with_allocators(Process::new_vector(2), ThreadConfig::default(), dataflow).unwrap();
+///
+/// // A builder of another type, which reports as it builds its allocator.
+/// struct Reporting(ThreadBuilder);
+/// impl AllocateBuilder for Reporting {
+///     type Allocator = Thread;
+///     fn build(self) -> Thread {
+///         println!("building an allocator");
+///         self.0.build()
+///     }
+/// }
+///
+/// // The builder may be chosen as the program runs, among builders of the same allocator.
+/// let builder: BoxedBuilder<Thread> = if std::env::var("TIMELY_REPORTING").is_ok() {
+///     Reporting(ThreadBuilder).boxed()
+/// } else {
+///     ThreadBuilder.boxed()
+/// };
+/// timely::execute::execute_with_allocators(vec![builder], ThreadConfig::default(), dataflow).unwrap();
+/// ```
+pub fn execute_with_allocators<A, T, F>(builders: Vec<A>, threads: ThreadConfig, func: F) -> Result<WorkerGuards<T>,InitializeError>
+where
+    A: AllocateBuilder+'static,
+    T: Send+'static,
+    F: Fn(&mut Worker<<A as AllocateBuilder>::Allocator>)->T+Send+Sync+'static {
+    let worker_logging = LogDestination::from_env("WORKER").map_err(|error| InitializeError::Config(error.to_string()))?;
+    try_initialize_from_with(builders, Box::new(()), threads, move |allocator| {
+        let mut worker = Worker::new(allocator);
+        if let Some(destination) = worker_logging.as_ref() {
+            destination.install(&mut worker);
+        }
+        let result = func(&mut worker);
+        while worker.step_or_park(None) { }
+        resume_failure(worker);
         result
     })
 }
+
+/// A destination for logged events, as a network address or a file path prefix, with the configuration of its sinks.
+#[derive(Clone, Debug)]
+pub(crate) enum LogDestination {
+    /// Events are sent to a network address, connecting when possible.
+    Addr(String, crate::logging::sink::SinkConfig),
+    /// Events are written to files whose names start with a prefix.
+    Path(String, crate::logging::sink::FileConfig),
+}
+
+impl LogDestination {
+    /// A network address, with sinks configured by `SinkConfig::from_env`.
+    pub(crate) fn addr(addr: String) -> Result<Self, ConfigError> {
+        Ok(LogDestination::Addr(addr, crate::logging::sink::SinkConfig::from_env()?))
+    }
+
+    /// A file path prefix, with sinks configured by `FileConfig::from_env`.
+    pub(crate) fn path(path: String) -> Result<Self, ConfigError> {
+        Ok(LogDestination::Path(path, crate::logging::sink::FileConfig::from_env()?))
+    }
+
+    /// The destination named by `TIMELY_{kind}_LOG_ADDR`, or else by `TIMELY_{kind}_LOG_PATH`.
+    fn from_env(kind: &str) -> Result<Option<Self>, ConfigError> {
+        if let Ok(addr) = ::std::env::var(format!("TIMELY_{}_LOG_ADDR", kind)) {
+            LogDestination::addr(addr).map(Some)
+        }
+        else if let Ok(path) = ::std::env::var(format!("TIMELY_{}_LOG_PATH", kind)) {
+            LogDestination::path(path).map(Some)
+        }
+        else {
+            Ok(None)
+        }
+    }
+
+    /// A function constructing loggers for communication threads, which send their events to the destination.
+    pub(crate) fn communication_logger(self) -> impl Fn(CommunicationSetup)->Option<Logger<CommunicationEvent, CommunicationSetup>>+Send+Sync {
+        use crate::logging::BatchLogger;
+        use crate::logging::sink::{TcpSink, FileSink};
+
+        move |events_setup| {
+            match &self {
+                LogDestination::Addr(addr, config) => {
+                    eprintln!("enabled COMM logging to {}", addr);
+                    // The sink connects when it can, buffering events until then.
+                    let mut logger = BatchLogger::new(TcpSink::new(addr.clone(), config.clone()));
+                    Some(Logger::new(crate::time::Instant::now(), events_setup, move |time, data| logger.publish_batch(time, data)))
+                },
+                LogDestination::Path(path, config) => {
+                    let direction = if events_setup.sender { "send" } else { "recv" };
+                    let prefix = match events_setup.remote {
+                        Some(remote) => format!("{}-comm-{}-{}-{}", path, events_setup.process, direction, remote),
+                        None => format!("{}-comm-{}-{}", path, events_setup.process, direction),
+                    };
+                    let mut logger = BatchLogger::new(FileSink::new(prefix, config.clone()));
+                    Some(Logger::new(crate::time::Instant::now(), events_setup, move |time, data| logger.publish_batch(time, data)))
+                },
+            }
+        }
+    }
+
+    /// Sends the `"timely"` events of `worker` to the destination.
+    pub(crate) fn install<A: Allocate>(&self, worker: &mut Worker<A>) {
+        use crate::logging::{BatchLogger, TimelyEvent};
+        use crate::logging::sink::{TcpSink, FileSink};
+
+        match self {
+            LogDestination::Addr(addr, config) => {
+                // The sink connects when it can, buffering events until then.
+                let mut logger = BatchLogger::new(TcpSink::new(addr.clone(), config.clone()));
+                worker.log_register().insert::<TimelyEvent,_>("timely", move |time, data| logger.publish_batch(time, data));
+            },
+            LogDestination::Path(path, config) => {
+                let prefix = format!("{}-worker-{}", path, worker.index());
+                let mut logger = BatchLogger::new(FileSink::new(prefix, config.clone()));
+                worker.log_register().insert::<TimelyEvent,_>("timely", move |time, data| logger.publish_batch(time, data));
+            },
+        }
+    }
+}
+
+/// Resumes the panic of the first failed dataflow of `worker`, if any, with the failure as payload.
+///
+/// The worker is dropped first, so that its communication threads shut down cleanly.
+fn resume_failure<A: Allocate>(worker: Worker<A>) {
+    let failure = worker.failures().into_iter().next();
+    drop(worker);
+    if let Some(failure) = failure {
+        ::std::panic::resume_unwind(Box::new(failure));
+    }
+}
@@ -0,0 +1,326 @@
+//! Discovering the processes of a cluster from the environment of an HPC scheduler.
+//!
+//! Jobs launched by `srun` under SLURM, by `pbsdsh` under PBS or Torque, or by `mpirun` of
+//! OpenMPI find their rank, the number of processes, and often the hosts of the processes in
+//! environment variables their launcher sets. `Allocation::detect` reads these, and
+//! `Allocation::config` describes an execution of the processes, so that the same program may
+//! be launched on each host without writing a hostfile.
+//!
+//! * SLURM: the rank is `SLURM_PROCID` of `SLURM_STEP_NUM_TASKS` (or `SLURM_NTASKS`) tasks,
+//!   which are placed on the hosts of `SLURM_STEP_NODELIST` (or `SLURM_JOB_NODELIST`) as
+//!   `SLURM_STEP_TASKS_PER_NODE` (or `SLURM_TASKS_PER_NODE`) describes, in blocks of
+//!   consecutive ranks, as by the default distribution of tasks. The number of workers is
+//!   `SLURM_CPUS_PER_TASK`, if set.
+//! * PBS: the rank is `PBS_VNODENUM`, set by `pbsdsh` for each line of `PBS_NODEFILE`, whose
+//!   lines are the hosts of the processes.
+//! * OpenMPI: the rank is `OMPI_COMM_WORLD_RANK` of `OMPI_COMM_WORLD_SIZE` processes.
+//!   OpenMPI does not describe the hosts of the processes; they are those of a SLURM or PBS
+//!   allocation of as many tasks or slots, in which ranks are placed in order, and are otherwise
+//!   unknown and must be supplied, for example with a rendezvous.
+//!
+//! Processes sharing a host are given consecutive ports, from a base port, in order of rank.
+//!
+//! # Examples
+//! ```
+//! use std::collections::HashMap;
+//! use timely::hpc::{Allocation, Scheduler};
+//!
+//! // The environment of the third task of `srun --ntasks=4 --cpus-per-task=8` on two nodes.
+//! let vars: HashMap<&str, &str> = vec![
+//!     ("SLURM_PROCID", "2"),
+//!     ("SLURM_STEP_NUM_TASKS", "4"),
+//!     ("SLURM_STEP_NODELIST", "node[07-08]"),
+//!     ("SLURM_STEP_TASKS_PER_NODE", "2(x2)"),
+//!     ("SLURM_CPUS_PER_TASK", "8"),
+//! ].into_iter().collect();
+//!
+//! let allocation = Allocation::from_vars(|name| vars.get(name).map(|value| value.to_string())).unwrap().unwrap();
+//! assert_eq!(allocation.scheduler, Scheduler::Slurm);
+//! assert_eq!(allocation.process, 2);
+//! assert_eq!(allocation.workers, Some(8));
+//! assert_eq!(allocation.addresses(2101).unwrap(), vec!["node07:2101", "node07:2102", "node08:2101", "node08:2102"]);
+//!
+//! if let Some(allocation) = Allocation::detect() {
+//!     let guards = allocation.unwrap().config(2101).execute(|worker| {
+//!         println!("worker {} of {}", worker.index(), worker.peers());
+//!     });
+//! }
+//! ```
+
+use std::path::PathBuf;
+
+use crate::config::{Config, ConfigError};
+
+/// The scheduler or launcher that describes the processes of a job.
+#[derive(Debug, Clone, Copy, Hash, Eq, PartialEq)]
+pub enum Scheduler {
+    /// SLURM, as for tasks started by `srun`.
+    Slurm,
+    /// PBS or Torque, as for processes started by `pbsdsh`.
+    Pbs,
+    /// OpenMPI, as for processes started by `mpirun`.
+    OpenMpi,
+}
+
+/// The processes of a job, as its scheduler describes them.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Allocation {
+    /// The scheduler that supplied the rank of the process.
+    pub scheduler: Scheduler,
+    /// The rank of this process, from zero up to the number of processes.
+    pub process: usize,
+    /// The number of processes.
+    pub processes: usize,
+    /// The host of each process, in order of rank, if the scheduler describes them.
+    pub hosts: Option<Vec<String>>,
+    /// The number of workers of each process, if the scheduler allots processors to each.
+    pub workers: Option<usize>,
+}
+
+impl Allocation {
+    /// Reads the processes of the job from the environment, if a scheduler describes them.
+    pub fn detect() -> Option<Result<Allocation, ConfigError>> {
+        Allocation::from_vars(|name| ::std::env::var(name).ok())
+    }
+
+    /// Reads the processes of the job from the variables `var` reports, if a scheduler describes them.
+    ///
+    /// The result is `None` if none of the schedulers has set a rank, and an error if the
+    /// variables of the scheduler that has cannot be parsed or do not agree.
+    pub fn from_vars<F: Fn(&str)->Option<String>>(var: F) -> Option<Result<Allocation, ConfigError>> {
+        let workers = match number(&var, "SLURM_CPUS_PER_TASK") {
+            Ok(workers) => workers,
+            Err(error) => return Some(Err(error)),
+        };
+        if var("OMPI_COMM_WORLD_RANK").is_some() {
+            Some(Allocation::openmpi(&var, workers))
+        }
+        else if var("SLURM_PROCID").is_some() {
+            Some(Allocation::slurm(&var, workers))
+        }
+        else if var("PBS_VNODENUM").is_some() {
+            Some(Allocation::pbs(&var, workers))
+        }
+        else {
+            None
+        }
+    }
+
+    /// The addresses of the processes, with ports increasing from `base` among the processes of each host.
+    pub fn addresses(&self, base: u16) -> Option<Vec<String>> {
+        self.hosts.as_ref().map(|hosts| {
+            hosts.iter().enumerate().map(|(rank, host)| {
+                let local = hosts[.. rank].iter().filter(|other| *other == host).count();
+                format!("{}:{}", host, base as usize + local)
+            }).collect()
+        })
+    }
+
+    /// A description of an execution of the processes, reached at ports increasing from `base` on each host.
+    ///
+    /// The description sets the rank and number of processes, their addresses, and the number of
+    /// workers, if the scheduler describes them. Without hosts, the addresses must be supplied,
+    /// for example with `Config::bootstrap`.
+    pub fn config(&self, base: u16) -> Config {
+        let mut config = Config::new().processes(self.processes).process(self.process);
+        if let Some(addresses) = self.addresses(base) {
+            config = config.addresses(addresses);
+        }
+        if let Some(workers) = self.workers {
+            config = config.workers(workers);
+        }
+        config
+    }
+
+    fn slurm<F: Fn(&str)->Option<String>>(var: &F, workers: Option<usize>) -> Result<Allocation, ConfigError> {
+        let process = required(var, "SLURM_PROCID")?;
+        let processes = first(var, &["SLURM_STEP_NUM_TASKS", "SLURM_NTASKS", "SLURM_NPROCS"])?.unwrap_or(1);
+        let hosts = match (text(var, &["SLURM_STEP_NODELIST", "SLURM_JOB_NODELIST", "SLURM_NODELIST"]), text(var, &["SLURM_STEP_TASKS_PER_NODE", "SLURM_TASKS_PER_NODE"])) {
+            (Some((nodes_name, nodes)), Some((tasks_name, tasks))) => {
+                let nodes = expand_hostlist(&nodes).map_err(|_| ConfigError::Env { variable: nodes_name.to_string(), value: nodes.clone() })?;
+                let tasks = expand_tasks_per_node(&tasks).map_err(|_| ConfigError::Env { variable: tasks_name.to_string(), value: tasks.clone() })?;
+                if nodes.len() != tasks.len() {
+                    return Err(ConfigError::AddressCount { addresses: nodes.len(), processes: tasks.len() });
+                }
+                Some(nodes.into_iter().zip(tasks).flat_map(|(node, count)| ::std::iter::repeat_n(node, count)).collect())
+            },
+            _ => None,
+        };
+        Allocation::checked(Scheduler::Slurm, process, processes, hosts, workers)
+    }
+
+    fn pbs<F: Fn(&str)->Option<String>>(var: &F, workers: Option<usize>) -> Result<Allocation, ConfigError> {
+        let process = required(var, "PBS_VNODENUM")?;
+        let hosts = pbs_nodes(var)?.ok_or_else(|| ConfigError::Env { variable: "PBS_NODEFILE".to_string(), value: String::new() })?;
+        Allocation::checked(Scheduler::Pbs, process, hosts.len(), Some(hosts), workers)
+    }
+
+    fn openmpi<F: Fn(&str)->Option<String>>(var: &F, workers: Option<usize>) -> Result<Allocation, ConfigError> {
+        let process = required(var, "OMPI_COMM_WORLD_RANK")?;
+        let processes = required(var, "OMPI_COMM_WORLD_SIZE")?;
+        // The hosts of an enclosing allocation are used if it has a slot for each process.
+        let hosts = match var("SLURM_JOB_ID").and(Allocation::slurm_hosts(var)) {
+            Some(hosts) => Some(hosts),
+            None => pbs_nodes(var).ok().flatten(),
+        };
+        let hosts = hosts.filter(|hosts| hosts.len() == processes);
+        Allocation::checked(Scheduler::OpenMpi, process, processes, hosts, workers)
+    }
+
+    /// The hosts of the tasks of a SLURM allocation, if they can be read.
+    fn slurm_hosts<F: Fn(&str)->Option<String>>(var: &F) -> Option<Vec<String>> {
+        let nodes = expand_hostlist(&var("SLURM_JOB_NODELIST")?).ok()?;
+        let tasks = expand_tasks_per_node(&var("SLURM_TASKS_PER_NODE")?).ok()?;
+        if nodes.len() != tasks.len() { return None; }
+        Some(nodes.into_iter().zip(tasks).flat_map(|(node, count)| ::std::iter::repeat_n(node, count)).collect())
+    }
+
+    fn checked(scheduler: Scheduler, process: usize, processes: usize, hosts: Option<Vec<String>>, workers: Option<usize>) -> Result<Allocation, ConfigError> {
+        if processes == 0 {
+            return Err(ConfigError::NoProcesses);
+        }
+        if process >= processes {
+            return Err(ConfigError::ProcessOutOfRange { process, processes });
+        }
+        if let Some(hosts) = hosts.as_ref() {
+            if hosts.len() != processes {
+                return Err(ConfigError::AddressCount { addresses: hosts.len(), processes });
+            }
+        }
+        if workers == Some(0) {
+            return Err(ConfigError::NoWorkers);
+        }
+        Ok(Allocation { scheduler, process, processes, hosts, workers })
+    }
+}
+
+/// Expands a SLURM host list, such as `node[01-03,07],login1`, into the names of its hosts.
+///
+/// Ranges keep the zero-padding of their bounds, and names may contain several bracketed lists,
+/// whose combinations are expanded in order.
+///
+/// # Examples
+/// ```
+/// use timely::hpc::expand_hostlist;
+///
+/// assert_eq!(expand_hostlist("node[01-03,07],login1").unwrap(), vec!["node01", "node02", "node03", "node07", "login1"]);
+/// assert_eq!(expand_hostlist("rack[1-2]-gpu[0,1]").unwrap(), vec!["rack1-gpu0", "rack1-gpu1", "rack2-gpu0", "rack2-gpu1"]);
+/// assert!(expand_hostlist("node[3-1]").is_err());
+/// ```
+pub fn expand_hostlist(list: &str) -> Result<Vec<String>, String> {
+    // Names are separated by commas outside of brackets.
+    let mut names = Vec::new();
+    let mut depth = 0;
+    let mut start = 0;
+    for (position, character) in list.char_indices() {
+        match character {
+            '[' => depth += 1,
+            ']' if depth == 0 => return Err(format!("unmatched ']' in {}", list)),
+            ']' => depth -= 1,
+            ',' if depth == 0 => {
+                names.push(&list[start .. position]);
+                start = position + 1;
+            },
+            _ => { },
+        }
+    }
+    if depth != 0 {
+        return Err(format!("unmatched '[' in {}", list));
+    }
+    names.push(&list[start ..]);
+
+    let mut hosts = Vec::new();
+    for name in names.into_iter().map(|name| name.trim()).filter(|name| !name.is_empty()) {
+        expand_name(name, &mut hosts)?;
+    }
+    Ok(hosts)
+}
+
+/// Expands the bracketed lists of `name` into `hosts`.
+fn expand_name(name: &str, hosts: &mut Vec<String>) -> Result<(), String> {
+    let open = match name.find('[') {
+        Some(open) => open,
+        None => { hosts.push(name.to_string()); return Ok(()); },
+    };
+    let close = open + name[open ..].find(']').ok_or_else(|| format!("unmatched '[' in {}", name))?;
+    let (prefix, ranges, rest) = (&name[.. open], &name[open + 1 .. close], &name[close + 1 ..]);
+
+    let mut suffixes = Vec::new();
+    expand_name(rest, &mut suffixes)?;
+    for range in ranges.split(',') {
+        let (low, high) = match range.find('-') {
+            Some(dash) => (&range[.. dash], &range[dash + 1 ..]),
+            None => (range, range),
+        };
+        let parse = |bound: &str| bound.parse::<usize>().map_err(|_| format!("invalid range {} in {}", range, name));
+        let (from, to) = (parse(low)?, parse(high)?);
+        if from > to {
+            return Err(format!("invalid range {} in {}", range, name));
+        }
+        for value in from ..= to {
+            for suffix in suffixes.iter() {
+                hosts.push(format!("{}{:0width$}{}", prefix, value, suffix, width = low.len()));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Expands a SLURM count of tasks on each node, such as `2(x3),1`, into a count for each node.
+///
+/// # Examples
+/// ```
+/// use timely::hpc::expand_tasks_per_node;
+///
+/// assert_eq!(expand_tasks_per_node("2(x3),1").unwrap(), vec![2, 2, 2, 1]);
+/// assert!(expand_tasks_per_node("two").is_err());
+/// ```
+pub fn expand_tasks_per_node(text: &str) -> Result<Vec<usize>, String> {
+    let mut counts = Vec::new();
+    for entry in text.split(',').map(|entry| entry.trim()) {
+        let invalid = || format!("invalid count of tasks {} in {}", entry, text);
+        let (count, repeat) = match entry.find("(x") {
+            Some(open) if entry.ends_with(')') => (&entry[.. open], entry[open + 2 .. entry.len() - 1].parse().map_err(|_| invalid())?),
+            Some(_) => return Err(invalid()),
+            None => (entry, 1),
+        };
+        let count = count.parse::<usize>().map_err(|_| invalid())?;
+        counts.extend(::std::iter::repeat_n(count, repeat));
+    }
+    Ok(counts)
+}
+
+/// The hosts of the lines of `PBS_NODEFILE`, if it is set.
+fn pbs_nodes<F: Fn(&str)->Option<String>>(var: &F) -> Result<Option<Vec<String>>, ConfigError> {
+    match var("PBS_NODEFILE") {
+        Some(path) => {
+            let path = PathBuf::from(path);
+            let text = ::std::fs::read_to_string(&path).map_err(|error| ConfigError::Hostfile { path: path.clone(), message: error.to_string() })?;
+            Ok(Some(text.lines().map(|line| line.trim()).filter(|line| !line.is_empty()).map(|line| line.to_string()).collect()))
+        },
+        None => Ok(None),
+    }
+}
+
+/// The value of the first of `names` that is set, with its name.
+fn text<F: Fn(&str)->Option<String>>(var: &F, names: &[&'static str]) -> Option<(&'static str, String)> {
+    names.iter().find_map(|name| var(name).map(|value| (*name, value)))
+}
+
+/// The number in the first of `names` that is set, if any is.
+fn first<F: Fn(&str)->Option<String>>(var: &F, names: &[&'static str]) -> Result<Option<usize>, ConfigError> {
+    match text(var, names) {
+        Some((variable, value)) => value.trim().parse().map(Some).map_err(|_| ConfigError::Env { variable: variable.to_string(), value }),
+        None => Ok(None),
+    }
+}
+
+/// The number in `name`, if it is set.
+fn number<F: Fn(&str)->Option<String>>(var: &F, name: &'static str) -> Result<Option<usize>, ConfigError> {
+    first(var, &[name])
+}
+
+/// The number in `name`, which must be set.
+fn required<F: Fn(&str)->Option<String>>(var: &F, name: &'static str) -> Result<usize, ConfigError> {
+    number(var, name)?.ok_or_else(|| ConfigError::Env { variable: name.to_string(), value: String::new() })
+}
@@ -0,0 +1,336 @@
+//! A service through which external producers feed the inputs of dataflows.
+//!
+//! With the `ingest` feature, an `Ingest` accepts connections from producers over TCP, with
+//! `serve`, and delivers the records and epoch advances they send to the inputs that workers
+//! of the process have attached under a name, with `attach`. Records are distributed among
+//! the workers attached to an input in turn, and advances reach all of them. Each worker moves
+//! what it has been sent into its `InputHandle` when it calls `IngestInput::pump`, which also
+//! reports the frontier of a probe of the dataflow. A producer's advance to an epoch is held
+//! back while any attached probe trails it by more than the `lag` of the `Ingest`, and a record
+//! is held back while the worker due to receive it has `MAX_QUEUED` instructions yet to pump.
+//! Either stops the service from reading the connection and, in turn, the producer from writing.
+//!
+//! The protocol is a sequence of frames, each a little-endian `u32` length followed by that
+//! many bytes. The first frame of a connection is the name of the input, in UTF-8, and the
+//! service closes connections naming inputs that no worker has attached. Each later frame is
+//! a tag byte and its payload: `0` followed by the bytes of a record, `1` followed by an epoch
+//! as a little-endian `u64`, or `2` to close the input. Advances to epochs an input has passed
+//! are ignored, so several producers may feed one input. A `Producer` speaks the protocol.
+//!
+//! # Examples
+//! ```
+//! use timely::dataflow::InputHandle;
+//! use timely::dataflow::operators::{Input, Inspect, Probe};
+//! use timely::ingest::{Ingest, Producer};
+//!
+//! let ingest = Ingest::new(2);
+//! let addr = ingest.serve("127.0.0.1:0").unwrap();
+//! let shared = ingest.clone();
+//!
+//! let guards = timely::execute(timely::Configuration::Process(2), move |worker| {
+//!     let mut input = InputHandle::<u64, Vec<u8>>::new();
+//!     let probe = worker.dataflow(|scope| {
+//!         scope.input_from(&mut input)
+//!              .inspect(|record| assert_eq!(record.len(), 4))
+//!              .probe()
+//!     });
+//!     let mut input = shared.attach("events", input, &probe);
+//!     while input.pump() {
+//!         worker.step_or_park(None);
+//!     }
+//! }).unwrap();
+//!
+//! // Producers should retry until the workers have attached their inputs.
+//! let mut producer = loop {
+//!     if ingest.attached("events") == 2 {
+//!         break Producer::connect(addr, "events").unwrap();
+//!     }
+//!     std::thread::yield_now();
+//! };
+//! for epoch in 0 .. 10u64 {
+//!     producer.send(&(epoch as u32).to_le_bytes()).unwrap();
+//!     producer.advance_to(epoch + 1).unwrap();
+//! }
+//! producer.close().unwrap();
+//! guards.join();
+//! ```
+
+use std::collections::{HashMap, VecDeque};
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream, ToSocketAddrs};
+use std::sync::{Arc, Condvar, Mutex};
+use std::thread::Thread;
+
+use crate::dataflow::{InputHandle, ProbeHandle};
+
+/// Frame tag of a record.
+const RECORD: u8 = 0;
+/// Frame tag of an epoch advance.
+const ADVANCE: u8 = 1;
+/// Frame tag closing the input.
+const CLOSE: u8 = 2;
+
+/// The largest frame the service accepts.
+const MAX_FRAME: usize = 1 << 30;
+
+/// The number of instructions a worker may have yet to pump before records for it are held back.
+pub const MAX_QUEUED: usize = 1 << 16;
+
+// An instruction for a worker's input.
+enum Command {
+    Record(Vec<u8>),
+    Advance(u64),
+    Close,
+}
+
+// The instructions for one worker attached to an input.
+struct Queue {
+    commands: VecDeque<Command>,
+    // The thread of the worker, to unpark once instructions are queued.
+    thread: Thread,
+    // The least time of the worker's probe, or `None` once its frontier is empty.
+    frontier: Option<u64>,
+}
+
+// A named input, and the workers attached to it.
+#[derive(Default)]
+struct Input {
+    queues: Vec<Queue>,
+    // The queue to receive the next record.
+    next: usize,
+    epoch: u64,
+    closed: bool,
+}
+
+/// A service feeding the inputs of the workers of a process, shared among their threads.
+#[derive(Clone)]
+pub struct Ingest {
+    state: Arc<(Mutex<HashMap<String, Input>>, Condvar)>,
+    lag: u64,
+}
+
+impl Ingest {
+    /// A service holding back producers while probes trail their advances by more than `lag` epochs.
+    pub fn new(lag: u64) -> Self {
+        Ingest { state: Arc::new((Mutex::new(HashMap::new()), Condvar::new())), lag }
+    }
+
+    /// Attaches `input` under `name` to the service, with the probe reporting the progress of its dataflow.
+    ///
+    /// The returned `IngestInput` owns the handle, and must be pumped by the worker thread
+    /// calling this method, which is unparked when there is more to pump.
+    pub fn attach(&self, name: &str, input: InputHandle<u64, Vec<u8>>, probe: &ProbeHandle<u64>) -> IngestInput {
+        let (state, _) = &*self.state;
+        let mut inputs = state.lock().expect("ingest state poisoned");
+        let entry = inputs.entry(name.to_owned()).or_default();
+        entry.queues.push(Queue {
+            commands: VecDeque::new(),
+            thread: std::thread::current(),
+            frontier: Some(*input.time()),
+        });
+        IngestInput {
+            name: name.to_owned(),
+            slot: entry.queues.len() - 1,
+            handle: Some(input),
+            probe: probe.clone(),
+            state: self.state.clone(),
+        }
+    }
+
+    /// The names of the inputs attached to the service.
+    pub fn inputs(&self) -> Vec<String> {
+        let (state, _) = &*self.state;
+        state.lock().expect("ingest state poisoned").keys().cloned().collect()
+    }
+
+    /// The number of workers attached to the input named `name`.
+    pub fn attached(&self, name: &str) -> usize {
+        let (state, _) = &*self.state;
+        state.lock().expect("ingest state poisoned").get(name).map(|input| input.queues.len()).unwrap_or(0)
+    }
+
+    /// Serves producers at `addr`, from a background thread, with a thread for each connection.
+    ///
+    /// The method returns the address at which the service listens, which reveals the port
+    /// chosen for addresses with port zero.
+    pub fn serve<S: ToSocketAddrs>(&self, addr: S) -> io::Result<SocketAddr> {
+        let listener = TcpListener::bind(addr)?;
+        let local = listener.local_addr()?;
+        let ingest = self.clone();
+        ::std::thread::Builder::new()
+            .name("ingest server".to_string())
+            .spawn(move || {
+                for stream in listener.incoming().flatten() {
+                    let ingest = ingest.clone();
+                    let _ = ::std::thread::Builder::new()
+                        .name("ingest connection".to_string())
+                        .spawn(move || { let _ = ingest.receive(stream); });
+                }
+            })?;
+        Ok(local)
+    }
+
+    // Delivers the frames of a connection to the input it names.
+    fn receive(&self, stream: TcpStream) -> io::Result<()> {
+        let mut reader = BufReader::new(stream);
+        let name = String::from_utf8(read_frame(&mut reader)?).map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error))?;
+        if self.attached(&name) == 0 {
+            return Err(io::Error::new(io::ErrorKind::NotFound, format!("no input named {}", name)));
+        }
+        loop {
+            let frame = read_frame(&mut reader)?;
+            let command = match frame.split_first() {
+                Some((&RECORD, record)) => Command::Record(record.to_vec()),
+                Some((&ADVANCE, epoch)) if epoch.len() == 8 => {
+                    let mut bytes = [0u8; 8];
+                    bytes.copy_from_slice(epoch);
+                    Command::Advance(u64::from_le_bytes(bytes))
+                },
+                Some((&CLOSE, _)) => Command::Close,
+                _ => return Err(io::Error::new(io::ErrorKind::InvalidData, "malformed frame")),
+            };
+            let close = matches!(command, Command::Close);
+            self.deliver(&name, command);
+            if close {
+                return Ok(());
+            }
+        }
+    }
+
+    // Queues `command` for the workers attached to `name`, first awaiting probes for advances,
+    // and the receiving worker's pumping for records.
+    fn deliver(&self, name: &str, command: Command) {
+        let (state, condvar) = &*self.state;
+        let mut inputs = state.lock().expect("ingest state poisoned");
+        match command {
+            Command::Advance(epoch) => {
+                let lag = self.lag;
+                inputs = condvar.wait_while(inputs, |inputs| {
+                    inputs[name].queues.iter().any(|queue| queue.frontier.map(|time| time.saturating_add(lag) < epoch).unwrap_or(false))
+                }).expect("ingest state poisoned");
+            },
+            Command::Record(_) => {
+                inputs = condvar.wait_while(inputs, |inputs| {
+                    let input = &inputs[name];
+                    !input.closed && input.queues[input.next].commands.len() >= MAX_QUEUED
+                }).expect("ingest state poisoned");
+            },
+            Command::Close => { },
+        }
+        let input = inputs.get_mut(name).expect("input detached");
+        if input.closed {
+            return;
+        }
+        match command {
+            Command::Record(record) => {
+                let queue = &mut input.queues[input.next];
+                queue.commands.push_back(Command::Record(record));
+                queue.thread.unpark();
+                input.next = (input.next + 1) % input.queues.len();
+            },
+            Command::Advance(epoch) => {
+                if epoch > input.epoch {
+                    input.epoch = epoch;
+                    for queue in input.queues.iter_mut() {
+                        queue.commands.push_back(Command::Advance(epoch));
+                        queue.thread.unpark();
+                    }
+                }
+            },
+            Command::Close => {
+                input.closed = true;
+                for queue in input.queues.iter_mut() {
+                    queue.commands.push_back(Command::Close);
+                    queue.thread.unpark();
+                }
+            },
+        }
+    }
+}
+
+/// An input attached to an `Ingest`, as returned by `Ingest::attach`.
+pub struct IngestInput {
+    name: String,
+    slot: usize,
+    handle: Option<InputHandle<u64, Vec<u8>>>,
+    probe: ProbeHandle<u64>,
+    state: Arc<(Mutex<HashMap<String, Input>>, Condvar)>,
+}
+
+impl IngestInput {
+    /// Moves the records and advances sent to this worker into its input, and reports the frontier of its probe.
+    ///
+    /// Returns `false` once a producer has closed the input, at which point the input handle
+    /// has been dropped. The worker thread is unparked when there is more to move.
+    pub fn pump(&mut self) -> bool {
+        let (state, condvar) = &*self.state;
+        let mut inputs = state.lock().expect("ingest state poisoned");
+        let queue = &mut inputs.get_mut(&self.name).expect("input detached").queues[self.slot];
+        while let Some(command) = queue.commands.pop_front() {
+            match (command, self.handle.as_mut()) {
+                (Command::Record(record), Some(handle)) => handle.send(record),
+                (Command::Advance(epoch), Some(handle)) => handle.advance_to(epoch),
+                (Command::Close, _) => { self.handle = None; },
+                _ => { },
+            }
+        }
+        queue.frontier = self.probe.with_frontier(|frontier| frontier.iter().min().cloned());
+        condvar.notify_all();
+        self.handle.is_some()
+    }
+}
+
+/// A producer feeding an input of an `Ingest`.
+pub struct Producer {
+    stream: BufWriter<TcpStream>,
+}
+
+impl Producer {
+    /// Connects to the service at `addr`, to feed the input named `name`.
+    pub fn connect<S: ToSocketAddrs>(addr: S, name: &str) -> io::Result<Self> {
+        let mut producer = Producer { stream: BufWriter::new(TcpStream::connect(addr)?) };
+        producer.frame(&[], name.as_bytes())?;
+        Ok(producer)
+    }
+
+    /// Sends `record` at the current epoch of the input.
+    ///
+    /// Writes stall while the worker due to receive the record has `MAX_QUEUED` instructions yet to pump.
+    pub fn send(&mut self, record: &[u8]) -> io::Result<()> {
+        self.frame(&[RECORD], record)
+    }
+
+    /// Advances the input to `epoch`, and flushes the records sent before it.
+    ///
+    /// Writes stall while the dataflow trails the advance by more than the lag of the service.
+    pub fn advance_to(&mut self, epoch: u64) -> io::Result<()> {
+        self.frame(&[ADVANCE], &epoch.to_le_bytes())?;
+        self.stream.flush()
+    }
+
+    /// Closes the input, for all producers.
+    pub fn close(mut self) -> io::Result<()> {
+        self.frame(&[CLOSE], &[])?;
+        self.stream.flush()
+    }
+
+    fn frame(&mut self, tag: &[u8], payload: &[u8]) -> io::Result<()> {
+        let length = (tag.len() + payload.len()) as u32;
+        self.stream.write_all(&length.to_le_bytes())?;
+        self.stream.write_all(tag)?;
+        self.stream.write_all(payload)
+    }
+}
+
+// Reads one length-prefixed frame.
+fn read_frame<R: Read>(reader: &mut R) -> io::Result<Vec<u8>> {
+    let mut length = [0u8; 4];
+    reader.read_exact(&mut length)?;
+    let length = u32::from_le_bytes(length) as usize;
+    if length > MAX_FRAME {
+        return Err(io::Error::new(io::ErrorKind::InvalidData, "frame too large"));
+    }
+    let mut frame = vec![0u8; length];
+    reader.read_exact(&mut frame)?;
+    Ok(frame)
+}
@@ -67,10 +67,18 @@ extern crate timely_communication;
 extern crate timely_bytes;
 extern crate timely_logging;
 
-pub use execute::{execute, execute_directly, execute_from_args, example};
+pub use execute::{execute, execute_directly, execute_from_args, example, try_execute, try_execute_from_args};
+pub use config::{Config, ConfigError};
 pub use order::PartialOrder;
 
-pub use timely_communication::Configuration;
+pub use timely_communication::{Configuration, InitializeError};
+
+// Processing time is read through `web-time` on `wasm32-unknown-unknown`, where `std::time::Instant`
+// panics; elsewhere its types are those of `std::time`.
+#[cfg(all(target_arch = "wasm32", target_os = "unknown"))]
+use web_time as time;
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+use std::time;
 
 /// Re-export of the `timely_communication` crate.
 pub mod communication {
@@ -92,12 +100,30 @@ pub mod progress;
 pub mod dataflow;
 pub mod synchronization;
 pub mod execute;
+pub mod config;
+pub mod cluster;
+pub mod hpc;
 pub mod order;
+pub mod consolidation;
 
 pub mod logging;
 // pub mod log_events;
 
 pub mod scheduling;
+pub mod testing;
+pub mod bench;
+#[cfg(feature = "metrics")]
+pub mod metrics;
+#[cfg(feature = "metrics-facade")]
+pub mod metrics_facade;
+#[cfg(feature = "monitor")]
+pub mod monitor;
+#[cfg(feature = "viz")]
+pub mod viz;
+#[cfg(feature = "trace")]
+pub mod trace;
+#[cfg(feature = "ingest")]
+pub mod ingest;
 
 /// A composite trait for types usable as data in timely dataflow.
 ///
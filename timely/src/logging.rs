@@ -7,6 +7,13 @@ pub type Logger<Event> = crate::logging_core::Logger<Event, WorkerIdentifier>;
 /// Logger for timely dataflow system events.
 pub type TimelyLogger = Logger<TimelyEvent>;
 
+pub mod recorder;
+pub mod schema;
+pub mod sink;
+pub mod source;
+#[cfg(feature = "tracing")]
+pub mod tracing;
+
 use std::time::Duration;
 use crate::dataflow::operators::capture::{Event, EventPusher};
 
@@ -51,6 +58,8 @@ pub struct OperatesEvent {
     pub addr: Vec<usize>,
     /// A helpful name.
     pub name: String,
+    /// Names of the scopes enclosing this instance, from the dataflow down, ending with `name`.
+    pub names: Vec<String>,
 }
 
 #[derive(Serialize, Deserialize, Abomonation, Debug, Clone, Hash, Eq, PartialEq, Ord, PartialOrd)]
@@ -83,6 +92,17 @@ pub struct ProgressEvent {
     pub messages: Vec<(usize, usize, String, i64)>,
     /// List of capability updates, containing Source descriptor, timestamp as string, and delta.
     pub internal: Vec<(usize, usize, String, i64)>,
+    /// The number of updates in the message.
+    pub updates: usize,
+    /// The size of the encoded updates, in bytes.
+    pub bytes: usize,
+    /// For messages received from other workers, the estimated time since they were sent.
+    ///
+    /// The estimate compares the system clocks of the two workers, and so includes any
+    /// difference between the clocks of their machines.
+    pub latency: Option<Duration>,
+    /// The number of events the event represents, as set by a sampling `LogFilter`, otherwise one.
+    pub sample_rate: usize,
 }
 
 #[derive(Serialize, Deserialize, Abomonation, Debug, Clone, Hash, Eq, PartialEq, Ord, PartialOrd)]
@@ -107,6 +127,8 @@ pub struct MessagesEvent {
     pub seq_no: usize,
     /// Number of typed records in the message.
     pub length: usize,
+    /// The number of events the event represents, as set by a sampling `LogFilter`, otherwise one.
+    pub sample_rate: usize,
 }
 
 /// Records the starting and stopping of an operator.
@@ -166,6 +188,126 @@ pub struct GuardedProgressEvent {
     pub is_start: bool,
 }
 
+#[derive(Serialize, Deserialize, Abomonation, Debug, Clone, Hash, Eq, PartialEq, Ord, PartialOrd)]
+/// Rounds completed by a loop variable for one epoch of its enclosing scope.
+pub struct IterationEvent {
+    /// Worker-unique identifier for the loop's feedback operator, linkable to `OperatesEvent`.
+    pub id: usize,
+    /// The epoch of the enclosing scope, formatted as a string.
+    pub epoch: String,
+    /// The number of rounds the loop has completed for the epoch.
+    pub rounds: u64,
+}
+
+#[derive(Serialize, Deserialize, Abomonation, Debug, Clone, Hash, Eq, PartialEq, Ord, PartialOrd)]
+/// The cumulative time spent in activations of an operator, reported as it shuts down.
+///
+/// The current time of each operator is also available through `Worker::introspect`.
+pub struct OperatorTimeEvent {
+    /// Worker-unique identifier for the operator, linkable to the identifiers in `OperatesEvent`.
+    pub id: usize,
+    /// The number of times the operator was scheduled.
+    pub activations: usize,
+    /// The total time spent in activations of the operator, including operators it contains.
+    pub elapsed: Duration,
+}
+
+#[derive(Serialize, Deserialize, Abomonation, Debug, Clone, Copy, Hash, Eq, PartialEq, Ord, PartialOrd)]
+/// The resource limit of a dataflow's `Quota`.
+pub enum QuotaKind {
+    /// The fraction of time the dataflow may spend scheduled.
+    Time,
+    /// The bytes the channels of the dataflow may buffer.
+    Bytes,
+}
+
+#[derive(Serialize, Deserialize, Abomonation, Debug, Clone, Hash, Eq, PartialEq, Ord, PartialOrd)]
+/// A dataflow exceeding its quota, and so throttled for its time or failed for its bytes.
+pub struct ThrottleEvent {
+    /// Worker-unique identifier for the dataflow, linkable to the identifiers in `OperatesEvent`.
+    pub id: usize,
+    /// The limit the dataflow exceeded.
+    pub quota: QuotaKind,
+}
+
+#[derive(Serialize, Deserialize, Abomonation, Debug, Clone, Hash, Eq, PartialEq, Ord, PartialOrd)]
+/// A channel or operator exceeding its saturation threshold, as set on the worker.
+///
+/// Each is reported when it first exceeds its threshold, and again only once it has recovered.
+pub enum SaturationEvent {
+    /// More messages are queued for the worker on a channel than the threshold.
+    Channel {
+        /// Channel identifier, linkable to `ChannelsEvent`.
+        channel: usize,
+        /// The estimated number of messages queued.
+        queued: usize,
+    },
+    /// An operator yielded with input remaining in as many successive activations as the threshold.
+    Operator {
+        /// Worker-unique identifier for the operator, linkable to `OperatesEvent`.
+        id: usize,
+        /// The number of successive activations that left input.
+        activations: usize,
+    },
+}
+
+#[derive(Serialize, Deserialize, Abomonation, Debug, Clone, Copy, Hash, Eq, PartialEq, Ord, PartialOrd)]
+/// The memory attributed to an operator, reported after an activation in which it changed.
+///
+/// Operators are accounted only once memory accounting is enabled on the worker.
+pub struct MemoryEvent {
+    /// Worker-unique identifier for the operator, linkable to `OperatesEvent`.
+    pub id: usize,
+    /// The estimated bytes queued for the operator in its input channels.
+    pub queued: usize,
+    /// The estimated bytes held by the operator, in its output buffers and as it reports.
+    pub held: usize,
+}
+
+#[derive(Serialize, Deserialize, Abomonation, Debug, Clone, Copy, Hash, Eq, PartialEq, Ord, PartialOrd)]
+/// The discarding of events by a logger bound with `Registry::insert_bounded`, whose action fell behind.
+pub struct DroppedEvent {
+    /// The number of events discarded since the last `DroppedEvent`.
+    pub count: usize,
+}
+
+#[derive(Serialize, Deserialize, Abomonation, Debug, Clone, Hash, Eq, PartialEq, Ord, PartialOrd)]
+/// The failure of the connection to another process, as the worker acts on it.
+pub struct PeerFailureEvent {
+    /// The identity of the process.
+    pub process: usize,
+    /// A description of the failure.
+    pub message: String,
+    /// What the worker does in response.
+    pub policy: crate::worker::PeerFailurePolicy,
+}
+
+#[derive(Serialize, Deserialize, Abomonation, Debug, Clone, Hash, Eq, PartialEq, Ord, PartialOrd)]
+/// The removal of the workers of failed processes from those of subsequently constructed dataflows.
+pub struct RescaleEvent {
+    /// The processes whose workers were removed.
+    pub processes: Vec<usize>,
+    /// The indices of the surviving workers, among which subsequent dataflows are constructed.
+    pub workers: Vec<usize>,
+    /// The dataflows dropped, as they included the removed workers.
+    pub dropped: Vec<usize>,
+}
+
+#[derive(Serialize, Deserialize, Abomonation, Debug, Clone, Hash, Eq, PartialEq, Ord, PartialOrd)]
+/// A capability held by an operator for a timestamp its input frontiers have passed.
+///
+/// Reported by the `leak-detection` feature, once for each leaked capability.
+pub struct CapabilityLeakEvent {
+    /// Worker-unique identifier for the operator, linkable to the identifiers in `OperatesEvent`.
+    pub id: usize,
+    /// The output at which the capability is held.
+    pub output: usize,
+    /// The timestamp of the capability, formatted with `Debug`.
+    pub time: String,
+    /// How long the capability has been abandoned by the operator's inputs.
+    pub abandoned: Duration,
+}
+
 #[derive(Serialize, Deserialize, Abomonation, Debug, PartialEq, Eq, Hash, Clone, Copy)]
 /// Identifier of the worker that generated a log line
 pub struct TimelySetup {
@@ -216,6 +358,8 @@ impl ParkEvent {
 
 #[derive(Serialize, Deserialize, Debug, Clone, Abomonation, Hash, Eq, PartialEq, Ord, PartialOrd)]
 /// An event in a timely worker
+///
+/// Events serialize under the versioned schema described by the `schema` module.
 pub enum TimelyEvent {
     /// Operator creation.
     Operates(OperatesEvent),
@@ -243,6 +387,26 @@ pub enum TimelyEvent {
     Input(InputEvent),
     /// Park event.
     Park(ParkEvent),
+    /// Loop iteration progress.
+    Iteration(IterationEvent),
+    /// Operator activation time.
+    OperatorTime(OperatorTimeEvent),
+    /// Dataflow throttling.
+    Throttle(ThrottleEvent),
+    /// Channel or operator saturation.
+    Saturation(SaturationEvent),
+    /// Operator memory usage.
+    Memory(MemoryEvent),
+    /// Events discarded by a bounded logger.
+    Dropped(DroppedEvent),
+    /// Failure of the connection to another process.
+    PeerFailure(PeerFailureEvent),
+    /// Removal of the workers of failed processes.
+    Rescale(RescaleEvent),
+    /// A capability leaked by an operator.
+    CapabilityLeak(CapabilityLeakEvent),
+    /// Dataflows whose frontiers have not advanced, as reported by a worker's watchdog.
+    Stall(crate::dataflow::watchdog::StallReport),
     /// Unstructured event.
     Text(String),
 }
@@ -298,3 +462,406 @@ impl From<InputEvent> for TimelyEvent {
 impl From<ParkEvent> for TimelyEvent {
     fn from(v: ParkEvent) -> TimelyEvent { TimelyEvent::Park(v) }
 }
+
+impl From<IterationEvent> for TimelyEvent {
+    fn from(v: IterationEvent) -> TimelyEvent { TimelyEvent::Iteration(v) }
+}
+
+impl From<OperatorTimeEvent> for TimelyEvent {
+    fn from(v: OperatorTimeEvent) -> TimelyEvent { TimelyEvent::OperatorTime(v) }
+}
+
+impl From<ThrottleEvent> for TimelyEvent {
+    fn from(v: ThrottleEvent) -> TimelyEvent { TimelyEvent::Throttle(v) }
+}
+
+impl From<SaturationEvent> for TimelyEvent {
+    fn from(v: SaturationEvent) -> TimelyEvent { TimelyEvent::Saturation(v) }
+}
+
+impl From<MemoryEvent> for TimelyEvent {
+    fn from(v: MemoryEvent) -> TimelyEvent { TimelyEvent::Memory(v) }
+}
+
+impl From<DroppedEvent> for TimelyEvent {
+    fn from(v: DroppedEvent) -> TimelyEvent { TimelyEvent::Dropped(v) }
+}
+
+impl From<PeerFailureEvent> for TimelyEvent {
+    fn from(v: PeerFailureEvent) -> TimelyEvent { TimelyEvent::PeerFailure(v) }
+}
+
+impl From<RescaleEvent> for TimelyEvent {
+    fn from(v: RescaleEvent) -> TimelyEvent { TimelyEvent::Rescale(v) }
+}
+
+impl From<CapabilityLeakEvent> for TimelyEvent {
+    fn from(v: CapabilityLeakEvent) -> TimelyEvent { TimelyEvent::CapabilityLeak(v) }
+}
+
+impl From<crate::dataflow::watchdog::StallReport> for TimelyEvent {
+    fn from(v: crate::dataflow::watchdog::StallReport) -> TimelyEvent { TimelyEvent::Stall(v) }
+}
+
+impl From<crate::logging_core::Dropped> for TimelyEvent {
+    fn from(v: crate::logging_core::Dropped) -> TimelyEvent { TimelyEvent::Dropped(DroppedEvent { count: v.count }) }
+}
+
+/// The kinds of `TimelyEvent`, by which a `LogFilter` selects events.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Hash, Eq, PartialEq, Ord, PartialOrd)]
+pub enum EventKind {
+    /// `TimelyEvent::Operates`.
+    Operates,
+    /// `TimelyEvent::Channels`.
+    Channels,
+    /// `TimelyEvent::Progress`.
+    Progress,
+    /// `TimelyEvent::PushProgress`.
+    PushProgress,
+    /// `TimelyEvent::Messages`.
+    Messages,
+    /// `TimelyEvent::Schedule`.
+    Schedule,
+    /// `TimelyEvent::Shutdown`.
+    Shutdown,
+    /// `TimelyEvent::Application`.
+    Application,
+    /// `TimelyEvent::GuardedMessage`.
+    GuardedMessage,
+    /// `TimelyEvent::GuardedProgress`.
+    GuardedProgress,
+    /// `TimelyEvent::CommChannels`.
+    CommChannels,
+    /// `TimelyEvent::Input`.
+    Input,
+    /// `TimelyEvent::Park`.
+    Park,
+    /// `TimelyEvent::Iteration`.
+    Iteration,
+    /// `TimelyEvent::OperatorTime`.
+    OperatorTime,
+    /// `TimelyEvent::Throttle`.
+    Throttle,
+    /// `TimelyEvent::Saturation`.
+    Saturation,
+    /// `TimelyEvent::Memory`.
+    Memory,
+    /// `TimelyEvent::Dropped`.
+    Dropped,
+    /// `TimelyEvent::PeerFailure`.
+    PeerFailure,
+    /// `TimelyEvent::Rescale`.
+    Rescale,
+    /// `TimelyEvent::CapabilityLeak`.
+    CapabilityLeak,
+    /// `TimelyEvent::Stall`.
+    Stall,
+    /// `TimelyEvent::Text`.
+    Text,
+}
+
+impl TimelyEvent {
+    /// The kind of the event.
+    pub fn kind(&self) -> EventKind {
+        match self {
+            TimelyEvent::Operates(_) => EventKind::Operates,
+            TimelyEvent::Channels(_) => EventKind::Channels,
+            TimelyEvent::Progress(_) => EventKind::Progress,
+            TimelyEvent::PushProgress(_) => EventKind::PushProgress,
+            TimelyEvent::Messages(_) => EventKind::Messages,
+            TimelyEvent::Schedule(_) => EventKind::Schedule,
+            TimelyEvent::Shutdown(_) => EventKind::Shutdown,
+            TimelyEvent::Application(_) => EventKind::Application,
+            TimelyEvent::GuardedMessage(_) => EventKind::GuardedMessage,
+            TimelyEvent::GuardedProgress(_) => EventKind::GuardedProgress,
+            TimelyEvent::CommChannels(_) => EventKind::CommChannels,
+            TimelyEvent::Input(_) => EventKind::Input,
+            TimelyEvent::Park(_) => EventKind::Park,
+            TimelyEvent::Iteration(_) => EventKind::Iteration,
+            TimelyEvent::OperatorTime(_) => EventKind::OperatorTime,
+            TimelyEvent::Throttle(_) => EventKind::Throttle,
+            TimelyEvent::Saturation(_) => EventKind::Saturation,
+            TimelyEvent::Memory(_) => EventKind::Memory,
+            TimelyEvent::Dropped(_) => EventKind::Dropped,
+            TimelyEvent::PeerFailure(_) => EventKind::PeerFailure,
+            TimelyEvent::Rescale(_) => EventKind::Rescale,
+            TimelyEvent::CapabilityLeak(_) => EventKind::CapabilityLeak,
+            TimelyEvent::Stall(_) => EventKind::Stall,
+            TimelyEvent::Text(_) => EventKind::Text,
+        }
+    }
+}
+
+/// A selection of timely events by kind and by operator, which may change at runtime.
+///
+/// Clones of a filter share their selection, so that a filter installed with a logger can be
+/// adjusted by the application as it runs. By default all events are selected. Restricting the
+/// operators selects events concerning operators whose addresses extend one of the supplied
+/// addresses, including operators within selected scopes, and events on their channels; events
+/// not concerning operators, such as `Park` events, are not affected.
+///
+/// Message and progress events, the most numerous, can also be sampled, retaining one in each
+/// so many selected events and recording the rate in their `sample_rate` fields.
+///
+/// A filter bound with `LogFilter::register` also declares its selection as the interest of the
+/// logger, through which workers skip the construction of unselected events of frequent kinds,
+/// rather than construct and then discard them. The events from which the filter learns the
+/// addresses of operators and channels, `Operates` and `Channels`, are always constructed.
+///
+/// # Examples
+/// ```
+/// use timely::dataflow::operators::{ToStream, Inspect};
+/// use timely::logging::{LogFilter, EventKind, TimelyEvent};
+///
+/// timely::execute_from_args(::std::env::args(), |worker| {
+///
+///     // Log only scheduling events of the first dataflow.
+///     let filter = LogFilter::new();
+///     filter.set_kinds(Some(vec![EventKind::Schedule]));
+///     filter.set_operators(Some(vec![vec![0]]));
+///
+///     worker.log_register().insert_filtered::<TimelyEvent,_,_>("timely", filter.predicate(), |_time, data| {
+///         for (_, _, event) in data.drain(..) {
+///             match event {
+///                 TimelyEvent::Schedule(_) => { },
+///                 TimelyEvent::Progress(event) => assert_eq!(event.sample_rate, 10),
+///                 _ => panic!("unselected event: {:?}", event),
+///             }
+///         }
+///     });
+///
+///     worker.dataflow::<usize,_,_>(|scope| {
+///         (0 .. 10).to_stream(scope)
+///                  .inspect(|x| println!("seen: {:?}", x));
+///     });
+///
+///     // The selection can be widened while the dataflow runs, here to one in ten progress events.
+///     filter.set_kinds(Some(vec![EventKind::Schedule, EventKind::Progress]));
+///     filter.set_sample_rate(EventKind::Progress, Some(10));
+/// }).unwrap();
+/// ```
+#[derive(Clone, Default)]
+pub struct LogFilter {
+    selection: ::std::rc::Rc<::std::cell::RefCell<Selection>>,
+}
+
+#[derive(Default)]
+struct Selection {
+    kinds: Option<::std::collections::HashSet<EventKind>>,
+    operators: Option<Vec<Vec<usize>>>,
+    // For sampled kinds, the sampling rate and the number of selected events since the last retained.
+    rates: ::std::collections::HashMap<EventKind, (usize, usize)>,
+    // Addresses of operators and scopes of channels, learned from logged events.
+    addresses: ::std::collections::HashMap<usize, Vec<usize>>,
+    channels: ::std::collections::HashMap<usize, Vec<usize>>,
+}
+
+impl Selection {
+    fn selects_kind(&self, kind: EventKind) -> bool {
+        self.kinds.as_ref().map(|kinds| kinds.contains(&kind)).unwrap_or(true)
+    }
+    fn selects_addr(&self, addr: Option<&[usize]>) -> bool {
+        match (&self.operators, addr) {
+            (Some(operators), Some(addr)) => operators.iter().any(|prefix| addr.starts_with(prefix)),
+            (Some(_), None) => false,
+            (None, _) => true,
+        }
+    }
+    fn sample(&mut self, kind: EventKind) -> Option<usize> {
+        match self.rates.get_mut(&kind) {
+            Some((rate, count)) => {
+                *count += 1;
+                if *count == *rate { *count = 0; Some(*rate) } else { None }
+            },
+            None => Some(1),
+        }
+    }
+}
+
+impl LogFilter {
+    /// Allocates a filter selecting all events.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Selects only events of `kinds`, or events of all kinds if `None`.
+    pub fn set_kinds(&self, kinds: Option<Vec<EventKind>>) {
+        self.selection.borrow_mut().kinds = kinds.map(|kinds| kinds.into_iter().collect());
+    }
+
+    /// Selects only events concerning operators within `addresses`, or all operators if `None`.
+    pub fn set_operators(&self, addresses: Option<Vec<Vec<usize>>>) {
+        self.selection.borrow_mut().operators = addresses;
+    }
+
+    /// Retains one in each `rate` selected events of `kind`, or all of them if `None`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `kind` is neither `EventKind::Messages` nor `EventKind::Progress`, whose events
+    /// record their sampling rate, or if `rate` is zero.
+    pub fn set_sample_rate(&self, kind: EventKind, rate: Option<usize>) {
+        assert!(kind == EventKind::Messages || kind == EventKind::Progress, "events of kind {:?} cannot be sampled", kind);
+        let mut selection = self.selection.borrow_mut();
+        match rate {
+            Some(rate) => {
+                assert!(rate > 0, "sampling rate must be positive");
+                selection.rates.insert(kind, (rate, 0));
+            },
+            None => { selection.rates.remove(&kind); },
+        }
+    }
+
+    /// Returns `true` if the filter selects and samples `event`, recording the sampling rate in it.
+    pub fn retain(&self, event: &mut TimelyEvent) -> bool {
+        if !self.selects(event) { return false; }
+        let rate = self.selection.borrow_mut().sample(event.kind());
+        match (rate, event) {
+            (None, _) => false,
+            (Some(rate), TimelyEvent::Messages(event)) => { event.sample_rate = rate; true },
+            (Some(rate), TimelyEvent::Progress(event)) => { event.sample_rate = rate; true },
+            (Some(_), _) => true,
+        }
+    }
+
+    /// Returns `true` if the filter selects `event`, without sampling it.
+    ///
+    /// Operators are known by their addresses only once the filter has observed their `Operates`
+    /// events, which it observes whether or not it selects them.
+    pub fn selects(&self, event: &TimelyEvent) -> bool {
+        let mut selection = self.selection.borrow_mut();
+        let operator = match event {
+            TimelyEvent::Operates(event) => {
+                selection.addresses.insert(event.id, event.addr.clone());
+                return selection.selects_kind(EventKind::Operates) && selection.selects_addr(Some(&event.addr));
+            },
+            TimelyEvent::Channels(event) => {
+                selection.channels.insert(event.id, event.scope_addr.clone());
+                return selection.selects_kind(EventKind::Channels) && selection.selects_addr(Some(&event.scope_addr));
+            },
+            TimelyEvent::Progress(event) => {
+                return selection.selects_kind(EventKind::Progress) && selection.selects_addr(Some(&event.addr));
+            },
+            TimelyEvent::Messages(event) => {
+                let known = selection.selects_addr(selection.channels.get(&event.channel).map(Vec::as_slice));
+                return selection.selects_kind(EventKind::Messages) && known;
+            },
+            TimelyEvent::Shutdown(event) => {
+                let selected = selection.selects_kind(EventKind::Shutdown) && selection.selects_addr(selection.addresses.get(&event.id).map(Vec::as_slice));
+                selection.addresses.remove(&event.id);
+                return selected;
+            },
+            TimelyEvent::PushProgress(event) => Some(event.op_id),
+            TimelyEvent::Schedule(event) => Some(event.id),
+            TimelyEvent::Iteration(event) => Some(event.id),
+            TimelyEvent::OperatorTime(event) => Some(event.id),
+            TimelyEvent::Throttle(event) => Some(event.id),
+            TimelyEvent::Memory(event) => Some(event.id),
+            TimelyEvent::Saturation(SaturationEvent::Operator { id, .. }) => Some(*id),
+            TimelyEvent::Saturation(SaturationEvent::Channel { channel, .. }) => {
+                let known = selection.selects_addr(selection.channels.get(channel).map(Vec::as_slice));
+                return selection.selects_kind(EventKind::Saturation) && known;
+            },
+            _ => None,
+        };
+        selection.selects_kind(event.kind()) && operator.map(|id| selection.selects_addr(selection.addresses.get(&id).map(Vec::as_slice))).unwrap_or(true)
+    }
+
+    /// Returns `true` if the filter may select events of `kind`.
+    pub fn interested(&self, kind: EventKind) -> bool {
+        self.selection.borrow().selects_kind(kind)
+    }
+
+    /// Returns `true` if the filter may select events of `kind` concerning the operator with identifier `id`.
+    pub fn interested_in_operator(&self, kind: EventKind, id: usize) -> bool {
+        let selection = self.selection.borrow();
+        selection.selects_kind(kind) && selection.selects_addr(selection.addresses.get(&id).map(Vec::as_slice))
+    }
+
+    /// Returns `true` if the filter may select events of `kind` concerning the channel with identifier `channel`.
+    pub fn interested_in_channel(&self, kind: EventKind, channel: usize) -> bool {
+        let selection = self.selection.borrow();
+        selection.selects_kind(kind) && selection.selects_addr(selection.channels.get(&channel).map(Vec::as_slice))
+    }
+
+    /// Returns `true` if the filter may select events of `kind` concerning the scope at `addr`.
+    pub fn interested_in_scope(&self, kind: EventKind, addr: &[usize]) -> bool {
+        let selection = self.selection.borrow();
+        selection.selects_kind(kind) && selection.selects_addr(Some(addr))
+    }
+
+    /// Binds `name` in `registry` to `action`, presenting only events the filter retains.
+    ///
+    /// The filter is declared as the interest of the logger, and so events it would not select
+    /// are not constructed, as `LogInterest` describes.
+    ///
+    /// # Examples
+    /// ```
+    /// use timely::dataflow::operators::{ToStream, Exchange, Inspect};
+    /// use timely::logging::{LogFilter, EventKind, TimelyEvent};
+    ///
+    /// timely::execute_from_args(::std::env::args(), |worker| {
+    ///
+    ///     // Log only the operators and their scheduling, skipping the construction of message events.
+    ///     let filter = LogFilter::new();
+    ///     filter.set_kinds(Some(vec![EventKind::Operates, EventKind::Schedule]));
+    ///     filter.register(&mut worker.log_register(), "timely", |_time, data| {
+    ///         for (_, _, event) in data.drain(..) {
+    ///             assert!(matches!(event, TimelyEvent::Operates(_) | TimelyEvent::Schedule(_)));
+    ///         }
+    ///     });
+    ///
+    ///     worker.dataflow::<usize,_,_>(|scope| {
+    ///         (0 .. 10).to_stream(scope)
+    ///                  .exchange(|x| *x as u64)
+    ///                  .inspect(|x| println!("seen: {:?}", x));
+    ///     });
+    /// }).unwrap();
+    /// ```
+    pub fn register<F>(&self, registry: &mut crate::logging_core::Registry<WorkerIdentifier>, name: &str, action: F) -> Option<Box<dyn ::std::any::Any>>
+    where
+        F: FnMut(&Duration, &mut Vec<(Duration, WorkerIdentifier, TimelyEvent)>)+'static,
+    {
+        registry.insert_interested(name, self.clone(), self.predicate(), action)
+    }
+
+    /// A predicate retaining events as the filter does, for `Registry::insert_filtered`.
+    pub fn predicate(&self) -> impl FnMut(&mut TimelyEvent)->bool+'static {
+        let filter = self.clone();
+        move |event| filter.retain(event)
+    }
+}
+
+/// The interest of a logger of timely events, consulted before constructing events.
+///
+/// Loggers bound with `LogFilter::register` declare the filter as their interest, and are
+/// interested only in the events it may select. Other loggers are interested in all events.
+pub trait LogInterest {
+    /// Returns `true` if the logger is interested in events of `kind`.
+    fn interested(&self, kind: EventKind) -> bool;
+    /// Returns `true` if the logger is interested in events of `kind` concerning the operator with identifier `id`.
+    fn interested_in_operator(&self, kind: EventKind, id: usize) -> bool;
+    /// Returns `true` if the logger is interested in events of `kind` concerning the channel with identifier `channel`.
+    fn interested_in_channel(&self, kind: EventKind, channel: usize) -> bool;
+    /// Returns `true` if the logger is interested in events of `kind` concerning the scope at `addr`.
+    fn interested_in_scope(&self, kind: EventKind, addr: &[usize]) -> bool;
+}
+
+impl LogInterest for TimelyLogger {
+    #[inline]
+    fn interested(&self, kind: EventKind) -> bool {
+        self.interest::<LogFilter>().map(|filter| filter.interested(kind)).unwrap_or(true)
+    }
+    #[inline]
+    fn interested_in_operator(&self, kind: EventKind, id: usize) -> bool {
+        self.interest::<LogFilter>().map(|filter| filter.interested_in_operator(kind, id)).unwrap_or(true)
+    }
+    #[inline]
+    fn interested_in_channel(&self, kind: EventKind, channel: usize) -> bool {
+        self.interest::<LogFilter>().map(|filter| filter.interested_in_channel(kind, channel)).unwrap_or(true)
+    }
+    #[inline]
+    fn interested_in_scope(&self, kind: EventKind, addr: &[usize]) -> bool {
+        self.interest::<LogFilter>().map(|filter| filter.interested_in_scope(kind, addr)).unwrap_or(true)
+    }
+}
@@ -0,0 +1,183 @@
+//! A flight recorder of recent events, for post-mortem inspection.
+//!
+//! A `FlightRecorder` retains the most recent events of each worker and communication thread of
+//! a process in memory, within a window of time and up to a number of events, and writes them
+//! out on demand with `dump`, or when a thread panics once `dump_on_panic` is called. This gives
+//! a trace of what led up to a failure without the cost of continuously writing logs elsewhere.
+//!
+//! Events reach the recorder as loggers flush them, which for workers happens in each step, and
+//! for communication threads once they have buffered a batch of events. The events of the step
+//! in which a worker panics may not have reached the recorder.
+//!
+//! # Examples
+//! ```
+//! use std::time::Duration;
+//! use timely::dataflow::operators::{ToStream, Inspect};
+//! use timely::logging::recorder::FlightRecorder;
+//!
+//! // Retain up to the last five seconds, or the last thousand events, of each worker.
+//! let recorder = FlightRecorder::new(Duration::from_secs(5), 1000);
+//! recorder.dump_on_panic(None);
+//!
+//! let shared = recorder.clone();
+//! timely::execute(timely::Configuration::Process(2), move |worker| {
+//!     shared.install(worker);
+//!     worker.dataflow::<usize,_,_>(|scope| {
+//!         (0 .. 10).to_stream(scope)
+//!                  .inspect(|x| println!("seen: {:?}", x));
+//!     });
+//! }).unwrap();
+//!
+//! let mut dump = Vec::new();
+//! recorder.dump(&mut dump).unwrap();
+//! let dump = String::from_utf8(dump).unwrap();
+//! assert!(dump.contains("== worker 1 =="));
+//! assert!(dump.contains("Operates"));
+//! ```
+
+use std::collections::{BTreeMap, VecDeque};
+use std::fs::File;
+use std::io::Write;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex, MutexGuard, TryLockError};
+use crate::time::{Duration, Instant};
+
+use crate::communication::Allocate;
+use crate::communication::logging::{CommunicationEvent, CommunicationSetup};
+use crate::logging::TimelyEvent;
+use crate::logging_core::Logger;
+use crate::worker::Worker;
+
+/// Recent events of the workers and communication threads of a process, shared among their threads.
+#[derive(Clone)]
+pub struct FlightRecorder {
+    window: Duration,
+    capacity: usize,
+    rings: Arc<Mutex<Rings>>,
+}
+
+/// The thread from which events were recorded.
+#[derive(Debug, Clone, Copy, Hash, Eq, PartialEq, Ord, PartialOrd)]
+enum Source {
+    Worker(usize),
+    // The process, the remote process if any, and whether the thread sends.
+    Communication(usize, Option<usize>, bool),
+}
+
+/// The recent events of each thread.
+type Rings = BTreeMap<Source, VecDeque<Record>>;
+
+/// A recorded event.
+#[derive(Debug)]
+enum Recorded {
+    Timely(TimelyEvent),
+    Communication(CommunicationEvent),
+}
+
+/// An event, with the time elapsed since its thread started logging.
+type Record = (Duration, Recorded);
+
+impl FlightRecorder {
+    /// Allocates a recorder retaining, for each thread, the events of the last `window` of time, and at most `capacity` of them.
+    pub fn new(window: Duration, capacity: usize) -> Self {
+        FlightRecorder { window, capacity, rings: Arc::new(Mutex::new(BTreeMap::new())) }
+    }
+
+    /// Records the events of `worker`, replacing its `"timely"` logger.
+    pub fn install<A: Allocate>(&self, worker: &mut Worker<A>) {
+        let recorder = self.clone();
+        let source = Source::Worker(worker.index());
+        worker.log_register().insert::<TimelyEvent,_>("timely", move |_time, data| {
+            recorder.record(source, data.drain(..).map(|(elapsed, _worker, event)| (elapsed, Recorded::Timely(event))));
+        });
+    }
+
+    /// A function constructing loggers for communication threads, recording their events.
+    ///
+    /// The function is suitable as the `log_fn` of `Configuration::Cluster`.
+    pub fn communication_logger(&self) -> impl Fn(CommunicationSetup)->Option<Logger<CommunicationEvent, CommunicationSetup>>+Send+Sync {
+        let recorder = self.clone();
+        move |setup| {
+            let recorder = recorder.clone();
+            let source = Source::Communication(setup.process, setup.remote, setup.sender);
+            Some(Logger::new(Instant::now(), setup, move |_time, data| {
+                recorder.record(source, data.drain(..).map(|(elapsed, _setup, event)| (elapsed, Recorded::Communication(event))));
+            }))
+        }
+    }
+
+    /// Writes the retained events to `writer`, grouped by thread and oldest first.
+    pub fn dump<W: Write>(&self, writer: &mut W) -> ::std::io::Result<()> {
+        write_rings(&self.lock(), writer)
+    }
+
+    /// Dumps the retained events when any thread panics, to the file at `path` or to standard error if `None`.
+    ///
+    /// The dump follows the output of the previously installed panic hook. Each panic rewrites
+    /// the file, so that it holds the events leading up to the most recent panic. If the events
+    /// are locked, as they are should the panic occur while recording, the dump is replaced by a
+    /// note saying so. A dump that cannot be written is abandoned.
+    pub fn dump_on_panic(&self, path: Option<PathBuf>) {
+        let recorder = self.clone();
+        let previous = ::std::panic::take_hook();
+        ::std::panic::set_hook(Box::new(move |info| {
+            previous(info);
+            let dump = |mut writer: &mut dyn Write| match recorder.rings.try_lock() {
+                Ok(rings) => write_rings(&rings, &mut writer),
+                Err(TryLockError::Poisoned(poisoned)) => write_rings(&poisoned.into_inner(), &mut writer),
+                Err(TryLockError::WouldBlock) => writeln!(writer, "flight recorder busy; dump skipped"),
+            };
+            let _ = match &path {
+                Some(path) => File::create(path).and_then(|mut file| dump(&mut file)),
+                None => dump(&mut ::std::io::stderr()),
+            };
+        }));
+    }
+
+    /// Appends events from `source`, and discards those of it outside the window or beyond the capacity.
+    fn record<I: Iterator<Item=Record>>(&self, source: Source, events: I) {
+        let mut rings = self.lock();
+        let ring = rings.entry(source).or_default();
+        for (elapsed, event) in events {
+            if ring.len() == self.capacity {
+                ring.pop_front();
+            }
+            if self.capacity > 0 {
+                ring.push_back((elapsed, event));
+            }
+        }
+        if let Some(&(latest, _)) = ring.back() {
+            while ring.front().map(|(elapsed, _)| latest.saturating_sub(*elapsed) > self.window).unwrap_or(false) {
+                ring.pop_front();
+            }
+        }
+    }
+
+    /// Locks the recorded events, even if a thread panicked while holding the lock.
+    fn lock(&self) -> MutexGuard<'_, Rings> {
+        self.rings.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+/// Writes the events of `rings` to `writer`, grouped by thread and oldest first.
+fn write_rings<W: Write>(rings: &Rings, writer: &mut W) -> ::std::io::Result<()> {
+    for (source, ring) in rings.iter() {
+        match source {
+            Source::Worker(index) => writeln!(writer, "== worker {} ==", index)?,
+            Source::Communication(process, remote, sender) => {
+                let direction = if *sender { "send" } else { "recv" };
+                match remote {
+                    Some(remote) => writeln!(writer, "== process {} {} {} ==", process, direction, remote)?,
+                    None => writeln!(writer, "== process {} {} ==", process, direction)?,
+                }
+            },
+        }
+        for (elapsed, event) in ring.iter() {
+            match event {
+                Recorded::Timely(event) => writeln!(writer, "{:?}\t{:?}", elapsed, event)?,
+                Recorded::Communication(event) => writeln!(writer, "{:?}\t{:?}", elapsed, event)?,
+            }
+        }
+    }
+    writer.flush()
+}
@@ -0,0 +1,98 @@
+//! A versioned schema for serialized logging events.
+//!
+//! The events of workers, `TimelyEvent`, and of communication threads, `CommunicationEvent`,
+//! along with the types they contain, implement serde's `Serialize` and `Deserialize`, so that
+//! they can be written to and read from the log streams of external consumers, for example as
+//! JSON or with bincode. A `Record` wraps each event with the version of the schema, `VERSION`,
+//! the time elapsed since its thread started logging, and the thread's setup, which for workers
+//! is the worker index and for communication threads is a `CommunicationSetup`.
+//!
+//! Events serialize with serde's default representation: structs as maps of their fields,
+//! enums externally tagged by the names of their variants, `Option`s as a value or null, and
+//! `Duration`s as their `secs` and `nanos`. For example, the start of an operator's activation
+//! is written as JSON as
+//!
+//! ```text
+//! {"version":6,"elapsed":{"secs":0,"nanos":5000},"setup":0,"event":{"Schedule":{"id":3,"start_stop":"Start"}}}
+//! ```
+//!
+//! The names, order, and types of the fields of each event, and the names and order of the
+//! variants of each enum, as documented on the types themselves, are the schema. Any change to
+//! them, including the addition of an event, changes `VERSION`, so that consumers can reject
+//! or separately interpret records they do not understand. Records with the same version are
+//! written alike by every release of the crate.
+//!
+//! # Examples
+//! ```
+//! use std::time::Duration;
+//! use timely::logging::{TimelyEvent, ScheduleEvent};
+//! use timely::logging::schema::{TimelyRecord, VERSION};
+//!
+//! let record = TimelyRecord::from((Duration::from_micros(5), 0, TimelyEvent::from(ScheduleEvent::start(3))));
+//! let json = serde_json::to_string(&record).unwrap();
+//! assert_eq!(json, r#"{"version":6,"elapsed":{"secs":0,"nanos":5000},"setup":0,"event":{"Schedule":{"id":3,"start_stop":"Start"}}}"#);
+//!
+//! let bytes = bincode::serialize(&record).unwrap();
+//! let decoded: TimelyRecord = bincode::deserialize(&bytes).unwrap();
+//! assert_eq!(decoded.version, VERSION);
+//! assert_eq!(decoded, record);
+//! ```
+//!
+//! Serialized records may be written by a logger, here as lines of JSON.
+//! ```
+//! use timely::logging::TimelyEvent;
+//! use timely::logging::schema::TimelyRecord;
+//!
+//! timely::execute_from_args(::std::env::args(), |worker| {
+//!     worker.log_register().insert::<TimelyEvent,_>("timely", |_time, data| {
+//!         for record in data.drain(..).map(TimelyRecord::from) {
+//!             println!("{}", serde_json::to_string(&record).unwrap());
+//!         }
+//!     });
+//! }).unwrap();
+//! ```
+
+use std::time::Duration;
+
+use crate::communication::logging::{CommunicationEvent, CommunicationSetup};
+use crate::logging::{TimelyEvent, WorkerIdentifier};
+
+/// The version of the schema of serialized events, which changes with any change to their form.
+pub const VERSION: u32 = 6;
+
+/// A logged event, with the version of its schema and the context in which it was logged.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct Record<S, E> {
+    /// The version of the schema with which the record was written.
+    pub version: u32,
+    /// The time elapsed since the logging thread started logging.
+    pub elapsed: Duration,
+    /// The setup of the logging thread.
+    pub setup: S,
+    /// The logged event.
+    pub event: E,
+}
+
+/// A record of an event of a worker.
+pub type TimelyRecord = Record<WorkerIdentifier, TimelyEvent>;
+/// A record of an event of a communication thread.
+pub type CommunicationRecord = Record<CommunicationSetup, CommunicationEvent>;
+
+impl<S, E> Record<S, E> {
+    /// Wraps `event`, logged by the thread with `setup` at `elapsed`, in a record of the current version.
+    pub fn new(elapsed: Duration, setup: S, event: E) -> Self {
+        Record { version: VERSION, elapsed, setup, event }
+    }
+
+    /// Indicates whether the record was written with the current version of the schema.
+    pub fn is_current(&self) -> bool {
+        self.version == VERSION
+    }
+}
+
+/// Wraps the elements of logged batches, which are the elapsed time, setup, and event.
+impl<S, E> From<(Duration, S, E)> for Record<S, E> {
+    fn from((elapsed, setup, event): (Duration, S, E)) -> Self {
+        Record::new(elapsed, setup, event)
+    }
+}
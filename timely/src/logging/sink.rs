@@ -0,0 +1,603 @@
+//! Resilient sinks of logged events, streamed to a TCP listener or written to files.
+//!
+//! A `TcpSink` writes events as an `EventWriter` would, but tolerates a listener that is not yet
+//! up, or that goes away: it buffers events while disconnected, periodically retries the
+//! connection, and discards events once its buffer is full, according to its `DropPolicy`.
+//! The number of discarded messages, and the most recent failure to connect or to write, are
+//! reported through a `SinkStatus`, which remains readable after the sink is dropped, and which
+//! counts the messages still buffered at that point.
+//! Each connection receives a stream of its own, beginning with a header, and once reconnected
+//! with the progress of the events sent on earlier connections, so that a listener replaying
+//! the stream observes the same frontier as the sink.
+//!
+//! Only messages are discarded. Progress updates are always retained, as a replayed stream
+//! without them could not advance, and so are buffered beyond the sink's capacity if need be.
+//! Events written shortly before a listener goes away may be lost without the sink noticing,
+//! as they are accepted by the operating system before the connection is known to be closed.
+//!
+//! A `FileSink` writes events to a sequence of local files, starting a new file once the current
+//! one reaches a size or an age, and optionally compressing and eventually deleting old files.
+//! Like the connections of a `TcpSink`, each file holds a stream of its own, which begins with
+//! the progress of the files before it, and so can be replayed without them. Failures to write,
+//! to rotate, or to compress files are reported through its `SinkStatus`, as are the messages it
+//! discards having failed to write them.
+//!
+//! When `execute` finds the `TIMELY_WORKER_LOG_ADDR` or `TIMELY_COMM_LOG_ADDR` environment
+//! variables, it streams the events of workers or communication threads to them through sinks
+//! configured by `SinkConfig::from_env`. Otherwise, when it finds `TIMELY_WORKER_LOG_PATH` or
+//! `TIMELY_COMM_LOG_PATH`, it writes the events to files prefixed by their values, through sinks
+//! configured by `FileConfig::from_env`.
+//!
+//! # Examples
+//! ```
+//! use std::net::TcpListener;
+//! use timely::dataflow::operators::capture::{Event, EventPusher, EventReader};
+//! use timely::dataflow::operators::capture::event::EventIterator;
+//! use timely::logging::sink::{TcpSink, SinkConfig};
+//!
+//! let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+//! let addr = listener.local_addr().unwrap().to_string();
+//!
+//! let mut sink = TcpSink::<u64, String>::new(addr, SinkConfig::default());
+//! sink.push(Event::Messages(0, vec!["hello".to_string()]));
+//! drop(sink);
+//!
+//! let (stream, _) = listener.accept().unwrap();
+//! let mut reader = EventReader::<u64, String, _>::new(stream);
+//! let mut event = reader.next();
+//! while event.is_none() { event = reader.next(); }
+//! assert_eq!(event, Some(&Event::Messages(0, vec!["hello".to_string()])));
+//! ```
+
+use std::collections::VecDeque;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::net::{TcpStream, ToSocketAddrs};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use crate::time::{Duration, Instant};
+
+use abomonation::Abomonation;
+
+use crate::config::ConfigError;
+use crate::dataflow::operators::capture::{Event, EventPusher};
+use crate::dataflow::operators::capture::event::binary::Header;
+use crate::progress::ChangeBatch;
+
+/// Which events a full sink discards.
+#[derive(Debug, Clone, Copy, Hash, Eq, PartialEq)]
+pub enum DropPolicy {
+    /// Discard the oldest buffered messages, to make room for new events.
+    Oldest,
+    /// Discard new messages, retaining those already buffered.
+    Newest,
+}
+
+/// The state of a sink, shared with the handles returned by its `status` method.
+///
+/// # Examples
+/// ```
+/// use std::net::TcpListener;
+/// use timely::dataflow::operators::capture::{Event, EventPusher};
+/// use timely::logging::sink::{TcpSink, SinkConfig};
+///
+/// // An address at which nothing listens.
+/// let addr = TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap().to_string();
+///
+/// let config = SinkConfig { capacity: 0, ..Default::default() };
+/// let mut sink = TcpSink::<u64, String>::new(addr, config);
+/// let status = sink.status();
+/// sink.push(Event::Messages(0, vec!["hello".to_string()]));
+/// drop(sink);
+///
+/// assert_eq!(status.dropped(), 1);
+/// assert!(status.error().is_some());
+/// ```
+#[derive(Debug, Default)]
+pub struct SinkStatus {
+    dropped: AtomicUsize,
+    error: Mutex<Option<String>>,
+}
+
+impl SinkStatus {
+    /// The number of messages the sink has discarded.
+    pub fn dropped(&self) -> usize {
+        self.dropped.load(Ordering::Relaxed)
+    }
+
+    /// A description of the most recent failure to connect, to write, or to compress, if any.
+    pub fn error(&self) -> Option<String> {
+        self.error.lock().expect("sink status poisoned").clone()
+    }
+
+    /// Records the discarding of `count` messages.
+    fn discard(&self, count: usize) {
+        self.dropped.fetch_add(count, Ordering::Relaxed);
+    }
+
+    /// Records a failure, replacing any earlier failure.
+    fn fail(&self, error: String) {
+        *self.error.lock().expect("sink status poisoned") = Some(error);
+    }
+}
+
+/// The configuration of a `TcpSink`.
+#[derive(Debug, Clone)]
+pub struct SinkConfig {
+    /// The least time between attempts to connect.
+    pub retry: Duration,
+    /// The time after which an attempt to connect, or to write, fails.
+    pub timeout: Duration,
+    /// The number of bytes of encoded messages buffered while disconnected.
+    pub capacity: usize,
+    /// Which messages to discard once the buffer is full.
+    pub policy: DropPolicy,
+}
+
+impl Default for SinkConfig {
+    fn default() -> Self {
+        SinkConfig {
+            retry: Duration::from_secs(1),
+            timeout: Duration::from_secs(1),
+            capacity: 1 << 26,
+            policy: DropPolicy::Oldest,
+        }
+    }
+}
+
+impl SinkConfig {
+    /// The default configuration, amended by environment variables.
+    ///
+    /// The variables are `TIMELY_LOG_RETRY_MS` and `TIMELY_LOG_TIMEOUT_MS`, in milliseconds,
+    /// `TIMELY_LOG_BUFFER_BYTES`, and `TIMELY_LOG_DROP`, which is either `oldest` or `newest`.
+    /// Variables that are not set leave the default in place, while a variable that cannot be
+    /// parsed is an error.
+    ///
+    /// # Examples
+    /// ```
+    /// use timely::logging::sink::SinkConfig;
+    ///
+    /// std::env::set_var("TIMELY_LOG_DROP", "sideways");
+    /// assert!(SinkConfig::from_env().is_err());
+    /// ```
+    pub fn from_env() -> Result<Self, ConfigError> {
+        let mut config = SinkConfig::default();
+        if let Some(millis) = parse_env::<u64>("TIMELY_LOG_RETRY_MS")? {
+            config.retry = Duration::from_millis(millis);
+        }
+        if let Some(millis) = parse_env::<u64>("TIMELY_LOG_TIMEOUT_MS")? {
+            config.timeout = Duration::from_millis(millis);
+        }
+        if let Some(bytes) = parse_env::<usize>("TIMELY_LOG_BUFFER_BYTES")? {
+            config.capacity = bytes;
+        }
+        if let Ok(policy) = ::std::env::var("TIMELY_LOG_DROP") {
+            config.policy = match &policy[..] {
+                "oldest" => DropPolicy::Oldest,
+                "newest" => DropPolicy::Newest,
+                _ => return Err(ConfigError::Env { variable: "TIMELY_LOG_DROP".to_string(), value: policy }),
+            };
+        }
+        Ok(config)
+    }
+}
+
+/// Parses the environment variable `name`, if it is set.
+fn parse_env<V: ::std::str::FromStr>(name: &str) -> Result<Option<V>, ConfigError> {
+    match ::std::env::var(name) {
+        Ok(value) => value.parse().map(Some).map_err(|_| ConfigError::Env { variable: name.to_string(), value }),
+        Err(_) => Ok(None),
+    }
+}
+
+/// Begins a stream with a header, and with the progress of `written` if any.
+fn begin_stream<T: Abomonation+Ord+Clone, D: Abomonation, W: Write>(writer: &mut W, written: &mut ChangeBatch<T>) -> ::std::io::Result<()> {
+    Header::new::<T, D>().write_to(writer)?;
+    if !written.is_empty() {
+        let progress = Event::<T, D>::Progress(written.iter().cloned().collect());
+        unsafe { ::abomonation::encode(&progress, writer)?; }
+    }
+    Ok(())
+}
+
+/// An encoded event, with its progress updates if any.
+struct Frame<T> {
+    progress: Option<Vec<(T, i64)>>,
+    bytes: Vec<u8>,
+}
+
+/// Streams events to a TCP listener, reconnecting and buffering as needed.
+pub struct TcpSink<T: Abomonation+Ord+Clone, D: Abomonation> {
+    addr: String,
+    config: SinkConfig,
+    stream: Option<TcpStream>,
+    // The time of the last attempt to connect, if any.
+    attempted: Option<Instant>,
+    frames: VecDeque<Frame<T>>,
+    // The bytes of buffered messages, excluding progress updates.
+    buffered: usize,
+    // The progress updates written to any connection.
+    written: ChangeBatch<T>,
+    status: Arc<SinkStatus>,
+    phantom: ::std::marker::PhantomData<D>,
+}
+
+impl<T: Abomonation+Ord+Clone, D: Abomonation> TcpSink<T, D> {
+    /// Allocates a sink streaming to `addr`, connecting with the first event.
+    pub fn new<S: Into<String>>(addr: S, config: SinkConfig) -> Self {
+        TcpSink {
+            addr: addr.into(),
+            config,
+            stream: None,
+            attempted: None,
+            frames: VecDeque::new(),
+            buffered: 0,
+            written: ChangeBatch::new(),
+            status: Arc::new(SinkStatus::default()),
+            phantom: ::std::marker::PhantomData,
+        }
+    }
+
+    /// Indicates whether the sink is connected to its listener.
+    pub fn connected(&self) -> bool {
+        self.stream.is_some()
+    }
+
+    /// The number of messages the sink has discarded.
+    pub fn dropped(&self) -> usize {
+        self.status.dropped()
+    }
+
+    /// A handle to the state of the sink, which remains readable once the sink is dropped.
+    pub fn status(&self) -> Arc<SinkStatus> {
+        self.status.clone()
+    }
+
+    /// Discards messages until those buffered fit the capacity, leaving progress updates in place.
+    fn trim(&mut self) {
+        while self.buffered > self.config.capacity {
+            let position = match self.config.policy {
+                DropPolicy::Oldest => self.frames.iter().position(|frame| frame.progress.is_none()),
+                DropPolicy::Newest => self.frames.iter().rposition(|frame| frame.progress.is_none()),
+            };
+            let frame = self.frames.remove(position.expect("messages buffered")).expect("position in bounds");
+            self.buffered -= frame.bytes.len();
+            self.status.discard(1);
+        }
+    }
+
+    /// Connects if disconnected and due to retry, and writes buffered frames until done or failed.
+    fn flush(&mut self, force: bool) {
+        if self.stream.is_none() {
+            let due = self.attempted.map(|attempted| attempted.elapsed() >= self.config.retry).unwrap_or(true);
+            if force || due {
+                self.attempted = Some(Instant::now());
+                match self.connect() {
+                    Ok(stream) => { self.stream = Some(stream); },
+                    Err(error) => { self.status.fail(format!("failed to connect to {}: {}", self.addr, error)); },
+                }
+            }
+        }
+        while let Some(stream) = self.stream.as_mut() {
+            let frame = match self.frames.front() {
+                Some(frame) => frame,
+                None => break,
+            };
+            match stream.write_all(&frame.bytes[..]) {
+                Ok(()) => {
+                    let frame = self.frames.pop_front().expect("frame present");
+                    match frame.progress {
+                        Some(progress) => { self.written.extend(progress.into_iter()); },
+                        None => { self.buffered -= frame.bytes.len(); },
+                    }
+                },
+                Err(error) => {
+                    // The frame is written again, in full, to the next connection.
+                    self.status.fail(format!("failed to write to {}: {}", self.addr, error));
+                    self.stream = None;
+                },
+            }
+        }
+    }
+
+    /// Connects to the listener, and begins its stream with a header and the progress written so far.
+    fn connect(&mut self) -> ::std::io::Result<TcpStream> {
+        let mut error = ::std::io::Error::new(::std::io::ErrorKind::NotFound, "no address for log sink");
+        for addr in self.addr.to_socket_addrs()? {
+            match TcpStream::connect_timeout(&addr, self.config.timeout) {
+                Ok(mut stream) => {
+                    stream.set_write_timeout(Some(self.config.timeout))?;
+                    begin_stream::<T, D, _>(&mut stream, &mut self.written)?;
+                    return Ok(stream);
+                },
+                Err(e) => { error = e; },
+            }
+        }
+        Err(error)
+    }
+}
+
+impl<T: Abomonation+Ord+Clone, D: Abomonation> EventPusher<T, D> for TcpSink<T, D> {
+    fn push(&mut self, event: Event<T, D>) {
+        let mut bytes = Vec::new();
+        unsafe { ::abomonation::encode(&event, &mut bytes).expect("Event abomonation failed"); }
+        let progress = match event {
+            Event::Progress(updates) => Some(updates),
+            Event::Messages(_, _) => None,
+        };
+        if progress.is_none() {
+            self.buffered += bytes.len();
+        }
+        self.frames.push_back(Frame { progress, bytes });
+        self.flush(false);
+        self.trim();
+    }
+}
+
+impl<T: Abomonation+Ord+Clone, D: Abomonation> Drop for TcpSink<T, D> {
+    fn drop(&mut self) {
+        // Make a final attempt to deliver buffered events.
+        if !self.frames.is_empty() {
+            self.flush(true);
+        }
+        self.status.discard(self.frames.iter().filter(|frame| frame.progress.is_none()).count());
+    }
+}
+
+/// The configuration of a `FileSink`.
+#[derive(Debug, Clone, Default)]
+pub struct FileConfig {
+    /// The size in bytes at which a file is closed and the next begun, if any.
+    pub max_bytes: Option<usize>,
+    /// The age at which a file is closed and the next begun, if any.
+    pub max_age: Option<Duration>,
+    /// The number of closed files to retain, deleting older files, or all if `None`.
+    pub keep: Option<usize>,
+    /// Compresses closed files with gzip, as they are closed, before older files are deleted.
+    ///
+    /// # Examples
+    /// ```
+    /// use timely::dataflow::operators::capture::{Event, EventPusher};
+    /// use timely::logging::sink::{FileSink, FileConfig};
+    ///
+    /// let prefix = std::env::temp_dir().join(format!("timely-gzip-{}", std::process::id()));
+    /// let config = FileConfig { max_bytes: Some(1), keep: Some(1), compress: true, ..Default::default() };
+    /// let mut sink = FileSink::<u64, String>::new(&prefix, config);
+    /// for word in ["hello", "to", "you"] {
+    ///     sink.push(Event::Messages(0, vec![word.to_string()]));
+    /// }
+    /// drop(sink);
+    ///
+    /// // Only the last file is kept, and only in its compressed form.
+    /// assert!(!std::path::Path::new(&format!("{}.000001.gz", prefix.display())).exists());
+    /// assert!(!std::path::Path::new(&format!("{}.000002", prefix.display())).exists());
+    /// std::fs::remove_file(format!("{}.000002.gz", prefix.display())).unwrap();
+    /// ```
+    #[cfg(feature = "gzip")]
+    pub compress: bool,
+}
+
+impl FileConfig {
+    /// The default configuration, amended by environment variables.
+    ///
+    /// The variables are `TIMELY_LOG_FILE_BYTES`, `TIMELY_LOG_FILE_SECS`, `TIMELY_LOG_FILE_KEEP`,
+    /// and, with the `gzip` feature, `TIMELY_LOG_FILE_GZIP`, which compresses closed files if set
+    /// to `1` or `true`.
+    /// Variables that are not set leave the default in place, which writes a single file and
+    /// never compresses it, while a variable that cannot be parsed is an error.
+    pub fn from_env() -> Result<Self, ConfigError> {
+        let mut config = FileConfig::default();
+        if let Some(bytes) = parse_env::<usize>("TIMELY_LOG_FILE_BYTES")? {
+            config.max_bytes = Some(bytes);
+        }
+        if let Some(secs) = parse_env::<u64>("TIMELY_LOG_FILE_SECS")? {
+            config.max_age = Some(Duration::from_secs(secs));
+        }
+        if let Some(keep) = parse_env::<usize>("TIMELY_LOG_FILE_KEEP")? {
+            config.keep = Some(keep);
+        }
+        #[cfg(feature = "gzip")]
+        if let Ok(compress) = ::std::env::var("TIMELY_LOG_FILE_GZIP") {
+            config.compress = compress == "1" || compress == "true";
+        }
+        Ok(config)
+    }
+}
+
+/// Writes events to rotating files, named by a prefix and a sequence number.
+///
+/// The files are named `{prefix}.{sequence}`, with the sequence number padded to six digits,
+/// and begin at sequence zero; compressed files have a further `.gz` extension. Events are
+/// buffered, and written out with each progress update, which for loggers is once each batch.
+///
+/// # Examples
+/// ```
+/// use timely::dataflow::operators::capture::{Event, EventPusher, EventReader};
+/// use timely::dataflow::operators::capture::event::EventIterator;
+/// use timely::logging::sink::{FileSink, FileConfig};
+///
+/// let prefix = std::env::temp_dir().join(format!("timely-sink-{}", std::process::id()));
+/// let config = FileConfig { max_bytes: Some(1), ..Default::default() };
+/// let mut sink = FileSink::<u64, String>::new(&prefix, config);
+/// sink.push(Event::Messages(0, vec!["hello".to_string()]));
+/// sink.push(Event::Messages(0, vec!["world".to_string()]));
+/// drop(sink);
+///
+/// // Each file exceeds one byte, and so each event has a file of its own.
+/// let second = std::fs::File::open(format!("{}.000001", prefix.display())).unwrap();
+/// let mut reader = EventReader::<u64, String, _>::new(second);
+/// let mut event = reader.next();
+/// while event.is_none() { event = reader.next(); }
+/// assert_eq!(event, Some(&Event::Messages(0, vec!["world".to_string()])));
+/// # std::fs::remove_file(format!("{}.000000", prefix.display())).unwrap();
+/// # std::fs::remove_file(format!("{}.000001", prefix.display())).unwrap();
+/// ```
+pub struct FileSink<T: Abomonation+Ord+Clone, D: Abomonation> {
+    prefix: PathBuf,
+    config: FileConfig,
+    // The current file, its path, the bytes written to it, and when it was begun.
+    file: Option<(BufWriter<File>, PathBuf, usize, Instant)>,
+    // The sequence number of the next file.
+    sequence: usize,
+    // The paths of closed files, oldest first.
+    closed: VecDeque<PathBuf>,
+    // The progress updates written to any file.
+    written: ChangeBatch<T>,
+    status: Arc<SinkStatus>,
+    phantom: ::std::marker::PhantomData<D>,
+}
+
+impl<T: Abomonation+Ord+Clone, D: Abomonation> FileSink<T, D> {
+    /// Allocates a sink writing to files named by `prefix`, beginning the first with the first event.
+    pub fn new<P: AsRef<Path>>(prefix: P, config: FileConfig) -> Self {
+        FileSink {
+            prefix: prefix.as_ref().to_owned(),
+            config,
+            file: None,
+            sequence: 0,
+            closed: VecDeque::new(),
+            written: ChangeBatch::new(),
+            status: Arc::new(SinkStatus::default()),
+            phantom: ::std::marker::PhantomData,
+        }
+    }
+
+    /// The number of messages the sink has discarded, having failed to write them.
+    pub fn dropped(&self) -> usize {
+        self.status.dropped()
+    }
+
+    /// A handle to the state of the sink, which remains readable once the sink is dropped.
+    ///
+    /// # Examples
+    /// ```
+    /// use timely::dataflow::operators::capture::{Event, EventPusher};
+    /// use timely::logging::sink::{FileSink, FileConfig};
+    ///
+    /// // A prefix in a directory that does not exist.
+    /// let prefix = std::env::temp_dir().join(format!("timely-missing-{}", std::process::id())).join("log");
+    /// let mut sink = FileSink::<u64, String>::new(&prefix, FileConfig::default());
+    /// let status = sink.status();
+    /// sink.push(Event::Messages(0, vec!["hello".to_string()]));
+    /// drop(sink);
+    ///
+    /// assert_eq!(status.dropped(), 1);
+    /// assert!(status.error().is_some());
+    /// ```
+    pub fn status(&self) -> Arc<SinkStatus> {
+        self.status.clone()
+    }
+
+    /// Closes the current file, if it has reached its size or age.
+    fn rotate(&mut self) {
+        let full = self.file.as_ref().map(|(_, _, bytes, begun)| {
+            self.config.max_bytes.map(|max| *bytes >= max).unwrap_or(false) ||
+            self.config.max_age.map(|max| begun.elapsed() >= max).unwrap_or(false)
+        });
+        if full == Some(true) {
+            self.close();
+        }
+    }
+
+    /// Closes the current file, compressing it and deleting old files as configured.
+    fn close(&mut self) {
+        if let Some((mut writer, path, _, _)) = self.file.take() {
+            if let Err(error) = writer.flush() {
+                self.status.fail(format!("failed to write log file {}: {}", path.display(), error));
+            }
+            drop(writer);
+            #[cfg(feature = "gzip")]
+            let path = if self.config.compress { compress(path, &self.status) } else { path };
+            self.closed.push_back(path);
+            if let Some(keep) = self.config.keep {
+                while self.closed.len() > keep {
+                    let path = self.closed.pop_front().expect("closed file present");
+                    let _ = ::std::fs::remove_file(&path);
+                }
+            }
+        }
+    }
+
+    /// Begins the next file, with a header and the progress written to earlier files.
+    fn open(&mut self) -> ::std::io::Result<()> {
+        let path = PathBuf::from(format!("{}.{:06}", self.prefix.display(), self.sequence));
+        self.sequence += 1;
+        let mut writer = BufWriter::new(File::create(&path)?);
+        begin_stream::<T, D, _>(&mut writer, &mut self.written)?;
+        self.file = Some((writer, path, 0, Instant::now()));
+        Ok(())
+    }
+
+    /// Writes `bytes` to the current file, beginning one if needed, and flushes if `flush`.
+    fn write(&mut self, bytes: &[u8], flush: bool) -> ::std::io::Result<()> {
+        if self.file.is_none() {
+            self.open()?;
+        }
+        let (writer, _, written, _) = self.file.as_mut().expect("file present");
+        writer.write_all(bytes)?;
+        *written += bytes.len();
+        if flush {
+            writer.flush()?;
+        }
+        Ok(())
+    }
+}
+
+impl<T: Abomonation+Ord+Clone, D: Abomonation> EventPusher<T, D> for FileSink<T, D> {
+    fn push(&mut self, event: Event<T, D>) {
+        self.rotate();
+        let mut bytes = Vec::new();
+        unsafe { ::abomonation::encode(&event, &mut bytes).expect("Event abomonation failed"); }
+        let progress = match event {
+            Event::Progress(updates) => Some(updates),
+            Event::Messages(_, _) => None,
+        };
+        if let Err(error) = self.write(&bytes[..], progress.is_some()) {
+            self.status.fail(format!("failed to write log file for {}: {}", self.prefix.display(), error));
+            // Begin a new file with the next event, rather than continue a damaged stream.
+            self.file = None;
+            if progress.is_none() {
+                self.status.discard(1);
+            }
+        }
+        // Progress updates are counted as written even if they failed, as files are begun with
+        // them, and the next file will carry them.
+        if let Some(progress) = progress {
+            self.written.extend(progress.into_iter());
+        }
+    }
+}
+
+impl<T: Abomonation+Ord+Clone, D: Abomonation> Drop for FileSink<T, D> {
+    fn drop(&mut self) {
+        self.close();
+    }
+}
+
+/// Compresses the file at `path`, replacing it with a `.gz` file, and returns the path of the file that remains.
+///
+/// If compression fails, the failure is recorded in `status`, the compressed file is removed,
+/// and the uncompressed file retained.
+#[cfg(feature = "gzip")]
+fn compress(path: PathBuf, status: &SinkStatus) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(".gz");
+    let gzipped = PathBuf::from(name);
+    let result = File::open(&path).and_then(|mut file| {
+        let mut encoder = ::flate2::write::GzEncoder::new(BufWriter::new(File::create(&gzipped)?), ::flate2::Compression::default());
+        ::std::io::copy(&mut file, &mut encoder)?;
+        encoder.finish()?.flush()
+    });
+    match result {
+        Ok(()) => {
+            let _ = ::std::fs::remove_file(&path);
+            gzipped
+        },
+        Err(error) => {
+            status.fail(format!("failed to compress log file {}: {}", path.display(), error));
+            let _ = ::std::fs::remove_file(&gzipped);
+            path
+        },
+    }
+}
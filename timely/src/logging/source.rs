@@ -0,0 +1,230 @@
+//! Sources of logged events, for dataflows of the same process.
+//!
+//! `log_source` binds a logger of a worker, by default that of its `"timely"` events, to a
+//! source operator of one of its dataflows, without capturing the events and replaying them
+//! over a network connection. The stream has timestamps of the time elapsed since the worker
+//! started logging, and presents each event at the time at which it was logged. The source
+//! holds a capability for the time through which the logger has reported its events, and so
+//! the frontier of the stream, and of anything computed from it, reveals when the events up
+//! to a time are complete. Progress events are among the `"timely"` events, as
+//! `TimelyEvent::Progress`, and may be selected from the stream.
+//!
+//! The events of communication threads are logged by loggers the threads construct, and a
+//! `CommunicationFeed` provides such loggers, through its `log_fn`, and sources of their events.
+//!
+//! A source reads its events once in each `period`. It runs for as long as its `LogSourceHandle`
+//! is retained, as its dataflow cannot complete while its logger may log further events, and
+//! dropping the handle completes the stream.
+//!
+//! # Examples
+//! ```
+//! use std::time::Duration;
+//! use timely::dataflow::ProbeHandle;
+//! use timely::dataflow::operators::{ToStream, Filter, Inspect, Probe};
+//! use timely::logging::TimelyEvent;
+//! use timely::logging::source::log_source;
+//!
+//! timely::execute_from_args(::std::env::args(), |worker| {
+//!     let mut probe = ProbeHandle::new();
+//!     let handle = worker.dataflow::<Duration,_,_>(|scope| {
+//!         let (handle, events) = log_source::<_, TimelyEvent>(scope, "timely", Duration::from_millis(10));
+//!         events.filter(|(_, _, event)| matches!(event, TimelyEvent::Operates(_)))
+//!               .inspect(|(elapsed, worker, event)| println!("{:?}\tworker {}:\t{:?}", elapsed, worker, event))
+//!               .probe_with(&mut probe);
+//!         handle
+//!     });
+//!
+//!     worker.dataflow::<u64,_,_>(|scope| {
+//!         (0 .. 10).to_stream(scope);
+//!     });
+//!
+//!     // Await the events logged so far, and then complete the stream.
+//!     let now = worker.timer().elapsed();
+//!     worker.step_while(|| probe.less_than(&now));
+//!     drop(handle);
+//! }).unwrap();
+//! ```
+
+use std::rc::{Rc, Weak};
+use std::cell::RefCell;
+use std::sync::{Arc, Mutex};
+use crate::time::{Duration, Instant};
+
+use crate::Data;
+use crate::communication::logging::{CommunicationEvent, CommunicationSetup};
+use crate::dataflow::{Scope, Stream};
+use crate::dataflow::operators::generic::operator::source;
+use crate::logging::WorkerIdentifier;
+use crate::logging_core::Logger;
+
+/// Retains a source of logged events, whose stream completes once the handle is dropped.
+pub struct LogSourceHandle {
+    _alive: Rc<()>,
+}
+
+/// Events logged and not yet read by a source, and the time through which they are complete.
+struct Feed<E> {
+    events: Vec<E>,
+    through: Duration,
+}
+
+/// Binds the logger `name` of the worker hosting `scope` to a source of its events, read once each `period`.
+///
+/// The source replaces the binding of `name`, and so presents the events of loggers obtained
+/// once it is constructed, which include those of dataflows constructed later.
+pub fn log_source<S, T>(scope: &mut S, name: &str, period: Duration) -> (LogSourceHandle, Stream<S, (Duration, WorkerIdentifier, T)>)
+where
+    S: Scope<Timestamp=Duration>,
+    T: Data,
+{
+    let feed = Rc::new(RefCell::new(Feed::<(Duration, WorkerIdentifier, T)> { events: Vec::new(), through: Duration::default() }));
+    let alive = Rc::new(());
+
+    let feed_source = feed.clone();
+    let alive_source = Rc::downgrade(&alive);
+    let stream = source(scope, &format!("LogSource({})", name), |capability, info| {
+        let activator = scope.activator_for(&info.address[..]);
+        let mut capability = Some(capability);
+        let mut events = Vec::new();
+        let mut batch = Vec::new();
+        move |output| {
+            if alive_source.upgrade().is_none() {
+                capability = None;
+            }
+            if let Some(capability) = capability.as_mut() {
+                // The feed is released before sending, which may log further events.
+                let through = {
+                    let mut feed = feed_source.borrow_mut();
+                    ::std::mem::swap(&mut events, &mut feed.events);
+                    feed.through
+                };
+                // Events are presented at the times they were logged, in runs of equal times.
+                let mut drain = events.drain(..).peekable();
+                while let Some(event) = drain.next() {
+                    let time = event.0;
+                    batch.push(event);
+                    while drain.peek().map(|event| event.0 == time) == Some(true) {
+                        batch.extend(drain.next());
+                    }
+                    output.session(&capability.delayed(&time)).give_vec(&mut batch);
+                }
+                drop(drain);
+                if through > *capability.time() {
+                    capability.downgrade(&through);
+                }
+                activator.activate_after(period);
+            }
+        }
+    });
+
+    let feed: Weak<RefCell<Feed<_>>> = Rc::downgrade(&feed);
+    let alive_logger = Rc::downgrade(&alive);
+    scope.log_register().insert::<T,_>(name, move |time, data| {
+        match (feed.upgrade(), alive_logger.upgrade()) {
+            (Some(feed), Some(_)) => {
+                let mut feed = feed.borrow_mut();
+                // Events logged at times the source has passed are presented at its time.
+                let through = feed.through;
+                feed.events.extend(data.drain(..).map(|(elapsed, id, event)| (::std::cmp::max(elapsed, through), id, event)));
+                feed.through = ::std::cmp::max(*time, through);
+            },
+            _ => data.clear(),
+        }
+    });
+
+    (LogSourceHandle { _alive: alive }, stream)
+}
+
+/// Loggers for communication threads, and sources of the events they log, shared among threads.
+///
+/// Communication threads log on clocks of their own, which are not aligned with those of workers,
+/// and report their progress only as they fill batches of events. Their events are instead
+/// presented at the time, since the feed was allocated, at which a source reads them, and their
+/// times are also measured since the feed was allocated. Each event is read by one source, and
+/// so with a source in each worker, each worker presents some of the events.
+///
+/// # Examples
+/// ```
+/// use std::time::Duration;
+/// use timely::dataflow::operators::Inspect;
+/// use timely::logging::source::CommunicationFeed;
+///
+/// let feed = CommunicationFeed::new();
+/// let config = timely::Configuration::Cluster {
+///     threads: 1,
+///     process: 0,
+///     addresses: vec!["localhost:0".to_string()],
+///     report: false,
+///     log_fn: Box::new(feed.log_fn()),
+/// };
+///
+/// timely::execute(config, move |worker| {
+///     let handle = worker.dataflow::<Duration,_,_>(|scope| {
+///         let (handle, events) = feed.source(scope, Duration::from_millis(10));
+///         events.inspect(|(elapsed, setup, event)| println!("{:?}\t{:?}:\t{:?}", elapsed, setup, event));
+///         handle
+///     });
+///     worker.step();
+///     drop(handle);
+/// }).unwrap();
+/// ```
+#[derive(Clone)]
+pub struct CommunicationFeed {
+    start: Instant,
+    events: Arc<Mutex<Vec<(Duration, CommunicationSetup, CommunicationEvent)>>>,
+}
+
+impl Default for CommunicationFeed {
+    fn default() -> Self {
+        CommunicationFeed { start: Instant::now(), events: Default::default() }
+    }
+}
+
+impl CommunicationFeed {
+    /// Allocates a feed with no logged events.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// A function constructing loggers for communication threads, whose events are read by the sources of the feed.
+    ///
+    /// The function is suitable as the `log_fn` of `Configuration::Cluster`.
+    pub fn log_fn(&self) -> impl Fn(CommunicationSetup)->Option<Logger<CommunicationEvent, CommunicationSetup>>+Send+Sync {
+        let feed = self.clone();
+        move |setup| {
+            let events = feed.events.clone();
+            Some(Logger::new(feed.start, setup, move |_time, data| {
+                events.lock().expect("feed poisoned").append(data);
+            }))
+        }
+    }
+
+    /// A source of the events of the feed, read once each `period`, in a dataflow of a worker.
+    pub fn source<S>(&self, scope: &mut S, period: Duration) -> (LogSourceHandle, Stream<S, (Duration, CommunicationSetup, CommunicationEvent)>)
+    where
+        S: Scope<Timestamp=Duration>,
+    {
+        let alive = Rc::new(());
+        let alive_source = Rc::downgrade(&alive);
+        let feed = self.clone();
+        let stream = source(scope, "LogSource(communication)", |capability, info| {
+            let activator = scope.activator_for(&info.address[..]);
+            let mut capability = Some(capability);
+            let mut batch = Vec::new();
+            move |output| {
+                if alive_source.upgrade().is_none() {
+                    capability = None;
+                }
+                if let Some(capability) = capability.as_mut() {
+                    ::std::mem::swap(&mut batch, &mut *feed.events.lock().expect("feed poisoned"));
+                    if !batch.is_empty() {
+                        output.session(capability).give_vec(&mut batch);
+                    }
+                    capability.downgrade(&feed.start.elapsed());
+                    activator.activate_after(period);
+                }
+            }
+        });
+        (LogSourceHandle { _alive: alive }, stream)
+    }
+}
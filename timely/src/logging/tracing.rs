@@ -0,0 +1,163 @@
+//! Forwarding of logged events to the `tracing` ecosystem.
+//!
+//! With the `tracing` feature, the events of workers and of communication threads can be
+//! presented to `tracing` subscribers as structured events, rather than to bespoke loggers.
+//! Each kind of event has its own target, such as `timely::schedule` or `timely::messages`, so
+//! that subscribers can filter them individually, and carries the fields of the event along
+//! with the `worker` and the `elapsed_ns` since the worker started. Events of each worker are
+//! emitted within a `timely_worker` span for the worker.
+//!
+//! Frequent events, of scheduling, messages, and progress, are emitted at the `TRACE` level;
+//! the construction and shutdown of operators and channels, and their memory, at `DEBUG`; throttled dataflows
+//! and unstructured text at `INFO`; saturated channels, operators, and queues at `WARN`; and failed connections to other processes at `ERROR`. Operator activations are reported as they stop, as events
+//! of the `timely::schedule` target with their `duration_ns`.
+//!
+//! # Examples
+//! ```
+//! use timely::dataflow::operators::{ToStream, Inspect};
+//!
+//! timely::execute_from_args(::std::env::args(), |worker| {
+//!     // Events reach whichever subscriber is installed for the thread.
+//!     timely::logging::tracing::forward(worker);
+//!     worker.dataflow::<usize,_,_>(|scope| {
+//!         (0 .. 10).to_stream(scope)
+//!                  .inspect(|x| println!("seen: {:?}", x));
+//!     });
+//! }).unwrap();
+//! ```
+
+use std::collections::HashMap;
+use crate::time::{Duration, Instant};
+
+use crate::communication::Allocate;
+use crate::communication::logging::{CommunicationEvent, CommunicationSetup};
+use crate::logging::{TimelyEvent, StartStop, ParkEvent, SaturationEvent};
+use crate::logging_core::Logger;
+use crate::worker::Worker;
+
+/// Forwards the events of `worker` to `tracing`, replacing its `"timely"` logger.
+pub fn forward<A: Allocate>(worker: &mut Worker<A>) {
+    let index = worker.index();
+    let span = ::tracing::debug_span!("timely_worker", worker = index);
+    let mut starts = HashMap::new();
+    worker.log_register().insert::<TimelyEvent,_>("timely", move |_time, data| {
+        let _entered = span.enter();
+        for (elapsed, worker, event) in data.drain(..) {
+            let elapsed_ns = elapsed.as_nanos() as u64;
+            match event {
+                TimelyEvent::Operates(event) => {
+                    ::tracing::debug!(target: "timely::operates", worker, elapsed_ns, id = event.id, addr = ?event.addr, name = %event.name, names = ?event.names);
+                },
+                TimelyEvent::Channels(event) => {
+                    ::tracing::debug!(target: "timely::channels", worker, elapsed_ns, id = event.id, scope_addr = ?event.scope_addr, source = ?event.source, target = ?event.target);
+                },
+                TimelyEvent::Progress(event) => {
+                    let latency_ns = event.latency.map(|latency| latency.as_nanos() as u64);
+                    ::tracing::trace!(target: "timely::progress", worker, elapsed_ns, is_send = event.is_send, source = event.source, channel = event.channel, seq_no = event.seq_no, addr = ?event.addr, updates = event.updates, bytes = event.bytes, latency_ns, sample_rate = event.sample_rate);
+                },
+                TimelyEvent::PushProgress(event) => {
+                    ::tracing::trace!(target: "timely::push_progress", worker, elapsed_ns, op_id = event.op_id);
+                },
+                TimelyEvent::Messages(event) => {
+                    ::tracing::trace!(target: "timely::messages", worker, elapsed_ns, is_send = event.is_send, channel = event.channel, source = event.source, target = event.target, seq_no = event.seq_no, length = event.length, sample_rate = event.sample_rate);
+                },
+                TimelyEvent::Schedule(event) => match event.start_stop {
+                    StartStop::Start => { starts.insert(event.id, elapsed); },
+                    StartStop::Stop => {
+                        let duration_ns = starts.remove(&event.id).map(|start| elapsed.saturating_sub(start).as_nanos() as u64);
+                        ::tracing::trace!(target: "timely::schedule", worker, elapsed_ns, id = event.id, duration_ns);
+                    },
+                },
+                TimelyEvent::Shutdown(event) => {
+                    ::tracing::debug!(target: "timely::shutdown", worker, elapsed_ns, id = event.id);
+                },
+                TimelyEvent::Application(event) => {
+                    ::tracing::trace!(target: "timely::application", worker, elapsed_ns, id = event.id, is_start = event.is_start);
+                },
+                TimelyEvent::GuardedMessage(event) => {
+                    ::tracing::trace!(target: "timely::guarded_message", worker, elapsed_ns, is_start = event.is_start);
+                },
+                TimelyEvent::GuardedProgress(event) => {
+                    ::tracing::trace!(target: "timely::guarded_progress", worker, elapsed_ns, is_start = event.is_start);
+                },
+                TimelyEvent::CommChannels(event) => {
+                    ::tracing::debug!(target: "timely::comm_channels", worker, elapsed_ns, identifier = event.identifier, kind = ?event.kind);
+                },
+                TimelyEvent::Input(event) => {
+                    ::tracing::trace!(target: "timely::input", worker, elapsed_ns, start_stop = ?event.start_stop);
+                },
+                TimelyEvent::Park(ParkEvent::Park(duration)) => {
+                    let duration_ns = duration.map(|duration: Duration| duration.as_nanos() as u64);
+                    ::tracing::trace!(target: "timely::park", worker, elapsed_ns, park = true, duration_ns);
+                },
+                TimelyEvent::Park(ParkEvent::Unpark) => {
+                    ::tracing::trace!(target: "timely::park", worker, elapsed_ns, park = false);
+                },
+                TimelyEvent::Iteration(event) => {
+                    ::tracing::debug!(target: "timely::iteration", worker, elapsed_ns, id = event.id, epoch = %event.epoch, rounds = event.rounds);
+                },
+                TimelyEvent::OperatorTime(event) => {
+                    ::tracing::debug!(target: "timely::operator_time", worker, elapsed_ns, id = event.id, activations = event.activations, elapsed_total_ns = event.elapsed.as_nanos() as u64);
+                },
+                TimelyEvent::Throttle(event) => {
+                    ::tracing::info!(target: "timely::throttle", worker, elapsed_ns, id = event.id, quota = ?event.quota);
+                },
+                TimelyEvent::Saturation(SaturationEvent::Channel { channel, queued }) => {
+                    ::tracing::warn!(target: "timely::saturation", worker, elapsed_ns, channel, queued);
+                },
+                TimelyEvent::Saturation(SaturationEvent::Operator { id, activations }) => {
+                    ::tracing::warn!(target: "timely::saturation", worker, elapsed_ns, id, activations);
+                },
+                TimelyEvent::Memory(event) => {
+                    ::tracing::debug!(target: "timely::memory", worker, elapsed_ns, id = event.id, queued = event.queued, held = event.held);
+                },
+                TimelyEvent::Dropped(event) => {
+                    ::tracing::warn!(target: "timely::dropped", worker, elapsed_ns, count = event.count);
+                },
+                TimelyEvent::PeerFailure(event) => {
+                    ::tracing::error!(target: "timely::peer_failure", worker, elapsed_ns, process = event.process, policy = ?event.policy, "{}", event.message);
+                },
+                TimelyEvent::Rescale(event) => {
+                    ::tracing::warn!(target: "timely::rescale", worker, elapsed_ns, processes = ?event.processes, workers = ?event.workers, dropped = ?event.dropped);
+                },
+                TimelyEvent::CapabilityLeak(event) => {
+                    ::tracing::warn!(target: "timely::capability_leak", worker, elapsed_ns, id = event.id, output = event.output, time = %event.time, abandoned_ns = event.abandoned.as_nanos() as u64);
+                },
+                TimelyEvent::Stall(report) => {
+                    ::tracing::warn!(target: "timely::stall", worker, elapsed_ns, stalled_ns = report.stalled.as_nanos() as u64, "{}", report);
+                },
+                TimelyEvent::Text(text) => {
+                    ::tracing::info!(target: "timely::text", worker, elapsed_ns, "{}", text);
+                },
+            }
+        }
+    });
+}
+
+/// A function constructing loggers for communication threads, forwarding their events to `tracing`.
+///
+/// The function is suitable as the `log_fn` of `Configuration::Cluster`. Messages are emitted at
+/// the `TRACE` level with the target `timely::communication`, the starting and stopping of
+/// threads at `DEBUG`, and saturated queues at `WARN`.
+pub fn communication_logger() -> impl Fn(CommunicationSetup)->Option<Logger<CommunicationEvent, CommunicationSetup>>+Send+Sync {
+    |setup| {
+        Some(Logger::new(Instant::now(), setup, |_time, data| {
+            for (elapsed, setup, event) in data.drain(..) {
+                let elapsed_ns = elapsed.as_nanos() as u64;
+                let (process, remote) = (setup.process, setup.remote);
+                match event {
+                    CommunicationEvent::Message(message) => {
+                        let header = message.header;
+                        ::tracing::trace!(target: "timely::communication", process, remote, elapsed_ns, is_send = message.is_send, channel = header.channel, source = header.source, target = header.target, length = header.length, seqno = header.seqno);
+                    },
+                    CommunicationEvent::State(state) => {
+                        ::tracing::debug!(target: "timely::communication", process, remote, elapsed_ns, send = state.send, start = state.start);
+                    },
+                    CommunicationEvent::Queue(queue) => {
+                        ::tracing::warn!(target: "timely::communication", process, remote, elapsed_ns, send = queue.send, worker = queue.worker, bytes = queue.bytes);
+                    },
+                }
+            }
+        }))
+    }
+}
@@ -0,0 +1,341 @@
+//! Aggregation of logged events into Prometheus metrics.
+//!
+//! A `Metrics` aggregates the events of the workers and communication threads of a process, and
+//! renders them in the Prometheus text exposition format, which `serve` presents on an HTTP
+//! endpoint. The metrics are
+//!
+//! * `timely_channel_messages_total` and `timely_channel_records_total`, the messages and records
+//!   sent and received by each worker on each dataflow channel,
+//! * `timely_network_messages_total` and `timely_network_bytes_total`, the messages and bytes
+//!   sent to and received from each other process on each channel,
+//! * `timely_operator_activations_total` and `timely_operator_schedule_seconds_total`, the
+//!   activations of each operator at each worker and the time spent in them,
+//! * `timely_saturation_total` and `timely_network_saturation_total`, the times channels and
+//!   operators at each worker, and the queues of network threads, were reported saturated,
+//! * `timely_operator_memory_bytes`, the bytes queued for and held by each running operator at
+//!   each worker, once memory accounting is enabled with `Worker::set_memory_accounting`, and
+//! * `timely_probe_lag_seconds`, the lag of the frontier of each tracked probe.
+//!
+//! Metrics are updated as loggers flush their events, which for workers happens in each step,
+//! and for communication threads once they have buffered a batch of events.
+//!
+//! When `execute` finds the `TIMELY_METRICS_ADDR` environment variable, it serves a `Metrics`
+//! for each process at that address, and installs it in each worker and communication thread.
+//! The address it serves them at, which reveals the port chosen for addresses with port zero, is
+//! then available from `served_at`, and with the `tracing` feature is reported as an event.
+//!
+//! # Examples
+//! ```
+//! use std::time::Duration;
+//! use timely::dataflow::InputHandle;
+//! use timely::dataflow::operators::{Input, Exchange, Probe};
+//! use timely::metrics::Metrics;
+//!
+//! let metrics = Metrics::new();
+//! let shared = metrics.clone();
+//!
+//! timely::execute(timely::Configuration::Process(2), move |worker| {
+//!     let tracker = shared.install(worker);
+//!
+//!     let mut input = InputHandle::new();
+//!     let probe = worker.dataflow::<u64,_,_>(|scope| {
+//!         scope.input_from(&mut input)
+//!              .exchange(|x: &u64| *x)
+//!              .probe()
+//!     });
+//!     // Timestamps are milliseconds of processing time.
+//!     tracker.track_probe("output", &probe, |time| Duration::from_millis(*time));
+//!
+//!     for round in 0 .. 10 {
+//!         input.send(round);
+//!         input.advance_to(round + 1);
+//!         worker.step_while(|| probe.less_than(input.time()));
+//!     }
+//! }).unwrap();
+//!
+//! let text = metrics.render();
+//! assert!(text.contains("# TYPE timely_channel_messages_total counter"));
+//! assert!(text.contains("timely_probe_lag_seconds{worker=\"1\",probe=\"output\"}"));
+//! ```
+
+use std::rc::Rc;
+use std::cell::RefCell;
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::Write as _;
+use std::io::{Read, Write};
+use std::net::{SocketAddr, TcpListener, ToSocketAddrs};
+use std::sync::{Arc, Mutex};
+use crate::time::{Duration, Instant};
+
+use crate::communication::Allocate;
+use crate::communication::logging::{CommunicationEvent, CommunicationSetup};
+use crate::dataflow::ProbeHandle;
+use crate::logging::{TimelyEvent, StartStop, SaturationEvent};
+use crate::logging_core::Logger;
+use crate::progress::Timestamp;
+use crate::worker::Worker;
+
+/// Metrics aggregated from the events of the workers of a process, shared among their threads.
+#[derive(Clone, Default)]
+pub struct Metrics {
+    state: Arc<Mutex<State>>,
+}
+
+/// Counters and gauges, keyed by their label values.
+#[derive(Default)]
+struct State {
+    // (worker, channel, is_send) -> (messages, records)
+    channels: BTreeMap<(usize, usize, bool), (u64, u64)>,
+    // (process, remote, channel, is_send) -> (messages, bytes)
+    network: BTreeMap<(usize, usize, usize, bool), (u64, u64)>,
+    // (worker, operator) -> (name, activations, elapsed)
+    operators: BTreeMap<(usize, usize), (String, u64, Duration)>,
+    // (worker, probe) -> lag
+    probes: BTreeMap<(usize, String), Duration>,
+    // (worker, kind, channel or operator) -> reports
+    saturation: BTreeMap<(usize, &'static str, usize), u64>,
+    // (process, remote, is_send) -> reports
+    network_saturation: BTreeMap<(usize, usize, bool), u64>,
+    // (worker, operator) -> (queued, held)
+    memory: BTreeMap<(usize, usize), (usize, usize)>,
+}
+
+// The address at which `execute` serves the metrics of the process.
+static SERVED: Mutex<Option<SocketAddr>> = Mutex::new(None);
+
+/// The address at which `execute` serves the metrics of the process, if it does.
+///
+/// The address is set once `execute` has bound it, before any worker starts, and so can be read
+/// from workers as well as once `execute` returns.
+///
+/// # Examples
+/// ```
+/// timely::execute_from_args(::std::env::args(), |worker| {
+///     if let (0, Some(address)) = (worker.index(), timely::metrics::served_at()) {
+///         println!("metrics at http://{}/metrics", address);
+///     }
+/// }).unwrap();
+/// ```
+pub fn served_at() -> Option<SocketAddr> {
+    *SERVED.lock().expect("metrics address poisoned")
+}
+
+/// Records that `execute` serves the metrics of the process at `address`.
+pub(crate) fn set_served_at(address: SocketAddr) {
+    *SERVED.lock().expect("metrics address poisoned") = Some(address);
+    #[cfg(feature = "tracing")]
+    ::tracing::info!(target: "timely::metrics", %address, "serving metrics");
+}
+
+/// A probe whose lag is reported, with its timestamps mapped to processing time.
+type TrackedProbe = (String, Box<dyn Fn(Duration)->Duration>);
+
+impl Metrics {
+    /// Allocates metrics with no recorded events.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Aggregates the events of `worker`, replacing its `"timely"` logger.
+    ///
+    /// The returned tracker reports the lag of probes of the worker, and should be retained for
+    /// as long as those probes are to be reported.
+    pub fn install<A: Allocate>(&self, worker: &mut Worker<A>) -> ProbeTracker {
+        let index = worker.index();
+        let state = self.state.clone();
+        let probes = Rc::new(RefCell::new(Vec::<TrackedProbe>::new()));
+        let tracked = Rc::downgrade(&probes);
+        let mut starts = HashMap::new();
+        worker.log_register().insert::<TimelyEvent,_>("timely", move |time, data| {
+            let mut state = state.lock().expect("metrics poisoned");
+            for (elapsed, _worker, event) in data.drain(..) {
+                match event {
+                    TimelyEvent::Operates(event) => {
+                        state.operators.entry((index, event.id)).or_default().0 = event.name;
+                    },
+                    TimelyEvent::Messages(event) => {
+                        let counts = state.channels.entry((index, event.channel, event.is_send)).or_insert((0, 0));
+                        // Sampled events stand for as many events as their sampling rate.
+                        counts.0 += event.sample_rate as u64;
+                        counts.1 += (event.length * event.sample_rate) as u64;
+                    },
+                    TimelyEvent::Schedule(event) => match event.start_stop {
+                        StartStop::Start => { starts.insert(event.id, elapsed); },
+                        StartStop::Stop => {
+                            if let Some(start) = starts.remove(&event.id) {
+                                let operator = state.operators.entry((index, event.id)).or_default();
+                                operator.1 += 1;
+                                operator.2 += elapsed.saturating_sub(start);
+                            }
+                        },
+                    },
+                    TimelyEvent::Saturation(SaturationEvent::Channel { channel, .. }) => {
+                        *state.saturation.entry((index, "channel", channel)).or_default() += 1;
+                    },
+                    TimelyEvent::Saturation(SaturationEvent::Operator { id, .. }) => {
+                        *state.saturation.entry((index, "operator", id)).or_default() += 1;
+                    },
+                    TimelyEvent::Memory(event) => {
+                        state.memory.insert((index, event.id), (event.queued, event.held));
+                    },
+                    TimelyEvent::Shutdown(event) => {
+                        state.memory.remove(&(index, event.id));
+                    },
+                    _ => { },
+                }
+            }
+            if let Some(probes) = tracked.upgrade() {
+                for (name, lag) in probes.borrow().iter() {
+                    state.probes.insert((index, name.clone()), lag(*time));
+                }
+            }
+        });
+        ProbeTracker { probes }
+    }
+
+    /// A function constructing loggers for communication threads, aggregating their events.
+    ///
+    /// The function is suitable as the `log_fn` of `Configuration::Cluster`.
+    pub fn communication_logger(&self) -> impl Fn(CommunicationSetup)->Option<Logger<CommunicationEvent, CommunicationSetup>>+Send+Sync {
+        let state = self.state.clone();
+        move |setup| {
+            let state = state.clone();
+            Some(Logger::new(Instant::now(), setup, move |_time, data| {
+                let mut state = state.lock().expect("metrics poisoned");
+                for (_elapsed, setup, event) in data.drain(..) {
+                    match event {
+                        CommunicationEvent::Message(message) => {
+                            let remote = setup.remote.unwrap_or(setup.process);
+                            let key = (setup.process, remote, message.header.channel, message.is_send);
+                            let counts = state.network.entry(key).or_insert((0, 0));
+                            counts.0 += 1;
+                            counts.1 += message.header.length as u64;
+                        },
+                        CommunicationEvent::Queue(queue) => {
+                            *state.network_saturation.entry((queue.process, queue.remote, queue.send)).or_default() += 1;
+                        },
+                        CommunicationEvent::State(_) => { },
+                    }
+                }
+            }))
+        }
+    }
+
+    /// Renders the metrics in the Prometheus text exposition format.
+    pub fn render(&self) -> String {
+        let state = self.state.lock().expect("metrics poisoned");
+        let mut text = String::new();
+
+        header(&mut text, "timely_channel_messages_total", "counter", "Messages on dataflow channels, by worker.");
+        for ((worker, channel, is_send), (messages, _)) in state.channels.iter() {
+            writeln!(text, "timely_channel_messages_total{{worker=\"{}\",channel=\"{}\",direction=\"{}\"}} {}", worker, channel, direction(*is_send), messages).unwrap();
+        }
+        header(&mut text, "timely_channel_records_total", "counter", "Records on dataflow channels, by worker.");
+        for ((worker, channel, is_send), (_, records)) in state.channels.iter() {
+            writeln!(text, "timely_channel_records_total{{worker=\"{}\",channel=\"{}\",direction=\"{}\"}} {}", worker, channel, direction(*is_send), records).unwrap();
+        }
+        header(&mut text, "timely_network_messages_total", "counter", "Messages to and from other processes.");
+        for ((process, remote, channel, is_send), (messages, _)) in state.network.iter() {
+            writeln!(text, "timely_network_messages_total{{process=\"{}\",remote=\"{}\",channel=\"{}\",direction=\"{}\"}} {}", process, remote, channel, direction(*is_send), messages).unwrap();
+        }
+        header(&mut text, "timely_network_bytes_total", "counter", "Bytes to and from other processes.");
+        for ((process, remote, channel, is_send), (_, bytes)) in state.network.iter() {
+            writeln!(text, "timely_network_bytes_total{{process=\"{}\",remote=\"{}\",channel=\"{}\",direction=\"{}\"}} {}", process, remote, channel, direction(*is_send), bytes).unwrap();
+        }
+        header(&mut text, "timely_operator_activations_total", "counter", "Activations of operators.");
+        for ((worker, id), (name, activations, _)) in state.operators.iter() {
+            writeln!(text, "timely_operator_activations_total{{worker=\"{}\",operator=\"{}\",name=\"{}\"}} {}", worker, id, escape(name), activations).unwrap();
+        }
+        header(&mut text, "timely_operator_schedule_seconds_total", "counter", "Time spent in activations of operators, including operators they contain.");
+        for ((worker, id), (name, _, elapsed)) in state.operators.iter() {
+            writeln!(text, "timely_operator_schedule_seconds_total{{worker=\"{}\",operator=\"{}\",name=\"{}\"}} {}", worker, id, escape(name), elapsed.as_secs_f64()).unwrap();
+        }
+        header(&mut text, "timely_saturation_total", "counter", "Reports of saturated channels and operators.");
+        for ((worker, kind, id), reports) in state.saturation.iter() {
+            writeln!(text, "timely_saturation_total{{worker=\"{}\",kind=\"{}\",id=\"{}\"}} {}", worker, kind, id, reports).unwrap();
+        }
+        header(&mut text, "timely_network_saturation_total", "counter", "Reports of saturated queues of network threads.");
+        for ((process, remote, is_send), reports) in state.network_saturation.iter() {
+            writeln!(text, "timely_network_saturation_total{{process=\"{}\",remote=\"{}\",direction=\"{}\"}} {}", process, remote, direction(*is_send), reports).unwrap();
+        }
+        header(&mut text, "timely_operator_memory_bytes", "gauge", "Estimated bytes queued for and held by operators.");
+        for ((worker, id), (queued, held)) in state.memory.iter() {
+            let name = state.operators.get(&(*worker, *id)).map(|operator| escape(&operator.0)).unwrap_or_default();
+            writeln!(text, "timely_operator_memory_bytes{{worker=\"{}\",operator=\"{}\",name=\"{}\",kind=\"queued\"}} {}", worker, id, name, queued).unwrap();
+            writeln!(text, "timely_operator_memory_bytes{{worker=\"{}\",operator=\"{}\",name=\"{}\",kind=\"held\"}} {}", worker, id, name, held).unwrap();
+        }
+        header(&mut text, "timely_probe_lag_seconds", "gauge", "Processing time by which the frontiers of probes trail the worker.");
+        for ((worker, name), lag) in state.probes.iter() {
+            writeln!(text, "timely_probe_lag_seconds{{worker=\"{}\",probe=\"{}\"}} {}", worker, escape(name), lag.as_secs_f64()).unwrap();
+        }
+        text
+    }
+
+    /// Serves the rendered metrics over HTTP at `addr`, from a background thread.
+    ///
+    /// Requests for any path receive the metrics. The method returns the address at which
+    /// the metrics are served, which reveals the port chosen for addresses with port zero.
+    pub fn serve<S: ToSocketAddrs>(&self, addr: S) -> ::std::io::Result<SocketAddr> {
+        let listener = TcpListener::bind(addr)?;
+        let local = listener.local_addr()?;
+        let metrics = self.clone();
+        ::std::thread::Builder::new()
+            .name("metrics server".to_string())
+            .spawn(move || {
+                for mut stream in listener.incoming().flatten() {
+                    // The request is not interpreted, beyond awaiting its arrival.
+                    let mut request = [0u8; 1024];
+                    let _ = stream.read(&mut request);
+                    let body = metrics.render();
+                    let _ = write!(
+                        stream,
+                        "HTTP/1.1 200 OK\r\nContent-Type: text/plain; version=0.0.4\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                        body.len(),
+                        body,
+                    );
+                }
+            })?;
+        Ok(local)
+    }
+}
+
+/// Reports the lag of probes at a worker, as returned by `Metrics::install`.
+pub struct ProbeTracker {
+    probes: Rc<RefCell<Vec<TrackedProbe>>>,
+}
+
+impl ProbeTracker {
+    /// Reports the lag of `probe` as `timely_probe_lag_seconds`, labeled with `name`.
+    ///
+    /// The function `to_elapsed` maps timestamps to the processing time since the start of the
+    /// worker they correspond to, for example for timestamps that count milliseconds. The lag
+    /// is the processing time by which the least timestamp of the frontier trails the worker,
+    /// and is zero once the frontier is empty. The lag is updated as the worker logs events.
+    pub fn track_probe<T, F>(&self, name: &str, probe: &ProbeHandle<T>, to_elapsed: F)
+    where
+        T: Timestamp,
+        F: Fn(&T)->Duration+'static,
+    {
+        let probe = probe.clone();
+        let lag = move |elapsed: Duration| {
+            probe.with_frontier(|frontier| {
+                frontier.iter().map(|time| elapsed.saturating_sub(to_elapsed(time))).max().unwrap_or_default()
+            })
+        };
+        self.probes.borrow_mut().push((name.to_owned(), Box::new(lag)));
+    }
+}
+
+fn header(text: &mut String, name: &str, kind: &str, help: &str) {
+    writeln!(text, "# HELP {} {}", name, help).unwrap();
+    writeln!(text, "# TYPE {} {}", name, kind).unwrap();
+}
+
+fn direction(is_send: bool) -> &'static str {
+    if is_send { "send" } else { "recv" }
+}
+
+/// Escapes quotes, backslashes, and newlines, for use within label values.
+fn escape(text: &str) -> String {
+    text.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n")
+}
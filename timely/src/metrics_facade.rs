@@ -0,0 +1,180 @@
+//! Reporting of logged events through the `metrics` facade.
+//!
+//! With the `metrics-facade` feature, `install` aggregates the events of a worker into counters,
+//! gauges, and histograms of the `metrics` crate, so that they reach whatever recorder the
+//! embedding application has installed, as an alternative to the Prometheus endpoint of the
+//! `metrics` feature. The metrics, labeled by `worker` and by channel or operator, are
+//!
+//! * `timely_channel_messages_total` and `timely_channel_records_total`, counters of the
+//!   messages and records sent and received on each dataflow channel, with a `direction`,
+//! * `timely_network_messages_total` and `timely_network_bytes_total`, counters of the messages
+//!   and bytes each process sends to and receives from each other process, reported by the
+//!   loggers of `communication_logger` and labeled by `process` and `remote`,
+//! * `timely_operator_activations_total`, a counter of the activations of each operator, and
+//!   `timely_operator_schedule_seconds`, a histogram of the time spent in each activation, and
+//! * `timely_probe_lag_seconds`, a gauge of the lag of the frontier of each tracked probe.
+//!
+//! Metrics are updated as loggers flush their events, which for workers happens in each step.
+//!
+//! # Examples
+//! ```
+//! use std::time::Duration;
+//! use timely::dataflow::InputHandle;
+//! use timely::dataflow::operators::{Input, Exchange, Probe};
+//!
+//! timely::execute(timely::Configuration::Process(2), |worker| {
+//!     let tracker = timely::metrics_facade::install(worker);
+//!
+//!     let mut input = InputHandle::new();
+//!     let probe = worker.dataflow::<u64,_,_>(|scope| {
+//!         scope.input_from(&mut input)
+//!              .exchange(|x: &u64| *x)
+//!              .probe()
+//!     });
+//!     // Timestamps are milliseconds of processing time.
+//!     tracker.track_probe("output", &probe, |time| Duration::from_millis(*time));
+//!
+//!     for round in 0 .. 10 {
+//!         input.send(round);
+//!         input.advance_to(round + 1);
+//!         worker.step_while(|| probe.less_than(input.time()));
+//!     }
+//! }).unwrap();
+//! ```
+
+use std::rc::Rc;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use crate::time::{Duration, Instant};
+
+use metrics_facade::{counter, gauge, histogram, Counter, Gauge, Histogram};
+
+use crate::communication::Allocate;
+use crate::communication::logging::{CommunicationEvent, CommunicationSetup};
+use crate::dataflow::ProbeHandle;
+use crate::logging::{TimelyEvent, StartStop};
+use crate::logging_core::Logger;
+use crate::progress::Timestamp;
+use crate::worker::Worker;
+
+/// A probe whose lag is reported, with its gauge and its timestamps mapped to processing time.
+type TrackedProbe = (Gauge, Box<dyn Fn(Duration)->Duration>);
+
+/// Reports the events of `worker` through the `metrics` facade, replacing its `"timely"` logger.
+///
+/// The returned tracker reports the lag of probes of the worker, and should be retained for
+/// as long as those probes are to be reported.
+pub fn install<A: Allocate>(worker: &mut Worker<A>) -> ProbeTracker {
+    let index = worker.index().to_string();
+    let probes = Rc::new(RefCell::new(Vec::<TrackedProbe>::new()));
+    let tracked = Rc::downgrade(&probes);
+    // Handles are retained, to avoid registering metrics for each event.
+    let mut channels = HashMap::<(usize, bool), (Counter, Counter)>::new();
+    let mut operators = HashMap::<usize, (Counter, Histogram)>::new();
+    let mut starts = HashMap::new();
+    worker.log_register().insert::<TimelyEvent,_>("timely", move |time, data| {
+        for (elapsed, _worker, event) in data.drain(..) {
+            match event {
+                TimelyEvent::Operates(event) => {
+                    operators.insert(event.id, operator_metrics(&index, event.id, event.name));
+                },
+                TimelyEvent::Messages(event) => {
+                    let (messages, records) = channels.entry((event.channel, event.is_send)).or_insert_with(|| {
+                        let labels = [("worker", index.clone()), ("channel", event.channel.to_string()), ("direction", direction(event.is_send).to_string())];
+                        (counter!("timely_channel_messages_total", &labels), counter!("timely_channel_records_total", &labels))
+                    });
+                    // Sampled events stand for as many events as their sampling rate.
+                    messages.increment(event.sample_rate as u64);
+                    records.increment((event.length * event.sample_rate) as u64);
+                },
+                TimelyEvent::Schedule(event) => match event.start_stop {
+                    StartStop::Start => { starts.insert(event.id, elapsed); },
+                    StartStop::Stop => {
+                        if let Some(start) = starts.remove(&event.id) {
+                            // Operators constructed before the logger was installed are reported without a name.
+                            let (activations, schedule) = operators.entry(event.id).or_insert_with(|| operator_metrics(&index, event.id, String::new()));
+                            activations.increment(1);
+                            schedule.record(elapsed.saturating_sub(start).as_secs_f64());
+                        }
+                    },
+                },
+                TimelyEvent::Shutdown(event) => {
+                    operators.remove(&event.id);
+                },
+                _ => { },
+            }
+        }
+        if let Some(probes) = tracked.upgrade() {
+            for (gauge, lag) in probes.borrow().iter() {
+                gauge.set(lag(*time).as_secs_f64());
+            }
+        }
+    });
+    ProbeTracker { worker: worker.index(), probes }
+}
+
+/// A function constructing loggers for communication threads, reporting their events through the `metrics` facade.
+///
+/// The function is suitable as the `log_fn` of `Configuration::Cluster`.
+pub fn communication_logger() -> impl Fn(CommunicationSetup)->Option<Logger<CommunicationEvent, CommunicationSetup>>+Send+Sync {
+    move |setup| {
+        let mut network = HashMap::<(usize, bool), (Counter, Counter)>::new();
+        Some(Logger::new(Instant::now(), setup, move |_time, data| {
+            for (_elapsed, setup, event) in data.drain(..) {
+                if let CommunicationEvent::Message(message) = event {
+                    let remote = setup.remote.unwrap_or(setup.process);
+                    let (messages, bytes) = network.entry((message.header.channel, message.is_send)).or_insert_with(|| {
+                        let labels = [
+                            ("process", setup.process.to_string()),
+                            ("remote", remote.to_string()),
+                            ("channel", message.header.channel.to_string()),
+                            ("direction", direction(message.is_send).to_string()),
+                        ];
+                        (counter!("timely_network_messages_total", &labels), counter!("timely_network_bytes_total", &labels))
+                    });
+                    messages.increment(1);
+                    bytes.increment(message.header.length as u64);
+                }
+            }
+        }))
+    }
+}
+
+/// Reports the lag of probes at a worker, as returned by `install`.
+pub struct ProbeTracker {
+    worker: usize,
+    probes: Rc<RefCell<Vec<TrackedProbe>>>,
+}
+
+impl ProbeTracker {
+    /// Reports the lag of `probe` as `timely_probe_lag_seconds`, labeled with `name`.
+    ///
+    /// The function `to_elapsed` maps timestamps to the processing time since the start of the
+    /// worker they correspond to, for example for timestamps that count milliseconds. The lag
+    /// is the processing time by which the least timestamp of the frontier trails the worker,
+    /// and is zero once the frontier is empty. The lag is updated as the worker logs events.
+    pub fn track_probe<T, F>(&self, name: &str, probe: &ProbeHandle<T>, to_elapsed: F)
+    where
+        T: Timestamp,
+        F: Fn(&T)->Duration+'static,
+    {
+        let probe = probe.clone();
+        let lag = move |elapsed: Duration| {
+            probe.with_frontier(|frontier| {
+                frontier.iter().map(|time| elapsed.saturating_sub(to_elapsed(time))).max().unwrap_or_default()
+            })
+        };
+        let gauge = gauge!("timely_probe_lag_seconds", "worker" => self.worker.to_string(), "probe" => name.to_owned());
+        self.probes.borrow_mut().push((gauge, Box::new(lag)));
+    }
+}
+
+/// The activation counter and schedule histogram of an operator.
+fn operator_metrics(worker: &str, id: usize, name: String) -> (Counter, Histogram) {
+    let labels = [("worker", worker.to_owned()), ("operator", id.to_string()), ("name", name)];
+    (counter!("timely_operator_activations_total", &labels), histogram!("timely_operator_schedule_seconds", &labels))
+}
+
+fn direction(is_send: bool) -> &'static str {
+    if is_send { "send" } else { "recv" }
+}
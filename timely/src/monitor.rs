@@ -0,0 +1,207 @@
+//! A dataflow monitoring the worker that hosts it.
+//!
+//! With the `monitor` feature, `install` constructs a small dataflow in a worker that consumes
+//! the worker's own `"timely"` log stream, and aggregates it into a `Summary` of the time spent
+//! scheduling each operator and of the volume of each channel. The summary, and the lag of the
+//! dataflow's frontier behind the worker, are available from the returned `MonitorHandle`, much
+//! as a source of the figures presented by timely-viz, without an external process to collect
+//! the log.
+//!
+//! The monitoring dataflow reads the log once in each `period`, and observes its own operators
+//! as it does any other. It runs for as long as its handle is retained, and while it runs the
+//! worker has a dataflow to complete; dropping the handle completes the dataflow.
+//!
+//! # Examples
+//! ```
+//! use std::time::Duration;
+//! use timely::dataflow::InputHandle;
+//! use timely::dataflow::operators::{Input, Exchange, Probe};
+//!
+//! timely::execute_from_args(::std::env::args(), |worker| {
+//!     let monitor = timely::monitor::install(worker, Duration::from_millis(10));
+//!
+//!     let mut input = InputHandle::new();
+//!     let probe = worker.dataflow::<u64,_,_>(|scope| {
+//!         scope.input_from(&mut input)
+//!              .exchange(|x: &u64| *x)
+//!              .probe()
+//!     });
+//!
+//!     for round in 0 .. 10 {
+//!         input.send(round);
+//!         input.advance_to(round + 1);
+//!         worker.step_while(|| probe.less_than(input.time()));
+//!     }
+//!
+//!     // Await a summary of the work so far.
+//!     let now = worker.timer().elapsed();
+//!     worker.step_while(|| monitor.probe().less_than(&now));
+//!
+//!     let summary = monitor.summary();
+//!     let exchange = summary.operators.values().find(|operator| operator.name == "Exchange").unwrap();
+//!     assert!(exchange.activations > 0);
+//!     assert!(summary.channels.values().any(|channel| channel.records > 0));
+//!     println!("monitor lag: {:?}", monitor.lag());
+//! }).unwrap();
+//! ```
+
+use std::rc::Rc;
+use std::cell::RefCell;
+use std::collections::{BTreeMap, HashMap};
+use crate::time::{Duration, Instant};
+
+use crate::communication::Allocate;
+use crate::dataflow::ProbeHandle;
+use crate::dataflow::channels::pact::Pipeline;
+use crate::dataflow::operators::Probe;
+use crate::dataflow::operators::generic::Operator;
+use crate::logging::{TimelyEvent, StartStop};
+use crate::logging::source::{log_source, LogSourceHandle};
+use crate::worker::Worker;
+
+/// The scheduling of an operator, as observed by the monitor.
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+pub struct OperatorSummary {
+    /// A helpful name.
+    pub name: String,
+    /// Sequence of nested scope identifiers indicating the path from the root to this instance.
+    pub addr: Vec<usize>,
+    /// The number of times the operator has been scheduled.
+    pub activations: usize,
+    /// The total time spent in activations of the operator.
+    pub elapsed: Duration,
+}
+
+/// The volume of a channel, as observed by the monitor.
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+pub struct ChannelSummary {
+    /// Sequence of nested scope identifiers indicating the path from the root to the scope.
+    pub scope_addr: Vec<usize>,
+    /// Source descriptor, indicating operator index and output port.
+    pub source: (usize, usize),
+    /// Target descriptor, indicating operator index and input port.
+    pub target: (usize, usize),
+    /// The number of messages received by the worker.
+    pub messages: usize,
+    /// The number of records received by the worker.
+    pub records: usize,
+}
+
+/// The aggregated events of a worker, by operator and channel identifier.
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+pub struct Summary {
+    /// Operators that have not shut down, by their worker-unique identifiers.
+    pub operators: BTreeMap<usize, OperatorSummary>,
+    /// Channels, by their worker-unique identifiers.
+    pub channels: BTreeMap<usize, ChannelSummary>,
+    /// The time since the worker started, through which the summary reflects its events.
+    pub through: Duration,
+}
+
+impl Summary {
+    /// Accounts for `event`, logged at `elapsed`.
+    fn observe(&mut self, elapsed: Duration, event: TimelyEvent, starts: &mut HashMap<usize, Duration>) {
+        match event {
+            TimelyEvent::Operates(event) => {
+                let operator = self.operators.entry(event.id).or_default();
+                operator.name = event.name;
+                operator.addr = event.addr;
+            },
+            TimelyEvent::Shutdown(event) => {
+                self.operators.remove(&event.id);
+            },
+            TimelyEvent::Channels(event) => {
+                let channel = self.channels.entry(event.id).or_default();
+                channel.scope_addr = event.scope_addr;
+                channel.source = event.source;
+                channel.target = event.target;
+            },
+            TimelyEvent::Messages(event) if !event.is_send => {
+                // Sampled events stand for as many events as their sampling rate.
+                let channel = self.channels.entry(event.channel).or_default();
+                channel.messages += event.sample_rate;
+                channel.records += event.length * event.sample_rate;
+            },
+            TimelyEvent::Schedule(event) => match event.start_stop {
+                StartStop::Start => { starts.insert(event.id, elapsed); },
+                StartStop::Stop => {
+                    if let (Some(start), Some(operator)) = (starts.remove(&event.id), self.operators.get_mut(&event.id)) {
+                        operator.activations += 1;
+                        operator.elapsed += elapsed.saturating_sub(start);
+                    }
+                },
+            },
+            _ => { },
+        }
+    }
+}
+
+/// Access to the summary of a monitoring dataflow, which runs while the handle is retained.
+pub struct MonitorHandle {
+    summary: Rc<RefCell<Summary>>,
+    probe: ProbeHandle<Duration>,
+    timer: Instant,
+    // Retained for as long as the monitor should run.
+    _source: LogSourceHandle,
+}
+
+impl MonitorHandle {
+    /// A copy of the summary of the events observed so far.
+    pub fn summary(&self) -> Summary {
+        self.summary.borrow().clone()
+    }
+
+    /// A probe of the monitoring dataflow, whose frontier is the time through which the summary is complete.
+    ///
+    /// Times are those elapsed since the worker started, as measured by `Worker::timer`.
+    pub fn probe(&self) -> &ProbeHandle<Duration> {
+        &self.probe
+    }
+
+    /// The time by which the frontier of the monitoring dataflow trails the worker.
+    ///
+    /// The lag is measured from the frontier of `probe`, and so reflects the events the dataflow
+    /// has yet to complete rather than when its operator last ran. A completed dataflow has no lag.
+    pub fn lag(&self) -> Duration {
+        let elapsed = self.timer.elapsed();
+        self.probe.with_frontier(|frontier| {
+            frontier.iter().min().map(|time| elapsed.saturating_sub(*time)).unwrap_or_default()
+        })
+    }
+}
+
+/// Installs a dataflow in `worker` that monitors its events, reading them once each `period`.
+///
+/// The monitor replaces the worker's `"timely"` logger.
+pub fn install<A: Allocate>(worker: &mut Worker<A>, period: Duration) -> MonitorHandle {
+
+    let summary = Rc::new(RefCell::new(Summary::default()));
+    let mut probe = ProbeHandle::new();
+
+    let summary_sink = summary.clone();
+
+    let source = worker.dataflow_named::<Duration,_,_>("Monitor", |scope| {
+        let (source, events) = log_source::<_, TimelyEvent>(scope, "timely", period);
+        events
+            .unary_frontier::<(),_,_,_>(Pipeline, "MonitorSummary", |_capability, _info| {
+                let mut starts = HashMap::new();
+                let mut vector = Vec::new();
+                move |input, _output| {
+                    let mut summary = summary_sink.borrow_mut();
+                    input.for_each(|_time, data| {
+                        data.swap(&mut vector);
+                        for (elapsed, _worker, event) in vector.drain(..) {
+                            summary.observe(elapsed, event, &mut starts);
+                        }
+                    });
+                    if let Some(through) = input.frontier().frontier().first() {
+                        summary.through = *through;
+                    }
+                }
+            })
+            .probe_with(&mut probe);
+        source
+    });
+
+    MonitorHandle { summary, probe, timer: worker.timer(), _source: source }
+}
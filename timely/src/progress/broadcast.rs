@@ -1,15 +1,212 @@
 //! Broadcasts progress information among workers.
 
+use crate::time::{Duration, SystemTime, UNIX_EPOCH};
+
 use crate::progress::{ChangeBatch, Timestamp};
-use crate::progress::Location;
+use crate::progress::{Location, Port};
 use crate::communication::{Message, Push, Pull};
 use crate::logging::TimelyLogger as Logger;
+use crate::logging::{EventKind, LogInterest};
+use crate::dataflow::graph::ProgressStats;
 
 /// A list of progress updates corresponding to `((child_scope, [in/out]_port, timestamp), delta)`
 pub type ProgressVec<T> = Vec<((Location, T), i64)>;
-/// A progress update message consisting of source worker id, sequence number and lists of
-/// message and internal updates
-pub type ProgressMsg<T> = Message<(usize, usize, ProgressVec<T>)>;
+/// A progress update message consisting of source worker id, sequence number, time of sending
+/// in nanoseconds since the Unix epoch, and lists of message and internal updates
+pub type ProgressMsg<T> = Message<(usize, usize, u64, EncodedProgress<T>)>;
+
+/// A compact encoding of a list of progress updates.
+///
+/// Updates are grouped into runs of the same location, and each run records its location as a
+/// difference from the previous run's, followed by the timestamps and differences of its updates.
+/// Locations, run lengths, and differences are written as variable-length integers, so that the
+/// updates of large scopes, whose counts change by small amounts, occupy a few bytes each beyond
+/// their timestamps.
+///
+/// # Examples
+/// ```
+/// use timely::progress::Location;
+/// use timely::progress::broadcast::EncodedProgress;
+///
+/// let updates = vec![
+///     ((Location::new_source(3, 0), 5u64), 1),
+///     ((Location::new_source(3, 0), 6u64), -1),
+///     ((Location::new_target(700, 1), 5u64), 2),
+/// ];
+///
+/// let mut encoded = EncodedProgress::new();
+/// encoded.encode(updates.iter());
+/// assert_eq!(encoded.len(), 3);
+///
+/// let mut decoded = Vec::new();
+/// encoded.decode(|update, diff| decoded.push((update, diff)));
+/// assert_eq!(decoded, updates);
+/// ```
+#[derive(Debug, Abomonation, Serialize, Deserialize)]
+pub struct EncodedProgress<T> {
+    // For each run its location delta, port, and length, and for each update its difference.
+    bytes: Vec<u8>,
+    // The timestamp of each update.
+    times: Vec<T>,
+}
+
+impl<T: Clone> EncodedProgress<T> {
+    /// Allocates a new empty encoding.
+    pub fn new() -> Self {
+        EncodedProgress { bytes: Vec::new(), times: Vec::new() }
+    }
+
+    /// Replaces the contents of `self` with `updates`.
+    ///
+    /// Updates are best presented in sorted order, as by a compacted `ChangeBatch`, so that
+    /// updates at the same location form a single run.
+    pub fn encode<'a, I>(&mut self, updates: I) where I: Iterator<Item=&'a ((Location, T), i64)>, T: 'a {
+        self.bytes.clear();
+        self.times.clear();
+        let mut updates = updates.peekable();
+        let mut node = 0;
+        while let Some(((location, _), _)) = updates.peek() {
+            let location = *location;
+            let mut run = Vec::new();
+            while let Some(((_, time), diff)) = updates.next_if(|((next, _), _)| next == &location) {
+                self.times.push(time.clone());
+                run.push(*diff);
+            }
+            write_varint(&mut self.bytes, zigzag(location.node as i64 - node as i64));
+            write_varint(&mut self.bytes, match location.port {
+                Port::Target(port) => (port as u64) << 1,
+                Port::Source(port) => ((port as u64) << 1) | 1,
+            });
+            write_varint(&mut self.bytes, run.len() as u64);
+            for diff in run {
+                write_varint(&mut self.bytes, zigzag(diff));
+            }
+            node = location.node;
+        }
+    }
+
+    /// Presents each encoded update to `logic`, in the order they were encoded.
+    pub fn decode<F: FnMut((Location, T), i64)>(&self, mut logic: F) {
+        let mut bytes = &self.bytes[..];
+        let mut times = self.times.iter();
+        let mut node = 0;
+        while !bytes.is_empty() {
+            node = (node as i64 + unzigzag(read_varint(&mut bytes).expect("progress encoding malformed node"))) as usize;
+            let port = read_varint(&mut bytes).expect("progress encoding malformed port");
+            let port = if port & 1 == 0 { Port::Target((port >> 1) as usize) } else { Port::Source((port >> 1) as usize) };
+            let location = Location { node, port };
+            for _ in 0 .. read_varint(&mut bytes).expect("progress encoding malformed count") {
+                let diff = unzigzag(read_varint(&mut bytes).expect("progress encoding malformed diff"));
+                let time = times.next().expect("progress encoding missing timestamp").clone();
+                logic((location, time), diff);
+            }
+        }
+    }
+
+    /// The number of encoded updates.
+    pub fn len(&self) -> usize {
+        self.times.len()
+    }
+
+    /// Returns `true` if there are no encoded updates.
+    pub fn is_empty(&self) -> bool {
+        self.times.is_empty()
+    }
+
+    /// The number of bytes the encoding occupies, counting timestamps by their size in memory.
+    pub fn byte_size(&self) -> usize {
+        self.bytes.len() + self.times.len() * ::std::mem::size_of::<T>()
+    }
+}
+
+impl<T: Clone> Clone for EncodedProgress<T> {
+    fn clone(&self) -> Self {
+        EncodedProgress { bytes: self.bytes.clone(), times: self.times.clone() }
+    }
+    fn clone_from(&mut self, source: &Self) {
+        self.bytes.clone_from(&source.bytes);
+        self.times.clone_from(&source.times);
+    }
+}
+
+impl<T: Clone> Default for EncodedProgress<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// Maps signed integers to unsigned integers, with small magnitudes to small values.
+fn zigzag(value: i64) -> u64 {
+    ((value << 1) ^ (value >> 63)) as u64
+}
+
+fn unzigzag(value: u64) -> i64 {
+    ((value >> 1) as i64) ^ -((value & 1) as i64)
+}
+
+// Writes `value` seven bits at a time, least significant first, marking all but the last byte.
+fn write_varint(bytes: &mut Vec<u8>, mut value: u64) {
+    while value >= 0x80 {
+        bytes.push((value as u8) | 0x80);
+        value >>= 7;
+    }
+    bytes.push(value as u8);
+}
+
+// Reads a value written by `write_varint`, or `None` if the bytes end first or encode more than 64 bits.
+fn read_varint(bytes: &mut &[u8]) -> Option<u64> {
+    let mut value = 0;
+    let mut shift = 0;
+    loop {
+        let (&byte, rest) = bytes.split_first()?;
+        *bytes = rest;
+        // The tenth byte holds the last bit of a `u64`, and ends the encoding.
+        if shift == 63 && byte > 1 { return None; }
+        value |= ((byte & 0x7f) as u64) << shift;
+        if byte & 0x80 == 0 { return Some(value); }
+        shift += 7;
+    }
+}
+
+/// How scopes exchange progress updates with other workers.
+///
+/// Sending updates promptly minimizes the latency of progress, while holding them back reduces
+/// the number of progress messages, which may dominate the traffic of large clusters running
+/// many small epochs. Policies apply to dataflows constructed after they are set, with
+/// `Worker::set_progress_policy`.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct ProgressPolicy {
+    /// Which updates must be sent.
+    pub mode: ProgressMode,
+    /// Holds updates that must be sent, to send several together.
+    pub batch: Option<ProgressBatch>,
+    /// Stamps messages with the system time as they are sent, so that the workers receiving
+    /// them estimate their latency, as reported by `Worker::progress_stats`.
+    pub latency: bool,
+}
+
+/// Which progress updates a scope must send, as it steps.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum ProgressMode {
+    /// Every update is sent in the step that produces it.
+    #[default]
+    Eager,
+    /// Updates are sent once they could advance a frontier visible to other workers.
+    Demand,
+}
+
+/// Bounds on the updates held back before sending them together.
+///
+/// Updates that must be sent are held until at least `updates` distinct updates are pending,
+/// or until `delay` has passed since the first was held, whichever is sooner. The delay is
+/// measured by the clock of the worker.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct ProgressBatch {
+    /// The number of distinct pending updates at which they are sent.
+    pub updates: usize,
+    /// The longest time an update is held.
+    pub delay: Duration,
+}
 
 /// Manages broadcasting of progress updates to and receiving updates from workers.
 pub struct Progcaster<T:Timestamp> {
@@ -24,6 +221,12 @@ pub struct Progcaster<T:Timestamp> {
     addr: Vec<usize>,
     /// Communication channel identifier
     channel_identifier: usize,
+    /// Updates being sent, encoded once for all workers
+    encoded: EncodedProgress<T>,
+    /// Counters of messages sent and received
+    stats: ProgressStats,
+    /// Whether to stamp sent messages with the system time
+    latency: bool,
 
     logging: Option<Logger>,
 }
@@ -39,6 +242,7 @@ impl<T:Timestamp+Send> Progcaster<T> {
             kind: crate::logging::CommChannelKind::Progress,
         }));
         let worker_index = worker.index();
+        let latency = worker.progress_policy().latency;
         let addr = path.clone();
         Progcaster {
             to_push: None,
@@ -46,19 +250,37 @@ impl<T:Timestamp+Send> Progcaster<T> {
             puller,
             source: worker_index,
             counter: 0,
+            stats: ProgressStats {
+                addr: addr.clone(),
+                channel: channel_identifier,
+                ..Default::default()
+            },
             addr,
             channel_identifier,
+            encoded: EncodedProgress::new(),
+            latency,
             logging,
         }
     }
 
+    /// Counters of the messages sent and received.
+    pub fn stats(&self) -> &ProgressStats {
+        &self.stats
+    }
+
     /// Sends pointstamp changes to all workers.
     pub fn send(&mut self, changes: &mut ChangeBatch<(Location, T)>) {
 
         changes.compact();
         if !changes.is_empty() {
 
-            self.logging.as_ref().map(|l| l.log(crate::logging::ProgressEvent {
+            self.encoded.encode(changes.iter());
+            let updates = self.encoded.len();
+            let bytes = self.encoded.byte_size();
+            // Messages not stamped with their send time carry zero.
+            let sent_at = if self.latency { unix_nanos() } else { 0 };
+
+            self.logging.as_ref().filter(|l| l.interested_in_scope(EventKind::Progress, &self.addr)).map(|l| l.log(crate::logging::ProgressEvent {
                 is_send: true,
                 source: self.source,
                 channel: self.channel_identifier,
@@ -67,6 +289,10 @@ impl<T:Timestamp+Send> Progcaster<T> {
                 // TODO: fill with additional data
                 messages: Vec::new(),
                 internal: Vec::new(),
+                updates,
+                bytes,
+                latency: None,
+                sample_rate: 1,
             }));
 
             for pusher in self.pushers.iter_mut() {
@@ -76,21 +302,28 @@ impl<T:Timestamp+Send> Progcaster<T> {
                     let tuple = tuple.as_mut();
                     tuple.0 = self.source;
                     tuple.1 = self.counter;
-                    tuple.2.clear(); tuple.2.extend(changes.iter().cloned());
+                    tuple.2 = sent_at;
+                    tuple.3.clone_from(&self.encoded);
                 }
                 // If we don't have an allocation ...
                 if self.to_push.is_none() {
                     self.to_push = Some(Message::from_typed((
                         self.source,
                         self.counter,
-                        changes.clone().into_inner(),
+                        sent_at,
+                        self.encoded.clone(),
                     )));
                 }
 
                 // TODO: This should probably use a broadcast channel.
                 pusher.push(&mut self.to_push);
                 pusher.done();
+
+                self.stats.sent_messages += 1;
+                self.stats.sent_updates += updates;
+                self.stats.sent_bytes += bytes;
             }
+            self.stats.largest_batch = ::std::cmp::max(self.stats.largest_batch, updates);
 
             self.counter += 1;
             changes.clear();
@@ -104,11 +337,29 @@ impl<T:Timestamp+Send> Progcaster<T> {
 
             let source = message.0;
             let counter = message.1;
-            let recv_changes = &message.2;
+            let recv_changes = &message.3;
+
+            let updates = recv_changes.len();
+            let bytes = recv_changes.byte_size();
+            // Compare system clocks to estimate the latency of stamped messages from other workers.
+            let latency = if source != self.source && message.2 != 0 {
+                Some(Duration::from_nanos(unix_nanos().saturating_sub(message.2)))
+            }
+            else {
+                None
+            };
+            self.stats.received_messages += 1;
+            self.stats.received_updates += updates;
+            self.stats.received_bytes += bytes;
+            if let Some(latency) = latency {
+                self.stats.remote_messages += 1;
+                self.stats.remote_latency += latency;
+                self.stats.max_latency = ::std::cmp::max(self.stats.max_latency, latency);
+            }
 
             let addr = &mut self.addr;
             let channel = self.channel_identifier;
-            self.logging.as_ref().map(|l| l.log(crate::logging::ProgressEvent {
+            self.logging.as_ref().filter(|l| l.interested_in_scope(EventKind::Progress, addr)).map(|l| l.log(crate::logging::ProgressEvent {
                 is_send: false,
                 source: source,
                 seq_no: counter,
@@ -117,13 +368,40 @@ impl<T:Timestamp+Send> Progcaster<T> {
                 // TODO: fill with additional data
                 messages: Vec::new(),
                 internal: Vec::new(),
+                updates,
+                bytes,
+                latency,
+                sample_rate: 1,
             }));
 
             // We clone rather than drain to avoid deserialization.
-            for &(ref update, delta) in recv_changes.iter() {
-                changes.update(update.clone(), delta);
-            }
+            recv_changes.decode(|update, delta| changes.update(update, delta));
         }
 
     }
 }
+
+// The current system time, in nanoseconds since the Unix epoch.
+fn unix_nanos() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|elapsed| elapsed.as_nanos() as u64).unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::{read_varint, write_varint};
+
+    #[test]
+    fn varint_bounds() {
+        for value in [0, 1, 127, 128, u64::MAX >> 1, u64::MAX] {
+            let mut bytes = Vec::new();
+            write_varint(&mut bytes, value);
+            assert_eq!(read_varint(&mut &bytes[..]), Some(value));
+            // Any proper prefix of an encoding is truncated.
+            assert_eq!(read_varint(&mut &bytes[.. bytes.len() - 1]), None);
+        }
+        // Eleven bytes, or a tenth byte with more than one bit, exceed 64 bits.
+        assert_eq!(read_varint(&mut &[0xff; 11][..]), None);
+        assert_eq!(read_varint(&mut &[0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0x02][..]), None);
+    }
+}
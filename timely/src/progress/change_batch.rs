@@ -256,6 +256,8 @@ impl<T:Ord> ChangeBatch<T> {
             self.updates.retain(|x| x.1 != 0);
         }
         self.clean = self.updates.len();
+        #[cfg(feature = "invariants")]
+        self.check_invariants();
     }
 
     /// Expose the internal vector of updates.
@@ -264,6 +266,20 @@ impl<T:Ord> ChangeBatch<T> {
     /// Expose the internal value of `clean`.
     pub fn unstable_internal_clean(&self) -> usize { self.clean }
 
+    // Panics unless the clean prefix of updates is sorted, with distinct items.
+    //
+    // Violations indicate that the `Ord` implementation of `T` is inconsistent with its `Eq`
+    // implementation, or is not a total order.
+    #[cfg(feature = "invariants")]
+    fn check_invariants(&self) {
+        let clean = &self.updates[.. self.clean];
+        for (index, window) in clean.windows(2).enumerate() {
+            if window[0].0.cmp(&window[1].0) != ::std::cmp::Ordering::Less {
+                panic!("ChangeBatch<{}> invariant violated by compact: items {} and {} are not strictly ordered, of {}", ::std::any::type_name::<T>(), index, index + 1, clean.len());
+            }
+        }
+    }
+
     /// Maintain the bounds of pending (non-compacted) updates versus clean (compacted) data.
     /// This function tries to minimize work by only compacting if enough work has accumulated.
     fn maintain_bounds(&mut self) {
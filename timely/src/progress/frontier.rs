@@ -29,9 +29,13 @@ impl<T: PartialOrder> Antichain<T> {
     /// assert!(!frontier.insert(3));
     ///```
     pub fn insert(&mut self, element: T) -> bool {
+        #[cfg(feature = "invariants")]
+        check_reflexive(&element, "Antichain::insert");
         if !self.elements.iter().any(|x| x.less_equal(&element)) {
             self.elements.retain(|x| !element.less_equal(x));
             self.elements.push(element);
+            #[cfg(feature = "invariants")]
+            check_antichain(&self.elements, "Antichain", "insert");
             true
         }
         else {
@@ -376,6 +380,8 @@ impl<T: PartialOrder+Ord+Clone> MutableAntichain<T> {
         if rebuild_required {
             self.rebuild()
         }
+        #[cfg(feature = "invariants")]
+        self.check_invariants("update_iter");
         self.changes.drain()
     }
 
@@ -454,6 +460,65 @@ impl<T: PartialOrder+Ord+Clone> MutableAntichain<T> {
             .map(|td| td.1)
             .sum()
     }
+
+    /// Reports the times with non-zero counts, and their counts, in order of time.
+    ///
+    /// # Examples
+    ///
+    ///```
+    /// use timely::progress::frontier::MutableAntichain;
+    ///
+    /// let mut frontier = MutableAntichain::new();
+    /// frontier.update_iter(vec![(2u64, 1), (1, 1), (2, 1), (3, 1), (3, -1)]);
+    /// assert_eq!(frontier.counts(), vec![(1, 1), (2, 2)]);
+    ///```
+    pub fn counts(&self) -> Vec<(T, i64)> {
+        let mut counts = self.updates.clone();
+        crate::consolidation::consolidate(&mut counts);
+        counts
+    }
+
+    // Panics unless the frontier is an antichain of the minimal times with positive counts.
+    //
+    // Violations indicate that the `Ord` implementation of `T` contradicts its `PartialOrder`
+    // implementation, or that one of them is not an order.
+    #[cfg(feature = "invariants")]
+    fn check_invariants(&self, operation: &str) {
+        check_antichain(&self.frontier, "MutableAntichain", operation);
+        let mut counts = self.updates.clone();
+        crate::consolidation::consolidate(&mut counts);
+        for (index, time) in self.frontier.iter().enumerate() {
+            let count = counts.binary_search_by(|(other, _)| other.cmp(time)).map(|position| counts[position].1).unwrap_or(0);
+            if count <= 0 {
+                panic!("MutableAntichain<{}> invariant violated by {}: frontier element {} has count {}, rather than a positive count", ::std::any::type_name::<T>(), operation, index, count);
+            }
+        }
+        for (time, count) in counts.iter().filter(|(_, count)| *count > 0) {
+            if !self.frontier.iter().any(|f| f.less_equal(time)) {
+                panic!("MutableAntichain<{}> invariant violated by {}: a time with count {} is not greater or equal to any frontier element", ::std::any::type_name::<T>(), operation, count);
+            }
+        }
+    }
+}
+
+// Panics unless `element` is less or equal to itself, as a partial order requires.
+#[cfg(feature = "invariants")]
+fn check_reflexive<T: PartialOrder>(element: &T, operation: &str) {
+    if !element.less_equal(element) {
+        panic!("{} of {}: element is not less or equal to itself, which violates the PartialOrder contract", operation, ::std::any::type_name::<T>());
+    }
+}
+
+// Panics unless `elements` are mutually incomparable.
+#[cfg(feature = "invariants")]
+fn check_antichain<T: PartialOrder>(elements: &[T], structure: &str, operation: &str) {
+    for (index1, element1) in elements.iter().enumerate() {
+        for (index2, element2) in elements.iter().enumerate() {
+            if index1 != index2 && element1.less_equal(element2) {
+                panic!("{}<{}> invariant violated by {}: element {} is less or equal to element {}, of {}", structure, ::std::any::type_name::<T>(), operation, index1, index2, elements.len());
+            }
+        }
+    }
 }
 
 /// Extension trait for filtering time changes through antichains.
@@ -587,3 +652,40 @@ impl<'a, T: 'a+PartialOrder> ::std::iter::IntoIterator for &'a AntichainRef<'a,
         self.iter()
     }
 }
+
+#[cfg(all(test, feature = "invariants"))]
+mod tests {
+
+    use crate::order::PartialOrder;
+    use super::{Antichain, MutableAntichain};
+
+    // A time whose partial order is reversed with respect to its total order.
+    #[derive(Clone, Debug, Eq, PartialEq, Ord, PartialOrd)]
+    struct Reversed(u64);
+
+    impl PartialOrder for Reversed {
+        fn less_equal(&self, other: &Self) -> bool { self.0 >= other.0 }
+    }
+
+    // A time that is not less or equal to itself.
+    #[derive(Clone, Debug, Eq, PartialEq, Ord, PartialOrd)]
+    struct Irreflexive(u64);
+
+    impl PartialOrder for Irreflexive {
+        fn less_equal(&self, other: &Self) -> bool { self.0 < other.0 }
+    }
+
+    #[test]
+    #[should_panic(expected = "violates the PartialOrder contract")]
+    fn antichains_reject_irreflexive_orders() {
+        let mut antichain = Antichain::new();
+        antichain.insert(Irreflexive(0));
+    }
+
+    #[test]
+    #[should_panic(expected = "MutableAntichain")]
+    fn mutable_antichains_reject_contradictory_orders() {
+        let mut antichain = MutableAntichain::new();
+        antichain.update_iter(vec![(Reversed(0), 1), (Reversed(1), 1)]).for_each(drop);
+    }
+}
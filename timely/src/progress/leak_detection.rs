@@ -0,0 +1,177 @@
+//! Detection of capabilities held for timestamps an operator's inputs have abandoned.
+//!
+//! An operator that retains a capability for a timestamp its input frontiers have passed
+//! prevents downstream frontiers from advancing, and is the most common cause of a stuck
+//! dataflow. This module tracks the capabilities each operator holds on this worker, and
+//! reports those held at such timestamps for longer than a threshold. The input frontiers
+//! are first advanced by the operator's internal summaries, so that capabilities an operator
+//! holds at times its summaries produce from its inputs (for example, the incremented times
+//! of a feedback operator) are not reported.
+//!
+//! The threshold defaults to one second, and can be set in milliseconds through the
+//! `TIMELY_LEAK_THRESHOLD_MS` environment variable. Operators without inputs (for example,
+//! dataflow inputs) are exempt, as they have no frontier to abandon timestamps, as are
+//! outputs that no input connects to.
+
+use std::collections::{HashMap, HashSet};
+use crate::time::{Duration, Instant};
+
+use crate::progress::{Timestamp, PathSummary, ChangeBatch, Antichain};
+use crate::progress::reachability::PortInformation;
+
+/// Tracks the capabilities held by one operator, and reports those it has leaked.
+pub struct LeakDetector<T: Timestamp> {
+    /// Capability counts held on this worker, for each output.
+    held: Vec<ChangeBatch<T>>,
+    /// The moments at which held capabilities were first observed abandoned.
+    abandoned: HashMap<(usize, T), Instant>,
+    /// Capabilities already reported, so that each leak is reported once.
+    reported: HashSet<(usize, T)>,
+    /// The duration a capability may be held abandoned before it is reported.
+    threshold: Duration,
+}
+
+impl<T: Timestamp> LeakDetector<T> {
+    /// Allocates a new detector for an operator with `outputs` outputs.
+    pub fn new(outputs: usize) -> Self {
+        let millis = ::std::env::var("TIMELY_LEAK_THRESHOLD_MS")
+            .ok()
+            .and_then(|x| x.parse::<u64>().ok())
+            .unwrap_or(1000);
+
+        LeakDetector {
+            held: vec![ChangeBatch::new(); outputs],
+            abandoned: HashMap::new(),
+            reported: HashSet::new(),
+            threshold: Duration::from_millis(millis),
+        }
+    }
+
+    /// Records a change to the capabilities held at `output`.
+    pub fn update(&mut self, output: usize, time: T, diff: i64) {
+        self.held[output].update(time, diff);
+    }
+
+    /// Returns capabilities newly found to have been abandoned for longer than the threshold.
+    ///
+    /// A capability is abandoned when no input frontier, advanced by the summary from that
+    /// input to the capability's output, is less or equal to its timestamp. The summaries are
+    /// indexed first by input and then by output, as returned by `get_internal_summary`.
+    /// Each result indicates the output, the timestamp, and how long it has been abandoned.
+    pub fn detect(&mut self, inputs: &[PortInformation<T>], summaries: &[Vec<Antichain<T::Summary>>]) -> Vec<(usize, T, Duration)> {
+
+        let mut leaks = Vec::new();
+        if inputs.is_empty() {
+            return leaks;
+        }
+
+        let now = Instant::now();
+        let mut current = HashSet::new();
+        for (output, held) in self.held.iter_mut().enumerate() {
+            if summaries.iter().all(|summary| summary[output].elements().is_empty()) {
+                continue;
+            }
+            for (time, diff) in held.iter() {
+                if *diff > 0 && !inputs.iter().zip(summaries).any(|(x, summary)| reaches(x, &summary[output], time)) {
+                    let key = (output, time.clone());
+                    let since = *self.abandoned.entry(key.clone()).or_insert(now);
+                    if now.duration_since(since) >= self.threshold && !self.reported.contains(&key) {
+                        self.reported.insert(key.clone());
+                        leaks.push((output, time.clone(), now.duration_since(since)));
+                    }
+                    current.insert(key);
+                }
+            }
+        }
+
+        // Forget capabilities that have been released, or whose inputs have caught up.
+        self.abandoned.retain(|key, _| current.contains(key));
+        self.reported.retain(|key| current.contains(key));
+
+        leaks
+    }
+
+    /// The moment the next abandoned capability would be reported, if any are pending.
+    ///
+    /// Operators that leak capabilities are often never scheduled again, and this deadline
+    /// allows the enclosing scope to re-activate itself to complete the detection.
+    pub fn next_report(&self) -> Option<Instant> {
+        self.abandoned
+            .iter()
+            .filter(|(key, _)| !self.reported.contains(key))
+            .map(|(_, since)| *since + self.threshold)
+            .min()
+    }
+}
+
+/// True when a time in the frontier of `input`, advanced by some element of `summary`, is less or equal to `time`.
+fn reaches<T: Timestamp>(input: &PortInformation<T>, summary: &Antichain<T::Summary>, time: &T) -> bool {
+    input.implications.frontier().iter().any(|frontier| {
+        summary.elements().iter().any(|path| path.results_in(frontier).is_some_and(|result| result.less_equal(time)))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+
+    use crate::progress::Antichain;
+    use crate::progress::reachability::PortInformation;
+    use super::LeakDetector;
+
+    #[test]
+    fn reports_abandoned_capability() {
+
+        let mut detector = LeakDetector::<usize>::new(1);
+        detector.threshold = ::std::time::Duration::from_millis(0);
+        let summaries = vec![vec![Antichain::from_elem(0)]];
+
+        let mut input = PortInformation::new();
+        input.implications.update_iter(vec![(5, 1)]);
+
+        // time 3 is less than the input frontier at 5, time 7 is not.
+        detector.update(0, 3, 1);
+        detector.update(0, 7, 1);
+        let leaks = detector.detect(&[input.clone()], &summaries);
+        assert_eq!(leaks.len(), 1);
+        assert_eq!((leaks[0].0, leaks[0].1), (0, 3));
+
+        // each leak is only reported once.
+        assert!(detector.detect(&[input.clone()], &summaries).is_empty());
+        assert_eq!(detector.next_report(), None);
+
+        // releasing the capability and re-acquiring it reports it again.
+        detector.update(0, 3, -1);
+        assert!(detector.detect(&[input.clone()], &summaries).is_empty());
+        detector.update(0, 3, 1);
+        assert_eq!(detector.detect(&[input], &summaries).len(), 1);
+    }
+
+    #[test]
+    fn ignores_operators_without_inputs() {
+        let mut detector = LeakDetector::<usize>::new(1);
+        detector.threshold = ::std::time::Duration::from_millis(0);
+        detector.update(0, 3, 1);
+        assert!(detector.detect(&[], &[]).is_empty());
+    }
+
+    #[test]
+    fn applies_internal_summaries() {
+
+        let mut detector = LeakDetector::<usize>::new(2);
+        detector.threshold = ::std::time::Duration::from_millis(0);
+
+        let mut input = PortInformation::new();
+        input.implications.update_iter(vec![(5, 1)]);
+
+        // the input reaches the first output at 5 + 1, and the second output not at all.
+        let summaries = vec![vec![Antichain::from_elem(1), Antichain::new()]];
+
+        // time 5 is less than the summarized frontier at 6, time 6 is not.
+        detector.update(0, 5, 1);
+        detector.update(0, 6, 1);
+        detector.update(1, 3, 1);
+        let leaks = detector.detect(&[input], &summaries);
+        assert_eq!(leaks.len(), 1);
+        assert_eq!((leaks[0].0, leaks[0].1), (0, 5));
+    }
+}
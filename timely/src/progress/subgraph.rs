@@ -6,13 +6,19 @@
 //! of the grouped operators.
 
 use std::rc::Rc;
-use std::cell::RefCell;
+use std::cell::{Cell, RefCell};
 use std::collections::BinaryHeap;
 use std::cmp::Reverse;
+use crate::time::{Duration, Instant};
 
 use crate::logging::TimelyLogger as Logger;
+use crate::logging::{EventKind, LogInterest};
 
-use crate::scheduling::Schedule;
+use crate::scheduling::{Schedule, Fuel, OperatorPriority};
+use crate::scheduling::history::ActivationHistory;
+use crate::scheduling::saturation::Saturation;
+use crate::dataflow::memory::MemoryAccount;
+use crate::dataflow::graph::{OperatorStatus, ProgressStats};
 use crate::scheduling::activate::Activations;
 
 use crate::progress::frontier::{Antichain, MutableAntichain, MutableAntichainFilter};
@@ -20,9 +26,11 @@ use crate::progress::{Timestamp, Operate, operate::SharedProgress};
 use crate::progress::{Location, Port, Source, Target};
 
 use crate::progress::ChangeBatch;
-use crate::progress::broadcast::Progcaster;
+use crate::progress::broadcast::{Progcaster, ProgressBatch, ProgressMode};
 use crate::progress::reachability;
 use crate::progress::timestamp::Refines;
+#[cfg(feature = "leak-detection")]
+use crate::progress::leak_detection::LeakDetector;
 
 // IMPORTANT : by convention, a child identifier of zero is used to indicate inputs and outputs of
 // the Subgraph itself. An identifier greater than zero corresponds to an actual child, which can
@@ -44,6 +52,9 @@ where
     /// A sequence of integers uniquely identifying the subgraph.
     pub path: Vec<usize>,
 
+    /// The names of the scopes enclosing the subgraph, from the dataflow down, ending with its own.
+    pub names: Vec<String>,
+
     /// The index assigned to the subgraph by its parent.
     index: usize,
 
@@ -105,6 +116,7 @@ where
         SubgraphBuilder {
             name: name.to_owned(),
             path,
+            names: vec![name.to_owned()],
             index,
             children,
             child_count: 1,
@@ -126,10 +138,13 @@ where
         {
             let mut child_path = self.path.clone();
             child_path.push(index);
+            let mut child_names = self.names.clone();
+            child_names.push(child.name().to_owned());
             self.logging.as_mut().map(|l| l.log(crate::logging::OperatesEvent {
                 id: identifier,
                 addr: child_path,
                 name: child.name().to_owned(),
+                names: child_names,
             }));
         }
         self.children.push(PerOperatorState::new(child, index, self.path.clone(), identifier, self.logging.clone()))
@@ -168,12 +183,24 @@ where
         let (tracker, scope_summary) = builder.build();
 
         let progcaster = Progcaster::new(worker, &self.path, self.logging.clone());
+        let progress_policy = worker.progress_policy();
 
         let mut incomplete = vec![true; self.children.len()];
         incomplete[0] = false;
         let incomplete_count = incomplete.len() - 1;
 
         let activations = worker.activations().clone();
+        let fuel = activations.borrow().fuel().clone();
+        let history = activations.borrow().history().clone();
+        let saturation = activations.borrow().saturation().clone();
+
+        // Each child observes whether its inputs remain unread, to detect saturation, and the memory attributed to it.
+        for child in self.children.iter_mut().skip(1) {
+            let mut address = self.path.clone();
+            address.push(child.index);
+            child.undrained = Some(saturation.undrained_inputs(&address[..]));
+            child.memory = worker.memory_account(&address[..]);
+        }
 
         activations.borrow_mut().activate(&self.path[..]);
 
@@ -185,7 +212,11 @@ where
             incomplete,
             incomplete_count,
             activations,
-            temp_active: BinaryHeap::new(),
+            fuel,
+            history,
+            saturation,
+            temp_active: Vec::new(),
+            temp_queue: BinaryHeap::new(),
             children: self.children,
             input_messages: self.input_messages,
             output_capabilities: self.output_capabilities,
@@ -198,7 +229,11 @@ where
             shared_progress: Rc::new(RefCell::new(SharedProgress::new(inputs, outputs))),
             scope_summary,
 
-            eager_progress_send: ::std::env::var("DEFAULT_PROGRESS_MODE") != Ok("DEMAND".to_owned()),
+            eager_progress_send: progress_policy.mode == ProgressMode::Eager,
+            progress_batch: progress_policy.batch,
+            progress_held: None,
+            #[cfg(feature = "leak-detection")]
+            leak_wakeup: None,
         }
     }
 }
@@ -227,7 +262,14 @@ where
 
     // shared activations (including children).
     activations: Rc<RefCell<Activations>>,
-    temp_active: BinaryHeap<Reverse<usize>>,
+    // budget for each child activation.
+    fuel: Fuel,
+    // recent activations of children, and their children.
+    history: ActivationHistory,
+    saturation: Saturation,
+    // children activated since last enqueued, and enqueued children by priority level and index.
+    temp_active: Vec<usize>,
+    temp_queue: BinaryHeap<(usize, Reverse<usize>)>,
 
     // shared state written to by the datapath, counting records entering this subgraph instance.
     input_messages: Vec<Rc<RefCell<ChangeBatch<TInner>>>>,
@@ -250,6 +292,12 @@ where
     scope_summary: Vec<Vec<Antichain<TInner::Summary>>>,
 
     eager_progress_send: bool,
+    // bounds on the progress updates held back to send together, and when they were first held.
+    progress_batch: Option<ProgressBatch>,
+    progress_held: Option<Instant>,
+    // the moment at which the subgraph is next activated to report leaked capabilities.
+    #[cfg(feature = "leak-detection")]
+    leak_wakeup: Option<Instant>,
 }
 
 impl<TOuter, TInner> Schedule for Subgraph<TOuter, TInner>
@@ -261,6 +309,40 @@ where
 
     fn path(&self) -> &[usize] { &self.path }
 
+    fn introspect(&self, statuses: &mut Vec<OperatorStatus>) {
+        for (index, child) in self.children.iter().enumerate().skip(1) {
+            let state = self.pointstamp_tracker.node_state(index);
+            let targets = &state.targets[..];
+            let mut address = self.path.clone();
+            address.push(index);
+            statuses.push(OperatorStatus {
+                id: child.id,
+                addr: address,
+                name: child.name.clone(),
+                frontiers: targets.iter().map(|port| port.implications.frontier().iter().map(|t| format!("{:?}", t)).collect()).collect(),
+                channels: vec![Vec::new(); child.inputs],
+                pending: targets.iter().map(|port| format_counts(&port.pointstamps)).collect(),
+                capabilities: state.sources.iter().map(|port| format_counts(&port.pointstamps)).collect(),
+                activations: child.activations,
+                elapsed: child.elapsed,
+                queued_bytes: child.memory.as_ref().map(|memory| memory.queued()),
+                held_bytes: child.memory.as_ref().map(|memory| memory.held()),
+            });
+            if let Some(operator) = child.operator.as_ref() {
+                operator.introspect(statuses);
+            }
+        }
+    }
+
+    fn progress_stats(&self, stats: &mut Vec<ProgressStats>) {
+        stats.push(self.progcaster.stats().clone());
+        for child in self.children.iter().skip(1) {
+            if let Some(operator) = child.operator.as_ref() {
+                operator.progress_stats(stats);
+            }
+        }
+    }
+
     fn schedule(&mut self) -> bool {
 
         // This method performs several actions related to progress tracking
@@ -281,25 +363,40 @@ where
             let temp_active = &mut self.temp_active;
             self.activations
                 .borrow_mut()
-                .for_extensions(&self.path[..], |index| temp_active.push(Reverse(index)));
+                .for_extensions(&self.path[..], |index| temp_active.push(index));
         }
 
         // Schedule child operators.
         //
         // We should be able to schedule arbitrary subsets of children, as
         // long as we eventually schedule all children that need to do work.
-        let mut previous = 0;
-        while let Some(Reverse(index)) = self.temp_active.pop() {
-            // De-duplicate, and don't revisit.
-            if index > previous {
-                self.activate_child(index);
-                previous = index;
+        // Children are scheduled by decreasing priority level, and then by increasing index.
+        let mut previous = None;
+        loop {
+            for index in self.temp_active.drain(..) {
+                let level = self.children[index].priority.level();
+                self.temp_queue.push((level, Reverse(index)));
+            }
+            match self.temp_queue.pop() {
+                Some(key) => {
+                    // De-duplicate, and don't revisit (or visit the scope itself).
+                    let Reverse(index) = key.1;
+                    if index > 0 && previous.map(|previous| key < previous).unwrap_or(true) {
+                        self.activate_child(index);
+                        previous = Some(key);
+                    }
+                }
+                None => break,
             }
         }
 
         // Transmit produced progress updates.
         self.send_progress();
 
+        // Report children holding capabilities their inputs have abandoned.
+        #[cfg(feature = "leak-detection")]
+        self.detect_leaks();
+
         // If child scopes surface more final pointstamp updates we must re-execute.
         if !self.final_pointstamp.is_empty() {
             self.activations.borrow_mut().activate(&self.path[..]);
@@ -326,7 +423,8 @@ where
 
         let child = &mut self.children[child_index];
 
-        let incomplete = child.schedule();
+        self.fuel.refuel();
+        let incomplete = child.schedule(&self.history, &self.saturation);
 
         if incomplete != self.incomplete[child_index] {
             if incomplete { self.incomplete_count += 1; }
@@ -350,6 +448,10 @@ where
             }
         }
 
+        // Check that messages were only delivered at times the child's input frontiers permit.
+        #[cfg(all(feature = "progress-validation", debug_assertions))]
+        child.validate_deliveries(self.pointstamp_tracker.node_state(child_index), &self.path[..]);
+
         // Extract progress statements into either pre- or post-exchange buffers.
         if child.local {
             child.extract_progress(&mut self.local_pointstamp, &mut self.temp_active);
@@ -361,6 +463,37 @@ where
         incomplete
     }
 
+    /// Reports capabilities held by children at timestamps their input frontiers have passed.
+    #[cfg(feature = "leak-detection")]
+    fn detect_leaks(&mut self) {
+        let mut next_report: Option<Instant> = None;
+        for (index, child) in self.children.iter_mut().enumerate().skip(1) {
+            if child.operator.is_some() {
+                let targets = &self.pointstamp_tracker.node_state(index).targets[..];
+                for (output, time, abandoned) in child.leak_detector.detect(targets, &child.internal_summary) {
+                    if let Some(l) = child.logging.as_ref() {
+                        l.log(crate::logging::CapabilityLeakEvent { id: child.id, output, time: format!("{:?}", time), abandoned });
+                    }
+                }
+                if let Some(deadline) = child.leak_detector.next_report() {
+                    next_report = Some(next_report.map_or(deadline, |x| ::std::cmp::min(x, deadline)));
+                }
+            }
+        }
+        // Re-activate to report pending leaks, as leaking operators may not otherwise be scheduled,
+        // unless an activation no later than the deadline is already pending.
+        let now = Instant::now();
+        if self.leak_wakeup.is_some_and(|wakeup| wakeup <= now) {
+            self.leak_wakeup = None;
+        }
+        if let Some(deadline) = next_report {
+            if self.leak_wakeup.is_none_or(|wakeup| deadline < wakeup) {
+                self.activations.borrow_mut().activate_after(&self.path[..], deadline.saturating_duration_since(now));
+                self.leak_wakeup = Some(deadline);
+            }
+        }
+    }
+
     /// Move frontier changes from parent into progress statements.
     fn accept_frontier(&mut self) {
         for (port, changes) in self.shared_progress.borrow_mut().frontiers.iter_mut().enumerate() {
@@ -449,7 +582,7 @@ where
             // Targets are actionable, sources are not.
             if let crate::progress::Port::Target(port) = location.port {
                 if self.children[location.node].notify {
-                    self.temp_active.push(Reverse(location.node));
+                    self.temp_active.push(location.node);
                 }
                 // TODO: This logic could also be guarded by `.notify`, but
                 // we want to be a bit careful to make sure all related logic
@@ -491,7 +624,24 @@ where
                 )
         };
 
-        if must_send {
+        if must_send && !self.local_pointstamp.is_empty() {
+            // Hold the updates if they may be batched, revisiting them once the delay passes.
+            if let Some(batch) = self.progress_batch {
+                let clock = self.activations.borrow().clock().clone();
+                let held = match self.progress_held {
+                    Some(held) => held,
+                    None => {
+                        let now = clock.now();
+                        self.progress_held = Some(now);
+                        self.activations.borrow_mut().activate_after(&self.path[..], batch.delay);
+                        now
+                    }
+                };
+                if self.local_pointstamp.iter().len() < batch.updates && clock.elapsed(held) < batch.delay {
+                    return;
+                }
+            }
+            self.progress_held = None;
             self.progcaster.send(&mut self.local_pointstamp);
         }
     }
@@ -566,6 +716,19 @@ struct PerOperatorState<T: Timestamp> {
     internal_summary: Vec<Vec<Antichain<T::Summary>>>,   // cached result from get_internal_summary.
 
     logging: Option<Logger>,
+
+    activations: usize,         // the number of times the operator has been scheduled.
+    elapsed: Duration,          // the total time spent in activations of the operator.
+    priority: OperatorPriority, // the scheduling priority of the operator within its scope.
+
+    undrained: Option<Rc<Cell<usize>>>, // the number of inputs left with messages remaining.
+    yields: usize,                      // successive activations that left messages remaining.
+
+    memory: Option<MemoryAccount>,      // the memory attributed to the operator, if accounted.
+    reported: (usize, usize),           // the queued and held bytes last logged.
+
+    #[cfg(feature = "leak-detection")]
+    leak_detector: LeakDetector<T>,     // capabilities held by the operator on this worker.
 }
 
 impl<T: Timestamp> PerOperatorState<T> {
@@ -584,9 +747,19 @@ impl<T: Timestamp> PerOperatorState<T> {
             edges: vec![Vec::new(); outputs],
 
             logging: None,
+            activations: 0,
+            elapsed: Duration::default(),
+            priority: OperatorPriority::default(),
+            undrained: None,
+            yields: 0,
+            memory: None,
+            reported: (0, 0),
 
             shared_progress: Rc::new(RefCell::new(SharedProgress::new(inputs,outputs))),
             internal_summary: Vec::new(),
+
+            #[cfg(feature = "leak-detection")]
+            leak_detector: LeakDetector::new(outputs),
         }
     }
 
@@ -602,6 +775,7 @@ impl<T: Timestamp> PerOperatorState<T> {
         let inputs = scope.inputs();
         let outputs = scope.outputs();
         let notify = scope.notify_me();
+        let priority = scope.priority().unwrap_or_default();
 
         let (internal_summary, shared_progress) = scope.get_internal_summary();
 
@@ -620,36 +794,80 @@ impl<T: Timestamp> PerOperatorState<T> {
             edges:              vec![vec![]; outputs],
 
             logging,
+            activations: 0,
+            elapsed: Duration::default(),
+            priority,
+            undrained: None,
+            yields: 0,
+            memory: None,
+            reported: (0, 0),
 
             shared_progress,
             internal_summary,
+
+            #[cfg(feature = "leak-detection")]
+            leak_detector: LeakDetector::new(outputs),
         }
     }
 
-    pub fn schedule(&mut self) -> bool {
+    pub fn schedule(&mut self, history: &ActivationHistory, saturation: &Saturation) -> bool {
 
         if let Some(ref mut operator) = self.operator {
 
+            self.activations += 1;
+
             // Perhaps log information about the start of the schedule call.
-            if let Some(l) = self.logging.as_mut() {
+            if let Some(l) = self.logging.as_ref() {
                 // FIXME: There is no contract that the operator must consume frontier changes.
                 //        This report could be spurious.
                 // TODO:  Perhaps fold this in to `ScheduleEvent::start()` as a "reason"?
-                let frontiers = &mut self.shared_progress.borrow_mut().frontiers[..];
-                if frontiers.iter_mut().any(|buffer| !buffer.is_empty()) {
-                    l.log(crate::logging::PushProgressEvent { op_id: self.id })
+                if l.interested_in_operator(EventKind::PushProgress, self.id) {
+                    let frontiers = &mut self.shared_progress.borrow_mut().frontiers[..];
+                    if frontiers.iter_mut().any(|buffer| !buffer.is_empty()) {
+                        l.log(crate::logging::PushProgressEvent { op_id: self.id })
+                    }
                 }
 
-                l.log(crate::logging::ScheduleEvent::start(self.id));
+                if l.interested_in_operator(EventKind::Schedule, self.id) {
+                    l.log(crate::logging::ScheduleEvent::start(self.id));
+                }
             }
 
+            let start = Instant::now();
             let incomplete = operator.schedule();
+            let elapsed = start.elapsed();
+            self.elapsed += elapsed;
+            history.record(self.id, start, elapsed);
 
             // Perhaps log information about the stop of the schedule call.
-            if let Some(l) = self.logging.as_mut() {
+            if let Some(l) = self.logging.as_ref().filter(|l| l.interested_in_operator(EventKind::Schedule, self.id)) {
                 l.log(crate::logging::ScheduleEvent::stop(self.id));
             }
 
+            // Report operators that repeatedly leave input unread.
+            if let (Some(threshold), Some(undrained)) = (saturation.operator_threshold(), self.undrained.as_ref()) {
+                if undrained.get() > 0 {
+                    self.yields += 1;
+                    if self.yields == threshold {
+                        if let Some(l) = self.logging.as_mut() {
+                            l.log(crate::logging::SaturationEvent::Operator { id: self.id, activations: self.yields });
+                        }
+                    }
+                }
+                else {
+                    self.yields = 0;
+                }
+            }
+
+            // Report changes to the memory attributed to the operator.
+            if let (Some(memory), Some(l)) = (self.memory.as_ref(), self.logging.as_mut()) {
+                let current = (memory.queued(), memory.held());
+                if current != self.reported {
+                    self.reported = current;
+                    l.log(crate::logging::MemoryEvent { id: self.id, queued: current.0, held: current.1 });
+                }
+            }
+
             incomplete
         }
         else {
@@ -670,6 +888,7 @@ impl<T: Timestamp> PerOperatorState<T> {
     fn shut_down(&mut self) {
         if self.operator.is_some() {
             if let Some(l) = self.logging.as_mut() {
+                l.log(crate::logging::OperatorTimeEvent { id: self.id, activations: self.activations, elapsed: self.elapsed });
                 l.log(crate::logging::ShutdownEvent{ id: self.id });
             }
             self.operator = None;
@@ -678,7 +897,7 @@ impl<T: Timestamp> PerOperatorState<T> {
     }
 
     /// Extracts shared progress information and converts to pointstamp changes.
-    fn extract_progress(&mut self, pointstamps: &mut ChangeBatch<(Location, T)>, temp_active: &mut BinaryHeap<Reverse<usize>>) {
+    fn extract_progress(&mut self, pointstamps: &mut ChangeBatch<(Location, T)>, temp_active: &mut Vec<usize>) {
 
         let shared_progress = &mut *self.shared_progress.borrow_mut();
 
@@ -692,6 +911,8 @@ impl<T: Timestamp> PerOperatorState<T> {
         for (output, internal) in shared_progress.internals.iter_mut().enumerate() {
             let source = Location::new_source(self.index, output);
             for (time, delta) in internal.drain() {
+                #[cfg(feature = "leak-detection")]
+                self.leak_detector.update(output, time.clone(), delta);
                 pointstamps.update((source, time.clone()), delta);
             }
         }
@@ -699,7 +920,7 @@ impl<T: Timestamp> PerOperatorState<T> {
             for (time, delta) in produced.drain() {
                 for target in &self.edges[output] {
                     pointstamps.update((Location::from(*target), time.clone()), delta);
-                    temp_active.push(Reverse(target.node));
+                    temp_active.push(target.node);
                 }
             }
         }
@@ -739,6 +960,32 @@ impl<T: Timestamp> PerOperatorState<T> {
             }
         }
     }
+
+    /// Test that messages consumed by the operator were at times its input frontiers permit.
+    ///
+    /// A message may only be delivered at a time greater or equal to some element of the frontier
+    /// of its input, as otherwise the operator may have acted on the completion of that time. The
+    /// frontiers are those reported to the operator, which have not yet been informed by the
+    /// progress statements of its current activation.
+    #[cfg(all(feature = "progress-validation", debug_assertions))]
+    fn validate_deliveries(&self, child_state: &reachability::PerOperator<T>, path: &[usize]) {
+        let shared_progress = &mut *self.shared_progress.borrow_mut();
+        for (input, consumed) in shared_progress.consumeds.iter_mut().enumerate() {
+            let frontier = &child_state.targets[input].implications;
+            let premature = consumed.iter().filter(|(time, diff)| *diff > 0 && !frontier.less_equal(time)).map(|(time, _)| time).collect::<Vec<_>>();
+            if !premature.is_empty() {
+                panic!(
+                    "Progress error: operator {} (id: {}, scope: {:?}) received messages at {:?} on input {}, not beyond its input frontier {:?}",
+                    self.name, self.id, path, premature, input, frontier.frontier().to_vec(),
+                );
+            }
+        }
+    }
+}
+
+// The non-zero counts of `counts`, with timestamps formatted as strings.
+fn format_counts<T: Timestamp>(counts: &MutableAntichain<T>) -> Vec<(String, i64)> {
+    counts.counts().into_iter().map(|(time, count)| (format!("{:?}", time), count)).collect()
 }
 
 // Explicitly shut down the operator to get logged information.
@@ -1,12 +1,16 @@
 //! Parking and unparking timely fibers.
 
 use std::rc::Rc;
-use std::cell::RefCell;
+use std::cell::{Cell, RefCell};
 use std::sync::mpsc::{Sender, Receiver};
-use std::thread::Thread;
-use std::collections::BinaryHeap;
-use std::time::{Duration, Instant};
-use std::cmp::Reverse;
+use crate::time::{Duration, Instant};
+
+use crate::communication::{Message, Push};
+use crate::communication::buzzer::Buzzer;
+use crate::scheduling::{Clock, Fuel};
+use crate::scheduling::history::ActivationHistory;
+use crate::scheduling::saturation::Saturation;
+use crate::scheduling::wheel::TimerWheel;
 
 /// Allocation-free activation tracker.
 pub struct Activations {
@@ -20,9 +24,19 @@ pub struct Activations {
     tx: Sender<Vec<usize>>,
     rx: Receiver<Vec<usize>>,
 
-    // Delayed activations.
+    // Delayed activations, at millisecond ticks since `timer` by `clock`.
     timer: Instant,
-    queue: BinaryHeap<Reverse<(Duration, Vec<usize>)>>,
+    clock: Clock,
+    queue: TimerWheel<Vec<usize>>,
+
+    // Budget for each operator activation.
+    fuel: Fuel,
+
+    // Recent operator activations.
+    history: ActivationHistory,
+
+    // Thresholds at which channels and operators are reported as saturated.
+    saturation: Saturation,
 }
 
 impl Activations {
@@ -38,10 +52,42 @@ impl Activations {
             tx,
             rx,
             timer,
-            queue: BinaryHeap::new(),
+            clock: Clock::system(),
+            queue: TimerWheel::new(),
+            fuel: Fuel::new(),
+            history: ActivationHistory::new(timer),
+            saturation: Saturation::new(),
         }
     }
 
+    /// The budget shared by operator activations.
+    pub fn fuel(&self) -> &Fuel {
+        &self.fuel
+    }
+
+    /// The record of recent operator activations.
+    pub fn history(&self) -> &ActivationHistory {
+        &self.history
+    }
+
+    /// The detection of saturated channels and operators.
+    pub fn saturation(&self) -> &Saturation {
+        &self.saturation
+    }
+
+    /// The clock by which delayed activations are timed.
+    pub fn clock(&self) -> &Clock {
+        &self.clock
+    }
+
+    /// Replaces the clock by which delayed activations are timed.
+    ///
+    /// Delayed activations already scheduled remain at their ticks since the creation of
+    /// `self`, measured by the new clock.
+    pub fn set_clock(&mut self, clock: Clock) {
+        self.clock = clock;
+    }
+
     /// Activates the task addressed by `path`.
     pub fn activate(&mut self, path: &[usize]) {
         self.bounds.push((self.slices.len(), path.len()));
@@ -49,17 +95,42 @@ impl Activations {
     }
 
     /// Schedules a future activation for the task addressed by `path`.
-    pub fn activate_after(&mut self, path: &[usize], delay: Duration) {
-        // TODO: We could have a minimum delay and immediately schedule anything less than that delay.
+    ///
+    /// Delayed activations are rounded up to whole milliseconds, so that those expiring in the same
+    /// millisecond wake the worker once. The returned handle can cancel the activation.
+    pub fn activate_after(&mut self, path: &[usize], delay: Duration) -> DelayedActivation {
         if delay == Duration::new(0, 0) {
             self.activate(path);
+            DelayedActivation { id: None }
         }
         else {
-            let moment = self.timer.elapsed() + delay;
-            self.queue.push(Reverse((moment, path.to_vec())));
+            let moment = self.clock.elapsed(self.timer) + delay;
+            let id = self.queue.insert(ceil_millis(moment), path.to_vec());
+            DelayedActivation { id: Some(id) }
         }
     }
 
+    /// Cancels a delayed activation, returning `true` if it had not yet occurred.
+    ///
+    /// # Examples
+    /// ```
+    /// use std::time::{Duration, Instant};
+    /// use timely::scheduling::Activations;
+    ///
+    /// let mut activations = Activations::new(Instant::now());
+    /// let delayed = activations.activate_after(&[0], Duration::from_millis(1));
+    /// assert!(activations.cancel(delayed));
+    /// assert_eq!(activations.empty_for(), None);
+    /// ```
+    pub fn cancel(&mut self, activation: DelayedActivation) -> bool {
+        activation.id.and_then(|id| self.queue.cancel(id)).is_some()
+    }
+
+    /// Indicates that a delayed activation has neither occurred nor been cancelled.
+    pub fn is_pending(&self, activation: &DelayedActivation) -> bool {
+        activation.id.map(|id| self.queue.contains(id)).unwrap_or(false)
+    }
+
     /// Discards the current active set and presents the next active set.
     pub fn advance(&mut self) {
 
@@ -69,11 +140,13 @@ impl Activations {
         }
 
         // Drain timer-based activations.
-        let now = self.timer.elapsed();
-        while self.queue.peek().map(|Reverse((t,_))| t <= &now) == Some(true) {
-            let Reverse((_time, path)) = self.queue.pop().unwrap();
-            self.activate(&path[..]);
-        }
+        let now = self.clock.elapsed(self.timer).as_millis() as u64;
+        let bounds = &mut self.bounds;
+        let slices = &mut self.slices;
+        self.queue.advance(now, |path| {
+            bounds.push((slices.len(), path.len()));
+            slices.extend(path);
+        });
 
         self.bounds.drain(.. self.clean);
 
@@ -94,6 +167,48 @@ impl Activations {
         self.clean = self.bounds.len();
     }
 
+    /// Re-activates the active paths that extend `path`, so that they remain active after `advance`.
+    ///
+    /// This allows a scheduler to defer tasks it has not yet scheduled to the next active set.
+    ///
+    /// # Examples
+    /// ```
+    /// use std::time::Instant;
+    /// use timely::scheduling::Activations;
+    ///
+    /// let mut activations = Activations::new(Instant::now());
+    /// activations.activate(&[0, 1]);
+    /// activations.activate(&[2]);
+    /// activations.advance();
+    ///
+    /// // Only activations extending `[0]` survive the next advance.
+    /// activations.defer(&[0]);
+    /// activations.advance();
+    ///
+    /// let mut active = Vec::new();
+    /// activations.for_extensions(&[], |index| active.push(index));
+    /// assert_eq!(active, vec![0]);
+    /// ```
+    pub fn defer(&mut self, path: &[usize]) {
+        self.defer_after(path, Duration::new(0, 0));
+    }
+
+    /// Re-activates the active paths that extend `path` after `delay`.
+    ///
+    /// This allows a scheduler to postpone tasks it declines to schedule, without losing their activations.
+    pub fn defer_after(&mut self, path: &[usize], delay: Duration) {
+        let mut deferred = Vec::new();
+        for (offset, length) in self.bounds[.. self.clean].iter() {
+            let active = &self.slices[*offset .. (*offset + *length)];
+            if active.starts_with(path) {
+                deferred.push(active.to_vec());
+            }
+        }
+        for active in deferred {
+            self.activate_after(&active[..], delay);
+        }
+    }
+
     /// Maps a function across activated paths.
     pub fn map_active(&self, logic: impl Fn(&[usize])) {
         for (offset, length) in self.bounds.iter() {
@@ -134,7 +249,7 @@ impl Activations {
     pub fn sync(&self) -> SyncActivations {
         SyncActivations {
             tx: self.tx.clone(),
-            thread: std::thread::current(),
+            buzzer: Buzzer::new(),
         }
     }
 
@@ -148,19 +263,33 @@ impl Activations {
             Some(Duration::new(0,0))
         }
         else {
-            self.queue.peek().map(|Reverse((t,_a))| {
-                let elapsed = self.timer.elapsed();
-                if t < &elapsed { Duration::new(0,0) }
-                else { *t - elapsed }
+            self.queue.next_deadline().map(|deadline| {
+                let moment = Duration::from_millis(deadline);
+                let elapsed = self.clock.elapsed(self.timer);
+                if moment < elapsed { Duration::new(0,0) }
+                else { moment - elapsed }
             })
         }
     }
 }
 
+/// A handle to a delayed activation, with which to cancel it.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub struct DelayedActivation {
+    /// The identifier of the activation's timer, absent for immediate activations.
+    id: Option<usize>,
+}
+
+/// The number of whole milliseconds in `duration`, rounded up.
+fn ceil_millis(duration: Duration) -> u64 {
+    let millis = duration.as_millis() as u64;
+    if Duration::from_millis(millis) < duration { millis + 1 } else { millis }
+}
+
 /// A thread-safe handle to an `Activations`.
 pub struct SyncActivations {
     tx: Sender<Vec<usize>>,
-    thread: Thread,
+    buzzer: Buzzer,
 }
 
 impl SyncActivations {
@@ -182,7 +311,7 @@ impl SyncActivations {
         for path in paths.into_iter() {
             self.tx.send(path).map_err(|_| SyncActivationError)?;
         }
-        self.thread.unpark();
+        self.buzzer.buzz();
         Ok(())
     }
 }
@@ -209,16 +338,173 @@ impl Activator {
     }
 
     /// Activates the associated path after a specified duration.
-    pub fn activate_after(&self, delay: Duration) {
-        if delay == Duration::new(0, 0) {
-            self.activate();
-        }
-        else {
-            self.queue
-                .borrow_mut()
-                .activate_after(&self.path[..], delay);
+    ///
+    /// The returned handle can cancel the activation, with `cancel`.
+    pub fn activate_after(&self, delay: Duration) -> DelayedActivation {
+        self.queue
+            .borrow_mut()
+            .activate_after(&self.path[..], delay)
+    }
+
+    /// Cancels a delayed activation, returning `true` if it had not yet occurred.
+    pub fn cancel(&self, activation: DelayedActivation) -> bool {
+        self.queue
+            .borrow_mut()
+            .cancel(activation)
+    }
+
+    /// Activates the associated path after a specified duration, returning a token for the activation.
+    ///
+    /// Unlike `activate_after`, the token can re-arm the activation, and cancels it when dropped,
+    /// which suits operators that debounce or time out their work.
+    ///
+    /// # Examples
+    /// ```
+    /// use std::time::Duration;
+    /// use timely::dataflow::operators::Inspect;
+    /// use timely::dataflow::operators::generic::operator::source;
+    /// use timely::scheduling::Scheduler;
+    ///
+    /// timely::execute_from_args(::std::env::args(), |worker| {
+    ///     worker.dataflow::<usize,_,_>(|scope| {
+    ///         source(scope, "Timeout", |capability, info| {
+    ///             let activator = scope.activator_for(&info.address[..]);
+    ///             let token = activator.schedule_after(Duration::from_secs(60));
+    ///             let mut capability = Some(capability);
+    ///             move |output| {
+    ///                 if let Some(cap) = capability.take() {
+    ///                     output.session(&cap).give(0);
+    ///                     // Re-arming replaces the pending activation, rather than adding another.
+    ///                     token.rearm(Duration::from_secs(60));
+    ///                     assert!(token.is_pending());
+    ///                     assert!(token.cancel());
+    ///                     assert!(!token.is_pending());
+    ///                 }
+    ///             }
+    ///         })
+    ///         .inspect(|x: &usize| println!("seen: {:?}", x));
+    ///     });
+    /// }).unwrap();
+    /// ```
+    pub fn schedule_after(&self, delay: Duration) -> ActivationToken {
+        let pending = Cell::new(Some(self.activate_after(delay)));
+        ActivationToken { activator: Activator::new(&self.path[..], self.queue.clone()), pending }
+    }
+
+    /// The clock by which delayed activations are timed.
+    pub fn clock(&self) -> Clock {
+        self.queue
+            .borrow()
+            .clock()
+            .clone()
+    }
+}
+
+/// A delayed activation of an operator, which can be cancelled or re-armed.
+///
+/// The pending activation, if any, is cancelled when the token is dropped.
+pub struct ActivationToken {
+    activator: Activator,
+    pending: Cell<Option<DelayedActivation>>,
+}
+
+impl ActivationToken {
+    /// Cancels the pending activation, returning `true` if it had not yet occurred.
+    pub fn cancel(&self) -> bool {
+        self.pending.take().map(|activation| self.activator.cancel(activation)).unwrap_or(false)
+    }
+
+    /// Replaces any pending activation with one after `delay`.
+    pub fn rearm(&self, delay: Duration) {
+        self.cancel();
+        self.pending.set(Some(self.activator.activate_after(delay)));
+    }
+
+    /// Indicates that the activation has neither occurred nor been cancelled.
+    pub fn is_pending(&self) -> bool {
+        self.pending.get().map(|activation| self.activator.queue.borrow().is_pending(&activation)).unwrap_or(false)
+    }
+}
+
+impl Drop for ActivationToken {
+    fn drop(&mut self) {
+        self.cancel();
+    }
+}
+
+/// Channels to each worker, carrying the paths of operators to activate.
+pub(crate) type RemotePushers = Rc<RefCell<Vec<Box<dyn Push<Message<Vec<usize>>>>>>>;
+
+/// A capability to activate a specific path on a specific worker, possibly in another process.
+///
+/// Data sent to another worker through a channel already activates the operator that receives
+/// it, and a `RemoteActivator` is for explicit wake-ups otherwise, for example to prompt an
+/// operator to look for work it shares with the activating worker by other means. Paths are
+/// interpreted by the receiving worker, and identify the same operator on all workers as long
+/// as they construct their dataflows in the same order.
+///
+/// # Examples
+/// ```
+/// use timely::dataflow::operators::Inspect;
+/// use timely::dataflow::operators::generic::operator::source;
+/// use timely::worker::AsWorker;
+///
+/// timely::execute_from_args(::std::env::args(), |worker| {
+///
+///     let peers = worker.peers();
+///     worker.dataflow::<usize,_,_>(|scope| {
+///         let activator_scope = scope.clone();
+///         source(scope, "Source", move |capability, info| {
+///             // Activators for the same operator on each worker.
+///             let activators = (0 .. peers)
+///                 .map(|worker| activator_scope.remote_activator_for(worker, &info.address[..]).unwrap())
+///                 .collect::<Vec<_>>();
+///             let mut cap = Some(capability);
+///             move |output| {
+///                 if let Some(cap) = cap.take() {
+///                     output.session(&cap).give(0);
+///                     // Wake each peer, though they would be scheduled anyhow.
+///                     for activator in activators.iter() {
+///                         activator.activate();
+///                     }
+///                 }
+///             }
+///         })
+///         .inspect(|x: &usize| println!("seen: {:?}", x));
+///     });
+/// }).unwrap();
+/// ```
+pub struct RemoteActivator {
+    worker: usize,
+    path: Vec<usize>,
+    pushers: RemotePushers,
+}
+
+impl RemoteActivator {
+    /// Creates a new activation handle for `path` on `worker`.
+    pub(crate) fn new(worker: usize, path: &[usize], pushers: RemotePushers) -> Self {
+        Self {
+            worker,
+            path: path.to_vec(),
+            pushers,
         }
     }
+
+    /// The index of the worker whose operator is activated.
+    pub fn worker(&self) -> usize {
+        self.worker
+    }
+
+    /// Activates the associated path on the associated worker.
+    ///
+    /// The activation is sent immediately, and wakes the worker if it is parked. As with data
+    /// sent through channels, the worker should not already have shut down.
+    pub fn activate(&self) {
+        let mut pushers = self.pushers.borrow_mut();
+        let pusher = &mut pushers[self.worker];
+        pusher.push(&mut Some(Message::from_typed(self.path.clone())));
+        pusher.done();
+    }
 }
 
 /// A thread-safe version of `Activator`.
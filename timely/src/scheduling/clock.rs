@@ -0,0 +1,69 @@
+//! Sources of processing time.
+//!
+//! Delayed activations, operator timers, and other processing-time behavior consult the clock
+//! of their worker. A worker uses the system clock by default, and may instead use a manual
+//! clock which only advances when asked to, so that tests can exercise timer-dependent
+//! operators deterministically and without sleeping.
+
+use std::rc::Rc;
+use std::cell::Cell;
+use crate::time::{Duration, Instant};
+
+/// A shared source of the current processing time.
+///
+/// # Examples
+/// ```
+/// use std::time::{Duration, Instant};
+/// use timely::scheduling::Clock;
+///
+/// let start = Instant::now();
+/// let clock = Clock::manual(start);
+/// assert_eq!(clock.now(), start);
+///
+/// // Clones share the same time.
+/// clock.clone().advance(Duration::from_secs(5));
+/// assert_eq!(clock.now(), start + Duration::from_secs(5));
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct Clock {
+    manual: Option<Rc<Cell<Instant>>>,
+}
+
+impl Clock {
+    /// A clock reporting the system time.
+    pub fn system() -> Self {
+        Clock { manual: None }
+    }
+
+    /// A clock starting at `start`, which only advances through `advance`.
+    pub fn manual(start: Instant) -> Self {
+        Clock { manual: Some(Rc::new(Cell::new(start))) }
+    }
+
+    /// The current time.
+    pub fn now(&self) -> Instant {
+        match &self.manual {
+            Some(now) => now.get(),
+            None => Instant::now(),
+        }
+    }
+
+    /// The time elapsed since `earlier`, or zero if `earlier` is in the future.
+    pub fn elapsed(&self, earlier: Instant) -> Duration {
+        self.now().saturating_duration_since(earlier)
+    }
+
+    /// Advances a manual clock by `duration`.
+    ///
+    /// This method has no effect on the system clock.
+    pub fn advance(&self, duration: Duration) {
+        if let Some(now) = &self.manual {
+            now.set(now.get() + duration);
+        }
+    }
+
+    /// Returns `true` if the clock only advances through `advance`.
+    pub fn is_manual(&self) -> bool {
+        self.manual.is_some()
+    }
+}
@@ -0,0 +1,114 @@
+//! Cooperative yielding for long-running operators.
+//!
+//! An operator presented with a large batch of work may occupy its worker for a long time,
+//! delaying every other operator. A worker may set a budget for the duration of each operator
+//! activation, and operators may consult a `Yielder` to learn when their budget is exhausted,
+//! at which point they should return and expect to be re-activated immediately.
+
+use std::rc::Rc;
+use std::cell::Cell;
+use crate::time::{Duration, Instant};
+
+use crate::scheduling::Activator;
+
+/// A budget for the duration of each operator activation, shared among a worker's operators.
+///
+/// The budget defaults to `None`, in which case activations never exhaust their fuel.
+#[derive(Clone, Default)]
+pub struct Fuel {
+    budget: Rc<Cell<Option<Duration>>>,
+    deadline: Rc<Cell<Option<Instant>>>,
+}
+
+impl Fuel {
+    /// Allocates a new fuel tracker with no budget.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the budget for each subsequent activation.
+    pub fn set_budget(&self, budget: Option<Duration>) {
+        self.budget.set(budget);
+    }
+
+    /// The budget for each activation.
+    pub fn budget(&self) -> Option<Duration> {
+        self.budget.get()
+    }
+
+    /// Replenishes the fuel, at the start of an activation.
+    pub fn refuel(&self) {
+        self.deadline.set(self.budget.get().map(|budget| Instant::now() + budget));
+    }
+
+    /// Indicates that the current activation has exhausted its budget.
+    pub fn exhausted(&self) -> bool {
+        self.deadline.get().map(|deadline| Instant::now() >= deadline).unwrap_or(false)
+    }
+}
+
+/// Indicates to an operator whether it should yield, and re-activates it if so.
+///
+/// # Examples
+/// ```
+/// use timely::dataflow::operators::Inspect;
+/// use timely::dataflow::operators::generic::operator::source;
+/// use timely::scheduling::Scheduler;
+///
+/// timely::execute_from_args(::std::env::args(), |worker| {
+///
+///     // Bound each operator activation to a millisecond.
+///     worker.set_activation_budget(Some(::std::time::Duration::from_millis(1)));
+///
+///     worker.dataflow::<usize,_,_>(|scope| {
+///         let yielder_scope = scope.clone();
+///         source(scope, "Source", move |capability, info| {
+///
+///             let yielder = yielder_scope.yielder_for(&info.address[..]);
+///             let mut cap = Some(capability);
+///             let mut next = 0;
+///
+///             move |output| {
+///                 if let Some(cap) = cap.as_ref() {
+///                     let mut session = output.session(cap);
+///                     // Produce records until done, or until asked to yield.
+///                     while next < 100_000 && !yielder.should_yield() {
+///                         session.give(next);
+///                         next += 1;
+///                     }
+///                 }
+///                 if next == 100_000 {
+///                     cap = None;
+///                 }
+///             }
+///         })
+///         .inspect(|_x: &usize| { });
+///     });
+/// });
+/// ```
+pub struct Yielder {
+    fuel: Fuel,
+    activator: Activator,
+}
+
+impl Yielder {
+    /// Creates a new yielder from fuel and an activator for the operator.
+    pub fn new(fuel: Fuel, activator: Activator) -> Self {
+        Yielder { fuel, activator }
+    }
+
+    /// Indicates that the operator should yield.
+    ///
+    /// If the current activation has exhausted its budget, this method activates the operator
+    /// so that it will be scheduled again, and returns `true`. The operator should then return
+    /// promptly and resume its work when next scheduled.
+    pub fn should_yield(&self) -> bool {
+        if self.fuel.exhausted() {
+            self.activator.activate();
+            true
+        }
+        else {
+            false
+        }
+    }
+}
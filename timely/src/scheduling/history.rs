@@ -0,0 +1,175 @@
+//! A record of recent operator activations.
+//!
+//! A worker may keep its most recent operator activations in a bounded buffer, which answers
+//! what the worker has been doing recently without the volume of a full log. The buffer is
+//! disabled by default, and enabled with `Worker::record_activations`.
+//!
+//! The activations may also be folded into stacks of operator names, with `write_folded`, from
+//! which tools such as `inferno` or `flamegraph.pl` render a flamegraph of where the worker's time
+//! has gone across its nested scopes.
+
+use std::rc::Rc;
+use std::cell::RefCell;
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use crate::time::{Duration, Instant};
+
+use crate::dataflow::graph::Operator;
+
+/// An activation of an operator.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Hash, Eq, PartialEq, Ord, PartialOrd)]
+pub struct ActivationRecord {
+    /// Worker-unique identifier for the operator, linkable to `OperatesEvent`.
+    pub id: usize,
+    /// The start of the activation, as the time elapsed since the worker started.
+    pub start: Duration,
+    /// The duration of the activation.
+    pub duration: Duration,
+}
+
+/// The most recent operator activations of a worker, shared among its scopes.
+///
+/// # Examples
+/// ```
+/// use std::time::{Duration, Instant};
+/// use timely::scheduling::history::ActivationHistory;
+///
+/// let history = ActivationHistory::new(Instant::now());
+/// history.set_capacity(Some(2));
+/// for id in 0 .. 3 {
+///     history.record(id, Instant::now(), Duration::from_millis(1));
+/// }
+///
+/// // Only the two most recent activations are retained, oldest first.
+/// let ids = history.records().iter().map(|record| record.id).collect::<Vec<_>>();
+/// assert_eq!(ids, vec![1, 2]);
+/// ```
+#[derive(Clone)]
+pub struct ActivationHistory {
+    origin: Instant,
+    ring: Rc<RefCell<Option<Ring>>>,
+}
+
+struct Ring {
+    capacity: usize,
+    records: VecDeque<ActivationRecord>,
+}
+
+impl ActivationHistory {
+    /// Allocates a disabled history, reporting the starts of activations relative to `origin`.
+    pub fn new(origin: Instant) -> Self {
+        ActivationHistory { origin, ring: Rc::new(RefCell::new(None)) }
+    }
+
+    /// Retains the most recent `capacity` activations, or none if `capacity` is `None`.
+    ///
+    /// Reducing the capacity discards the oldest records, and disabling the history discards them all.
+    pub fn set_capacity(&self, capacity: Option<usize>) {
+        let mut ring = self.ring.borrow_mut();
+        match capacity {
+            Some(capacity) => {
+                let ring = ring.get_or_insert_with(|| Ring { capacity, records: VecDeque::with_capacity(capacity) });
+                ring.capacity = capacity;
+                while ring.records.len() > capacity {
+                    ring.records.pop_front();
+                }
+            },
+            None => { *ring = None; },
+        }
+    }
+
+    /// The number of activations retained, if the history is enabled.
+    pub fn capacity(&self) -> Option<usize> {
+        self.ring.borrow().as_ref().map(|ring| ring.capacity)
+    }
+
+    /// Records an activation of operator `id` from `start` lasting `duration`, if enabled.
+    #[inline]
+    pub fn record(&self, id: usize, start: Instant, duration: Duration) {
+        if let Some(ring) = self.ring.borrow_mut().as_mut() {
+            if ring.capacity > 0 {
+                if ring.records.len() == ring.capacity {
+                    ring.records.pop_front();
+                }
+                ring.records.push_back(ActivationRecord {
+                    id,
+                    start: start.saturating_duration_since(self.origin),
+                    duration,
+                });
+            }
+        }
+    }
+
+    /// The retained activations, in the order they completed.
+    ///
+    /// Scopes complete after the operators they contain, and so follow their activations.
+    pub fn records(&self) -> Vec<ActivationRecord> {
+        self.ring.borrow().as_ref().map(|ring| ring.records.iter().cloned().collect()).unwrap_or_default()
+    }
+}
+
+/// Writes `records` to `writer` as folded stacks, one line for each path of names with time spent in it.
+///
+/// Each line contains the names of an operator's enclosing scopes and then its own, separated by
+/// semicolons, followed by a space and the time spent in activations of the operator, in
+/// microseconds. A scope is charged for the time of its activations not spent in the operators
+/// it contains, and operators with the same path of names are charged together. The operators,
+/// as described by `Worker::graph`, supply the names; records of operators not among them are
+/// ignored, and semicolons in names are replaced by colons.
+///
+/// # Examples
+/// ```
+/// use timely::dataflow::Scope;
+/// use timely::dataflow::operators::{ToStream, Map, Inspect};
+/// use timely::scheduling::history::write_folded;
+///
+/// timely::execute_from_args(::std::env::args(), |worker| {
+///
+///     worker.record_activations(Some(1000));
+///     worker.dataflow_named::<usize,_,_>("Numbers", |scope| {
+///         scope.region_named("Squares", |inner| {
+///             (0..10).to_stream(inner)
+///                    .map(|x| x * x)
+///                    .inspect(|x| println!("seen: {:?}", x));
+///         });
+///     });
+///     while worker.step() { }
+///
+///     let mut folded = Vec::new();
+///     write_folded(&mut folded, &worker.activation_history(), &worker.graph().operators).unwrap();
+///     let folded = String::from_utf8(folded).unwrap();
+///     assert!(folded.lines().any(|line| line.starts_with("Numbers;Squares;Map ")));
+/// }).unwrap();
+/// ```
+pub fn write_folded<W: ::std::io::Write>(writer: &mut W, records: &[ActivationRecord], operators: &[Operator]) -> ::std::io::Result<()> {
+    let operators = operators.iter().map(|op| (op.id, op)).collect::<HashMap<_,_>>();
+
+    // The total time of the activations of each operator.
+    let mut totals: HashMap<usize, Duration> = HashMap::new();
+    for record in records.iter().filter(|record| operators.contains_key(&record.id)) {
+        *totals.entry(record.id).or_default() += record.duration;
+    }
+
+    // The time of the operators each scope contains, by the address of the scope.
+    let mut contained: HashMap<&[usize], Duration> = HashMap::new();
+    for (id, total) in totals.iter() {
+        let addr = &operators[id].addr[..];
+        if let Some((_, scope)) = addr.split_last() {
+            *contained.entry(scope).or_default() += *total;
+        }
+    }
+
+    let mut stacks: BTreeMap<String, Duration> = BTreeMap::new();
+    for (id, total) in totals.iter() {
+        let op = operators[id];
+        let own = total.saturating_sub(contained.get(&op.addr[..]).copied().unwrap_or_default());
+        let path = op.names.iter().map(|name| name.replace(';', ":")).collect::<Vec<_>>().join(";");
+        *stacks.entry(path).or_default() += own;
+    }
+
+    for (path, time) in stacks {
+        if time.as_micros() > 0 {
+            writeln!(writer, "{} {}", path, time.as_micros())?;
+        }
+    }
+    Ok(())
+}
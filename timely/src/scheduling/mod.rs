@@ -3,9 +3,20 @@
 use std::rc::Rc;
 use std::cell::RefCell;
 
+use crate::dataflow::graph::{OperatorStatus, ProgressStats};
+
 pub mod activate;
+pub mod clock;
+pub mod fuel;
+pub mod history;
+pub mod priority;
+pub mod saturation;
+pub mod wheel;
 
-pub use self::activate::{Activations, Activator, ActivateOnDrop, SyncActivator};
+pub use self::activate::{Activations, Activator, ActivateOnDrop, SyncActivator, DelayedActivation, ActivationToken, RemoteActivator};
+pub use self::clock::Clock;
+pub use self::fuel::{Fuel, Yielder};
+pub use self::priority::OperatorPriority;
 
 /// A type that can be scheduled.
 pub trait Schedule {
@@ -18,6 +29,19 @@ pub trait Schedule {
     /// The return value indicates whether `self` has outstanding
     /// work and would be upset if the computation terminated.
     fn schedule(&mut self) -> bool;
+    /// Reports the current state of operators contained within `self`.
+    ///
+    /// Scopes report each of their children, and those of nested scopes. Other operators
+    /// have nothing to report.
+    fn introspect(&self, _statuses: &mut Vec<OperatorStatus>) { }
+    /// Reports counters of the progress traffic of `self` and of the scopes it contains.
+    ///
+    /// Scopes report themselves and their nested scopes. Other operators have nothing to report.
+    fn progress_stats(&self, _stats: &mut Vec<ProgressStats>) { }
+    /// The scheduling priority of `self` among the operators of its scope, if it has one.
+    ///
+    /// Operators without a priority are scheduled at the default level.
+    fn priority(&self) -> Option<OperatorPriority> { None }
 }
 
 /// Methods for types which schedule fibers.
@@ -34,4 +58,13 @@ pub trait Scheduler {
         let sync_activations = self.activations().borrow().sync();
         SyncActivator::new(path, sync_activations)
     }
+    /// The clock by which processing time is measured.
+    fn clock(&self) -> Clock {
+        self.activations().borrow().clock().clone()
+    }
+    /// Constructs a `Yielder` tied to the specified operator address.
+    fn yielder_for(&self, path: &[usize]) -> Yielder {
+        let fuel = self.activations().borrow().fuel().clone();
+        Yielder::new(fuel, self.activator_for(path))
+    }
 }
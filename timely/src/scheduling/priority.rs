@@ -0,0 +1,63 @@
+//! Scheduling priorities for operators within their scopes.
+//!
+//! When a scope is scheduled it schedules each of its active children, by default in the order
+//! in which they were constructed. Operators with higher priority levels are scheduled first,
+//! so that latency-critical operators, for example those on control streams, act before bulk
+//! operators in the same step.
+
+use std::rc::Rc;
+use std::cell::Cell;
+
+/// The scheduling level of an operator among the active operators of its scope.
+///
+/// Active operators of higher levels are scheduled first, and those of the same level in the
+/// order of their construction. The level defaults to zero, and is shared among clones of the
+/// priority, so that it may be adjusted while the dataflow runs.
+///
+/// # Examples
+/// ```
+/// use timely::dataflow::operators::ToStream;
+/// use timely::dataflow::operators::generic::builder_rc::OperatorBuilder;
+/// use timely::dataflow::channels::pact::Pipeline;
+///
+/// timely::example(|scope| {
+///     let stream = (0..10).to_stream(scope);
+///
+///     let mut builder = OperatorBuilder::new("Control".to_owned(), scope.clone());
+///     let mut input = builder.new_input(&stream, Pipeline);
+///     builder.set_priority(1);
+///     let priority = builder.priority();
+///
+///     builder.build(move |_capabilities| {
+///         let mut vector = Vec::new();
+///         move |_frontiers| {
+///             input.for_each(|_time, data| data.swap(&mut vector));
+///             // Return to the default level once the operator has seen data.
+///             if !vector.is_empty() {
+///                 priority.set_level(0);
+///             }
+///         }
+///     });
+/// });
+/// ```
+#[derive(Clone, Default, Debug)]
+pub struct OperatorPriority {
+    level: Rc<Cell<usize>>,
+}
+
+impl OperatorPriority {
+    /// Allocates a new priority at `level`.
+    pub fn new(level: usize) -> Self {
+        OperatorPriority { level: Rc::new(Cell::new(level)) }
+    }
+
+    /// The current level.
+    pub fn level(&self) -> usize {
+        self.level.get()
+    }
+
+    /// Sets the level, which takes effect the next time the operator is activated.
+    pub fn set_level(&self, level: usize) {
+        self.level.set(level);
+    }
+}
@@ -0,0 +1,91 @@
+//! Detection of saturated channels and operators.
+//!
+//! A worker may report, as `SaturationEvent`s in its `"timely"` log, the channels whose queues
+//! of messages grow beyond a threshold, and the operators that repeatedly yield while input
+//! remains for them to read. Both are disabled by default, and enabled with
+//! `Worker::set_channel_saturation` and `Worker::set_operator_saturation`. The network threads
+//! of a process report their own saturated queues, as `QueueEvent`s of their loggers.
+
+use std::rc::Rc;
+use std::cell::{Cell, RefCell};
+use std::collections::HashMap;
+
+use crate::communication::allocator::Event;
+
+/// Thresholds for saturation, and the state by which it is detected, shared among a worker's scopes.
+#[derive(Clone, Default)]
+pub struct Saturation {
+    channel_threshold: Rc<Cell<Option<usize>>>,
+    operator_threshold: Rc<Cell<Option<usize>>>,
+    // For each channel, the messages awaiting this worker and whether they exceed the threshold.
+    pending: Rc<RefCell<HashMap<usize, (usize, bool)>>>,
+    // For each operator address, the number of its inputs last left with messages remaining.
+    undrained: Rc<RefCell<Undrained>>,
+}
+
+/// Counts of unread inputs, by operator address.
+type Undrained = HashMap<Vec<usize>, Rc<Cell<usize>>>;
+
+impl Saturation {
+    /// Allocates saturation detection with no thresholds.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Reports channels once more than `threshold` messages await this worker, or none if `None`.
+    ///
+    /// Queued messages are counted from the time the threshold is set.
+    pub fn set_channel_threshold(&self, threshold: Option<usize>) {
+        self.channel_threshold.set(threshold);
+        self.pending.borrow_mut().clear();
+    }
+
+    /// The number of queued messages beyond which channels are reported.
+    pub fn channel_threshold(&self) -> Option<usize> {
+        self.channel_threshold.get()
+    }
+
+    /// Reports operators once they yield with input remaining `threshold` times in a row, or none if `None`.
+    pub fn set_operator_threshold(&self, threshold: Option<usize>) {
+        self.operator_threshold.set(threshold);
+    }
+
+    /// The number of successive activations leaving input, at which operators are reported.
+    pub fn operator_threshold(&self) -> Option<usize> {
+        self.operator_threshold.get()
+    }
+
+    /// The number of inputs of the operator at `address` last left with messages remaining.
+    ///
+    /// The count is shared by the inputs of the operator, which maintain it as they are read.
+    pub fn undrained_inputs(&self, address: &[usize]) -> Rc<Cell<usize>> {
+        self.undrained.borrow_mut().entry(address.to_vec()).or_default().clone()
+    }
+
+    /// Discards the state of the operators and channels of the dataflow at `dataflow`.
+    pub fn forget_dataflow(&self, dataflow: usize, channels: &[usize]) {
+        self.undrained.borrow_mut().retain(|address, _| address[0] != dataflow);
+        let mut pending = self.pending.borrow_mut();
+        for channel in channels.iter() {
+            pending.remove(channel);
+        }
+    }
+
+    /// Accounts for `event` on `channel`, and returns the queued messages if the channel has become saturated.
+    ///
+    /// Messages are counted as queued from when they are pushed until their puller next finds
+    /// the channel empty, and so the count is an estimate. A saturated channel is reported again
+    /// only once its queue falls back to the threshold.
+    pub fn record(&self, channel: usize, event: &Event) -> Option<usize> {
+        let threshold = self.channel_threshold.get()?;
+        let mut pending = self.pending.borrow_mut();
+        let (queued, saturated) = pending.entry(channel).or_insert((0, false));
+        match event {
+            Event::Pushed(count) => { *queued += count; },
+            Event::Pulled(count) => { *queued = queued.saturating_sub(*count); },
+        }
+        let newly = *queued > threshold && !*saturated;
+        *saturated = *queued > threshold;
+        if newly { Some(*queued) } else { None }
+    }
+}
@@ -0,0 +1,189 @@
+//! A hierarchical timer wheel, for many fine-grained timers.
+//!
+//! Timers are placed in slots of wheels of increasing granularity, according to how far in the
+//! future they expire. As time advances, the slots of coarser wheels are cascaded into finer
+//! wheels, and timers in the current slot of the finest wheel expire. Inserting and cancelling
+//! timers take constant time, and advancing takes time proportional to the elapsed ticks and to
+//! the number of expiring or cascading timers.
+
+use std::collections::HashMap;
+
+/// The number of bits of the tick indexing the slots of each wheel.
+const BITS: u32 = 6;
+/// The number of slots in each wheel.
+const SLOTS: usize = 1 << BITS;
+/// The number of wheels, beyond which timers wait in an overflow list.
+const LEVELS: usize = 4;
+
+/// A hierarchical timer wheel, expiring payloads of type `P` at integer ticks.
+///
+/// # Examples
+/// ```
+/// use timely::scheduling::wheel::TimerWheel;
+///
+/// let mut wheel = TimerWheel::new();
+/// let soon = wheel.insert(5, "soon");
+/// let _later = wheel.insert(1_000, "later");
+/// let cancelled = wheel.insert(5, "cancelled");
+/// assert_eq!(wheel.cancel(cancelled), Some("cancelled"));
+/// assert_eq!(wheel.next_deadline(), Some(5));
+///
+/// let mut expired = Vec::new();
+/// wheel.advance(10, |payload| expired.push(payload));
+/// assert_eq!(expired, vec!["soon"]);
+/// assert_eq!(wheel.cancel(soon), None);
+///
+/// wheel.advance(1_000, |payload| expired.push(payload));
+/// assert_eq!(expired, vec!["soon", "later"]);
+/// assert!(wheel.is_empty());
+/// ```
+pub struct TimerWheel<P> {
+    /// The tick up to which timers have expired.
+    current: u64,
+    /// Identifiers of timers in each slot of each wheel, including cancelled timers.
+    wheels: Vec<Vec<Vec<usize>>>,
+    /// Identifiers of timers beyond the range of the wheels.
+    overflow: Vec<usize>,
+    /// Pending timers, with their deadlines and payloads.
+    timers: HashMap<usize, (u64, P)>,
+    next_id: usize,
+}
+
+impl<P> TimerWheel<P> {
+
+    /// Allocates a new empty timer wheel at tick zero.
+    pub fn new() -> Self {
+        TimerWheel {
+            current: 0,
+            wheels: (0 .. LEVELS).map(|_| (0 .. SLOTS).map(|_| Vec::new()).collect()).collect(),
+            overflow: Vec::new(),
+            timers: HashMap::new(),
+            next_id: 0,
+        }
+    }
+
+    /// Inserts a timer expiring at `deadline` with `payload`, and returns its identifier.
+    ///
+    /// Timers with deadlines not after the current tick expire at the next call to `advance`.
+    pub fn insert(&mut self, deadline: u64, payload: P) -> usize {
+        let id = self.next_id;
+        self.next_id += 1;
+        self.timers.insert(id, (deadline, payload));
+        self.place(id, deadline);
+        id
+    }
+
+    /// Cancels the timer identified by `id`, returning its payload if it had not yet expired.
+    pub fn cancel(&mut self, id: usize) -> Option<P> {
+        // The identifier remains in its slot, and is discarded when the slot is next visited.
+        self.timers.remove(&id).map(|(_deadline, payload)| payload)
+    }
+
+    /// Indicates that the timer identified by `id` has neither expired nor been cancelled.
+    pub fn contains(&self, id: usize) -> bool {
+        self.timers.contains_key(&id)
+    }
+
+    /// Indicates that no timers are pending.
+    pub fn is_empty(&self) -> bool {
+        self.timers.is_empty()
+    }
+
+    /// Expires timers with deadlines up to and including `now`, presenting their payloads to `action`.
+    pub fn advance(&mut self, now: u64, mut action: impl FnMut(P)) {
+        // Timers placed at or before the current tick are found in its slot.
+        self.expire(&mut action);
+        while self.current < now {
+            if self.timers.is_empty() {
+                self.current = now;
+                return;
+            }
+            self.current += 1;
+            // Cascade coarser wheels whose slots begin at the new tick, coarsest first.
+            for level in (1 .. LEVELS).rev() {
+                if self.current & ((1 << (BITS * level as u32)) - 1) == 0 {
+                    if level == LEVELS - 1 {
+                        let overflow = ::std::mem::take(&mut self.overflow);
+                        self.replace(overflow);
+                    }
+                    let slot = self.slot(level, self.current);
+                    let ids = ::std::mem::take(&mut self.wheels[level][slot]);
+                    self.replace(ids);
+                }
+            }
+            self.expire(&mut action);
+        }
+    }
+
+    /// The earliest deadline of a pending timer.
+    ///
+    /// The deadline is found by inspecting the next occupied slot of each wheel, rather than
+    /// all pending timers.
+    pub fn next_deadline(&self) -> Option<u64> {
+        let mut earliest: Option<u64> = None;
+        for level in 0 .. LEVELS {
+            // The current slot of a coarser wheel holds only timers of its next revolution.
+            let start = self.slot(level, self.current) + if level > 0 { 1 } else { 0 };
+            for offset in 0 .. SLOTS {
+                let slot = &self.wheels[level][(start + offset) % SLOTS];
+                if let Some(deadline) = self.earliest(slot) {
+                    earliest = Some(earliest.map_or(deadline, |earliest| earliest.min(deadline)));
+                    break;
+                }
+            }
+        }
+        if let Some(deadline) = self.earliest(&self.overflow) {
+            earliest = Some(earliest.map_or(deadline, |earliest| earliest.min(deadline)));
+        }
+        earliest
+    }
+
+    /// The earliest deadline of the pending timers among `ids`.
+    fn earliest(&self, ids: &[usize]) -> Option<u64> {
+        ids.iter().filter_map(|id| self.timers.get(id)).map(|timer| timer.0).min()
+    }
+
+    /// The slot of `tick` in the wheel at `level`.
+    fn slot(&self, level: usize, tick: u64) -> usize {
+        ((tick >> (BITS * level as u32)) as usize) & (SLOTS - 1)
+    }
+
+    /// Places the timer `id` in the finest wheel whose range includes `deadline`.
+    fn place(&mut self, id: usize, deadline: u64) {
+        let delay = deadline.saturating_sub(self.current);
+        match (0 .. LEVELS).find(|level| delay < 1 << (BITS * (*level as u32 + 1))) {
+            Some(level) => {
+                let tick = ::std::cmp::max(deadline, self.current);
+                let slot = self.slot(level, tick);
+                self.wheels[level][slot].push(id);
+            },
+            None => self.overflow.push(id),
+        }
+    }
+
+    /// Places pending timers among `ids` again, relative to the current tick.
+    fn replace(&mut self, ids: Vec<usize>) {
+        for id in ids {
+            if let Some(deadline) = self.timers.get(&id).map(|timer| timer.0) {
+                self.place(id, deadline);
+            }
+        }
+    }
+
+    /// Expires the pending timers in the slot of the current tick in the finest wheel.
+    fn expire(&mut self, action: &mut impl FnMut(P)) {
+        let slot = self.slot(0, self.current);
+        let ids = ::std::mem::take(&mut self.wheels[0][slot]);
+        for id in ids {
+            if let Some((_deadline, payload)) = self.timers.remove(&id) {
+                action(payload);
+            }
+        }
+    }
+}
+
+impl<P> Default for TimerWheel<P> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
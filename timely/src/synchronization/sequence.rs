@@ -2,7 +2,7 @@
 
 use std::rc::Rc;
 use std::cell::RefCell;
-use std::time::{Instant, Duration};
+use crate::time::{Instant, Duration};
 use std::collections::VecDeque;
 
 use crate::{communication::Allocate, ExchangeData, PartialOrder};
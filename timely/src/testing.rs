@@ -0,0 +1,163 @@
+//! Deterministic tests of dataflows.
+//!
+//! A `Harness` drives a dataflow on a single worker, from programmatic input to captured output,
+//! so that tests can assert on the output of each epoch. The worker measures processing time
+//! by a manual clock, which the test advances explicitly; delayed activations, operator timers,
+//! and time quotas observe only this clock, so that timer-dependent operators can be tested
+//! without sleeping.
+//!
+//! # Examples
+//! ```
+//! use std::time::Duration;
+//! use timely::dataflow::operators::Accumulate;
+//! use timely::testing::Harness;
+//!
+//! let mut harness = Harness::<usize,_,_>::new(|stream| stream.count_every(Duration::from_secs(60)));
+//!
+//! harness.send("a");
+//! harness.send("b");
+//! harness.settle();
+//! assert!(harness.output_at(&0).is_empty());
+//!
+//! // A minute later the partial count is reported, without the test waiting a minute.
+//! harness.advance_clock(Duration::from_secs(60));
+//! assert_eq!(harness.output_at(&0), vec![2]);
+//!
+//! // Completing the epoch reports the final count.
+//! harness.advance_to(1);
+//! assert!(harness.complete(&0));
+//! assert_eq!(harness.output_at(&0), vec![2]);
+//! ```
+
+pub mod golden;
+#[cfg(feature = "proptest")]
+pub mod properties;
+
+use std::rc::Rc;
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+use crate::Data;
+use crate::order::TotalOrder;
+use crate::communication::allocator::Thread;
+use crate::dataflow::{InputHandle, ProbeHandle, Stream};
+use crate::dataflow::scopes::Child;
+use crate::dataflow::operators::{Input, Inspect, Probe};
+use crate::progress::Timestamp;
+use crate::progress::timestamp::Refines;
+use crate::scheduling::Clock;
+use crate::worker::Worker;
+
+/// Drives a dataflow from `D` input to `R` output, with processing time advanced explicitly.
+pub struct Harness<T: Timestamp, D: Data, R: Data> {
+    input: InputHandle<T, D>,
+    probe: ProbeHandle<T>,
+    output: Rc<RefCell<BTreeMap<T, Vec<R>>>>,
+    clock: Clock,
+    worker: Worker<Thread>,
+}
+
+impl<T: Timestamp+Refines<()>+TotalOrder, D: Data, R: Data> Harness<T, D, R> {
+
+    /// Constructs the dataflow `logic` applies to the input stream, capturing the stream it returns.
+    ///
+    /// Processing time starts at the creation of the worker, and advances only through
+    /// `advance_clock`.
+    pub fn new<F>(logic: F) -> Self
+    where
+        F: for<'a> FnOnce(&Stream<Child<'a, Worker<Thread>, T>, D>) -> Stream<Child<'a, Worker<Thread>, T>, R>,
+    {
+        let mut worker = Worker::new(Thread::new());
+        let clock = Clock::manual(worker.timer());
+        worker.set_clock(clock.clone());
+
+        let mut input = InputHandle::new();
+        let output = Rc::new(RefCell::new(BTreeMap::new()));
+        let captured = output.clone();
+        let probe = worker.dataflow(|scope| {
+            let stream = scope.input_from(&mut input);
+            logic(&stream)
+                .inspect_batch(move |time, data| {
+                    captured
+                        .borrow_mut()
+                        .entry(time.clone())
+                        .or_insert_with(Vec::new)
+                        .extend(data.iter().cloned());
+                })
+                .probe()
+        });
+
+        let mut harness = Harness { input, probe, output, clock, worker };
+        harness.settle();
+        harness
+    }
+
+    /// Introduces `datum` at the current epoch of the input.
+    ///
+    /// The record is presented to the dataflow at the next `settle`.
+    pub fn send(&mut self, datum: D) {
+        self.input.send(datum);
+    }
+
+    /// The current epoch of the input.
+    pub fn time(&self) -> &T {
+        self.input.time()
+    }
+
+    /// Advances the input to `time`, and settles the dataflow.
+    pub fn advance_to(&mut self, time: T) {
+        self.input.advance_to(time);
+        self.settle();
+    }
+
+    /// Advances processing time by `duration`, and settles the dataflow.
+    ///
+    /// Timers and delayed activations due by the new time fire during the settling.
+    pub fn advance_clock(&mut self, duration: Duration) {
+        self.clock.advance(duration);
+        self.settle();
+    }
+
+    /// The processing time elapsed since the creation of the harness.
+    pub fn elapsed(&self) -> Duration {
+        self.clock.elapsed(self.worker.timer())
+    }
+
+    /// Presents sent records, and steps the worker until it has no work to do at the current
+    /// processing time.
+    pub fn settle(&mut self) {
+        self.input.flush();
+        while self.worker.step_if_active() { }
+    }
+
+    /// Returns `true` if the output at `time` is complete.
+    pub fn complete(&self, time: &T) -> bool {
+        !self.probe.less_equal(time)
+    }
+
+    /// Removes and returns the output produced at `time` so far.
+    pub fn output_at(&mut self, time: &T) -> Vec<R> {
+        self.output.borrow_mut().remove(time).unwrap_or_default()
+    }
+
+    /// Removes and returns the output produced so far, ordered by time.
+    pub fn take_output(&mut self) -> Vec<(T, Vec<R>)> {
+        ::std::mem::take(&mut *self.output.borrow_mut()).into_iter().collect()
+    }
+
+    /// Closes the input, settles the dataflow, and returns the output not yet taken.
+    ///
+    /// Operators awaiting timers may hold their output open, in which case the processing time
+    /// should first be advanced to fire them.
+    pub fn close(mut self) -> Vec<(T, Vec<R>)> {
+        ::std::mem::replace(&mut self.input, InputHandle::new()).close();
+        self.settle();
+        self.take_output()
+    }
+
+    /// The worker hosting the dataflow, for example to inspect its operators or logs.
+    pub fn worker(&mut self) -> &mut Worker<Thread> {
+        &mut self.worker
+    }
+}
@@ -0,0 +1,238 @@
+//! Comparison of dataflow output with stored golden files.
+//!
+//! A `Golden` holds the output of a dataflow in a canonical form, independent of the order in
+//! which workers and operators produced it: the records of each epoch, in order of the epochs,
+//! each record rendered by its `Debug` implementation, and the records of each epoch sorted by
+//! their rendering. Its textual form, which is what golden files hold, lists each epoch on a line
+//! `@ time`, followed by its records on lines indented by two spaces.
+//!
+//! `Golden::assert_matches` compares the output with a golden file, and on a mismatch panics with
+//! the records each epoch is missing and the records it has in excess, or if the golden file does
+//! not exist. The golden file is written instead only if the `TIMELY_UPDATE_GOLDEN` environment
+//! variable is set, so that golden files are created, or regenerated once changes in output have
+//! been reviewed, deliberately rather than by any run of the tests.
+//!
+//! # Examples
+//! ```
+//! use timely::dataflow::operators::{ToStream, Exchange, Map, Capture};
+//! use timely::testing::golden::Golden;
+//!
+//! let captured = timely::example(|scope| {
+//!     (0..6u64).to_stream(scope)
+//!              .exchange(|x| *x)
+//!              .map(|x| format!("record {}", x))
+//!              .capture()
+//! });
+//!
+//! let golden = Golden::from_events(captured.try_iter());
+//! assert_eq!(golden.to_string().lines().next(), Some("@ 0"));
+//!
+//! let path = std::env::temp_dir().join(format!("timely-golden-{}.txt", std::process::id()));
+//! std::fs::write(&path, golden.to_string()).unwrap();
+//! golden.assert_matches(&path);
+//! # std::fs::remove_file(&path).unwrap();
+//! ```
+
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::{self, Debug, Display};
+use std::path::Path;
+
+use crate::dataflow::operators::capture::Event;
+
+/// The environment variable which, when set, causes golden files to be rewritten rather than compared.
+pub const UPDATE_VARIABLE: &str = "TIMELY_UPDATE_GOLDEN";
+
+/// Dataflow output in canonical form: the sorted renderings of the records of each epoch.
+#[derive(Debug, Clone, Eq, PartialEq, Default)]
+pub struct Golden {
+    epochs: Vec<(String, Vec<String>)>,
+}
+
+impl Golden {
+    /// The canonical form of the data events of a captured stream.
+    pub fn from_events<T: Ord+Debug, D: Debug, I: IntoIterator<Item=Event<T, D>>>(events: I) -> Self {
+        Self::from_output(events.into_iter().filter_map(|event| match event {
+            Event::Messages(time, data) => Some((time, data)),
+            Event::Progress(_) => None,
+        }))
+    }
+
+    /// The canonical form of output presented as records by time, as `Harness::take_output` does.
+    pub fn from_output<T: Ord+Debug, D: Debug, I: IntoIterator<Item=(T, Vec<D>)>>(output: I) -> Self {
+        let mut epochs = BTreeMap::new();
+        for (time, data) in output {
+            epochs.entry(time).or_insert_with(Vec::new).extend(data.iter().map(render));
+        }
+        let epochs = epochs.into_iter().filter(|(_, records)| !records.is_empty()).map(|(time, mut records)| {
+            records.sort();
+            (render(&time), records)
+        }).collect();
+        Golden { epochs }
+    }
+
+    /// Parses the textual form of a golden file.
+    ///
+    /// Blank lines and lines starting with `#` are ignored.
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let mut epochs: Vec<(String, Vec<String>)> = Vec::new();
+        for (number, line) in text.lines().enumerate() {
+            if let Some(time) = line.strip_prefix("@ ") {
+                epochs.push((time.to_owned(), Vec::new()));
+            }
+            else if let Some(record) = line.strip_prefix("  ") {
+                match epochs.last_mut() {
+                    Some((_, records)) => records.push(record.to_owned()),
+                    None => return Err(format!("line {}: record before the first epoch", number + 1)),
+                }
+            }
+            else if !line.trim().is_empty() && !line.starts_with('#') {
+                return Err(format!("line {}: expected an epoch or a record", number + 1));
+            }
+        }
+        for (_, records) in epochs.iter_mut() {
+            records.sort();
+        }
+        Ok(Golden { epochs })
+    }
+
+    /// The differences of this output from `expected`, by epoch, or `None` if they are equal.
+    pub fn diff(&self, expected: &Golden) -> Option<GoldenDiff> {
+        let actual = self.epochs.iter().map(|(time, records)| (time, records)).collect::<HashMap<_,_>>();
+        let wanted = expected.epochs.iter().map(|(time, records)| (time, records)).collect::<HashMap<_,_>>();
+        // Epochs are reported in the order of the expected output, followed by any others.
+        let mut times = expected.epochs.iter().map(|(time, _)| time).collect::<Vec<_>>();
+        times.extend(self.epochs.iter().map(|(time, _)| time).filter(|time| !wanted.contains_key(time)));
+
+        let empty = Vec::new();
+        let mut epochs = Vec::new();
+        for time in times {
+            let (missing, excess) = differences(wanted.get(time).copied().unwrap_or(&empty), actual.get(time).copied().unwrap_or(&empty));
+            if !missing.is_empty() || !excess.is_empty() {
+                epochs.push(EpochDiff { time: time.clone(), missing, excess });
+            }
+        }
+        if epochs.is_empty() { None } else { Some(GoldenDiff { epochs }) }
+    }
+
+    /// Asserts that this output matches the golden file at `path`.
+    ///
+    /// If `TIMELY_UPDATE_GOLDEN` is set, the file is written instead. Otherwise a missing file
+    /// is a failure, as a golden file that is never committed would never be compared with.
+    pub fn assert_matches<P: AsRef<Path>>(&self, path: P) {
+        let path = path.as_ref();
+        if std::env::var_os(UPDATE_VARIABLE).is_some() {
+            if let Some(parent) = path.parent() {
+                std::fs::create_dir_all(parent).unwrap_or_else(|error| panic!("failed to create {}: {}", parent.display(), error));
+            }
+            std::fs::write(path, self.to_string()).unwrap_or_else(|error| panic!("failed to write {}: {}", path.display(), error));
+            return;
+        }
+        if !path.exists() {
+            panic!("golden file missing: {} (set {} to write it)", path.display(), UPDATE_VARIABLE);
+        }
+        let text = std::fs::read_to_string(path).unwrap_or_else(|error| panic!("failed to read {}: {}", path.display(), error));
+        let expected = Golden::parse(&text).unwrap_or_else(|error| panic!("failed to parse {}: {}", path.display(), error));
+        if let Some(diff) = self.diff(&expected) {
+            panic!("output differs from {} (set {} to update it):\n{}", path.display(), UPDATE_VARIABLE, diff);
+        }
+    }
+}
+
+impl Display for Golden {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (time, records) in self.epochs.iter() {
+            writeln!(f, "@ {}", time)?;
+            for record in records.iter() {
+                writeln!(f, "  {}", record)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// The differences of output from a golden file, as found by `Golden::diff`.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct GoldenDiff {
+    /// The epochs whose records differ.
+    pub epochs: Vec<EpochDiff>,
+}
+
+/// The differences of the records of one epoch.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct EpochDiff {
+    /// The rendering of the epoch.
+    pub time: String,
+    /// Records of the golden file absent from the output, as many times as they are absent.
+    pub missing: Vec<String>,
+    /// Records of the output absent from the golden file, as many times as they are in excess.
+    pub excess: Vec<String>,
+}
+
+impl Display for GoldenDiff {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for epoch in self.epochs.iter() {
+            writeln!(f, "@ {}", epoch.time)?;
+            for record in epoch.missing.iter() {
+                writeln!(f, "- {}", record)?;
+            }
+            for record in epoch.excess.iter() {
+                writeln!(f, "+ {}", record)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+// The single-line rendering of a record or time.
+fn render<X: Debug>(item: &X) -> String {
+    format!("{:?}", item).replace('\n', "\\n")
+}
+
+// The records of `expected` absent from `actual`, and those of `actual` absent from `expected`,
+// counting multiplicities, for sorted inputs.
+fn differences(expected: &[String], actual: &[String]) -> (Vec<String>, Vec<String>) {
+    let (mut missing, mut excess) = (Vec::new(), Vec::new());
+    let (mut e, mut a) = (0, 0);
+    while e < expected.len() || a < actual.len() {
+        match (expected.get(e), actual.get(a)) {
+            (Some(x), Some(y)) if x == y => { e += 1; a += 1; },
+            (Some(x), Some(y)) if x < y => { missing.push(x.clone()); e += 1; },
+            (Some(_), Some(y)) => { excess.push(y.clone()); a += 1; },
+            (Some(x), None) => { missing.push(x.clone()); e += 1; },
+            (None, Some(y)) => { excess.push(y.clone()); a += 1; },
+            (None, None) => unreachable!(),
+        }
+    }
+    (missing, excess)
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::Golden;
+
+    #[test]
+    fn canonical_form_ignores_order() {
+        let first = Golden::from_output(vec![(1, vec!["b", "a"]), (0, vec!["c"]), (1, vec!["a"])]);
+        let second = Golden::from_output(vec![(0, vec!["c"]), (1, vec!["a", "a", "b"]), (2, vec![])]);
+        assert_eq!(first, second);
+        assert_eq!(first.to_string(), "@ 0\n  \"c\"\n@ 1\n  \"a\"\n  \"a\"\n  \"b\"\n");
+        assert_eq!(Golden::parse(&first.to_string()), Ok(first));
+    }
+
+    #[test]
+    #[should_panic(expected = "golden file missing")]
+    fn missing_file_fails() {
+        let path = std::env::temp_dir().join(format!("timely-golden-missing-{}.txt", std::process::id()));
+        Golden::from_output(vec![(0, vec![1])]).assert_matches(&path);
+    }
+
+    #[test]
+    fn diffs_report_records_by_epoch() {
+        let expected = Golden::from_output(vec![(0, vec![1, 2, 2]), (1, vec![3])]);
+        let actual = Golden::from_output(vec![(0, vec![2, 4]), (2, vec![5])]);
+        let diff = actual.diff(&expected).unwrap();
+        assert_eq!(diff.to_string(), "@ 0\n- 1\n- 2\n+ 4\n@ 1\n- 3\n@ 2\n+ 5\n");
+        assert_eq!(expected.diff(&expected), None);
+    }
+}
@@ -0,0 +1,186 @@
+//! Property tests of dataflows, with `proptest`.
+//!
+//! With the `proptest` feature, `schedules` generates input `Schedule`s: batches of records, each
+//! introduced at a random epoch by a random worker, which then takes a random number of steps
+//! before introducing its next batch. `run` executes a dataflow with several workers on the
+//! input a schedule describes, until the dataflow is complete, and returns its output. Properties
+//! can be asserted of these runs within `proptest!`, or with `assert_matches_reference`, which
+//! compares the output of a dataflow with that of a sequential reference computation. As
+//! schedules are generated by `proptest` strategies, failures shrink to minimal schedules, which
+//! are reported by their `Debug` representation.
+//!
+//! # Examples
+//! ```
+//! use timely::dataflow::operators::{Exchange, Map};
+//! use timely::testing::properties::assert_matches_reference;
+//! use proptest::prelude::*;
+//!
+//! // Doubling records is indifferent to where they are exchanged.
+//! assert_matches_reference(
+//!     2,
+//!     any::<u8>(),
+//!     |stream| stream.exchange(|x: &u8| *x as u64).map(|x| 2 * x as u32),
+//!     |schedule| schedule.records().map(|(time, x)| (time, 2 * *x as u32)).collect(),
+//! );
+//! ```
+
+use std::sync::{Arc, Mutex};
+
+use proptest::strategy::Strategy;
+use proptest::test_runner::{Config, TestError, TestRunner};
+
+use crate::Data;
+use crate::communication::{Allocator, Configuration};
+use crate::dataflow::{InputHandle, Stream};
+use crate::dataflow::scopes::Child;
+use crate::dataflow::operators::{Input, Inspect, Probe};
+use crate::worker::Worker;
+
+/// The number of epochs among which `assert_matches_reference` distributes batches.
+const EPOCHS: u64 = 4;
+/// The greatest number of batches `assert_matches_reference` generates.
+const BATCHES: usize = 16;
+
+/// Input for a dataflow: batches of records, introduced by workers at epochs.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Schedule<D> {
+    /// The number of workers to run.
+    pub workers: usize,
+    /// The batches of records, in the order workers introduce them.
+    pub batches: Vec<Batch<D>>,
+}
+
+/// A batch of records introduced by a worker at an epoch.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Batch<D> {
+    /// The index of the worker introducing the batch.
+    pub worker: usize,
+    /// The epoch at which the batch is introduced.
+    pub time: u64,
+    /// The records of the batch.
+    pub data: Vec<D>,
+    /// The number of steps the worker takes after introducing the batch.
+    pub steps: usize,
+}
+
+impl<D> Schedule<D> {
+    /// The records of the schedule with their epochs, as introduced by each worker in turn.
+    pub fn records(&self) -> impl Iterator<Item=(u64, &D)> {
+        self.batches.iter().flat_map(|batch| batch.data.iter().map(move |datum| (batch.time, datum)))
+    }
+}
+
+/// Generates schedules for `workers` workers, of at most `batches` batches of records drawn
+/// from `data`, at epochs less than `epochs`.
+///
+/// Schedules shrink towards fewer and smaller batches, at earlier epochs and on lower workers.
+pub fn schedules<S>(data: S, workers: usize, epochs: u64, batches: usize) -> impl Strategy<Value=Schedule<S::Value>>
+where
+    S: Strategy,
+    S::Value: Data,
+{
+    assert!(workers > 0 && epochs > 0, "schedules require workers and epochs");
+    let batch = (0 .. workers, 0 .. epochs, proptest::collection::vec(data, 0 .. 8), 0 .. 3usize)
+        .prop_map(|(worker, time, data, steps)| Batch { worker, time, data, steps });
+    proptest::collection::vec(batch, 0 ..= batches)
+        .prop_map(move |batches| Schedule { workers, batches })
+}
+
+/// Runs the dataflow `logic` applies to the input `schedule` describes, and returns its output.
+///
+/// Each worker introduces its batches in order of their epochs, advancing its input as it goes,
+/// and closes its input once it has introduced them all. The output is sorted by epoch and then
+/// by record, as its order depends on the interleaving of workers.
+pub fn run<D, R, L>(schedule: &Schedule<D>, logic: L) -> Vec<(u64, R)>
+where
+    D: Data+Send+Sync,
+    R: Data+Send+Ord,
+    L: for<'a> Fn(&Stream<Child<'a, Worker<Allocator>, u64>, D>) -> Stream<Child<'a, Worker<Allocator>, u64>, R>+Send+Sync+'static,
+{
+    let output = Arc::new(Mutex::new(Vec::new()));
+    let captured = output.clone();
+    let shared = Arc::new(schedule.clone());
+    crate::execute(Configuration::Process(schedule.workers), move |worker| {
+        let captured = captured.clone();
+        let mut input = InputHandle::new();
+        worker.dataflow(|scope| {
+            let stream = scope.input_from(&mut input);
+            logic(&stream)
+                .inspect_batch(move |time, data| {
+                    captured.lock().expect("output poisoned").extend(data.iter().map(|datum| (*time, datum.clone())));
+                })
+                .probe();
+        });
+
+        let index = worker.index();
+        let mut batches = shared.batches.iter().filter(|batch| batch.worker == index).collect::<Vec<_>>();
+        batches.sort_by_key(|batch| batch.time);
+        for batch in batches {
+            input.advance_to(batch.time);
+            for datum in batch.data.iter() {
+                input.send(datum.clone());
+            }
+            for _ in 0 .. batch.steps {
+                worker.step();
+            }
+        }
+    })
+    .expect("property test execution failed")
+    .join()
+    .into_iter()
+    .for_each(|result| result.expect("property test worker failed"));
+
+    let mut output = ::std::mem::take(&mut *output.lock().expect("output poisoned"));
+    output.sort();
+    output
+}
+
+/// Asserts that the dataflow `logic` produces the same output as the sequential computation
+/// `reference`, for schedules of `workers` workers introducing records drawn from `data`.
+///
+/// Outputs are compared as sorted by epoch and then by record. The assertion panics with a
+/// minimal failing schedule, if it finds a failing schedule.
+pub fn assert_matches_reference<S, R, L, F>(workers: usize, data: S, logic: L, reference: F)
+where
+    S: Strategy,
+    S::Value: Data+Send+Sync,
+    R: Data+Send+Ord+::std::fmt::Debug,
+    L: for<'a> Fn(&Stream<Child<'a, Worker<Allocator>, u64>, S::Value>) -> Stream<Child<'a, Worker<Allocator>, u64>, R>+Send+Sync+'static,
+    F: Fn(&Schedule<S::Value>) -> Vec<(u64, R)>,
+{
+    let logic = Arc::new(logic);
+    let mut runner = TestRunner::new(Config { cases: 32, ..Config::default() });
+    let result = runner.run(&schedules(data, workers, EPOCHS, BATCHES), |schedule| {
+        let logic = logic.clone();
+        let output = run(&schedule, move |stream| logic(stream));
+        let mut expected = reference(&schedule);
+        expected.sort();
+        proptest::prop_assert_eq!(output, expected);
+        Ok(())
+    });
+    match result {
+        Ok(()) => { },
+        Err(TestError::Fail(reason, schedule)) => panic!("{}\nminimal failing schedule: {:#?}", reason, schedule),
+        Err(error) => panic!("{}", error),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use proptest::prelude::*;
+    use crate::dataflow::operators::{Exchange, Filter};
+    use super::assert_matches_reference;
+
+    #[test]
+    #[should_panic(expected = "minimal failing schedule")]
+    fn reports_failing_schedules() {
+        // A dataflow losing large records.
+        assert_matches_reference(
+            2,
+            any::<u8>(),
+            |stream| stream.exchange(|x: &u8| *x as u64).filter(|x| *x < 200),
+            |schedule| schedule.records().map(|(time, x)| (time, *x)).collect(),
+        );
+    }
+}
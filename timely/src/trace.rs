@@ -0,0 +1,360 @@
+//! Trace spans for operator activations and epochs, in the form of OpenTelemetry.
+//!
+//! A `Tracer` turns the logged events of a worker into spans, and presents them in batches to a
+//! `SpanExporter`. Each operator activation becomes a span, named by the operator. Epochs become
+//! spans once registered with `EpochSpans::open`, which start when the epoch is opened at an input
+//! and end when a chosen probe passes the epoch. An epoch may continue a trace of the services
+//! that feed it, by opening it with the `SpanContext` of their request, as carried by the W3C
+//! `traceparent` header.
+//!
+//! The exporter may forward spans to an OpenTelemetry SDK, or render them with `to_otlp_json`
+//! as the body of an OTLP/HTTP request to a collector.
+//!
+//! # Examples
+//! ```
+//! use std::rc::Rc;
+//! use std::cell::RefCell;
+//! use timely::dataflow::InputHandle;
+//! use timely::dataflow::operators::{Input, Map, Probe};
+//! use timely::trace::{Tracer, Span, SpanContext};
+//!
+//! timely::execute_from_args(::std::env::args(), |worker| {
+//!
+//!     let exported = Rc::new(RefCell::new(Vec::new()));
+//!     let shared = exported.clone();
+//!     let tracer = Tracer::install(worker, move |spans: Vec<Span>| shared.borrow_mut().extend(spans));
+//!
+//!     let mut input = InputHandle::new();
+//!     let probe = worker.dataflow::<usize,_,_>(|scope| {
+//!         scope.input_from(&mut input).map(|x: usize| x + 1).probe()
+//!     });
+//!     let epochs = tracer.epochs("requests", &probe);
+//!
+//!     // The first epoch continues the trace of an upstream request.
+//!     let parent = SpanContext::from_traceparent("00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01").unwrap();
+//!     epochs.open_with_parent(0, parent);
+//!     for round in 0 .. 3 {
+//!         input.send(round);
+//!         input.advance_to(round + 1);
+//!         epochs.open(round + 1);
+//!         worker.step_while(|| probe.less_than(input.time()));
+//!     }
+//!
+//!     let exported = exported.borrow();
+//!     let epoch = exported.iter().find(|span| span.name == "requests").unwrap();
+//!     assert_eq!(epoch.trace_id, parent.trace_id);
+//!     assert_eq!(epoch.parent_span_id, Some(parent.span_id));
+//!     assert!(exported.iter().any(|span| span.name == "Map"));
+//!     assert!(timely::trace::to_otlp_json("pipeline", &exported[..]).starts_with("{\"resourceSpans\""));
+//! });
+//! ```
+
+use std::rc::Rc;
+use std::cell::{Cell, RefCell};
+use std::collections::HashMap;
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+use std::fmt::{Debug, Write};
+use crate::time::{SystemTime, UNIX_EPOCH};
+
+use crate::communication::Allocate;
+use crate::dataflow::ProbeHandle;
+use crate::logging::{TimelyEvent, StartStop};
+use crate::progress::Timestamp;
+use crate::worker::Worker;
+
+/// A completed span, as presented to a `SpanExporter`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Span {
+    /// The identifier of the trace the span belongs to.
+    pub trace_id: u128,
+    /// The identifier of the span.
+    pub span_id: u64,
+    /// The identifier of the parent span, absent for root spans.
+    pub parent_span_id: Option<u64>,
+    /// The name of the span, for activations the name of the operator.
+    pub name: String,
+    /// The start of the span.
+    pub start: SystemTime,
+    /// The end of the span.
+    pub end: SystemTime,
+    /// Attributes describing the span, such as `timely.worker` and `timely.operator.id`.
+    pub attributes: Vec<(String, String)>,
+}
+
+/// The identifiers of a span, which other spans may name as their parent.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub struct SpanContext {
+    /// The identifier of the trace.
+    pub trace_id: u128,
+    /// The identifier of the span.
+    pub span_id: u64,
+}
+
+impl SpanContext {
+    /// Parses a W3C `traceparent` header, of the form `00-{trace id}-{span id}-{flags}`.
+    pub fn from_traceparent(header: &str) -> Option<Self> {
+        let mut parts = header.trim().split('-');
+        let _version = parts.next().filter(|version| version.len() == 2)?;
+        let trace_id = parts.next().filter(|id| id.len() == 32).and_then(|id| u128::from_str_radix(id, 16).ok())?;
+        let span_id = parts.next().filter(|id| id.len() == 16).and_then(|id| u64::from_str_radix(id, 16).ok())?;
+        if trace_id == 0 || span_id == 0 { None } else { Some(SpanContext { trace_id, span_id }) }
+    }
+
+    /// Formats the context as a W3C `traceparent` header, with the sampled flag set.
+    pub fn to_traceparent(&self) -> String {
+        format!("00-{:032x}-{:016x}-01", self.trace_id, self.span_id)
+    }
+}
+
+/// A recipient of completed spans.
+pub trait SpanExporter {
+    /// Receives a batch of completed spans.
+    fn export(&mut self, spans: Vec<Span>);
+}
+
+impl<F: FnMut(Vec<Span>)> SpanExporter for F {
+    fn export(&mut self, spans: Vec<Span>) { self(spans) }
+}
+
+/// A source of random identifiers.
+struct Ids {
+    state: Cell<u64>,
+}
+
+impl Ids {
+    fn new() -> Self {
+        let mut hasher = RandomState::new().build_hasher();
+        hasher.write_u128(SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_nanos());
+        Ids { state: Cell::new(hasher.finish()) }
+    }
+    /// The next non-zero identifier, by the SplitMix64 generator.
+    fn next(&self) -> u64 {
+        loop {
+            let state = self.state.get().wrapping_add(0x9E3779B97F4A7C15);
+            self.state.set(state);
+            let mut z = state;
+            z = (z ^ (z >> 30)).wrapping_mul(0xBF58476D1CE4E5B9);
+            z = (z ^ (z >> 27)).wrapping_mul(0x94D049BB133111EB);
+            z ^= z >> 31;
+            if z != 0 { return z; }
+        }
+    }
+    fn next_trace(&self) -> u128 {
+        ((self.next() as u128) << 64) | self.next() as u128
+    }
+}
+
+/// Closes the epochs a probe has passed, presenting their spans.
+type EpochCloser = Box<dyn Fn(&mut Vec<Span>)>;
+
+/// State shared by a tracer, its logger, and its epoch spans.
+struct Shared {
+    worker: usize,
+    // The system time at which the worker started, to which logged times are relative.
+    origin: SystemTime,
+    ids: Ids,
+    // The trace of activation spans.
+    trace_id: u128,
+    epochs: RefCell<Vec<EpochCloser>>,
+}
+
+/// Produces spans from the events of a worker, as returned by `Tracer::install`.
+pub struct Tracer {
+    shared: Rc<Shared>,
+}
+
+impl Tracer {
+    /// Exports spans for the operator activations of `worker`, replacing its `"timely"` logger.
+    ///
+    /// Activations of each worker form one trace. Spans are exported as the worker flushes its
+    /// logs, which happens in each step, for as long as the tracer is retained.
+    pub fn install<A: Allocate, E: SpanExporter+'static>(worker: &mut Worker<A>, mut exporter: E) -> Self {
+        let ids = Ids::new();
+        let shared = Rc::new(Shared {
+            worker: worker.index(),
+            origin: SystemTime::now() - worker.timer().elapsed(),
+            trace_id: ids.next_trace(),
+            ids,
+            epochs: RefCell::new(Vec::new()),
+        });
+        let logged = Rc::downgrade(&shared);
+        let mut names = HashMap::new();
+        let mut starts = HashMap::new();
+        worker.log_register().insert::<TimelyEvent,_>("timely", move |_time, data| {
+            let shared = match logged.upgrade() { Some(shared) => shared, None => { return; } };
+            let mut spans = Vec::new();
+            for (elapsed, _worker, event) in data.drain(..) {
+                match event {
+                    TimelyEvent::Operates(event) => {
+                        let addr = event.addr.iter().map(|x| x.to_string()).collect::<Vec<_>>().join(",");
+                        names.insert(event.id, (event.name, addr));
+                    },
+                    TimelyEvent::Schedule(event) => match event.start_stop {
+                        StartStop::Start => { starts.insert(event.id, elapsed); },
+                        StartStop::Stop => {
+                            if let Some(start) = starts.remove(&event.id) {
+                                let (name, addr) = names.get(&event.id).cloned().unwrap_or_default();
+                                spans.push(Span {
+                                    trace_id: shared.trace_id,
+                                    span_id: shared.ids.next(),
+                                    parent_span_id: None,
+                                    name,
+                                    start: shared.origin + start,
+                                    end: shared.origin + elapsed,
+                                    attributes: vec![
+                                        ("timely.worker".to_string(), shared.worker.to_string()),
+                                        ("timely.operator.id".to_string(), event.id.to_string()),
+                                        ("timely.operator.addr".to_string(), addr),
+                                    ],
+                                });
+                            }
+                        },
+                    },
+                    TimelyEvent::Shutdown(event) => { names.remove(&event.id); },
+                    _ => { },
+                }
+            }
+            for closer in shared.epochs.borrow().iter() {
+                closer(&mut spans);
+            }
+            if !spans.is_empty() {
+                exporter.export(spans);
+            }
+        });
+        Tracer { shared }
+    }
+
+    /// The trace of the activation spans of the worker.
+    pub fn trace_id(&self) -> u128 {
+        self.shared.trace_id
+    }
+
+    /// Spans for the epochs of a dataflow, named `name`, which end once `probe` passes them.
+    pub fn epochs<T: Timestamp+Debug>(&self, name: &str, probe: &ProbeHandle<T>) -> EpochSpans<T> {
+        let open = Rc::new(RefCell::new(Vec::<OpenEpoch<T>>::new()));
+        let pending = Rc::downgrade(&open);
+        let probe = probe.clone();
+        let name = name.to_owned();
+        let worker = self.shared.worker;
+        self.shared.epochs.borrow_mut().push(Box::new(move |spans| {
+            if let Some(open) = pending.upgrade() {
+                let end = SystemTime::now();
+                open.borrow_mut().retain(|epoch| {
+                    let passed = !probe.less_equal(&epoch.time);
+                    if passed {
+                        spans.push(Span {
+                            trace_id: epoch.context.trace_id,
+                            span_id: epoch.context.span_id,
+                            parent_span_id: epoch.parent,
+                            name: name.clone(),
+                            start: epoch.start,
+                            end,
+                            attributes: vec![
+                                ("timely.worker".to_string(), worker.to_string()),
+                                ("timely.epoch".to_string(), format!("{:?}", epoch.time)),
+                            ],
+                        });
+                    }
+                    !passed
+                });
+            }
+        }));
+        EpochSpans { shared: self.shared.clone(), open }
+    }
+}
+
+/// An epoch opened but not yet passed by its probe.
+struct OpenEpoch<T> {
+    time: T,
+    context: SpanContext,
+    parent: Option<u64>,
+    start: SystemTime,
+}
+
+/// Spans for the epochs of a dataflow, as returned by `Tracer::epochs`.
+pub struct EpochSpans<T: Timestamp> {
+    shared: Rc<Shared>,
+    open: Rc<RefCell<Vec<OpenEpoch<T>>>>,
+}
+
+impl<T: Timestamp> EpochSpans<T> {
+    /// Opens a span for the epoch `time`, as the root of a new trace.
+    ///
+    /// The span ends once the probe passes `time`, and so should be opened before the input
+    /// advances beyond it.
+    pub fn open(&self, time: T) -> SpanContext {
+        let context = SpanContext { trace_id: self.shared.ids.next_trace(), span_id: self.shared.ids.next() };
+        self.push(time, context, None)
+    }
+
+    /// Opens a span for the epoch `time`, as a child of `parent`.
+    pub fn open_with_parent(&self, time: T, parent: SpanContext) -> SpanContext {
+        let context = SpanContext { trace_id: parent.trace_id, span_id: self.shared.ids.next() };
+        self.push(time, context, Some(parent.span_id))
+    }
+
+    /// The number of epochs opened that the probe has not yet been observed to pass.
+    pub fn open_epochs(&self) -> usize {
+        self.open.borrow().len()
+    }
+
+    fn push(&self, time: T, context: SpanContext, parent: Option<u64>) -> SpanContext {
+        self.open.borrow_mut().push(OpenEpoch { time, context, parent, start: SystemTime::now() });
+        context
+    }
+}
+
+/// Renders spans as an OTLP/JSON `ExportTraceServiceRequest`, attributed to `service`.
+///
+/// The result is suitable as the body of a request to the `/v1/traces` endpoint of an
+/// OpenTelemetry collector.
+pub fn to_otlp_json(service: &str, spans: &[Span]) -> String {
+    let mut json = String::new();
+    write!(
+        json,
+        "{{\"resourceSpans\":[{{\"resource\":{{\"attributes\":[{}]}},\"scopeSpans\":[{{\"scope\":{{\"name\":\"timely\"}},\"spans\":[",
+        attribute("service.name", service),
+    ).unwrap();
+    for (index, span) in spans.iter().enumerate() {
+        if index > 0 { json.push(','); }
+        write!(json, "{{\"traceId\":\"{:032x}\",\"spanId\":\"{:016x}\",", span.trace_id, span.span_id).unwrap();
+        if let Some(parent) = span.parent_span_id {
+            write!(json, "\"parentSpanId\":\"{:016x}\",", parent).unwrap();
+        }
+        let attributes = span.attributes.iter().map(|(key, value)| attribute(key, value)).collect::<Vec<_>>();
+        write!(
+            json,
+            "\"name\":\"{}\",\"kind\":1,\"startTimeUnixNano\":\"{}\",\"endTimeUnixNano\":\"{}\",\"attributes\":[{}]}}",
+            escape(&span.name),
+            unix_nanos(span.start),
+            unix_nanos(span.end),
+            attributes.join(","),
+        ).unwrap();
+    }
+    json.push_str("]}]}]}");
+    json
+}
+
+fn attribute(key: &str, value: &str) -> String {
+    format!("{{\"key\":\"{}\",\"value\":{{\"stringValue\":\"{}\"}}}}", escape(key), escape(value))
+}
+
+fn unix_nanos(time: SystemTime) -> u128 {
+    time.duration_since(UNIX_EPOCH).unwrap_or_default().as_nanos()
+}
+
+/// Escapes quotes, backslashes, and control characters, for use within JSON strings.
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            c if (c as u32) < 0x20 => { write!(escaped, "\\u{:04x}", c as u32).unwrap(); },
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
@@ -0,0 +1,213 @@
+//! A live feed of the progress of the dataflows of a process, for dashboards.
+//!
+//! A `Feed` collects snapshots of the workers of a process, once in each interval, and `serve`
+//! streams them to clients over HTTP as server-sent events, which browsers receive with an
+//! `EventSource`. Each event is a JSON object of the form
+//!
+//! ```text
+//! {"workers":[{"worker":0,
+//!              "operators":[{"id":2,"addr":[0, 1],"name":"Input","frontiers":[],"activations":3}, ...],
+//!              "channels":[{"id":3,"sent":{"messages":1,"records":10,"bytes":80},"received":{...}}, ...]}, ...]}
+//! ```
+//!
+//! in which operators are those of `Worker::introspect`, with the frontier of each input and
+//! their timestamps formatted as strings, and channels are those of `Worker::channel_counts`.
+//! The operators and channels may be related to the structure of the dataflows through the graph
+//! of `Worker::graph`, whose JSON rendering uses the same identifiers.
+//!
+//! Workers take snapshots at the end of a step, and so a worker parked for longer than the
+//! interval reports its state as of its last step.
+//!
+//! When `execute` finds the `TIMELY_VIZ_ADDR` environment variable, it serves a `Feed` for each
+//! process at that address, and installs it in each worker, with an interval of the milliseconds
+//! in `TIMELY_VIZ_INTERVAL_MS`, or of one second. The address it serves the feed at is then
+//! available from `served_at`, and with the `tracing` feature is reported as an event.
+//!
+//! # Examples
+//! ```
+//! use std::time::Duration;
+//! use timely::dataflow::InputHandle;
+//! use timely::dataflow::operators::{Input, Exchange, Probe};
+//! use timely::viz::Feed;
+//!
+//! let feed = Feed::new(Duration::from_millis(100));
+//! let shared = feed.clone();
+//!
+//! timely::execute(timely::Configuration::Process(2), move |worker| {
+//!     shared.install(worker);
+//!
+//!     let mut input = InputHandle::new();
+//!     let probe = worker.dataflow::<u64,_,_>(|scope| {
+//!         scope.input_from(&mut input)
+//!              .exchange(|x: &u64| *x)
+//!              .probe()
+//!     });
+//!
+//!     for round in 0 .. 10 {
+//!         input.send(round);
+//!         input.advance_to(round + 1);
+//!         worker.step_while(|| probe.less_than(input.time()));
+//!     }
+//! }).unwrap();
+//!
+//! let json = feed.render();
+//! assert!(json.starts_with("{\"workers\":[{\"worker\":0,"));
+//! assert!(json.contains("\"name\":\"Exchange\""));
+//! ```
+
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+use std::io::{Read, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream, ToSocketAddrs};
+use std::sync::{Arc, Mutex};
+use crate::time::Duration;
+
+use crate::communication::Allocate;
+use crate::dataflow::graph::escape;
+use crate::worker::Worker;
+
+/// The most clients a feed streams to at once, beyond which clients are refused.
+pub const MAX_CLIENTS: usize = 16;
+
+// The time allowed for a client to send its request, and to accept each event.
+const CLIENT_TIMEOUT: Duration = Duration::from_secs(1);
+
+// The address at which `execute` serves the feed of the process.
+static SERVED: Mutex<Option<SocketAddr>> = Mutex::new(None);
+
+/// The address at which `execute` serves the feed of the process, if it does.
+///
+/// The address is set once `execute` has bound it, before any worker starts, and so can be read
+/// from workers as well as once `execute` returns.
+pub fn served_at() -> Option<SocketAddr> {
+    *SERVED.lock().expect("feed address poisoned")
+}
+
+/// Records that `execute` serves the feed of the process at `address`.
+pub(crate) fn set_served_at(address: SocketAddr) {
+    *SERVED.lock().expect("feed address poisoned") = Some(address);
+    #[cfg(feature = "tracing")]
+    ::tracing::info!(target: "timely::viz", %address, "serving feed");
+}
+
+/// Snapshots of the workers of a process, shared among their threads and the clients of the feed.
+#[derive(Clone)]
+pub struct Feed {
+    // The latest snapshot of each worker, rendered as JSON, by worker index.
+    snapshots: Arc<Mutex<BTreeMap<usize, String>>>,
+    interval: Duration,
+}
+
+impl Feed {
+    /// Allocates a feed taking and sending snapshots once each `interval`.
+    pub fn new(interval: Duration) -> Self {
+        Feed { snapshots: Default::default(), interval }
+    }
+
+    /// The interval at which snapshots are taken and sent.
+    pub fn interval(&self) -> Duration {
+        self.interval
+    }
+
+    /// Takes snapshots of `worker` at the end of its steps, once each interval.
+    pub fn install<A: Allocate>(&self, worker: &Worker<A>) {
+        let snapshots = self.snapshots.clone();
+        worker.on_step(self.interval, move |worker| {
+            let snapshot = snapshot(worker);
+            snapshots.lock().expect("feed poisoned").insert(worker.index(), snapshot);
+        });
+    }
+
+    /// Renders the latest snapshots of the workers as JSON.
+    pub fn render(&self) -> String {
+        let snapshots = self.snapshots.lock().expect("feed poisoned");
+        let workers = snapshots.values().map(|snapshot| &snapshot[..]).collect::<Vec<_>>();
+        format!("{{\"workers\":[{}]}}", workers.join(","))
+    }
+
+    /// Streams the rendered snapshots over HTTP at `addr` as server-sent events, from background threads.
+    ///
+    /// Requests for any path receive the stream, with an event once each interval, until the
+    /// client disconnects or fails to accept an event within a second. At most `MAX_CLIENTS`
+    /// clients are streamed to at once, and further requests are answered with status 503. One
+    /// thread accepts clients and another sends each event to all of them. The method returns
+    /// the address at which the feed is served, which reveals the port chosen for addresses with
+    /// port zero.
+    pub fn serve<S: ToSocketAddrs>(&self, addr: S) -> ::std::io::Result<SocketAddr> {
+        let listener = TcpListener::bind(addr)?;
+        let local = listener.local_addr()?;
+        let clients = Arc::new(Mutex::new(Vec::new()));
+        let accepted = clients.clone();
+        ::std::thread::Builder::new()
+            .name("viz server".to_string())
+            .spawn(move || {
+                for stream in listener.incoming().flatten() {
+                    accept(stream, &accepted);
+                }
+            })?;
+        let feed = self.clone();
+        ::std::thread::Builder::new()
+            .name("viz feed".to_string())
+            .spawn(move || loop {
+                ::std::thread::sleep(feed.interval);
+                let mut clients = clients.lock().expect("feed clients poisoned");
+                if !clients.is_empty() {
+                    let event = format!("data: {}\n\n", feed.render());
+                    clients.retain_mut(|client: &mut TcpStream| client.write_all(event.as_bytes()).and_then(|_| client.flush()).is_ok());
+                }
+            })?;
+        Ok(local)
+    }
+}
+
+/// Answers the request of `stream`, and adds it to `clients` unless there are as many as allowed.
+fn accept(mut stream: TcpStream, clients: &Mutex<Vec<TcpStream>>) {
+    // The request is not interpreted, beyond awaiting its arrival.
+    let mut request = [0u8; 1024];
+    let _ = stream.set_read_timeout(Some(CLIENT_TIMEOUT));
+    let _ = stream.read(&mut request);
+    let mut clients = clients.lock().expect("feed clients poisoned");
+    if clients.len() >= MAX_CLIENTS {
+        let _ = stream.write_all(b"HTTP/1.1 503 Service Unavailable\r\nContent-Length: 0\r\nConnection: close\r\n\r\n");
+        return;
+    }
+    let header = "HTTP/1.1 200 OK\r\nContent-Type: text/event-stream\r\nCache-Control: no-cache\r\nAccess-Control-Allow-Origin: *\r\n\r\n";
+    if stream.set_write_timeout(Some(CLIENT_TIMEOUT)).and_then(|_| stream.write_all(header.as_bytes())).is_ok() {
+        clients.push(stream);
+    }
+}
+
+/// Renders the operators and channel volumes of `worker` as JSON.
+fn snapshot<A: Allocate>(worker: &Worker<A>) -> String {
+    let mut json = String::new();
+    write!(json, "{{\"worker\":{},\"operators\":[", worker.index()).unwrap();
+    for (position, status) in worker.introspect().iter().enumerate() {
+        if position > 0 { json.push(','); }
+        let frontiers = status.frontiers.iter().map(|frontier| {
+            let times = frontier.iter().map(|time| format!("\"{}\"", escape(time))).collect::<Vec<_>>();
+            format!("[{}]", times.join(","))
+        }).collect::<Vec<_>>();
+        write!(
+            json,
+            "{{\"id\":{},\"addr\":{:?},\"name\":\"{}\",\"frontiers\":[{}],\"activations\":{}}}",
+            status.id,
+            status.addr,
+            escape(&status.name),
+            frontiers.join(","),
+            status.activations,
+        ).unwrap();
+    }
+    json.push_str("],\"channels\":[");
+    for (position, counts) in worker.channel_counts().iter().enumerate() {
+        if position > 0 { json.push(','); }
+        write!(
+            json,
+            "{{\"id\":{},\"sent\":{{\"messages\":{},\"records\":{},\"bytes\":{}}},\"received\":{{\"messages\":{},\"records\":{},\"bytes\":{}}}}}",
+            counts.channel,
+            counts.sent.messages, counts.sent.records, counts.sent.bytes,
+            counts.received.messages, counts.received.records, counts.received.bytes,
+        ).unwrap();
+    }
+    json.push_str("]}");
+    json
+}
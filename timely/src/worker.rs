@@ -1,19 +1,37 @@
 //! The root of each single-threaded worker.
 
 use std::rc::Rc;
-use std::cell::{RefCell, RefMut};
+use std::cell::{Cell, RefCell, RefMut};
 use std::any::Any;
-use std::time::{Instant, Duration};
-use std::collections::HashMap;
+use std::panic::AssertUnwindSafe;
+use crate::time::{Instant, Duration};
+use std::collections::{HashMap, HashSet};
 use std::collections::hash_map::Entry;
+use std::cmp::Reverse;
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{Context, Poll, Waker};
+#[cfg(feature = "rayon")]
+use std::sync::Arc;
+use std::sync::Mutex;
+use std::sync::mpsc::{channel, Sender, Receiver, RecvTimeoutError};
 
 use crate::communication::{Allocate, Data, Push, Pull};
+use crate::communication::allocator::PeerFailure;
 use crate::communication::allocator::thread::{ThreadPusher, ThreadPuller};
-use crate::scheduling::{Schedule, Scheduler, Activations};
+use crate::scheduling::{Schedule, Scheduler, Activations, Clock, RemoteActivator};
+use crate::scheduling::activate::RemotePushers;
+use crate::logging::{EventKind, LogInterest};
+use crate::scheduling::history::ActivationRecord;
 use crate::progress::timestamp::{Refines};
 use crate::progress::SubgraphBuilder;
+use crate::progress::broadcast::{ProgressPolicy, ProgressMode};
 use crate::progress::operate::Operate;
 use crate::dataflow::scopes::Child;
+use crate::dataflow::graph::{Graph, Operator, Channel, OperatorStatus, ProgressStats};
+use crate::dataflow::memory::{MemoryAccount, MemoryAccounts};
+use crate::dataflow::channels::counts::{ChannelCounter, ChannelCounts};
+use crate::dataflow::watchdog::Watchdog;
 use crate::logging::TimelyLogger;
 
 /// Methods provided by the root Worker.
@@ -48,6 +66,33 @@ pub trait AsWorker : Scheduler {
     fn log_register(&self) -> ::std::cell::RefMut<crate::logging_core::Registry<crate::logging::WorkerIdentifier>>;
     /// Provides access to the timely logging stream.
     fn logging(&self) -> Option<crate::logging::TimelyLogger> { self.log_register().get("timely") }
+    /// The number of records at which operator outputs flush, unless configured otherwise.
+    fn output_capacity(&self) -> Option<usize> { None }
+    /// The number of records at which exchange channels send their buffers, unless configured otherwise.
+    fn exchange_capacity(&self) -> Option<usize> { None }
+    /// The thread pool shared by the workers of the process, on which operators may fan out work.
+    #[cfg(feature = "rayon")]
+    fn pool(&self) -> Option<Arc<rayon::ThreadPool>> { None }
+    /// Records an operator, for the description of the dataflow graph.
+    fn register_operator(&self, _operator: Operator) { }
+    /// Records a channel between operators, for the description of the dataflow graph.
+    fn register_channel(&self, _channel: Channel) { }
+    /// A shared count of the bytes buffered in the channels of the dataflow with index `dataflow`.
+    ///
+    /// Channels add the bytes of records as they are sent, and subtract them as they are received.
+    fn buffered_counter(&self, _dataflow: usize) -> Option<Rc<Cell<i64>>> { None }
+    /// The memory account of the operator at `address`, if memory accounting is enabled.
+    fn memory_account(&self, _address: &[usize]) -> Option<MemoryAccount> { None }
+    /// The counts of the channel with identifier `identifier`, if the implementor keeps them.
+    fn channel_counter(&self, _identifier: usize) -> Option<ChannelCounter> { None }
+    /// Constructs a `RemoteActivator` for the operator at `path` on the worker with index `worker`.
+    ///
+    /// The result is `None` if the implementor cannot communicate activations to other workers.
+    fn remote_activator_for(&self, _worker: usize, _path: &[usize]) -> Option<RemoteActivator> { None }
+    /// A flag set once the computation is shutting down, at which point input handles close.
+    fn shutdown_signal(&self) -> Option<Rc<Cell<bool>>> { None }
+    /// How newly constructed scopes exchange progress updates with other workers.
+    fn progress_policy(&self) -> ProgressPolicy { ProgressPolicy::default() }
 }
 
 /// A `Worker` is the entry point to a timely dataflow computation. It wraps a `Allocate`,
@@ -55,6 +100,9 @@ pub trait AsWorker : Scheduler {
 pub struct Worker<A: Allocate> {
     timer: Instant,
     paths: Rc<RefCell<HashMap<usize, Vec<usize>>>>,
+    // Descriptions of constructed operators and channels, by identifier.
+    operators: Rc<RefCell<HashMap<usize, Operator>>>,
+    channels: Rc<RefCell<HashMap<usize, Channel>>>,
     allocator: Rc<RefCell<A>>,
     identifiers: Rc<RefCell<usize>>,
     // dataflows: Rc<RefCell<Vec<Wrapper>>>,
@@ -68,17 +116,247 @@ pub struct Worker<A: Allocate> {
     // Temporary storage for channel identifiers during dataflow construction.
     // These are then associated with a dataflow once constructed.
     temp_channel_ids: Rc<RefCell<Vec<usize>>>,
+
+    // Coordinates the retirement of dataflows with other workers.
+    control: Rc<RefCell<Control>>,
+
+    // Channels carrying activations to and from other workers.
+    remote_pushers: RemotePushers,
+    remote_puller: Rc<RefCell<Box<dyn Pull<ActivationMsg>>>>,
+
+    // Arrivals of workers at barriers.
+    barriers: Rc<RefCell<Barriers>>,
+
+    // Coordinates the shutdown of the computation with other workers.
+    shutdown: Rc<RefCell<Shutdown>>,
+
+    // Time after which a step defers remaining dataflows to the next step.
+    step_budget: Option<Duration>,
+
+    // Whether panics in dataflows are caught, failing the dataflow rather than the worker.
+    catch_panics: bool,
+
+    // Counts of bytes buffered in the channels of dataflows under construction.
+    buffered: Rc<RefCell<HashMap<usize, Rc<Cell<i64>>>>>,
+
+    // Accounts of the memory attributed to operators, if enabled.
+    memory: MemoryAccounts,
+
+    // Counts of the volume moved by channels, by identifier.
+    counters: Rc<RefCell<HashMap<usize, ChannelCounter>>>,
+
+    // Functions called at the end of steps, at most once in each interval, with the interval and the time each is next due.
+    step_hooks: Rc<RefCell<Vec<(Duration, Instant, StepHook<A>)>>>,
+
+    // Reports dataflows whose frontiers have stopped advancing, if enabled.
+    watchdog: Option<Watchdog>,
+
+    // The number of records at which outputs of newly constructed operators flush.
+    output_capacity: Rc<Cell<Option<usize>>>,
+
+    // The number of records at which exchange channels of newly constructed operators send.
+    exchange_capacity: Rc<Cell<Option<usize>>>,
+
+    // The thread pool shared by the workers of the process.
+    #[cfg(feature = "rayon")]
+    pool: Rc<RefCell<Option<Arc<rayon::ThreadPool>>>>,
+
+    // How newly constructed scopes exchange progress updates.
+    progress_policy: Rc<Cell<ProgressPolicy>>,
+
+    // What the worker does once the connection to another process fails.
+    peer_failure_policy: Rc<Cell<PeerFailurePolicy>>,
+    // The number of failed connections the worker has acted on.
+    peer_failures_seen: Rc<Cell<usize>>,
+    // The indices of the workers among which newly constructed dataflows are constructed.
+    live_workers: Rc<RefCell<Vec<usize>>>,
+    // The position of this worker among `live_workers`, updated as they change.
+    live_index: Rc<Cell<usize>>,
+}
+
+/// What a worker does once the connection to another process fails.
+///
+/// A process whose connection fails, because it has crashed or the network has failed, sends
+/// nothing further, and the progress of dataflows that exchange data or progress with it stalls.
+#[derive(Serialize, Deserialize, Abomonation, Debug, Clone, Copy, Default, Hash, Eq, PartialEq, Ord, PartialOrd)]
+pub enum PeerFailurePolicy {
+    /// The worker panics with the `PeerFailure` as its payload, taking down the computation.
+    #[default]
+    Abort,
+    /// The worker continues, for dataflows that do not depend on the failed process.
+    ///
+    /// The failures are reported by `Worker::peer_failures`, and dataflows that depend on a
+    /// failed process may be dropped with `Worker::drop_dataflow`.
+    Continue,
+    /// The worker drops its dataflows, and constructs subsequent dataflows among the surviving workers.
+    ///
+    /// Each failure removes the workers of the failed process from those among which dataflows
+    /// are constructed, as reported by `Worker::live_workers` and logged as a `RescaleEvent`. The
+    /// dataflows installed at the time include the removed workers and could not complete, and
+    /// are dropped. Subsequently constructed dataflows exchange data and progress only among the
+    /// surviving workers, whose scopes present them by their positions among the survivors, and
+    /// so exchange partitions are rerouted to them. This suits dataflows whose work may be
+    /// repeated or redistributed, such as embarrassingly parallel batches.
+    ///
+    /// Workers act on a failure at the end of the step in which they learn of it, and those that
+    /// construct a dataflow should agree on the survivors, for example by awaiting the report of
+    /// the failure with `Worker::peer_failures` before constructing the dataflow.
+    Degrade,
+}
+
+impl std::str::FromStr for PeerFailurePolicy {
+    type Err = String;
+    fn from_str(policy: &str) -> Result<Self, Self::Err> {
+        match policy {
+            "abort" => Ok(PeerFailurePolicy::Abort),
+            "continue" => Ok(PeerFailurePolicy::Continue),
+            "degrade" => Ok(PeerFailurePolicy::Degrade),
+            _ => Err(format!("unknown peer failure policy: {}", policy)),
+        }
+    }
+}
+
+/// Scheduling preferences for a dataflow, relative to other dataflows on the same worker.
+///
+/// In each step, active dataflows with a higher `level` are scheduled before those with a
+/// lower level. Among dataflows of the same level, those that have received the least
+/// scheduling time relative to their `weight` are scheduled first. If the worker has a step
+/// budget, dataflows not scheduled before the budget is spent are deferred to the next step,
+/// and each deferral raises the level of a dataflow until it is next scheduled.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct Priority {
+    /// Active dataflows of higher levels are scheduled first.
+    pub level: usize,
+    /// The relative share of scheduling time among active dataflows of the same level.
+    pub weight: u32,
+}
+
+impl Default for Priority {
+    fn default() -> Self {
+        Priority { level: 0, weight: 1 }
+    }
+}
+
+/// Limits on the resources a dataflow may use, enforced by each worker.
+///
+/// A dataflow that has been scheduled for more than `time_fraction` of a window of scheduling
+/// time, which renews every 100 milliseconds, is throttled: it is not scheduled again until the
+/// next window, even if active. A dataflow whose channels buffer more than `buffered_bytes`
+/// bytes at a worker fails, as if it had panicked, and is retired on all workers. Buffered bytes
+/// are estimated from the sizes of the record types, without any data they own, and account for
+/// records sent by operators at the worker but not yet received by operators at the worker.
+///
+/// Each worker logs a `ThrottleEvent` when it throttles a dataflow, or fails one.
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct Quota {
+    /// The largest fraction of each window of time the dataflow may spend scheduled.
+    pub time_fraction: Option<f64>,
+    /// The most bytes the channels of the dataflow may buffer.
+    pub buffered_bytes: Option<usize>,
+}
+
+/// A handle to a dataflow, as returned by `Worker::install_dataflow`.
+///
+/// The handle identifies the dataflow by its index, and its methods act on the worker they are
+/// presented with, which should be the worker that installed the dataflow.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct DataflowHandle {
+    index: usize,
+    name: String,
+    installed: Instant,
+}
+
+impl DataflowHandle {
+    /// The index of the dataflow, as used by `drop_dataflow`, `set_priority`, and other methods.
+    pub fn index(&self) -> usize { self.index }
+    /// The name of the dataflow.
+    pub fn name(&self) -> &str { &self.name }
+    /// The time at which the dataflow was installed, by the clock of its worker.
+    pub fn installed(&self) -> Instant { self.installed }
+
+    /// The status of the dataflow at `worker`.
+    pub fn status<A: Allocate>(&self, worker: &Worker<A>) -> DataflowStatus {
+        let control = worker.control.borrow();
+        if let Some(error) = control.failures.iter().find(|error| error.dataflow == self.index) {
+            DataflowStatus::Failed(error.clone())
+        }
+        else if !worker.dataflows.borrow().contains_key(&self.index) {
+            DataflowStatus::Dropped
+        }
+        else if control.retiring.contains_key(&self.index) {
+            DataflowStatus::Retiring
+        }
+        else {
+            DataflowStatus::Running
+        }
+    }
+
+    /// The current state of the operators of the dataflow at `worker`, as reported by `Worker::introspect`.
+    pub fn introspect<A: Allocate>(&self, worker: &Worker<A>) -> Vec<OperatorStatus> {
+        let mut statuses = worker.introspect();
+        statuses.retain(|status| status.addr[0] == self.index);
+        statuses
+    }
+
+    /// Drops the dataflow on all workers, as `Worker::retire_dataflow`.
+    pub fn retire<A: Allocate>(&self, worker: &mut Worker<A>) {
+        worker.retire_dataflow(self.index);
+    }
+
+    /// Drops the dataflow on `worker` only, as `Worker::drop_dataflow`.
+    pub fn drop_local<A: Allocate>(&self, worker: &mut Worker<A>) {
+        worker.drop_dataflow(self.index);
+    }
+}
+
+/// The status of a dataflow at a worker, as reported by `DataflowHandle::status`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum DataflowStatus {
+    /// The dataflow is installed and scheduled.
+    Running,
+    /// The dataflow is being retired, and will be dropped once all workers have stopped scheduling it.
+    Retiring,
+    /// The dataflow failed, and has been or will be retired.
+    Failed(DataflowError),
+    /// The dataflow is not installed, having been dropped.
+    Dropped,
 }
 
+/// The window of time within which a dataflow's time quota applies.
+const QUOTA_WINDOW: Duration = Duration::from_millis(100);
+
+/// The identifier of the channel of control messages among workers.
+const CONTROL_CHANNEL: usize = 0;
+/// The identifier of the channel of activations requested by other workers.
+const REMOTE_ACTIVATION_CHANNEL: usize = 1;
+/// The identifier of the channel of arrivals at barriers.
+const BARRIER_CHANNEL: usize = 2;
+/// The identifier of the channel of requests to shut down.
+const SHUTDOWN_CHANNEL: usize = 3;
+/// The number of channels the worker allocates for itself, before those of its dataflows.
+const WORKER_CHANNELS: usize = 4;
+
+// Dataflows are constructed among the live workers, and so scopes see a worker's position among the
+// live workers as its index, and their number as its peers. These differ from the allocator's index
+// and peers, reported by the inherent `Worker::index` and `Worker::peers`, once `PeerFailurePolicy::Degrade`
+// has removed workers.
 impl<A: Allocate> AsWorker for Worker<A> {
-    fn index(&self) -> usize { self.allocator.borrow().index() }
-    fn peers(&self) -> usize { self.allocator.borrow().peers() }
+    fn index(&self) -> usize { self.live_index.get() }
+    fn peers(&self) -> usize { self.live_workers.borrow().len() }
     fn allocate<D: Data>(&mut self, identifier: usize, address: &[usize]) -> (Vec<Box<dyn Push<Message<D>>>>, Box<dyn Pull<Message<D>>>) {
         if address.len() == 0 { panic!("Unacceptable address: Length zero"); }
         let mut paths = self.paths.borrow_mut();
         paths.insert(identifier, address.to_vec());
         self.temp_channel_ids.borrow_mut().push(identifier);
-        self.allocator.borrow_mut().allocate(identifier)
+        let (pushers, puller) = self.allocator.borrow_mut().allocate(identifier);
+        let live = self.live_workers.borrow();
+        if live.len() == pushers.len() {
+            (pushers, puller)
+        }
+        else {
+            let pushers = pushers.into_iter().enumerate().filter(|(worker, _)| live.contains(worker)).map(|(_, pusher)| pusher).collect();
+            (pushers, puller)
+        }
     }
     fn pipeline<T: 'static>(&mut self, identifier: usize, address: &[usize]) -> (ThreadPusher<Message<T>>, ThreadPuller<Message<T>>) {
         if address.len() == 0 { panic!("Unacceptable address: Length zero"); }
@@ -92,6 +370,33 @@ impl<A: Allocate> AsWorker for Worker<A> {
     fn log_register(&self) -> RefMut<crate::logging_core::Registry<crate::logging::WorkerIdentifier>> {
         self.log_register()
     }
+    fn output_capacity(&self) -> Option<usize> { self.output_capacity.get() }
+    fn exchange_capacity(&self) -> Option<usize> { self.exchange_capacity.get() }
+    #[cfg(feature = "rayon")]
+    fn pool(&self) -> Option<Arc<rayon::ThreadPool>> { self.pool.borrow().clone() }
+    fn register_operator(&self, operator: Operator) {
+        self.operators.borrow_mut().insert(operator.id, operator);
+    }
+    fn register_channel(&self, channel: Channel) {
+        self.channels.borrow_mut().insert(channel.id, channel);
+    }
+    fn buffered_counter(&self, dataflow: usize) -> Option<Rc<Cell<i64>>> {
+        Some(self.buffered.borrow_mut().entry(dataflow).or_default().clone())
+    }
+    fn memory_account(&self, address: &[usize]) -> Option<MemoryAccount> {
+        self.memory.account(address)
+    }
+    fn channel_counter(&self, identifier: usize) -> Option<ChannelCounter> {
+        Some(self.counters.borrow_mut().entry(identifier).or_default().clone())
+    }
+    fn remote_activator_for(&self, worker: usize, path: &[usize]) -> Option<RemoteActivator> {
+        let worker = self.live_workers.borrow()[worker];
+        Some(RemoteActivator::new(worker, path, self.remote_pushers.clone()))
+    }
+    fn shutdown_signal(&self) -> Option<Rc<Cell<bool>>> {
+        Some(self.shutdown.borrow().requested.clone())
+    }
+    fn progress_policy(&self) -> ProgressPolicy { self.progress_policy.get() }
 }
 
 impl<A: Allocate> Scheduler for Worker<A> {
@@ -102,20 +407,69 @@ impl<A: Allocate> Scheduler for Worker<A> {
 
 impl<A: Allocate> Worker<A> {
     /// Allocates a new `Worker` bound to a channel allocator.
-    pub fn new(c: A) -> Worker<A> {
+    pub fn new(mut c: A) -> Worker<A> {
         let now = Instant::now();
         let index = c.index();
+        let peers = c.peers();
+        // Allocate the worker's own channels first, so that their identifiers agree across workers.
+        let (pushers, puller) = c.allocate(CONTROL_CHANNEL);
+        let (remote_pushers, remote_puller) = c.allocate(REMOTE_ACTIVATION_CHANNEL);
+        let (barrier_pushers, barrier_puller) = c.allocate(BARRIER_CHANNEL);
+        let (shutdown_pushers, shutdown_puller) = c.allocate(SHUTDOWN_CHANNEL);
         Worker {
             timer: now.clone(),
             paths:  Default::default(),
+            operators:  Default::default(),
+            channels:  Default::default(),
             allocator: Rc::new(RefCell::new(c)),
-            identifiers:  Default::default(),
+            identifiers: Rc::new(RefCell::new(WORKER_CHANNELS)),
             dataflows: Default::default(),
             dataflow_counter:  Default::default(),
             logging: Rc::new(RefCell::new(crate::logging_core::Registry::new(now.clone(), index))),
             activations: Rc::new(RefCell::new(Activations::new(now.clone()))),
             active_dataflows: Default::default(),
             temp_channel_ids:  Default::default(),
+            control: Rc::new(RefCell::new(Control {
+                pushers,
+                puller,
+                retiring: HashMap::new(),
+                failures: Vec::new(),
+            })),
+            remote_pushers: Rc::new(RefCell::new(remote_pushers)),
+            remote_puller: Rc::new(RefCell::new(remote_puller)),
+            barriers: Rc::new(RefCell::new(Barriers {
+                pushers: barrier_pushers,
+                puller: barrier_puller,
+                index,
+                reached: 0,
+                awaiting: false,
+                arrivals: HashMap::new(),
+            })),
+            shutdown: Rc::new(RefCell::new(Shutdown {
+                pushers: shutdown_pushers,
+                puller: shutdown_puller,
+                requested: Rc::new(Cell::new(false)),
+            })),
+            step_budget: None,
+            catch_panics: false,
+            buffered: Default::default(),
+            memory: MemoryAccounts::new(),
+            counters: Default::default(),
+            step_hooks: Default::default(),
+            watchdog: None,
+            output_capacity: Rc::new(Cell::new(None)),
+            exchange_capacity: Rc::new(Cell::new(None)),
+            #[cfg(feature = "rayon")]
+            pool: Default::default(),
+            progress_policy: Rc::new(Cell::new(ProgressPolicy {
+                mode: if ::std::env::var("DEFAULT_PROGRESS_MODE") == Ok("DEMAND".to_owned()) { ProgressMode::Demand } else { ProgressMode::Eager },
+                batch: None,
+                latency: false,
+            })),
+            peer_failure_policy: Rc::new(Cell::new(PeerFailurePolicy::default())),
+            peer_failures_seen: Rc::new(Cell::new(0)),
+            live_workers: Rc::new(RefCell::new((0 .. peers).collect())),
+            live_index: Rc::new(Cell::new(index)),
         }
     }
 
@@ -172,18 +526,176 @@ impl<A: Allocate> Worker<A> {
     /// });
     /// ```
     pub fn step_or_park(&mut self, duration: Option<Duration>) -> bool {
+        self.step_or_park_until(duration, None)
+    }
+
+    /// Schedules activated operators until `deadline`, and then returns.
+    ///
+    /// The worker repeatedly performs steps, parking when there is no work to perform, until the
+    /// deadline passes. Dataflows not yet scheduled in a step when the deadline passes are deferred
+    /// to the next step, as with `set_step_budget`, though at least one step is always performed.
+    /// This allows applications to interleave the work of the worker with other event loops, at a
+    /// bounded cadence. Like `step`, the method returns `true` if dataflows remain.
+    ///
+    /// # Examples
+    /// ```
+    /// timely::execute_from_args(::std::env::args(), |worker| {
+    ///
+    ///     use std::time::{Duration, Instant};
+    ///     use timely::dataflow::operators::{ToStream, Inspect, Probe};
+    ///
+    ///     let probe =
+    ///     worker.dataflow::<usize,_,_>(|scope| {
+    ///         (0 .. 10)
+    ///             .to_stream(scope)
+    ///             .inspect(|x| println!("{:?}", x))
+    ///             .probe()
+    ///     });
+    ///
+    ///     while probe.less_than(&1) {
+    ///         worker.step_with_deadline(Instant::now() + Duration::from_millis(10));
+    ///         // Attend to other work here.
+    ///     }
+    /// });
+    /// ```
+    pub fn step_with_deadline(&mut self, deadline: Instant) -> bool {
+        loop {
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            let incomplete = self.step_or_park_until(Some(remaining), Some(deadline));
+            if !incomplete || Instant::now() >= deadline {
+                return incomplete;
+            }
+        }
+    }
+
+    /// Returns a future that performs one step of the computation, once there is work to perform.
+    ///
+    /// The future is an alternative to `step_or_park(None)` for workers driven by an asynchronous
+    /// executor, as it waits for work without parking the thread. It resolves once operators are
+    /// activated, by data received from other workers, by a `SyncActivator`, or by the expiry
+    /// of a delayed activation, and then schedules active dataflows as `step` does, resolving to
+    /// `true` if dataflows remain. The future resolves immediately if there are no dataflows.
+    ///
+    /// Other workers and `SyncActivator`s wake the executor through the buzzers of the thread on
+    /// which the worker was created, and the future must be polled on that thread. The expiry of
+    /// delayed activations is awaited by a short-lived helper thread.
+    ///
+    /// # Examples
+    /// ```
+    /// use timely::communication::Allocate;
+    /// use timely::worker::Worker;
+    ///
+    /// // Steps the worker until its dataflows are complete, yielding to the executor otherwise.
+    /// async fn run<A: Allocate>(worker: &mut Worker<A>) {
+    ///     while worker.step_async().await { }
+    /// }
+    /// ```
+    pub fn step_async(&mut self) -> StepAsync<'_, A> {
+        StepAsync { worker: self, armed: None }
+    }
+
+    /// Performs one step of the computation without parking, and reports when to step again.
+    ///
+    /// The method suits workers driven by an event loop that must not block, such as that of a
+    /// browser, where a worker on the `Thread` allocator is created with `Worker::new` rather than
+    /// by `execute`. After each step the event loop should step the worker again as indicated:
+    /// after the returned delay, which is zero while operators remain active and otherwise
+    /// awaits the next delayed activation, or once it has supplied the worker with more input.
+    ///
+    /// # Examples
+    /// ```
+    /// use timely::communication::allocator::Thread;
+    /// use timely::dataflow::operators::{ToStream, Inspect};
+    /// use timely::worker::{NextStep, Worker};
+    ///
+    /// let mut worker = Worker::new(Thread::new());
+    /// worker.dataflow::<u64,_,_>(|scope| {
+    ///     (0 .. 10).to_stream(scope).inspect(|x| println!("{:?}", x));
+    /// });
+    ///
+    /// // An event loop would schedule a callback rather than loop.
+    /// while let NextStep::After(_delay) = worker.step_nonblocking() { }
+    /// ```
+    pub fn step_nonblocking(&mut self) -> NextStep {
+        if !self.step() {
+            NextStep::Complete
+        }
+        else {
+            match self.activations.borrow().empty_for() {
+                Some(delay) => NextStep::After(delay),
+                None => NextStep::Idle,
+            }
+        }
+    }
+
+    // Performs one step, after which dataflows not yet scheduled once `deadline` passes are deferred.
+    fn step_or_park_until(&mut self, duration: Option<Duration>, deadline: Option<Instant>) -> bool {
+
+        let empty_for = self.receive_events();
+        // Determine the minimum park duration, where `None` are an absence of a constraint.
+        let delay = match (duration, empty_for) {
+            (Some(x), Some(y)) => Some(std::cmp::min(x,y)),
+            (x, y) => x.or(y),
+        };
+        // Wake for the watchdog, if it asks to observe the frontiers of parked workers.
+        let delay = match (delay, self.watchdog.as_ref().and_then(|watchdog| watchdog.next_check())) {
+            (Some(x), Some(y)) => Some(std::cmp::min(x,y)),
+            (x, y) => x.or(y),
+        };
+
+        // Consider parking only if we have no pending events, some dataflows, and a non-zero duration.
+        if !self.dataflows.borrow().is_empty() && delay != Some(Duration::new(0,0)) {
+
+            // Log parking and flush log.
+            self.logging().as_mut().map(|l| l.log(crate::logging::ParkEvent::park(delay)));
+            self.logging.borrow_mut().flush();
+
+            self.allocator
+                .borrow()
+                .await_events(delay);
+
+            // Log return from unpark.
+            self.logging().as_mut().map(|l| l.log(crate::logging::ParkEvent::unpark()));
+        }
+        else {
+            self.schedule_dataflows(deadline);
+        }
+
+        self.finish_step()
+    }
+
+    // Performs one step if there is work to do without waiting, and indicates whether it did.
+    pub(crate) fn step_if_active(&mut self) -> bool {
+        let active = self.receive_events() == Some(Duration::new(0, 0));
+        if active {
+            self.schedule_dataflows(None);
+        }
+        self.finish_step();
+        active
+    }
 
+    // Receives channel events and messages from other workers, and returns the time until the next activation.
+    fn receive_events(&mut self) -> Option<Duration> {
+
+        let mut saturated = Vec::new();
+        // Arrivals at barriers are received by `barrier` itself, which should not park after they are.
+        let awaiting = self.barriers.borrow().awaiting;
+        let mut arrivals = false;
         {   // Process channel events. Activate responders.
             let mut allocator = self.allocator.borrow_mut();
             allocator.receive();
             let events = allocator.events().clone();
             let mut borrow = events.borrow_mut();
             let paths = self.paths.borrow();
-            for (channel, _event) in borrow.drain(..) {
-                // TODO: Pay more attent to `_event`.
-                // Consider tracking whether a channel
+            let saturation = self.activations.borrow().saturation().clone();
+            for (channel, event) in borrow.drain(..) {
+                arrivals |= awaiting && channel == BARRIER_CHANNEL;
+                // TODO: Consider tracking whether a channel
                 // in non-empty, and only activating
                 // on the basis of non-empty channels.
+                if let Some(queued) = saturation.record(channel, &event) {
+                    saturated.push(crate::logging::SaturationEvent::Channel { channel, queued });
+                }
                 // TODO: This is a sloppy way to deal
                 // with channels that may not be alloc'd.
                 if let Some(path) = paths.get(&channel) {
@@ -193,57 +705,125 @@ impl<A: Allocate> Worker<A> {
                 }
             }
         }
+        if !saturated.is_empty() {
+            if let Some(logger) = self.logging() {
+                logger.log_many(saturated);
+            }
+        }
+
+        // Act on control messages from other workers.
+        self.receive_control();
+        self.shutdown.borrow_mut().receive();
+
+        // Act on activations sent by other workers.
+        while let Some(message) = self.remote_puller.borrow_mut().pull() {
+            self.activations
+                .borrow_mut()
+                .activate(&message[..]);
+        }
 
         // Organize activations.
         self.activations
             .borrow_mut()
             .advance();
 
-        // Consider parking only if we have no pending events, some dataflows, and a non-zero duration.
-        let empty_for = self.activations.borrow().empty_for();
-        // Determine the minimum park duration, where `None` are an absence of a constraint.
-        let delay = match (duration, empty_for) {
-            (Some(x), Some(y)) => Some(std::cmp::min(x,y)),
-            (x, y) => x.or(y),
-        };
+        if arrivals { Some(Duration::new(0, 0)) } else { self.activations.borrow().empty_for() }
+    }
 
-        if !self.dataflows.borrow().is_empty() && delay != Some(Duration::new(0,0)) {
+    // Schedules active dataflows, deferring those not yet scheduled once `deadline` passes.
+    fn schedule_dataflows(&mut self, deadline: Option<Instant>) {
 
-            // Log parking and flush log.
-            self.logging().as_mut().map(|l| l.log(crate::logging::ParkEvent::park(delay)));
-            self.logging.borrow_mut().flush();
+        let active_dataflows = &mut self.active_dataflows;
+        self.activations
+            .borrow_mut()
+            .for_extensions(&[], |index| active_dataflows.push(index));
 
-            self.allocator
-                .borrow()
-                .await_events(delay);
+        // Dataflows being retired are no longer scheduled.
+        let control = self.control.borrow();
+        active_dataflows.retain(|index| !control.retiring.contains_key(index));
 
-            // Log return from unpark.
-            self.logging().as_mut().map(|l| l.log(crate::logging::ParkEvent::unpark()));
-        }
-        else {   // Schedule active dataflows.
+        let mut dataflows = self.dataflows.borrow_mut();
 
-            let active_dataflows = &mut self.active_dataflows;
-            self.activations
-                .borrow_mut()
-                .for_extensions(&[], |index| active_dataflows.push(index));
-
-            let mut dataflows = self.dataflows.borrow_mut();
-            for index in active_dataflows.drain(..) {
-                // Step dataflow if it exists, remove if not incomplete.
-                if let Entry::Occupied(mut entry) = dataflows.entry(index) {
-                    let incomplete = entry.get_mut().step();
-                    if !incomplete {
+        // Order dataflows by priority level, raised by each deferral so that none starve,
+        // and then by scheduling time relative to weight, least served first.
+        active_dataflows.sort_by_key(|index| {
+            dataflows
+                .get(index)
+                .map(|wrapper| (Reverse(wrapper.priority.level.saturating_add(wrapper.deferrals)), wrapper.served()))
+        });
+
+        let mut failed = Vec::new();
+        let start = Instant::now();
+        for (position, index) in active_dataflows.drain(..).enumerate() {
+            // Once the step budget is spent or the deadline passes, defer remaining dataflows to the next step.
+            let spent = self.step_budget.map(|budget| start.elapsed() >= budget) == Some(true);
+            let late = deadline.map(|deadline| Instant::now() >= deadline) == Some(true);
+            if position > 0 && (spent || late) {
+                if let Some(wrapper) = dataflows.get_mut(&index) {
+                    wrapper.deferrals += 1;
+                    self.activations.borrow_mut().defer(&[index]);
+                }
+                continue;
+            }
+            // Step dataflow if it exists, remove if not incomplete.
+            if let Entry::Occupied(mut entry) = dataflows.entry(index) {
+                // Dataflows exceeding their quotas are failed, or throttled until the next window.
+                if let Some(message) = entry.get_mut().exceeds_bytes() {
+                    failed.push((index, message));
+                    continue;
+                }
+                let now = self.activations.borrow().clock().now();
+                if let Some(remaining) = entry.get_mut().throttle_for(now) {
+                    self.activations.borrow_mut().defer_after(&[index], remaining);
+                    continue;
+                }
+                let outcome = if self.catch_panics {
+                    let wrapper = entry.get_mut();
+                    std::panic::catch_unwind(AssertUnwindSafe(|| wrapper.step())).map_err(|payload| panic_message(&*payload))
+                }
+                else {
+                    Ok(entry.get_mut().step())
+                };
+                match outcome {
+                    Ok(true) => { },
+                    Ok(false) => {
                         let mut paths = self.paths.borrow_mut();
                         for channel in entry.get_mut().channel_ids.drain(..) {
                             paths.remove(&channel);
                         }
                         entry.remove_entry();
-                    }
+                    },
+                    Err(message) => failed.push((index, message)),
                 }
             }
         }
 
-        // Clean up, indicate if dataflows remain.
+        // Failed dataflows are retired, informing other workers of the failure.
+        drop(control);
+        let index = self.index();
+        for (dataflow, message) in failed {
+            self.control.borrow_mut().fail(dataflow, index, message);
+        }
+    }
+
+    // Cleans up after a step, and indicates if dataflows remain.
+    fn finish_step(&mut self) -> bool {
+        self.receive_peer_failures();
+        if !self.step_hooks.borrow().is_empty() {
+            let now = Instant::now();
+            for (interval, due, hook) in self.step_hooks.borrow_mut().iter_mut() {
+                if *due <= now {
+                    *due = now + *interval;
+                    hook(self);
+                }
+            }
+        }
+        if let Some(mut watchdog) = self.watchdog.take() {
+            if let (Some(report), Some(l)) = (watchdog.check(self), self.logging()) {
+                l.log(report);
+            }
+            self.watchdog = Some(watchdog);
+        }
         self.logging.borrow_mut().flush();
         self.allocator.borrow_mut().release();
         !self.dataflows.borrow().is_empty()
@@ -275,6 +855,12 @@ impl<A: Allocate> Worker<A> {
 
     /// The index of the worker out of its peers.
     ///
+    /// This is the worker's index among all workers of the computation, which never changes.
+    /// Scopes instead present the worker by its position among the live workers, through
+    /// `AsWorker::index`, which is also available as `Worker::live_index`. The two agree until
+    /// `PeerFailurePolicy::Degrade` removes workers, after which code within dataflows, such as
+    /// exchange functions, should use the scope's index rather than this one.
+    ///
     /// # Examples
     /// ```
     /// timely::execute_from_args(::std::env::args(), |worker| {
@@ -288,6 +874,10 @@ impl<A: Allocate> Worker<A> {
     /// });
     /// ```
     pub fn index(&self) -> usize { self.allocator.borrow().index() }
+    /// The position of the worker among the live workers, as presented to scopes by `AsWorker::index`.
+    ///
+    /// This equals `Worker::index` unless `PeerFailurePolicy::Degrade` has removed workers.
+    pub fn live_index(&self) -> usize { self.live_index.get() }
     /// The total number of peer workers.
     ///
     /// # Examples
@@ -304,6 +894,37 @@ impl<A: Allocate> Worker<A> {
     /// ```
     pub fn peers(&self) -> usize { self.allocator.borrow().peers() }
 
+    /// The number of processes hosting the workers.
+    pub fn processes(&self) -> usize { self.allocator.borrow().worker_offsets().len() - 1 }
+
+    /// The process hosting this worker.
+    pub fn process(&self) -> usize { self.process_of(self.index()) }
+
+    /// The process hosting the worker with index `worker`.
+    ///
+    /// Processes may host different numbers of workers, whose indices are assigned in order of
+    /// the processes, and so the process of a worker is found from the number of workers of each
+    /// process, which the processes exchange as they connect.
+    pub fn process_of(&self, worker: usize) -> usize {
+        let offsets = self.allocator.borrow().worker_offsets();
+        offsets[1 ..].iter().position(|offset| worker < *offset).unwrap_or(offsets.len() - 2)
+    }
+
+    /// The indices of the workers hosted by `process`.
+    ///
+    /// # Examples
+    /// ```
+    /// timely::execute(timely::Configuration::Process(3), |worker| {
+    ///     assert_eq!(worker.processes(), 1);
+    ///     assert_eq!(worker.process(), 0);
+    ///     assert_eq!(worker.process_workers(0), 0 .. 3);
+    /// }).unwrap();
+    /// ```
+    pub fn process_workers(&self, process: usize) -> ::std::ops::Range<usize> {
+        let offsets = self.allocator.borrow().worker_offsets();
+        offsets[process] .. offsets[process + 1]
+    }
+
     /// A timer started at the initiation of the timely computation.
     ///
     /// # Examples
@@ -370,12 +991,7 @@ impl<A: Allocate> Worker<A> {
         self.dataflow_core("Dataflow", logging, Box::new(()), |_, child| func(child))
     }
 
-    /// Construct a new dataflow with specific configurations.
-    ///
-    /// This method constructs a new dataflow, using a name, logger, and additional
-    /// resources specified as argument. The name is cosmetic, the logger is used to
-    /// handle events generated by the dataflow, and the additional resources are kept
-    /// alive for as long as the dataflow is alive (use case: shared library bindings).
+    /// Construct a new dataflow with a (purely cosmetic) name.
     ///
     /// # Examples
     /// ```
@@ -383,52 +999,585 @@ impl<A: Allocate> Worker<A> {
     ///
     ///     // We must supply the timestamp type here, although
     ///     // it would generally be determined by type inference.
-    ///     worker.dataflow_core::<usize,_,_,_>(
-    ///         "dataflow X",           // Dataflow name
-    ///         None,                   // Optional logger
-    ///         37,                     // Any resources
-    ///         |resources, scope| {    // Closure
+    ///     worker.dataflow_named::<usize,_,_>("Some Dataflow", |scope| {
     ///
-    ///             // uses of `resources`, `scope`to build dataflow
+    ///         // uses of `scope` to build dataflow
     ///
-    ///         }
-    ///     );
+    ///     });
     /// });
     /// ```
-    pub fn dataflow_core<T, R, F, V>(&mut self, name: &str, mut logging: Option<TimelyLogger>, mut resources: V, func: F) -> R
+    pub fn dataflow_named<T, R, F>(&mut self, name: &str, func: F) -> R
     where
         T: Refines<()>,
-        F: FnOnce(&mut V, &mut Child<Self, T>)->R,
-        V: Any+'static,
+        F: FnOnce(&mut Child<Self, T>)->R,
     {
-        let addr = vec![];
-        let dataflow_index = self.allocate_dataflow_index();
-        let identifier = self.new_identifier();
-
-        let subscope = SubgraphBuilder::new_from(dataflow_index, addr, logging.clone(), name);
-        let subscope = RefCell::new(subscope);
+        let logging = self.logging.borrow_mut().get("timely");
+        self.dataflow_core(name, logging, Box::new(()), |_, child| func(child))
+    }
 
-        let result = {
-            let mut builder = Child {
-                subgraph: &subscope,
-                parent: self.clone(),
-                logging: logging.clone(),
-            };
-            func(&mut resources, &mut builder)
+    /// Construct a new named dataflow, returning a handle by which to manage it.
+    ///
+    /// The handle records the index, name, and installation time of the dataflow, and reports
+    /// its status or requests its teardown on the worker it is presented with.
+    ///
+    /// # Examples
+    /// ```
+    /// use timely::dataflow::operators::{Input, Inspect};
+    /// use timely::worker::DataflowStatus;
+    ///
+    /// timely::execute_from_args(::std::env::args(), |worker| {
+    ///
+    ///     let (handle, mut input) = worker.install_dataflow::<usize,_,_>("Numbers", |scope| {
+    ///         let (input, stream) = scope.new_input::<usize>();
+    ///         stream.inspect(|x| println!("{:?}", x));
+    ///         input
+    ///     });
+    ///     assert_eq!(handle.name(), "Numbers");
+    ///     assert_eq!(handle.status(worker), DataflowStatus::Running);
+    ///
+    ///     // The dataflow cannot complete while `input` is held, and is torn down instead.
+    ///     input.send(worker.index());
+    ///     handle.retire(worker);
+    ///     while handle.status(worker) != DataflowStatus::Dropped {
+    ///         worker.step();
+    ///     }
+    /// });
+    /// ```
+    pub fn install_dataflow<T, R, F>(&mut self, name: &str, func: F) -> (DataflowHandle, R)
+    where
+        T: Refines<()>,
+        F: FnOnce(&mut Child<Self, T>)->R,
+    {
+        let handle = DataflowHandle {
+            index: self.next_dataflow_index(),
+            name: name.to_owned(),
+            installed: self.activations.borrow().clock().now(),
         };
+        let result = self.dataflow_named(name, func);
+        (handle, result)
+    }
 
-        let mut operator = subscope.into_inner().build(self);
-
-        logging.as_mut().map(|l| l.log(crate::logging::OperatesEvent {
-            id: identifier,
+    /// Construct a new dataflow with a scheduling priority.
+    ///
+    /// # Examples
+    /// ```
+    /// use timely::worker::Priority;
+    ///
+    /// timely::execute_from_args(::std::env::args(), |worker| {
+    ///
+    ///     // Receive twice the scheduling time of dataflows of default priority.
+    ///     worker.dataflow_with_priority::<usize,_,_>(Priority { level: 0, weight: 2 }, |scope| {
+    ///
+    ///         // uses of `scope` to build dataflow
+    ///
+    ///     });
+    /// });
+    /// ```
+    pub fn dataflow_with_priority<T, R, F>(&mut self, priority: Priority, func: F) -> R
+    where
+        T: Refines<()>,
+        F: FnOnce(&mut Child<Self, T>)->R,
+    {
+        let index = self.next_dataflow_index();
+        let result = self.dataflow(func);
+        self.set_priority(index, priority);
+        result
+    }
+
+    /// Sets the scheduling priority of an identified dataflow.
+    ///
+    /// The method has no effect if the dataflow is not installed.
+    pub fn set_priority(&mut self, dataflow_identifier: usize, priority: Priority) {
+        if let Some(wrapper) = self.dataflows.borrow_mut().get_mut(&dataflow_identifier) {
+            wrapper.priority = priority;
+        }
+    }
+
+    /// Sets the resource limits of an identified dataflow.
+    ///
+    /// The method has no effect if the dataflow is not installed.
+    ///
+    /// # Examples
+    /// ```
+    /// use timely::dataflow::operators::{ToStream, Inspect};
+    /// use timely::worker::Quota;
+    ///
+    /// timely::execute_from_args(::std::env::args(), |worker| {
+    ///
+    ///     let index = worker.next_dataflow_index();
+    ///     worker.dataflow::<usize,_,_>(|scope| {
+    ///         (0 .. 10).to_stream(scope)
+    ///                  .inspect(|x| println!("seen: {:?}", x));
+    ///     });
+    ///
+    ///     // Spend at most a quarter of the worker's time, and buffer at most a megabyte.
+    ///     worker.set_quota(index, Quota { time_fraction: Some(0.25), buffered_bytes: Some(1 << 20) });
+    ///     assert_eq!(worker.buffered_bytes(index), Some(0));
+    /// });
+    /// ```
+    pub fn set_quota(&mut self, dataflow_identifier: usize, quota: Quota) {
+        if let Some(wrapper) = self.dataflows.borrow_mut().get_mut(&dataflow_identifier) {
+            wrapper.quota = quota;
+        }
+    }
+
+    /// The bytes buffered in the channels of an identified dataflow, as estimated for `Quota`.
+    ///
+    /// The result is `None` if the dataflow is not installed.
+    pub fn buffered_bytes(&self, dataflow_identifier: usize) -> Option<usize> {
+        self.dataflows
+            .borrow()
+            .get(&dataflow_identifier)
+            .map(|wrapper| ::std::cmp::max(wrapper.buffered.get(), 0) as usize)
+    }
+
+    /// Bounds the time each step spends scheduling dataflows.
+    ///
+    /// Once a step has spent `budget` scheduling dataflows, the remaining active dataflows
+    /// are deferred to the next step. At least one dataflow is scheduled in each step. The
+    /// default of `None` schedules all active dataflows in each step.
+    ///
+    /// # Examples
+    /// ```
+    /// use std::time::Duration;
+    ///
+    /// timely::execute_from_args(::std::env::args(), |worker| {
+    ///     worker.set_step_budget(Some(Duration::from_millis(10)));
+    /// });
+    /// ```
+    pub fn set_step_budget(&mut self, budget: Option<Duration>) {
+        self.step_budget = budget;
+    }
+
+    /// Sets whether panics in dataflows fail the dataflow, rather than the worker.
+    ///
+    /// If set, a panic while scheduling a dataflow is caught and the dataflow is retired, as
+    /// with `retire_dataflow`, on all workers. Each worker records the failure, reported by
+    /// `failures`, and once the worker completes `execute` resumes the panic with the first
+    /// failure as its payload, which `WorkerGuards::join_payloads` presents. The default of
+    /// `false` leaves panics to unwind the worker thread.
+    ///
+    /// Other dataflows continue to run, though they may observe the failed dataflow as stalled,
+    /// and should consult `failures` if they depend on it. As with retired dataflows, no further
+    /// records should be sent to the inputs of a failed dataflow.
+    ///
+    /// # Examples
+    /// ```
+    /// use timely::dataflow::operators::{ToStream, Inspect};
+    /// use timely::worker::DataflowError;
+    ///
+    /// // Silence the report of the panic.
+    /// std::panic::set_hook(Box::new(|_| { }));
+    ///
+    /// let guards = timely::execute_from_args(::std::env::args(), |worker| {
+    ///     worker.set_catch_panics(true);
+    ///     worker.dataflow::<usize,_,_>(|scope| {
+    ///         (0 .. 10).to_stream(scope)
+    ///                  .inspect(|x| if *x == 5 { panic!("found five") });
+    ///     });
+    /// }).unwrap();
+    ///
+    /// for result in guards.join_payloads() {
+    ///     let payload = result.unwrap_err();
+    ///     let error = payload.downcast_ref::<DataflowError>().unwrap();
+    ///     assert_eq!(error.dataflow, 0);
+    ///     assert_eq!(error.message, "found five");
+    /// }
+    /// ```
+    pub fn set_catch_panics(&mut self, catch: bool) {
+        self.catch_panics = catch;
+    }
+
+    /// The dataflows that have failed, on this worker or on others, as recorded by this worker.
+    ///
+    /// Failures are only recorded if panics are caught, as arranged by `set_catch_panics`.
+    pub fn failures(&self) -> Vec<DataflowError> {
+        self.control.borrow().failures.clone()
+    }
+
+    /// Sets what the worker does once the connection to another process fails.
+    ///
+    /// Network threads record the failures of their connections, which the worker acts on at the
+    /// end of its next step. Each failure is logged as a `PeerFailureEvent`, and then by default
+    /// the worker aborts, resuming a panic with the `PeerFailure` as its payload. With the policy
+    /// `Continue`, the worker instead continues with the surviving processes, for dataflows that
+    /// can tolerate the loss, and with the policy `Degrade` it drops its dataflows and constructs
+    /// subsequent dataflows among the surviving workers. All workers should set the same policy.
+    ///
+    /// # Examples
+    /// ```
+    /// use timely::worker::PeerFailurePolicy;
+    ///
+    /// timely::execute_from_args(::std::env::args(), |worker| {
+    ///     worker.set_peer_failure_policy(PeerFailurePolicy::Continue);
+    ///     worker.step();
+    ///     for failure in worker.peer_failures() {
+    ///         println!("worker {}: {}", worker.index(), failure);
+    ///     }
+    /// }).unwrap();
+    /// ```
+    pub fn set_peer_failure_policy(&mut self, policy: PeerFailurePolicy) {
+        // Surviving workers may drop channels that the failed workers' former peers still send on.
+        if policy == PeerFailurePolicy::Degrade {
+            self.allocator.borrow_mut().discard_undeliverable();
+        }
+        self.peer_failure_policy.set(policy);
+    }
+
+    /// The connections to other processes that have failed, and that the worker has acted on.
+    pub fn peer_failures(&self) -> Vec<PeerFailure> {
+        let mut failures = self.allocator.borrow().peer_failures();
+        failures.truncate(self.peer_failures_seen.get());
+        failures
+    }
+
+    /// The indices of the workers among which dataflows are constructed.
+    ///
+    /// These are all workers, unless the policy `PeerFailurePolicy::Degrade` has removed the
+    /// workers of failed processes. Scopes present workers by their positions among these.
+    ///
+    /// # Examples
+    /// ```
+    /// timely::execute(timely::Configuration::Process(2), |worker| {
+    ///     assert_eq!(worker.live_workers(), vec![0, 1]);
+    /// }).unwrap();
+    /// ```
+    pub fn live_workers(&self) -> Vec<usize> {
+        self.live_workers.borrow().clone()
+    }
+
+    // Logs failures of connections not yet acted on, and aborts or degrades if the policy says to.
+    fn receive_peer_failures(&mut self) {
+        let failures = self.allocator.borrow().peer_failures();
+        let seen = self.peer_failures_seen.get();
+        if failures.len() > seen {
+            self.peer_failures_seen.set(failures.len());
+            let policy = self.peer_failure_policy.get();
+            if let Some(logger) = self.logging() {
+                logger.log_many(failures[seen ..].iter().map(|failure| crate::logging::PeerFailureEvent {
+                    process: failure.process,
+                    message: failure.message.clone(),
+                    policy,
+                }));
+            }
+            if policy == PeerFailurePolicy::Abort {
+                self.logging.borrow_mut().flush();
+                std::panic::resume_unwind(Box::new(failures[seen].clone()));
+            }
+            if policy == PeerFailurePolicy::Degrade {
+                let processes = failures[seen ..].iter().map(|failure| failure.process).collect::<Vec<_>>();
+                let lost = processes.iter().flat_map(|process| self.process_workers(*process)).collect::<HashSet<_>>();
+                self.live_workers.borrow_mut().retain(|worker| !lost.contains(worker));
+                // The worker's own process is never among those lost, and so it remains live.
+                let index = self.index();
+                self.live_index.set(self.live_workers.borrow().iter().position(|worker| *worker == index).expect("worker not live"));
+                let mut dropped = self.installed_dataflows();
+                dropped.sort();
+                for dataflow in dropped.iter() {
+                    self.drop_dataflow(*dataflow);
+                }
+                if let Some(logger) = self.logging() {
+                    logger.log(crate::logging::RescaleEvent { processes, workers: self.live_workers(), dropped });
+                }
+            }
+        }
+    }
+
+    /// Sets the number of records at which the outputs of subsequently constructed operators flush.
+    ///
+    /// Smaller capacities reduce the latency of records through pipelines, at the expense of
+    /// throughput, and larger capacities do the reverse. Setting the capacity before constructing
+    /// a dataflow establishes the default for its operators, which may override it individually
+    /// with `OperatorBuilder::set_output_capacity`. The capacity `None` restores the default.
+    ///
+    /// # Examples
+    /// ```
+    /// use timely::dataflow::operators::{ToStream, Inspect};
+    ///
+    /// timely::execute_from_args(::std::env::args(), |worker| {
+    ///
+    ///     // Flush outputs after every sixteen records.
+    ///     worker.set_output_capacity(Some(16));
+    ///     worker.dataflow::<usize,_,_>(|scope| {
+    ///         (0..100).to_stream(scope)
+    ///                 .inspect_batch(|_t, batch| assert!(batch.len() <= 16));
+    ///     });
+    ///     worker.set_output_capacity(None);
+    /// }).unwrap();
+    /// ```
+    pub fn set_output_capacity(&mut self, capacity: Option<usize>) {
+        assert!(capacity != Some(0), "Output capacity must be positive");
+        self.output_capacity.set(capacity);
+    }
+
+    /// Sets the number of records at which the exchange channels of subsequently constructed operators send.
+    ///
+    /// An exchange buffers records for each worker, and sends them once it holds this many for
+    /// the worker, or as the operator completes a time or yields. As with `set_output_capacity`,
+    /// setting the capacity before constructing a dataflow establishes the default for its
+    /// channels, which may override it individually with `Exchange::with_capacity`. The capacity
+    /// `None` restores the default.
+    ///
+    /// # Examples
+    /// ```
+    /// use timely::dataflow::operators::{ToStream, Exchange, Inspect};
+    ///
+    /// timely::execute(timely::Configuration::Process(2), |worker| {
+    ///
+    ///     // Send exchanged records in batches of up to sixty-four.
+    ///     worker.set_exchange_capacity(Some(64));
+    ///     worker.dataflow::<usize,_,_>(|scope| {
+    ///         (0..1000u64).to_stream(scope)
+    ///                     .exchange(|x| *x)
+    ///                     .inspect_batch(|_t, batch| assert!(batch.len() <= 64));
+    ///     });
+    ///     worker.set_exchange_capacity(None);
+    /// }).unwrap();
+    /// ```
+    pub fn set_exchange_capacity(&mut self, capacity: Option<usize>) {
+        assert!(capacity != Some(0), "Exchange capacity must be positive");
+        self.exchange_capacity.set(capacity);
+    }
+
+    /// Sets the thread pool on which operators may fan out work, as for `ParallelMap`.
+    ///
+    /// A pool is typically shared by the workers of a process, as `Config::pool_threads` arranges,
+    /// so that the threads of the pool are not multiplied by the number of workers. Operators
+    /// constructed without a pool use the global pool of `rayon`.
+    #[cfg(feature = "rayon")]
+    pub fn set_pool(&mut self, pool: Option<Arc<rayon::ThreadPool>>) {
+        *self.pool.borrow_mut() = pool;
+    }
+
+    /// Sets how the scopes of subsequently constructed dataflows exchange progress updates.
+    ///
+    /// The default policy sends progress updates eagerly, in each step that produces them, unless
+    /// the `DEFAULT_PROGRESS_MODE` environment variable is `DEMAND`. Computations with many small
+    /// epochs on many workers may exchange fewer progress messages by sending updates on demand,
+    /// or by holding updates to send them in batches, at the expense of the latency of progress.
+    /// All workers should set the same policy.
+    ///
+    /// # Examples
+    /// ```
+    /// use std::time::Duration;
+    /// use timely::dataflow::InputHandle;
+    /// use timely::dataflow::operators::{Input, Exchange, Probe};
+    /// use timely::progress::broadcast::{ProgressPolicy, ProgressMode, ProgressBatch};
+    ///
+    /// timely::execute_from_args(::std::env::args(), |worker| {
+    ///
+    ///     // Send progress updates on demand, in batches of up to a millisecond.
+    ///     worker.set_progress_policy(ProgressPolicy {
+    ///         mode: ProgressMode::Demand,
+    ///         batch: Some(ProgressBatch { updates: 64, delay: Duration::from_millis(1) }),
+    ///         latency: false,
+    ///     });
+    ///
+    ///     let mut input = InputHandle::new();
+    ///     let probe = worker.dataflow::<usize,_,_>(|scope| {
+    ///         scope.input_from(&mut input)
+    ///              .exchange(|x: &usize| *x as u64)
+    ///              .probe()
+    ///     });
+    ///
+    ///     for round in 0..100 {
+    ///         input.send(round);
+    ///         input.advance_to(round + 1);
+    ///         worker.step_while(|| probe.less_than(input.time()));
+    ///     }
+    /// }).unwrap();
+    /// ```
+    pub fn set_progress_policy(&mut self, policy: ProgressPolicy) {
+        self.progress_policy.set(policy);
+    }
+
+    /// Bounds the time of each operator activation, for operators that cooperatively yield.
+    ///
+    /// Operators that consult a `Yielder` (see `Scheduler::yielder_for`) are asked to yield
+    /// once an activation exceeds `budget`, and are re-activated to resume their work. The
+    /// default of `None` never asks operators to yield.
+    pub fn set_activation_budget(&mut self, budget: Option<Duration>) {
+        self.activations.borrow().fuel().set_budget(budget);
+    }
+
+    /// Reports channels on which more than `threshold` messages await the worker, or none if `None`.
+    ///
+    /// Each channel is reported as a `SaturationEvent::Channel` in the `"timely"` log when its
+    /// queue first exceeds the threshold, and again only once the queue has fallen back to it.
+    /// Messages are counted from when they are pushed until their operator finds the channel
+    /// empty, so that the count estimates the length of the queue.
+    ///
+    /// # Examples
+    /// ```
+    /// use timely::dataflow::InputHandle;
+    /// use timely::dataflow::operators::{Input, Exchange, Probe};
+    /// use timely::logging::{TimelyEvent, SaturationEvent};
+    ///
+    /// timely::execute_from_args(::std::env::args(), |worker| {
+    ///
+    ///     worker.set_channel_saturation(Some(1000));
+    ///     worker.set_operator_saturation(Some(10));
+    ///     worker.log_register().insert::<TimelyEvent,_>("timely", |_time, data| {
+    ///         for (_, _, event) in data.drain(..) {
+    ///             if let TimelyEvent::Saturation(SaturationEvent::Channel { channel, queued }) = event {
+    ///                 println!("channel {} saturated with {} messages", channel, queued);
+    ///             }
+    ///         }
+    ///     });
+    ///
+    ///     let mut input = InputHandle::new();
+    ///     let probe = worker.dataflow::<usize,_,_>(|scope| {
+    ///         scope.input_from(&mut input)
+    ///              .exchange(|x: &usize| *x as u64)
+    ///              .probe()
+    ///     });
+    ///
+    ///     for round in 0..100 {
+    ///         input.send(round);
+    ///         input.advance_to(round + 1);
+    ///         worker.step_while(|| probe.less_than(input.time()));
+    ///     }
+    /// }).unwrap();
+    /// ```
+    pub fn set_channel_saturation(&mut self, threshold: Option<usize>) {
+        self.activations.borrow().saturation().set_channel_threshold(threshold);
+    }
+
+    /// Reports operators that yield with input remaining in `threshold` successive activations, or none if `None`.
+    ///
+    /// An operator yields with input remaining if it returns without having read each of its
+    /// inputs until it found them empty. Each operator is reported as a
+    /// `SaturationEvent::Operator` in the `"timely"` log when the number of such successive
+    /// activations reaches the threshold, and again only after an activation that read all of
+    /// its input.
+    pub fn set_operator_saturation(&mut self, threshold: Option<usize>) {
+        self.activations.borrow().saturation().set_operator_threshold(threshold);
+    }
+
+    /// Enables or disables the attribution of memory to the operators of subsequently constructed dataflows.
+    ///
+    /// Each accounted operator reports the bytes queued for it in its input channels and the
+    /// bytes it holds, in `Worker::introspect` and as `MemoryEvent`s in the `"timely"` log.
+    /// Accounting costs a little for each message sent, and is disabled by default. See the
+    /// `dataflow::memory` module for what is counted.
+    pub fn set_memory_accounting(&mut self, enabled: bool) {
+        self.memory.set_enabled(enabled);
+    }
+
+    /// Reports dataflows whose frontiers have not advanced for the period of `watchdog`, or none if `None`.
+    ///
+    /// Once the frontiers of the worker's operators have not advanced for the period, while some
+    /// operator holds capabilities or awaits records, the worker logs a `StallReport` as a
+    /// `TimelyEvent::Stall`, naming those operators, the timestamps of their capabilities, and the
+    /// channels with undelivered records. Each stall is reported once. Frontiers are observed as
+    /// the worker steps, and parked workers wake to observe them only if the watchdog was built
+    /// `with_wakeups`. See the `dataflow::watchdog` module for details.
+    ///
+    /// # Examples
+    /// ```
+    /// use std::time::Duration;
+    /// use timely::dataflow::watchdog::Watchdog;
+    /// use timely::logging::TimelyEvent;
+    ///
+    /// timely::execute_from_args(::std::env::args(), |worker| {
+    ///     worker.log_register().insert::<TimelyEvent,_>("timely", |_time, data| {
+    ///         for (_, worker, event) in data.iter() {
+    ///             if let TimelyEvent::Stall(report) = event {
+    ///                 eprint!("Stalled dataflows at worker {}: {}", worker, report);
+    ///             }
+    ///         }
+    ///     });
+    ///     worker.set_watchdog(Some(Watchdog::new(Duration::from_secs(10)).with_wakeups()));
+    /// }).unwrap();
+    /// ```
+    pub fn set_watchdog(&mut self, watchdog: Option<Watchdog>) {
+        self.watchdog = watchdog;
+    }
+
+    /// Sets the clock by which the worker measures processing time.
+    ///
+    /// The clock times delayed activations, operator timers, and the windows of time quotas, but
+    /// not the time spent in operators. A manual clock allows tests to advance processing time
+    /// explicitly, as in `testing::Harness`.
+    pub fn set_clock(&mut self, clock: Clock) {
+        self.activations.borrow_mut().set_clock(clock);
+    }
+
+    /// Construct a new dataflow with specific configurations.
+    ///
+    /// This method constructs a new dataflow, using a name, logger, and additional
+    /// resources specified as argument. The name is cosmetic, the logger is used to
+    /// handle events generated by the dataflow, and the additional resources are kept
+    /// alive for as long as the dataflow is alive (use case: shared library bindings).
+    ///
+    /// # Examples
+    /// ```
+    /// timely::execute_from_args(::std::env::args(), |worker| {
+    ///
+    ///     // We must supply the timestamp type here, although
+    ///     // it would generally be determined by type inference.
+    ///     worker.dataflow_core::<usize,_,_,_>(
+    ///         "dataflow X",           // Dataflow name
+    ///         None,                   // Optional logger
+    ///         37,                     // Any resources
+    ///         |resources, scope| {    // Closure
+    ///
+    ///             // uses of `resources`, `scope`to build dataflow
+    ///
+    ///         }
+    ///     );
+    /// });
+    /// ```
+    pub fn dataflow_core<T, R, F, V>(&mut self, name: &str, mut logging: Option<TimelyLogger>, mut resources: V, func: F) -> R
+    where
+        T: Refines<()>,
+        F: FnOnce(&mut V, &mut Child<Self, T>)->R,
+        V: Any+'static,
+    {
+        let addr = vec![];
+        let dataflow_index = self.allocate_dataflow_index();
+        let identifier = self.new_identifier();
+
+        let subscope = SubgraphBuilder::new_from(dataflow_index, addr, logging.clone(), name);
+        let subscope = RefCell::new(subscope);
+
+        let result = {
+            let mut builder = Child {
+                subgraph: &subscope,
+                parent: self.clone(),
+                logging: logging.clone(),
+            };
+            func(&mut resources, &mut builder)
+        };
+
+        let mut operator = subscope.into_inner().build(self);
+
+        self.register_operator(Operator {
+            id: identifier,
+            addr: operator.path().to_vec(),
+            names: vec![operator.name().to_string()],
+        });
+        logging.as_mut().map(|l| l.log(crate::logging::OperatesEvent {
+            id: identifier,
             addr: operator.path().to_vec(),
             name: operator.name().to_string(),
+            names: vec![operator.name().to_string()],
         }));
 
         logging.as_mut().map(|l| l.flush());
 
-        operator.get_internal_summary();
-        operator.set_external_summary();
+        // Operators may first be scheduled in setting their external summaries, and may panic.
+        let outcome = if self.catch_panics {
+            std::panic::catch_unwind(AssertUnwindSafe(|| {
+                operator.get_internal_summary();
+                operator.set_external_summary();
+            })).map_err(|payload| panic_message(&*payload))
+        }
+        else {
+            operator.get_internal_summary();
+            operator.set_external_summary();
+            Ok(())
+        };
 
         let mut temp_channel_ids = self.temp_channel_ids.borrow_mut();
         let channel_ids = temp_channel_ids.drain(..).collect::<Vec<_>>();
@@ -439,18 +1588,460 @@ impl<A: Allocate> Worker<A> {
             operate: Some(Box::new(operator)),
             resources: Some(Box::new(resources)),
             channel_ids,
+            priority: Priority::default(),
+            elapsed: Duration::default(),
+            deferrals: 0,
+            activations: 0,
+            quota: Quota::default(),
+            buffered: self.buffered.borrow_mut().remove(&dataflow_index).unwrap_or_default(),
+            window_start: self.activations.borrow().clock().now(),
+            window_elapsed: Duration::default(),
+            throttled: false,
         };
         self.dataflows.borrow_mut().insert(dataflow_index, wrapper);
 
+        if let Err(message) = outcome {
+            let index = self.index();
+            self.control.borrow_mut().fail(dataflow_index, index, message);
+        }
+
         result
 
     }
 
+    /// Drops an identified dataflow on this worker.
+    ///
+    /// This method removes the identified dataflow, which will no longer be scheduled.
+    /// Various other resources will be cleaned up, though the method is currently in
+    /// public beta rather than expected to work. Other workers hosting the dataflow
+    /// are not informed, and may continue to send data to this worker; to remove a
+    /// dataflow from all workers, use `retire_dataflow`.
+    pub fn drop_dataflow(&mut self, dataflow_identifier: usize) {
+        if let Some(mut entry) = self.dataflows.borrow_mut().remove(&dataflow_identifier) {
+            self.activations.borrow().saturation().forget_dataflow(dataflow_identifier, &entry.channel_ids[..]);
+            self.memory.forget_dataflow(dataflow_identifier);
+            // Garbage collect channel_id to path information.
+            let mut paths = self.paths.borrow_mut();
+            let mut counters = self.counters.borrow_mut();
+            for channel in entry.channel_ids.drain(..) {
+                paths.remove(&channel);
+                counters.remove(&channel);
+            }
+            self.operators.borrow_mut().retain(|_, op| op.addr[0] != dataflow_identifier);
+            self.channels.borrow_mut().retain(|_, channel| channel.scope_addr[0] != dataflow_identifier);
+        }
+    }
+
+    /// Drops an identified dataflow on all workers.
+    ///
+    /// This method informs all workers, each of which stops scheduling the dataflow and
+    /// informs all workers that it has done so. Once a worker has heard from all workers,
+    /// no further messages for the dataflow remain in flight, and it drops the dataflow
+    /// and its channels. The method may be called by any number of workers, and returns
+    /// before the dataflow is dropped; each worker continues to report the dataflow among
+    /// its `installed_dataflows` until it has been dropped there.
+    ///
+    /// # Examples
+    /// ```
+    /// timely::execute(timely::Configuration::Process(2), |worker| {
+    ///
+    ///     use timely::dataflow::operators::{Input, Inspect};
+    ///
+    ///     // The dataflow cannot complete while `input` is held.
+    ///     let index = worker.next_dataflow_index();
+    ///     let mut input = worker.dataflow::<usize,_,_>(|scope| {
+    ///         let (input, stream) = scope.new_input::<usize>();
+    ///         stream.inspect(|x| println!("{:?}", x));
+    ///         input
+    ///     });
+    ///
+    ///     input.send(worker.index());
+    ///     if worker.index() == 0 {
+    ///         worker.retire_dataflow(index);
+    ///     }
+    ///
+    ///     while worker.installed_dataflows().contains(&index) {
+    ///         worker.step();
+    ///     }
+    /// }).unwrap();
+    /// ```
+    pub fn retire_dataflow(&mut self, dataflow_identifier: usize) {
+        let index = self.index();
+        self.control.borrow_mut().retire(dataflow_identifier, index);
+    }
+
+    /// Returns the next index to be used for dataflow construction.
+    ///
+    /// This identifier will appear in the address of contained operators, and can
+    /// be used to drop the dataflow using `self.drop_dataflow()`.
+    pub fn next_dataflow_index(&self) -> usize {
+        *self.dataflow_counter.borrow()
+    }
+
+    /// The names of the scopes enclosing an operator, from its dataflow down, ending with its own.
+    ///
+    /// The operator is identified by its worker-unique identifier, as found in the `id` field
+    /// of logged events or in the `global_id` of an `OperatorInfo`. Dataflows are named by
+    /// `dataflow_named`, and regions by `region_named`. The result is `None` for identifiers
+    /// of operators that do not exist, or whose dataflows have been dropped.
+    ///
+    /// # Examples
+    /// ```
+    /// use std::rc::Rc;
+    /// use std::cell::Cell;
+    /// use timely::dataflow::Scope;
+    /// use timely::dataflow::operators::Inspect;
+    /// use timely::dataflow::operators::generic::operator::source;
+    ///
+    /// timely::execute_from_args(::std::env::args(), |worker| {
+    ///
+    ///     let identifier = Rc::new(Cell::new(0));
+    ///     let shared = identifier.clone();
+    ///     worker.dataflow_named::<usize,_,_>("Pipeline", |scope| {
+    ///         scope.region_named("Ingest", |inner| {
+    ///             source(inner, "Numbers", |_capability, info| {
+    ///                 shared.set(info.global_id);
+    ///                 |_output| { }
+    ///             })
+    ///             .inspect(|x: &usize| println!("{:?}", x));
+    ///         });
+    ///     });
+    ///
+    ///     assert_eq!(
+    ///         worker.operator_names(identifier.get()),
+    ///         Some(vec!["Pipeline".to_string(), "Ingest".to_string(), "Numbers".to_string()])
+    ///     );
+    /// });
+    /// ```
+    pub fn operator_names(&self, identifier: usize) -> Option<Vec<String>> {
+        self.operators.borrow().get(&identifier).map(|op| op.names.clone())
+    }
+
+    /// Describes the operators and channels of the dataflows installed on this worker.
+    ///
+    /// See `Graph` for an example, and for rendering the description as DOT or JSON.
+    pub fn graph(&self) -> Graph {
+        let mut operators = self.operators.borrow().values().cloned().collect::<Vec<_>>();
+        operators.sort_by(|x, y| x.addr.cmp(&y.addr));
+        let mut channels = self.channels.borrow().values().cloned().collect::<Vec<_>>();
+        channels.sort_by_key(|channel| channel.id);
+        Graph { operators, channels }
+    }
+
+    /// Snapshots the cumulative counts of the channels of the dataflows installed on this worker.
+    ///
+    /// The counts are ordered by channel identifier. See the `counts` module for an example.
+    pub fn channel_counts(&self) -> Vec<ChannelCounts> {
+        let mut counts = self.counters.borrow().iter().map(|(channel, counter)| counter.counts(*channel)).collect::<Vec<_>>();
+        counts.sort_by_key(|counts| counts.channel);
+        counts
+    }
+
+    /// Calls `hook` with the worker at the end of a step, at most once in each `interval`, for as long as the worker runs.
+    ///
+    /// The hook is first called at the end of the next step.
+    #[cfg(feature = "viz")]
+    pub(crate) fn on_step<F: FnMut(&Worker<A>)+'static>(&self, interval: Duration, hook: F) {
+        self.step_hooks.borrow_mut().push((interval, Instant::now(), Box::new(hook)));
+    }
+
+    /// Reports the current state of each operator in the dataflows installed on this worker.
+    ///
+    /// Each dataflow, and each operator within it, reports its input frontiers, the channels
+    /// connected to its inputs, and the number of times it has been scheduled. The results
+    /// are ordered by operator address.
+    ///
+    /// # Examples
+    /// ```
+    /// use timely::dataflow::InputHandle;
+    /// use timely::dataflow::operators::{Input, Probe};
+    ///
+    /// timely::execute_from_args(::std::env::args(), |worker| {
+    ///
+    ///     let mut input = InputHandle::new();
+    ///     let probe = worker.dataflow::<usize,_,_>(|scope| {
+    ///         scope.input_from(&mut input)
+    ///              .probe()
+    ///     });
+    ///
+    ///     input.send(0);
+    ///     input.advance_to(5);
+    ///     worker.step_while(|| probe.less_than(input.time()));
+    ///
+    ///     // The probe's input frontier has reached the input's time.
+    ///     let statuses = worker.introspect();
+    ///     let probe = statuses.iter().find(|status| status.name == "Probe").unwrap();
+    ///     assert_eq!(probe.frontiers, vec![vec!["5".to_string()]]);
+    ///     assert_eq!(probe.channels[0].len(), 1);
+    ///     assert!(probe.activations > 0);
+    ///
+    ///     // The most expensive operator, by time spent in its activations.
+    ///     let hottest = statuses.iter().filter(|status| status.addr.len() > 1).max_by_key(|status| status.elapsed).unwrap();
+    ///     println!("hottest operator: {} ({:?})", hottest.name, hottest.elapsed);
+    /// });
+    /// ```
+    pub fn introspect(&self) -> Vec<OperatorStatus> {
+        let mut statuses = Vec::new();
+        for (index, wrapper) in self.dataflows.borrow().iter() {
+            if let Some(operate) = wrapper.operate.as_ref() {
+                statuses.push(OperatorStatus {
+                    id: wrapper.identifier,
+                    addr: vec![*index],
+                    name: operate.name().to_owned(),
+                    frontiers: Vec::new(),
+                    channels: Vec::new(),
+                    pending: Vec::new(),
+                    capabilities: Vec::new(),
+                    activations: wrapper.activations,
+                    elapsed: wrapper.elapsed,
+                    queued_bytes: None,
+                    held_bytes: None,
+                });
+                operate.introspect(&mut statuses);
+            }
+        }
+        // Associate channels with the operator inputs they target. Channels targeting index zero
+        // leave their scope through one of its outputs, and so target no operator input.
+        let mut positions = HashMap::new();
+        for (position, status) in statuses.iter().enumerate() {
+            positions.insert(status.addr.clone(), position);
+        }
+        for channel in self.channels.borrow().values().filter(|channel| channel.target.0 != 0) {
+            let mut target = channel.scope_addr.clone();
+            target.push(channel.target.0);
+            if let Some(&position) = positions.get(&target) {
+                if let Some(channels) = statuses[position].channels.get_mut(channel.target.1) {
+                    channels.push(channel.id);
+                }
+            }
+        }
+        for status in statuses.iter_mut() {
+            for channels in status.channels.iter_mut() {
+                channels.sort();
+            }
+        }
+        statuses.sort_by(|x, y| x.addr.cmp(&y.addr));
+        statuses
+    }
+
+    /// Steps the worker until all workers have reached the same barrier.
+    ///
+    /// Each worker's `n`th call to `barrier` waits for the `n`th call of every other worker, and
+    /// so all workers must call it the same number of times. Barriers are useful to coordinate
+    /// phases of a computation, for example to let each worker load reference data before any
+    /// worker introduces input, or to agree that all workers are ready to shut down.
+    ///
+    /// The worker continues to step its dataflows while it waits, so that other workers awaiting
+    /// their progress are not blocked. A worker without dataflows parks until other workers arrive.
+    ///
+    /// # Examples
+    /// ```
+    /// use std::sync::{Arc, Mutex};
+    ///
+    /// let loaded = Arc::new(Mutex::new(0));
+    /// timely::execute(timely::Configuration::Process(3), move |worker| {
+    ///
+    ///     // Each worker loads its share of the reference data.
+    ///     *loaded.lock().unwrap() += 1;
+    ///
+    ///     // No worker proceeds until every worker has finished loading.
+    ///     worker.barrier();
+    ///     assert_eq!(*loaded.lock().unwrap(), 3);
+    ///
+    /// }).unwrap();
+    /// ```
+    pub fn barrier(&mut self) {
+        let barrier = self.barriers.borrow_mut().arrive();
+        // Workers removed by `PeerFailurePolicy::Degrade` as the worker waits are not awaited.
+        while !self.barriers.borrow_mut().passed(barrier, &self.live_workers.borrow()) {
+            if self.dataflows.borrow().is_empty() {
+                // With no dataflows to step, await messages from other workers.
+                self.receive_events();
+                self.receive_peer_failures();
+                if self.barriers.borrow_mut().passed(barrier, &self.live_workers.borrow()) {
+                    break;
+                }
+                self.allocator.borrow().await_events(None);
+            }
+            else {
+                self.step_or_park(None);
+            }
+        }
+        self.barriers.borrow_mut().awaiting = false;
+    }
+
+    /// Requests the shutdown of the computation, at all workers.
+    ///
+    /// Once a worker learns of the request, which it does as it steps, its input handles close
+    /// and discard further records, and `shutdown_requested` returns `true`. The dataflows then
+    /// drain as their frontiers advance to empty, and each worker should return from its closure
+    /// once it observes the request, after which `execute` steps its remaining dataflows to
+    /// completion, closes connections between processes, and returns, at all processes.
+    ///
+    /// Operators other than inputs that hold capabilities, for example custom sources, should
+    /// consult the flag of `AsWorker::shutdown_signal`, and release their capabilities once set.
+    ///
+    /// # Examples
+    /// ```
+    /// use timely::dataflow::InputHandle;
+    /// use timely::dataflow::operators::{Input, Exchange, Probe};
+    ///
+    /// timely::execute(timely::Configuration::Process(2), |worker| {
+    ///
+    ///     let mut input = InputHandle::new();
+    ///     let probe = worker.dataflow::<usize,_,_>(|scope| {
+    ///         scope.input_from(&mut input)
+    ///              .exchange(|x: &usize| *x as u64)
+    ///              .probe()
+    ///     });
+    ///
+    ///     // Produce input indefinitely, until one worker decides that the computation is done.
+    ///     let mut round = 0;
+    ///     while !worker.shutdown_requested() {
+    ///         if worker.index() == 0 && round == 10 {
+    ///             worker.request_shutdown();
+    ///         }
+    ///         input.send(round);
+    ///         input.advance_to(round + 1);
+    ///         worker.step_while(|| probe.less_than(input.time()));
+    ///         round += 1;
+    ///     }
+    /// }).unwrap();
+    /// ```
+    pub fn request_shutdown(&mut self) {
+        let index = self.index();
+        self.shutdown.borrow_mut().request(index);
+    }
+
+    /// Returns `true` once any worker has requested the shutdown of the computation.
+    pub fn shutdown_requested(&self) -> bool {
+        self.shutdown.borrow_mut().receive()
+    }
+
+    /// Reports counters of the progress traffic of each scope of each dataflow, ordered by address.
+    ///
+    /// Latencies are estimated only for dataflows constructed under a `ProgressPolicy` that sets
+    /// `latency`, as stamping messages with the system time has a cost on each send.
+    ///
+    /// # Examples
+    /// ```
+    /// use timely::dataflow::InputHandle;
+    /// use timely::dataflow::operators::{Input, Exchange, Probe};
+    /// use timely::progress::broadcast::ProgressPolicy;
+    ///
+    /// timely::execute_from_args(::std::env::args(), |worker| {
+    ///
+    ///     worker.set_progress_policy(ProgressPolicy { latency: true, ..Default::default() });
+    ///
+    ///     let mut input = InputHandle::new();
+    ///     let probe = worker.dataflow::<usize,_,_>(|scope| {
+    ///         scope.input_from(&mut input)
+    ///              .exchange(|x: &usize| *x as u64)
+    ///              .probe()
+    ///     });
+    ///
+    ///     for round in 0..10 {
+    ///         input.send(round);
+    ///         input.advance_to(round + 1);
+    ///         worker.step_while(|| probe.less_than(input.time()));
+    ///     }
+    ///
+    ///     let stats = worker.progress_stats();
+    ///     assert!(stats[0].sent_messages > 0);
+    ///     println!("updates per message: {}, latency: {:?}", stats[0].mean_batch(), stats[0].mean_latency());
+    /// }).unwrap();
+    /// ```
+    pub fn progress_stats(&self) -> Vec<ProgressStats> {
+        let mut stats = Vec::new();
+        for wrapper in self.dataflows.borrow().values() {
+            if let Some(operate) = wrapper.operate.as_ref() {
+                operate.progress_stats(&mut stats);
+            }
+        }
+        stats.sort_by(|x, y| x.addr.cmp(&y.addr));
+        stats
+    }
+
+    /// Retains the most recent `capacity` operator activations, or none if `capacity` is `None`.
+    ///
+    /// The history is disabled by default. Once enabled, each activation of an operator in any
+    /// dataflow is recorded, and the most recent are available from `activation_history` and
+    /// `dump_activations`, to learn what the worker has been doing recently.
+    ///
+    /// # Examples
+    /// ```
+    /// use timely::dataflow::operators::{ToStream, Inspect};
+    ///
+    /// timely::execute_from_args(::std::env::args(), |worker| {
+    ///
+    ///     worker.record_activations(Some(1000));
+    ///     worker.dataflow::<usize,_,_>(|scope| {
+    ///         (0..10).to_stream(scope)
+    ///                .inspect(|x| println!("seen: {:?}", x));
+    ///     });
+    ///     while worker.step() { }
+    ///
+    ///     // The activations of the last second.
+    ///     let cutoff = worker.timer().elapsed().saturating_sub(std::time::Duration::from_secs(1));
+    ///     let history = worker.activation_history();
+    ///     assert!(history.iter().filter(|record| record.start >= cutoff).count() > 0);
+    ///
+    ///     let mut dump = Vec::new();
+    ///     worker.dump_activations(&mut dump).unwrap();
+    ///     assert!(String::from_utf8(dump).unwrap().contains("Inspect"));
+    /// }).unwrap();
+    /// ```
+    pub fn record_activations(&mut self, capacity: Option<usize>) {
+        self.activations.borrow().history().set_capacity(capacity);
+    }
+
+    /// The most recent operator activations, in the order they completed.
+    ///
+    /// The result is empty unless recording was enabled with `record_activations`.
+    pub fn activation_history(&self) -> Vec<ActivationRecord> {
+        self.activations.borrow().history().records()
+    }
+
+    /// Writes the most recent operator activations to `writer`, one line for each.
+    ///
+    /// Each line contains the start of the activation relative to the start of the worker, its
+    /// duration, and the identifier and names of the operator, separated by tabs.
+    pub fn dump_activations<W: ::std::io::Write>(&self, writer: &mut W) -> ::std::io::Result<()> {
+        let operators = self.operators.borrow();
+        for record in self.activation_history() {
+            let names = operators.get(&record.id).map(|op| op.names.join("/")).unwrap_or_default();
+            writeln!(writer, "{:?}\t{:?}\t{}\t{}", record.start, record.duration, record.id, names)?;
+        }
+        Ok(())
+    }
+
+    /// Writes the most recent operator activations to `writer` as folded stacks, for rendering as a flamegraph.
+    ///
+    /// See `scheduling::history::write_folded` for the format.
+    pub fn dump_folded_activations<W: ::std::io::Write>(&self, writer: &mut W) -> ::std::io::Result<()> {
+        crate::scheduling::history::write_folded(writer, &self.activation_history(), &self.graph().operators)
+    }
+
+    /// List the current dataflow indices.
+    pub fn installed_dataflows(&self) -> Vec<usize> {
+        self.dataflows.borrow().keys().cloned().collect()
+    }
+
     // Acquire a new distinct dataflow identifier.
     fn allocate_dataflow_index(&mut self) -> usize {
         *self.dataflow_counter.borrow_mut() += 1;
         *self.dataflow_counter.borrow() - 1
     }
+
+    // Receives control messages, and drops dataflows all workers have stopped scheduling.
+    fn receive_control(&mut self) {
+        let index = self.index();
+        let peers = self.live_workers.borrow().len();
+        let retired = self.control.borrow_mut().receive(index, peers);
+        for dataflow in retired {
+            self.drop_dataflow(dataflow);
+        }
+    }
 }
 
 use crate::communication::Message;
@@ -460,6 +2051,8 @@ impl<A: Allocate> Clone for Worker<A> {
         Worker {
             timer: self.timer,
             paths: self.paths.clone(),
+            operators: self.operators.clone(),
+            channels: self.channels.clone(),
             allocator: self.allocator.clone(),
             identifiers: self.identifiers.clone(),
             dataflows: self.dataflows.clone(),
@@ -468,16 +2061,311 @@ impl<A: Allocate> Clone for Worker<A> {
             activations: self.activations.clone(),
             active_dataflows: Vec::new(),
             temp_channel_ids: self.temp_channel_ids.clone(),
+            control: self.control.clone(),
+            remote_pushers: self.remote_pushers.clone(),
+            remote_puller: self.remote_puller.clone(),
+            barriers: self.barriers.clone(),
+            shutdown: self.shutdown.clone(),
+            step_budget: self.step_budget,
+            catch_panics: self.catch_panics,
+            buffered: self.buffered.clone(),
+            memory: self.memory.clone(),
+            counters: self.counters.clone(),
+            step_hooks: self.step_hooks.clone(),
+            watchdog: self.watchdog.clone(),
+            output_capacity: self.output_capacity.clone(),
+            exchange_capacity: self.exchange_capacity.clone(),
+            #[cfg(feature = "rayon")]
+            pool: self.pool.clone(),
+            progress_policy: self.progress_policy.clone(),
+            peer_failure_policy: self.peer_failure_policy.clone(),
+            peer_failures_seen: self.peer_failures_seen.clone(),
+            live_workers: self.live_workers.clone(),
+            live_index: self.live_index.clone(),
         }
     }
 }
 
+/// A future performing one step of a worker, once it has work to perform.
+///
+/// Returned by `Worker::step_async`.
+pub struct StepAsync<'a, A: Allocate> {
+    worker: &'a mut Worker<A>,
+    // The time at which the timer thread will wake the task, for the next delayed activation.
+    armed: Option<Instant>,
+}
+
+impl<'a, A: Allocate> Future for StepAsync<'a, A> {
+    type Output = bool;
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<bool> {
+        let this = self.get_mut();
+        // Register before receiving, so that events arriving afterwards wake the task.
+        crate::communication::buzzer::register_waker(cx.waker());
+        let empty_for = this.worker.receive_events();
+        if !this.worker.dataflows.borrow().is_empty() && empty_for != Some(Duration::new(0,0)) {
+            if let Some(delay) = empty_for {
+                let wake_at = Instant::now() + delay;
+                if this.armed.map(|armed| wake_at < armed || armed <= Instant::now()).unwrap_or(true) {
+                    this.armed = Some(wake_at);
+                    wake_at_time(wake_at, cx.waker().clone());
+                }
+            }
+            this.worker.finish_step();
+            Poll::Pending
+        }
+        else {
+            this.worker.schedule_dataflows(None);
+            Poll::Ready(this.worker.finish_step())
+        }
+    }
+}
+
+/// Wakes `waker` at `moment`, from a timer thread shared by all tasks of the process.
+fn wake_at_time(moment: Instant, waker: Waker) {
+    static TIMER: Mutex<Option<Sender<(Instant, Waker)>>> = Mutex::new(None);
+    let mut timer = TIMER.lock().expect("timer poisoned");
+    let sender = timer.get_or_insert_with(|| {
+        let (sender, receiver) = channel();
+        std::thread::Builder::new()
+            .name("timely timer".to_string())
+            .spawn(move || run_timer(receiver))
+            .expect("failed to spawn timer thread");
+        sender
+    });
+    // The thread exits only if it panics, and its tasks are then woken by their next events.
+    let _ = sender.send((moment, waker));
+}
+
+/// Wakes each received waker at its moment, until the sending half is dropped.
+fn run_timer(receiver: Receiver<(Instant, Waker)>) {
+    let mut pending: Vec<(Instant, Waker)> = Vec::new();
+    loop {
+        let now = Instant::now();
+        pending.retain(|(moment, waker)| if *moment <= now { waker.wake_by_ref(); false } else { true });
+        let received = match pending.iter().map(|(moment, _)| *moment).min() {
+            Some(next) => receiver.recv_timeout(next.saturating_duration_since(now)),
+            None => receiver.recv().map_err(|_| RecvTimeoutError::Disconnected),
+        };
+        match received {
+            Ok(timer) => pending.push(timer),
+            Err(RecvTimeoutError::Timeout) => { },
+            Err(RecvTimeoutError::Disconnected) => return,
+        }
+    }
+}
+
+/// When a worker driven by `Worker::step_nonblocking` should next be stepped.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum NextStep {
+    /// After the delay, which is zero if operators are already active.
+    After(Duration),
+    /// Once the worker has received more input, as no operators are active or scheduled.
+    Idle,
+    /// Never, as the worker has no dataflows.
+    Complete,
+}
+
+/// A function called at the end of each step of a worker.
+type StepHook<A> = Box<dyn FnMut(&Worker<A>)>;
+
+/// A remote activation, indicating the path of the operator to activate.
+type ActivationMsg = Message<Vec<usize>>;
+
+/// A control message, indicating that a worker has stopped scheduling a dataflow.
+///
+/// The fields are the dataflow index, the index of the worker, and a description of the panic
+/// if the dataflow failed at the worker.
+type ControlMsg = Message<(usize, usize, Option<String>)>;
+
+/// A barrier message, indicating the number of the barrier a worker has reached, and the index of the worker.
+type BarrierMsg = Message<(usize, usize)>;
+
+/// A shutdown message, indicating the index of the worker requesting shutdown.
+type ShutdownMsg = Message<usize>;
+
+/// The failure of a dataflow, caused by a panic at one of its operators.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct DataflowError {
+    /// The index of the dataflow.
+    pub dataflow: usize,
+    /// The index of the worker at which the panic occurred.
+    pub worker: usize,
+    /// The message of the panic.
+    pub message: String,
+}
+
+impl std::fmt::Display for DataflowError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "dataflow {} failed at worker {}: {}", self.dataflow, self.worker, self.message)
+    }
+}
+
+impl std::error::Error for DataflowError {}
+
+/// The message of a panic, if its payload is a string.
+fn panic_message(payload: &(dyn Any+Send)) -> String {
+    if let Some(message) = payload.downcast_ref::<&str>() {
+        message.to_string()
+    }
+    else if let Some(message) = payload.downcast_ref::<String>() {
+        message.clone()
+    }
+    else {
+        "unknown panic payload".to_string()
+    }
+}
+
+/// Channels to and from other workers, used to coordinate the retirement of dataflows.
+struct Control {
+    pushers: Vec<Box<dyn Push<ControlMsg>>>,
+    puller: Box<dyn Pull<ControlMsg>>,
+    // Dataflows being retired, and the workers known to have stopped scheduling each.
+    retiring: HashMap<usize, HashSet<usize>>,
+    // Dataflows known to have failed.
+    failures: Vec<DataflowError>,
+}
+
+impl Control {
+    /// Stops scheduling `dataflow` and informs all workers, if not already retiring it.
+    fn retire(&mut self, dataflow: usize, index: usize) {
+        self.retire_with(dataflow, index, None);
+    }
+
+    /// Retires `dataflow` after it failed with `message`, informing all workers of the failure.
+    fn fail(&mut self, dataflow: usize, index: usize, message: String) {
+        if self.retiring.contains_key(&dataflow) {
+            // Other workers are already retiring the dataflow, and need not hear of the failure.
+            self.failures.push(DataflowError { dataflow, worker: index, message });
+        }
+        else {
+            self.retire_with(dataflow, index, Some(message));
+        }
+    }
+
+    fn retire_with(&mut self, dataflow: usize, index: usize, failure: Option<String>) {
+        if let Entry::Vacant(entry) = self.retiring.entry(dataflow) {
+            entry.insert(HashSet::new());
+            for pusher in self.pushers.iter_mut() {
+                pusher.push(&mut Some(Message::from_typed((dataflow, index, failure.clone()))));
+                pusher.done();
+            }
+        }
+    }
+
+    /// Receives control messages, and returns dataflows all `peers` have stopped scheduling.
+    fn receive(&mut self, index: usize, peers: usize) -> Vec<usize> {
+        let mut received = Vec::new();
+        while let Some(message) = self.puller.pull() {
+            let (dataflow, worker) = (message.0, message.1);
+            if let Some(failure) = message.as_mut().2.take() {
+                self.failures.push(DataflowError { dataflow, worker, message: failure });
+            }
+            received.push((dataflow, worker));
+        }
+
+        let mut retired = Vec::new();
+        for (dataflow, worker) in received {
+            // Messages from other workers oblige us to retire the dataflow too.
+            self.retire(dataflow, index);
+            let stopped = self.retiring.get_mut(&dataflow).expect("dataflow not retiring");
+            stopped.insert(worker);
+            if stopped.len() == peers {
+                self.retiring.remove(&dataflow);
+                retired.push(dataflow);
+            }
+        }
+        retired
+    }
+}
+
+/// Coordinates barriers with other workers.
+struct Barriers {
+    pushers: Vec<Box<dyn Push<BarrierMsg>>>,
+    puller: Box<dyn Pull<BarrierMsg>>,
+    // The index of this worker.
+    index: usize,
+    // The number of barriers this worker has reached.
+    reached: usize,
+    // Whether this worker is waiting at a barrier, and should not park once arrivals are received.
+    awaiting: bool,
+    // The workers known to have reached each barrier this worker has not yet passed.
+    arrivals: HashMap<usize, HashSet<usize>>,
+}
+
+impl Barriers {
+    /// Informs all workers that this worker has reached its next barrier, and returns its number.
+    fn arrive(&mut self) -> usize {
+        let barrier = self.reached;
+        self.reached += 1;
+        self.awaiting = true;
+        for pusher in self.pushers.iter_mut() {
+            pusher.push(&mut Some(Message::from_typed((barrier, self.index))));
+            pusher.done();
+        }
+        barrier
+    }
+
+    /// Receives arrivals, and indicates whether all `live` workers have reached `barrier`.
+    fn passed(&mut self, barrier: usize, live: &[usize]) -> bool {
+        while let Some(message) = self.puller.pull() {
+            let (reached, worker) = **message;
+            self.arrivals.entry(reached).or_default().insert(worker);
+        }
+        // Arrivals of workers that have since failed are not counted towards the live workers.
+        if self.arrivals.get(&barrier).is_some_and(|arrivals| live.iter().all(|worker| arrivals.contains(worker))) {
+            self.arrivals.remove(&barrier);
+            true
+        }
+        else {
+            false
+        }
+    }
+}
+
+/// Coordinates the shutdown of the computation with other workers.
+struct Shutdown {
+    pushers: Vec<Box<dyn Push<ShutdownMsg>>>,
+    puller: Box<dyn Pull<ShutdownMsg>>,
+    // Set once any worker has requested shutdown, and shared with input handles.
+    requested: Rc<Cell<bool>>,
+}
+
+impl Shutdown {
+    /// Requests shutdown from worker `index`, informing all workers, if not already requested.
+    fn request(&mut self, index: usize) {
+        if !self.requested.get() {
+            self.requested.set(true);
+            for pusher in self.pushers.iter_mut() {
+                pusher.push(&mut Some(Message::from_typed(index)));
+                pusher.done();
+            }
+        }
+    }
+
+    /// Receives requests, and indicates whether any worker has requested shutdown.
+    fn receive(&mut self) -> bool {
+        while self.puller.pull().is_some() {
+            self.requested.set(true);
+        }
+        self.requested.get()
+    }
+}
+
 struct Wrapper {
     logging: Option<TimelyLogger>,
     identifier: usize,
     operate: Option<Box<dyn Schedule>>,
     resources: Option<Box<dyn Any>>,
     channel_ids: Vec<usize>,
+    priority: Priority,
+    elapsed: Duration,      // total time spent scheduling the dataflow.
+    deferrals: usize,       // steps deferred since the dataflow was last scheduled.
+    activations: usize,     // the number of times the dataflow has been scheduled.
+    quota: Quota,
+    buffered: Rc<Cell<i64>>,    // bytes buffered in the channels of the dataflow.
+    window_start: Instant,      // the start of the current window of the time quota.
+    window_elapsed: Duration,   // time spent scheduling the dataflow in the current window.
+    throttled: bool,            // whether the dataflow has been throttled in the current window.
 }
 
 impl Wrapper {
@@ -489,23 +2377,73 @@ impl Wrapper {
     fn step(&mut self) -> bool {
 
         // Perhaps log information about the start of the schedule call.
-        if let Some(l) = self.logging.as_mut() {
+        if let Some(l) = self.logging.as_ref().filter(|l| l.interested_in_operator(EventKind::Schedule, self.identifier)) {
             l.log(crate::logging::ScheduleEvent::start(self.identifier));
         }
 
+        self.activations += 1;
+        let start = Instant::now();
         let incomplete = self.operate.as_mut().map(|op| op.schedule()).unwrap_or(false);
+        let elapsed = start.elapsed();
+        self.elapsed += elapsed;
+        self.window_elapsed += elapsed;
+        self.deferrals = 0;
         if !incomplete {
             self.operate = None;
             self.resources = None;
         }
 
         // Perhaps log information about the stop of the schedule call.
-        if let Some(l) = self.logging.as_mut() {
+        if let Some(l) = self.logging.as_ref().filter(|l| l.interested_in_operator(EventKind::Schedule, self.identifier)) {
             l.log(crate::logging::ScheduleEvent::stop(self.identifier));
         }
 
         incomplete
     }
+
+    /// Scheduling time received, relative to the weight of the dataflow.
+    fn served(&self) -> Duration {
+        self.elapsed / ::std::cmp::max(self.priority.weight, 1)
+    }
+
+    /// The time until the dataflow may next be scheduled, if it has spent its time quota.
+    fn throttle_for(&mut self, now: Instant) -> Option<Duration> {
+        let fraction = self.quota.time_fraction?;
+        if now >= self.window_start + QUOTA_WINDOW {
+            self.window_start = now;
+            self.window_elapsed = Duration::default();
+            self.throttled = false;
+        }
+        if self.window_elapsed.as_secs_f64() > fraction * QUOTA_WINDOW.as_secs_f64() {
+            if !self.throttled {
+                self.throttled = true;
+                self.log_throttle(crate::logging::QuotaKind::Time);
+            }
+            Some(self.window_start + QUOTA_WINDOW - now)
+        }
+        else {
+            None
+        }
+    }
+
+    /// A description of the excess, if the dataflow buffers more bytes than its quota allows.
+    fn exceeds_bytes(&mut self) -> Option<String> {
+        let limit = self.quota.buffered_bytes?;
+        let buffered = self.buffered.get();
+        if buffered > limit as i64 {
+            self.log_throttle(crate::logging::QuotaKind::Bytes);
+            Some(format!("buffered {} bytes, exceeding its quota of {}", buffered, limit))
+        }
+        else {
+            None
+        }
+    }
+
+    fn log_throttle(&mut self, quota: crate::logging::QuotaKind) {
+        if let Some(l) = self.logging.as_mut() {
+            l.log(crate::logging::ThrottleEvent { id: self.identifier, quota });
+        }
+    }
 }
 
 impl Drop for Wrapper {
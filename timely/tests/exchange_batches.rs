@@ -0,0 +1,60 @@
+#![cfg(feature = "arrow")]
+
+extern crate timely;
+
+use std::sync::{Arc, Mutex};
+
+use arrow_array::{Array, RecordBatch, UInt64Array};
+use arrow_schema::{DataType, Field, Schema};
+
+use timely::Configuration;
+use timely::dataflow::operators::{ToStream, ExchangeBatches, Inspect};
+
+#[test] fn exchange_batches_1w() { exchange_batches_helper(Configuration::Thread, 1); }
+#[test] fn exchange_batches_2w() { exchange_batches_helper(Configuration::Process(2), 2); }
+#[test] fn exchange_batches_3w() { exchange_batches_helper(Configuration::Process(3), 3); }
+
+// This method asserts that each row reaches the worker its key selects, and that no row is lost or repeated.
+fn exchange_batches_helper(config: Configuration, peers: usize) {
+    let received = Arc::new(Mutex::new(Vec::new()));
+    let shared = received.clone();
+    timely::execute(config, move |worker| {
+        let index = worker.index();
+        let received = shared.clone();
+        worker.dataflow::<u64,_,_>(|scope| {
+            let schema = Arc::new(Schema::new(vec![
+                Field::new("key", DataType::UInt64, false),
+                Field::new("origin", DataType::UInt64, false),
+            ]));
+            // Several batches from each worker, with keys for every worker and one without any rows.
+            let batches = (0 .. 4u64).map(|batch| {
+                let keys = UInt64Array::from((0 .. 10 * batch).collect::<Vec<u64>>());
+                let origin = UInt64Array::from(vec![index as u64; 10 * batch as usize]);
+                RecordBatch::try_new(schema.clone(), vec![Arc::new(keys), Arc::new(origin)]).unwrap()
+            }).collect::<Vec<_>>();
+
+            batches.to_stream(scope)
+                .exchange_batches(|batch| {
+                    let keys = batch.column(0).as_any().downcast_ref::<UInt64Array>().unwrap();
+                    keys.values().to_vec()
+                })
+                .inspect(move |batch| {
+                    let keys = batch.column(0).as_any().downcast_ref::<UInt64Array>().unwrap();
+                    let origin = batch.column(1).as_any().downcast_ref::<UInt64Array>().unwrap();
+                    let mut received = received.lock().unwrap();
+                    for row in 0 .. batch.num_rows() {
+                        assert_eq!(keys.value(row) as usize % peers, index);
+                        received.push((origin.value(row), keys.value(row)));
+                    }
+                });
+        });
+    }).unwrap();
+
+    let mut received = received.lock().unwrap().clone();
+    received.sort();
+    let mut expected = (0 .. peers as u64)
+        .flat_map(|origin| (0 .. 4u64).flat_map(move |batch| (0 .. 10 * batch).map(move |key| (origin, key))))
+        .collect::<Vec<_>>();
+    expected.sort();
+    assert_eq!(received, expected);
+}
@@ -0,0 +1,42 @@
+#![cfg(all(feature = "progress-validation", debug_assertions))]
+
+extern crate timely;
+
+use timely::scheduling::Scheduler;
+use timely::dataflow::channels::Message;
+use timely::dataflow::operators::{Exchange, Inspect, Probe, ToStream};
+use timely::dataflow::operators::generic::builder_raw::OperatorBuilder;
+
+#[test]
+fn permits_correct_dataflows() {
+    timely::execute(timely::Configuration::Process(2), |worker| {
+        worker.dataflow::<u64,_,_>(|scope| {
+            (0..100u64).to_stream(scope).exchange(|x| *x).inspect(|_| ()).probe();
+        });
+    }).unwrap();
+}
+
+#[test]
+#[should_panic(expected = "received messages at [0] on input 0, not beyond its input frontier [5]")]
+fn detects_premature_deliveries() {
+    timely::example(|scope| {
+        let mut builder = OperatorBuilder::new("Premature".to_owned(), scope.clone());
+        let (mut output, stream) = builder.new_output::<u64>();
+        let activator = scope.activator_for(&builder.operator_info().address[..]);
+        let mut activations = 0;
+        builder.build(move |progress| {
+            activations += 1;
+            match activations {
+                // Downgrade the initial capability to time 5.
+                1 => { progress.internals[0].update(0, -1); progress.internals[0].update(5, 1); },
+                // Send a message at time 0, once the downgrade is visible downstream.
+                3 => { Message::push_at(&mut vec![0], 0, &mut output); },
+                4 => { progress.internals[0].update(5, -1); },
+                _ => { },
+            }
+            if activations < 4 { activator.activate(); }
+            false
+        });
+        stream.inspect(|_| ());
+    });
+}
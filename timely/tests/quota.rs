@@ -0,0 +1,35 @@
+extern crate timely;
+
+use timely::Configuration;
+use timely::dataflow::InputHandle;
+use timely::dataflow::operators::{Input, Exchange, Probe};
+
+// Records sent between workers are not counted as buffered, at either worker, and so the
+// count returns to zero however skewed the exchange.
+#[test]
+fn skewed_exchange_discharges_buffered_bytes() {
+    let guards = timely::execute(Configuration::Process(3), |worker| {
+        let index = worker.next_dataflow_index();
+        let mut input = InputHandle::new();
+        let probe = worker.dataflow::<u64,_,_>(|scope| {
+            scope.input_from(&mut input)
+                 .exchange(|_: &u64| 0)
+                 .probe()
+        });
+        let mut buffered = Vec::new();
+        for round in 0 .. 10 {
+            for record in 0 .. 1000u64 {
+                input.send(record);
+            }
+            input.advance_to(round + 1);
+            while probe.less_than(input.time()) {
+                worker.step();
+            }
+            buffered.push(worker.buffered_bytes(index));
+        }
+        buffered
+    }).unwrap();
+    for buffered in guards.join() {
+        assert_eq!(buffered.unwrap(), vec![Some(0); 10]);
+    }
+}